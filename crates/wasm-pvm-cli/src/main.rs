@@ -57,12 +57,20 @@ enum Commands {
         )]
         debug_skip_llvm_passes: bool,
 
+        #[arg(
+            long,
+            help = "DEBUG ONLY: panic at a failed internal invariant check instead of \
+                    reporting an internal error (run with RUST_BACKTRACE=1)"
+        )]
+        debug_panic_on_internal_error: bool,
+
         #[arg(
             long,
             help = "Disable every optional optimization at once (peephole, register cache, \
-                    icmp fusion, address-mask elision, shrink-wrap, DSE, const-prop, inlining, \
+                    icmp fusion, address-mask elision, shrink-wrap, DSE, dead-block elim, const-prop, inlining, \
                     cross-block cache, regalloc, fallthrough jumps, aggressive regalloc, \
-                    scratch/caller-saved alloc, lazy spill, libcall recognition, mergefunc). \
+                    scratch/caller-saved alloc, caller-saved preference, lazy spill, libcall recognition, mergefunc, \
+                    scheduling, function dedup, tail calls). \
                     LLVM passes stay on (the backend requires mem2reg). Used by the no-opts \
                     differential CI job."
         )]
@@ -89,6 +97,9 @@ enum Commands {
         #[arg(long, help = "Disable dead store elimination")]
         no_dead_store_elim: bool,
 
+        #[arg(long, help = "Disable unreachable code elimination")]
+        no_dead_block_elim: bool,
+
         #[arg(
             long,
             help = "Disable constant propagation (redundant LoadImm elimination)"
@@ -104,6 +115,13 @@ enum Commands {
         )]
         inline_threshold: Option<u32>,
 
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Fully unroll loops with a constant trip count of at most N (off by default)"
+        )]
+        unroll: Option<u32>,
+
         #[arg(long, help = "Disable cross-block register cache propagation")]
         no_cross_block_cache: bool,
 
@@ -134,6 +152,12 @@ enum Commands {
         )]
         no_caller_saved_alloc: bool,
 
+        #[arg(
+            long,
+            help = "Disable the caller-saved (r5-r8) preference in leaf functions"
+        )]
+        no_prefer_caller_saved: bool,
+
         #[arg(
             long,
             help = "Disable lazy spill (skip stack stores for register-allocated values)"
@@ -152,6 +176,24 @@ enum Commands {
         )]
         no_mergefunc: bool,
 
+        #[arg(
+            long,
+            help = "Disable per-block instruction scheduling (shortens live ranges before regalloc)"
+        )]
+        no_scheduling: bool,
+
+        #[arg(
+            long,
+            help = "Disable emitting a single copy of functions with byte-identical bodies"
+        )]
+        no_dedup_functions: bool,
+
+        #[arg(
+            long,
+            help = "Disable LLVM tailcallelim (turns self-recursive tail calls into loops)"
+        )]
+        no_tail_calls: bool,
+
         #[arg(
             long,
             help = "Override maximum memory pages (default: 16 = 1 MB, each page = 64 KB)"
@@ -165,6 +207,107 @@ enum Commands {
                     unsupported features the module uses."
         )]
         trap_floats: bool,
+
+        #[arg(
+            long,
+            help = "Split loads/stores whose alignment hint is below natural alignment \
+                    into byte accesses (for hosts that penalize misaligned access)"
+        )]
+        split_misaligned: bool,
+
+        #[arg(
+            long,
+            help = "Emulate f32/f64 constants, arithmetic and comparisons with integer code \
+                    instead of rejecting them"
+        )]
+        soft_float: bool,
+
+        #[arg(
+            long,
+            value_name = "BYTES",
+            help = "Pad so every function starts at a multiple of BYTES in the code section \
+                    (helps address-range symbolication in profilers)"
+        )]
+        function_alignment: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Page-align every function and call through the jump table, so a \
+                    function's code can be swapped in place (hot reload)"
+        )]
+        function_sections: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Pass parameters in and allocate only the first N of r9-r12 (1-4, default 4)"
+        )]
+        local_regs: Option<usize>,
+
+        #[arg(
+            long,
+            value_name = "BYTES",
+            help = "Stack size in bytes, a multiple of 4096 (default 65536)"
+        )]
+        stack_size: Option<u32>,
+
+        #[arg(
+            long = "reserve-reg",
+            value_name = "N",
+            help = "Never touch register rN, for hosts that claim it (only 5 and 6; repeatable)"
+        )]
+        reserved_registers: Vec<u8>,
+
+        #[arg(
+            long,
+            default_value_t = 0,
+            help = "Seed for tie-breaks in scheduling and block layout (0 keeps the built-in choices)"
+        )]
+        seed: u64,
+
+        #[arg(
+            long,
+            help = "Address data relative to a host-initialized base register (r12) for relocation"
+        )]
+        pic: bool,
+
+        #[arg(
+            long,
+            help = "Append a table mapping each Trap's code offset to its WASM function \
+                    to the SPI metadata"
+        )]
+        trap_table: bool,
+
+        #[arg(
+            long,
+            help = "Skip WASM validation for modules already validated upstream. Invalid \
+                    input then fails with a less specific error"
+        )]
+        no_validate: bool,
+    },
+    /// Re-run the peephole and dead-store passes on an existing SPI blob.
+    Optimize {
+        #[arg(help = "Input SPI file")]
+        input: PathBuf,
+
+        #[arg(short, long, help = "Output SPI file")]
+        output: PathBuf,
+    },
+    /// Decode an SPI blob and check that it is structurally sound.
+    Verify {
+        #[arg(help = "Input SPI file")]
+        input: PathBuf,
+    },
+    /// Print the opcode table: byte value, mnemonic and encoding family.
+    #[command(alias = "list-opcodes")]
+    Opcodes {
+        #[arg(long, help = "Print a Markdown table instead of aligned columns")]
+        markdown: bool,
+    },
+    /// Describe what an instruction does, e.g. `explain rot_r_64`.
+    Explain {
+        #[arg(help = "Instruction mnemonic (case and underscores are ignored)")]
+        mnemonic: String,
     },
 }
 
@@ -185,6 +328,7 @@ fn main() -> Result<()> {
             verbose,
             json,
             debug_skip_llvm_passes,
+            debug_panic_on_internal_error,
             no_all_opts,
             no_peephole,
             no_register_cache,
@@ -192,20 +336,37 @@ fn main() -> Result<()> {
             no_address_mask_elision,
             no_shrink_wrap,
             no_dead_store_elim,
+            no_dead_block_elim,
             no_const_prop,
             no_inline,
             inline_threshold,
+            unroll,
             no_cross_block_cache,
             no_register_alloc,
             no_fallthrough_jumps,
             no_aggressive_regalloc,
             no_scratch_reg_alloc,
             no_caller_saved_alloc,
+            no_prefer_caller_saved,
             no_lazy_spill,
             no_libcall_recognition,
             no_mergefunc,
+            no_scheduling,
+            no_dedup_functions,
+            no_tail_calls,
             max_memory,
             trap_floats,
+            split_misaligned,
+            soft_float,
+            function_alignment,
+            function_sections,
+            local_regs,
+            stack_size,
+            reserved_registers,
+            seed,
+            pic,
+            trap_table,
+            no_validate,
         } => {
             let wasm = read_wasm(&input)?;
 
@@ -215,7 +376,7 @@ fn main() -> Result<()> {
             let metadata = format!("{filename} (wasm-pvm {COMPILER_VERSION})");
 
             let import_map = if let Some(imports_path) = imports {
-                Some(parse_import_map(&imports_path)?)
+                Some(read_import_map(&imports_path)?)
             } else {
                 None
             };
@@ -233,40 +394,55 @@ fn main() -> Result<()> {
             // `--no-*` flags still apply on top (their effect is idempotent in
             // that case). `--debug-skip-llvm-passes` and `--inline-threshold`
             // are independent of `--no-all-opts`.
-            let base = if no_all_opts {
-                OptimizationFlags::all_disabled()
+            let mut optimizations = if no_all_opts {
+                OptimizationFlags::none()
             } else {
-                OptimizationFlags::default()
+                OptimizationFlags::all()
             };
+            optimizations.llvm_passes = !debug_skip_llvm_passes;
+            optimizations.peephole &= !no_peephole;
+            optimizations.register_cache &= !no_register_cache;
+            optimizations.icmp_branch_fusion &= !no_icmp_fusion;
+            optimizations.address_mask_elision &= !no_address_mask_elision;
+            optimizations.shrink_wrap_callee_saves &= !no_shrink_wrap;
+            optimizations.dead_store_elimination &= !no_dead_store_elim;
+            optimizations.dead_block_elimination &= !no_dead_block_elim;
+            optimizations.constant_propagation &= !no_const_prop;
+            optimizations.inlining &= !no_inline;
+            optimizations.cross_block_cache &= !no_cross_block_cache;
+            optimizations.register_allocation &= !no_register_alloc;
+            optimizations.fallthrough_jumps &= !no_fallthrough_jumps;
+            optimizations.aggressive_register_allocation &= !no_aggressive_regalloc;
+            optimizations.allocate_scratch_regs &= !no_scratch_reg_alloc;
+            optimizations.allocate_caller_saved_regs &= !no_caller_saved_alloc;
+            optimizations.prefer_caller_saved_regs &= !no_prefer_caller_saved;
+            optimizations.lazy_spill &= !no_lazy_spill;
+            optimizations.libcall_recognition &= !no_libcall_recognition;
+            optimizations.mergefunc &= !no_mergefunc;
+            optimizations.scheduling &= !no_scheduling;
+            optimizations.dedup_functions &= !no_dedup_functions;
+            optimizations.tail_calls &= !no_tail_calls;
+            optimizations.inline_threshold = inline_threshold.or(optimizations.inline_threshold);
+            optimizations.unroll = unroll.or(optimizations.unroll);
             let options = CompileOptions {
                 import_map,
                 adapter: adapter_wat,
                 metadata: metadata.into_bytes(),
-                optimizations: OptimizationFlags {
-                    llvm_passes: !debug_skip_llvm_passes,
-                    peephole: base.peephole && !no_peephole,
-                    register_cache: base.register_cache && !no_register_cache,
-                    icmp_branch_fusion: base.icmp_branch_fusion && !no_icmp_fusion,
-                    address_mask_elision: base.address_mask_elision && !no_address_mask_elision,
-                    shrink_wrap_callee_saves: base.shrink_wrap_callee_saves && !no_shrink_wrap,
-                    dead_store_elimination: base.dead_store_elimination && !no_dead_store_elim,
-                    constant_propagation: base.constant_propagation && !no_const_prop,
-                    inlining: base.inlining && !no_inline,
-                    cross_block_cache: base.cross_block_cache && !no_cross_block_cache,
-                    register_allocation: base.register_allocation && !no_register_alloc,
-                    fallthrough_jumps: base.fallthrough_jumps && !no_fallthrough_jumps,
-                    aggressive_register_allocation: base.aggressive_register_allocation
-                        && !no_aggressive_regalloc,
-                    allocate_scratch_regs: base.allocate_scratch_regs && !no_scratch_reg_alloc,
-                    allocate_caller_saved_regs: base.allocate_caller_saved_regs
-                        && !no_caller_saved_alloc,
-                    lazy_spill: base.lazy_spill && !no_lazy_spill,
-                    inline_threshold: inline_threshold.or(base.inline_threshold),
-                    libcall_recognition: base.libcall_recognition && !no_libcall_recognition,
-                    mergefunc: base.mergefunc && !no_mergefunc,
-                },
+                optimizations,
                 max_memory_pages: max_memory,
                 trap_floats,
+                split_misaligned_accesses: split_misaligned,
+                enable_soft_float: soft_float,
+                function_alignment,
+                function_sections,
+                local_regs,
+                stack_size,
+                reserved_registers,
+                seed,
+                pic,
+                panic_on_internal_error: debug_panic_on_internal_error,
+                trap_table,
+                validate: !no_validate,
             };
 
             let start = Instant::now();
@@ -284,11 +460,92 @@ fn main() -> Result<()> {
                 print_text(&stats, &input, &output, verbose, elapsed.as_millis() as u64);
             }
         }
+        Commands::Optimize { input, output } => {
+            let bytes =
+                fs::read(&input).with_context(|| format!("Failed to read {}", input.display()))?;
+            let program =
+                wasm_pvm::SpiProgram::decode(&bytes).context("Failed to decode SPI program")?;
+            let optimized = wasm_pvm::reoptimize(&program).context("Re-optimization failed")?;
+
+            let encoded = optimized.encode();
+            fs::write(&output, &encoded)
+                .with_context(|| format!("Failed to write output to {}", output.display()))?;
+
+            println!(
+                "{} -> {}: {} -> {} bytes ({})",
+                input.display(),
+                output.display(),
+                format_number(bytes.len()),
+                format_number(encoded.len()),
+                reduction_pct(bytes.len(), encoded.len())
+            );
+        }
+        Commands::Verify { input } => {
+            let bytes =
+                fs::read(&input).with_context(|| format!("Failed to read {}", input.display()))?;
+            let program =
+                wasm_pvm::SpiProgram::decode(&bytes).context("Failed to decode SPI program")?;
+            program.validate().context("Invalid SPI layout")?;
+            let code = program.code();
+            code.validate().context("Invalid code")?;
+
+            let instructions = code.instructions().len();
+            println!("{}: ok", input.display());
+            row("Instructions", format_number(instructions));
+            row(
+                "Code",
+                format!(
+                    "{} bytes",
+                    format_number(code.offset_of(instructions).unwrap_or(0))
+                ),
+            );
+            row("Jump table", format_number(code.jump_table().len()));
+            row(
+                "RO data",
+                format!("{} bytes", format_number(program.ro_data().len())),
+            );
+            row(
+                "RW data",
+                format!("{} bytes", format_number(program.rw_data().len())),
+            );
+            row("Heap pages", program.heap_pages());
+            row(
+                "Stack",
+                format!("{} bytes", format_number(program.stack_size() as usize)),
+            );
+        }
+        Commands::Opcodes { markdown } => print_opcodes(markdown),
+        Commands::Explain { mnemonic } => {
+            let opcode = wasm_pvm::Opcode::from_name(&mnemonic).with_context(|| {
+                format!("unknown mnemonic `{mnemonic}` (run `opcodes` for the full list)")
+            })?;
+            let byte = opcode as u8;
+            println!("{} (opcode {byte}, 0x{byte:02X})", opcode.name());
+            println!("  encoding: {:?}", opcode.encoding_family());
+            println!("  {}", opcode.description());
+        }
     }
 
     Ok(())
 }
 
+fn print_opcodes(markdown: bool) {
+    if markdown {
+        println!("| Byte | Mnemonic | Encoding |");
+        println!("|-----:|----------|----------|");
+    }
+    for opcode in wasm_pvm::Opcode::all() {
+        let byte = opcode as u8;
+        let name = format!("{opcode:?}");
+        let family = format!("{:?}", opcode.encoding_family());
+        if markdown {
+            println!("| {byte} | `{name}` | {family} |");
+        } else {
+            println!("{byte:>3}  0x{byte:02X}  {name:<24} {family}");
+        }
+    }
+}
+
 // ── Text output (Style C) ──
 
 fn section(title: &str) {
@@ -396,6 +653,36 @@ fn print_text(stats: &CompileStats, input: &Path, output: &Path, verbose: bool,
     row("RW data", format!("{} bytes", stats.rw_data_bytes));
     row("Heap pages", stats.heap_pages);
     row("Stack size", format!("{} bytes", stats.stack_size));
+    if let Some(mem) = &stats.exported_memory {
+        row(
+            "Exported memory",
+            format!(
+                "\"{}\" at 0x{:X} ({} bytes)",
+                mem.name, mem.base, mem.initial_bytes
+            ),
+        );
+    }
+    if let Some(mem) = &stats.imported_memory {
+        row(
+            "Imported memory",
+            format!(
+                "\"{}\".\"{}\" at 0x{:X} ({} bytes)",
+                mem.module, mem.name, mem.base, mem.initial_bytes
+            ),
+        );
+    }
+    for global in &stats.exported_globals {
+        row(
+            "Exported global",
+            format!(
+                "\"{}\" at 0x{:X} ({} bytes{})",
+                global.name,
+                global.address,
+                global.width,
+                if global.mutable { ", mutable" } else { "" }
+            ),
+        );
+    }
     println!();
 
     // ── Output ──
@@ -418,6 +705,16 @@ fn print_text(stats: &CompileStats, input: &Path, output: &Path, verbose: bool,
         "SPI blob size",
         format!("{} bytes", format_number(stats.spi_blob_bytes)),
     );
+    let disabled: Vec<&str> = stats
+        .optimizations
+        .named()
+        .into_iter()
+        .filter(|(_, on)| !on)
+        .map(|(name, _)| name)
+        .collect();
+    if !disabled.is_empty() {
+        row("Disabled opts", disabled.join(", "));
+    }
 
     // ── Verbose: per-function + optimization stats ──
     if verbose {
@@ -433,7 +730,11 @@ fn print_verbose_text(stats: &CompileStats) {
     section("Functions");
     for f in &stats.functions {
         let kind = if f.is_leaf { "leaf" } else { "calls" };
-        let entry_marker = if f.is_entry { " [entry]" } else { "" };
+        let entry_marker = match f.shared_with {
+            Some(original) => format!(" [= #{original}]"),
+            None if f.is_entry => " [entry]".to_string(),
+            None => String::new(),
+        };
         let regalloc_info = if f.regalloc.allocated_values > 0 {
             format!(
                 "regalloc: {}/{} \u{2192} {{{}}}",
@@ -549,6 +850,13 @@ fn print_json(stats: &CompileStats, input: &Path, output: &Path, verbose: bool,
         }));
     }
 
+    let optimizations: serde_json::Map<String, serde_json::Value> = stats
+        .optimizations
+        .named()
+        .into_iter()
+        .map(|(name, on)| (name.to_string(), serde_json::Value::Bool(on)))
+        .collect();
+
     let mut obj = serde_json::json!({
         "version": COMPILER_VERSION,
         "compile_time_ms": ms,
@@ -580,6 +888,24 @@ fn print_json(stats: &CompileStats, input: &Path, output: &Path, verbose: bool,
             "rw_data_bytes": stats.rw_data_bytes,
             "heap_pages": stats.heap_pages,
             "stack_bytes": stats.stack_size,
+            "exported_memory": stats.exported_memory.as_ref().map(|mem| serde_json::json!({
+                "name": mem.name,
+                "base": format!("0x{:X}", mem.base),
+                "initial_bytes": mem.initial_bytes,
+            })),
+            "imported_memory": stats.imported_memory.as_ref().map(|mem| serde_json::json!({
+                "module": mem.module,
+                "name": mem.name,
+                "base": format!("0x{:X}", mem.base),
+                "initial_bytes": mem.initial_bytes,
+            })),
+            "exported_globals": stats.exported_globals.iter().map(|global| serde_json::json!({
+                "name": global.name,
+                "index": global.index,
+                "address": format!("0x{:X}", global.address),
+                "width": global.width,
+                "mutable": global.mutable,
+            })).collect::<Vec<_>>(),
         },
         "output": {
             "destination": output.display().to_string(),
@@ -589,6 +915,7 @@ fn print_json(stats: &CompileStats, input: &Path, output: &Path, verbose: bool,
             "jump_table_bytes": stats.jump_table_entries * 4,
             "spi_blob_bytes": stats.spi_blob_bytes,
         },
+        "optimizations": optimizations,
     });
 
     if verbose {
@@ -603,6 +930,7 @@ fn print_json(stats: &CompileStats, input: &Path, output: &Path, verbose: bool,
                     "frame_size": f.frame_size,
                     "is_leaf": f.is_leaf,
                     "is_entry": f.is_entry,
+                    "shared_with": f.shared_with,
                     "pre_dse_instructions": f.pre_dse_instructions,
                     "pre_peephole_instructions": f.pre_peephole_instructions,
                     "regalloc": {
@@ -643,63 +971,10 @@ fn read_wasm(path: &PathBuf) -> Result<Vec<u8>> {
     }
 }
 
-/// Parse an import map file.
-///
-/// Format (one mapping per line):
-/// ```text
-/// # Comments start with #
-/// abort = trap
-/// console.log = nop
-/// read = ecalli:5
-/// ```
-///
-/// Actions: `trap` (emit unreachable), `nop` (return 0), `ecalli:N` (emit PVM ecalli N).
-fn parse_import_map(path: &PathBuf) -> Result<BTreeMap<String, ImportAction>> {
+/// Read an import map file (format: see [`wasm_pvm::parse_import_map`]).
+fn read_import_map(path: &PathBuf) -> Result<BTreeMap<String, ImportAction>> {
     let contents =
         fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
-
-    let mut map = BTreeMap::new();
-
-    for (line_num, line) in contents.lines().enumerate() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
-
-        let (name, action) = line.split_once('=').ok_or_else(|| {
-            anyhow::anyhow!(
-                "{}:{}: invalid format, expected 'name = action'",
-                path.display(),
-                line_num + 1
-            )
-        })?;
-
-        let name = name.trim().to_string();
-        let action_str = action.trim();
-
-        let action = if action_str == "trap" {
-            ImportAction::Trap
-        } else if action_str == "nop" {
-            ImportAction::Nop
-        } else if let Some(idx_str) = action_str.strip_prefix("ecalli:") {
-            let idx: u32 = idx_str.parse().map_err(|_| {
-                anyhow::anyhow!(
-                    "{}:{}: invalid ecalli index '{idx_str}', expected a number",
-                    path.display(),
-                    line_num + 1
-                )
-            })?;
-            ImportAction::Ecalli(idx)
-        } else {
-            anyhow::bail!(
-                "{}:{}: unknown action '{action_str}', expected 'trap', 'nop', or 'ecalli:N'",
-                path.display(),
-                line_num + 1
-            );
-        };
-
-        map.insert(name, action);
-    }
-
-    Ok(map)
+    wasm_pvm::parse_import_map(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))
 }
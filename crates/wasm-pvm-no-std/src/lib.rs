@@ -0,0 +1,37 @@
+//! Uses the `wasm-pvm` instruction encoder and program blob layout from a
+//! `#![no_std]` crate. CI builds it for a target without `std`, so anything
+//! in those modules that starts depending on `std` fails the build.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use wasm_pvm::{Instruction, ProgramBlob, Result};
+
+/// Encodes each instruction on its own and decodes it back.
+///
+/// # Errors
+///
+/// Returns the decoder's error for the first instruction that fails to decode.
+pub fn roundtrip_instructions(instructions: &[Instruction]) -> Result<Vec<Instruction>> {
+    instructions
+        .iter()
+        .map(|instr| {
+            let bytes = instr.encode();
+            let (decoded, len) = Instruction::decode(&bytes)?;
+            debug_assert_eq!(len, bytes.len());
+            Ok(decoded)
+        })
+        .collect()
+}
+
+/// Encodes `blob` and decodes the bytes into a fresh blob.
+///
+/// # Errors
+///
+/// Returns the decoder's error if the encoded bytes are rejected.
+pub fn roundtrip_blob(blob: &ProgramBlob) -> Result<ProgramBlob> {
+    ProgramBlob::decode(&blob.encode())
+}
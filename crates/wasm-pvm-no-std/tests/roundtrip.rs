@@ -0,0 +1,42 @@
+//! The `no_std` build of `wasm-pvm` encodes and decodes the same bytes as
+//! the default build.
+
+use wasm_pvm::{Instruction, ProgramBlob};
+use wasm_pvm_no_std::{roundtrip_blob, roundtrip_instructions};
+
+fn sample() -> Vec<Instruction> {
+    vec![
+        Instruction::LoadImm { reg: 7, value: -3 },
+        Instruction::LoadImm64 {
+            reg: 8,
+            value: 0x1234_5678_9abc,
+        },
+        Instruction::Add32 {
+            dst: 9,
+            src1: 7,
+            src2: 8,
+        },
+        Instruction::BranchEqImm {
+            reg: 9,
+            value: 0,
+            offset: 5,
+        },
+        Instruction::Fallthrough,
+        Instruction::Trap,
+    ]
+}
+
+#[test]
+fn instructions_roundtrip() {
+    let instructions = sample();
+    assert_eq!(roundtrip_instructions(&instructions).unwrap(), instructions);
+}
+
+#[test]
+fn program_blob_roundtrip() {
+    let blob = ProgramBlob::new(sample()).with_jump_table(vec![0, 2]);
+    let decoded = roundtrip_blob(&blob).unwrap();
+    assert_eq!(decoded.instructions(), blob.instructions());
+    assert_eq!(decoded.jump_table(), blob.jump_table());
+    assert_eq!(decoded.encode(), blob.encode());
+}
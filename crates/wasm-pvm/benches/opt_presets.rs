@@ -0,0 +1,80 @@
+//! Compare optimization presets on the WAT fixtures.
+//!
+//! Usage: `cargo bench -p wasm-pvm --bench opt_presets`
+//!
+//! For each fixture and preset, prints the PVM instruction count and SPI blob
+//! size from [`CompileStats`], and the gas the harness interpreter charges for
+//! one run with the fixture's arguments. Every figure is deterministic, so two
+//! runs of this table can be diffed directly to spot a regression in any one
+//! optimization.
+
+use std::fs;
+use std::path::PathBuf;
+
+use wasm_pvm::test_harness::{ExitReason, run_program, wat_to_wasm};
+use wasm_pvm::{CompileOptions, OptimizationFlags, compile_with_stats};
+
+/// Fixture name under `tests/fixtures/wat` (without `.jam.wat`) and its
+/// arguments: little-endian u32 words.
+const FIXTURES: &[(&str, &[u32])] = &[
+    ("fibonacci", &[20]),
+    ("factorial", &[10]),
+    ("gcd", &[48, 18]),
+    ("is-prime", &[97]),
+];
+
+/// Every pass on (`all-on`), every optional pass off (`all-off`), and all on
+/// but one.
+fn presets() -> Vec<(&'static str, OptimizationFlags)> {
+    let all = OptimizationFlags::all;
+    vec![
+        ("all-on", all()),
+        ("all-off", OptimizationFlags::none()),
+        ("no-peephole", all().with_peephole(false)),
+        ("no-register-cache", all().with_register_cache(false)),
+        ("no-register-alloc", all().with_register_allocation(false)),
+        ("no-const-prop", all().with_constant_propagation(false)),
+        ("no-inline", all().with_inlining(false)),
+        ("no-fallthrough-jumps", all().with_fallthrough_jumps(false)),
+        ("no-scheduling", all().with_scheduling(false)),
+    ]
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../tests/fixtures/wat")
+        .join(format!("{name}.jam.wat"))
+}
+
+fn main() {
+    println!(
+        "{:<12} {:<22} {:>8} {:>8} {:>10}",
+        "fixture", "preset", "instrs", "bytes", "gas"
+    );
+    for &(name, words) in FIXTURES {
+        let path = fixture_path(name);
+        let wat = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("read {}: {err}", path.display()));
+        let wasm = wat_to_wasm(&wat).unwrap_or_else(|err| panic!("{name}: {err}"));
+        let args: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+        for (preset, optimizations) in presets() {
+            let options = CompileOptions {
+                optimizations,
+                ..CompileOptions::default()
+            };
+            let (program, stats) = compile_with_stats(&wasm, &options)
+                .unwrap_or_else(|err| panic!("{name} ({preset}): {err}"));
+            let result = run_program(&program, &args);
+            let gas = if result.exit == ExitReason::Halt {
+                result.gas_used.to_string()
+            } else {
+                format!("{:?}", result.exit)
+            };
+            println!(
+                "{name:<12} {preset:<22} {:>8} {:>8} {gas:>10}",
+                stats.pvm_instructions, stats.spi_blob_bytes
+            );
+        }
+    }
+}
@@ -33,6 +33,13 @@ pub const SCRATCH1: u8 = 5;
 /// General purpose scratch register.
 pub const SCRATCH2: u8 = 6;
 
+/// Registers `CompileOptions::reserved_registers` may withhold from codegen.
+/// r5/r6 have no fixed role: they are register-allocation targets and scratch
+/// for a few lowerings (bulk memory, funnel shifts, signed i64 saturating
+/// arithmetic). Every other register belongs to the calling convention or is
+/// a fixed temporary.
+pub const RESERVABLE_REGS: [u8; 2] = [SCRATCH1, SCRATCH2];
+
 /// Return value register (a0).
 /// Holds the first return value from a function call.
 /// Also used as the pointer to arguments (`args_ptr`) in the entry function.
@@ -49,8 +56,20 @@ pub const ARGS_LEN_REG: u8 = 8;
 pub const FIRST_LOCAL_REG: u8 = 9;
 
 /// Number of registers dedicated to local variables (r9-r12).
+///
+/// This is the default and the upper bound; `CompileOptions::local_regs` can
+/// lower it (e.g. for a host that reserves r12), in which case only
+/// r9..r9+N-1 carry parameters, are saved/restored and are register-allocated.
 pub const MAX_LOCAL_REGS: usize = 4;
 
+/// Base register for position-independent code (`CompileOptions::pic`).
+///
+/// The host loads it with the offset the program was relocated by; every
+/// absolute data address (globals, `RO_DATA`, linear memory) is then formed
+/// relative to it. It is the last local register, so PIC builds use at most
+/// `MAX_LOCAL_REGS - 1` local registers.
+pub const PIC_BASE_REG: u8 = 12;
+
 /// Maximum number of data registers for `host_call_N` imports (r7-r12).
 /// This is the hard ceiling: r7 (`RETURN_VALUE_REG`) through r12 = 6 registers.
 pub const MAX_HOST_CALL_DATA_ARGS: u8 = 6;
@@ -118,6 +137,14 @@ pub const OPERAND_SPILL_BASE: i32 = -0x100;
 /// Used by `host_call_r8` to retrieve the captured value.
 pub const R8_CAPTURE_SLOT_OFFSET: i32 = -0x108;
 
+/// Left in r7 when `call_indirect` traps on a table slot with no function:
+/// never initialized, or holding an import (which has no dispatch entry).
+pub const CALL_INDIRECT_NULL_TRAP: i32 = 1;
+
+/// Left in r7 when `call_indirect` traps because the slot's function has a
+/// different type than the call site expects.
+pub const CALL_INDIRECT_SIGNATURE_TRAP: i32 = 2;
+
 // ── Memory Layout ──
 
 // Re-export memory layout constants for convenience.
@@ -1,15 +1,31 @@
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+};
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[cfg(feature = "compiler")]
     #[error("WASM parsing error: {0}")]
     WasmParse(#[from] wasmparser::BinaryReaderError),
 
+    /// Reading the module failed (see `translate::compile_reader`).
+    #[cfg(feature = "compiler")]
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("Unsupported WASM feature: {0}")]
     Unsupported(String),
 
     #[error("Float operations are not supported by PVM")]
     FloatNotSupported,
 
+    /// A WASM operator that validates but belongs to a proposal this compiler
+    /// has no lowering for. `reason` explains the limitation.
+    #[error("Unsupported WASM operator {op}: {reason}")]
+    UnsupportedWasmOp { op: String, reason: String },
+
     #[error("No exported function found")]
     NoExportedFunction,
 
@@ -19,9 +35,22 @@ pub enum Error {
     #[error("Unresolved import: {0}")]
     UnresolvedImport(String),
 
+    /// Malformed import map text (see `translate::parse_import_map`).
+    #[error("Invalid import map: {0}")]
+    ImportMap(String),
+
+    /// Malformed assembly text (see [`Instruction::parse_asm`](crate::Instruction::parse_asm)).
+    #[error("Invalid assembly: {0}")]
+    Asm(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
+    /// The SPI sections don't fit the fixed memory layout (see
+    /// [`SpiProgram::validate`](crate::SpiProgram::validate)).
+    #[error("Invalid SPI layout: {0}")]
+    SpiLayout(String),
+
     /// Wrapper attaching the source WASM operator location to a translation error.
     ///
     /// `op_offset` is `Some(byte_offset)` for errors raised by the WASM-operator
@@ -52,6 +81,26 @@ pub enum Error {
     AdapterMerge { context: String, cause: Box<Error> },
 }
 
+#[cfg(feature = "compiler")]
+impl Error {
+    /// Check an internal invariant. A violation is a compiler bug rather than
+    /// a property of the input: it becomes [`Error::Internal`] so embedders
+    /// can recover, or panics with the same message when `panic` is set
+    /// (`CompileOptions::panic_on_internal_error`).
+    pub(crate) fn check_invariant(
+        holds: bool,
+        panic: bool,
+        msg: impl FnOnce() -> String,
+    ) -> Result<()> {
+        if holds {
+            return Ok(());
+        }
+        let msg = msg();
+        assert!(!panic, "internal error: {msg}");
+        Err(Error::Internal(msg))
+    }
+}
+
 fn format_op_offset(op_offset: Option<&usize>) -> String {
     match op_offset {
         Some(o) => format!(" at byte offset 0x{o:x}"),
@@ -59,4 +108,4 @@ fn format_op_offset(op_offset: Option<&usize>) -> String {
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
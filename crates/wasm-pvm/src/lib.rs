@@ -2,12 +2,16 @@
     clippy::too_many_lines, // TODO: Remove after refactoring lowering.rs (Task #30)
     clippy::missing_errors_doc // TODO: Add docs in V2 (Task #34/Documentation)
 )]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod abi;
 pub mod error;
 pub mod memory_layout;
 pub mod pvm;
 pub mod spi;
+pub mod trap_table;
 
 #[cfg(feature = "compiler")]
 pub mod llvm_backend;
@@ -24,14 +28,20 @@ pub mod translate;
 pub mod test_harness;
 
 pub use error::{Error, Result};
-pub use pvm::{Instruction, Opcode, ProgramBlob};
+pub use pvm::{
+    EncodingFamily, GasEstimate, Instruction, Opcode, ProgramBlob, disassemble, estimate_gas,
+};
 pub use spi::SpiProgram;
+pub use trap_table::{TrapEntry, TrapTable};
 
 #[cfg(feature = "compiler")]
 pub use translate::{
-    CompileOptions, ImportAction, OptimizationFlags, compile, compile_with_options,
-    compile_with_stats,
+    CompileOptions, ImportAction, OptimizationFlags, compile, compile_reader, compile_with_options,
+    compile_with_stats, parse_import_map,
 };
 
 #[cfg(feature = "compiler")]
 pub use translate::stats::CompileStats;
+
+#[cfg(feature = "compiler")]
+pub use pvm::reoptimize;
@@ -241,6 +241,15 @@ fn is_fusable_bitwise_not(instr: InstructionValue<'_>) -> bool {
     }
 }
 
+/// Check if `val` is the identity element of `op`, i.e. `x op val == x`.
+/// Matches `x | 0`, `x ^ 0`, `x + 0` and `x & -1` (all ones).
+fn is_identity_constant(op: BinaryOp, val: BasicValueEnum<'_>) -> bool {
+    matches!(
+        (op, try_get_constant(val)),
+        (BinaryOp::Or | BinaryOp::Xor | BinaryOp::Add, Some(0)) | (BinaryOp::And, Some(-1))
+    )
+}
+
 pub fn lower_binary_arith<'ctx>(
     e: &mut PvmEmitter<'ctx>,
     instr: InstructionValue<'ctx>,
@@ -259,6 +268,28 @@ pub fn lower_binary_arith<'ctx>(
     let bits = operand_bit_width(instr);
     let dst = result_reg(e, instr);
 
+    // Identity elision: the result is the non-constant operand unchanged, so
+    // copy it into the result instead of emitting a no-op ALU instruction.
+    // (Bitwise NOT `x ^ -1` is handled by the immediate folding below and
+    // lowers to a single `XorImm { value: -1 }`.)
+    let identity_src = if is_identity_constant(op, rhs) {
+        Some(lhs)
+    } else if is_identity_constant(op, lhs) {
+        Some(rhs)
+    } else {
+        None
+    };
+    if let Some(src) = identity_src {
+        let src_reg = operand_reg(e, src, dst);
+        if src_reg == dst {
+            e.load_operand(src, dst)?;
+        } else {
+            e.emit(Instruction::MoveReg { dst, src: src_reg });
+        }
+        e.store_to_slot(slot, dst);
+        return Ok(());
+    }
+
     // Try immediate folding for operations with constant RHS.
     if let Some(rhs_const) = try_get_constant(rhs)
         && i32::try_from(rhs_const).is_ok()
@@ -617,7 +648,13 @@ pub fn lower_binary_arith<'ctx>(
     Ok(())
 }
 
-/// Check if an instruction has exactly one use and that use is a branch instruction.
+/// Check if an instruction has exactly one use and that use is the branch
+/// ending its own block.
+///
+/// The deferred comparison is picked up by the next conditional branch that
+/// gets lowered, which is only guaranteed to be its user within one block:
+/// a `br` in another block may come after some other branch in the layout,
+/// or before the comparison.
 fn is_single_use_by_branch(instr: InstructionValue<'_>) -> bool {
     let first_use = instr.get_first_use();
     let Some(use_val) = first_use else {
@@ -627,9 +664,10 @@ fn is_single_use_by_branch(instr: InstructionValue<'_>) -> bool {
     if use_val.get_next_use().is_some() {
         return false;
     }
-    // The user must be a Br instruction.
+    // The user must be a Br instruction in the same block.
     if let inkwell::values::AnyValueEnum::InstructionValue(user) = use_val.get_user() {
-        return user.get_opcode() == InstructionOpcode::Br;
+        return user.get_opcode() == InstructionOpcode::Br
+            && user.get_parent() == instr.get_parent();
     }
     false
 }
@@ -1060,11 +1098,15 @@ fn try_get_inverted_condition(val: BasicValueEnum<'_>) -> Option<BasicValueEnum<
         InstructionOpcode::Xor => {
             let op0 = instr.get_operand(0).and_then(Operand::value)?;
             let op1 = instr.get_operand(1).and_then(Operand::value)?;
-            // xor(x, 1) — boolean inversion
-            if try_get_constant(op1) == Some(1) {
+            // xor(x, 1) — boolean inversion. An `i1` `true` sign-extends
+            // to -1, so compare the zero-extended constant.
+            let is_one = |v: BasicValueEnum<'_>| {
+                v.is_int_value() && v.into_int_value().get_zero_extended_constant() == Some(1)
+            };
+            if is_one(op1) {
                 return Some(op0);
             }
-            if try_get_constant(op0) == Some(1) {
+            if is_one(op0) {
                 return Some(op1);
             }
             None
@@ -1089,78 +1131,150 @@ fn try_get_inverted_condition(val: BasicValueEnum<'_>) -> Option<BasicValueEnum<
     }
 }
 
+/// Lower `select` to a conditional move.
+///
+/// `CmovIz`/`CmovNz` copy whole registers, so selecting between pointers with
+/// the high bit set (e.g. into the args region near `0xFEFF_0000`) keeps them
+/// intact. The `*Imm` forms sign-extend their immediate and are only used for
+/// constants that survive that round trip.
+///
+/// Side-effect-free `if`/`else` diamonds that only pick a value (`if (c) x = a;
+/// else x = b;`) also end up here: simplifycfg folds their phi into a `select`
+/// before the backend runs, so they become a `Cmov` rather than a branch.
 pub fn lower_select<'ctx>(e: &mut PvmEmitter<'ctx>, instr: InstructionValue<'ctx>) -> Result<()> {
-    // select i1 %cond, i64 %true_val, i64 %false_val
+    // select i1 %cond, iN %true_val, iN %false_val
     let cond = get_operand(instr, 0)?;
     let true_val = get_operand(instr, 1)?;
     let false_val = get_operand(instr, 2)?;
+    let bits = operand_bit_width(instr);
     let slot = result_slot(e, instr)?;
 
-    // Load-side coalescing: use allocated registers directly as Cmov operands.
-    // Safe because all select operands are simultaneously live (different alloc
-    // regs guaranteed by the allocator). Store-side coalescing (result_reg) is
-    // NOT applied because loading into the alloc reg triggers invalidate_reg
-    // which can corrupt cache state for subsequent operand loads.
+    // The conditional move overwrites its default operand, so that is always
+    // a fresh copy in TEMP_RESULT: reusing the value's allocated register
+    // would clobber it whenever the other side is selected. The moved-in
+    // value and the condition are only read and can stay where they are.
+    // Store-side coalescing (result_reg) is NOT applied because loading into
+    // the alloc reg triggers invalidate_reg, which can corrupt cache state for
+    // subsequent operand loads.
 
     // Try to use CmovNzImm/CmovIzImm when one operand is a constant that fits i32.
     // CmovNzImm: if cond != 0, dst = imm (keeps dst otherwise)
     // CmovIzImm: if cond == 0, dst = imm (keeps dst otherwise)
-    let true_const = try_get_constant(true_val).filter(|v| i32::try_from(*v).is_ok());
-    let false_const = try_get_constant(false_val).filter(|v| i32::try_from(*v).is_ok());
-
-    if let Some(tv) = true_const {
+    if let Some(tv) = select_immediate(true_val, bits) {
         // true_val is constant: load false_val as default, CmovNzImm overwrites if cond != 0
-        let def_reg = operand_reg_avoiding(e, false_val, TEMP_RESULT, &[TEMP1]);
-        e.load_operand(false_val, def_reg)?;
-        let cond_reg = operand_reg_avoiding(e, cond, TEMP1, &[TEMP_RESULT]);
-        e.load_operand(cond, cond_reg)?;
+        load_select_value(e, false_val, bits)?;
+        let cond_reg = load_select_condition(e, cond, &[TEMP_RESULT])?;
         e.emit(Instruction::CmovNzImm {
-            dst: def_reg,
+            dst: TEMP_RESULT,
             cond: cond_reg,
-            value: tv as i32,
+            value: tv,
         });
-        e.store_to_slot(slot, def_reg);
-    } else if let Some(fv) = false_const {
+    } else if let Some(fv) = select_immediate(false_val, bits) {
         // false_val is constant: load true_val as default, CmovIzImm overwrites if cond == 0
-        let def_reg = operand_reg_avoiding(e, true_val, TEMP_RESULT, &[TEMP1]);
-        e.load_operand(true_val, def_reg)?;
-        let cond_reg = operand_reg_avoiding(e, cond, TEMP1, &[TEMP_RESULT]);
-        e.load_operand(cond, cond_reg)?;
+        load_select_value(e, true_val, bits)?;
+        let cond_reg = load_select_condition(e, cond, &[TEMP_RESULT])?;
         e.emit(Instruction::CmovIzImm {
-            dst: def_reg,
-            cond: cond_reg,
-            value: fv as i32,
-        });
-        e.store_to_slot(slot, def_reg);
-    } else if let Some(inner_cond) = try_get_inverted_condition(cond) {
-        // Inverted condition: select(!c, tv, fv) ≡ select(c, fv, tv)
-        // Use CmovIz: load false_val as default, overwrite with true_val when inner_cond==0
-        let def_reg = operand_reg_avoiding(e, false_val, TEMP_RESULT, &[TEMP1, TEMP2]);
-        e.load_operand(false_val, def_reg)?;
-        let src_reg = operand_reg_avoiding(e, true_val, TEMP2, &[TEMP_RESULT, TEMP1]);
-        e.load_operand(true_val, src_reg)?;
-        let cond_reg = operand_reg_avoiding(e, inner_cond, TEMP1, &[TEMP_RESULT, TEMP2]);
-        e.load_operand(inner_cond, cond_reg)?;
-        e.emit(Instruction::CmovIz {
-            dst: def_reg,
-            src: src_reg,
+            dst: TEMP_RESULT,
             cond: cond_reg,
+            value: fv,
         });
-        e.store_to_slot(slot, def_reg);
     } else {
-        // Neither is a small constant: use register CmovNz
-        let def_reg = operand_reg_avoiding(e, false_val, TEMP_RESULT, &[TEMP1, TEMP2]);
-        e.load_operand(false_val, def_reg)?;
+        // Inverted condition: select(!c, tv, fv) ≡ select(c, fv, tv), so
+        // CmovIz on the inner condition replaces the default with true_val.
+        let (cond, move_if_zero) = match try_get_inverted_condition(cond) {
+            Some(inner_cond) => (inner_cond, true),
+            None => (cond, false),
+        };
+        load_select_value(e, false_val, bits)?;
         let src_reg = operand_reg_avoiding(e, true_val, TEMP2, &[TEMP_RESULT, TEMP1]);
         e.load_operand(true_val, src_reg)?;
-        let cond_reg = operand_reg_avoiding(e, cond, TEMP1, &[TEMP_RESULT, TEMP2]);
-        e.load_operand(cond, cond_reg)?;
-        e.emit(Instruction::CmovNz {
-            dst: def_reg,
-            src: src_reg,
-            cond: cond_reg,
+        let cond_reg = load_select_condition(e, cond, &[TEMP_RESULT, src_reg])?;
+        e.emit(if move_if_zero {
+            Instruction::CmovIz {
+                dst: TEMP_RESULT,
+                src: src_reg,
+                cond: cond_reg,
+            }
+        } else {
+            Instruction::CmovNz {
+                dst: TEMP_RESULT,
+                src: src_reg,
+                cond: cond_reg,
+            }
         });
-        e.store_to_slot(slot, def_reg);
     }
+    e.store_to_slot(slot, TEMP_RESULT);
     Ok(())
 }
+
+/// A `bits`-wide select operand as the immediate of `CmovNzImm`/`CmovIzImm`,
+/// which sign-extend it: in the value's register form, i.e. 0 or 1 for an
+/// `i1` and sign-extended otherwise.
+fn select_immediate(val: BasicValueEnum<'_>, bits: u32) -> Option<i32> {
+    let BasicValueEnum::IntValue(iv) = val else {
+        return None;
+    };
+    let value = if bits == 1 {
+        iv.get_zero_extended_constant()? as i64
+    } else {
+        iv.get_sign_extended_constant()?
+    };
+    i32::try_from(value).ok()
+}
+
+/// Load a select operand into TEMP_RESULT, materializing an `i1` constant as
+/// 0 or 1 (a plain constant load sign-extends `true` to -1).
+fn load_select_value<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    val: BasicValueEnum<'ctx>,
+    bits: u32,
+) -> Result<()> {
+    if bits == 1
+        && let Some(value) = select_immediate(val, bits)
+    {
+        e.emit(Instruction::LoadImm {
+            reg: TEMP_RESULT,
+            value,
+        });
+        Ok(())
+    } else {
+        e.load_operand(val, TEMP_RESULT)
+    }
+}
+
+/// Load a select condition, avoiding the `avoid` registers, and return the
+/// register whose zero-ness decides the select.
+///
+/// An `i1` from a compare, `trunc` or constant is 0 or 1 in its register (a
+/// constant `true` as -1, which is as non-zero). Any other `i1` only
+/// guarantees bit 0 (`xor %c, true` lowers to `XorImm -1`, which leaves -2
+/// for a true `%c`), so it is masked into TEMP1 first. Wider conditions
+/// (the operand of an inverted `icmp eq x, 0`) are tested whole.
+fn load_select_condition<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    cond: BasicValueEnum<'ctx>,
+    avoid: &[u8],
+) -> Result<u8> {
+    let cond_reg = operand_reg_avoiding(e, cond, TEMP1, avoid);
+    e.load_operand(cond, cond_reg)?;
+    let BasicValueEnum::IntValue(iv) = cond else {
+        return Ok(cond_reg);
+    };
+    let normalized = iv.get_type().get_bit_width() > 1
+        || iv.is_const()
+        || iv.as_instruction().is_some_and(|i| {
+            matches!(
+                i.get_opcode(),
+                InstructionOpcode::ICmp | InstructionOpcode::Trunc
+            )
+        });
+    if normalized {
+        return Ok(cond_reg);
+    }
+    e.emit(Instruction::AndImm {
+        dst: TEMP1,
+        src: cond_reg,
+        value: 1,
+    });
+    Ok(TEMP1)
+}
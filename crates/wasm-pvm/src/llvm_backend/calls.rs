@@ -20,6 +20,7 @@ use super::emitter::{
     LlvmCallFixup, LlvmIndirectCallFixup, LoweringContext, PvmEmitter, get_operand, result_slot,
 };
 use crate::abi::{HostCallVariant, TEMP_RESULT, TEMP1, TEMP2, parse_host_call_variant};
+use crate::translate::wasm_module::describe_function;
 
 /// Lower a WASM function call.
 pub fn lower_wasm_call<'ctx>(
@@ -47,9 +48,17 @@ pub fn lower_wasm_call<'ctx>(
         .function_signatures
         .get(global_func_idx as usize)
         .copied()
-        .ok_or_else(|| Error::Internal(format!("unknown function index: {global_func_idx}")))?;
+        .ok_or_else(|| {
+            Error::Internal(format!(
+                "unknown function index: {}",
+                describe_function(&ctx.function_names, global_func_idx)
+            ))
+        })?;
 
     // Spill register-allocated values before the call (r5/r6 are caller-saved).
+    // Self-recursive calls need no special casing: every activation's locals
+    // live in its own SP-relative frame, which the callee's frame sits below,
+    // so beyond these dirty registers there is nothing to save or restore.
     e.spill_allocated_regs();
 
     // Load arguments from LLVM call operands into r9-r12 (first 4) and
@@ -58,20 +67,22 @@ pub fn lower_wasm_call<'ctx>(
 
     for i in 0..num_args {
         let arg = get_operand(instr, i as u32)?;
-        if i < abi::MAX_LOCAL_REGS {
+        if i < e.config.local_regs {
             e.load_operand(arg, abi::FIRST_LOCAL_REG + i as u8)?;
         } else {
-            debug_assert!(
+            Error::check_invariant(
                 e.config.param_overflow_reserved,
-                "direct call needs param overflow (arg {i}) but area was not reserved"
-            );
+                e.config.panic_on_internal_error,
+                || format!("direct call needs param overflow (arg {i}) but area was not reserved"),
+            )?;
             e.load_operand(arg, TEMP1)?;
             let overflow_offset =
-                e.config.param_overflow_base + ((i - abi::MAX_LOCAL_REGS) * 8) as i32;
+                e.config.param_overflow_base + ((i - e.config.local_regs) * 8) as i32;
             e.emit(Instruction::LoadImm {
                 reg: TEMP2,
                 value: overflow_offset,
             });
+            e.emit_pic_rebase(TEMP2);
             e.emit(Instruction::StoreIndU64 {
                 base: TEMP2,
                 src: TEMP1,
@@ -80,14 +91,29 @@ pub fn lower_wasm_call<'ctx>(
         }
     }
 
-    // Emit call fixup: LoadImmJump combines return address load + jump to callee.
     let call_return_addr = e.alloc_call_return_addr();
     let jump_instr = e.instructions.len();
-    e.emit(Instruction::LoadImmJump {
-        reg: abi::RETURN_ADDR_REG,
-        value: call_return_addr,
-        offset: 0, // patched during fixup resolution
-    });
+    if ctx.function_sections {
+        // Jump through the callee's jump-table entry so its code can move
+        // without re-linking callers.
+        e.emit(Instruction::LoadImm {
+            reg: TEMP1,
+            value: 2 * (local_func_idx as i32 + 1),
+        });
+        e.emit(Instruction::LoadImmJumpInd {
+            base: TEMP1,
+            dst: abi::RETURN_ADDR_REG,
+            value: call_return_addr,
+            offset: 0,
+        });
+    } else {
+        // Emit call fixup: LoadImmJump combines return address load + jump to callee.
+        e.emit(Instruction::LoadImmJump {
+            reg: abi::RETURN_ADDR_REG,
+            value: call_return_addr,
+            offset: 0, // patched during fixup resolution
+        });
+    }
 
     // Return point: callee clobbers all caller-saved registers.
     e.emit(Instruction::Fallthrough);
@@ -97,11 +123,19 @@ pub fn lower_wasm_call<'ctx>(
     // registers actually used for this call's arguments.
     e.reload_allocated_regs_after_call_with_arity(num_args);
 
-    e.call_fixups.push(LlvmCallFixup {
-        return_addr_instr: jump_instr, // same instruction for LoadImmJump
-        jump_instr,
-        target_func: local_func_idx,
-    });
+    if ctx.function_sections {
+        let jump_ind_instr = jump_instr + 1;
+        e.indirect_call_fixups.push(LlvmIndirectCallFixup {
+            return_addr_instr: jump_ind_instr,
+            jump_ind_instr,
+        });
+    } else {
+        e.call_fixups.push(LlvmCallFixup {
+            return_addr_instr: jump_instr, // same instruction for LoadImmJump
+            jump_instr,
+            target_func: local_func_idx,
+        });
+    }
 
     // If function returns a value, store r7 to result slot.
     if has_return {
@@ -130,7 +164,8 @@ pub fn lower_import_call<'ctx>(
         .copied()
         .ok_or_else(|| {
             Error::Internal(format!(
-                "unknown import function signature for index {global_func_idx}"
+                "unknown import function signature for index {}",
+                describe_function(&ctx.function_names, global_func_idx)
             ))
         })?;
 
@@ -232,6 +267,7 @@ fn lower_mapped_import<'ctx>(
                     abi::MAX_HOST_CALL_DATA_ARGS,
                 )));
             }
+            check_pic_data_args(e, num_args)?;
 
             // Spill register-allocated values before ecalli.
             e.spill_allocated_regs();
@@ -314,6 +350,17 @@ fn extract_ecalli_index(instr: InstructionValue<'_>) -> Result<u32> {
     })
 }
 
+/// Reject host calls whose data arguments would overwrite the PIC base register.
+fn check_pic_data_args(e: &PvmEmitter<'_>, data_args: usize) -> Result<()> {
+    if e.config.pic_enabled && data_args > usize::from(abi::PIC_BASE_REG - abi::RETURN_VALUE_REG) {
+        return Err(Error::Unsupported(format!(
+            "host call with {data_args} data arguments would overwrite r{}, the pic base register",
+            abi::PIC_BASE_REG
+        )));
+    }
+    Ok(())
+}
+
 /// Emit an `ecalli` for typed `host_call_N` variants (`host_call_0` through `host_call_6`).
 ///
 /// Convention: `host_call_N(ecalli_index, r7, r8, ..., r7+N-1) -> i64`
@@ -339,6 +386,7 @@ fn lower_host_call_typed<'ctx>(
     }
 
     let ecalli_index = extract_ecalli_index(instr)?;
+    check_pic_data_args(e, expected_data_args as usize)?;
 
     // Spill register-allocated values before ecalli.
     e.spill_allocated_regs();
@@ -441,6 +489,7 @@ fn lower_pvm_ptr<'ctx>(
         src: TEMP_RESULT,
         value: ctx.wasm_memory_base,
     });
+    e.emit_pic_rebase(TEMP_RESULT);
 
     if has_return {
         let slot = result_slot(e, instr)?;
@@ -456,40 +505,64 @@ pub fn lower_pvm_call_indirect<'ctx>(
     instr: InstructionValue<'ctx>,
     ctx: &LoweringContext,
 ) -> Result<()> {
-    // __pvm_call_indirect(type_idx, table_entry, arg0, arg1, ...)
-    // Operands: [type_idx, table_entry, arg0, ..., argN-1, fn_ptr]
+    lower_dynamic_call(e, instr, ctx, "__pvm_call_indirect", true)
+}
+
+/// Lower `call_ref`: the callee operand is already a jump-ref (what a funcref
+/// holds), so the dispatch-table lookup and signature check are skipped —
+/// validation guarantees the static type. A null (0) ref faults on the jump.
+pub fn lower_pvm_call_ref<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    instr: InstructionValue<'ctx>,
+    ctx: &LoweringContext,
+) -> Result<()> {
+    lower_dynamic_call(e, instr, ctx, "__pvm_call_ref", false)
+}
+
+fn lower_dynamic_call<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    instr: InstructionValue<'ctx>,
+    ctx: &LoweringContext,
+    name: &str,
+    via_table: bool,
+) -> Result<()> {
+    // name(type_idx, callee, arg0, arg1, ...) where callee is a table entry
+    // index (`via_table`) or a jump-ref.
+    // Operands: [type_idx, callee, arg0, ..., argN-1, fn_ptr]
     let num_operands = instr.get_num_operands();
 
     // Prevent underflow when calculating num_args.
     if num_operands < 3 {
         return Err(Error::Internal(format!(
-            "__pvm_call_indirect requires at least 3 operands, got {num_operands}"
+            "{name} requires at least 3 operands, got {num_operands}"
         )));
     }
 
-    let num_args = (num_operands - 3) as usize; // subtract type_idx, table_entry, fn_ptr
+    let num_args = (num_operands - 3) as usize; // subtract type_idx, callee, fn_ptr
 
     let type_idx_val = get_operand(instr, 0)?;
-    let table_entry_val = get_operand(instr, 1)?;
+    let callee_val = get_operand(instr, 1)?;
 
     // Validate type_idx is a constant integer (required for signature validation).
     let expected_type_idx = match type_idx_val {
-        BasicValueEnum::IntValue(iv) => iv.get_zero_extended_constant().ok_or_else(|| {
-            Error::Internal("__pvm_call_indirect type_idx must be a constant".into())
-        })? as u32,
+        BasicValueEnum::IntValue(iv) => iv
+            .get_zero_extended_constant()
+            .ok_or_else(|| Error::Internal(format!("{name} type_idx must be a constant")))?
+            as u32,
         _ => {
-            return Err(Error::Internal(
-                "__pvm_call_indirect type_idx must be an integer".into(),
-            ));
+            return Err(Error::Internal(format!(
+                "{name} type_idx must be an integer"
+            )));
         }
     };
 
     // Spill register-allocated values before indirect call (r5/r6 are caller-saved).
     e.spill_allocated_regs();
 
-    // Load table entry index into ARGS_LEN_REG and save it in the spill area.
-    // Using OPERAND_SPILL_BASE ensures we have reserved space in the frame.
-    e.load_operand(table_entry_val, abi::ARGS_LEN_REG)?;
+    // Load the callee (table index or jump-ref) into ARGS_LEN_REG and save it
+    // in the spill area. Using OPERAND_SPILL_BASE ensures we have reserved
+    // space in the frame.
+    e.load_operand(callee_val, abi::ARGS_LEN_REG)?;
     e.emit(Instruction::StoreIndU64 {
         base: abi::STACK_PTR_REG,
         src: abi::ARGS_LEN_REG,
@@ -499,20 +572,26 @@ pub fn lower_pvm_call_indirect<'ctx>(
     // Load function arguments into r9-r12 and overflow area.
     for i in 0..num_args {
         let arg = get_operand(instr, (i + 2) as u32)?;
-        if i < abi::MAX_LOCAL_REGS {
+        if i < e.config.local_regs {
             e.load_operand(arg, abi::FIRST_LOCAL_REG + i as u8)?;
         } else {
-            debug_assert!(
+            Error::check_invariant(
                 e.config.param_overflow_reserved,
-                "indirect call needs param overflow (arg {i}) but area was not reserved"
-            );
+                e.config.panic_on_internal_error,
+                || {
+                    format!(
+                        "indirect call needs param overflow (arg {i}) but area was not reserved"
+                    )
+                },
+            )?;
             e.load_operand(arg, TEMP1)?;
             let overflow_offset =
-                e.config.param_overflow_base + ((i - abi::MAX_LOCAL_REGS) * 8) as i32;
+                e.config.param_overflow_base + ((i - e.config.local_regs) * 8) as i32;
             e.emit(Instruction::LoadImm {
                 reg: TEMP2,
                 value: overflow_offset,
             });
+            e.emit_pic_rebase(TEMP2);
             e.emit(Instruction::StoreIndU64 {
                 base: TEMP2,
                 src: TEMP1,
@@ -521,44 +600,16 @@ pub fn lower_pvm_call_indirect<'ctx>(
         }
     }
 
-    // Restore table index from saved location.
+    // Restore the callee from its saved location.
     e.emit(Instruction::LoadIndU64 {
         dst: abi::ARGS_LEN_REG, // r8, used as SAVED_TABLE_IDX_REG
         base: abi::STACK_PTR_REG,
         offset: abi::OPERAND_SPILL_BASE, // Use documented spill area
     });
 
-    // Dispatch table lookup: each entry is 8 bytes (4-byte jump ref + 4-byte type index).
-    // table_addr = RO_DATA_BASE + (table_idx << 3)
-    e.emit(Instruction::ShloLImm32 {
-        dst: abi::ARGS_LEN_REG,
-        src: abi::ARGS_LEN_REG,
-        value: 3,
-    });
-    e.emit(Instruction::AddImm32 {
-        dst: abi::ARGS_LEN_REG,
-        src: abi::ARGS_LEN_REG,
-        value: abi::RO_DATA_BASE,
-    });
-
-    // Load and validate type signature.
-    e.emit(Instruction::LoadIndU32 {
-        dst: TEMP1,
-        base: abi::ARGS_LEN_REG,
-        offset: 4, // type index at offset 4
-    });
-
-    let sig_ok_label = e.alloc_label();
-    e.emit_branch_eq_imm_to_label(TEMP1, expected_type_idx as i32, sig_ok_label);
-    e.emit(Instruction::Trap);
-    e.define_label(sig_ok_label);
-
-    // Load jump address from dispatch table (at offset 0).
-    e.emit(Instruction::LoadIndU32 {
-        dst: abi::ARGS_LEN_REG,
-        base: abi::ARGS_LEN_REG,
-        offset: 0,
-    });
+    if via_table {
+        lower_dispatch_table_lookup(e, expected_type_idx);
+    }
 
     // Emit indirect call: LoadImmJumpInd combines return-address load + indirect jump.
     // The return address is a jump table address ((index+1)*2), pre-computed
@@ -606,6 +657,60 @@ pub fn lower_pvm_call_indirect<'ctx>(
     Ok(())
 }
 
+/// Replace the table index in `ARGS_LEN_REG` with the entry's jump-ref,
+/// trapping if the entry's signature isn't `expected_type_idx`.
+///
+/// Null slots and imported functions are stored as `u32::MAX` and trap on
+/// their own path, so the two failures have different trap PCs and leave
+/// different codes in r7 (`abi::CALL_INDIRECT_*_TRAP`).
+fn lower_dispatch_table_lookup(e: &mut PvmEmitter<'_>, expected_type_idx: u32) {
+    // Dispatch table lookup: each entry is 8 bytes (4-byte jump ref + 4-byte type index).
+    // table_addr = RO_DATA_BASE + (table_idx << 3)
+    e.emit(Instruction::ShloLImm32 {
+        dst: abi::ARGS_LEN_REG,
+        src: abi::ARGS_LEN_REG,
+        value: 3,
+    });
+    e.emit(Instruction::AddImm32 {
+        dst: abi::ARGS_LEN_REG,
+        src: abi::ARGS_LEN_REG,
+        value: abi::RO_DATA_BASE,
+    });
+    e.emit_pic_rebase(abi::ARGS_LEN_REG);
+
+    // Load and validate type signature. Sign-extended so that the `u32::MAX`
+    // of an empty entry compares equal to the immediate -1.
+    e.emit(Instruction::LoadIndI32 {
+        dst: TEMP1,
+        base: abi::ARGS_LEN_REG,
+        offset: 4, // type index at offset 4
+    });
+
+    let sig_ok_label = e.alloc_label();
+    let null_entry_label = e.alloc_label();
+    e.emit_branch_eq_imm_to_label(TEMP1, expected_type_idx as i32, sig_ok_label);
+    e.emit_branch_eq_imm_to_label(TEMP1, -1, null_entry_label);
+    e.emit(Instruction::LoadImm {
+        reg: abi::RETURN_VALUE_REG,
+        value: abi::CALL_INDIRECT_SIGNATURE_TRAP,
+    });
+    e.emit(Instruction::Trap);
+    e.define_label(null_entry_label);
+    e.emit(Instruction::LoadImm {
+        reg: abi::RETURN_VALUE_REG,
+        value: abi::CALL_INDIRECT_NULL_TRAP,
+    });
+    e.emit(Instruction::Trap);
+    e.define_label(sig_ok_label);
+
+    // Load jump address from dispatch table (at offset 0).
+    e.emit(Instruction::LoadIndU32 {
+        dst: abi::ARGS_LEN_REG,
+        base: abi::ARGS_LEN_REG,
+        offset: 0,
+    });
+}
+
 /// Lower a call instruction (dispatches to intrinsic, wasm call, or import).
 pub fn lower_call<'ctx>(
     e: &mut PvmEmitter<'ctx>,
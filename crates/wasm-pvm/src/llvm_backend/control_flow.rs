@@ -21,8 +21,8 @@ use crate::pvm::Instruction;
 use crate::{Error, Result, abi};
 
 use super::emitter::{
-    PvmEmitter, SCRATCH1, SCRATCH2, get_bb_operand, get_operand, has_phi_from, operand_reg,
-    operand_reg_avoiding, result_slot, try_get_constant,
+    FusedIcmp, PvmEmitter, SCRATCH1, SCRATCH2, get_bb_operand, get_operand, has_phi_from,
+    operand_reg, operand_reg_avoiding, result_slot, try_get_constant,
 };
 use crate::abi::{STACK_PTR_REG, TEMP_RESULT, TEMP1, TEMP2};
 
@@ -70,32 +70,39 @@ pub fn lower_br<'ctx>(
         let fused = e.pending_fused_icmp.take();
 
         if !then_has_phis && !else_has_phis {
-            if let Some(fused) = fused {
-                // Emit fused comparison+branch to then_label, fallthrough to else.
-                emit_fused_branch(e, &fused, then_label)?;
-                e.emit_jump_to_label(else_label);
+            // Normally branch to then and fall through to else. When the block
+            // layout placed `then` next (its `else` is cold), branch to else on
+            // the inverted condition so the hot path falls through instead.
+            let invert = e.config.fallthrough_jumps_enabled
+                && then_label != else_label
+                && e.next_block_label == Some(then_label);
+            let (taken_label, next_label) = if invert {
+                (else_label, then_label)
             } else {
-                // Load-side coalescing for branch condition (no dst conflict — branches have no dest).
-                let cond_reg = operand_reg(e, cond, TEMP1);
-                if cond_reg == TEMP1 {
-                    e.load_operand(cond, TEMP1)?;
-                }
-                e.emit_branch_ne_imm_to_label(cond_reg, 0, then_label);
-                e.emit_jump_to_label(else_label);
-            }
+                (then_label, else_label)
+            };
+            emit_cond_branch(e, cond, fused, invert, taken_label)?;
+            e.emit_jump_to_label(next_label);
+        } else if then_has_phis != else_has_phis {
+            // Only one edge carries phi copies (typically a `br_if` loop
+            // back-edge): branch straight to the other successor and emit the
+            // copies inline before the jump. Without a trampoline label in the
+            // way, the jump can still fall through into the next block, and
+            // when the copies come out empty `invert_branch_over_jump` folds
+            // the pair into a single branch.
+            let (taken_label, copy_bb, copy_label) = if then_has_phis {
+                (else_label, then_bb, then_label)
+            } else {
+                (then_label, else_bb, else_label)
+            };
+            emit_cond_branch(e, cond, fused, then_has_phis, taken_label)?;
+            emit_phi_copies(e, current_bb, copy_bb)?;
+            e.emit_jump_to_label(copy_label);
         } else {
             // Need per-edge phi copies. Create trampolines.
             let saved_next = e.next_block_label.take();
             let then_trampoline = e.alloc_label();
-            if let Some(fused) = fused {
-                emit_fused_branch(e, &fused, then_trampoline)?;
-            } else {
-                let cond_reg = operand_reg(e, cond, TEMP1);
-                if cond_reg == TEMP1 {
-                    e.load_operand(cond, TEMP1)?;
-                }
-                e.emit_branch_ne_imm_to_label(cond_reg, 0, then_trampoline);
-            }
+            emit_cond_branch(e, cond, fused, false, then_trampoline)?;
 
             // Else path: phi copies + jump to else.
             emit_phi_copies(e, current_bb, else_bb)?;
@@ -111,6 +118,39 @@ pub fn lower_br<'ctx>(
     Ok(())
 }
 
+/// Branch to `label` when `cond` holds (or, with `invert`, when it doesn't),
+/// using the fused comparison when the condition's `icmp` was deferred.
+fn emit_cond_branch<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    cond: BasicValueEnum<'ctx>,
+    fused: Option<FusedIcmp<'ctx>>,
+    invert: bool,
+    label: usize,
+) -> Result<()> {
+    if let Some(fused) = fused {
+        let fused = if invert {
+            FusedIcmp {
+                predicate: inverse_predicate(fused.predicate),
+                ..fused
+            }
+        } else {
+            fused
+        };
+        return emit_fused_branch(e, &fused, label);
+    }
+    // Load-side coalescing for branch condition (no dst conflict — branches have no dest).
+    let cond_reg = operand_reg(e, cond, TEMP1);
+    if cond_reg == TEMP1 {
+        e.load_operand(cond, TEMP1)?;
+    }
+    if invert {
+        e.emit_branch_eq_imm_to_label(cond_reg, 0, label);
+    } else {
+        e.emit_branch_ne_imm_to_label(cond_reg, 0, label);
+    }
+    Ok(())
+}
+
 /// Lower a switch instruction.
 pub fn lower_switch<'ctx>(
     e: &mut PvmEmitter<'ctx>,
@@ -222,6 +262,7 @@ pub fn lower_return<'ctx>(
                 src: TEMP1,
                 value: wasm_memory_base,
             });
+            e.emit_pic_rebase(abi::ARGS_PTR_REG);
             // r8 = r7 + len (end address)
             e.emit(Instruction::Add64 {
                 dst: abi::ARGS_LEN_REG,
@@ -261,7 +302,7 @@ fn emit_epilogue(e: &mut PvmEmitter<'_>, is_main: bool) {
         });
     } else {
         // Restore callee-saved registers r9-r12 (only those actually saved).
-        for i in 0..abi::MAX_LOCAL_REGS {
+        for i in 0..e.config.local_regs {
             if let Some(offset) = e.callee_save_offsets[i] {
                 e.emit(Instruction::LoadIndU64 {
                     dst: abi::FIRST_LOCAL_REG + i as u8,
@@ -295,6 +336,22 @@ fn emit_epilogue(e: &mut PvmEmitter<'_>, is_main: bool) {
     }
 }
 
+/// The predicate that holds exactly when `predicate` does not.
+fn inverse_predicate(predicate: IntPredicate) -> IntPredicate {
+    match predicate {
+        IntPredicate::EQ => IntPredicate::NE,
+        IntPredicate::NE => IntPredicate::EQ,
+        IntPredicate::ULT => IntPredicate::UGE,
+        IntPredicate::UGE => IntPredicate::ULT,
+        IntPredicate::ULE => IntPredicate::UGT,
+        IntPredicate::UGT => IntPredicate::ULE,
+        IntPredicate::SLT => IntPredicate::SGE,
+        IntPredicate::SGE => IntPredicate::SLT,
+        IntPredicate::SLE => IntPredicate::SGT,
+        IntPredicate::SGT => IntPredicate::SLE,
+    }
+}
+
 /// Emit a fused comparison+branch for a deferred `ICmp`.
 ///
 /// Loads the `ICmp` operands into TEMP1/TEMP2 and emits a single PVM branch
@@ -302,7 +359,7 @@ fn emit_epilogue(e: &mut PvmEmitter<'_>, is_main: bool) {
 /// Falls through otherwise.
 fn emit_fused_branch<'a>(
     e: &mut PvmEmitter<'a>,
-    fused: &super::emitter::FusedIcmp<'a>,
+    fused: &FusedIcmp<'a>,
     true_label: usize,
 ) -> Result<()> {
     // Try immediate folding: branch-imm instructions avoid loading one operand.
@@ -31,6 +31,7 @@ use crate::translate::OptimizationFlags;
 use super::regalloc::RegAllocResult;
 
 /// Context for lowering functions from a single WASM module.
+#[allow(clippy::struct_excessive_bools)]
 pub struct LoweringContext {
     pub wasm_memory_base: i32,
     pub num_globals: usize,
@@ -56,7 +57,12 @@ pub struct LoweringContext {
     /// so the 256-byte overflow region is reserved. Emit sites that read or
     /// write overflow slots guard on this flag.
     pub param_overflow_reserved: bool,
+    /// Number of local registers (r9..r9+N-1) used for parameters, callee
+    /// saves and register allocation. At most `MAX_LOCAL_REGS`.
+    pub local_regs: usize,
     pub function_signatures: Vec<(usize, bool)>,
+    /// Name-section names by global function index, for diagnostics.
+    pub function_names: Vec<Option<String>>,
     pub type_signatures: Vec<(usize, usize)>,
     pub function_table: Vec<u32>,
     pub num_imported_funcs: usize,
@@ -75,6 +81,17 @@ pub struct LoweringContext {
     pub wasm_import_map: Option<BTreeMap<String, crate::translate::ImportAction>>,
     /// Optimization flags controlling which compiler passes are enabled.
     pub optimizations: OptimizationFlags,
+    /// Tie-break seed for scheduling and block layout (`CompileOptions::seed`).
+    pub seed: u64,
+    /// Form absolute data addresses relative to `PIC_BASE_REG` (`CompileOptions::pic`).
+    pub pic: bool,
+    /// Panic on a failed invariant check (`CompileOptions::panic_on_internal_error`).
+    pub panic_on_internal_error: bool,
+    /// Route direct calls through the callee's jump-table entry, which sits
+    /// at local index `i` (`CompileOptions::function_sections`).
+    pub function_sections: bool,
+    /// Registers codegen must leave untouched (`CompileOptions::reserved_registers`).
+    pub reserved_registers: Vec<u8>,
 }
 
 /// Result of lowering one LLVM function to PVM instructions.
@@ -144,6 +161,10 @@ pub struct EmitterConfig {
     /// and no emit site should touch `param_overflow_base`.
     pub param_overflow_reserved: bool,
 
+    /// Number of local registers in use (see `LoweringContext::local_regs`).
+    /// Registers beyond this are never written, so they need no saving.
+    pub local_regs: usize,
+
     /// Whether the register cache (store-load forwarding) is enabled.
     pub register_cache_enabled: bool,
 
@@ -169,6 +190,12 @@ pub struct EmitterConfig {
 
     /// Whether lazy spill is enabled (skip stack stores for register-allocated values).
     pub lazy_spill_enabled: bool,
+
+    /// Whether absolute data addresses are rebased on `PIC_BASE_REG`.
+    pub pic_enabled: bool,
+
+    /// Panic instead of returning `Error::Internal` on a failed invariant check.
+    pub panic_on_internal_error: bool,
 }
 
 /// PVM code emitter for a single function.
@@ -207,7 +234,9 @@ pub struct PvmEmitter<'ctx> {
 
     /// Maps stack slot offset → register that currently holds this slot's value.
     slot_cache: BTreeMap<i32, u8>,
-    /// Reverse: register → slot offset it holds (for fast invalidation).
+    /// Reverse: register → slot offset it holds (for fast invalidation). After a
+    /// `MoveReg` several registers may hold the same slot; `slot_cache` names
+    /// one of them and falls back to another when that one is overwritten.
     reg_to_slot: [Option<i32>; 13],
 
     /// Pending fused `ICmp`: when an `ICmp` has a single use (by a branch), we defer
@@ -217,7 +246,8 @@ pub struct PvmEmitter<'ctx> {
 
     /// Maps register → known constant value currently held (for constant propagation).
     /// When a `LoadImm`/`LoadImm64` is about to be emitted, we check if the target
-    /// register already holds the same constant and skip the load if so.
+    /// register already holds the same constant and skip the load if so; a
+    /// `LoadImm64` of a constant held elsewhere becomes a `MoveReg`.
     reg_to_const: [Option<u64>; 13],
 
     /// Next jump table index for call return addresses.
@@ -294,11 +324,8 @@ impl CacheSnapshot {
     /// Invalidate a register's cache entries in this snapshot.
     /// Used to remove entries for registers that a terminator may clobber.
     pub fn invalidate_reg(&mut self, reg: u8) {
-        let idx = reg as usize;
-        if let Some(slot) = self.reg_to_slot[idx].take() {
-            self.slot_cache.remove(&slot);
-        }
-        self.reg_to_const[idx] = None;
+        forget_cached_reg(&mut self.slot_cache, &mut self.reg_to_slot, reg);
+        self.reg_to_const[reg as usize] = None;
         self.invalidate_alloc_reg(reg);
     }
 
@@ -310,6 +337,36 @@ impl CacheSnapshot {
     }
 }
 
+/// Drop `reg`'s register-cache entry. If `slot_cache` named `reg` for its
+/// slot, hand the slot to another register holding a copy, if any.
+fn forget_cached_reg(
+    slot_cache: &mut BTreeMap<i32, u8>,
+    reg_to_slot: &mut [Option<i32>; 13],
+    reg: u8,
+) {
+    let Some(slot) = reg_to_slot[reg as usize].take() else {
+        return;
+    };
+    if slot_cache.get(&slot) == Some(&reg) {
+        match reg_to_slot.iter().position(|&s| s == Some(slot)) {
+            Some(copy) => slot_cache.insert(slot, copy as u8),
+            None => slot_cache.remove(&slot),
+        };
+    }
+}
+
+/// Drop every register-cache entry for `slot` (its value changed).
+fn forget_cached_slot(
+    slot_cache: &mut BTreeMap<i32, u8>,
+    reg_to_slot: &mut [Option<i32>; 13],
+    slot: i32,
+) {
+    slot_cache.remove(&slot);
+    for held in reg_to_slot.iter_mut().filter(|held| **held == Some(slot)) {
+        *held = None;
+    }
+}
+
 /// Deferred `ICmp` info for branch fusion.
 pub struct FusedIcmp<'ctx> {
     pub predicate: IntPredicate,
@@ -409,6 +466,18 @@ pub fn bb_key(cache: &mut BbKeyCache, bb: BasicBlock<'_>) -> BbKey {
 
 impl<'ctx> PvmEmitter<'ctx> {
     pub fn new(config: EmitterConfig, call_return_base: usize) -> Self {
+        // Default frame: ra at 0, then every in-use local register.
+        let mut used_callee_regs = [false; 4];
+        let mut callee_save_offsets = [None; 4];
+        for (i, (used, offset)) in used_callee_regs
+            .iter_mut()
+            .zip(callee_save_offsets.iter_mut())
+            .take(config.local_regs)
+            .enumerate()
+        {
+            *used = true;
+            *offset = Some(8 * (i as i32 + 1));
+        }
         Self {
             config,
             instructions: Vec::new(),
@@ -430,8 +499,8 @@ impl<'ctx> PvmEmitter<'ctx> {
             block_single_pred: BTreeMap::new(),
             next_call_return_idx: call_return_base,
             call_return_base_idx: call_return_base,
-            used_callee_regs: [true; 4],
-            callee_save_offsets: [Some(8), Some(16), Some(24), Some(32)],
+            used_callee_regs,
+            callee_save_offsets,
             has_calls: true, // conservative default
             regalloc: RegAllocResult::default(),
             alloc_reg_slot: [None; 13],
@@ -514,7 +583,7 @@ impl<'ctx> PvmEmitter<'ctx> {
         self.byte_offset
     }
 
-    pub fn emit(&mut self, instr: Instruction) {
+    pub fn emit(&mut self, mut instr: Instruction) {
         // Constant propagation: skip LoadImm/LoadImm64 if register already holds the value.
         if self.config.constant_propagation_enabled {
             match &instr {
@@ -532,11 +601,49 @@ impl<'ctx> PvmEmitter<'ctx> {
                 }
                 _ => {}
             }
+            // A 64-bit constant already materialized in another register is
+            // copied (2-byte `MoveReg`) instead of re-encoding all 8 bytes.
+            if let Instruction::LoadImm64 { reg, value } = instr
+                && let Some(src) = self.reg_holding_const(value)
+            {
+                instr = Instruction::MoveReg { dst: reg, src };
+            }
         }
 
+        // A register copy carries the source's known constant over to `dst`.
+        let copied_const = match &instr {
+            Instruction::MoveReg { src, .. } if self.config.constant_propagation_enabled => {
+                self.reg_to_const[*src as usize]
+            }
+            _ => None,
+        };
+        // ...and the slot it caches, so the value stays cached in `dst` after
+        // `src` is overwritten.
+        let copied_slot = match &instr {
+            Instruction::MoveReg { dst, src }
+                if dst != src && self.config.register_cache_enabled =>
+            {
+                self.reg_to_slot[*src as usize]
+            }
+            _ => None,
+        };
+
         if let Some(reg) = instr.dest_reg() {
             self.invalidate_reg(reg);
         }
+        // Calls and host calls clobber registers behind our back; no constant
+        // survives them, whether or not the caller clears the cache afterwards.
+        if matches!(
+            instr,
+            Instruction::LoadImmJump { .. }
+                | Instruction::LoadImmJumpInd { .. }
+                | Instruction::Ecalli { .. }
+        ) {
+            self.reg_to_const = [None; 13];
+        }
+        if let (Instruction::MoveReg { dst, .. }, Some(slot)) = (&instr, copied_slot) {
+            self.reg_to_slot[*dst as usize] = Some(slot);
+        }
 
         // Track constants after emit.
         if self.config.constant_propagation_enabled {
@@ -547,14 +654,52 @@ impl<'ctx> PvmEmitter<'ctx> {
                 Instruction::LoadImm64 { reg, value } => {
                     self.reg_to_const[*reg as usize] = Some(*value);
                 }
+                Instruction::MoveReg { dst, .. } => {
+                    self.reg_to_const[*dst as usize] = copied_const;
+                }
                 _ => {}
             }
         }
 
-        self.byte_offset += instr.encode().len();
+        self.byte_offset += instr.encoded_len();
         self.instructions.push(instr);
     }
 
+    /// Lowest-numbered register known to hold `value`, if any.
+    fn reg_holding_const(&self, value: u64) -> Option<u8> {
+        self.reg_to_const
+            .iter()
+            .position(|&c| c == Some(value))
+            .map(|r| r as u8)
+    }
+
+    /// Relocate the absolute data address in `reg` by adding `PIC_BASE_REG`.
+    /// No-op unless `pic` is enabled.
+    pub fn emit_pic_rebase(&mut self, reg: u8) {
+        if self.config.pic_enabled {
+            self.emit(Instruction::Add64 {
+                dst: reg,
+                src1: reg,
+                src2: crate::abi::PIC_BASE_REG,
+            });
+        }
+    }
+
+    /// Base register for a linear-memory access at `addr_reg + wasm_memory_base`.
+    /// Under `pic` that is `addr_reg + PIC_BASE_REG`, computed into `scratch`
+    /// so `addr_reg` (possibly register-allocated) is left intact.
+    pub fn pic_access_base(&mut self, addr_reg: u8, scratch: u8) -> u8 {
+        if !self.config.pic_enabled {
+            return addr_reg;
+        }
+        self.emit(Instruction::Add64 {
+            dst: scratch,
+            src1: addr_reg,
+            src2: crate::abi::PIC_BASE_REG,
+        });
+        scratch
+    }
+
     pub fn emit_jump_to_label(&mut self, label: usize) {
         // If jumping to the next block in layout order, skip the Jump — execution
         // will fall through naturally. define_label() emits a Fallthrough if needed.
@@ -903,7 +1048,7 @@ impl<'ctx> PvmEmitter<'ctx> {
                 src: reg,
                 offset: slot,
             };
-            self.byte_offset += instr.encode().len();
+            self.byte_offset += instr.encoded_len();
             self.instructions.push(instr);
         }
         self.alloc_dirty[reg_idx] = false;
@@ -938,10 +1083,11 @@ impl<'ctx> PvmEmitter<'ctx> {
     /// Used for register-aware phi copies where we manage alloc state manually.
     pub fn emit_raw_move(&mut self, dst: u8, src: u8) {
         let instr = Instruction::MoveReg { dst, src };
-        self.byte_offset += instr.encode().len();
+        self.byte_offset += instr.encoded_len();
         self.instructions.push(instr);
-        // Clear stale constant cache for dst — the register no longer holds
-        // a known constant after the move.
+        // Clear stale cache entries for dst — the register no longer holds
+        // the slot or constant it held before the move.
+        forget_cached_reg(&mut self.slot_cache, &mut self.reg_to_slot, dst);
         self.reg_to_const[dst as usize] = None;
     }
 
@@ -989,17 +1135,17 @@ impl<'ctx> PvmEmitter<'ctx> {
     /// `reload_allocated_regs_after_call_with_arity` when the call's argument
     /// count is known.
     pub fn reload_allocated_regs_after_call(&mut self) {
-        self.reload_allocated_regs_after_call_with_arity(crate::abi::MAX_LOCAL_REGS);
+        self.reload_allocated_regs_after_call_with_arity(self.config.local_regs);
     }
 
     /// Invalidate only the allocated registers actually clobbered by a call
-    /// with `num_args` arguments. Registers r9..r9+min(num_args,4)-1 are
+    /// with `num_args` arguments. Registers r9..r9+min(num_args,local_regs)-1 are
     /// clobbered by argument setup; higher registers remain valid.
     /// r5/r6 (scratch) and r7/r8 (caller-saved) are always clobbered by calls.
     /// Note: `clear_reg_cache()` already clears all alloc state before this is
     /// called, so this is largely a documentation/safety net.
     pub fn reload_allocated_regs_after_call_with_arity(&mut self, num_args: usize) {
-        let clobbered_locals = num_args.min(crate::abi::MAX_LOCAL_REGS);
+        let clobbered_locals = num_args.min(self.config.local_regs);
         self.invalidate_allocated_regs_where(|r| {
             r == crate::abi::SCRATCH1
                 || r == crate::abi::SCRATCH2
@@ -1018,13 +1164,10 @@ impl<'ctx> PvmEmitter<'ctx> {
             return;
         }
         // Remove any previous slot cached in this register.
-        if let Some(old_slot) = self.reg_to_slot[reg as usize].take() {
-            self.slot_cache.remove(&old_slot);
-        }
-        // Remove any previous register caching this slot.
-        if let Some(old_reg) = self.slot_cache.insert(slot, reg) {
-            self.reg_to_slot[old_reg as usize] = None;
-        }
+        forget_cached_reg(&mut self.slot_cache, &mut self.reg_to_slot, reg);
+        // Remove any previous registers (and copies) caching this slot.
+        forget_cached_slot(&mut self.slot_cache, &mut self.reg_to_slot, slot);
+        self.slot_cache.insert(slot, reg);
         self.reg_to_slot[reg as usize] = Some(slot);
     }
 
@@ -1033,9 +1176,7 @@ impl<'ctx> PvmEmitter<'ctx> {
     /// stack before losing it.
     fn invalidate_reg(&mut self, reg: u8) {
         let idx = reg as usize;
-        if let Some(slot) = self.reg_to_slot[idx].take() {
-            self.slot_cache.remove(&slot);
-        }
+        forget_cached_reg(&mut self.slot_cache, &mut self.reg_to_slot, reg);
         if self.regalloc.reg_to_slot.contains_key(&reg) {
             // Lazy spill: if the register is dirty, spill to stack before clearing.
             if self.config.lazy_spill_enabled
@@ -1047,7 +1188,7 @@ impl<'ctx> PvmEmitter<'ctx> {
                     src: reg,
                     offset: slot,
                 };
-                self.byte_offset += instr.encode().len();
+                self.byte_offset += instr.encoded_len();
                 self.instructions.push(instr);
             }
             self.alloc_reg_slot[idx] = None;
@@ -1064,9 +1205,7 @@ impl<'ctx> PvmEmitter<'ctx> {
     /// register-allocation mapping pointing to `slot` are cleared.
     /// Caller is responsible for spilling dirty registers beforehand.
     pub fn invalidate_cache_for_slot(&mut self, slot: i32) {
-        if let Some(reg) = self.slot_cache.remove(&slot) {
-            self.reg_to_slot[reg as usize] = None;
-        }
+        forget_cached_slot(&mut self.slot_cache, &mut self.reg_to_slot, slot);
         for r_idx in 0..self.alloc_reg_slot.len() {
             if self.alloc_reg_slot[r_idx] == Some(slot) {
                 self.alloc_reg_slot[r_idx] = None;
@@ -1176,120 +1315,133 @@ impl<'ctx> PvmEmitter<'ctx> {
     // ── Fixup resolution ──
 
     pub fn resolve_fixups(&mut self) -> Result<()> {
-        // Convergence bound derived from the actual problem size, not a magic
-        // constant. Each relaxable offset field has at most 5 encoded widths
-        // (0/1/2/3/4 bytes) and its width is monotonically non-decreasing as
-        // the layout expands, so a fixup can grow at most 4 times. A pass that
-        // makes progress grows at least one fixup, hence the loop converges in
-        // ≤ 4·(#fixups) growth passes; `+2` covers the final no-change pass and
-        // an empty-fixup function. This can never falsely fail on a layout that
-        // is still legitimately progressing.
-        let max_relaxation_passes = self.fixups.len().saturating_mul(4) + 2;
-        // Branch/jump offsets are encoded with minimal length, so patching an
-        // offset can change the instruction's encoded size, which shifts every
-        // later instruction and changes other offsets. Resolve by iterating to
-        // a fixpoint: placeholder offsets are 0 (the smallest encoding) and
-        // distances only grow as instructions grow, so sizes are monotonically
-        // non-decreasing and the loop terminates within a few passes.
-        //
-        // `LoadImmJump` (direct calls) keeps a fixed 4-byte offset
-        // (`encode_one_reg_one_imm_one_off_fixed`): its offset is patched at
-        // link time after function layout is final, so its size never moves.
-
-        // Current byte offset of each instruction (consistent with
-        // `self.labels`, which were recorded against the same encodings).
-        let n = self.instructions.len();
-        let mut offsets = Vec::with_capacity(n + 1);
-        let mut running = 0usize;
-        for instr in &self.instructions {
-            offsets.push(running);
-            running += instr.encode().len();
-        }
-        offsets.push(running);
-
-        // Labels hold byte offsets; convert to instruction indices so they
-        // survive size changes during relaxation.
-        let mut offset_to_idx: BTreeMap<usize, usize> = BTreeMap::new();
-        for (idx, &off) in offsets.iter().enumerate() {
-            offset_to_idx.entry(off).or_insert(idx);
-        }
-        let label_indices: Vec<Option<usize>> = self
-            .labels
-            .iter()
-            .map(|l| l.and_then(|off| offset_to_idx.get(&off).copied()))
-            .collect();
+        self.byte_offset =
+            resolve_label_fixups(&mut self.instructions, &self.fixups, &mut self.labels)?;
+        Ok(())
+    }
+}
 
-        for _pass in 0..max_relaxation_passes {
-            let mut changed = false;
-            for &(instr_idx, label_id) in &self.fixups {
-                let Some(target_idx) = label_indices.get(label_id).copied().flatten() else {
-                    return Err(Error::Unsupported("unresolved label".to_string()));
-                };
+/// Patch every `(instruction index, label)` fixup with the label's relative
+/// offset, relaxing variable-width offset encodings to a fixpoint.
+///
+/// `labels` hold byte offsets into `instructions` on entry and are rewritten to
+/// the final layout's offsets. Returns the final code size in bytes.
+pub(crate) fn resolve_label_fixups(
+    instructions: &mut [Instruction],
+    fixups: &[(usize, usize)],
+    labels: &mut [Option<usize>],
+) -> Result<usize> {
+    // Convergence bound derived from the actual problem size, not a magic
+    // constant. Each relaxable offset field has at most 5 encoded widths
+    // (0/1/2/3/4 bytes) and its width is monotonically non-decreasing as
+    // the layout expands, so a fixup can grow at most 4 times. A pass that
+    // makes progress grows at least one fixup, hence the loop converges in
+    // ≤ 4·(#fixups) growth passes; `+2` covers the final no-change pass and
+    // an empty-fixup function. This can never falsely fail on a layout that
+    // is still legitimately progressing.
+    let max_relaxation_passes = fixups.len().saturating_mul(4) + 2;
+    // Branch/jump offsets are encoded with minimal length, so patching an
+    // offset can change the instruction's encoded size, which shifts every
+    // later instruction and changes other offsets. Resolve by iterating to
+    // a fixpoint: placeholder offsets are 0 (the smallest encoding) and
+    // distances only grow as instructions grow, so sizes are monotonically
+    // non-decreasing and the loop terminates within a few passes.
+    //
+    // `LoadImmJump` (direct calls) keeps a fixed 4-byte offset
+    // (`encode_one_reg_one_imm_one_off_fixed`): its offset is patched at
+    // link time after function layout is final, so its size never moves.
+
+    // Current byte offset of each instruction (consistent with
+    // `labels`, which were recorded against the same encodings).
+    let n = instructions.len();
+    let mut offsets = Vec::with_capacity(n + 1);
+    let mut running = 0usize;
+    for instr in &*instructions {
+        offsets.push(running);
+        running += instr.encoded_len();
+    }
+    offsets.push(running);
+
+    // Labels hold byte offsets; convert to instruction indices so they
+    // survive size changes during relaxation.
+    let mut offset_to_idx: BTreeMap<usize, usize> = BTreeMap::new();
+    for (idx, &off) in offsets.iter().enumerate() {
+        offset_to_idx.entry(off).or_insert(idx);
+    }
+    let label_indices: Vec<Option<usize>> = labels
+        .iter()
+        .map(|l| l.and_then(|off| offset_to_idx.get(&off).copied()))
+        .collect();
+
+    for _pass in 0..max_relaxation_passes {
+        let mut changed = false;
+        for &(instr_idx, label_id) in fixups {
+            let Some(target_idx) = label_indices.get(label_id).copied().flatten() else {
+                return Err(Error::Unsupported("unresolved label".to_string()));
+            };
 
-                // PVM jump offsets are relative to the instruction start.
-                let relative_offset = i32::try_from(
-                    offsets[target_idx] as i64 - offsets[instr_idx] as i64,
-                )
-                .map_err(|_| Error::Internal("branch offset exceeds i32 range".to_string()))?;
-
-                let old_len = self.instructions[instr_idx].encode().len();
-                match &mut self.instructions[instr_idx] {
-                    Instruction::Jump { offset }
-                    | Instruction::LoadImmJump { offset, .. }
-                    | Instruction::BranchNeImm { offset, .. }
-                    | Instruction::BranchEqImm { offset, .. }
-                    | Instruction::BranchGeSImm { offset, .. }
-                    | Instruction::BranchLtUImm { offset, .. }
-                    | Instruction::BranchLeUImm { offset, .. }
-                    | Instruction::BranchGeUImm { offset, .. }
-                    | Instruction::BranchGtUImm { offset, .. }
-                    | Instruction::BranchLtSImm { offset, .. }
-                    | Instruction::BranchLeSImm { offset, .. }
-                    | Instruction::BranchGtSImm { offset, .. }
-                    | Instruction::BranchEq { offset, .. }
-                    | Instruction::BranchNe { offset, .. }
-                    | Instruction::BranchGeU { offset, .. }
-                    | Instruction::BranchLtU { offset, .. }
-                    | Instruction::BranchLtS { offset, .. }
-                    | Instruction::BranchGeS { offset, .. } => {
-                        *offset = relative_offset;
-                    }
-                    _ => {
-                        return Err(Error::Unsupported(
-                            "cannot fixup non-jump instruction".to_string(),
-                        ));
-                    }
+            // PVM jump offsets are relative to the instruction start.
+            let relative_offset =
+                i32::try_from(offsets[target_idx] as i64 - offsets[instr_idx] as i64)
+                    .map_err(|_| Error::Internal("branch offset exceeds i32 range".to_string()))?;
+
+            let old_len = instructions[instr_idx].encoded_len();
+            match &mut instructions[instr_idx] {
+                Instruction::Jump { offset }
+                | Instruction::JumpFixed { offset }
+                | Instruction::LoadImmJump { offset, .. }
+                | Instruction::BranchNeImm { offset, .. }
+                | Instruction::BranchEqImm { offset, .. }
+                | Instruction::BranchGeSImm { offset, .. }
+                | Instruction::BranchLtUImm { offset, .. }
+                | Instruction::BranchLeUImm { offset, .. }
+                | Instruction::BranchGeUImm { offset, .. }
+                | Instruction::BranchGtUImm { offset, .. }
+                | Instruction::BranchLtSImm { offset, .. }
+                | Instruction::BranchLeSImm { offset, .. }
+                | Instruction::BranchGtSImm { offset, .. }
+                | Instruction::BranchEq { offset, .. }
+                | Instruction::BranchNe { offset, .. }
+                | Instruction::BranchGeU { offset, .. }
+                | Instruction::BranchLtU { offset, .. }
+                | Instruction::BranchLtS { offset, .. }
+                | Instruction::BranchGeS { offset, .. } => {
+                    *offset = relative_offset;
                 }
-                if self.instructions[instr_idx].encode().len() != old_len {
-                    changed = true;
+                _ => {
+                    return Err(Error::Unsupported(
+                        "cannot fixup non-jump instruction".to_string(),
+                    ));
                 }
             }
-
-            if !changed {
-                // Layout is final; refresh label byte offsets for downstream
-                // consumers and the emitter's own byte counter.
-                for (label_id, idx) in label_indices.iter().enumerate() {
-                    if let Some(idx) = idx {
-                        self.labels[label_id] = Some(offsets[*idx]);
-                    }
-                }
-                self.byte_offset = offsets[n];
-                return Ok(());
+            if instructions[instr_idx].encoded_len() != old_len {
+                changed = true;
             }
+        }
 
-            // Recompute offsets after the size changes and go again.
-            running = 0;
-            for (idx, instr) in self.instructions.iter().enumerate() {
-                offsets[idx] = running;
-                running += instr.encode().len();
+        if !changed {
+            // Layout is final; refresh label byte offsets for downstream
+            // consumers and report the final code size.
+            for (label_id, idx) in label_indices.iter().enumerate() {
+                if let Some(idx) = idx {
+                    labels[label_id] = Some(offsets[*idx]);
+                }
             }
-            offsets[n] = running;
+            return Ok(offsets[n]);
         }
 
-        Err(Error::Internal(
-            "branch offset relaxation did not converge".to_string(),
-        ))
+        // Recompute offsets after the size changes and go again.
+        running = 0;
+        for (idx, instr) in instructions.iter().enumerate() {
+            offsets[idx] = running;
+            running += instr.encoded_len();
+        }
+        offsets[n] = running;
     }
+
+    Err(Error::Internal(
+        "branch offset relaxation did not converge".to_string(),
+    ))
 }
 
 /// Try to extract a constant integer value from a `BasicValueEnum` without emitting instructions.
@@ -1579,7 +1731,7 @@ pub fn pre_scan_function<'ctx>(
     // PVM intrinsics (__pvm_load_*, __pvm_store_*, etc.) and LLVM intrinsics
     // (llvm.*) are NOT real function calls — they don't use the calling
     // convention and don't clobber callee-saved registers (r9-r12).
-    // Only wasm_func_* and __pvm_call_indirect are real calls.
+    // Only wasm_func_*, __pvm_call_indirect and __pvm_call_ref are real calls.
     let mut has_calls = false;
     for bb in function.get_basic_blocks() {
         for instr in bb.get_instructions() {
@@ -1598,20 +1750,20 @@ pub fn pre_scan_function<'ctx>(
     // Determine which callee-saved registers are used (shrink wrapping).
     if !is_main && emitter.config.shrink_wrap_enabled {
         let num_params = function.count_params() as usize;
+        let local_regs = emitter.config.local_regs;
         let mut used = [false; 4];
 
         // Parameters mapped to r9-r12 count as used.
-        for u in used
-            .iter_mut()
-            .take(crate::abi::MAX_LOCAL_REGS.min(num_params))
-        {
+        for u in used.iter_mut().take(local_regs.min(num_params)) {
             *u = true;
         }
 
         // If the function contains any call instruction, all callee-saved regs are used
         // (because the callee may clobber them and expects us to preserve them).
         if has_calls {
-            used = [true; 4];
+            for u in used.iter_mut().take(local_regs) {
+                *u = true;
+            }
         } else {
             // Check usage for non-call instructions if we assume registers might be used.
             // But since we don't allocate r9-r12 as temps, they are only used for params.
@@ -1730,7 +1882,8 @@ fn instruction_produces_value(instr: InstructionValue<'_>) -> bool {
 /// LLVM intrinsics (`llvm.*`) are lowered inline — they only use temp/scratch
 /// registers and never clobber callee-saved registers.
 ///
-/// Real calls: `wasm_func_*` (direct) and `__pvm_call_indirect` (indirect).
+/// Real calls: `wasm_func_*` (direct) and `__pvm_call_indirect` /
+/// `__pvm_call_ref` (indirect).
 pub(super) fn is_real_call(instr: InstructionValue<'_>) -> bool {
     let call_site: std::result::Result<inkwell::values::CallSiteValue, _> = instr.try_into();
     let Ok(call_site) = call_site else {
@@ -1741,7 +1894,7 @@ pub(super) fn is_real_call(instr: InstructionValue<'_>) -> bool {
     };
     let name = fn_val.get_name().to_string_lossy();
     // PVM intrinsics and LLVM intrinsics are NOT real calls.
-    if name.starts_with("__pvm_") && name != "__pvm_call_indirect" {
+    if name.starts_with("__pvm_") && !is_dynamic_call_intrinsic(&name) {
         return false;
     }
     if name.starts_with("llvm.") {
@@ -1750,6 +1903,12 @@ pub(super) fn is_real_call(instr: InstructionValue<'_>) -> bool {
     true
 }
 
+/// The `__pvm_*` intrinsics that lower to a real (indirect) call using the
+/// standard calling convention.
+pub(super) fn is_dynamic_call_intrinsic(name: &str) -> bool {
+    matches!(name, "__pvm_call_indirect" | "__pvm_call_ref")
+}
+
 /// Check whether any instruction in the function will lower to a path that
 /// clobbers r5/r6 (`abi::SCRATCH1`/`abi::SCRATCH2`).
 ///
@@ -1811,8 +1970,9 @@ pub use crate::abi::{ARGS_LEN_REG as SCRATCH1, ARGS_PTR_REG as SCRATCH2};
 
 #[cfg(test)]
 mod tests {
-    use super::apply_dst_conflict_fallback;
-    use crate::abi::{FIRST_LOCAL_REG, TEMP_RESULT, TEMP1, TEMP2};
+    use super::{EmitterConfig, PvmEmitter, apply_dst_conflict_fallback};
+    use crate::abi::{FIRST_LOCAL_REG, STACK_PTR_REG, TEMP_RESULT, TEMP1, TEMP2};
+    use crate::pvm::Instruction;
 
     #[test]
     fn fallback_returns_op_reg_when_already_fallback() {
@@ -1859,4 +2019,152 @@ mod tests {
             TEMP_RESULT
         );
     }
+
+    fn emitter(register_cache_enabled: bool) -> PvmEmitter<'static> {
+        PvmEmitter::new(
+            EmitterConfig {
+                wasm_memory_base: 0,
+                param_overflow_base: 0,
+                param_overflow_reserved: false,
+                local_regs: 4,
+                register_cache_enabled,
+                constant_propagation_enabled: true,
+                icmp_fusion_enabled: false,
+                address_mask_elision_enabled: false,
+                shrink_wrap_enabled: false,
+                cross_block_cache_enabled: false,
+                register_allocation_enabled: false,
+                fallthrough_jumps_enabled: false,
+                lazy_spill_enabled: false,
+                pic_enabled: false,
+                panic_on_internal_error: true,
+            },
+            0,
+        )
+    }
+
+    /// `TEMP_RESULT` holds slot 16 (as after a load), then is copied to TEMP1.
+    fn copy_of_slot_16(e: &mut PvmEmitter<'_>) {
+        e.emit(Instruction::LoadIndU64 {
+            dst: TEMP_RESULT,
+            base: STACK_PTR_REG,
+            offset: 16,
+        });
+        e.cache_slot(16, TEMP_RESULT);
+        e.emit(Instruction::MoveReg {
+            dst: TEMP1,
+            src: TEMP_RESULT,
+        });
+    }
+
+    #[test]
+    fn move_reg_copy_keeps_the_slot_cached_after_its_source_is_overwritten() {
+        let mut e = emitter(true);
+        copy_of_slot_16(&mut e);
+        assert_eq!(e.slot_cache.get(&16), Some(&TEMP_RESULT));
+
+        e.emit(Instruction::LoadImm {
+            reg: TEMP_RESULT,
+            value: 7,
+        });
+        assert_eq!(e.slot_cache.get(&16), Some(&TEMP1));
+        assert_eq!(e.reg_to_slot[TEMP_RESULT as usize], None);
+
+        e.emit(Instruction::LoadImm {
+            reg: TEMP1,
+            value: 8,
+        });
+        assert_eq!(e.slot_cache.get(&16), None);
+    }
+
+    #[test]
+    fn writing_the_slot_drops_every_copy() {
+        let mut e = emitter(true);
+        copy_of_slot_16(&mut e);
+
+        // A new value for slot 16 arrives in TEMP2.
+        e.cache_slot(16, TEMP2);
+        assert_eq!(e.reg_to_slot[TEMP_RESULT as usize], None);
+        assert_eq!(e.reg_to_slot[TEMP1 as usize], None);
+
+        e.emit(Instruction::LoadImm {
+            reg: TEMP2,
+            value: 0,
+        });
+        assert_eq!(e.slot_cache.get(&16), None);
+    }
+
+    #[test]
+    fn constants_are_forgotten_across_calls_and_ecalli() {
+        const C: u64 = 0x1234_5678_9abc_def0;
+        let clobbers = [
+            Instruction::LoadImmJump {
+                reg: 0,
+                value: 2,
+                offset: 0,
+            },
+            Instruction::LoadImmJumpInd {
+                base: TEMP2,
+                dst: 0,
+                value: 2,
+                offset: 0,
+            },
+            Instruction::Ecalli { index: 1 },
+        ];
+        for clobber in clobbers {
+            let mut e = emitter(true);
+            e.emit(Instruction::LoadImm64 {
+                reg: TEMP1,
+                value: C,
+            });
+            e.emit(clobber.clone());
+            // TEMP1 may no longer hold C: reload it rather than copy it, and
+            // don't skip rewriting TEMP1 itself. Only the fresh load counts.
+            e.emit(Instruction::LoadImm64 {
+                reg: TEMP_RESULT,
+                value: C,
+            });
+            e.emit(Instruction::LoadImm64 {
+                reg: TEMP1,
+                value: C,
+            });
+            assert_eq!(
+                &e.instructions[2..],
+                [
+                    Instruction::LoadImm64 {
+                        reg: TEMP_RESULT,
+                        value: C,
+                    },
+                    Instruction::MoveReg {
+                        dst: TEMP1,
+                        src: TEMP_RESULT,
+                    },
+                ],
+                "after {clobber:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn raw_move_forgets_the_slot_its_destination_held() {
+        let mut e = emitter(true);
+        copy_of_slot_16(&mut e);
+
+        // TEMP1 is overwritten; slot 16 stays cached in TEMP_RESULT only.
+        e.emit_raw_move(TEMP1, TEMP2);
+        assert_eq!(e.reg_to_slot[TEMP1 as usize], None);
+        assert_eq!(e.slot_cache.get(&16), Some(&TEMP_RESULT));
+
+        e.emit_raw_move(TEMP_RESULT, TEMP2);
+        assert_eq!(e.reg_to_slot[TEMP_RESULT as usize], None);
+        assert_eq!(e.slot_cache.get(&16), None);
+    }
+
+    #[test]
+    fn copies_are_not_tracked_without_register_cache() {
+        let mut e = emitter(false);
+        copy_of_slot_16(&mut e);
+        assert!(e.slot_cache.is_empty());
+        assert_eq!(e.reg_to_slot, [None; 13]);
+    }
 }
@@ -90,8 +90,14 @@ pub fn lower_pvm_intrinsic<'ctx>(
         // `__multi3` body in `llvm_frontend::libcall_recognition`.
         "__pvm_mul_upper_uu" => emit_pvm_mul_upper_uu(e, instr),
 
+        // ── Multi-value results ──
+        // Results after the first travel through the param-overflow area.
+        "__pvm_result_get" => emit_pvm_result_get(e, instr),
+        "__pvm_result_set" => emit_pvm_result_set(e, instr),
+
         // ── Indirect calls ──
         "__pvm_call_indirect" => super::calls::lower_pvm_call_indirect(e, instr, ctx),
+        "__pvm_call_ref" => super::calls::lower_pvm_call_ref(e, instr, ctx),
 
         _ => Err(crate::Error::Unsupported(format!(
             "unknown PVM intrinsic: {name}"
@@ -132,6 +138,67 @@ fn emit_pvm_mul_upper_uu<'ctx>(
     Ok(())
 }
 
+/// Address of extra-result slot `index` (operand 0, a constant): the
+/// function's second result lives at `param_overflow_base`, the third 8 bytes
+/// after it, and so on.
+fn result_overflow_offset(e: &PvmEmitter<'_>, instr: InstructionValue<'_>) -> Result<i32> {
+    let index = try_get_constant(get_operand(instr, 0)?).ok_or_else(|| {
+        crate::Error::Internal("multi-value result index is not a constant".into())
+    })?;
+    crate::Error::check_invariant(
+        e.config.param_overflow_reserved,
+        e.config.panic_on_internal_error,
+        || format!("extra result {index} needs the param overflow area but it was not reserved"),
+    )?;
+    Ok(e.config.param_overflow_base + (index * 8) as i32)
+}
+
+/// `__pvm_result_get(index) -> i64`: read an extra result of the call that
+/// just returned. The frontend emits these immediately after the call, before
+/// anything else can reuse the area.
+fn emit_pvm_result_get<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    instr: InstructionValue<'ctx>,
+) -> Result<()> {
+    let offset = result_overflow_offset(e, instr)?;
+    let slot = result_slot(e, instr)?;
+    let dst = result_reg(e, instr);
+    e.emit(Instruction::LoadImm {
+        reg: TEMP1,
+        value: offset,
+    });
+    e.emit_pic_rebase(TEMP1);
+    e.emit(Instruction::LoadIndU64 {
+        dst,
+        base: TEMP1,
+        offset: 0,
+    });
+    e.store_to_slot(slot, dst);
+    Ok(())
+}
+
+/// `__pvm_result_set(index, value)`: write an extra result just before the
+/// function returns. The prologue has already copied any overflow params out
+/// of the area, so it is free to reuse.
+fn emit_pvm_result_set<'ctx>(
+    e: &mut PvmEmitter<'ctx>,
+    instr: InstructionValue<'ctx>,
+) -> Result<()> {
+    let offset = result_overflow_offset(e, instr)?;
+    e.load_operand(get_operand(instr, 1)?, TEMP2)?;
+    e.emit(Instruction::LoadImm {
+        reg: TEMP1,
+        value: offset,
+    });
+    e.emit_pic_rebase(TEMP1);
+    e.emit(Instruction::StoreIndU64 {
+        base: TEMP1,
+        src: TEMP2,
+        offset: 0,
+    });
+    Ok(())
+}
+
 /// Lower an LLVM intrinsic call (smax, smin, umax, umin, bitreverse, bswap, abs, ctlz, cttz, ctpop, fshl, fshr, assume).
 pub fn lower_llvm_intrinsic<'ctx>(
     e: &mut PvmEmitter<'ctx>,
@@ -459,6 +526,9 @@ pub fn lower_llvm_intrinsic<'ctx>(
         return Ok(());
     }
 
+    // `ctlz`/`cttz` carry an `is_zero_poison` flag. It needs no handling:
+    // the native ops return the operand width for zero, which is WASM's
+    // defined result, and any value is a valid refinement of poison.
     if name.contains("ctlz") {
         let val = get_operand(instr, 0)?;
         let dst = result_reg(e, instr);
@@ -44,6 +44,14 @@ fn resolve_global_slot(idx: u32, ctx: &LoweringContext) -> Result<(i32, u32)> {
                 ctx.global_widths.len()
             ))
         })?;
+    // The compiler-managed memory-size slot is never a user global; refuse
+    // to let `global.get`/`global.set` alias it.
+    if ctx.has_memory_size_global && crate::memory_layout::overlaps_memory_size_slot(address, width)
+    {
+        return Err(Error::Internal(format!(
+            "wasm_global_{idx} at 0x{address:X} overlaps the memory-size slot"
+        )));
+    }
     Ok((address, width))
 }
 
@@ -253,7 +261,10 @@ pub fn emit_pvm_load<'ctx>(
     let dst = result_reg(e, instr);
 
     // Load-side coalescing: use allocated register for the address operand.
+    // Under `pic` the rebased address goes into `dst`, which the load
+    // overwrites anyway.
     let addr_reg = prepare_operand(e, addr, TEMP1, dst)?;
+    let addr_reg = e.pic_access_base(addr_reg, dst);
 
     let offset = ctx.wasm_memory_base;
     match kind {
@@ -326,6 +337,7 @@ pub fn emit_pvm_store<'ctx>(
         if addr_reg == TEMP1 {
             e.load_operand(addr, TEMP1)?;
         }
+        let addr_reg = e.pic_access_base(addr_reg, TEMP1);
         match kind {
             PvmStoreKind::U8 => e.emit(Instruction::StoreImmIndU8 {
                 base: addr_reg,
@@ -360,6 +372,8 @@ pub fn emit_pvm_store<'ctx>(
     if val_reg == TEMP2 {
         e.load_operand(val, TEMP2)?;
     }
+    // TEMP1 is kept free of the value above, so it can take the rebased address.
+    let addr_reg = e.pic_access_base(addr_reg, TEMP1);
 
     match kind {
         PvmStoreKind::U8 => e.emit(Instruction::StoreIndU8 {
@@ -427,6 +441,11 @@ pub fn emit_pvm_memory_grow<'ctx>(
     });
 
     // new_size = current + delta
+    //
+    // The u32 delta can be anything up to 0xFFFFFFFF (`memory.grow(-1)`).
+    // A sum that wraps 32 bits comes out below `current`; a sum of 2^31 or
+    // more is sign-extended by `Add32` into a huge u64. The two checks below
+    // send both to the failure path.
     e.emit(Instruction::Add32 {
         dst: SCRATCH2,
         src1: TEMP_RESULT,
@@ -463,31 +482,30 @@ pub fn emit_pvm_memory_grow<'ctx>(
         offset: 0,
     });
 
-    // Success: store new_size.
-    e.emit(Instruction::StoreU32 {
-        src: SCRATCH2,
-        address: global_addr,
-    });
-
-    // SBRK: grow PVM memory by (delta * 65536) bytes.
+    // SBRK: grow PVM memory by (delta * 65536) bytes. The shift is 64-bit: a
+    // delta of 32768+ pages would wrap or sign-extend as a 32-bit shift.
     e.emit(Instruction::Sub32 {
         dst: SCRATCH1,
         src1: SCRATCH2,
         src2: TEMP_RESULT,
     });
-    e.emit(Instruction::LoadImm {
-        reg: SCRATCH2,
-        value: 16,
-    });
-    e.emit(Instruction::ShloL32 {
+    e.emit(Instruction::ShloLImm64 {
         dst: SCRATCH1,
-        src1: SCRATCH1,
-        src2: SCRATCH2,
+        src: SCRATCH1,
+        value: 16,
     });
     e.emit(Instruction::Sbrk {
         dst: SCRATCH1,
         src: SCRATCH1,
     });
+    // Sbrk returns 0 when the host refuses; the memory size stays unchanged.
+    e.emit_branch_eq_imm_to_label(SCRATCH1, 0, fail_label);
+
+    // Success: store new_size.
+    e.emit(Instruction::StoreU32 {
+        src: SCRATCH2,
+        address: global_addr,
+    });
 
     // TEMP_RESULT has old size — jump to end.
     e.emit_jump_to_label(end_label);
@@ -506,6 +524,8 @@ pub fn emit_pvm_memory_grow<'ctx>(
 
 /// Emit memory.fill operation using word-sized (64-bit) stores for the bulk,
 /// with a byte-by-byte tail for the remaining 0-7 bytes.
+///
+/// Traps before writing anything if `dst + len > memory_size_bytes`.
 pub fn emit_pvm_memory_fill<'ctx>(
     e: &mut PvmEmitter<'ctx>,
     instr: InstructionValue<'ctx>,
@@ -522,12 +542,15 @@ pub fn emit_pvm_memory_fill<'ctx>(
     e.load_operand(val, TEMP2)?; // value (single byte)
     e.load_operand(len, TEMP_RESULT)?; // size (counter)
 
+    emit_wasm_range_check(e, TEMP1, TEMP_RESULT);
+
     // Add wasm_memory_base to dest.
     e.emit(Instruction::AddImm32 {
         dst: TEMP1,
         src: TEMP1,
         value: ctx.wasm_memory_base,
     });
+    e.emit_pic_rebase(TEMP1);
 
     let word_loop = e.alloc_label();
     let byte_loop = e.alloc_label();
@@ -628,8 +651,73 @@ pub fn emit_pvm_memory_fill<'ctx>(
     Ok(())
 }
 
+/// Trap unless `[addr, addr + len)` lies within `[0, memory.size * 65536)`.
+///
+/// `addr_reg` and `len_reg` hold WASM-space (untranslated) i32 values and are
+/// left untouched. Both are zero-extended and summed in 64 bits so that an
+/// address near `u32::MAX` cannot wrap back into range. Clobbers SCRATCH1/2.
+fn emit_wasm_range_check(e: &mut PvmEmitter<'_>, addr_reg: u8, len_reg: u8) {
+    use crate::abi::{SCRATCH1, SCRATCH2};
+
+    // SCRATCH1 = zext(addr) + zext(len)
+    e.emit(Instruction::ShloLImm64 {
+        dst: SCRATCH1,
+        src: addr_reg,
+        value: 32,
+    });
+    e.emit(Instruction::ShloRImm64 {
+        dst: SCRATCH1,
+        src: SCRATCH1,
+        value: 32,
+    });
+    e.emit(Instruction::ShloLImm64 {
+        dst: SCRATCH2,
+        src: len_reg,
+        value: 32,
+    });
+    e.emit(Instruction::ShloRImm64 {
+        dst: SCRATCH2,
+        src: SCRATCH2,
+        value: 32,
+    });
+    e.emit(Instruction::Add64 {
+        dst: SCRATCH1,
+        src1: SCRATCH1,
+        src2: SCRATCH2,
+    });
+
+    // SCRATCH2 = memory_size * 65536 (64-bit shift: 65536 pages must not wrap).
+    e.emit(Instruction::LoadU32 {
+        dst: SCRATCH2,
+        address: abi::memory_size_global_offset(),
+    });
+    e.emit(Instruction::ShloLImm64 {
+        dst: SCRATCH2,
+        src: SCRATCH2,
+        value: 16,
+    });
+
+    // BranchGeU branches when reg2 >= reg1: skip the trap if size >= addr + len.
+    let ok_label = e.alloc_label();
+    let fixup_idx = e.instructions.len();
+    e.fixups.push((fixup_idx, ok_label));
+    e.emit(Instruction::BranchGeU {
+        reg1: SCRATCH1,
+        reg2: SCRATCH2,
+        offset: 0,
+    });
+    e.emit(Instruction::Trap);
+    e.define_label(ok_label);
+}
+
 /// Emit memory.copy with memmove semantics (handles overlapping regions).
 ///
+/// Both `[src, src + len)` and `[dst, dst + len)` are bounds-checked against
+/// the current memory size before translation to PVM addresses, so an
+/// out-of-range copy traps instead of reaching the globals/spill region that
+/// sits below `wasm_memory_base` (or running past the end of the heap). Per
+/// the spec the check also applies when `len == 0`.
+///
 /// When `dst > src`, a naive forward copy corrupts overlapping bytes before
 /// they are read. We detect this case and copy backward (from high to low
 /// addresses) so that source data is always read before being overwritten.
@@ -652,6 +740,10 @@ pub fn emit_pvm_memory_copy<'ctx>(
     e.load_operand(src_addr, TEMP2)?; // src
     e.load_operand(len, TEMP_RESULT)?; // size (counter)
 
+    // Bounds-check both ranges in WASM space.
+    emit_wasm_range_check(e, TEMP1, TEMP_RESULT);
+    emit_wasm_range_check(e, TEMP2, TEMP_RESULT);
+
     // Add wasm_memory_base to both addresses.
     e.emit(Instruction::AddImm32 {
         dst: TEMP1,
@@ -663,6 +755,8 @@ pub fn emit_pvm_memory_copy<'ctx>(
         src: TEMP2,
         value: ctx.wasm_memory_base,
     });
+    e.emit_pic_rebase(TEMP1);
+    e.emit_pic_rebase(TEMP2);
 
     // Check for overlap and direction: if dst > src, copy backwards.
     // PVM branch semantics: BranchLtU{reg1: a, reg2: b} branches if b < a.
@@ -842,10 +936,11 @@ pub fn emit_pvm_data_drop<'ctx>(
         }
     };
 
-    let length_addr = *ctx
-        .data_segment_length_addrs
-        .get(&seg_idx)
-        .ok_or_else(|| Error::Internal(format!("unknown passive data segment index {seg_idx}")))?;
+    // Active segments are dropped implicitly at instantiation and have no
+    // length slot, so dropping one again is a no-op.
+    let Some(&length_addr) = ctx.data_segment_length_addrs.get(&seg_idx) else {
+        return Ok(());
+    };
 
     // Store 0 to the segment's effective length address.
     e.emit(Instruction::StoreImmU32 {
@@ -921,6 +1016,7 @@ pub fn emit_pvm_memory_init<'ctx>(
         reg: SCRATCH2,
         value: length_addr,
     });
+    e.emit_pic_rebase(SCRATCH2);
     e.emit(Instruction::LoadIndU32 {
         dst: SCRATCH2,
         base: SCRATCH2,
@@ -991,6 +1087,8 @@ pub fn emit_pvm_memory_init<'ctx>(
         src: TEMP1,
         value: ctx.wasm_memory_base,
     });
+    e.emit_pic_rebase(TEMP1);
+    e.emit_pic_rebase(TEMP2);
 
     // Loop: while size > 0: dst++ = src++
     let loop_start = e.alloc_label();
@@ -7,6 +7,7 @@
 // - `control_flow`: Branches, phi nodes, switch, return
 // - `calls`: Direct calls, indirect calls, import stubs
 // - `intrinsics`: PVM and LLVM intrinsic lowering
+// - `scheduling`: Per-block instruction scheduling (runs on LLVM IR before lowering)
 
 // We use 'as' casts extensively for:
 // - PVM register indices (u8) from iterators
@@ -26,8 +27,11 @@ mod emitter;
 mod intrinsics;
 mod memory;
 pub(crate) mod regalloc;
+mod scheduling;
 mod successors;
+mod tie_break;
 
+pub(crate) use emitter::resolve_label_fixups;
 pub use emitter::{
     EmitterConfig, LlvmCallFixup, LlvmFunctionTranslation, LlvmIndirectCallFixup, LoweringContext,
 };
@@ -82,6 +86,7 @@ fn lower_function_inner(
         wasm_memory_base: ctx.wasm_memory_base,
         param_overflow_base: ctx.param_overflow_base,
         param_overflow_reserved: ctx.param_overflow_reserved,
+        local_regs: ctx.local_regs,
         register_cache_enabled: ctx.optimizations.register_cache,
         icmp_fusion_enabled: ctx.optimizations.icmp_branch_fusion,
         // Sign/zero extension only agree on valid addresses when the whole
@@ -94,8 +99,33 @@ fn lower_function_inner(
         register_allocation_enabled: ctx.optimizations.register_allocation,
         fallthrough_jumps_enabled: ctx.optimizations.fallthrough_jumps,
         lazy_spill_enabled: ctx.optimizations.lazy_spill,
+        pic_enabled: ctx.pic,
+        panic_on_internal_error: ctx.panic_on_internal_error,
     };
     let mut emitter = PvmEmitter::new(config, call_return_base);
+    let mut ties = tie_break::TieBreaker::new(ctx.seed, &function.get_name().to_string_lossy());
+
+    // Phase 0: Reorder each block's instructions to shorten live ranges. Runs
+    // on the IR in place so pre-scan, regalloc and lowering share one order.
+    if ctx.optimizations.scheduling {
+        let rescheduled = scheduling::schedule_function(function, &mut ties);
+        tracing::debug!(
+            target: "wasm_pvm::scheduling",
+            function = %function.get_name().to_string_lossy(),
+            rescheduled_blocks = rescheduled,
+            "instruction scheduling"
+        );
+    }
+
+    // The scratch-heavy lowerings hard-wire r5/r6, the only reservable registers.
+    if !ctx.reserved_registers.is_empty() && !emitter::scratch_regs_safe(function) {
+        return Err(Error::Unsupported(format!(
+            "needs r{}/r{} as scratch (bulk memory, funnel shift or signed i64 \
+             saturating arithmetic), but reserved_registers excludes them",
+            abi::SCRATCH1,
+            abi::SCRATCH2
+        )));
+    }
 
     // Phase 1: Pre-scan — allocate labels for blocks and slots for all SSA values.
     pre_scan_function(&mut emitter, function, is_main);
@@ -104,7 +134,7 @@ fn lower_function_inner(
     // Compute the block emission order once and share it with regalloc — see
     // `regalloc::run`'s `block_order` doc.
     let block_order: Vec<BasicBlock<'_>> = if ctx.optimizations.fallthrough_jumps {
-        compute_block_layout(function, &mut emitter.bb_key_cache)
+        compute_block_layout(function, &mut emitter.bb_key_cache, &mut ties)
     } else {
         function.get_basic_blocks()
     };
@@ -121,9 +151,12 @@ fn lower_function_inner(
             &emitter.value_slots,
             is_leaf,
             function.count_params() as usize,
+            emitter.config.local_regs,
             ctx.optimizations.aggressive_register_allocation,
             scratch_safe,
             ctx.optimizations.allocate_caller_saved_regs,
+            ctx.optimizations.prefer_caller_saved_regs,
+            &ctx.reserved_registers,
             &block_order,
         );
 
@@ -254,11 +287,11 @@ fn lower_function_inner(
                                     .regalloc
                                     .stats
                                     .max_call_args
-                                    .min(crate::abi::MAX_LOCAL_REGS);
+                                    .min(emitter.config.local_regs);
                                 let first_safe =
                                     crate::abi::FIRST_LOCAL_REG + clobbered_locals as u8;
                                 let last_safe =
-                                    crate::abi::FIRST_LOCAL_REG + crate::abi::MAX_LOCAL_REGS as u8;
+                                    crate::abi::FIRST_LOCAL_REG + emitter.config.local_regs as u8;
                                 emitter
                                     .set_alloc_reg_slot_filtered(&first_snap.alloc_reg_slot, |r| {
                                         r >= first_safe && r < last_safe
@@ -347,6 +380,20 @@ fn lower_function_inner(
     // Collect pre-DSE instruction count for stats.
     let pre_dse_instructions = emitter.instructions.len();
 
+    // Dead block elimination: LLVM can't tell that a call to a trapping
+    // import never returns, so the code after its `Trap` is still emitted.
+    // Running first also keeps the loads in that code from pinning stores
+    // for DSE.
+    if ctx.optimizations.dead_block_elimination {
+        crate::pvm::peephole::eliminate_dead_blocks(
+            &mut emitter.instructions,
+            &mut emitter.fixups,
+            &mut emitter.call_fixups,
+            &mut emitter.indirect_call_fixups,
+            &mut emitter.labels,
+        );
+    }
+
     // Dead store elimination: remove SP-relative stores that are never loaded from.
     // With register-aware phi resolution (Phase 5), phi destination values are
     // read from registers (via alloc_reg_slot), not from the stack. The spill
@@ -365,6 +412,16 @@ fn lower_function_inner(
         );
     }
 
+    // `define_label` separated every block with a `Fallthrough`; keep only the
+    // ones in front of labels a jump or branch actually targets.
+    crate::pvm::peephole::remove_untargeted_fallthroughs(
+        &mut emitter.instructions,
+        &mut emitter.fixups,
+        &mut emitter.call_fixups,
+        &mut emitter.indirect_call_fixups,
+        &mut emitter.labels,
+    );
+
     // Collect pre-peephole instruction count for stats.
     let pre_peephole_instructions = emitter.instructions.len();
 
@@ -379,6 +436,15 @@ fn lower_function_inner(
         );
     }
 
+    // Position-independent output: rebase absolute loads/stores last, after
+    // the peephole passes had their chance to fold addresses into them.
+    if emitter.config.pic_enabled {
+        crate::pvm::peephole::rebase_absolute_accesses(
+            &mut emitter.instructions,
+            crate::abi::PIC_BASE_REG,
+        );
+    }
+
     emitter.resolve_fixups()?;
 
     if emitter.config.register_allocation_enabled {
@@ -436,39 +502,62 @@ fn lower_function_inner(
 }
 
 /// Compute the basic-block emission order, biased toward letting trailing
-/// `Jump` instructions fall through to the next block.
+/// `Jump` instructions fall through to the next block and toward keeping hot
+/// paths contiguous.
 ///
 /// LLVM's IR block order isn't chosen with PVM fallthroughs in mind. By
 /// greedily placing each block's "preferred successor" immediately after it,
 /// we let `emit_jump_to_label` elide a 5-byte `Jump` in favor of an implicit
 /// fallthrough.
 ///
+/// A block is *cold* if it ends in `unreachable` (a trap, e.g. after
+/// `llvm.trap` or a failed soft-float range check) or if every path out of it
+/// leads to a cold block. Cold blocks are never preferred successors of hot
+/// ones and are emitted after all hot blocks, so error paths cost a taken
+/// branch and the likely path runs straight through.
+///
 /// Preferred successor per terminator (matching the *final* `Jump` emitted by
 /// `control_flow.rs`):
 /// - Unconditional `br dest` → `dest`.
 /// - Conditional `br cond, then, else` → `else` (the trailing
-///   `Jump else_label` after `BranchIfX then_label`).
+///   `Jump else_label` after `BranchIfX then_label`), unless `else` is cold
+///   and `then` is not: then `then`, and `lower_br` inverts the condition to
+///   branch to `else` instead.
 /// - `switch val, default, ...` → `default` (the trailing `Jump default_label`
-///   after all `BranchEqImm` case branches).
+///   after all `BranchEqImm` case branches), unless `default` is cold.
 /// - `ret` / `unreachable` → none.
 ///
+/// When only one edge of a conditional `br` needs phi copies, `lower_br`
+/// branches to the other edge and ends with the copies plus a `Jump` to the
+/// phi side. `else` stays preferred anyway: for a `br_if` loop back-edge the
+/// phi side is the already-placed header, and keeping the exit next lets the
+/// peephole fold `branch exit; jump header` into one branch when the copies
+/// are empty.
+///
 /// Trampoline paths in `lower_br` / `lower_switch` (used when phi copies are
 /// needed on every outgoing edge) emit a final `Jump` to a different target
 /// than the one named above. Such blocks miss the fallthrough but remain
 /// correct.
 ///
-/// Algorithm: greedy trace construction. Iterate the original IR order; for
-/// each unplaced block, walk preferred-successor links until hitting a placed
-/// block or a terminator without a preferred successor.
+/// Algorithm: greedy trace construction. The entry block starts the first
+/// trace; then iterate the original IR order, first over hot and then over
+/// cold blocks, and from each unplaced block walk preferred-successor links
+/// until hitting a placed block or a terminator without a preferred successor.
 fn compute_block_layout<'ctx>(
     function: FunctionValue<'ctx>,
     bb_key_cache: &mut emitter::BbKeyCache,
+    ties: &mut tie_break::TieBreaker,
 ) -> Vec<BasicBlock<'ctx>> {
     let ir_blocks = function.get_basic_blocks();
     if ir_blocks.is_empty() {
         return ir_blocks;
     }
 
+    let cold = cold_blocks(&ir_blocks, bb_key_cache);
+    let is_cold = |cache: &mut emitter::BbKeyCache, bb: BasicBlock<'ctx>| {
+        cold.contains(&emitter::bb_key(cache, bb))
+    };
+
     let mut preferred_next: BTreeMap<BbKey, BasicBlock<'ctx>> = BTreeMap::new();
     for &bb in &ir_blocks {
         let Some(term) = bb.get_terminator() else {
@@ -480,13 +569,37 @@ fn compute_block_layout<'ctx>(
                     term.get_operand(0)
                         .and_then(inkwell::values::Operand::block)
                 } else {
-                    term.get_operand(1)
-                        .and_then(inkwell::values::Operand::block)
+                    let else_bb = term
+                        .get_operand(1)
+                        .and_then(inkwell::values::Operand::block);
+                    let then_bb = term
+                        .get_operand(2)
+                        .and_then(inkwell::values::Operand::block);
+                    match (else_bb, then_bb) {
+                        (Some(else_bb), Some(then_bb))
+                            if is_cold(bb_key_cache, else_bb)
+                                && !is_cold(bb_key_cache, then_bb) =>
+                        {
+                            Some(then_bb)
+                        }
+                        // Two equally hot (or cold) successors: a seeded
+                        // tie-break may fall through to `then` instead.
+                        (Some(else_bb), Some(then_bb))
+                            if is_cold(bb_key_cache, else_bb) == is_cold(bb_key_cache, then_bb)
+                                && ties.flip() =>
+                        {
+                            Some(then_bb)
+                        }
+                        _ => else_bb,
+                    }
                 }
             }
             InstructionOpcode::Switch => term
                 .get_operand(1)
-                .and_then(inkwell::values::Operand::block),
+                .and_then(inkwell::values::Operand::block)
+                .filter(|&default_bb| {
+                    !is_cold(bb_key_cache, default_bb) || is_cold(bb_key_cache, bb)
+                }),
             _ => None,
         };
         if let Some(next) = next {
@@ -498,7 +611,13 @@ fn compute_block_layout<'ctx>(
     let mut placed: BTreeSet<BbKey> = BTreeSet::new();
     let mut layout: Vec<BasicBlock<'ctx>> = Vec::with_capacity(ir_blocks.len());
 
-    for &start in &ir_blocks {
+    let hot_starts = ir_blocks
+        .iter()
+        .enumerate()
+        .filter(|&(idx, &bb)| idx == 0 || !is_cold(bb_key_cache, bb))
+        .map(|(_, &bb)| bb)
+        .collect::<Vec<_>>();
+    for start in hot_starts.into_iter().chain(ir_blocks.iter().copied()) {
         let start_key = emitter::bb_key(bb_key_cache, start);
         if placed.contains(&start_key) {
             continue;
@@ -527,6 +646,43 @@ fn compute_block_layout<'ctx>(
     layout
 }
 
+/// Blocks from which every path ends in `unreachable` (see
+/// `compute_block_layout`). Computed as a fixed point: a block is cold once
+/// its terminator is `unreachable` or all of its successors are cold.
+fn cold_blocks(
+    ir_blocks: &[BasicBlock<'_>],
+    bb_key_cache: &mut emitter::BbKeyCache,
+) -> BTreeSet<BbKey> {
+    let mut cold: BTreeSet<BbKey> = BTreeSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &bb in ir_blocks {
+            let key = emitter::bb_key(bb_key_cache, bb);
+            if cold.contains(&key) {
+                continue;
+            }
+            let Some(term) = bb.get_terminator() else {
+                continue;
+            };
+            let is_cold = if term.get_opcode() == InstructionOpcode::Unreachable {
+                true
+            } else {
+                let succs = successors::collect_successors(term);
+                !succs.is_empty()
+                    && succs
+                        .into_iter()
+                        .all(|succ| cold.contains(&emitter::bb_key(bb_key_cache, succ)))
+            };
+            if is_cold {
+                cold.insert(key);
+                changed = true;
+            }
+        }
+    }
+    cold
+}
+
 /// Restore `alloc_reg_slot` for phi destinations at the start of a block.
 ///
 /// After `define_label` clears all alloc state, this re-establishes ownership
@@ -602,7 +758,7 @@ fn emit_prologue<'ctx>(
         }
 
         // Save callee-saved registers r9-r12 (only those actually used).
-        for i in 0..abi::MAX_LOCAL_REGS {
+        for i in 0..e.config.local_regs {
             if let Some(offset) = e.callee_save_offsets[i] {
                 e.emit(Instruction::StoreIndU64 {
                     base: abi::STACK_PTR_REG,
@@ -630,25 +786,35 @@ fn emit_prologue<'ctx>(
                     src: abi::ARGS_PTR_REG,
                     value: -e.config.wasm_memory_base,
                 });
+                if e.config.pic_enabled {
+                    // The host hands over a relocated pointer.
+                    e.emit(Instruction::Sub64 {
+                        dst: abi::ARGS_PTR_REG,
+                        src1: abi::ARGS_PTR_REG,
+                        src2: abi::PIC_BASE_REG,
+                    });
+                }
                 e.store_to_slot(slot, abi::ARGS_PTR_REG);
             } else if i == 1 {
                 e.store_to_slot(slot, abi::ARGS_LEN_REG);
             }
-        } else if i < abi::MAX_LOCAL_REGS {
-            // First 4 params come in r9-r12.
+        } else if i < e.config.local_regs {
+            // First `local_regs` params (4 by default) come in r9-r12.
             e.store_to_slot(slot, abi::FIRST_LOCAL_REG + i as u8);
         } else {
-            debug_assert!(
+            Error::check_invariant(
                 e.config.param_overflow_reserved,
-                "function prologue reads overflow param {i} but area was not reserved"
-            );
+                e.config.panic_on_internal_error,
+                || format!("function prologue reads overflow param {i} but area was not reserved"),
+            )?;
             // Overflow params from the parameter overflow area.
             let overflow_offset =
-                e.config.param_overflow_base + ((i - abi::MAX_LOCAL_REGS) * 8) as i32;
+                e.config.param_overflow_base + ((i - e.config.local_regs) * 8) as i32;
             e.emit(Instruction::LoadImm {
                 reg: TEMP1,
                 value: overflow_offset,
             });
+            e.emit_pic_rebase(TEMP1);
             e.emit(Instruction::LoadIndU64 {
                 dst: TEMP1,
                 base: TEMP1,
@@ -778,7 +944,9 @@ mod tests {
             global_widths: Vec::new(),
             param_overflow_base: 0,
             param_overflow_reserved: false,
+            local_regs: crate::abi::MAX_LOCAL_REGS,
             function_signatures: vec![(1, true)],
+            function_names: vec![],
             type_signatures: vec![],
             function_table: vec![],
             num_imported_funcs: 0,
@@ -791,6 +959,11 @@ mod tests {
             data_segment_length_addrs: BTreeMap::new(),
             wasm_import_map: None,
             optimizations: OptimizationFlags::default(),
+            seed: 0,
+            pic: false,
+            panic_on_internal_error: true,
+            function_sections: false,
+            reserved_registers: Vec::new(),
         }
     }
 
@@ -839,4 +1012,166 @@ mod tests {
             translation.instructions
         );
     }
+
+    /// Lower the single function `wasm_func_0` of hand-written IR with the
+    /// default optimization flags.
+    fn lower_ir(ir: &str) -> Vec<Instruction> {
+        lower_ir_with(ir, OptimizationFlags::default())
+    }
+
+    /// [`lower_ir`] with the given optimization flags.
+    fn lower_ir_with(ir: &str, optimizations: OptimizationFlags) -> Vec<Instruction> {
+        use inkwell::context::Context;
+        use inkwell::memory_buffer::MemoryBuffer;
+
+        let context = Context::create();
+        let buffer = MemoryBuffer::create_from_memory_range_copy(ir.as_bytes(), "lower_ir_test");
+        let module = context
+            .create_module_from_ir(buffer)
+            .expect("hand-written IR should parse");
+        let func = module
+            .get_function("wasm_func_0")
+            .expect("function wasm_func_0 should exist");
+        let ctx = LoweringContext {
+            optimizations,
+            ..minimal_lowering_context()
+        };
+        lower_function(func, &ctx, false, 0, "wasm_func_0", 0)
+            .expect("lowering should succeed")
+            .instructions
+    }
+
+    /// Identity ops (`x | 0`, `x ^ 0`, `x & -1`, `x + 0`) become plain copies.
+    /// LLVM's instcombine removes them before the backend ever sees them, so
+    /// this feeds the backend IR that still contains them.
+    #[test]
+    fn identity_alu_ops_are_elided() {
+        let instructions = lower_ir(
+            r"
+            define i64 @wasm_func_0(i64 %0) {
+            entry:
+              %a = or i64 %0, 0
+              %b = xor i64 %a, 0
+              %c = and i64 %b, -1
+              %d = add i64 %c, 0
+              ret i64 %d
+            }
+        ",
+        );
+        assert!(
+            !instructions.iter().any(|i| matches!(
+                i,
+                Instruction::Or { .. }
+                    | Instruction::OrImm { .. }
+                    | Instruction::Xor { .. }
+                    | Instruction::XorImm { .. }
+                    | Instruction::And { .. }
+                    | Instruction::AndImm { .. }
+                    | Instruction::Add64 { .. }
+                    | Instruction::AddImm64 { value: 0, .. }
+            )),
+            "identity ops should be elided, got: {instructions:?}"
+        );
+    }
+
+    /// Blocks reached only by falling through don't start a new basic block,
+    /// so their labels get no `Fallthrough`.
+    #[test]
+    fn straight_line_blocks_have_no_fallthroughs() {
+        let instructions = lower_ir(
+            r"
+            define i64 @wasm_func_0(i64 %0) {
+            entry:
+              %a = mul i64 %0, 3
+              br label %first
+            first:
+              %b = add i64 %a, 7
+              br label %second
+            second:
+              %c = xor i64 %b, 5
+              ret i64 %c
+            }
+        ",
+        );
+        assert!(
+            !instructions
+                .iter()
+                .any(|i| matches!(i, Instruction::Fallthrough)),
+            "untargeted labels kept their Fallthrough: {instructions:?}"
+        );
+    }
+
+    /// A loop header is a branch target and keeps its `Fallthrough`.
+    #[test]
+    fn loop_header_keeps_its_fallthrough() {
+        let instructions = lower_ir(
+            r"
+            define i64 @wasm_func_0(i64 %0) {
+            entry:
+              %start = add i64 %0, 1
+              br label %loop
+            loop:
+              %i = phi i64 [ %start, %entry ], [ %next, %loop ]
+              %next = mul i64 %i, 3
+              %done = icmp ugt i64 %next, 1000
+              br i1 %done, label %exit, label %loop
+            exit:
+              ret i64 %next
+            }
+        ",
+        );
+        assert!(
+            instructions
+                .iter()
+                .any(|i| matches!(i, Instruction::Fallthrough)),
+            "the loop header lost its Fallthrough: {instructions:?}"
+        );
+    }
+
+    /// Stack-slot stores and reloads (`SP`-relative accesses) in `instructions`.
+    fn count_slot_traffic(instructions: &[Instruction]) -> (usize, usize) {
+        let sp = crate::abi::STACK_PTR_REG;
+        let stores = instructions
+            .iter()
+            .filter(|i| matches!(i, Instruction::StoreIndU64 { base, .. } if *base == sp))
+            .count();
+        let loads = instructions
+            .iter()
+            .filter(|i| matches!(i, Instruction::LoadIndU64 { base, .. } if *base == sp))
+            .count();
+        (stores, loads)
+    }
+
+    /// Two interleaved expression trees: scheduled, each product's operands are
+    /// computed right before it, so one of them is still in a register and the
+    /// block reloads fewer values from their stack slots. Register allocation
+    /// is off so every value lives in a slot and only the order differs.
+    #[test]
+    fn scheduling_reduces_spill_traffic() {
+        let ir = r"
+            define i64 @wasm_func_0(i64 %a, i64 %b) {
+            entry:
+              %a1 = add i64 %a, 1
+              %b1 = add i64 %b, 1
+              %a2 = add i64 %a, 2
+              %b2 = add i64 %b, 2
+              %a3 = mul i64 %a1, %a2
+              %b3 = mul i64 %b1, %b2
+              %r = add i64 %a3, %b3
+              ret i64 %r
+            }
+        ";
+        let flags = OptimizationFlags::all().with_register_allocation(false);
+        let (stores_on, loads_on) = count_slot_traffic(&lower_ir_with(ir, flags.clone()));
+        let (stores_off, loads_off) =
+            count_slot_traffic(&lower_ir_with(ir, flags.with_scheduling(false)));
+        assert!(
+            loads_on < loads_off,
+            "slot reloads: scheduled {loads_on}, unscheduled {loads_off}"
+        );
+        assert!(
+            stores_on + loads_on < stores_off + loads_off,
+            "slot traffic: scheduled {stores_on}+{loads_on}, unscheduled {stores_off}+{loads_off}"
+        );
+    }
 }
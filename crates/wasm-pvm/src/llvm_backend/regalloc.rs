@@ -33,7 +33,8 @@ use std::collections::{BTreeMap, BTreeSet};
 use inkwell::values::{FunctionValue, PhiValue};
 
 use super::emitter::{
-    BbKey, BbKeyCache, ValKey, ValKeyCache, is_real_call, val_key_basic, val_key_instr,
+    BbKey, BbKeyCache, ValKey, ValKeyCache, is_dynamic_call_intrinsic, is_real_call, val_key_basic,
+    val_key_instr,
 };
 use super::successors::collect_successors;
 
@@ -109,10 +110,15 @@ pub struct RegAllocStats {
 ///
 /// `value_slots` maps `ValKey` → stack slot offset (from the pre-scan bump allocator).
 /// `num_params` is the number of function parameters (for determining available callee-saved regs).
+/// `local_regs` is how many of r9-r12 the calling convention uses; the rest
+/// are never allocated.
 /// `aggressive` lowers the minimum-use threshold from 2 to 1, capturing more candidates.
 /// `scratch_regs_safe` indicates that this function never clobbers r5/r6
 /// (`abi::SCRATCH1`/`SCRATCH2`), making them available for allocation.
 /// `allocate_caller_saved` enables r7/r8 allocation in leaf functions.
+/// `prefer_caller_saved` steers intervals that don't span a call towards
+/// r5-r8 (see `linear_scan`).
+/// `reserved_regs` are never allocated (`CompileOptions::reserved_registers`).
 /// `block_order` is the block emission order the backend will actually use.
 /// Live intervals, loop-header detection, and call-position bookkeeping are
 /// all keyed off this order, so when the emitter uses a non-IR layout (e.g.
@@ -128,9 +134,12 @@ pub fn run<'ctx>(
     value_slots: &BTreeMap<ValKey, i32>,
     is_leaf: bool,
     num_params: usize,
+    local_regs: usize,
     aggressive: bool,
     scratch_regs_safe: bool,
     allocate_caller_saved: bool,
+    prefer_caller_saved: bool,
+    reserved_regs: &[u8],
     block_order: &[inkwell::basic_block::BasicBlock<'ctx>],
 ) -> RegAllocResult {
     let fn_name = function.get_name().to_string_lossy().to_string();
@@ -258,9 +267,10 @@ pub fn run<'ctx>(
     // non-param values causes correctness failures in tests with parameter
     // overflow / loop back-edges (see integration tests).
     let first_alloc_idx = num_params;
-    for i in first_alloc_idx..crate::abi::MAX_LOCAL_REGS {
+    for i in first_alloc_idx..local_regs {
         allocatable_regs.push(crate::abi::FIRST_LOCAL_REG + i as u8);
     }
+    allocatable_regs.retain(|reg| !reserved_regs.contains(reg));
     stats.allocatable_regs = allocatable_regs.len();
 
     // Non-leaf functions need at least one allocatable register to proceed.
@@ -293,7 +303,7 @@ pub fn run<'ctx>(
     }
 
     // Phase 4: Linear scan allocation.
-    let mut result = linear_scan(intervals, &allocatable_regs, is_leaf);
+    let mut result = linear_scan(intervals, &allocatable_regs, is_leaf, prefer_caller_saved);
     stats.allocated_values = result.val_to_reg.len();
     result.stats = stats;
 
@@ -341,7 +351,7 @@ fn linearize(
 }
 
 /// Returns the maximum direct call argument count used in this function.
-/// Only counts real calls (`wasm_func_*`, `__pvm_call_indirect`), not intrinsics
+/// Only counts real calls (`wasm_func_*`, `__pvm_call_indirect`, `__pvm_call_ref`), not intrinsics
 /// (`__pvm_load/store/memory_*`, `llvm.*`) and import function declarations
 /// (`host_call_N`, `ecalli:N`, etc.) which don't use outgoing argument registers
 /// (r9+). Import calls are lowered by `lower_import_call` which loads args
@@ -357,8 +367,8 @@ fn max_call_args(function: FunctionValue<'_>) -> usize {
                     && let Some(fn_val) = cs.get_called_fn_value()
                 {
                     let name = fn_val.get_name().to_string_lossy();
-                    // Skip PVM intrinsics (except call_indirect) and LLVM intrinsics.
-                    if (name.starts_with("__pvm_") && name != "__pvm_call_indirect")
+                    // Skip PVM intrinsics (except dynamic calls) and LLVM intrinsics.
+                    if (name.starts_with("__pvm_") && !is_dynamic_call_intrinsic(&name))
                         || name.starts_with("llvm.")
                     {
                         continue;
@@ -367,8 +377,8 @@ fn max_call_args(function: FunctionValue<'_>) -> usize {
                     // lowered by lower_import_call which handles register
                     // loading into r7+ (host_call_N, ecalli:N, trap, nop),
                     // NOT via the standard r9+ calling convention.
-                    // __pvm_call_indirect is also a declaration but uses the
-                    // standard convention, so exclude it from this check.
+                    // __pvm_call_indirect / __pvm_call_ref are also declarations
+                    // but use the standard convention, so exclude them here.
                     if fn_val.count_basic_blocks() == 0 && !name.starts_with("__pvm_") {
                         continue;
                     }
@@ -674,11 +684,15 @@ fn update_use(
 /// `is_leaf` controls register class preference: in non-leaf functions,
 /// call-spanning intervals prefer callee-saved registers and non-call-spanning
 /// intervals prefer caller-saved registers. In leaf functions, all registers
-/// are equal (no calls to invalidate them).
+/// are equal (no calls to invalidate them) unless `prefer_caller_saved` is
+/// set, which extends the caller-saved preference to them: r5-r8 are free at
+/// entry, while each r9-r12 the function touches costs a save/restore pair in
+/// the prologue/epilogue.
 fn linear_scan(
     mut intervals: Vec<LiveInterval>,
     allocatable_regs: &[u8],
     is_leaf: bool,
+    prefer_caller_saved: bool,
 ) -> RegAllocResult {
     // Sort by start point (ascending), then by spill_weight descending (prefer
     // allocating high-weight values when two intervals start at the same point).
@@ -717,8 +731,9 @@ fn linear_scan(
 
         // Prefer the hinted register if available (e.g., r7 for call return values).
         // For non-leaf functions: call-spanning intervals prefer callee-saved
-        // registers (survive calls without invalidation), non-call-spanning
-        // intervals prefer caller-saved (leave callee-saved for others).
+        // registers (survive calls without invalidation). Non-call-spanning
+        // intervals prefer caller-saved (no prologue save, and callee-saved
+        // stay free for call-spanning values).
         let reg = if let Some(pref) = interval.preferred_reg {
             if let Some(pos) = free_regs.iter().position(|&r| r == pref) {
                 Some(free_regs.swap_remove(pos))
@@ -736,8 +751,8 @@ fn linear_scan(
             } else {
                 free_regs.pop()
             }
-        } else if !is_leaf {
-            // Non-call-spanning in non-leaf: prefer caller-saved (r5-r8).
+        } else if !is_leaf || prefer_caller_saved {
+            // Not call-spanning: prefer caller-saved (r5-r8).
             let caller_pos = free_regs.iter().position(|&r| {
                 r == crate::abi::SCRATCH1
                     || r == crate::abi::SCRATCH2
@@ -825,7 +840,7 @@ mod tests {
             },
         ];
 
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
 
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
         assert_eq!(result.val_to_reg.get(&ValKey(2)), Some(&9));
@@ -860,7 +875,7 @@ mod tests {
             },
         ];
 
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
 
         assert!(!result.val_to_reg.contains_key(&ValKey(1)));
         assert_eq!(result.val_to_reg.get(&ValKey(2)), Some(&9));
@@ -892,7 +907,7 @@ mod tests {
             },
         ];
 
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
 
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
         assert!(!result.val_to_reg.contains_key(&ValKey(2)));
@@ -924,7 +939,7 @@ mod tests {
             },
         ];
 
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
 
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
         assert!(!result.val_to_reg.contains_key(&ValKey(2)));
@@ -952,7 +967,7 @@ mod tests {
             spans_calls: false,
         }];
 
-        let result = linear_scan(intervals, &[9, 7], true);
+        let result = linear_scan(intervals, &[9, 7], true, true);
 
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&7));
     }
@@ -971,7 +986,7 @@ mod tests {
             spans_calls: false,
         }];
 
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
 
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
     }
@@ -1020,7 +1035,7 @@ mod tests {
         // Only 1 register: without early expiration, ValKey(2) would be evicted
         // or spilled. With early expiration, ValKey(1) frees the register at 3,
         // and ValKey(2) gets it at 4.
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
         assert_eq!(result.val_to_reg.get(&ValKey(2)), Some(&9));
     }
@@ -1052,7 +1067,7 @@ mod tests {
             },
         ];
 
-        let result = linear_scan(intervals, &[9], true);
+        let result = linear_scan(intervals, &[9], true, true);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
         // ValKey(2) not allocated — ValKey(1) still active (higher weight)
         assert!(!result.val_to_reg.contains_key(&ValKey(2)));
@@ -1075,7 +1090,7 @@ mod tests {
 
         // free_regs = [9, 5]: pop() would give r5 (caller-saved), but the
         // callee-saved preference should seek out r9 instead.
-        let result = linear_scan(intervals, &[9, 5], false);
+        let result = linear_scan(intervals, &[9, 5], false, true);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
     }
 
@@ -1096,8 +1111,11 @@ mod tests {
         }];
 
         // free_regs = [5, 9]: pop() would give 9, but the caller-saved
-        // preference explicitly seeks r5.
-        let result = linear_scan(intervals, &[5, 9], false);
+        // preference explicitly seeks r5 — with or without
+        // `prefer_caller_saved`, which only concerns leaf functions.
+        let result = linear_scan(intervals.clone(), &[5, 9], false, true);
+        assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&5));
+        let result = linear_scan(intervals, &[5, 9], false, false);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&5));
     }
 
@@ -1117,7 +1135,29 @@ mod tests {
         }];
 
         // In leaf, pop() gives 9 (last element), no preference applied.
-        let result = linear_scan(intervals, &[5, 9], true);
+        let result = linear_scan(intervals, &[5, 9], true, false);
+        assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
+    }
+
+    #[test]
+    fn leaf_function_prefers_caller_saved() {
+        // In a leaf function, r9 would cost a prologue save/restore pair while
+        // r5 is free — with the preference on, r5 wins over pop() order.
+        let interval = LiveInterval {
+            val_key: ValKey(1),
+            slot: 8,
+            start: 0,
+            end: 5,
+            expiration: 5,
+            spill_weight: 3.0,
+            preferred_reg: None,
+            spans_calls: false,
+        };
+
+        let result = linear_scan(vec![interval.clone()], &[5, 9], true, true);
+        assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&5));
+
+        let result = linear_scan(vec![interval], &[5, 9], true, false);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&9));
     }
 
@@ -1150,7 +1190,7 @@ mod tests {
             },
         ];
 
-        let result = linear_scan(intervals, &[11, 5], false);
+        let result = linear_scan(intervals, &[11, 5], false, true);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&5));
         assert_eq!(result.val_to_reg.get(&ValKey(2)), Some(&11));
     }
@@ -1172,7 +1212,7 @@ mod tests {
 
         // Only callee-saved available — preference can't find caller-saved,
         // so it falls back to pop() and gets r11.
-        let result = linear_scan(intervals, &[11], false);
+        let result = linear_scan(intervals, &[11], false, true);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&11));
     }
 
@@ -1191,7 +1231,7 @@ mod tests {
             spans_calls: true,
         }];
 
-        let result = linear_scan(intervals, &[9, 7], false);
+        let result = linear_scan(intervals, &[9, 7], false, true);
         assert_eq!(result.val_to_reg.get(&ValKey(1)), Some(&7));
     }
 }
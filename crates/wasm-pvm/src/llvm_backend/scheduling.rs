@@ -0,0 +1,317 @@
+// Per-block instruction scheduling.
+//
+// The backend lowers instructions in LLVM order, which can interleave the
+// definitions of independent expression trees and keep many short-lived values
+// live at once. This pass reorders each block in place so that every operand
+// tree is emitted directly in front of its consumer (a depth-first
+// linearization, Sethi-Ullman style), shortening live ranges before register
+// allocation runs.
+//
+// It runs on the LLVM IR before `pre_scan_function`, so slot allocation,
+// regalloc linearization and lowering all observe the same order.
+//
+// Ordering rules:
+//   - Phis stay at the head of the block, the terminator stays last.
+//   - Instructions with side effects or that may trap (memory accesses, calls,
+//     division/remainder, anything not known to be pure) are "roots" and keep
+//     their relative order.
+//   - Pure instructions move freely but never ahead of their operands.
+//   - An `icmp` whose only user is the terminator is emitted last so
+//     ICmp+Branch fusion keeps the comparison adjacent to the branch.
+//   - Operand trees are visited in operand order unless a `TieBreaker` seed
+//     shuffles them; any order is valid, it only changes live ranges.
+
+use std::collections::BTreeMap;
+
+use inkwell::basic_block::BasicBlock;
+use inkwell::values::{
+    AnyValueEnum, AsValueRef, FunctionValue, InstructionOpcode, InstructionValue, Operand,
+};
+
+use super::tie_break::TieBreaker;
+
+/// Reorder the instructions of every basic block in `function`.
+/// Returns the number of blocks whose order changed.
+pub(crate) fn schedule_function(function: FunctionValue<'_>, ties: &mut TieBreaker) -> usize {
+    let mut changed = 0;
+    for bb in function.get_basic_blocks() {
+        if schedule_block(bb, ties) {
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Whether `instr` can be moved freely relative to other pure instructions:
+/// no memory effects, no calls and no way to trap.
+fn is_pure(instr: InstructionValue<'_>) -> bool {
+    matches!(
+        instr.get_opcode(),
+        InstructionOpcode::Add
+            | InstructionOpcode::Sub
+            | InstructionOpcode::Mul
+            | InstructionOpcode::And
+            | InstructionOpcode::Or
+            | InstructionOpcode::Xor
+            | InstructionOpcode::Shl
+            | InstructionOpcode::LShr
+            | InstructionOpcode::AShr
+            | InstructionOpcode::ICmp
+            | InstructionOpcode::Select
+            | InstructionOpcode::ZExt
+            | InstructionOpcode::SExt
+            | InstructionOpcode::Trunc
+            | InstructionOpcode::Freeze
+    )
+}
+
+/// Returns the instruction using `instr` if it has exactly one use.
+fn single_user(instr: InstructionValue<'_>) -> Option<InstructionValue<'_>> {
+    let first_use = instr.get_first_use()?;
+    if first_use.get_next_use().is_some() {
+        return None;
+    }
+    match first_use.get_user() {
+        AnyValueEnum::InstructionValue(iv) => Some(iv),
+        AnyValueEnum::IntValue(iv) => iv.as_instruction(),
+        _ => None,
+    }
+}
+
+/// Compute the scheduled order of `body` (indices into `body`).
+///
+/// `body` excludes phis and the terminator. Roots are visited in their
+/// original order; each root first emits any not-yet-emitted operands
+/// (depth-first, in operand order) and then itself. Pure instructions whose
+/// only consumers are other body instructions are emitted on first demand.
+fn compute_order(
+    body: &[InstructionValue<'_>],
+    terminator: Option<InstructionValue<'_>>,
+    ties: &mut TieBreaker,
+) -> Vec<usize> {
+    let index: BTreeMap<usize, usize> = body
+        .iter()
+        .enumerate()
+        .map(|(i, instr)| (instr.as_value_ref() as usize, i))
+        .collect();
+
+    // In-block data dependencies, in operand order.
+    let mut preds: Vec<Vec<usize>> = body
+        .iter()
+        .map(|instr| {
+            (0..instr.get_num_operands())
+                .filter_map(|i| instr.get_operand(i).and_then(Operand::value))
+                .filter_map(|v| index.get(&(v.as_value_ref() as usize)).copied())
+                .collect()
+        })
+        .collect();
+
+    for node_preds in &mut preds {
+        ties.shuffle(node_preds);
+    }
+
+    let mut has_body_user = vec![false; body.len()];
+    for p in preds.iter().flatten() {
+        has_body_user[*p] = true;
+    }
+
+    let term_ptr = terminator.map(|t| t.as_value_ref() as usize);
+    let feeds_terminator_only = |instr: InstructionValue<'_>| {
+        term_ptr.is_some()
+            && instr.get_opcode() == InstructionOpcode::ICmp
+            && single_user(instr).map(|u| u.as_value_ref() as usize) == term_ptr
+    };
+
+    let mut roots = Vec::new();
+    let mut tail = Vec::new();
+    for (i, &instr) in body.iter().enumerate() {
+        if feeds_terminator_only(instr) {
+            tail.push(i);
+        } else if !is_pure(instr) || !has_body_user[i] {
+            roots.push(i);
+        }
+    }
+    roots.extend(tail);
+
+    let mut emitted = vec![false; body.len()];
+    let mut order = Vec::with_capacity(body.len());
+    // Explicit stack of (node, next operand to visit) — long dependency
+    // chains would overflow the call stack with a recursive walk.
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for root in roots {
+        if emitted[root] {
+            continue;
+        }
+        stack.push((root, 0));
+        while let Some(top) = stack.last_mut() {
+            let (node, next) = *top;
+            if let Some(&pred) = preds[node].get(next) {
+                top.1 += 1;
+                if !emitted[pred] {
+                    stack.push((pred, 0));
+                }
+            } else {
+                stack.pop();
+                if !emitted[node] {
+                    emitted[node] = true;
+                    order.push(node);
+                }
+            }
+        }
+    }
+
+    // Every body instruction is either a root or transitively feeds one;
+    // anything left over (dead pure code) keeps its original position at the end.
+    order.extend((0..body.len()).filter(|&i| !emitted[i]));
+    order
+}
+
+/// Schedule a single block. Returns true if the instruction order changed.
+fn schedule_block(bb: BasicBlock<'_>, ties: &mut TieBreaker) -> bool {
+    let terminator = bb.get_terminator();
+    let body: Vec<InstructionValue<'_>> = bb
+        .get_instructions()
+        .filter(|i| i.get_opcode() != InstructionOpcode::Phi && Some(*i) != terminator)
+        .collect();
+    if body.len() < 2 {
+        return false;
+    }
+
+    let order = compute_order(&body, terminator, ties);
+    if order.iter().enumerate().all(|(pos, &i)| pos == i) {
+        return false;
+    }
+
+    // Re-insert the body in scheduled order, each instruction in front of the
+    // terminator (or at the block end for an unterminated block).
+    let builder = bb.get_context().create_builder();
+    for &i in &order {
+        let instr = body[i];
+        instr.remove_from_basic_block();
+        match terminator {
+            Some(term) => builder.position_before(&term),
+            None => builder.position_at_end(bb),
+        }
+        builder.insert_instruction(&instr, None);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inkwell::context::Context;
+    use inkwell::memory_buffer::MemoryBuffer;
+    use inkwell::module::Module;
+
+    fn parse<'ctx>(context: &'ctx Context, ir: &str) -> Module<'ctx> {
+        let buffer = MemoryBuffer::create_from_memory_range_copy(ir.as_bytes(), "sched_test");
+        context
+            .create_module_from_ir(buffer)
+            .expect("hand-written IR should parse")
+    }
+
+    /// Maximum number of in-block values simultaneously live at any point.
+    fn max_live_values(bb: BasicBlock<'_>) -> usize {
+        let instrs: Vec<_> = bb.get_instructions().collect();
+        let pos: BTreeMap<usize, usize> = instrs
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.as_value_ref() as usize, i))
+            .collect();
+        let last_use: Vec<usize> = instrs
+            .iter()
+            .enumerate()
+            .map(|(def, instr)| {
+                let mut last = def;
+                let mut use_iter = instr.get_first_use();
+                while let Some(u) = use_iter {
+                    let user = u.get_user().as_value_ref() as usize;
+                    last = last.max(pos.get(&user).copied().unwrap_or(instrs.len()));
+                    use_iter = u.get_next_use();
+                }
+                last
+            })
+            .collect();
+        (0..instrs.len())
+            .map(|at| (0..=at).filter(|&def| last_use[def] > at).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Two independent expression trees interleaved by the frontend: the
+    /// scheduler should finish one tree before starting the other.
+    #[test]
+    fn independent_chains_reduce_live_values() {
+        let ir = r"
+            define i64 @wasm_func_0(i64 %a, i64 %b) {
+            entry:
+              %a1 = add i64 %a, 1
+              %b1 = add i64 %b, 1
+              %a2 = add i64 %a, 2
+              %b2 = add i64 %b, 2
+              %a3 = mul i64 %a1, %a2
+              %b3 = mul i64 %b1, %b2
+              %r = add i64 %a3, %b3
+              ret i64 %r
+            }
+        ";
+        let context = Context::create();
+        let module = parse(&context, ir);
+        let func = module.get_function("wasm_func_0").expect("function");
+        let bb = func.get_first_basic_block().expect("entry block");
+
+        let before = max_live_values(bb);
+        assert_eq!(schedule_function(func, &mut TieBreaker::new(0, "")), 1);
+        let after = max_live_values(bb);
+        assert!(after < before, "live values {before} -> {after}");
+        module.verify().expect("scheduled IR must stay valid");
+    }
+
+    /// Memory accesses keep their relative order; pure address arithmetic
+    /// sinks next to the store that consumes it.
+    #[test]
+    fn memory_ops_keep_relative_order() {
+        let ir = r"
+            define void @wasm_func_0(ptr %p, ptr %q, i64 %v) {
+            entry:
+              %w = add i64 %v, 7
+              %x = load i64, ptr %q
+              store i64 %x, ptr %p
+              %y = load i64, ptr %p
+              store i64 %w, ptr %q
+              store i64 %y, ptr %p
+              ret void
+            }
+        ";
+        let context = Context::create();
+        let module = parse(&context, ir);
+        let func = module.get_function("wasm_func_0").expect("function");
+        let bb = func.get_first_basic_block().expect("entry block");
+        let memory_ops = |bb: BasicBlock<'_>| -> Vec<usize> {
+            bb.get_instructions()
+                .filter(|i| {
+                    matches!(
+                        i.get_opcode(),
+                        InstructionOpcode::Load | InstructionOpcode::Store
+                    )
+                })
+                .map(|i| i.as_value_ref() as usize)
+                .collect()
+        };
+
+        let before = memory_ops(bb);
+        schedule_function(func, &mut TieBreaker::new(0, ""));
+        assert_eq!(memory_ops(bb), before);
+        module.verify().expect("scheduled IR must stay valid");
+
+        // `%w` now sits directly in front of its only consumer.
+        let opcodes: Vec<_> = bb.get_instructions().map(|i| i.get_opcode()).collect();
+        let add = opcodes
+            .iter()
+            .position(|op| *op == InstructionOpcode::Add)
+            .expect("add");
+        assert_eq!(opcodes[add - 1], InstructionOpcode::Load);
+        assert_eq!(opcodes[add + 1], InstructionOpcode::Store);
+    }
+}
@@ -0,0 +1,92 @@
+// Seeded tie-breaking for heuristic backend passes.
+//
+// Instruction scheduling and block layout make arbitrary choices where their
+// cost models see no difference (which independent operand tree to emit first,
+// which of two hot successors to fall through to). `CompileOptions::seed` lets
+// experiments perturb exactly those choices, reproducibly. Seed 0 keeps the
+// built-in choice everywhere, so default output is unaffected.
+
+/// Per-function source of tie-break decisions.
+pub(crate) struct TieBreaker {
+    /// xorshift64* state; `None` when tie-breaking is disabled (seed 0).
+    state: Option<u64>,
+}
+
+impl TieBreaker {
+    /// A tie-breaker for the function named `salt`. Mixing in the name gives
+    /// each function its own stream, so its choices don't depend on how many
+    /// draws earlier functions made.
+    pub(crate) fn new(seed: u64, salt: &str) -> Self {
+        if seed == 0 {
+            return Self { state: None };
+        }
+        // FNV-1a over the name, starting from the seed.
+        let mut hash = 0xcbf2_9ce4_8422_2325u64 ^ seed;
+        for byte in salt.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        // xorshift is stuck at zero, so force a nonzero state.
+        Self {
+            state: Some(hash | 1),
+        }
+    }
+
+    fn draw(&mut self) -> Option<u64> {
+        let state = self.state.as_mut()?;
+        *state ^= *state >> 12;
+        *state ^= *state << 25;
+        *state ^= *state >> 27;
+        Some(state.wrapping_mul(0x2545_f491_4f6c_dd1d))
+    }
+
+    /// An index in `0..n`; always 0 (the built-in choice) when disabled.
+    pub(crate) fn pick(&mut self, n: usize) -> usize {
+        match self.draw() {
+            Some(r) if n > 1 => (r % n as u64) as usize,
+            _ => 0,
+        }
+    }
+
+    /// Whether to take the alternative of a two-way tie.
+    pub(crate) fn flip(&mut self) -> bool {
+        self.pick(2) == 1
+    }
+
+    /// Shuffle `items` in place; a no-op when disabled.
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        if self.state.is_none() {
+            return;
+        }
+        for i in (1..items.len()).rev() {
+            let j = self.pick(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TieBreaker;
+
+    #[test]
+    fn seed_zero_keeps_builtin_choices() {
+        let mut ties = TieBreaker::new(0, "wasm_func_0");
+        let mut items = [1, 2, 3, 4];
+        ties.shuffle(&mut items);
+        assert_eq!(items, [1, 2, 3, 4]);
+        assert!(!ties.flip());
+        assert_eq!(ties.pick(7), 0);
+    }
+
+    #[test]
+    fn same_seed_and_function_repeat() {
+        let draws = |seed, name| {
+            let mut ties = TieBreaker::new(seed, name);
+            (0..16).map(|_| ties.pick(1000)).collect::<Vec<_>>()
+        };
+        assert_eq!(draws(7, "f"), draws(7, "f"));
+        assert_ne!(draws(7, "f"), draws(8, "f"));
+        assert_ne!(draws(7, "f"), draws(7, "g"));
+    }
+}
@@ -15,8 +15,9 @@ use inkwell::types::{BasicMetadataTypeEnum, IntType};
 use inkwell::values::{
     BasicMetadataValueEnum, FunctionValue, GlobalValue, IntValue, PhiValue, PointerValue,
 };
-use wasmparser::{FunctionBody, Operator};
+use wasmparser::{FunctionBody, MemArg, Operator};
 
+use super::softfloat;
 use crate::translate::wasm_module::WasmModule;
 use crate::{Error, Result};
 
@@ -85,22 +86,92 @@ fn float_op_stack_effect(op: &Operator) -> Option<(usize, usize)> {
     })
 }
 
+/// Whether `op` comes from the GC proposal (`i31ref`, structs, arrays, casts).
+/// These validate under wasmparser's default features but need a managed heap
+/// and type-tagged references, which PVM code has no runtime for.
+fn is_gc_operator(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::RefI31
+            | Operator::I31GetS
+            | Operator::I31GetU
+            | Operator::RefEq
+            | Operator::StructNew { .. }
+            | Operator::StructNewDefault { .. }
+            | Operator::StructGet { .. }
+            | Operator::StructGetS { .. }
+            | Operator::StructGetU { .. }
+            | Operator::StructSet { .. }
+            | Operator::ArrayNew { .. }
+            | Operator::ArrayNewDefault { .. }
+            | Operator::ArrayNewFixed { .. }
+            | Operator::ArrayNewData { .. }
+            | Operator::ArrayNewElem { .. }
+            | Operator::ArrayGet { .. }
+            | Operator::ArrayGetS { .. }
+            | Operator::ArrayGetU { .. }
+            | Operator::ArraySet { .. }
+            | Operator::ArrayLen
+            | Operator::ArrayFill { .. }
+            | Operator::ArrayCopy { .. }
+            | Operator::ArrayInitData { .. }
+            | Operator::ArrayInitElem { .. }
+            | Operator::RefTestNonNull { .. }
+            | Operator::RefTestNullable { .. }
+            | Operator::RefCastNonNull { .. }
+            | Operator::RefCastNullable { .. }
+            | Operator::BrOnCast { .. }
+            | Operator::BrOnCastFail { .. }
+            | Operator::AnyConvertExtern
+            | Operator::ExternConvertAny
+    )
+}
+
+/// Compare the operand-stack depth at a construct's `else`/`end` against the
+/// depth its frame recorded plus its result count.
+fn check_frame_balance(
+    kind: &str,
+    actual: usize,
+    stack_depth: usize,
+    results: usize,
+) -> std::result::Result<(), String> {
+    let expected = stack_depth + results;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "operand stack imbalance at end of {kind}: depth {actual}, expected {expected} \
+             ({stack_depth} on entry + {results} result(s))"
+        ))
+    }
+}
+
 /// WASM control flow frame, tracking LLVM basic blocks and phi nodes.
+///
+/// Block/If frames carry one phi per result, in stack order (the deepest
+/// result first); `stack_depth` is the operand-stack height on entry below
+/// any block parameters, so the frame ends with exactly
+/// `stack_depth + results` values. A Loop carries one header phi per
+/// parameter instead, since that is what a branch back to it passes.
 enum ControlFrame<'ctx> {
     Block {
         merge_bb: BasicBlock<'ctx>,
-        result_phi: Option<PhiValue<'ctx>>,
+        result_phis: Vec<PhiValue<'ctx>>,
         stack_depth: usize,
     },
     Loop {
         header_bb: BasicBlock<'ctx>,
         merge_bb: BasicBlock<'ctx>,
+        param_phis: Vec<PhiValue<'ctx>>,
         stack_depth: usize,
+        results: usize,
     },
     If {
         else_bb: BasicBlock<'ctx>,
         merge_bb: BasicBlock<'ctx>,
-        result_phi: Option<PhiValue<'ctx>>,
+        result_phis: Vec<PhiValue<'ctx>>,
+        /// The block parameters, which the else arm starts from again.
+        params: Vec<IntValue<'ctx>>,
         stack_depth: usize,
         else_seen: bool,
     },
@@ -123,19 +194,13 @@ impl<'ctx> ControlFrame<'ctx> {
         }
     }
 
-    fn result_phi(&self) -> Option<PhiValue<'ctx>> {
-        match self {
-            Self::Block { result_phi, .. } | Self::If { result_phi, .. } => *result_phi,
-            Self::Loop { .. } => None,
-        }
-    }
-
-    /// Whether a branch to this frame's target carries a result value.
-    /// Block/If targets (merge) can have results; Loop targets (header) never do.
-    fn br_has_result(&self) -> bool {
+    /// The phis a branch to this frame's target feeds, one per carried value.
+    /// Block/If targets (merge) take the results; Loop targets (header) the
+    /// parameters.
+    fn br_phis(&self) -> Vec<PhiValue<'ctx>> {
         match self {
-            Self::Block { result_phi, .. } | Self::If { result_phi, .. } => result_phi.is_some(),
-            Self::Loop { .. } => false,
+            Self::Block { result_phis, .. } | Self::If { result_phis, .. } => result_phis.clone(),
+            Self::Loop { param_phis, .. } => param_phis.clone(),
         }
     }
 }
@@ -159,12 +224,24 @@ pub struct WasmToLlvm<'ctx> {
     // Type signatures for indirect calls: (num_params, num_results) per type index
     type_signatures: Vec<(usize, usize)>,
 
+    // Result count per function (imports first, then locals). Results after
+    // the first go through the param-overflow area; see `push_call_results`.
+    function_results: Vec<usize>,
+
     // Module-wide configuration
     /// When true, every f32/f64 operator is replaced with a runtime trap instead
     /// of being a compile error. Lets users push past the float wall to see what
     /// other unsupported features exist downstream.
     trap_floats: bool,
 
+    /// When true, loads and stores whose alignment hint is below their natural
+    /// alignment are split into single-byte accesses.
+    split_misaligned_accesses: bool,
+
+    /// When true, float constants, loads, stores, arithmetic and comparisons
+    /// are lowered to integer code by `softfloat` instead of being rejected.
+    soft_float: bool,
+
     /// When true, functions whose `name` custom section entry matches a known
     /// compiler-builtins libcall (`__multi3`, `__udivti3`) have their bodies
     /// replaced with a hand-crafted PVM-friendly implementation. See
@@ -216,17 +293,26 @@ struct PvmIntrinsics<'ctx> {
     #[allow(dead_code)] // Infrastructure ready for when data.drop is fully supported
     data_drop: FunctionValue<'ctx>,
     call_indirect: FunctionValue<'ctx>,
+    call_ref: FunctionValue<'ctx>,
     /// Upper 64 bits of an unsigned 64×64→128 multiply. Lowers to `MulUpperUU`.
     /// Used by the synthesized `__multi3` body.
     mul_upper_uu: FunctionValue<'ctx>,
+    /// Extra results of multi-value functions, passed through the
+    /// param-overflow area: `result_get(i)` reads result `i + 1` after a call,
+    /// `result_set(i, v)` writes it before a return.
+    result_get: FunctionValue<'ctx>,
+    result_set: FunctionValue<'ctx>,
 }
 
 impl<'ctx> WasmToLlvm<'ctx> {
     #[must_use]
+    #[allow(clippy::fn_params_excessive_bools)]
     pub fn new(
         context: &'ctx Context,
         module_name: &str,
         trap_floats: bool,
+        split_misaligned_accesses: bool,
+        soft_float: bool,
         recognize_libcalls: bool,
     ) -> Self {
         let module = context.create_module(module_name);
@@ -242,9 +328,12 @@ impl<'ctx> WasmToLlvm<'ctx> {
             i64_type: context.i64_type(),
             pvm_intrinsics,
             type_signatures: Vec::new(),
+            function_results: Vec::new(),
             functions: Vec::new(),
             globals: Vec::new(),
             trap_floats,
+            split_misaligned_accesses,
+            soft_float,
             recognize_libcalls,
             libcall_targets: crate::translate::wasm_module::LibcallTargets::default(),
             operand_stack: Vec::new(),
@@ -270,6 +359,10 @@ impl<'ctx> WasmToLlvm<'ctx> {
         self.context
     }
 
+    pub(super) fn module(&self) -> &Module<'ctx> {
+        &self.module
+    }
+
     pub(super) fn i64_type(&self) -> IntType<'ctx> {
         self.i64_type
     }
@@ -393,11 +486,17 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 void_type.fn_type(&[i64_type.into()], false),
             ),
             call_indirect: decl("__pvm_call_indirect", call_indirect_sig),
+            // (type_idx: i64, func_ref: i64, args...) -> i64 — same shape as
+            // call_indirect, but the callee is a jump-ref rather than a table slot.
+            call_ref: decl("__pvm_call_ref", call_indirect_sig),
             // (a: i64, b: i64) -> i64 — upper 64 bits of unsigned 64×64→128 product.
             mul_upper_uu: decl(
                 "__pvm_mul_upper_uu",
                 i64_type.fn_type(&[i64_type.into(), i64_type.into()], false),
             ),
+            // (index: i64) -> i64 / (index: i64, val: i64) -> void
+            result_get: decl("__pvm_result_get", load_sig),
+            result_set: decl("__pvm_result_set", store_sig),
         }
     }
 
@@ -407,12 +506,24 @@ impl<'ctx> WasmToLlvm<'ctx> {
         run_llvm_passes: bool,
         run_inlining: bool,
         inline_threshold: Option<u32>,
+        unroll_max_trip_count: Option<u32>,
         run_mergefunc: bool,
+        run_tailcallelim: bool,
     ) -> Result<Module<'ctx>> {
         self.declare_functions(wasm_module);
         self.declare_globals(wasm_module);
         self.type_signatures
             .clone_from(&wasm_module.type_signatures);
+        self.function_results = wasm_module
+            .imported_func_type_indices
+            .iter()
+            .chain(&wasm_module.function_type_indices)
+            .map(|&type_idx| {
+                self.type_signatures
+                    .get(type_idx as usize)
+                    .map_or(0, |&(_, results)| results)
+            })
+            .collect();
         if self.recognize_libcalls {
             self.libcall_targets = wasm_module.libcall_targets.clone();
         }
@@ -420,20 +531,27 @@ impl<'ctx> WasmToLlvm<'ctx> {
         for (local_idx, func_body) in wasm_module.functions.iter().enumerate() {
             let global_idx = wasm_module.num_imported_funcs as usize + local_idx;
             let func_value = self.functions[global_idx];
-            let (num_params, has_return) = wasm_module.function_signatures[global_idx];
+            let (num_params, _) = wasm_module.function_signatures[global_idx];
+            let num_results = self.function_results[global_idx];
             let display_name = wasm_module.local_function_display_name(local_idx);
             self.translate_function(
                 func_body,
                 func_value,
                 num_params,
-                has_return,
+                num_results,
                 global_idx,
                 &display_name,
             )?;
         }
 
         if run_llvm_passes {
-            self.run_optimization_passes(run_inlining, inline_threshold, run_mergefunc)?;
+            self.run_optimization_passes(
+                run_inlining,
+                inline_threshold,
+                unroll_max_trip_count,
+                run_mergefunc,
+                run_tailcallelim,
+            )?;
         }
 
         self.module
@@ -467,7 +585,10 @@ impl<'ctx> WasmToLlvm<'ctx> {
         // 8 B for i64/f64) is enforced by the backend via `ctx.global_widths`,
         // which picks `LoadU32`/`LoadU64` and `StoreU32`/`StoreU64` per global.
         // Keeping the LLVM IR shape uniform avoids changing LLVM-pass
-        // outcomes for i32-only modules (the common case).
+        // outcomes for i32-only modules (the common case). Funcref globals
+        // start at 0 here; their jump-refs are written into RW data at link
+        // time, which is safe because the globals are externally visible and
+        // LLVM never folds loads from them to this initializer.
         self.globals.clear();
         for (idx, &init_value) in wasm_module.global_init_values.iter().enumerate() {
             let global = self
@@ -483,7 +604,7 @@ impl<'ctx> WasmToLlvm<'ctx> {
         func_body: &FunctionBody,
         func_value: FunctionValue<'ctx>,
         num_params: usize,
-        has_return: bool,
+        num_results: usize,
         func_idx: usize,
         func_name: &str,
     ) -> Result<()> {
@@ -491,7 +612,7 @@ impl<'ctx> WasmToLlvm<'ctx> {
         self.locals.clear();
         self.control_stack.clear();
         self.current_fn = Some(func_value);
-        self.has_return = has_return;
+        self.has_return = num_results > 0;
         self.unreachable = false;
 
         let entry_bb = self.context.append_basic_block(func_value, "entry");
@@ -551,17 +672,10 @@ impl<'ctx> WasmToLlvm<'ctx> {
         // The function body's final End pops this frame and emits the return.
         let fn_val = self.current_fn.unwrap();
         let merge_bb = self.context.append_basic_block(fn_val, "fn_return");
-        let result_phi = if has_return {
-            self.builder.position_at_end(merge_bb);
-            let phi = llvm_err(self.builder.build_phi(self.i64_type, "fn_result"))?;
-            self.builder.position_at_end(entry_bb);
-            Some(phi)
-        } else {
-            None
-        };
+        let result_phis = self.build_result_phis(merge_bb, num_results, "fn_result")?;
         self.control_stack.push(ControlFrame::Block {
             merge_bb,
-            result_phi,
+            result_phis,
             stack_depth: 0,
         });
 
@@ -601,8 +715,10 @@ impl<'ctx> WasmToLlvm<'ctx> {
         // `self.unreachable = true` because the operand stack must keep its
         // expected shape for the remainder of the function body (in particular,
         // function-level result phis must still receive an incoming branch).
+        // Operators with a soft-float lowering are real code, not traps.
         if self.trap_floats
             && !self.unreachable
+            && !self.has_soft_float_lowering(op)
             && let Some((pop, push)) = float_op_stack_effect(op)
         {
             return self.emit_float_trap(pop, push);
@@ -616,7 +732,7 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     let dummy_bb = self.builder.get_insert_block().unwrap();
                     self.control_stack.push(ControlFrame::Block {
                         merge_bb: dummy_bb,
-                        result_phi: None,
+                        result_phis: Vec::new(),
                         stack_depth: self.operand_stack.len(),
                     });
                     return Ok(());
@@ -627,7 +743,8 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     self.control_stack.push(ControlFrame::If {
                         else_bb: dummy_bb,
                         merge_bb: dummy_bb,
-                        result_phi: None,
+                        result_phis: Vec::new(),
+                        params: Vec::new(),
                         stack_depth: self.operand_stack.len(),
                         else_seen: false,
                     });
@@ -650,6 +767,14 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 self.push(self.i64_type.const_int(*value as u64, false));
                 Ok(())
             }
+            Operator::F32Const { value } if self.soft_float => {
+                self.push(self.i64_type.const_int(u64::from(value.bits()), false));
+                Ok(())
+            }
+            Operator::F64Const { value } if self.soft_float => {
+                self.push(self.i64_type.const_int(value.bits(), false));
+                Ok(())
+            }
 
             // === Locals ===
             Operator::LocalGet { local_index } => {
@@ -863,7 +988,7 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 self.pop()?;
                 Ok(())
             }
-            Operator::Select => {
+            Operator::Select | Operator::TypedSelect { .. } => {
                 let cond = self.pop()?;
                 let val2 = self.pop()?;
                 let val1 = self.pop()?;
@@ -885,13 +1010,16 @@ impl<'ctx> WasmToLlvm<'ctx> {
 
             // === Control flow ===
             Operator::Nop => Ok(()),
-            Operator::DataDrop { .. } => {
-                // DataDrop is not yet fully supported - would require tracking dropped segments
-                // to ensure subsequent memory.init operations trap as required by WASM spec.
-                // For now, reject compilation to avoid silent incorrect behavior.
-                Err(crate::Error::Unsupported(
-                    "data.drop operator is not yet supported".to_string(),
-                ))
+            Operator::DataDrop { data_index } => {
+                // Zeroes the segment's runtime length, so any later
+                // `memory.init` with `len > 0` fails its bounds check.
+                let wasm_seg_idx = self.i64_type.const_int(u64::from(*data_index), false);
+                llvm_err(self.builder.build_call(
+                    self.pvm_intrinsics.data_drop,
+                    &[wasm_seg_idx.into()],
+                    "datadrop",
+                ))?;
+                Ok(())
             }
 
             Operator::Unreachable => {
@@ -905,13 +1033,9 @@ impl<'ctx> WasmToLlvm<'ctx> {
             Operator::Return => {
                 if !self.unreachable {
                     // Extract frame data before mutably borrowing self
-                    let fn_phi = self.control_stack[0].result_phi();
+                    let fn_phis = self.control_stack[0].br_phis();
                     let fn_merge = self.control_stack[0].merge_bb();
-                    if let Some(phi) = fn_phi {
-                        let val = self.pop()?;
-                        let current_bb = self.builder.get_insert_block().unwrap();
-                        phi.add_incoming(&[(&val, current_bb)]);
-                    }
+                    self.add_result_incomings(&fn_phis, true)?;
                     llvm_err(self.builder.build_unconditional_branch(fn_merge))?;
                     self.unreachable = true;
                 }
@@ -923,50 +1047,56 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     // In dead code, just track nesting for End matching
                     self.control_stack.push(ControlFrame::Block {
                         merge_bb: self.builder.get_insert_block().unwrap(),
-                        result_phi: None,
+                        result_phis: Vec::new(),
                         stack_depth: self.operand_stack.len(),
                     });
                     return Ok(());
                 }
-                let has_result = !matches!(blockty, wasmparser::BlockType::Empty);
+                let (params, results) = self.block_arity(*blockty)?;
+                // The parameters stay where they are and become the block's
+                // own operands.
+                let stack_depth = self.params_base(params)?;
                 let fn_val = self.current_fn.unwrap();
                 let merge_bb = self.context.append_basic_block(fn_val, "block_merge");
-                let result_phi = if has_result {
-                    let current_bb = self.builder.get_insert_block().unwrap();
-                    self.builder.position_at_end(merge_bb);
-                    let phi = llvm_err(self.builder.build_phi(self.i64_type, "block_result"))?;
-                    self.builder.position_at_end(current_bb);
-                    Some(phi)
-                } else {
-                    None
-                };
+                let result_phis = self.build_result_phis(merge_bb, results, "block_result")?;
                 self.control_stack.push(ControlFrame::Block {
                     merge_bb,
-                    result_phi,
-                    stack_depth: self.operand_stack.len(),
+                    result_phis,
+                    stack_depth,
                 });
                 Ok(())
             }
 
-            Operator::Loop { .. } => {
+            Operator::Loop { blockty } => {
                 if self.unreachable {
                     self.control_stack.push(ControlFrame::Loop {
                         header_bb: self.builder.get_insert_block().unwrap(),
                         merge_bb: self.builder.get_insert_block().unwrap(),
+                        param_phis: Vec::new(),
                         stack_depth: self.operand_stack.len(),
+                        results: 0,
                     });
                     return Ok(());
                 }
+                let (params, results) = self.block_arity(*blockty)?;
                 let fn_val = self.current_fn.unwrap();
                 let header_bb = self.context.append_basic_block(fn_val, "loop_header");
                 let merge_bb = self.context.append_basic_block(fn_val, "loop_merge");
+                // The parameters enter the header through phis, which every
+                // `br` back to the loop feeds as well.
+                let param_phis = self.build_result_phis(header_bb, params, "loop_param")?;
+                self.add_result_incomings(&param_phis, true)?;
                 // Branch from current block to loop header
                 llvm_err(self.builder.build_unconditional_branch(header_bb))?;
                 self.builder.position_at_end(header_bb);
+                let stack_depth = self.operand_stack.len();
+                self.push_phi_results(&param_phis);
                 self.control_stack.push(ControlFrame::Loop {
                     header_bb,
                     merge_bb,
-                    stack_depth: self.operand_stack.len(),
+                    param_phis,
+                    stack_depth,
+                    results,
                 });
                 Ok(())
             }
@@ -976,14 +1106,17 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     self.control_stack.push(ControlFrame::If {
                         else_bb: self.builder.get_insert_block().unwrap(),
                         merge_bb: self.builder.get_insert_block().unwrap(),
-                        result_phi: None,
+                        result_phis: Vec::new(),
+                        params: Vec::new(),
                         stack_depth: self.operand_stack.len(),
                         else_seen: false,
                     });
                     return Ok(());
                 }
-                let has_result = !matches!(blockty, wasmparser::BlockType::Empty);
+                let (params, results) = self.block_arity(*blockty)?;
                 let cond = self.pop()?;
+                let stack_depth = self.params_base(params)?;
+                let params = self.operand_stack[stack_depth..].to_vec();
                 let cond32 = llvm_err(self.builder.build_int_truncate(
                     cond,
                     self.i32_type,
@@ -1001,15 +1134,7 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 let else_bb = self.context.append_basic_block(fn_val, "if_else");
                 let merge_bb = self.context.append_basic_block(fn_val, "if_merge");
 
-                let result_phi = if has_result {
-                    let current_bb = self.builder.get_insert_block().unwrap();
-                    self.builder.position_at_end(merge_bb);
-                    let phi = llvm_err(self.builder.build_phi(self.i64_type, "if_result"))?;
-                    self.builder.position_at_end(current_bb);
-                    Some(phi)
-                } else {
-                    None
-                };
+                let result_phis = self.build_result_phis(merge_bb, results, "if_result")?;
 
                 llvm_err(
                     self.builder
@@ -1020,8 +1145,9 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 self.control_stack.push(ControlFrame::If {
                     else_bb,
                     merge_bb,
-                    result_phi,
-                    stack_depth: self.operand_stack.len(),
+                    result_phis,
+                    params,
+                    stack_depth,
                     else_seen: false,
                 });
                 Ok(())
@@ -1033,15 +1159,22 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     .control_stack
                     .last()
                     .ok_or_else(|| Error::Internal("Else without matching If".into()))?;
-                let (merge, else_block, phi, depth) = if let ControlFrame::If {
+                let (merge, else_block, phis, params, depth) = if let ControlFrame::If {
                     else_bb,
                     merge_bb,
-                    result_phi,
+                    result_phis,
+                    params,
                     stack_depth,
                     ..
                 } = frame
                 {
-                    (*merge_bb, *else_bb, *result_phi, *stack_depth)
+                    (
+                        *merge_bb,
+                        *else_bb,
+                        result_phis.clone(),
+                        params.clone(),
+                        *stack_depth,
+                    )
                 } else {
                     return Err(Error::Internal("Else without matching If frame".into()));
                 };
@@ -1062,16 +1195,14 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 }
 
                 if !self.unreachable {
-                    if let Some(phi) = phi {
-                        let val = self.pop()?;
-                        let current_bb = self.builder.get_insert_block().unwrap();
-                        phi.add_incoming(&[(&val, current_bb)]);
-                    }
+                    self.assert_frame_balance("if", depth, phis.len());
+                    self.add_result_incomings(&phis, true)?;
                     llvm_err(self.builder.build_unconditional_branch(merge))?;
                 }
 
                 self.builder.position_at_end(else_block);
                 self.operand_stack.truncate(depth);
+                self.operand_stack.extend(params);
                 self.unreachable = false;
                 Ok(())
             }
@@ -1085,22 +1216,34 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 match frame {
                     ControlFrame::Block {
                         merge_bb,
-                        result_phi,
+                        result_phis,
                         stack_depth,
                     } => {
                         if self.control_stack.is_empty() {
                             // Function-level End: branch to merge which has the return
                             if !self.unreachable {
-                                if let Some(phi) = result_phi {
-                                    let val = self.pop()?;
-                                    let current_bb = self.builder.get_insert_block().unwrap();
-                                    phi.add_incoming(&[(&val, current_bb)]);
-                                }
+                                self.assert_frame_balance(
+                                    "function body",
+                                    stack_depth,
+                                    result_phis.len(),
+                                );
+                                self.add_result_incomings(&result_phis, true)?;
                                 llvm_err(self.builder.build_unconditional_branch(merge_bb))?;
                             }
-                            // Position at merge block and emit actual return
+                            // Position at merge block and emit actual return.
+                            // The first result goes back in r7, the rest
+                            // through the param-overflow area.
                             self.builder.position_at_end(merge_bb);
-                            if let Some(phi) = result_phi {
+                            for (i, phi) in result_phis.iter().enumerate().skip(1) {
+                                let index = self.i64_type.const_int(i as u64 - 1, false);
+                                let val = phi.as_basic_value().into_int_value();
+                                llvm_err(self.builder.build_call(
+                                    self.pvm_intrinsics.result_set,
+                                    &[index.into(), val.into()],
+                                    "",
+                                ))?;
+                            }
+                            if let Some(phi) = result_phis.first() {
                                 let ret_val = phi.as_basic_value().into_int_value();
                                 llvm_err(self.builder.build_return(Some(&ret_val)))?;
                             } else {
@@ -1118,24 +1261,20 @@ impl<'ctx> WasmToLlvm<'ctx> {
                         } else {
                             // Nested block End
                             if !self.unreachable {
-                                if let Some(phi) = result_phi {
-                                    let val = self.pop()?;
-                                    let current_bb = self.builder.get_insert_block().unwrap();
-                                    phi.add_incoming(&[(&val, current_bb)]);
-                                }
+                                self.assert_frame_balance("block", stack_depth, result_phis.len());
+                                self.add_result_incomings(&result_phis, true)?;
                                 llvm_err(self.builder.build_unconditional_branch(merge_bb))?;
                             }
                             self.builder.position_at_end(merge_bb);
                             self.operand_stack.truncate(stack_depth);
-                            if let Some(phi) = result_phi {
-                                self.push(phi.as_basic_value().into_int_value());
-                            }
+                            self.push_phi_results(&result_phis);
                             self.unreachable = false;
                         }
                     }
                     ControlFrame::Loop {
                         merge_bb,
                         stack_depth,
+                        results,
                         ..
                     } => {
                         // The only path into a loop's `merge_bb` is the
@@ -1145,8 +1284,19 @@ impl<'ctx> WasmToLlvm<'ctx> {
                         // dead — `unreachable` must stay set. Terminate the
                         // empty `merge_bb` with `unreachable` so the LLVM
                         // verifier accepts it as a valid (dead) block.
+                        //
+                        // For the same reason loop results need no phis:
+                        // the fall-through values dominate `merge_bb`.
                         let fell_through = !self.unreachable;
+                        let mut values = vec![self.i64_type.const_zero(); results];
                         if fell_through {
+                            self.assert_frame_balance("loop", stack_depth, results);
+                            let base = self
+                                .operand_stack
+                                .len()
+                                .checked_sub(results)
+                                .ok_or_else(|| Error::Internal("operand stack underflow".into()))?;
+                            values = self.operand_stack.split_off(base);
                             llvm_err(self.builder.build_unconditional_branch(merge_bb))?;
                         }
                         self.builder.position_at_end(merge_bb);
@@ -1154,12 +1304,14 @@ impl<'ctx> WasmToLlvm<'ctx> {
                         if !fell_through {
                             llvm_err(self.builder.build_unreachable())?;
                         }
+                        self.operand_stack.extend(values);
                         self.unreachable = !fell_through;
                     }
                     ControlFrame::If {
                         else_bb,
                         merge_bb,
-                        result_phi,
+                        result_phis,
+                        params,
                         stack_depth,
                         else_seen,
                     } => {
@@ -1173,25 +1325,25 @@ impl<'ctx> WasmToLlvm<'ctx> {
                         }
 
                         if !self.unreachable {
-                            if let Some(phi) = result_phi {
-                                let val = self.pop()?;
-                                let current_bb = self.builder.get_insert_block().unwrap();
-                                phi.add_incoming(&[(&val, current_bb)]);
-                            }
+                            self.assert_frame_balance("if", stack_depth, result_phis.len());
+                            self.add_result_incomings(&result_phis, true)?;
                             llvm_err(self.builder.build_unconditional_branch(merge_bb))?;
                         }
 
                         if !else_seen {
-                            // No else branch: else_bb falls through to merge
+                            // No else branch: else_bb falls through to merge,
+                            // passing the parameters on as the results (the
+                            // validator requires the two to match).
                             self.builder.position_at_end(else_bb);
+                            for (phi, param) in result_phis.iter().zip(&params) {
+                                phi.add_incoming(&[(param, else_bb)]);
+                            }
                             llvm_err(self.builder.build_unconditional_branch(merge_bb))?;
                         }
 
                         self.builder.position_at_end(merge_bb);
                         self.operand_stack.truncate(stack_depth);
-                        if let Some(phi) = result_phi {
-                            self.push(phi.as_basic_value().into_int_value());
-                        }
+                        self.push_phi_results(&result_phis);
                         self.unreachable = false;
                     }
                 }
@@ -1203,13 +1355,8 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     let depth = *relative_depth as usize;
                     let idx = self.control_stack.len() - 1 - depth;
                     let target_bb = self.control_stack[idx].br_target();
-                    if self.control_stack[idx].br_has_result() {
-                        let val = self.pop()?;
-                        let current_bb = self.builder.get_insert_block().unwrap();
-                        if let Some(phi) = self.control_stack[idx].result_phi() {
-                            phi.add_incoming(&[(&val, current_bb)]);
-                        }
-                    }
+                    let phis = self.control_stack[idx].br_phis();
+                    self.add_result_incomings(&phis, true)?;
                     llvm_err(self.builder.build_unconditional_branch(target_bb))?;
                     self.unreachable = true;
                 }
@@ -1219,31 +1366,34 @@ impl<'ctx> WasmToLlvm<'ctx> {
             Operator::BrIf { relative_depth } => {
                 if !self.unreachable {
                     let cond = self.pop()?;
-                    let cond32 = llvm_err(self.builder.build_int_truncate(
-                        cond,
-                        self.i32_type,
-                        "brif_c",
-                    ))?;
-                    let cond_bool = llvm_err(self.builder.build_int_compare(
-                        IntPredicate::NE,
-                        cond32,
-                        self.i32_type.const_zero(),
-                        "brif_test",
-                    ))?;
+                    // When the condition comes straight from a comparison
+                    // (`zext i1 %cmp`), branch on the `i1` itself so the
+                    // backend can fuse the compare into the branch even
+                    // when instcombine does not run.
+                    let cond_bool = if let Some(cmp) = Self::peel_bool_zext(cond) {
+                        cmp
+                    } else {
+                        let cond32 = llvm_err(self.builder.build_int_truncate(
+                            cond,
+                            self.i32_type,
+                            "brif_c",
+                        ))?;
+                        llvm_err(self.builder.build_int_compare(
+                            IntPredicate::NE,
+                            cond32,
+                            self.i32_type.const_zero(),
+                            "brif_test",
+                        ))?
+                    };
 
                     let depth = *relative_depth as usize;
                     let idx = self.control_stack.len() - 1 - depth;
                     let target_bb = self.control_stack[idx].br_target();
 
-                    // If the target block has a result, we need to pass it.
-                    // For br_if, the value stays on the stack for the fallthrough path.
-                    if self.control_stack[idx].br_has_result() {
-                        let val = self.peek()?;
-                        let current_bb = self.builder.get_insert_block().unwrap();
-                        if let Some(phi) = self.control_stack[idx].result_phi() {
-                            phi.add_incoming(&[(&val, current_bb)]);
-                        }
-                    }
+                    // If the target block has results, we need to pass them.
+                    // For br_if, the values stay on the stack for the fallthrough path.
+                    let phis = self.control_stack[idx].br_phis();
+                    self.add_result_incomings(&phis, false)?;
 
                     let fn_val = self.current_fn.unwrap();
                     let continue_bb = self.context.append_basic_block(fn_val, "brif_cont");
@@ -1270,29 +1420,19 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     let default_idx = self.control_stack.len() - 1 - default_depth;
                     let default_bb = self.control_stack[default_idx].br_target();
 
-                    // Add phi incoming for default target
-                    if self.control_stack[default_idx].br_has_result() {
-                        let val = self.peek()?;
-                        let current_bb = self.builder.get_insert_block().unwrap();
-                        if let Some(phi) = self.control_stack[default_idx].result_phi() {
-                            phi.add_incoming(&[(&val, current_bb)]);
-                        }
-                    }
+                    // Add phi incomings for default target
+                    let phis = self.control_stack[default_idx].br_phis();
+                    self.add_result_incomings(&phis, false)?;
 
                     let target_depths: Vec<u32> = targets
                         .targets()
                         .collect::<std::result::Result<Vec<_>, _>>()?;
 
                     // Add phi incomings for all non-default targets
-                    let current_bb = self.builder.get_insert_block().unwrap();
                     for &depth in &target_depths {
                         let idx = self.control_stack.len() - 1 - depth as usize;
-                        if self.control_stack[idx].br_has_result() {
-                            let val = self.peek()?;
-                            if let Some(phi) = self.control_stack[idx].result_phi() {
-                                phi.add_incoming(&[(&val, current_bb)]);
-                            }
-                        }
+                        let phis = self.control_stack[idx].br_phis();
+                        self.add_result_incomings(&phis, false)?;
                     }
 
                     let cases: Vec<(IntValue<'ctx>, BasicBlock<'ctx>)> = target_depths
@@ -1313,64 +1453,62 @@ impl<'ctx> WasmToLlvm<'ctx> {
             }
 
             // === Memory loads ===
-            Operator::I32Load { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i32, memarg.offset)
-            }
-            Operator::I64Load { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i64, memarg.offset)
+            Operator::I32Load { memarg } => self.emit_load(self.pvm_intrinsics.load_i32, memarg),
+            Operator::I64Load { memarg } => self.emit_load(self.pvm_intrinsics.load_i64, memarg),
+            Operator::F32Load { memarg } if self.soft_float => {
+                self.emit_load(self.pvm_intrinsics.load_i32, memarg)
             }
-            Operator::I32Load8U { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i8u, memarg.offset)
-            }
-            Operator::I32Load8S { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i8s, memarg.offset)
+            Operator::F64Load { memarg } if self.soft_float => {
+                self.emit_load(self.pvm_intrinsics.load_i64, memarg)
             }
+            Operator::I32Load8U { memarg } => self.emit_load(self.pvm_intrinsics.load_i8u, memarg),
+            Operator::I32Load8S { memarg } => self.emit_load(self.pvm_intrinsics.load_i8s, memarg),
             Operator::I32Load16U { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i16u, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i16u, memarg)
             }
             Operator::I32Load16S { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i16s, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i16s, memarg)
             }
             Operator::I64Load8U { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i8u_64, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i8u_64, memarg)
             }
             Operator::I64Load8S { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i8s_64, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i8s_64, memarg)
             }
             Operator::I64Load16U { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i16u_64, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i16u_64, memarg)
             }
             Operator::I64Load16S { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i16s_64, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i16s_64, memarg)
             }
             Operator::I64Load32U { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i32u_64, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i32u_64, memarg)
             }
             Operator::I64Load32S { memarg } => {
-                self.emit_load(self.pvm_intrinsics.load_i32s_64, memarg.offset)
+                self.emit_load(self.pvm_intrinsics.load_i32s_64, memarg)
             }
 
             // === Memory stores ===
-            Operator::I32Store { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i32, memarg.offset)
-            }
-            Operator::I64Store { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i64, memarg.offset)
+            Operator::I32Store { memarg } => self.emit_store(self.pvm_intrinsics.store_i32, memarg),
+            Operator::I64Store { memarg } => self.emit_store(self.pvm_intrinsics.store_i64, memarg),
+            Operator::F32Store { memarg } if self.soft_float => {
+                self.emit_store(self.pvm_intrinsics.store_i32, memarg)
             }
-            Operator::I32Store8 { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i8, memarg.offset)
+            Operator::F64Store { memarg } if self.soft_float => {
+                self.emit_store(self.pvm_intrinsics.store_i64, memarg)
             }
+            Operator::I32Store8 { memarg } => self.emit_store(self.pvm_intrinsics.store_i8, memarg),
             Operator::I32Store16 { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i16, memarg.offset)
+                self.emit_store(self.pvm_intrinsics.store_i16, memarg)
             }
             Operator::I64Store8 { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i8_64, memarg.offset)
+                self.emit_store(self.pvm_intrinsics.store_i8_64, memarg)
             }
             Operator::I64Store16 { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i16_64, memarg.offset)
+                self.emit_store(self.pvm_intrinsics.store_i16_64, memarg)
             }
             Operator::I64Store32 { memarg } => {
-                self.emit_store(self.pvm_intrinsics.store_i32_64, memarg.offset)
+                self.emit_store(self.pvm_intrinsics.store_i32_64, memarg)
             }
 
             // === Memory management ===
@@ -1453,15 +1591,8 @@ impl<'ctx> WasmToLlvm<'ctx> {
                 }
                 args.reverse();
                 let result = llvm_err(self.builder.build_call(target_fn, &args, "call"))?;
-                if target_fn.get_type().get_return_type().is_some() {
-                    let val = result
-                        .try_as_basic_value()
-                        .basic()
-                        .ok_or_else(|| Error::Internal("call returned void unexpectedly".into()))?
-                        .into_int_value();
-                    self.push(val);
-                }
-                Ok(())
+                let num_results = self.function_results[*function_index as usize];
+                self.push_call_results(result, num_results, "call returned void unexpectedly")
             }
             Operator::CallIndirect {
                 type_index,
@@ -1494,33 +1625,94 @@ impl<'ctx> WasmToLlvm<'ctx> {
                     &all_args,
                     "call_indirect",
                 ))?;
-                if num_results > 0 {
-                    let val = result
-                        .try_as_basic_value()
-                        .basic()
-                        .ok_or_else(|| Error::Internal("call_indirect returned void".into()))?
-                        .into_int_value();
-                    self.push(val);
+                self.push_call_results(result, num_results, "call_indirect returned void")
+            }
+
+            Operator::CallRef { type_index } => {
+                let (num_params, num_results) = self
+                    .type_signatures
+                    .get(*type_index as usize)
+                    .copied()
+                    .ok_or_else(|| Error::Internal(format!("unknown type index {type_index}")))?;
+                let func_ref = self.pop()?;
+                let mut all_args: Vec<BasicMetadataValueEnum> = Vec::with_capacity(num_params + 2);
+                for _ in 0..num_params {
+                    all_args.push(self.pop()?.into());
                 }
+                all_args.push(func_ref.into());
+                all_args.push(
+                    self.i64_type
+                        .const_int(u64::from(*type_index), false)
+                        .into(),
+                );
+                all_args.reverse();
+                let result = llvm_err(self.builder.build_call(
+                    self.pvm_intrinsics.call_ref,
+                    &all_args,
+                    "call_ref",
+                ))?;
+                self.push_call_results(result, num_results, "call_ref returned void")
+            }
+
+            // === References (null is 0; funcrefs are dispatch-table jump-refs) ===
+            Operator::RefNull { .. } => {
+                self.push(self.i64_type.const_zero());
                 Ok(())
             }
+            Operator::RefIsNull => self.i64_eqz(),
 
-            // === Float stubs ===
-            Operator::I32TruncSatF64U
-            | Operator::I32TruncSatF64S
-            | Operator::I32TruncSatF32U
-            | Operator::I32TruncSatF32S
-            | Operator::I64TruncSatF64U
-            | Operator::I64TruncSatF64S
-            | Operator::I64TruncSatF32U
-            | Operator::I64TruncSatF32S => Err(Error::FloatNotSupported),
+            op if is_gc_operator(op) => Err(Error::UnsupportedWasmOp {
+                op: format!("{op:?}"),
+                reason: "GC proposal references (i31ref, structs, arrays) need a managed heap \
+                         that PVM does not provide; compile without GC types"
+                    .to_string(),
+            }),
 
-            _ => Err(Error::Unsupported(format!("{op:?}"))),
+            // === Soft-float operators and int↔float conversions ===
+            _ => {
+                if self.soft_float
+                    && let Some(float_op) = softfloat::float_op_for(op)
+                {
+                    let mut operands = Vec::with_capacity(float_op.arity());
+                    for _ in 0..float_op.arity() {
+                        operands.push(self.pop()?);
+                    }
+                    operands.reverse();
+                    let result = softfloat::emit_float_op(self, float_op, &operands)?;
+                    self.push(result);
+                    return Ok(());
+                }
+                let conversion = softfloat::conversion_for(op)
+                    .ok_or_else(|| Error::Unsupported(format!("{op:?}")))?;
+                let val = self.pop()?;
+                let result = softfloat::emit_conversion(self, conversion, val)?;
+                self.push(result);
+                Ok(())
+            }
         }
     }
 
     // ── Trap-floats helper ──
 
+    /// Whether `op` compiles to real code through `softfloat` (and so must
+    /// not be trapped under `--trap-floats`).
+    fn has_soft_float_lowering(&self, op: &Operator) -> bool {
+        if softfloat::conversion_for(op).is_some() {
+            return true;
+        }
+        self.soft_float
+            && (softfloat::float_op_for(op).is_some()
+                || matches!(
+                    op,
+                    Operator::F32Const { .. }
+                        | Operator::F64Const { .. }
+                        | Operator::F32Load { .. }
+                        | Operator::F64Load { .. }
+                        | Operator::F32Store { .. }
+                        | Operator::F64Store { .. }
+                ))
+    }
+
     /// Emit a runtime trap (`@llvm.trap()` + `unreachable`) and continue
     /// codegen in a fresh basic block. Pops `pop` operand-stack entries for
     /// the operator's inputs and pushes `push` zero placeholders for its
@@ -1556,7 +1748,7 @@ impl<'ctx> WasmToLlvm<'ctx> {
 
     /// Look up (and cache via the LLVM module) the `@llvm.trap` intrinsic
     /// declaration. Not overloaded, so no parameter type list is needed.
-    fn llvm_trap_intrinsic(&self) -> Result<FunctionValue<'ctx>> {
+    pub(super) fn llvm_trap_intrinsic(&self) -> Result<FunctionValue<'ctx>> {
         let intrinsic = Intrinsic::find("llvm.trap")
             .ok_or_else(|| Error::Internal("llvm.trap intrinsic not found".to_string()))?;
         intrinsic
@@ -1566,6 +1758,126 @@ impl<'ctx> WasmToLlvm<'ctx> {
 
     // ── Stack helpers ──
 
+    /// Debug-build check that a construct's reachable fall-through leaves
+    /// exactly its results above the depth recorded when its frame was pushed.
+    /// A mismatch is a frontend bug that would otherwise surface as a wrong
+    /// value at run time.
+    fn assert_frame_balance(&self, kind: &str, stack_depth: usize, results: usize) {
+        if cfg!(debug_assertions)
+            && let Err(msg) =
+                check_frame_balance(kind, self.operand_stack.len(), stack_depth, results)
+        {
+            let func = self
+                .current_fn
+                .map(|f| f.get_name().to_string_lossy().into_owned())
+                .unwrap_or_default();
+            panic!("{msg} in {func}");
+        }
+    }
+
+    /// Resolve a block type to its `(params, results)` counts. Type-index
+    /// block types take theirs from the referenced function type.
+    fn block_arity(&self, blockty: wasmparser::BlockType) -> Result<(usize, usize)> {
+        match blockty {
+            wasmparser::BlockType::Empty => Ok((0, 0)),
+            wasmparser::BlockType::Type(_) => Ok((0, 1)),
+            wasmparser::BlockType::FuncType(type_idx) => self
+                .type_signatures
+                .get(type_idx as usize)
+                .copied()
+                .ok_or_else(|| Error::Internal(format!("unknown type index {type_idx}"))),
+        }
+    }
+
+    /// Operand-stack height below a block's `params` topmost values.
+    fn params_base(&self, params: usize) -> Result<usize> {
+        self.operand_stack
+            .len()
+            .checked_sub(params)
+            .ok_or_else(|| Error::Internal("operand stack underflow".into()))
+    }
+
+    /// One phi per carried value at the top of `bb`, leaving the builder where
+    /// it was.
+    fn build_result_phis(
+        &self,
+        bb: BasicBlock<'ctx>,
+        count: usize,
+        name: &str,
+    ) -> Result<Vec<PhiValue<'ctx>>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let current_bb = self.builder.get_insert_block().unwrap();
+        self.builder.position_at_end(bb);
+        let phis = (0..count)
+            .map(|_| llvm_err(self.builder.build_phi(self.i64_type, name)))
+            .collect::<Result<Vec<_>>>();
+        self.builder.position_at_end(current_bb);
+        phis
+    }
+
+    /// Feed the top `phis.len()` operands (deepest first) into `phis` from the
+    /// current block. `consume` pops them (`br`, `end`); otherwise they stay
+    /// for the fall-through path (`br_if`, `br_table`).
+    fn add_result_incomings(&mut self, phis: &[PhiValue<'ctx>], consume: bool) -> Result<()> {
+        let base = self
+            .operand_stack
+            .len()
+            .checked_sub(phis.len())
+            .ok_or_else(|| Error::Internal("operand stack underflow".into()))?;
+        if !phis.is_empty() {
+            let current_bb = self.builder.get_insert_block().unwrap();
+            for (phi, val) in phis.iter().zip(&self.operand_stack[base..]) {
+                phi.add_incoming(&[(val, current_bb)]);
+            }
+        }
+        if consume {
+            self.operand_stack.truncate(base);
+        }
+        Ok(())
+    }
+
+    fn push_phi_results(&mut self, phis: &[PhiValue<'ctx>]) {
+        for phi in phis {
+            self.push(phi.as_basic_value().into_int_value());
+        }
+    }
+
+    /// Push the `count` results of a call that just returned: the first is
+    /// the call's value (r7), the rest are read back from the param-overflow
+    /// area, where the callee stored them before returning.
+    fn push_call_results(
+        &mut self,
+        call: inkwell::values::CallSiteValue<'ctx>,
+        count: usize,
+        void_msg: &str,
+    ) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+        let first = call
+            .try_as_basic_value()
+            .basic()
+            .ok_or_else(|| Error::Internal(void_msg.into()))?
+            .into_int_value();
+        self.push(first);
+        for i in 1..count {
+            let index = self.i64_type.const_int(i as u64 - 1, false);
+            let val = llvm_err(self.builder.build_call(
+                self.pvm_intrinsics.result_get,
+                &[index.into()],
+                "call_result",
+            ))?
+            .try_as_basic_value()
+            .basic()
+            .ok_or_else(|| Error::Internal("result_get returned void".into()))?
+            .into_int_value();
+            self.push(val);
+        }
+        Ok(())
+    }
+
     fn push(&mut self, val: IntValue<'ctx>) {
         self.operand_stack.push(val);
     }
@@ -1585,14 +1897,40 @@ impl<'ctx> WasmToLlvm<'ctx> {
 
     // ── Memory operation helpers ──
 
-    fn emit_load(&mut self, intrinsic: FunctionValue<'ctx>, offset: u64) -> Result<()> {
+    /// Effective address of a memory access: the popped base plus the static offset.
+    fn effective_address(
+        &self,
+        addr: IntValue<'ctx>,
+        offset: u64,
+        name: &str,
+    ) -> Result<IntValue<'ctx>> {
+        if offset == 0 {
+            return Ok(addr);
+        }
+        let offset_val = self.i64_type.const_int(offset, false);
+        llvm_err(self.builder.build_int_add(addr, offset_val, name))
+    }
+
+    /// Whether an access should be split into single-byte accesses: the
+    /// option is on and the alignment hint is below the natural alignment.
+    fn split_access(&self, memarg: &MemArg) -> bool {
+        self.split_misaligned_accesses && memarg.align < memarg.max_align
+    }
+
+    fn emit_load(&mut self, intrinsic: FunctionValue<'ctx>, memarg: &MemArg) -> Result<()> {
         let addr = self.pop()?;
-        let eff_addr = if offset == 0 {
-            addr
-        } else {
-            let offset_val = self.i64_type.const_int(offset, false);
-            llvm_err(self.builder.build_int_add(addr, offset_val, "load_addr"))?
-        };
+        let eff_addr = self.effective_address(addr, memarg.offset, "load_addr")?;
+        if self.split_access(memarg) {
+            let signed = [
+                self.pvm_intrinsics.load_i16s,
+                self.pvm_intrinsics.load_i16s_64,
+                self.pvm_intrinsics.load_i32s_64,
+            ]
+            .contains(&intrinsic);
+            let val = self.emit_byte_load(eff_addr, 1 << memarg.max_align, signed)?;
+            self.push(val);
+            return Ok(());
+        }
         let result = llvm_err(
             self.builder
                 .build_call(intrinsic, &[eff_addr.into()], "load"),
@@ -1606,15 +1944,56 @@ impl<'ctx> WasmToLlvm<'ctx> {
         Ok(())
     }
 
-    fn emit_store(&mut self, intrinsic: FunctionValue<'ctx>, offset: u64) -> Result<()> {
+    /// Assemble a little-endian `width`-byte value from `LoadIndU8`s,
+    /// sign-extending it to 64 bits when `signed`, as the wide load would.
+    fn emit_byte_load(
+        &self,
+        addr: IntValue<'ctx>,
+        width: u64,
+        signed: bool,
+    ) -> Result<IntValue<'ctx>> {
+        let mut acc = self.i64_type.const_zero();
+        for i in 0..width {
+            let byte_addr = self.effective_address(addr, i, "byte_addr")?;
+            let byte = llvm_err(self.builder.build_call(
+                self.pvm_intrinsics.load_i8u,
+                &[byte_addr.into()],
+                "byte",
+            ))?
+            .try_as_basic_value()
+            .basic()
+            .ok_or_else(|| Error::Internal("load intrinsic returned void".into()))?
+            .into_int_value();
+            let shift = self.i64_type.const_int(8 * i, false);
+            let byte = llvm_err(self.builder.build_left_shift(byte, shift, "byte_shl"))?;
+            acc = llvm_err(self.builder.build_or(acc, byte, "bytes"))?;
+        }
+        if signed && width < 8 {
+            let shift = self.i64_type.const_int(64 - 8 * width, false);
+            let high = llvm_err(self.builder.build_left_shift(acc, shift, "sext_shl"))?;
+            acc = llvm_err(self.builder.build_right_shift(high, shift, true, "sext"))?;
+        }
+        Ok(acc)
+    }
+
+    fn emit_store(&mut self, intrinsic: FunctionValue<'ctx>, memarg: &MemArg) -> Result<()> {
         let val = self.pop()?;
         let addr = self.pop()?;
-        let eff_addr = if offset == 0 {
-            addr
-        } else {
-            let offset_val = self.i64_type.const_int(offset, false);
-            llvm_err(self.builder.build_int_add(addr, offset_val, "store_addr"))?
-        };
+        let eff_addr = self.effective_address(addr, memarg.offset, "store_addr")?;
+        if self.split_access(memarg) {
+            // Little-endian: byte `i` is `val >> 8i`; `StoreIndU8` keeps the low 8 bits.
+            for i in 0..1u64 << memarg.max_align {
+                let byte_addr = self.effective_address(eff_addr, i, "byte_addr")?;
+                let shift = self.i64_type.const_int(8 * i, false);
+                let byte = llvm_err(self.builder.build_right_shift(val, shift, false, "byte"))?;
+                llvm_err(self.builder.build_call(
+                    self.pvm_intrinsics.store_i8,
+                    &[byte_addr.into(), byte.into()],
+                    "store",
+                ))?;
+            }
+            return Ok(());
+        }
         llvm_err(
             self.builder
                 .build_call(intrinsic, &[eff_addr.into(), val.into()], "store"),
@@ -1662,6 +2041,22 @@ impl<'ctx> WasmToLlvm<'ctx> {
 
     // ── Comparison helpers ──
 
+    /// If `val` is `zext i1 %b` (the shape every comparison helper pushes),
+    /// return `%b`. `zext(b) != 0` is exactly `b`, so branches can use it
+    /// directly instead of re-testing the widened value against zero.
+    fn peel_bool_zext(val: IntValue<'ctx>) -> Option<IntValue<'ctx>> {
+        let instr = val.as_instruction()?;
+        if instr.get_opcode() != inkwell::values::InstructionOpcode::ZExt {
+            return None;
+        }
+        match instr.get_operand(0)?.value()? {
+            inkwell::values::BasicValueEnum::IntValue(b) if b.get_type().get_bit_width() == 1 => {
+                Some(b)
+            }
+            _ => None,
+        }
+    }
+
     fn i32_cmp(&mut self, pred: IntPredicate) -> Result<()> {
         let rhs = self.pop()?;
         let lhs = self.pop()?;
@@ -1898,7 +2293,9 @@ impl<'ctx> WasmToLlvm<'ctx> {
         &self,
         run_inlining: bool,
         inline_threshold: Option<u32>,
+        unroll_max_trip_count: Option<u32>,
         run_mergefunc: bool,
+        run_tailcallelim: bool,
     ) -> Result<()> {
         use inkwell::passes::PassBuilderOptions;
         use inkwell::targets::{InitializationConfig, Target, TargetMachine};
@@ -1938,6 +2335,24 @@ impl<'ctx> WasmToLlvm<'ctx> {
             )
             .map_err(|e| Error::Internal(format!("LLVM pre-inline passes failed: {e}")))?;
 
+        // Phase 1b (optional): Turn self-recursive calls in tail position
+        // (`%r = call @self(...); ret %r`) into a branch back to the entry,
+        // with the arguments flowing in through header phis. The backend then
+        // lowers a loop instead of pushing a frame per call, so deep
+        // recursion no longer hits the stack-overflow check in the prologue.
+        // `tailcallelim` also introduces an accumulator for associative
+        // operations applied to the result (`n + sum(n - 1)`).
+        //
+        // Runs before inlining so the recursive function, now a loop, is
+        // judged by its real size, and needs the Phase 1 `simplifycfg` to
+        // have merged the call and the `ret` into one block.
+        if run_tailcallelim {
+            let opts = PassBuilderOptions::create();
+            self.module
+                .run_passes("tailcallelim", &machine, opts)
+                .map_err(|e| Error::Internal(format!("LLVM tailcallelim pass failed: {e}")))?;
+        }
+
         // Phase 2 (optional): Inline small functions at the CGSCC level.
         // This must run as a separate pass invocation because `inline` is a CGSCC pass
         // and cannot be mixed with function passes in the new pass manager pipeline.
@@ -1997,6 +2412,32 @@ impl<'ctx> WasmToLlvm<'ctx> {
             )
             .map_err(|e| Error::Internal(format!("LLVM optimization passes failed: {e}")))?;
 
+        // Phase 3b (optional): Fully unroll loops with a small constant trip count.
+        //
+        // `loop-rotate` turns top-tested `block { loop { br_if exit; ... } }`
+        // shapes into bottom-tested ones so SCEV can compute the trip count.
+        // `full-unroll-max` bounds the trip count; partial, runtime, upper-bound
+        // and peeling variants are disabled because each keeps a back edge or
+        // duplicates code without removing one. LLVM's O2 full-unroll size
+        // threshold still applies on top, which guards against blowup from big
+        // bodies. The unrolled copies are then cleaned up the same way as Phase 3.
+        // Must run before `mergefunc`, which needs the final bodies.
+        if let Some(max_trip_count) = unroll_max_trip_count {
+            let opts = PassBuilderOptions::create();
+            self.module
+                .run_passes(
+                    &format!(
+                        "function(loop(loop-rotate),\
+                         loop-unroll<O2;full-unroll-max={max_trip_count};no-partial;no-runtime;\
+                         no-upperbound;no-peeling;no-profile-peeling>,\
+                         instcombine<max-iterations=20>,simplifycfg,dce)"
+                    ),
+                    &machine,
+                    opts,
+                )
+                .map_err(|e| Error::Internal(format!("LLVM loop unrolling failed: {e}")))?;
+        }
+
         // Phase 4 (optional): Merge byte-identical function bodies.
         //
         // `mergefunc` is a module-level pass that does two things:
@@ -2031,3 +2472,32 @@ impl<'ctx> WasmToLlvm<'ctx> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::check_frame_balance;
+
+    #[test]
+    fn balanced_frames_pass() {
+        assert!(check_frame_balance("block", 3, 3, 0).is_ok());
+        assert!(check_frame_balance("if", 4, 3, 1).is_ok());
+    }
+
+    #[test]
+    fn leftover_operand_is_reported() {
+        // `(block (i32.const 1))` without a result type leaves one value behind.
+        let msg = check_frame_balance("block", 2, 1, 0).unwrap_err();
+        assert_eq!(
+            msg,
+            "operand stack imbalance at end of block: depth 2, expected 1 \
+             (1 on entry + 0 result(s))"
+        );
+    }
+
+    #[test]
+    fn missing_result_is_reported() {
+        // A loop with a result whose value was dropped by codegen.
+        let msg = check_frame_balance("loop", 0, 0, 1).unwrap_err();
+        assert!(msg.contains("end of loop: depth 0, expected 1"), "{msg}");
+    }
+}
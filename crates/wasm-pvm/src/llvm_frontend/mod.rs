@@ -2,6 +2,7 @@
 
 mod function_builder;
 mod libcall_recognition;
+mod softfloat;
 
 pub use function_builder::WasmToLlvm;
 pub use libcall_recognition::LibcallKind;
@@ -17,8 +18,12 @@ use crate::translate::wasm_module::WasmModule;
 /// Creates an LLVM context-scoped module with all functions and globals,
 /// then optionally runs LLVM optimization passes in three phases:
 /// - Phase 1 (pre-inline cleanup): `mem2reg`, `instcombine`, `simplifycfg`
+/// - Phase 1b (optional, `run_tailcallelim`): `tailcallelim`, turning
+///   self-recursive tail calls into loops
 /// - Phase 2 (inlining, controlled by `run_inlining`): `cgscc(inline)` with default threshold 225
 /// - Phase 3 (post-inline cleanup): `instcombine<max-iterations=2>`, `simplifycfg`, `gvn`, `dce`
+/// - Phase 3b (optional, `unroll_max_trip_count`): full unrolling of loops with a small
+///   constant trip count
 ///
 /// `run_llvm_passes` gates the entire optimization pipeline (all three phases).
 /// `run_inlining` enables/disables Phase 2 independently (requires `run_llvm_passes = true`).
@@ -29,16 +34,29 @@ pub fn translate_wasm_to_llvm<'ctx>(
     run_llvm_passes: bool,
     run_inlining: bool,
     inline_threshold: Option<u32>,
+    unroll_max_trip_count: Option<u32>,
     trap_floats: bool,
+    split_misaligned_accesses: bool,
+    soft_float: bool,
     libcall_recognition: bool,
     run_mergefunc: bool,
+    run_tailcallelim: bool,
 ) -> Result<Module<'ctx>> {
-    let translator = WasmToLlvm::new(context, "wasm_module", trap_floats, libcall_recognition);
+    let translator = WasmToLlvm::new(
+        context,
+        "wasm_module",
+        trap_floats,
+        split_misaligned_accesses,
+        soft_float,
+        libcall_recognition,
+    );
     translator.translate_module(
         wasm_module,
         run_llvm_passes,
         run_inlining,
         inline_threshold,
+        unroll_max_trip_count,
         run_mergefunc,
+        run_tailcallelim,
     )
 }
@@ -0,0 +1,960 @@
+//! Software floating-point lowering.
+//!
+//! PVM has no floating-point unit. Float values travel through the i64-uniform
+//! operand stack as their IEEE 754 bit patterns — f64 as all 64 bits, f32 in
+//! the low 32 bits (zero-extended, like an i32) — and the operators lowered
+//! here manipulate those bits with plain integer IR.
+//!
+//! Covered so far:
+//!
+//! - `f{32,64}.convert_i{32,64}_{s,u}`: integer → float, rounded to nearest
+//!   with ties to even.
+//! - `i{32,64}.trunc_f{32,64}_{s,u}`: float → integer, truncating toward zero
+//!   and trapping (`@llvm.trap`) on NaN, ±∞ and out-of-range inputs.
+//! - `i{32,64}.trunc_sat_f{32,64}_{s,u}`: the same, but NaN converts to 0 and
+//!   out-of-range inputs (±∞ included) saturate to the target's min/max.
+//! - `{i32,f32,i64,f64}.reinterpret_*`: bit-preserving, so (nearly) free.
+//!
+//! With `--soft-float` (`CompileOptions::enable_soft_float`) also:
+//!
+//! - `f{32,64}.{const,load,store}`: the integer operations on the bit pattern.
+//! - `f{32,64}.{neg,abs,copysign}`: sign-bit manipulation.
+//! - `f{32,64}.{add,sub,mul,div}`, `f64.promote_f32`, `f32.demote_f64`:
+//!   correctly rounded (nearest, ties to even), with subnormals.
+//! - `f{32,64}.{eq,ne,lt,gt,le,ge}`.
+//!
+//! Every other float operator (`min`/`max`, `sqrt`, `ceil`/`floor`/`trunc`/
+//! `nearest`) is still rejected, or trapped under
+//! `--trap-floats` (see `docs/src/trap-floats.md`).
+//!
+//! The conversions are not behind the flag on purpose. Each one is a single
+//! routine whose result is exact, correctly rounded or a trap, so enabling it
+//! can only turn a compile error into the value WASM specifies. Integer code
+//! also picks up lone conversions (e.g. a `u32` scaled through `f64`) without
+//! doing any float math. The arithmetic inlines a rounding routine at every
+//! operator and lets float-heavy modules through that used to be rejected,
+//! which is why it stays opt-in.
+
+use inkwell::IntPredicate;
+use inkwell::intrinsics::Intrinsic;
+use inkwell::values::{FunctionValue, IntValue};
+use wasmparser::Operator;
+
+use super::function_builder::WasmToLlvm;
+use crate::{Error, Result};
+
+fn llvm_err<T>(r: std::result::Result<T, inkwell::builder::BuilderError>) -> Result<T> {
+    r.map_err(|e| Error::Internal(format!("LLVM builder error: {e:?}")))
+}
+
+/// IEEE 754 binary format of a float operand or result.
+#[derive(Debug, Clone, Copy)]
+pub enum FloatFormat {
+    F32,
+    F64,
+}
+
+impl FloatFormat {
+    const fn width(self) -> u32 {
+        match self {
+            Self::F32 => 32,
+            Self::F64 => 64,
+        }
+    }
+
+    /// Explicit mantissa bits (without the implicit leading one).
+    const fn mantissa_bits(self) -> u32 {
+        match self {
+            Self::F32 => 23,
+            Self::F64 => 52,
+        }
+    }
+
+    const fn exponent_bias(self) -> u64 {
+        match self {
+            Self::F32 => 127,
+            Self::F64 => 1023,
+        }
+    }
+
+    const fn sign_bit(self) -> u64 {
+        1 << (self.width() - 1)
+    }
+
+    const fn abs_mask(self) -> u64 {
+        self.sign_bit() - 1
+    }
+
+    /// Biased exponent of infinities and NaNs (all ones).
+    const fn max_exponent(self) -> u64 {
+        (1 << (self.width() - 1 - self.mantissa_bits())) - 1
+    }
+
+    const fn infinity(self) -> u64 {
+        self.max_exponent() << self.mantissa_bits()
+    }
+
+    const fn canonical_nan(self) -> u64 {
+        self.infinity() | 1 << (self.mantissa_bits() - 1)
+    }
+}
+
+/// Integer side of a conversion: width and signedness.
+#[derive(Debug, Clone, Copy)]
+pub enum IntKind {
+    I32S,
+    I32U,
+    I64S,
+    I64U,
+}
+
+impl IntKind {
+    const fn bits(self) -> u32 {
+        match self {
+            Self::I32S | Self::I32U => 32,
+            Self::I64S | Self::I64U => 64,
+        }
+    }
+
+    const fn signed(self) -> bool {
+        matches!(self, Self::I32S | Self::I64S)
+    }
+}
+
+/// A float operator lowered by this module.
+#[derive(Debug, Clone, Copy)]
+pub enum Conversion {
+    /// Integer → float (`convert`).
+    Convert { from: IntKind, to: FloatFormat },
+    /// Float → integer, trapping (`trunc`).
+    Trunc { from: FloatFormat, to: IntKind },
+    /// Float → integer, saturating (`trunc_sat`).
+    TruncSat { from: FloatFormat, to: IntKind },
+    /// Bit-preserving reinterpret of a value `bits` wide.
+    Reinterpret { bits: u32 },
+}
+
+/// The softfloat lowering for `op`, or `None` if `op` isn't one we handle.
+pub fn conversion_for(op: &Operator) -> Option<Conversion> {
+    use FloatFormat::{F32, F64};
+    use IntKind::{I32S, I32U, I64S, I64U};
+
+    let convert = |from, to| Some(Conversion::Convert { from, to });
+    let trunc = |from, to| Some(Conversion::Trunc { from, to });
+    let trunc_sat = |from, to| Some(Conversion::TruncSat { from, to });
+    match op {
+        Operator::F32ConvertI32S => convert(I32S, F32),
+        Operator::F32ConvertI32U => convert(I32U, F32),
+        Operator::F32ConvertI64S => convert(I64S, F32),
+        Operator::F32ConvertI64U => convert(I64U, F32),
+        Operator::F64ConvertI32S => convert(I32S, F64),
+        Operator::F64ConvertI32U => convert(I32U, F64),
+        Operator::F64ConvertI64S => convert(I64S, F64),
+        Operator::F64ConvertI64U => convert(I64U, F64),
+        Operator::I32TruncF32S => trunc(F32, I32S),
+        Operator::I32TruncF32U => trunc(F32, I32U),
+        Operator::I32TruncF64S => trunc(F64, I32S),
+        Operator::I32TruncF64U => trunc(F64, I32U),
+        Operator::I64TruncF32S => trunc(F32, I64S),
+        Operator::I64TruncF32U => trunc(F32, I64U),
+        Operator::I64TruncF64S => trunc(F64, I64S),
+        Operator::I64TruncF64U => trunc(F64, I64U),
+        Operator::I32TruncSatF32S => trunc_sat(F32, I32S),
+        Operator::I32TruncSatF32U => trunc_sat(F32, I32U),
+        Operator::I32TruncSatF64S => trunc_sat(F64, I32S),
+        Operator::I32TruncSatF64U => trunc_sat(F64, I32U),
+        Operator::I64TruncSatF32S => trunc_sat(F32, I64S),
+        Operator::I64TruncSatF32U => trunc_sat(F32, I64U),
+        Operator::I64TruncSatF64S => trunc_sat(F64, I64S),
+        Operator::I64TruncSatF64U => trunc_sat(F64, I64U),
+        Operator::I32ReinterpretF32 | Operator::F32ReinterpretI32 => {
+            Some(Conversion::Reinterpret { bits: 32 })
+        }
+        Operator::I64ReinterpretF64 | Operator::F64ReinterpretI64 => {
+            Some(Conversion::Reinterpret { bits: 64 })
+        }
+        _ => None,
+    }
+}
+
+/// Emit IR for `conversion` applied to `value`, returning the result value.
+pub fn emit_conversion<'ctx>(
+    translator: &WasmToLlvm<'ctx>,
+    conversion: Conversion,
+    value: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    match conversion {
+        Conversion::Convert { from, to } => emit_convert(translator, value, from, to),
+        Conversion::Trunc { from, to } => emit_trunc(translator, value, from, to, false),
+        Conversion::TruncSat { from, to } => emit_trunc(translator, value, from, to, true),
+        Conversion::Reinterpret { bits: 64 } => Ok(value),
+        Conversion::Reinterpret { .. } => low_32_bits(translator, value),
+    }
+}
+
+fn low_32_bits<'ctx>(
+    translator: &WasmToLlvm<'ctx>,
+    value: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let mask = translator.i64_type().const_int(0xFFFF_FFFF, false);
+    llvm_err(translator.builder().build_and(value, mask, "sf_lo32"))
+}
+
+fn ctlz<'ctx>(translator: &WasmToLlvm<'ctx>, value: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+    let i64_type = translator.i64_type();
+    let intrinsic = Intrinsic::find("llvm.ctlz")
+        .ok_or_else(|| Error::Internal("llvm.ctlz intrinsic not found".into()))?;
+    let fn_val: FunctionValue<'ctx> = intrinsic
+        .get_declaration(translator.module(), &[i64_type.into()])
+        .ok_or_else(|| Error::Internal("llvm.ctlz declaration failed".into()))?;
+    // is_zero_poison = false: ctlz(0) = 64.
+    let zero_defined = translator.context().bool_type().const_zero();
+    llvm_err(translator.builder().build_call(
+        fn_val,
+        &[value.into(), zero_defined.into()],
+        "sf_lz",
+    ))?
+    .try_as_basic_value()
+    .basic()
+    .map(inkwell::values::BasicValueEnum::into_int_value)
+    .ok_or_else(|| Error::Internal("llvm.ctlz returned void".into()))
+}
+
+/// Integer → float, round to nearest, ties to even.
+///
+/// Split the input into sign and 64-bit magnitude, normalize the magnitude so
+/// its leading one sits in bit 63, and keep the top `mantissa + 1` bits. The
+/// dropped bits decide rounding; the round-up increment is added to the
+/// assembled `exponent | mantissa` word so a mantissa carry ripples into the
+/// exponent on its own. No integer is large enough to overflow even f32.
+fn emit_convert<'ctx>(
+    translator: &WasmToLlvm<'ctx>,
+    value: IntValue<'ctx>,
+    from: IntKind,
+    to: FloatFormat,
+) -> Result<IntValue<'ctx>> {
+    let b = translator.builder();
+    let i64_type = translator.i64_type();
+    let c = |v: u64| i64_type.const_int(v, false);
+
+    let wide = match from {
+        IntKind::I32S => {
+            let narrow = llvm_err(b.build_int_truncate(value, translator.i32_type(), "sf_i32"))?;
+            llvm_err(b.build_int_s_extend(narrow, i64_type, "sf_sext"))?
+        }
+        IntKind::I32U => low_32_bits(translator, value)?,
+        IntKind::I64S | IntKind::I64U => value,
+    };
+    let (negative, magnitude) = if from.signed() {
+        let negative = llvm_err(b.build_int_compare(IntPredicate::SLT, wide, c(0), "sf_neg"))?;
+        // |i64::MIN| wraps to 0x8000_0000_0000_0000, which is the right
+        // unsigned magnitude.
+        let negated = llvm_err(b.build_int_sub(c(0), wide, "sf_negated"))?;
+        let magnitude = llvm_err(b.build_select(negative, negated, wide, "sf_mag"))?;
+        (Some(negative), magnitude.into_int_value())
+    } else {
+        (None, wide)
+    };
+
+    let mantissa_bits = to.mantissa_bits();
+    let dropped = 63 - mantissa_bits;
+
+    let lz = ctlz(translator, magnitude)?;
+    let lz_masked = llvm_err(b.build_and(lz, c(63), "sf_lz_m"))?;
+    let normalized = llvm_err(b.build_left_shift(magnitude, lz_masked, "sf_norm"))?;
+    let mantissa =
+        llvm_err(b.build_right_shift(normalized, c(u64::from(dropped)), false, "sf_mant"))?;
+    let rest = llvm_err(b.build_and(normalized, c((1 << dropped) - 1), "sf_rest"))?;
+
+    let half = c(1 << (dropped - 1));
+    let above_half = llvm_err(b.build_int_compare(IntPredicate::UGT, rest, half, "sf_above"))?;
+    let tie = llvm_err(b.build_int_compare(IntPredicate::EQ, rest, half, "sf_tie"))?;
+    let lsb = llvm_err(b.build_and(mantissa, c(1), "sf_lsb"))?;
+    let odd = llvm_err(b.build_int_compare(IntPredicate::NE, lsb, c(0), "sf_odd"))?;
+    let tie_to_even = llvm_err(b.build_and(tie, odd, "sf_tie_odd"))?;
+    let round_up = llvm_err(b.build_or(above_half, tie_to_even, "sf_round"))?;
+    let round_up = llvm_err(b.build_int_z_extend(round_up, i64_type, "sf_round64"))?;
+
+    // Biased exponent of the leading one: (63 - lz) + bias.
+    let exponent = llvm_err(b.build_int_sub(c(63 + to.exponent_bias()), lz, "sf_exp"))?;
+    let exponent =
+        llvm_err(b.build_left_shift(exponent, c(u64::from(mantissa_bits)), "sf_exp_sh"))?;
+    let fraction = llvm_err(b.build_and(mantissa, c((1 << mantissa_bits) - 1), "sf_frac"))?;
+    let bits = llvm_err(b.build_or(exponent, fraction, "sf_bits"))?;
+    let mut bits = llvm_err(b.build_int_add(bits, round_up, "sf_rounded"))?;
+
+    if let Some(negative) = negative {
+        let sign = llvm_err(b.build_int_z_extend(negative, i64_type, "sf_sign"))?;
+        let sign = llvm_err(b.build_left_shift(sign, c(u64::from(to.width() - 1)), "sf_sign_sh"))?;
+        bits = llvm_err(b.build_or(bits, sign, "sf_signed"))?;
+    }
+
+    let is_zero = llvm_err(b.build_int_compare(IntPredicate::EQ, magnitude, c(0), "sf_zero"))?;
+    Ok(llvm_err(b.build_select(is_zero, c(0), bits, "sf_convert"))?.into_int_value())
+}
+
+/// Float → integer, truncating toward zero. Traps on NaN, infinities and
+/// values whose truncation doesn't fit `to`, or with `saturating` maps NaN to
+/// 0 and clamps the rest to `to`'s minimum or maximum by sign.
+///
+/// `|x| < 1` truncates to 0 (including -0.x for unsigned targets). Otherwise
+/// the unbiased exponent must be ≤ 63 — NaN and ±∞ have the maximum exponent
+/// and fail this too — and the exact magnitude `mantissa · 2^(e - M)` is
+/// range-checked against the target before the sign is applied.
+fn emit_trunc<'ctx>(
+    translator: &WasmToLlvm<'ctx>,
+    value: IntValue<'ctx>,
+    from: FloatFormat,
+    to: IntKind,
+    saturating: bool,
+) -> Result<IntValue<'ctx>> {
+    let b = translator.builder();
+    let i64_type = translator.i64_type();
+    let c = |v: u64| i64_type.const_int(v, false);
+
+    let bits = match from {
+        FloatFormat::F32 => low_32_bits(translator, value)?,
+        FloatFormat::F64 => value,
+    };
+    let mantissa_bits = u64::from(from.mantissa_bits());
+    let exponent_mask = (1u64 << (from.width() - 1 - from.mantissa_bits())) - 1;
+
+    let exponent = llvm_err(b.build_right_shift(bits, c(mantissa_bits), false, "sf_exp"))?;
+    let exponent = llvm_err(b.build_and(exponent, c(exponent_mask), "sf_exp_m"))?;
+    let fraction = llvm_err(b.build_and(bits, c((1 << mantissa_bits) - 1), "sf_frac"))?;
+    let sign =
+        llvm_err(b.build_right_shift(bits, c(u64::from(from.width() - 1)), false, "sf_sign"))?;
+    let negative = llvm_err(b.build_int_compare(IntPredicate::NE, sign, c(0), "sf_neg"))?;
+
+    let below_one = llvm_err(b.build_int_compare(
+        IntPredicate::ULT,
+        exponent,
+        c(from.exponent_bias()),
+        "sf_lt1",
+    ))?;
+    let e = llvm_err(b.build_int_sub(exponent, c(from.exponent_bias()), "sf_e"))?;
+    let too_big = llvm_err(b.build_int_compare(IntPredicate::UGT, e, c(63), "sf_huge"))?;
+
+    // Exact magnitude of the integer part. Shift amounts are masked so the
+    // unused arm of the select never shifts by ≥ 64.
+    let mantissa = llvm_err(b.build_or(fraction, c(1 << mantissa_bits), "sf_mant"))?;
+    let left_amount = llvm_err(b.build_int_sub(e, c(mantissa_bits), "sf_lamt"))?;
+    let left_amount = llvm_err(b.build_and(left_amount, c(63), "sf_lamt_m"))?;
+    let right_amount = llvm_err(b.build_int_sub(c(mantissa_bits), e, "sf_ramt"))?;
+    let right_amount = llvm_err(b.build_and(right_amount, c(63), "sf_ramt_m"))?;
+    let shifted_left = llvm_err(b.build_left_shift(mantissa, left_amount, "sf_shl"))?;
+    let shifted_right = llvm_err(b.build_right_shift(mantissa, right_amount, false, "sf_shr"))?;
+    let scale_up = llvm_err(b.build_int_compare(IntPredicate::UGE, e, c(mantissa_bits), "sf_up"))?;
+    let magnitude =
+        llvm_err(b.build_select(scale_up, shifted_left, shifted_right, "sf_mag"))?.into_int_value();
+
+    let out_of_range = if to.signed() {
+        // Negative values may reach 2^(N-1), positive ones 2^(N-1) - 1.
+        let limit = llvm_err(b.build_int_add(
+            c((1 << (to.bits() - 1)) - 1),
+            llvm_err(b.build_int_z_extend(negative, i64_type, "sf_neg64"))?,
+            "sf_limit",
+        ))?;
+        llvm_err(b.build_int_compare(IntPredicate::UGT, magnitude, limit, "sf_oor"))?
+    } else {
+        // Any negative value with |x| ≥ 1 is out of range.
+        let limit = c(u64::MAX >> (64 - to.bits()));
+        let too_large =
+            llvm_err(b.build_int_compare(IntPredicate::UGT, magnitude, limit, "sf_big"))?;
+        llvm_err(b.build_or(negative, too_large, "sf_oor"))?
+    };
+    let invalid = llvm_err(b.build_or(too_big, out_of_range, "sf_invalid"))?;
+    let not_below_one = llvm_err(b.build_not(below_one, "sf_ge1"))?;
+    // Out of range for `trunc`: trap, or saturate.
+    let trap = llvm_err(b.build_and(not_below_one, invalid, "sf_trap"))?;
+
+    let truncated = || -> Result<IntValue<'ctx>> {
+        let negated = llvm_err(b.build_int_sub(c(0), magnitude, "sf_negated"))?;
+        let signed =
+            llvm_err(b.build_select(negative, negated, magnitude, "sf_signed"))?.into_int_value();
+        Ok(llvm_err(b.build_select(below_one, c(0), signed, "sf_trunc"))?.into_int_value())
+    };
+
+    let result = if saturating {
+        // Only NaN has the maximum exponent and a non-zero fraction.
+        let max_exponent = llvm_err(b.build_int_compare(
+            IntPredicate::EQ,
+            exponent,
+            c(from.max_exponent()),
+            "sf_exp_max",
+        ))?;
+        let has_fraction =
+            llvm_err(b.build_int_compare(IntPredicate::NE, fraction, c(0), "sf_has_frac"))?;
+        let nan = llvm_err(b.build_and(max_exponent, has_fraction, "sf_nan"))?;
+        let (min, max) = if to.signed() {
+            let min = u64::MAX << (to.bits() - 1);
+            (min, !min)
+        } else {
+            (0, u64::MAX >> (64 - to.bits()))
+        };
+        let clamped =
+            llvm_err(b.build_select(negative, c(min), c(max), "sf_clamp"))?.into_int_value();
+        let saturated = llvm_err(b.build_select(nan, c(0), clamped, "sf_sat"))?.into_int_value();
+        llvm_err(b.build_select(trap, saturated, truncated()?, "sf_trunc_sat"))?.into_int_value()
+    } else {
+        let func = b
+            .get_insert_block()
+            .and_then(inkwell::basic_block::BasicBlock::get_parent)
+            .ok_or_else(|| Error::Internal("softfloat trunc outside a function".into()))?;
+        let context = translator.context();
+        let trap_bb = context.append_basic_block(func, "sf_trunc_trap");
+        let ok_bb = context.append_basic_block(func, "sf_trunc_ok");
+        llvm_err(b.build_conditional_branch(trap, trap_bb, ok_bb))?;
+
+        b.position_at_end(trap_bb);
+        llvm_err(b.build_call(translator.llvm_trap_intrinsic()?, &[], "sf_trap"))?;
+        llvm_err(b.build_unreachable())?;
+
+        b.position_at_end(ok_bb);
+        truncated()?
+    };
+    if to.bits() == 32 {
+        low_32_bits(translator, result)
+    } else {
+        Ok(result)
+    }
+}
+
+// ── Arithmetic, comparisons and promote/demote (`--soft-float`) ──
+//
+// Finite non-zero operands are unpacked into a sign, a signed exponent `e`
+// and a 64-bit significand `sig` whose leading one sits in bit 62, so that
+// the value is `sig · 2^(e - bias - 62)` for normals and subnormals alike.
+// The spare low bits act as guard/round bits and a result's bit 0 doubles as
+// the sticky bit. Each operator computes the finite case branch-free and then
+// selects over it for NaN, ±∞ and ±0 operands. NaN results are always the
+// positive canonical NaN.
+
+/// Float comparison predicate (`f{32,64}.{eq,ne,lt,gt,le,ge}`).
+#[derive(Debug, Clone, Copy)]
+pub enum FloatCmp {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+/// A float operator lowered only when soft-float is enabled.
+#[derive(Debug, Clone, Copy)]
+pub enum FloatOp {
+    Neg(FloatFormat),
+    Abs(FloatFormat),
+    Copysign(FloatFormat),
+    Add(FloatFormat),
+    Sub(FloatFormat),
+    Mul(FloatFormat),
+    Div(FloatFormat),
+    Compare(FloatFormat, FloatCmp),
+    /// `f64.promote_f32`.
+    Promote,
+    /// `f32.demote_f64`.
+    Demote,
+}
+
+impl FloatOp {
+    /// Number of operands popped from the operand stack.
+    pub const fn arity(self) -> usize {
+        match self {
+            Self::Neg(_) | Self::Abs(_) | Self::Promote | Self::Demote => 1,
+            Self::Copysign(_)
+            | Self::Add(_)
+            | Self::Sub(_)
+            | Self::Mul(_)
+            | Self::Div(_)
+            | Self::Compare(..) => 2,
+        }
+    }
+}
+
+/// The soft-float lowering for `op`, or `None` if `op` isn't one we handle.
+/// Constants, loads and stores need no lowering beyond their integer
+/// counterparts and are not listed here.
+pub fn float_op_for(op: &Operator) -> Option<FloatOp> {
+    use FloatCmp::{Eq, Ge, Gt, Le, Lt, Ne};
+    use FloatFormat::{F32, F64};
+
+    Some(match op {
+        Operator::F32Neg => FloatOp::Neg(F32),
+        Operator::F64Neg => FloatOp::Neg(F64),
+        Operator::F32Abs => FloatOp::Abs(F32),
+        Operator::F64Abs => FloatOp::Abs(F64),
+        Operator::F32Copysign => FloatOp::Copysign(F32),
+        Operator::F64Copysign => FloatOp::Copysign(F64),
+        Operator::F32Add => FloatOp::Add(F32),
+        Operator::F64Add => FloatOp::Add(F64),
+        Operator::F32Sub => FloatOp::Sub(F32),
+        Operator::F64Sub => FloatOp::Sub(F64),
+        Operator::F32Mul => FloatOp::Mul(F32),
+        Operator::F64Mul => FloatOp::Mul(F64),
+        Operator::F32Div => FloatOp::Div(F32),
+        Operator::F64Div => FloatOp::Div(F64),
+        Operator::F32Eq => FloatOp::Compare(F32, Eq),
+        Operator::F32Ne => FloatOp::Compare(F32, Ne),
+        Operator::F32Lt => FloatOp::Compare(F32, Lt),
+        Operator::F32Gt => FloatOp::Compare(F32, Gt),
+        Operator::F32Le => FloatOp::Compare(F32, Le),
+        Operator::F32Ge => FloatOp::Compare(F32, Ge),
+        Operator::F64Eq => FloatOp::Compare(F64, Eq),
+        Operator::F64Ne => FloatOp::Compare(F64, Ne),
+        Operator::F64Lt => FloatOp::Compare(F64, Lt),
+        Operator::F64Gt => FloatOp::Compare(F64, Gt),
+        Operator::F64Le => FloatOp::Compare(F64, Le),
+        Operator::F64Ge => FloatOp::Compare(F64, Ge),
+        Operator::F64PromoteF32 => FloatOp::Promote,
+        Operator::F32DemoteF64 => FloatOp::Demote,
+        _ => return None,
+    })
+}
+
+/// Emit IR for `op` applied to `operands` (in WASM operand order), returning
+/// the result value.
+pub fn emit_float_op<'ctx>(
+    translator: &WasmToLlvm<'ctx>,
+    op: FloatOp,
+    operands: &[IntValue<'ctx>],
+) -> Result<IntValue<'ctx>> {
+    let ops = Ops { translator };
+    let lhs = operands[0];
+    let rhs = || operands[1];
+    match op {
+        FloatOp::Neg(f) => ops.xor(lhs, ops.c(f.sign_bit())),
+        FloatOp::Abs(f) => ops.and(lhs, ops.c(f.abs_mask())),
+        FloatOp::Copysign(f) => {
+            let magnitude = ops.and(lhs, ops.c(f.abs_mask()))?;
+            let sign = ops.and(rhs(), ops.c(f.sign_bit()))?;
+            ops.or(magnitude, sign)
+        }
+        FloatOp::Add(f) => emit_add(&ops, f, lhs, rhs()),
+        FloatOp::Sub(f) => {
+            let negated = ops.xor(rhs(), ops.c(f.sign_bit()))?;
+            emit_add(&ops, f, lhs, negated)
+        }
+        FloatOp::Mul(f) => emit_mul(&ops, f, lhs, rhs()),
+        FloatOp::Div(f) => emit_div(&ops, f, lhs, rhs()),
+        FloatOp::Compare(f, cmp) => emit_compare(&ops, f, cmp, lhs, rhs()),
+        FloatOp::Promote => emit_reformat(&ops, FloatFormat::F32, FloatFormat::F64, lhs),
+        FloatOp::Demote => emit_reformat(&ops, FloatFormat::F64, FloatFormat::F32, lhs),
+    }
+}
+
+/// Thin wrapper over the builder for the bit manipulation below. Values are
+/// `i64`, predicates `i1`.
+struct Ops<'a, 'ctx> {
+    translator: &'a WasmToLlvm<'ctx>,
+}
+
+impl<'ctx> Ops<'_, 'ctx> {
+    fn c(&self, value: u64) -> IntValue<'ctx> {
+        self.translator.i64_type().const_int(value, false)
+    }
+
+    fn and(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_and(a, b, "sf"))
+    }
+
+    fn or(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_or(a, b, "sf"))
+    }
+
+    fn xor(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_xor(a, b, "sf"))
+    }
+
+    fn not(&self, a: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_not(a, "sf"))
+    }
+
+    fn add(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_int_add(a, b, "sf"))
+    }
+
+    fn sub(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_int_sub(a, b, "sf"))
+    }
+
+    fn mul(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_int_mul(a, b, "sf"))
+    }
+
+    fn udiv(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_int_unsigned_div(a, b, "sf"))
+    }
+
+    fn urem(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_int_unsigned_rem(a, b, "sf"))
+    }
+
+    /// Upper 64 bits of the unsigned 128-bit product.
+    fn mul_upper(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        let builder = self.translator.builder();
+        llvm_err(builder.build_call(
+            self.translator.pvm_mul_upper_uu(),
+            &[a.into(), b.into()],
+            "sf",
+        ))?
+        .try_as_basic_value()
+        .basic()
+        .map(inkwell::values::BasicValueEnum::into_int_value)
+        .ok_or_else(|| Error::Internal("mul_upper_uu must return a value".into()))
+    }
+
+    fn shl(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_left_shift(a, b, "sf"))
+    }
+
+    fn lshr(&self, a: IntValue<'ctx>, b: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(
+            self.translator
+                .builder()
+                .build_right_shift(a, b, false, "sf"),
+        )
+    }
+
+    fn cmp(
+        &self,
+        pred: IntPredicate,
+        a: IntValue<'ctx>,
+        b: IntValue<'ctx>,
+    ) -> Result<IntValue<'ctx>> {
+        llvm_err(
+            self.translator
+                .builder()
+                .build_int_compare(pred, a, b, "sf"),
+        )
+    }
+
+    fn select(
+        &self,
+        cond: IntValue<'ctx>,
+        a: IntValue<'ctx>,
+        b: IntValue<'ctx>,
+    ) -> Result<IntValue<'ctx>> {
+        Ok(llvm_err(self.translator.builder().build_select(cond, a, b, "sf"))?.into_int_value())
+    }
+
+    fn zext(&self, flag: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        llvm_err(self.translator.builder().build_int_z_extend(
+            flag,
+            self.translator.i64_type(),
+            "sf",
+        ))
+    }
+
+    /// `(x >> n) | sticky`, where `sticky` is 1 if any set bit was shifted
+    /// out. `n` may be any unsigned amount; `x` must be below 2^63, so
+    /// clamping `n` to 63 still leaves only the sticky bit.
+    fn shift_right_sticky(&self, x: IntValue<'ctx>, n: IntValue<'ctx>) -> Result<IntValue<'ctx>> {
+        let large = self.cmp(IntPredicate::UGT, n, self.c(63))?;
+        let n = self.select(large, self.c(63), n)?;
+        let shifted = self.lshr(x, n)?;
+        let restored = self.shl(shifted, n)?;
+        let lost = self.cmp(IntPredicate::NE, x, restored)?;
+        self.or(shifted, self.zext(lost)?)
+    }
+}
+
+/// A float operand split into its classification and normalized parts.
+struct Unpacked<'ctx> {
+    /// The sign bit, in place.
+    sign: IntValue<'ctx>,
+    /// `|x|` as bits; orders finite magnitudes like the values themselves.
+    abs: IntValue<'ctx>,
+    is_nan: IntValue<'ctx>,
+    is_inf: IntValue<'ctx>,
+    is_zero: IntValue<'ctx>,
+    /// Biased exponent, adjusted below 1 for normalized subnormals.
+    exponent: IntValue<'ctx>,
+    /// Significand with its leading one in bit 62 (0 for zeros).
+    sig: IntValue<'ctx>,
+}
+
+fn unpack<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    f: FloatFormat,
+    bits: IntValue<'ctx>,
+) -> Result<Unpacked<'ctx>> {
+    let m = u64::from(f.mantissa_bits());
+    let abs = ops.and(bits, ops.c(f.abs_mask()))?;
+    let sign = ops.and(bits, ops.c(f.sign_bit()))?;
+    let field = ops.lshr(abs, ops.c(m))?;
+    let mantissa = ops.and(bits, ops.c((1 << m) - 1))?;
+
+    let special = ops.cmp(IntPredicate::EQ, field, ops.c(f.max_exponent()))?;
+    let fraction_zero = ops.cmp(IntPredicate::EQ, mantissa, ops.c(0))?;
+    let is_nan = ops.and(special, ops.not(fraction_zero)?)?;
+    let is_inf = ops.and(special, fraction_zero)?;
+    let is_zero = ops.cmp(IntPredicate::EQ, abs, ops.c(0))?;
+
+    // Subnormals have no implicit one and the exponent of the smallest normal.
+    let subnormal = ops.cmp(IntPredicate::EQ, field, ops.c(0))?;
+    let with_one = ops.or(mantissa, ops.c(1 << m))?;
+    let sig = ops.select(subnormal, mantissa, with_one)?;
+    let sig = ops.shl(sig, ops.c(62 - m))?;
+    let exponent = ops.select(subnormal, ops.c(1), field)?;
+
+    // Only subnormals move; zeros come out with garbage exponents that the
+    // callers' special-case selects override.
+    let lz = ops.sub(ctlz(ops.translator, sig)?, ops.c(1))?;
+    let lz = ops.and(lz, ops.c(63))?;
+    let sig = ops.shl(sig, lz)?;
+    let exponent = ops.sub(exponent, lz)?;
+
+    Ok(Unpacked {
+        sign,
+        abs,
+        is_nan,
+        is_inf,
+        is_zero,
+        exponent,
+        sig,
+    })
+}
+
+/// Round `sig · 2^(exponent - bias - 62)` to nearest, ties to even, and
+/// assemble it with `sign` (already in place). `exponent` is signed; results
+/// below the normal range become subnormal (or zero), results above it ∞.
+fn round_pack<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    f: FloatFormat,
+    sign: IntValue<'ctx>,
+    exponent: IntValue<'ctx>,
+    sig: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let m = u64::from(f.mantissa_bits());
+    let round_bits = 62 - m;
+
+    let tiny = ops.cmp(IntPredicate::SLT, exponent, ops.c(1))?;
+    let denormalize = ops.sub(ops.c(1), exponent)?;
+    let shift = ops.select(tiny, denormalize, ops.c(0))?;
+    let sig = ops.shift_right_sticky(sig, shift)?;
+    let exponent = ops.select(tiny, ops.c(1), exponent)?;
+
+    let rest = ops.and(sig, ops.c((1 << round_bits) - 1))?;
+    let half = ops.c(1 << (round_bits - 1));
+    let mantissa = ops.lshr(sig, ops.c(round_bits))?;
+    let above_half = ops.cmp(IntPredicate::UGT, rest, half)?;
+    let tie = ops.cmp(IntPredicate::EQ, rest, half)?;
+    let odd = ops.cmp(IntPredicate::NE, ops.and(mantissa, ops.c(1))?, ops.c(0))?;
+    let round_up = ops.or(above_half, ops.and(tie, odd)?)?;
+
+    // The mantissa still carries its leading one, which bumps `exponent - 1`
+    // back up; a rounding carry ripples into the exponent the same way.
+    let biased = ops.shl(ops.sub(exponent, ops.c(1))?, ops.c(m))?;
+    let bits = ops.add(ops.add(biased, mantissa)?, ops.zext(round_up)?)?;
+    let overflow = ops.cmp(
+        IntPredicate::UGE,
+        ops.lshr(bits, ops.c(m))?,
+        ops.c(f.max_exponent()),
+    )?;
+    let bits = ops.select(overflow, ops.c(f.infinity()), bits)?;
+    ops.or(bits, sign)
+}
+
+/// Addition (and subtraction, with `b`'s sign flipped by the caller).
+///
+/// The larger-magnitude operand fixes the exponent and sign; the other is
+/// aligned to it with a sticky shift. Same signs add, shifting a carry into
+/// bit 63 back down; opposite signs subtract and renormalize, and exact
+/// cancellation yields +0.
+fn emit_add<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    format: FloatFormat,
+    a: IntValue<'ctx>,
+    b: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let x = unpack(ops, format, a)?;
+    let y = unpack(ops, format, b)?;
+
+    let swap = ops.cmp(IntPredicate::UGT, y.abs, x.abs)?;
+    let big_exp = ops.select(swap, y.exponent, x.exponent)?;
+    let big_sig = ops.select(swap, y.sig, x.sig)?;
+    let sign = ops.select(swap, y.sign, x.sign)?;
+    let small_exp = ops.select(swap, x.exponent, y.exponent)?;
+    let small_sig = ops.select(swap, x.sig, y.sig)?;
+    let small_sig = ops.shift_right_sticky(small_sig, ops.sub(big_exp, small_exp)?)?;
+    let same_sign = ops.cmp(IntPredicate::EQ, x.sign, y.sign)?;
+
+    let sum = ops.add(big_sig, small_sig)?;
+    let carry = ops.lshr(sum, ops.c(63))?;
+    let carried = ops.or(ops.lshr(sum, ops.c(1))?, ops.and(sum, ops.c(1))?)?;
+    let has_carry = ops.cmp(IntPredicate::NE, carry, ops.c(0))?;
+    let sum_sig = ops.select(has_carry, carried, sum)?;
+    let sum_exp = ops.add(big_exp, carry)?;
+
+    let diff = ops.sub(big_sig, small_sig)?;
+    let lz = ops.sub(ctlz(ops.translator, diff)?, ops.c(1))?;
+    let lz = ops.and(lz, ops.c(63))?;
+    let diff_sig = ops.shl(diff, lz)?;
+    let diff_exp = ops.sub(big_exp, lz)?;
+
+    let sig = ops.select(same_sign, sum_sig, diff_sig)?;
+    let exponent = ops.select(same_sign, sum_exp, diff_exp)?;
+    let result = round_pack(ops, format, sign, exponent, sig)?;
+    let cancelled = ops.and(
+        ops.not(same_sign)?,
+        ops.cmp(IntPredicate::EQ, diff, ops.c(0))?,
+    )?;
+    let result = ops.select(cancelled, ops.c(0), result)?;
+
+    // Special operands, lowest priority first. Adding ±0 is exact; two zeros
+    // give -0 only if both are negative.
+    let result = ops.select(y.is_zero, a, result)?;
+    let result = ops.select(x.is_zero, b, result)?;
+    let both_zero = ops.and(x.is_zero, y.is_zero)?;
+    let result = ops.select(both_zero, ops.and(x.sign, y.sign)?, result)?;
+    let result = ops.select(y.is_inf, b, result)?;
+    let result = ops.select(x.is_inf, a, result)?;
+    let opposite_infs = ops.and(ops.and(x.is_inf, y.is_inf)?, ops.not(same_sign)?)?;
+    let nan = ops.or(ops.or(x.is_nan, y.is_nan)?, opposite_infs)?;
+    ops.select(nan, ops.c(format.canonical_nan()), result)
+}
+
+/// Multiplication: the 126-bit significand product is cut back to 63 bits
+/// (high word via `MulUpperUU`), folding the discarded bits into the sticky
+/// bit, and renormalized by one if it reached bit 63.
+fn emit_mul<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    format: FloatFormat,
+    a: IntValue<'ctx>,
+    b: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let x = unpack(ops, format, a)?;
+    let y = unpack(ops, format, b)?;
+    let sign = ops.xor(x.sign, y.sign)?;
+
+    let hi = ops.mul_upper(x.sig, y.sig)?;
+    let lo = ops.mul(x.sig, y.sig)?;
+    let sig = ops.or(ops.shl(hi, ops.c(2))?, ops.lshr(lo, ops.c(62))?)?;
+    let lost = ops.cmp(IntPredicate::NE, ops.shl(lo, ops.c(2))?, ops.c(0))?;
+    let top = ops.lshr(sig, ops.c(63))?;
+    let has_top = ops.cmp(IntPredicate::NE, top, ops.c(0))?;
+    let halved = ops.or(ops.lshr(sig, ops.c(1))?, ops.and(sig, ops.c(1))?)?;
+    let sig = ops.select(has_top, halved, sig)?;
+    let sig = ops.or(sig, ops.zext(lost)?)?;
+    let exponent = ops.sub(
+        ops.add(x.exponent, y.exponent)?,
+        ops.c(format.exponent_bias()),
+    )?;
+    let exponent = ops.add(exponent, top)?;
+    let result = round_pack(ops, format, sign, exponent, sig)?;
+
+    let any_zero = ops.or(x.is_zero, y.is_zero)?;
+    let any_inf = ops.or(x.is_inf, y.is_inf)?;
+    let result = ops.select(any_zero, sign, result)?;
+    let result = ops.select(any_inf, ops.or(ops.c(format.infinity()), sign)?, result)?;
+    let nan = ops.or(ops.or(x.is_nan, y.is_nan)?, ops.and(any_zero, any_inf)?)?;
+    ops.select(nan, ops.c(format.canonical_nan()), result)
+}
+
+/// Division by restoring long division on 53-bit significands: the first
+/// quotient bit comes from aligning the dividend to at least the divisor,
+/// the remaining 62 from chunks small enough that the shifted remainder
+/// never overflows. A non-zero final remainder sets the sticky bit.
+fn emit_div<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    format: FloatFormat,
+    a: IntValue<'ctx>,
+    b: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let x = unpack(ops, format, a)?;
+    let y = unpack(ops, format, b)?;
+    let sign = ops.xor(x.sign, y.sign)?;
+
+    // Both significands have at least 10 trailing zeros.
+    let dividend = ops.lshr(x.sig, ops.c(10))?;
+    let divisor = ops.lshr(y.sig, ops.c(10))?;
+    // A zero divisor is a special case below, but must not reach `udiv`.
+    let divisor_zero = ops.cmp(IntPredicate::EQ, divisor, ops.c(0))?;
+    let divisor = ops.select(divisor_zero, ops.c(1), divisor)?;
+    let smaller = ops.cmp(IntPredicate::ULT, dividend, divisor)?;
+    let doubled = ops.shl(dividend, ops.c(1))?;
+    let dividend = ops.select(smaller, doubled, dividend)?;
+
+    let mut quotient = ops.udiv(dividend, divisor)?;
+    let mut remainder = ops.urem(dividend, divisor)?;
+    for chunk in [11, 11, 11, 11, 11, 7] {
+        remainder = ops.shl(remainder, ops.c(chunk))?;
+        let digits = ops.udiv(remainder, divisor)?;
+        quotient = ops.or(ops.shl(quotient, ops.c(chunk))?, digits)?;
+        remainder = ops.urem(remainder, divisor)?;
+    }
+    let inexact = ops.cmp(IntPredicate::NE, remainder, ops.c(0))?;
+    let sig = ops.or(quotient, ops.zext(inexact)?)?;
+    let exponent = ops.add(
+        ops.sub(x.exponent, y.exponent)?,
+        ops.c(format.exponent_bias()),
+    )?;
+    let exponent = ops.sub(exponent, ops.zext(smaller)?)?;
+    let result = round_pack(ops, format, sign, exponent, sig)?;
+
+    let result = ops.select(ops.or(x.is_zero, y.is_inf)?, sign, result)?;
+    let infinite = ops.or(x.is_inf, y.is_zero)?;
+    let result = ops.select(infinite, ops.or(ops.c(format.infinity()), sign)?, result)?;
+    let nan = ops.or(
+        ops.or(x.is_nan, y.is_nan)?,
+        ops.or(ops.and(x.is_zero, y.is_zero)?, ops.and(x.is_inf, y.is_inf)?)?,
+    )?;
+    ops.select(nan, ops.c(format.canonical_nan()), result)
+}
+
+/// Comparisons map each operand to a signed key (`±|x|`, so +0 and -0
+/// coincide) and compare the keys; anything involving NaN is unordered.
+fn emit_compare<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    format: FloatFormat,
+    cmp: FloatCmp,
+    a: IntValue<'ctx>,
+    b: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let x = unpack(ops, format, a)?;
+    let y = unpack(ops, format, b)?;
+    let key = |u: &Unpacked<'ctx>| -> Result<IntValue<'ctx>> {
+        let negative = ops.cmp(IntPredicate::NE, u.sign, ops.c(0))?;
+        ops.select(negative, ops.sub(ops.c(0), u.abs)?, u.abs)
+    };
+    let (kx, ky) = (key(&x)?, key(&y)?);
+    let unordered = ops.or(x.is_nan, y.is_nan)?;
+    let pred = match cmp {
+        FloatCmp::Eq => IntPredicate::EQ,
+        FloatCmp::Ne => IntPredicate::NE,
+        FloatCmp::Lt => IntPredicate::SLT,
+        FloatCmp::Gt => IntPredicate::SGT,
+        FloatCmp::Le => IntPredicate::SLE,
+        FloatCmp::Ge => IntPredicate::SGE,
+    };
+    let ordered_result = ops.cmp(pred, kx, ky)?;
+    let result = if matches!(cmp, FloatCmp::Ne) {
+        ops.or(ordered_result, unordered)?
+    } else {
+        ops.and(ordered_result, ops.not(unordered)?)?
+    };
+    ops.zext(result)
+}
+
+/// `f64.promote_f32` (exact) and `f32.demote_f64` (rounded): rebias the
+/// exponent and repack in the target format.
+fn emit_reformat<'ctx>(
+    ops: &Ops<'_, 'ctx>,
+    from: FloatFormat,
+    to: FloatFormat,
+    value: IntValue<'ctx>,
+) -> Result<IntValue<'ctx>> {
+    let x = unpack(ops, from, value)?;
+    let negative = ops.cmp(IntPredicate::NE, x.sign, ops.c(0))?;
+    let sign = ops.select(negative, ops.c(to.sign_bit()), ops.c(0))?;
+    let exponent = ops.add(
+        ops.sub(x.exponent, ops.c(from.exponent_bias()))?,
+        ops.c(to.exponent_bias()),
+    )?;
+    let result = round_pack(ops, to, sign, exponent, x.sig)?;
+    let result = ops.select(x.is_zero, sign, result)?;
+    let result = ops.select(x.is_inf, ops.or(ops.c(to.infinity()), sign)?, result)?;
+    ops.select(x.is_nan, ops.c(to.canonical_nan()), result)
+}
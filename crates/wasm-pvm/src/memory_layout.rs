@@ -15,16 +15,17 @@
 //!   0x00000 - 0x0FFFF   Reserved (fault on access)
 //!   0x10000 - 0x1FFFF   Read-only data segment (RO_DATA_BASE)
 //!   0x20000 - 0x2FFFF   Gap zone (unmapped, guard between RO and RW)
-//!   0x30000             Mem-size slot (4 bytes, only when memory.size/grow/init used)
+//!   0x30000             Mem-size slot (4 bytes, only when memory.size/grow/init/copy used)
 //!   0x30000 / 0x30004+  User globals (per-global width: 4 B for i32/f32, 8 B for i64/f64;
 //!                       packed in declaration order, no padding)
 //!   globals_end+        Passive data segment effective-length slots (4 bytes each)
-//!   passive_lens_end+   Parameter overflow area (256 bytes, 8-byte aligned, only when any module type signature has >4 params — gated by `needs_param_overflow`, which covers both local functions and `call_indirect` types)
+//!   passive_lens_end+   Parameter overflow area (256 bytes, 8-byte aligned, only when any module type signature has >4 params or >1 result — gated by `needs_param_overflow`, which covers both local functions and `call_indirect` types)
 //!   region_end          WASM linear memory (no 4KB alignment; sits immediately after last region)
 //!   ...
 //!   0xFEFE0000          Stack segment end (stack grows downward)
 //!   0xFFFF0000          Exit address (EXIT_ADDRESS)
 //! ```
+use alloc::vec::Vec;
 
 /// Base address for the read-only data segment (dispatch tables, constant data).
 pub const RO_DATA_BASE: i32 = 0x10000;
@@ -60,10 +61,9 @@ pub const PASSIVE_SEG_LEN_BYTES: usize = 4;
 pub fn global_storage_width(ty: wasmparser::ValType) -> u32 {
     match ty {
         wasmparser::ValType::I32 | wasmparser::ValType::F32 => 4,
-        // i64/f64 are 8 bytes by definition. `Ref(_)` (funcref/externref)
-        // is 1 abstract WASM slot, sized as i64 (8 B) at our i64-uniform
-        // ABI — listed here for exhaustiveness even though `WasmModule::parse`
-        // rejects ref globals before this function is called.
+        // i64/f64 are 8 bytes by definition. `Ref(_)` is 1 abstract WASM
+        // slot, sized as i64 (8 B) at our i64-uniform ABI; funcref globals
+        // store a jump-ref there (`WasmModule::parse` rejects externref).
         wasmparser::ValType::I64 | wasmparser::ValType::F64 | wasmparser::ValType::Ref(_) => 8,
         // v128 is 16 bytes — its actual WASM width. Reaching the backend
         // with a 16-byte slot would surface as
@@ -85,6 +85,11 @@ pub const PARAM_OVERFLOW_SIZE: usize = 256;
 /// WASM linear memory, so `WasmModule::parse` rejects them.
 pub const MAX_TOTAL_PARAMS: usize = crate::abi::MAX_LOCAL_REGS + PARAM_OVERFLOW_SIZE / 8;
 
+/// Maximum result count of a function signature. The first result returns in
+/// r7; the callee writes the rest to the param-overflow window right before
+/// returning, and the caller reads them back right after the call.
+pub const MAX_RESULTS: usize = 1 + PARAM_OVERFLOW_SIZE / 8;
+
 /// Compute the base address for the parameter overflow area.
 /// Placed right after the globals region, 8-byte aligned.
 #[must_use]
@@ -120,12 +125,13 @@ pub fn stack_limit(stack_size: u32) -> i32 {
 ///
 /// Layout (from `GLOBAL_MEMORY_BASE` upward):
 /// 1. The compiler-managed memory-size slot (4 bytes) — only when the module
-///    uses `memory.size`/`memory.grow`/`memory.init`.
+///    uses `memory.size`/`memory.grow`/`memory.init`/`memory.copy`.
 /// 2. User globals (4 bytes each).
 /// 3. Passive data segment effective-length slots (4 bytes each).
 /// 4. Parameter overflow area (256 bytes) — only when any module type
 ///    signature (local function or `call_indirect` target) has more than
-///    `MAX_LOCAL_REGS` parameters (tracked by `needs_param_overflow`).
+///    `MAX_LOCAL_REGS` parameters or more than one result (tracked by
+///    `needs_param_overflow`).
 ///
 /// `wasm_memory_base` sits immediately after region 4 (or the last present
 /// region). It is **not** 4KB-aligned: anan-as allocates `rw_data` one PVM page
@@ -191,7 +197,8 @@ pub fn compute_global_offsets(widths: &[u32], has_mem_size_global: bool) -> Vec<
 /// Always `GLOBAL_MEMORY_BASE` (`0x30000`) when emitted — a stable, program-
 /// independent slot so memory-op lowering doesn't need to know `num_globals`.
 ///
-/// Only meaningful when the module uses `memory.size`/`memory.grow`/`memory.init`.
+/// Only meaningful when the module uses `memory.size`/`memory.grow`/`memory.init`/
+/// `memory.copy`.
 /// When unused, the slot is not emitted and user globals occupy position 0
 /// instead — callers must gate on `needs_memory_size_global`.
 #[must_use]
@@ -199,6 +206,20 @@ pub fn memory_size_global_offset() -> i32 {
     GLOBAL_MEMORY_BASE
 }
 
+/// Whether a user global at `address` with `width` bytes overlaps the
+/// memory-size slot `[GLOBAL_MEMORY_BASE, GLOBAL_MEMORY_BASE + 4)`.
+///
+/// [`compute_global_offsets`] reserves the slot ahead of every user global, so
+/// this never holds for its output; the compiler checks it anyway before
+/// trusting an offset, since a collision would let `global.set` corrupt the
+/// memory size that `memory.grow` and the bounds checks rely on.
+#[must_use]
+pub fn overlaps_memory_size_slot(address: i32, width: u32) -> bool {
+    let slot = i64::from(memory_size_global_offset());
+    let start = i64::from(address);
+    start < slot + MEM_SIZE_SLOT_BYTES as i64 && slot < start + i64::from(width)
+}
+
 /// Offset within `GLOBAL_MEMORY_BASE` for a passive data segment's effective length.
 /// Stored after the mem-size slot (when present) and user globals.
 /// Used for bounds checking in `memory.init` and zeroed by `data.drop`.
@@ -369,6 +390,37 @@ mod tests {
         assert_eq!(memory_size_global_offset(), 0x30000);
     }
 
+    #[test]
+    fn memory_size_slot_overlap() {
+        assert!(overlaps_memory_size_slot(0x30000, 4));
+        assert!(overlaps_memory_size_slot(0x2FFFC, 8));
+        assert!(overlaps_memory_size_slot(0x30003, 4));
+        assert!(!overlaps_memory_size_slot(0x30004, 8));
+        assert!(!overlaps_memory_size_slot(0x2FFFC, 4));
+    }
+
+    #[test]
+    fn no_global_collides_with_mem_size_slot_at_max_global_count() {
+        // wasmparser's `MAX_WASM_GLOBALS`; i64 globals make the region largest.
+        let widths = vec![8; 1_000_000];
+        let offsets = compute_global_offsets(&widths, true);
+        assert_eq!(offsets[0], 0x30004);
+        assert!(
+            offsets
+                .iter()
+                .zip(&widths)
+                .all(|(&addr, &w)| !overlaps_memory_size_slot(addr, w))
+        );
+        // The last global ends exactly where the region (and the first passive
+        // segment length) begins, with no wrap-around back into the slot.
+        let end = offsets[widths.len() - 1] + 8;
+        assert_eq!(
+            end as usize,
+            0x30000 + globals_region_size(&widths, 0, true)
+        );
+        assert_eq!(data_segment_length_offset(&widths, 0, true), end);
+    }
+
     #[test]
     fn data_segment_length_after_mem_size_and_globals() {
         // With mem-size (4B) + 5 i32 globals (4B each): lens start at 0x30000 + 4 + 20 = 0x30018.
@@ -411,4 +463,27 @@ mod tests {
             (0xFEFE_0000u32 - 64 * 1024) as i32
         );
     }
+
+    #[test]
+    fn stack_limit_is_an_unsigned_offset_below_the_segment_end() {
+        for stack_size in [0, 1, 4096, DEFAULT_STACK_SIZE, 1 << 20, 0x7EFE_0000] {
+            let limit = stack_limit(stack_size) as u32;
+            assert_eq!(
+                limit,
+                0xFEFE_0000 - stack_size,
+                "stack_size {stack_size:#x}"
+            );
+            // Every realistic limit is negative as an i32, so the prologue must
+            // zero-extend it (LoadImm64) for the unsigned SP comparison.
+            assert!(stack_limit(stack_size) < 0, "stack_size {stack_size:#x}");
+            assert_ne!(
+                u64::from(limit),
+                i64::from(stack_limit(stack_size)) as u64,
+                "stack_size {stack_size:#x}"
+            );
+        }
+        // The whole segment-end range wraps instead of underflowing.
+        assert_eq!(stack_limit(STACK_SEGMENT_END as u32), 0);
+        assert_eq!(stack_limit(u32::MAX), (STACK_SEGMENT_END as u32 + 1) as i32);
+    }
 }
@@ -0,0 +1,421 @@
+// Textual assembly: `Display` and `parse_asm` for `Instruction`.
+
+use alloc::{format, vec::Vec};
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Instruction, Opcode};
+use crate::{Error, Result};
+
+/// Operand kinds, by `Instruction` field type: each renders one field and
+/// parses it back.
+mod reg {
+    use core::fmt;
+
+    pub fn show(reg: u8, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "r{reg}")
+    }
+
+    pub fn parse(text: &str) -> Option<u8> {
+        let n: u8 = text.strip_prefix(['r', 'R'])?.parse().ok()?;
+        (n <= 12).then_some(n)
+    }
+}
+
+mod imm {
+    use core::fmt;
+
+    pub fn show(value: i32, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{value}")
+    }
+
+    pub fn parse(text: &str) -> Option<i32> {
+        i32::try_from(super::parse_int(text)?).ok()
+    }
+}
+
+mod uimm {
+    use core::fmt;
+
+    pub fn show(value: u32, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{value}")
+    }
+
+    pub fn parse(text: &str) -> Option<u32> {
+        u32::try_from(super::parse_int(text)?).ok()
+    }
+}
+
+/// 64-bit immediates print in hex and also parse from a negative decimal,
+/// wrapped to their two's complement.
+mod imm64 {
+    use core::fmt;
+
+    pub fn show(value: u64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{value:#x}")
+    }
+
+    pub fn parse(text: &str) -> Option<u64> {
+        let value = super::parse_int(text)?;
+        u64::try_from(value)
+            .ok()
+            .or_else(|| i64::try_from(value).ok().map(i64::cast_unsigned))
+    }
+}
+
+/// A decimal or `0x` hex integer with an optional leading `-`.
+fn parse_int(text: &str) -> Option<i128> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let magnitude = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// One line per variant: its fields in declaration order and their operand
+/// kinds. Every variant shares its name with its [`Opcode`]; `JumpFixed` and
+/// `Unknown` have none and are handled separately.
+macro_rules! asm_forms {
+    ($($variant:ident { $($field:ident: $kind:ident),* },)*) => {
+        impl Instruction {
+            fn write_asm(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(Self::$variant { $($field),* } => {
+                        f.write_str(Opcode::$variant.name())?;
+                        let mut _sep = " ";
+                        $(
+                            f.write_str(_sep)?;
+                            $kind::show(*$field, f)?;
+                            _sep = ", ";
+                        )*
+                        Ok(())
+                    })*
+                    Self::JumpFixed { offset } => write!(f, "{JUMP_FIXED} {offset}"),
+                    Self::Unknown { opcode, raw_bytes } => {
+                        write!(f, "{UNKNOWN} {opcode}, 0x")?;
+                        raw_bytes.iter().try_for_each(|b| write!(f, "{b:02x}"))
+                    }
+                }
+            }
+
+            fn from_asm(opcode: Opcode, operands: &[&str]) -> Option<Self> {
+                let mut operands = operands.iter();
+                let instr = match opcode {
+                    $(Opcode::$variant => Self::$variant {
+                        $($field: $kind::parse(operands.next()?)?),*
+                    },)*
+                };
+                operands.next().is_none().then_some(instr)
+            }
+        }
+    };
+}
+
+/// Mnemonic for `Instruction::JumpFixed`, a `jump` with a fixed 4-byte offset.
+const JUMP_FIXED: &str = "jump_fixed";
+/// Mnemonic for `Instruction::Unknown`: the opcode byte, then every raw byte
+/// (opcode included) as one hex string.
+const UNKNOWN: &str = "unknown";
+
+asm_forms! {
+    Trap {},
+    Fallthrough {},
+    LoadImm64 { reg: reg, value: imm64 },
+    LoadImm { reg: reg, value: imm },
+    Add32 { dst: reg, src1: reg, src2: reg },
+    Sub32 { dst: reg, src1: reg, src2: reg },
+    Mul32 { dst: reg, src1: reg, src2: reg },
+    DivU32 { dst: reg, src1: reg, src2: reg },
+    DivS32 { dst: reg, src1: reg, src2: reg },
+    RemU32 { dst: reg, src1: reg, src2: reg },
+    RemS32 { dst: reg, src1: reg, src2: reg },
+    Add64 { dst: reg, src1: reg, src2: reg },
+    Sub64 { dst: reg, src1: reg, src2: reg },
+    Mul64 { dst: reg, src1: reg, src2: reg },
+    DivU64 { dst: reg, src1: reg, src2: reg },
+    DivS64 { dst: reg, src1: reg, src2: reg },
+    RemU64 { dst: reg, src1: reg, src2: reg },
+    RemS64 { dst: reg, src1: reg, src2: reg },
+    ShloL64 { dst: reg, src1: reg, src2: reg },
+    ShloR64 { dst: reg, src1: reg, src2: reg },
+    SharR64 { dst: reg, src1: reg, src2: reg },
+    AddImm32 { dst: reg, src: reg, value: imm },
+    AddImm64 { dst: reg, src: reg, value: imm },
+    AndImm { dst: reg, src: reg, value: imm },
+    XorImm { dst: reg, src: reg, value: imm },
+    OrImm { dst: reg, src: reg, value: imm },
+    MulImm32 { dst: reg, src: reg, value: imm },
+    MulImm64 { dst: reg, src: reg, value: imm },
+    ShloLImm32 { dst: reg, src: reg, value: imm },
+    ShloRImm32 { dst: reg, src: reg, value: imm },
+    SharRImm32 { dst: reg, src: reg, value: imm },
+    ShloLImm64 { dst: reg, src: reg, value: imm },
+    ShloRImm64 { dst: reg, src: reg, value: imm },
+    SharRImm64 { dst: reg, src: reg, value: imm },
+    NegAddImm32 { dst: reg, src: reg, value: imm },
+    NegAddImm64 { dst: reg, src: reg, value: imm },
+    SetGtUImm { dst: reg, src: reg, value: imm },
+    SetGtSImm { dst: reg, src: reg, value: imm },
+    Jump { offset: imm },
+    LoadImmJump { reg: reg, value: imm, offset: imm },
+    JumpInd { reg: reg, offset: imm },
+    LoadIndU32 { dst: reg, base: reg, offset: imm },
+    StoreIndU32 { base: reg, src: reg, offset: imm },
+    LoadIndU64 { dst: reg, base: reg, offset: imm },
+    StoreIndU64 { base: reg, src: reg, offset: imm },
+    BranchNeImm { reg: reg, value: imm, offset: imm },
+    BranchEqImm { reg: reg, value: imm, offset: imm },
+    BranchGeSImm { reg: reg, value: imm, offset: imm },
+    BranchLtUImm { reg: reg, value: imm, offset: imm },
+    BranchLeUImm { reg: reg, value: imm, offset: imm },
+    BranchGeUImm { reg: reg, value: imm, offset: imm },
+    BranchGtUImm { reg: reg, value: imm, offset: imm },
+    BranchLtSImm { reg: reg, value: imm, offset: imm },
+    BranchLeSImm { reg: reg, value: imm, offset: imm },
+    BranchGtSImm { reg: reg, value: imm, offset: imm },
+    MoveReg { dst: reg, src: reg },
+    BranchEq { reg1: reg, reg2: reg, offset: imm },
+    BranchNe { reg1: reg, reg2: reg, offset: imm },
+    BranchGeU { reg1: reg, reg2: reg, offset: imm },
+    BranchLtU { reg1: reg, reg2: reg, offset: imm },
+    BranchLtS { reg1: reg, reg2: reg, offset: imm },
+    BranchGeS { reg1: reg, reg2: reg, offset: imm },
+    SetLtU { dst: reg, src1: reg, src2: reg },
+    SetLtS { dst: reg, src1: reg, src2: reg },
+    CmovIz { dst: reg, src: reg, cond: reg },
+    CmovNz { dst: reg, src: reg, cond: reg },
+    And { dst: reg, src1: reg, src2: reg },
+    Xor { dst: reg, src1: reg, src2: reg },
+    Or { dst: reg, src1: reg, src2: reg },
+    SetLtUImm { dst: reg, src: reg, value: imm },
+    SetLtSImm { dst: reg, src: reg, value: imm },
+    ShloL32 { dst: reg, src1: reg, src2: reg },
+    ShloR32 { dst: reg, src1: reg, src2: reg },
+    SharR32 { dst: reg, src1: reg, src2: reg },
+    Sbrk { dst: reg, src: reg },
+    CountSetBits64 { dst: reg, src: reg },
+    CountSetBits32 { dst: reg, src: reg },
+    LeadingZeroBits64 { dst: reg, src: reg },
+    LeadingZeroBits32 { dst: reg, src: reg },
+    TrailingZeroBits64 { dst: reg, src: reg },
+    TrailingZeroBits32 { dst: reg, src: reg },
+    SignExtend8 { dst: reg, src: reg },
+    SignExtend16 { dst: reg, src: reg },
+    ZeroExtend16 { dst: reg, src: reg },
+    LoadIndU8 { dst: reg, base: reg, offset: imm },
+    LoadIndI8 { dst: reg, base: reg, offset: imm },
+    StoreIndU8 { base: reg, src: reg, offset: imm },
+    LoadIndU16 { dst: reg, base: reg, offset: imm },
+    LoadIndI16 { dst: reg, base: reg, offset: imm },
+    StoreIndU16 { base: reg, src: reg, offset: imm },
+    CmovIzImm { dst: reg, cond: reg, value: imm },
+    CmovNzImm { dst: reg, cond: reg, value: imm },
+    StoreImmU8 { address: imm, value: imm },
+    StoreImmU16 { address: imm, value: imm },
+    StoreImmU32 { address: imm, value: imm },
+    StoreImmU64 { address: imm, value: imm },
+    StoreImmIndU8 { base: reg, offset: imm, value: imm },
+    StoreImmIndU16 { base: reg, offset: imm, value: imm },
+    StoreImmIndU32 { base: reg, offset: imm, value: imm },
+    StoreImmIndU64 { base: reg, offset: imm, value: imm },
+    LoadU8 { dst: reg, address: imm },
+    LoadI8 { dst: reg, address: imm },
+    LoadU16 { dst: reg, address: imm },
+    LoadI16 { dst: reg, address: imm },
+    LoadU32 { dst: reg, address: imm },
+    LoadI32 { dst: reg, address: imm },
+    LoadU64 { dst: reg, address: imm },
+    StoreU8 { src: reg, address: imm },
+    StoreU16 { src: reg, address: imm },
+    StoreU32 { src: reg, address: imm },
+    StoreU64 { src: reg, address: imm },
+    LoadIndI32 { dst: reg, base: reg, offset: imm },
+    ReverseBytes { dst: reg, src: reg },
+    ShloLImmAlt32 { dst: reg, src: reg, value: imm },
+    ShloRImmAlt32 { dst: reg, src: reg, value: imm },
+    SharRImmAlt32 { dst: reg, src: reg, value: imm },
+    ShloLImmAlt64 { dst: reg, src: reg, value: imm },
+    ShloRImmAlt64 { dst: reg, src: reg, value: imm },
+    SharRImmAlt64 { dst: reg, src: reg, value: imm },
+    RotRImm64 { dst: reg, src: reg, value: imm },
+    RotRImmAlt64 { dst: reg, src: reg, value: imm },
+    RotRImm32 { dst: reg, src: reg, value: imm },
+    RotRImmAlt32 { dst: reg, src: reg, value: imm },
+    LoadImmJumpInd { base: reg, dst: reg, value: imm, offset: imm },
+    MulUpperSS { dst: reg, src1: reg, src2: reg },
+    MulUpperUU { dst: reg, src1: reg, src2: reg },
+    MulUpperSU { dst: reg, src1: reg, src2: reg },
+    RotL64 { dst: reg, src1: reg, src2: reg },
+    RotL32 { dst: reg, src1: reg, src2: reg },
+    RotR64 { dst: reg, src1: reg, src2: reg },
+    RotR32 { dst: reg, src1: reg, src2: reg },
+    AndInv { dst: reg, src1: reg, src2: reg },
+    OrInv { dst: reg, src1: reg, src2: reg },
+    Xnor { dst: reg, src1: reg, src2: reg },
+    Max { dst: reg, src1: reg, src2: reg },
+    MaxU { dst: reg, src1: reg, src2: reg },
+    Min { dst: reg, src1: reg, src2: reg },
+    MinU { dst: reg, src1: reg, src2: reg },
+    Ecalli { index: uimm },
+}
+
+impl fmt::Display for Instruction {
+    /// The mnemonic ([`Opcode::name`]) followed by the operands in field
+    /// order, comma-separated: registers as `rN`, immediates and offsets in
+    /// decimal, `load_imm_64` values in hex. [`Instruction::parse_asm`]
+    /// reads it back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_asm(f)
+    }
+}
+
+impl Instruction {
+    /// Parse one line of assembly, as written by `Display`: e.g.
+    /// `add_64 r3, r1, r2` or `load_imm r0, 1234`.
+    ///
+    /// Mnemonics go through [`Opcode::from_name`], so case and underscores
+    /// are ignored (`add64` works). Immediates may also be `0x` hex, and
+    /// anything after a `;` is a comment.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Asm`] for an unknown mnemonic, a wrong operand count,
+    /// a register outside `r0`-`r12`, or an immediate that does not fit its
+    /// field.
+    pub fn parse_asm(line: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::Asm(format!("{reason} in `{}`", line.trim()));
+        let code = line.split(';').next().unwrap_or_default().trim();
+        let (mnemonic, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let operands: Vec<&str> = if rest.trim().is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',').map(str::trim).collect()
+        };
+
+        let is = |name: &str| {
+            mnemonic
+                .replace('_', "")
+                .eq_ignore_ascii_case(&name.replace('_', ""))
+        };
+        if is(JUMP_FIXED) {
+            return match operands[..] {
+                [offset] => imm::parse(offset)
+                    .map(|offset| Self::JumpFixed { offset })
+                    .ok_or_else(|| invalid("bad operands")),
+                _ => Err(invalid("bad operands")),
+            };
+        }
+        if is(UNKNOWN) {
+            return match operands[..] {
+                [opcode, raw] => parse_unknown(opcode, raw).ok_or_else(|| invalid("bad operands")),
+                _ => Err(invalid("bad operands")),
+            };
+        }
+
+        let opcode = Opcode::from_name(mnemonic)
+            .ok_or_else(|| invalid(&format!("unknown mnemonic `{mnemonic}`")))?;
+        Self::from_asm(opcode, &operands).ok_or_else(|| invalid("bad operands"))
+    }
+}
+
+fn parse_unknown(opcode: &str, raw: &str) -> Option<Instruction> {
+    let opcode = u8::try_from(parse_int(opcode)?).ok()?;
+    let hex = raw.strip_prefix("0x")?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let raw_bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(Instruction::Unknown { opcode, raw_bytes })
+}
+
+impl FromStr for Instruction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse_asm(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pvm::sample_instructions;
+
+    #[test]
+    fn display_round_trips_all_variants() {
+        for instr in sample_instructions() {
+            let text = instr.to_string();
+            let parsed = Instruction::parse_asm(&text).unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(parsed, instr, "{text}");
+        }
+    }
+
+    #[test]
+    fn operands_follow_field_order() {
+        let add = Instruction::Add64 {
+            dst: 3,
+            src1: 1,
+            src2: 2,
+        };
+        assert_eq!(add.to_string(), "add_64 r3, r1, r2");
+        assert_eq!(Instruction::parse_asm("add64 r3, r1, r2").unwrap(), add);
+
+        let load = Instruction::LoadImm {
+            reg: 0,
+            value: 1234,
+        };
+        assert_eq!(load.to_string(), "load_imm r0, 1234");
+        assert_eq!("LoadImm r0, 0x4d2".parse::<Instruction>().unwrap(), load);
+
+        let store = Instruction::StoreIndU32 {
+            base: 1,
+            src: 7,
+            offset: -8,
+        };
+        assert_eq!(store.to_string(), "store_ind_u32 r1, r7, -8");
+        assert_eq!(Instruction::Trap.to_string(), "trap");
+        assert_eq!(
+            Instruction::parse_asm("  fallthrough  ; end of block").unwrap(),
+            Instruction::Fallthrough
+        );
+    }
+
+    #[test]
+    fn load_imm_64_accepts_negative_decimal() {
+        assert_eq!(
+            Instruction::parse_asm("load_imm_64 r2, -1").unwrap(),
+            Instruction::LoadImm64 {
+                reg: 2,
+                value: u64::MAX,
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        for line in [
+            "",
+            "frobnicate r1",
+            "add_64 r3, r1",
+            "add_64 r3, r1, r2, r4",
+            "add_64 r13, r1, r2",
+            "add_64 r3, 1, r2",
+            "load_imm r0, 0x80000000",
+            "load_imm r0, twelve",
+            "unknown 254, 0xfea",
+        ] {
+            let err = Instruction::parse_asm(line).expect_err(line);
+            assert!(matches!(err, Error::Asm(_)), "{line}: {err}");
+        }
+    }
+}
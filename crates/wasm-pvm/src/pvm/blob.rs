@@ -1,16 +1,40 @@
+use alloc::{format, vec, vec::Vec};
+
 use super::Instruction;
+use crate::{Error, Result};
+
+/// Byte offset of every instruction in `instructions` when encoded back to
+/// back, plus the total code length as a final entry (`len() + 1` entries).
+///
+/// This is the one place instruction offsets are computed; fixup resolution,
+/// the disassembler and [`ProgramBlob::offset_of`] all index into it.
+#[must_use]
+pub fn instruction_offsets(instructions: &[Instruction]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(instructions.len() + 1);
+    let mut offset = 0;
+    offsets.push(offset);
+    for instr in instructions {
+        offset += instr.encoded_len();
+        offsets.push(offset);
+    }
+    offsets
+}
 
 pub struct ProgramBlob {
     instructions: Vec<Instruction>,
     jump_table: Vec<u32>,
+    /// `instruction_offsets(&instructions)`, kept in sync by `new`.
+    offsets: Vec<usize>,
 }
 
 impl ProgramBlob {
     #[must_use]
     pub fn new(instructions: Vec<Instruction>) -> Self {
+        let offsets = instruction_offsets(&instructions);
         Self {
             instructions,
             jump_table: Vec::new(),
+            offsets,
         }
     }
 
@@ -25,6 +49,128 @@ impl ProgramBlob {
         &self.instructions
     }
 
+    #[must_use]
+    pub fn jump_table(&self) -> &[u32] {
+        &self.jump_table
+    }
+
+    /// Total length of the encoded code section in bytes.
+    #[must_use]
+    pub fn code_len(&self) -> usize {
+        self.offsets[self.instructions.len()]
+    }
+
+    /// Byte offset of instruction `idx` in the code section. `idx ==
+    /// instructions().len()` gives the end of the code; anything past that
+    /// is `None`.
+    #[must_use]
+    pub fn offset_of(&self, idx: usize) -> Option<usize> {
+        self.offsets.get(idx).copied()
+    }
+
+    /// Decode a blob produced by [`ProgramBlob::encode`].
+    ///
+    /// Instruction boundaries come from the instruction-start mask; each
+    /// instruction is decoded from exactly the bytes up to the next start.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(bytes, "program blob");
+        let jump_table_len = reader.var_u32("jump table length")?;
+        let item_len = usize::from(reader.take(1, "jump table item length")?[0]);
+        if item_len > 4 {
+            return Err(reader.malformed("jump table items wider than 4 bytes"));
+        }
+        let code_len = reader.var_u32("code length")?;
+
+        let mut jump_table = Vec::with_capacity(jump_table_len as usize);
+        for _ in 0..jump_table_len {
+            jump_table.push(reader.le_u32(item_len, "jump table")?);
+        }
+
+        let code_len = code_len as usize;
+        let code = reader.take(code_len, "code")?;
+        let mask = reader.take(code_len.div_ceil(8), "instruction mask")?;
+        reader.finish()?;
+
+        let starts: Vec<usize> = (0..code_len)
+            .filter(|pc| (mask[pc / 8] >> (pc % 8)) & 1 == 1)
+            .collect();
+        if code_len > 0 && starts.first() != Some(&0) {
+            return Err(reader.malformed("code does not start at an instruction"));
+        }
+        let mut instructions = Vec::with_capacity(starts.len());
+        for (i, &start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(code_len);
+            let (mut instr, consumed) = Instruction::decode(&code[start..end])?;
+            // `JumpFixed` shares `Jump`'s opcode; only its width tells them apart.
+            if let Instruction::Jump { offset } = instr
+                && consumed != instr.encode().len()
+            {
+                instr = Instruction::JumpFixed { offset };
+            }
+            if consumed != end - start || instr.encode().len() != consumed {
+                return Err(reader.malformed(&format!(
+                    "instruction at {start} is {consumed} bytes, mask says {}",
+                    end - start
+                )));
+            }
+            instructions.push(instr);
+        }
+
+        Ok(Self::new(instructions).with_jump_table(jump_table))
+    }
+
+    /// Code offsets that start a basic block: offset 0 plus every instruction
+    /// following a terminator, in ascending order. These are the only valid
+    /// targets for branches, jumps and jump-table entries.
+    #[must_use]
+    pub fn basic_block_starts(&self) -> Vec<u32> {
+        let mut starts = Vec::new();
+        let mut at_block_start = true;
+        for (instr, &offset) in self.instructions.iter().zip(&self.offsets) {
+            if at_block_start {
+                starts.push(offset as u32);
+            }
+            at_block_start = instr.is_terminating();
+        }
+        starts
+    }
+
+    /// Check that the code is well formed for a PVM: every instruction has a
+    /// known opcode, and every static jump/branch target and jump-table entry
+    /// is one of [`basic_block_starts`](Self::basic_block_starts). The error
+    /// names the first offending instruction (index and code offset) or
+    /// jump-table entry.
+    pub fn validate(&self) -> Result<()> {
+        let starts = self.basic_block_starts();
+        let is_block_start = |target: i64| {
+            u32::try_from(target).is_ok_and(|target| starts.binary_search(&target).is_ok())
+        };
+        for (idx, (instr, &at)) in self.instructions.iter().zip(&self.offsets).enumerate() {
+            if let Instruction::Unknown { opcode, .. } = instr {
+                return Err(Error::Internal(format!(
+                    "instruction {idx} at offset {at} has unknown opcode {opcode:#x}"
+                )));
+            }
+            if let Some(rel) = instr.jump_offset() {
+                let target = at as i64 + i64::from(rel);
+                if !is_block_start(target) {
+                    return Err(Error::Internal(format!(
+                        "instruction {idx} at offset {at} ({instr:?}) targets {target}, \
+                         which does not start a basic block"
+                    )));
+                }
+            }
+        }
+        for (entry, &target) in self.jump_table.iter().enumerate() {
+            if !is_block_start(i64::from(target)) {
+                return Err(Error::Internal(format!(
+                    "jump table entry {entry} targets {target}, which does not start a basic block"
+                )));
+            }
+        }
+        Ok(())
+    }
+
     #[must_use]
     pub fn encode(&self) -> Vec<u8> {
         let (code, mask) = self.encode_code_and_mask();
@@ -85,6 +231,77 @@ fn pack_mask(bits: &[bool]) -> Vec<u8> {
     packed
 }
 
+/// Sequential reader over an encoded SPI / program blob.
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    context: &'static str,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8], context: &'static str) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            context,
+        }
+    }
+
+    pub(crate) fn malformed(&self, what: &str) -> Error {
+        Error::Internal(format!("malformed {}: {what}", self.context))
+    }
+
+    pub(crate) fn take(&mut self, len: usize, what: &str) -> Result<&'a [u8]> {
+        let chunk = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or_else(|| self.malformed(&format!("truncated {what}")))?;
+        self.pos += len;
+        Ok(chunk)
+    }
+
+    pub(crate) fn var_u32(&mut self, what: &str) -> Result<u32> {
+        let (value, len) = decode_var_u32(&self.bytes[self.pos..])
+            .ok_or_else(|| self.malformed(&format!("bad {what}")))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Little-endian unsigned integer of `len` (≤ 4) bytes.
+    pub(crate) fn le_u32(&mut self, len: usize, what: &str) -> Result<u32> {
+        let mut raw = [0u8; 4];
+        raw[..len].copy_from_slice(self.take(len, what)?);
+        Ok(u32::from_le_bytes(raw))
+    }
+
+    /// Fail if anything is left after the last field.
+    pub(crate) fn finish(&self) -> Result<()> {
+        if self.pos == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(self.malformed(&format!("{} trailing bytes", self.bytes.len() - self.pos)))
+        }
+    }
+}
+
+/// Inverse of [`encode_var_u32`]: returns the value and the number of bytes
+/// read, or `None` if `bytes` is truncated or the value exceeds `u32`.
+pub(crate) fn decode_var_u32(bytes: &[u8]) -> Option<(u32, usize)> {
+    let first = *bytes.first()?;
+    let len = first.leading_ones() as usize;
+    let rest = bytes.get(1..=len)?;
+    let mut value = 0u64;
+    for (i, &b) in rest.iter().enumerate() {
+        value |= u64::from(b) << (8 * i);
+    }
+    if len < 8 {
+        let high = u64::from(first) & ((1 << (8 - len)) - 1);
+        value |= high << (8 * len);
+    }
+    u32::try_from(value).ok().map(|v| (v, len + 1))
+}
+
 pub(crate) fn encode_var_u32(value: u32) -> Vec<u8> {
     if value == 0 {
         return vec![0];
@@ -136,6 +353,60 @@ mod tests {
         assert_eq!(encode_var_u32(16384), vec![0xc0, 0x00, 0x40]);
     }
 
+    #[test]
+    fn test_decode_var_u32_roundtrip() {
+        for value in [
+            0,
+            1,
+            127,
+            128,
+            145,
+            300,
+            16383,
+            16384,
+            1 << 21,
+            1 << 28,
+            u32::MAX,
+        ] {
+            let encoded = encode_var_u32(value);
+            assert_eq!(
+                decode_var_u32(&encoded),
+                Some((value, encoded.len())),
+                "{value}"
+            );
+        }
+        assert_eq!(decode_var_u32(&[]), None);
+        assert_eq!(decode_var_u32(&[0x80]), None, "truncated");
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let blob = ProgramBlob::new(vec![
+            Instruction::JumpFixed { offset: 5 },
+            Instruction::LoadImm64 {
+                reg: 7,
+                value: 0xFEFD_0000,
+            },
+            Instruction::Jump { offset: -10 },
+            Instruction::LoadImm { reg: 2, value: -1 },
+            Instruction::BranchEqImm {
+                reg: 2,
+                value: 3,
+                offset: 300,
+            },
+            Instruction::Trap,
+        ])
+        .with_jump_table(vec![10, 0x1234]);
+        let encoded = blob.encode();
+
+        let decoded = ProgramBlob::decode(&encoded).expect("decode");
+        assert_eq!(decoded.instructions(), blob.instructions());
+        assert_eq!(decoded.jump_table(), blob.jump_table());
+        assert_eq!(decoded.encode(), encoded);
+
+        assert!(ProgramBlob::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
     #[test]
     fn test_pack_mask() {
         assert_eq!(pack_mask(&[true, false, false]), vec![0b0000_0001]);
@@ -145,6 +416,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_basic_block_starts() {
+        let blob = ProgramBlob::new(vec![
+            Instruction::MoveReg { dst: 2, src: 3 }, // 0..2
+            Instruction::Trap,                       // 2..3
+            Instruction::MoveReg { dst: 2, src: 3 }, // 3..5
+            Instruction::MoveReg { dst: 4, src: 2 }, // 5..7
+            Instruction::Fallthrough,                // 7..8
+            Instruction::Trap,                       // 8..9
+        ]);
+        // A trailing terminator doesn't open a block past the end of code.
+        assert_eq!(blob.basic_block_starts(), vec![0, 3, 8]);
+    }
+
+    #[test]
+    fn test_basic_block_starts_empty() {
+        assert!(ProgramBlob::new(Vec::new()).basic_block_starts().is_empty());
+    }
+
+    /// 0x0: `BranchEqImm` (4 bytes) to `target`, 0x4: `MoveReg`, 0x6: `Trap`,
+    /// 0x7: `Trap`. Blocks start at 0, 4 and 7.
+    fn branch_to(target: i32) -> ProgramBlob {
+        ProgramBlob::new(vec![
+            Instruction::BranchEqImm {
+                reg: 2,
+                value: 1,
+                offset: target,
+            },
+            Instruction::MoveReg { dst: 2, src: 3 },
+            Instruction::Trap,
+            Instruction::Trap,
+        ])
+    }
+
+    #[test]
+    fn validate_accepts_block_start_targets() {
+        assert!(branch_to(7).with_jump_table(vec![4, 0]).validate().is_ok());
+        assert!(ProgramBlob::new(Vec::new()).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_branch_into_a_block() {
+        let err = branch_to(6).validate().unwrap_err().to_string();
+        assert!(err.contains("instruction 0 at offset 0"), "{err}");
+        assert!(err.contains("targets 6"), "{err}");
+        assert!(branch_to(-1).validate().is_err());
+        // Past the end of the code.
+        assert!(branch_to(8).validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_jump_table_entry_mid_instruction() {
+        let err = branch_to(7)
+            .with_jump_table(vec![4, 5])
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("jump table entry 1 targets 5"), "{err}");
+    }
+
+    #[test]
+    fn validate_rejects_unknown_opcodes() {
+        let blob = ProgramBlob::new(vec![Instruction::Unknown {
+            opcode: 0xFE,
+            raw_bytes: vec![0xFE],
+        }]);
+        assert!(blob.validate().is_err());
+    }
+
+    #[test]
+    fn test_offsets_match_encoded_code() {
+        let instructions = crate::pvm::sample_instructions();
+        let blob = ProgramBlob::new(instructions.clone());
+        let (code, mask) = blob.encode_code_and_mask();
+        assert_eq!(blob.code_len(), code.len());
+
+        let mut summed = 0;
+        for (idx, instr) in instructions.iter().enumerate() {
+            assert_eq!(blob.offset_of(idx), Some(summed), "{instr:?}");
+            assert_eq!((mask[summed / 8] >> (summed % 8)) & 1, 1, "{instr:?}");
+            summed += instr.encode().len();
+        }
+        assert_eq!(blob.offset_of(instructions.len()), Some(code.len()));
+        assert_eq!(blob.offset_of(instructions.len() + 1), None);
+        assert_eq!(ProgramBlob::new(Vec::new()).code_len(), 0);
+    }
+
     #[test]
     fn test_load_imm64_mask() {
         // LoadImm64 encodes to 10 bytes, Trap to 1 byte
@@ -0,0 +1,117 @@
+// Human-readable listing of a program blob, for inspecting deployed bytes.
+
+use alloc::collections::BTreeSet;
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use super::ProgramBlob;
+
+/// Render `blob` as one instruction per line, prefixed with its code offset.
+///
+/// Every offset targeted by a jump, branch or jump-table entry gets an
+/// `L_0x..:` label line, static jumps and branches are annotated with their
+/// absolute target, and a blank line separates basic blocks (after each
+/// terminating instruction). Bytes that did not decode to a known opcode
+/// appear as `Unknown { .. }` lines instead of failing.
+#[must_use]
+pub fn disassemble(blob: &ProgramBlob) -> String {
+    let instructions = blob.instructions();
+    let offsets: Vec<i64> = (0..instructions.len())
+        .map(|idx| blob.offset_of(idx).unwrap_or_default() as i64)
+        .collect();
+
+    let mut targets: BTreeSet<i64> = blob.jump_table().iter().map(|&t| i64::from(t)).collect();
+    for (instr, &at) in instructions.iter().zip(&offsets) {
+        if let Some(rel) = instr.jump_offset() {
+            targets.insert(at + i64::from(rel));
+        }
+    }
+
+    let mut out = String::new();
+    if !blob.jump_table().is_empty() {
+        out.push_str("; jump table\n");
+        for (i, target) in blob.jump_table().iter().enumerate() {
+            // Dynamic jump addresses are `2 * (index + 1)`.
+            let _ = writeln!(out, ";   {:#x} -> L_{target:#x}", 2 * (i + 1));
+        }
+        out.push('\n');
+    }
+    for (instr, &at) in instructions.iter().zip(&offsets) {
+        if targets.contains(&at) {
+            let _ = writeln!(out, "L_{at:#x}:");
+        }
+        let _ = write!(out, "  {at:#06x}  {instr:?}");
+        if let Some(rel) = instr.jump_offset() {
+            let _ = write!(out, "  ; -> {:#x}", at + i64::from(rel));
+        }
+        out.push('\n');
+        if instr.is_terminating() {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pvm::{Instruction, sample_instructions};
+
+    #[test]
+    fn labels_branch_and_jump_table_targets() {
+        // 0x0: LoadImm (3 bytes), 0x3: BranchEqImm (4 bytes), 0x7: Trap, 0x8: Fallthrough.
+        let blob = ProgramBlob::new(vec![
+            Instruction::LoadImm { reg: 2, value: 1 },
+            Instruction::BranchEqImm {
+                reg: 2,
+                value: 1,
+                offset: 5,
+            },
+            Instruction::Trap,
+            Instruction::Fallthrough,
+        ])
+        .with_jump_table(vec![7]);
+
+        let text = disassemble(&blob);
+        assert_eq!(
+            text,
+            "; jump table\n\
+             ;   0x2 -> L_0x7\n\
+             \n  \
+             0x0000  LoadImm { reg: 2, value: 1 }\n  \
+             0x0003  BranchEqImm { reg: 2, value: 1, offset: 5 }  ; -> 0x8\n\
+             \n\
+             L_0x7:\n  \
+             0x0007  Trap\n\
+             \n\
+             L_0x8:\n  \
+             0x0008  Fallthrough\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn unknown_opcodes_are_listed_not_rejected() {
+        let blob = ProgramBlob::decode(
+            &ProgramBlob::new(vec![Instruction::Unknown {
+                opcode: 0xFE,
+                raw_bytes: vec![0xFE],
+            }])
+            .encode(),
+        )
+        .expect("decode");
+        assert!(disassemble(&blob).contains("Unknown { opcode: 254"));
+    }
+
+    #[test]
+    fn sample_listing_survives_an_encode_decode_round_trip() {
+        let blob = ProgramBlob::new(sample_instructions());
+        let decoded = ProgramBlob::decode(&blob.encode()).expect("decode");
+        let text = disassemble(&decoded);
+        assert_eq!(text, disassemble(&blob));
+        assert_eq!(
+            text.lines().filter(|l| l.starts_with("  0x")).count(),
+            sample_instructions().len()
+        );
+    }
+}
@@ -0,0 +1,368 @@
+// Static gas estimate for a program blob, for fee estimation without running
+// the VM.
+//
+// Every opcode has a fixed cost from a small set of classes (plain ALU work,
+// memory access, multiply, divide, control flow, host calls). The estimate
+// never executes anything, so it cannot know how often a loop runs or where an
+// indirect jump goes: loops are counted once and dynamic jumps end a path.
+
+use alloc::{vec, vec::Vec};
+
+use super::{Instruction, Opcode, ProgramBlob};
+
+/// Moves, immediate loads, add/sub, bitwise ops, shifts, compares and the
+/// other single-step ALU instructions. Also `Trap` and `Fallthrough`.
+pub const BASE_COST: u64 = 1;
+/// Loads and stores, absolute or register-indirect.
+pub const MEMORY_COST: u64 = 2;
+/// Multiplies, including the upper-half `MulUpper*` forms.
+pub const MUL_COST: u64 = 3;
+/// Division and remainder, signed and unsigned.
+pub const DIV_COST: u64 = 10;
+/// Jumps and branches, taken or not, direct or indirect.
+pub const BRANCH_COST: u64 = 2;
+/// `ecalli` and `sbrk`: the instruction itself, not the host's work.
+pub const HOST_CALL_COST: u64 = 10;
+
+/// Cost of each opcode, indexed by opcode byte. Bytes that are not an opcode
+/// cost [`BASE_COST`], the same as the `Trap` they execute as.
+pub const OPCODE_COSTS: [u64; 256] = {
+    let mut costs = [BASE_COST; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        if let Some(opcode) = Opcode::from_u8(byte as u8) {
+            costs[byte] = opcode_cost(opcode);
+        }
+        byte += 1;
+    }
+    costs
+};
+
+const fn opcode_cost(opcode: Opcode) -> u64 {
+    use Opcode as O;
+    match opcode {
+        O::Ecalli | O::Sbrk => HOST_CALL_COST,
+        O::Jump
+        | O::JumpInd
+        | O::LoadImmJump
+        | O::LoadImmJumpInd
+        | O::BranchEqImm
+        | O::BranchNeImm
+        | O::BranchLtUImm
+        | O::BranchLeUImm
+        | O::BranchGeUImm
+        | O::BranchGtUImm
+        | O::BranchLtSImm
+        | O::BranchLeSImm
+        | O::BranchGeSImm
+        | O::BranchGtSImm
+        | O::BranchEq
+        | O::BranchNe
+        | O::BranchLtU
+        | O::BranchLtS
+        | O::BranchGeU
+        | O::BranchGeS => BRANCH_COST,
+        O::StoreImmU8
+        | O::StoreImmU16
+        | O::StoreImmU32
+        | O::StoreImmU64
+        | O::LoadU8
+        | O::LoadI8
+        | O::LoadU16
+        | O::LoadI16
+        | O::LoadU32
+        | O::LoadI32
+        | O::LoadU64
+        | O::StoreU8
+        | O::StoreU16
+        | O::StoreU32
+        | O::StoreU64
+        | O::StoreImmIndU8
+        | O::StoreImmIndU16
+        | O::StoreImmIndU32
+        | O::StoreImmIndU64
+        | O::StoreIndU8
+        | O::StoreIndU16
+        | O::StoreIndU32
+        | O::StoreIndU64
+        | O::LoadIndU8
+        | O::LoadIndI8
+        | O::LoadIndU16
+        | O::LoadIndI16
+        | O::LoadIndU32
+        | O::LoadIndI32
+        | O::LoadIndU64 => MEMORY_COST,
+        O::Mul32
+        | O::Mul64
+        | O::MulImm32
+        | O::MulImm64
+        | O::MulUpperSS
+        | O::MulUpperUU
+        | O::MulUpperSU => MUL_COST,
+        O::DivU32
+        | O::DivS32
+        | O::RemU32
+        | O::RemS32
+        | O::DivU64
+        | O::DivS64
+        | O::RemU64
+        | O::RemS64 => DIV_COST,
+        _ => BASE_COST,
+    }
+}
+
+/// Cost of one instruction under [`OPCODE_COSTS`].
+#[must_use]
+pub fn instruction_cost(instr: &Instruction) -> u64 {
+    instr
+        .encode()
+        .first()
+        .map_or(BASE_COST, |&byte| OPCODE_COSTS[usize::from(byte)])
+}
+
+/// Result of [`estimate_gas`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// Number of instructions in the blob.
+    pub instruction_count: usize,
+    /// Sum of every instruction's cost: executing each instruction once.
+    pub static_cost: u64,
+    /// Cost of the most expensive path through the basic-block graph that
+    /// only follows forward edges, starting from any block. Backward jumps
+    /// and branches (loop back-edges) are not followed, so a loop body is
+    /// counted once however many times it runs, and a path ends at any
+    /// indirect jump (returns, `call_indirect`) or `Trap`.
+    pub max_path_cost: u64,
+}
+
+/// Estimate the gas needed to run `blob` from its instruction mix alone.
+///
+/// This is a static estimate, not a bound: loops count once, so a program
+/// that iterates can cost far more than either figure. Use it to compare
+/// builds or price straight-line code; run the program for an exact count.
+#[must_use]
+pub fn estimate_gas(blob: &ProgramBlob) -> GasEstimate {
+    let instructions = blob.instructions();
+    let costs: Vec<u64> = instructions.iter().map(instruction_cost).collect();
+
+    // Blocks as `[first, last]` instruction indices, in code order.
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    let mut first = 0;
+    for (idx, instr) in instructions.iter().enumerate() {
+        if instr.is_terminating() || idx + 1 == instructions.len() {
+            blocks.push((first, idx));
+            first = idx + 1;
+        }
+    }
+    let block_at = |offset: i64| {
+        blocks.binary_search_by_key(&offset, |&(first, _)| {
+            blob.offset_of(first).unwrap_or_default() as i64
+        })
+    };
+
+    // Longest path over forward edges, filled from the last block back so
+    // every successor is already done.
+    let mut path_cost = vec![0u64; blocks.len()];
+    for (block, &(first, last)) in blocks.iter().enumerate().rev() {
+        let terminator = &instructions[last];
+        let mut successors = Vec::with_capacity(2);
+        if let Some(rel) = terminator.jump_offset() {
+            let at = blob.offset_of(last).unwrap_or_default() as i64;
+            if let Ok(target) = block_at(at + i64::from(rel)) {
+                successors.push(target);
+            }
+        }
+        let falls_through = !terminator.is_terminating()
+            || matches!(terminator, Instruction::Fallthrough)
+            || terminator.invert_branch().is_some();
+        if falls_through {
+            successors.push(block + 1);
+        }
+        let tail = successors
+            .into_iter()
+            .filter(|&succ| succ > block && succ < blocks.len())
+            .map(|succ| path_cost[succ])
+            .max()
+            .unwrap_or(0);
+        path_cost[block] = costs[first..=last].iter().sum::<u64>() + tail;
+    }
+
+    GasEstimate {
+        instruction_count: instructions.len(),
+        static_cost: costs.iter().sum(),
+        max_path_cost: path_cost.into_iter().max().unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `instrs` with the jump at index `from` retargeted to instruction `to`.
+    /// The placeholder offset must have the final offset's encoded width.
+    fn retarget(mut instrs: Vec<Instruction>, from: usize, to: usize) -> Vec<Instruction> {
+        let offsets = super::super::instruction_offsets(&instrs);
+        let rel = i32::try_from(offsets[to] as i64 - offsets[from] as i64).unwrap();
+        instrs[from] = match instrs[from] {
+            Instruction::Jump { .. } => Instruction::Jump { offset: rel },
+            Instruction::BranchEqImm { reg, value, .. } => Instruction::BranchEqImm {
+                reg,
+                value,
+                offset: rel,
+            },
+            Instruction::BranchLtUImm { reg, value, .. } => Instruction::BranchLtUImm {
+                reg,
+                value,
+                offset: rel,
+            },
+            ref other => panic!("not a jump: {other:?}"),
+        };
+        instrs
+    }
+
+    #[test]
+    fn cost_classes() {
+        let cost = |op: Opcode| OPCODE_COSTS[op as usize];
+        assert!(Opcode::all().all(|op| cost(op) >= BASE_COST));
+        assert_eq!(cost(Opcode::Add64), BASE_COST);
+        assert!(cost(Opcode::Mul64) > cost(Opcode::Add64));
+        assert!(cost(Opcode::DivU64) > cost(Opcode::Mul64));
+        assert_eq!(cost(Opcode::LoadImm64), BASE_COST);
+        assert_eq!(cost(Opcode::LoadIndU32), MEMORY_COST);
+        for op in Opcode::all().filter(|op| op.is_terminating()) {
+            let expected = match op {
+                Opcode::Trap | Opcode::Fallthrough => BASE_COST,
+                _ => BRANCH_COST,
+            };
+            assert_eq!(cost(op), expected, "{op:?}");
+        }
+        // Not an opcode.
+        assert_eq!(OPCODE_COSTS[255], BASE_COST);
+    }
+
+    #[test]
+    fn straight_line_sums_every_instruction() {
+        let blob = ProgramBlob::new(vec![
+            Instruction::LoadImm { reg: 2, value: 7 },
+            Instruction::Add64 {
+                dst: 3,
+                src1: 2,
+                src2: 2,
+            },
+            Instruction::Mul64 {
+                dst: 3,
+                src1: 3,
+                src2: 2,
+            },
+            Instruction::DivU64 {
+                dst: 4,
+                src1: 3,
+                src2: 2,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 4,
+                offset: 0,
+            },
+            Instruction::Ecalli { index: 1 },
+            Instruction::Trap,
+        ]);
+        let expected = BASE_COST * 3 + MUL_COST + DIV_COST + MEMORY_COST + HOST_CALL_COST;
+        assert_eq!(
+            estimate_gas(&blob),
+            GasEstimate {
+                instruction_count: 7,
+                static_cost: expected,
+                max_path_cost: expected,
+            }
+        );
+    }
+
+    #[test]
+    fn loops_count_once() {
+        // r2 = 0; loop: r2 += 1; if r2 < 100 goto loop; trap
+        let instrs = retarget(
+            vec![
+                Instruction::LoadImm { reg: 2, value: 0 },
+                Instruction::Fallthrough,
+                Instruction::AddImm32 {
+                    dst: 2,
+                    src: 2,
+                    value: 1,
+                },
+                Instruction::BranchLtUImm {
+                    reg: 2,
+                    value: 100,
+                    offset: -1,
+                },
+                Instruction::Trap,
+            ],
+            3,
+            2,
+        );
+        let estimate = estimate_gas(&ProgramBlob::new(instrs));
+        let expected = BASE_COST * 4 + BRANCH_COST;
+        assert_eq!(estimate.static_cost, expected);
+        assert_eq!(estimate.max_path_cost, expected);
+    }
+
+    #[test]
+    fn max_path_takes_the_costlier_arm() {
+        // if r2 == 0 goto else; r3 = r2 / r4; goto join;
+        // else: r3 = r2 + r4; join: trap
+        let instrs = vec![
+            Instruction::BranchEqImm {
+                reg: 2,
+                value: 0,
+                offset: 1,
+            },
+            Instruction::DivU64 {
+                dst: 3,
+                src1: 2,
+                src2: 4,
+            },
+            Instruction::Jump { offset: 1 },
+            Instruction::Add64 {
+                dst: 3,
+                src1: 2,
+                src2: 4,
+            },
+            Instruction::Fallthrough,
+            Instruction::Trap,
+        ];
+        let instrs = retarget(retarget(instrs, 0, 3), 2, 5);
+        let estimate = estimate_gas(&ProgramBlob::new(instrs));
+        assert_eq!(
+            estimate.static_cost,
+            BRANCH_COST * 2 + DIV_COST + BASE_COST * 3
+        );
+        assert_eq!(
+            estimate.max_path_cost,
+            BRANCH_COST * 2 + DIV_COST + BASE_COST
+        );
+    }
+
+    #[test]
+    fn indirect_jumps_end_a_path() {
+        let blob = ProgramBlob::new(vec![
+            Instruction::Mul32 {
+                dst: 2,
+                src1: 2,
+                src2: 2,
+            },
+            Instruction::JumpInd { reg: 0, offset: 0 },
+            Instruction::Trap,
+        ]);
+        let estimate = estimate_gas(&blob);
+        assert_eq!(estimate.static_cost, MUL_COST + BRANCH_COST + BASE_COST);
+        assert_eq!(estimate.max_path_cost, MUL_COST + BRANCH_COST);
+    }
+
+    #[test]
+    fn empty_blob() {
+        let estimate = estimate_gas(&ProgramBlob::new(Vec::new()));
+        assert_eq!(estimate.instruction_count, 0);
+        assert_eq!(estimate.static_cost, 0);
+        assert_eq!(estimate.max_path_cost, 0);
+    }
+}
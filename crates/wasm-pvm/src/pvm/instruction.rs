@@ -1,4 +1,6 @@
-use super::Opcode;
+use alloc::{format, string::ToString, vec, vec::Vec};
+
+use super::{EncodingFamily, Opcode};
 use crate::{Error, Result};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -733,6 +735,10 @@ impl Instruction {
     ///
     /// Returns `(instruction, consumed_bytes)`.
     ///
+    /// Dispatches on [`Opcode::encoding_family`]: the family fixes the operand
+    /// layout, and only the mapping of operands onto `Instruction` fields is
+    /// per-opcode.
+    ///
     /// For encodings where the last immediate does not carry an explicit length
     /// (`OneImm`, `OneRegOneImm`, `TwoRegOneImm`, `TwoImm`, `OneRegTwoImm`,
     /// `TwoRegTwoImm`), this decoder consumes the remaining bytes as that
@@ -745,123 +751,125 @@ impl Instruction {
         }
 
         let opcode = bytes[0];
+        let Some(op) = Opcode::from_u8(opcode) else {
+            return Ok((
+                Self::Unknown {
+                    opcode,
+                    raw_bytes: bytes.to_vec(),
+                },
+                bytes.len(),
+            ));
+        };
+
+        let family = op.encoding_family();
+        if bytes.len() < family.min_len() {
+            return Err(Error::Internal(format!(
+                "{family:?}: expected at least {} bytes, got {}",
+                family.min_len(),
+                bytes.len()
+            )));
+        }
+        let mismatch = || {
+            Error::Internal(format!(
+                "{family:?} decode dispatch mismatch for opcode {opcode}"
+            ))
+        };
+
+        match family {
+            EncodingFamily::NoArgs => {
+                let instruction = match op {
+                    Opcode::Trap => Self::Trap,
+                    Opcode::Fallthrough => Self::Fallthrough,
+                    _ => return Err(mismatch()),
+                };
+                Ok((instruction, 1))
+            }
 
-        match opcode {
-            op if op == Opcode::Trap as u8 => Ok((Self::Trap, 1)),
-            op if op == Opcode::Fallthrough as u8 => Ok((Self::Fallthrough, 1)),
+            EncodingFamily::OneImm => {
+                let index = decode_imm_unsigned(&bytes[1..], "Ecalli index")?;
+                match op {
+                    Opcode::Ecalli => Ok((Self::Ecalli { index }, bytes.len())),
+                    _ => Err(mismatch()),
+                }
+            }
 
-            op if op == Opcode::LoadImm64 as u8 => {
-                ensure_min_len(bytes, 10, "LoadImm64")?;
+            EncodingFamily::OneRegExtImm => {
                 let reg = bytes[1] & 0x0F;
                 let mut raw = [0u8; 8];
                 raw.copy_from_slice(&bytes[2..10]);
-                Ok((
-                    Self::LoadImm64 {
-                        reg,
-                        value: u64::from_le_bytes(raw),
-                    },
-                    10,
-                ))
+                let value = u64::from_le_bytes(raw);
+                match op {
+                    Opcode::LoadImm64 => Ok((Self::LoadImm64 { reg, value }, 10)),
+                    _ => Err(mismatch()),
+                }
             }
 
-            op if op == Opcode::Jump as u8 => {
+            EncodingFamily::OneOff => {
                 let offset = decode_imm_signed(&bytes[1..], "Jump offset")?;
-                Ok((Self::Jump { offset }, bytes.len()))
+                match op {
+                    Opcode::Jump => Ok((Self::Jump { offset }, bytes.len())),
+                    _ => Err(mismatch()),
+                }
             }
 
-            op if op == Opcode::Ecalli as u8 => {
-                let index = decode_imm_unsigned(&bytes[1..], "Ecalli index")?;
-                Ok((Self::Ecalli { index }, bytes.len()))
-            }
-
-            op if op == Opcode::LoadImm as u8
-                || op == Opcode::JumpInd as u8
-                || op == Opcode::LoadU8 as u8
-                || op == Opcode::LoadI8 as u8
-                || op == Opcode::LoadU16 as u8
-                || op == Opcode::LoadI16 as u8
-                || op == Opcode::LoadU32 as u8
-                || op == Opcode::LoadI32 as u8
-                || op == Opcode::LoadU64 as u8
-                || op == Opcode::StoreU8 as u8
-                || op == Opcode::StoreU16 as u8
-                || op == Opcode::StoreU32 as u8
-                || op == Opcode::StoreU64 as u8 =>
-            {
-                ensure_min_len(bytes, 2, "OneRegOneImm")?;
+            EncodingFamily::OneRegOneImm => {
                 let reg = bytes[1] & 0x0F;
                 let value = decode_imm_signed(&bytes[2..], "OneRegOneImm immediate")?;
 
                 let instruction = match op {
-                    op if op == Opcode::LoadImm as u8 => Self::LoadImm { reg, value },
-                    op if op == Opcode::JumpInd as u8 => Self::JumpInd { reg, offset: value },
-                    op if op == Opcode::LoadU8 as u8 => Self::LoadU8 {
+                    Opcode::LoadImm => Self::LoadImm { reg, value },
+                    Opcode::JumpInd => Self::JumpInd { reg, offset: value },
+                    Opcode::LoadU8 => Self::LoadU8 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::LoadI8 as u8 => Self::LoadI8 {
+                    Opcode::LoadI8 => Self::LoadI8 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::LoadU16 as u8 => Self::LoadU16 {
+                    Opcode::LoadU16 => Self::LoadU16 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::LoadI16 as u8 => Self::LoadI16 {
+                    Opcode::LoadI16 => Self::LoadI16 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::LoadU32 as u8 => Self::LoadU32 {
+                    Opcode::LoadU32 => Self::LoadU32 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::LoadI32 as u8 => Self::LoadI32 {
+                    Opcode::LoadI32 => Self::LoadI32 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::LoadU64 as u8 => Self::LoadU64 {
+                    Opcode::LoadU64 => Self::LoadU64 {
                         dst: reg,
                         address: value,
                     },
-                    op if op == Opcode::StoreU8 as u8 => Self::StoreU8 {
+                    Opcode::StoreU8 => Self::StoreU8 {
                         src: reg,
                         address: value,
                     },
-                    op if op == Opcode::StoreU16 as u8 => Self::StoreU16 {
+                    Opcode::StoreU16 => Self::StoreU16 {
                         src: reg,
                         address: value,
                     },
-                    op if op == Opcode::StoreU32 as u8 => Self::StoreU32 {
+                    Opcode::StoreU32 => Self::StoreU32 {
                         src: reg,
                         address: value,
                     },
-                    op if op == Opcode::StoreU64 as u8 => Self::StoreU64 {
+                    Opcode::StoreU64 => Self::StoreU64 {
                         src: reg,
                         address: value,
                     },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "OneRegOneImm decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, bytes.len()))
             }
 
-            op if op == Opcode::LoadImmJump as u8
-                || op == Opcode::BranchEqImm as u8
-                || op == Opcode::BranchNeImm as u8
-                || op == Opcode::BranchLtUImm as u8
-                || op == Opcode::BranchLeUImm as u8
-                || op == Opcode::BranchGeUImm as u8
-                || op == Opcode::BranchGtUImm as u8
-                || op == Opcode::BranchLtSImm as u8
-                || op == Opcode::BranchLeSImm as u8
-                || op == Opcode::BranchGeSImm as u8
-                || op == Opcode::BranchGtSImm as u8 =>
-            {
-                ensure_min_len(bytes, 2, "OneRegOneImmOneOff")?;
+            EncodingFamily::OneRegOneImmOneOff => {
                 let reg = bytes[1] & 0x0F;
                 let imm_len = (bytes[1] >> 4) as usize;
                 if imm_len > 4 {
@@ -876,55 +884,24 @@ impl Instruction {
                 let offset = decode_imm_signed(&bytes[imm_end..], "OneRegOneImmOneOff offset")?;
 
                 let instruction = match op {
-                    op if op == Opcode::LoadImmJump as u8 => {
-                        Self::LoadImmJump { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchEqImm as u8 => {
-                        Self::BranchEqImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchNeImm as u8 => {
-                        Self::BranchNeImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchLtUImm as u8 => {
-                        Self::BranchLtUImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchLeUImm as u8 => {
-                        Self::BranchLeUImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchGeUImm as u8 => {
-                        Self::BranchGeUImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchGtUImm as u8 => {
-                        Self::BranchGtUImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchLtSImm as u8 => {
-                        Self::BranchLtSImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchLeSImm as u8 => {
-                        Self::BranchLeSImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchGeSImm as u8 => {
-                        Self::BranchGeSImm { reg, value, offset }
-                    }
-                    op if op == Opcode::BranchGtSImm as u8 => {
-                        Self::BranchGtSImm { reg, value, offset }
-                    }
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "OneRegOneImmOneOff decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    Opcode::LoadImmJump => Self::LoadImmJump { reg, value, offset },
+                    Opcode::BranchEqImm => Self::BranchEqImm { reg, value, offset },
+                    Opcode::BranchNeImm => Self::BranchNeImm { reg, value, offset },
+                    Opcode::BranchLtUImm => Self::BranchLtUImm { reg, value, offset },
+                    Opcode::BranchLeUImm => Self::BranchLeUImm { reg, value, offset },
+                    Opcode::BranchGeUImm => Self::BranchGeUImm { reg, value, offset },
+                    Opcode::BranchGtUImm => Self::BranchGtUImm { reg, value, offset },
+                    Opcode::BranchLtSImm => Self::BranchLtSImm { reg, value, offset },
+                    Opcode::BranchLeSImm => Self::BranchLeSImm { reg, value, offset },
+                    Opcode::BranchGeSImm => Self::BranchGeSImm { reg, value, offset },
+                    Opcode::BranchGtSImm => Self::BranchGtSImm { reg, value, offset },
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, bytes.len()))
             }
 
-            op if op == Opcode::StoreImmU8 as u8
-                || op == Opcode::StoreImmU16 as u8
-                || op == Opcode::StoreImmU32 as u8
-                || op == Opcode::StoreImmU64 as u8 =>
-            {
-                ensure_min_len(bytes, 2, "TwoImm")?;
+            EncodingFamily::TwoImm => {
                 let imm1_len = (bytes[1] & 0x0F) as usize;
                 if imm1_len > 4 {
                     return Err(Error::Internal(format!(
@@ -939,26 +916,17 @@ impl Instruction {
                 let value = decode_imm_signed(&bytes[imm1_end..], "TwoImm value")?;
 
                 let instruction = match op {
-                    op if op == Opcode::StoreImmU8 as u8 => Self::StoreImmU8 { address, value },
-                    op if op == Opcode::StoreImmU16 as u8 => Self::StoreImmU16 { address, value },
-                    op if op == Opcode::StoreImmU32 as u8 => Self::StoreImmU32 { address, value },
-                    op if op == Opcode::StoreImmU64 as u8 => Self::StoreImmU64 { address, value },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "TwoImm decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    Opcode::StoreImmU8 => Self::StoreImmU8 { address, value },
+                    Opcode::StoreImmU16 => Self::StoreImmU16 { address, value },
+                    Opcode::StoreImmU32 => Self::StoreImmU32 { address, value },
+                    Opcode::StoreImmU64 => Self::StoreImmU64 { address, value },
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, bytes.len()))
             }
 
-            op if op == Opcode::StoreImmIndU8 as u8
-                || op == Opcode::StoreImmIndU16 as u8
-                || op == Opcode::StoreImmIndU32 as u8
-                || op == Opcode::StoreImmIndU64 as u8 =>
-            {
-                ensure_min_len(bytes, 2, "OneRegTwoImm")?;
+            EncodingFamily::OneRegTwoImm => {
                 let base = bytes[1] & 0x0F;
                 let imm1_len = (bytes[1] >> 4) as usize;
                 if imm1_len > 4 {
@@ -973,384 +941,182 @@ impl Instruction {
                 let value = decode_imm_signed(&bytes[imm1_end..], "OneRegTwoImm value")?;
 
                 let instruction = match op {
-                    op if op == Opcode::StoreImmIndU8 as u8 => Self::StoreImmIndU8 {
+                    Opcode::StoreImmIndU8 => Self::StoreImmIndU8 {
                         base,
                         offset,
                         value,
                     },
-                    op if op == Opcode::StoreImmIndU16 as u8 => Self::StoreImmIndU16 {
+                    Opcode::StoreImmIndU16 => Self::StoreImmIndU16 {
                         base,
                         offset,
                         value,
                     },
-                    op if op == Opcode::StoreImmIndU32 as u8 => Self::StoreImmIndU32 {
+                    Opcode::StoreImmIndU32 => Self::StoreImmIndU32 {
                         base,
                         offset,
                         value,
                     },
-                    op if op == Opcode::StoreImmIndU64 as u8 => Self::StoreImmIndU64 {
+                    Opcode::StoreImmIndU64 => Self::StoreImmIndU64 {
                         base,
                         offset,
                         value,
                     },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "OneRegTwoImm decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, bytes.len()))
             }
 
-            op if op == Opcode::MoveReg as u8
-                || op == Opcode::Sbrk as u8
-                || op == Opcode::CountSetBits64 as u8
-                || op == Opcode::CountSetBits32 as u8
-                || op == Opcode::LeadingZeroBits64 as u8
-                || op == Opcode::LeadingZeroBits32 as u8
-                || op == Opcode::TrailingZeroBits64 as u8
-                || op == Opcode::TrailingZeroBits32 as u8
-                || op == Opcode::SignExtend8 as u8
-                || op == Opcode::SignExtend16 as u8
-                || op == Opcode::ZeroExtend16 as u8
-                || op == Opcode::ReverseBytes as u8 =>
-            {
-                ensure_min_len(bytes, 2, "TwoReg")?;
+            EncodingFamily::TwoReg => {
                 let src = (bytes[1] >> 4) & 0x0F;
                 let dst = bytes[1] & 0x0F;
 
                 let instruction = match op {
-                    op if op == Opcode::MoveReg as u8 => Self::MoveReg { dst, src },
-                    op if op == Opcode::Sbrk as u8 => Self::Sbrk { dst, src },
-                    op if op == Opcode::CountSetBits64 as u8 => Self::CountSetBits64 { dst, src },
-                    op if op == Opcode::CountSetBits32 as u8 => Self::CountSetBits32 { dst, src },
-                    op if op == Opcode::LeadingZeroBits64 as u8 => {
-                        Self::LeadingZeroBits64 { dst, src }
-                    }
-                    op if op == Opcode::LeadingZeroBits32 as u8 => {
-                        Self::LeadingZeroBits32 { dst, src }
-                    }
-                    op if op == Opcode::TrailingZeroBits64 as u8 => {
-                        Self::TrailingZeroBits64 { dst, src }
-                    }
-                    op if op == Opcode::TrailingZeroBits32 as u8 => {
-                        Self::TrailingZeroBits32 { dst, src }
-                    }
-                    op if op == Opcode::SignExtend8 as u8 => Self::SignExtend8 { dst, src },
-                    op if op == Opcode::SignExtend16 as u8 => Self::SignExtend16 { dst, src },
-                    op if op == Opcode::ZeroExtend16 as u8 => Self::ZeroExtend16 { dst, src },
-                    op if op == Opcode::ReverseBytes as u8 => Self::ReverseBytes { dst, src },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "TwoReg decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    Opcode::MoveReg => Self::MoveReg { dst, src },
+                    Opcode::Sbrk => Self::Sbrk { dst, src },
+                    Opcode::CountSetBits64 => Self::CountSetBits64 { dst, src },
+                    Opcode::CountSetBits32 => Self::CountSetBits32 { dst, src },
+                    Opcode::LeadingZeroBits64 => Self::LeadingZeroBits64 { dst, src },
+                    Opcode::LeadingZeroBits32 => Self::LeadingZeroBits32 { dst, src },
+                    Opcode::TrailingZeroBits64 => Self::TrailingZeroBits64 { dst, src },
+                    Opcode::TrailingZeroBits32 => Self::TrailingZeroBits32 { dst, src },
+                    Opcode::SignExtend8 => Self::SignExtend8 { dst, src },
+                    Opcode::SignExtend16 => Self::SignExtend16 { dst, src },
+                    Opcode::ZeroExtend16 => Self::ZeroExtend16 { dst, src },
+                    Opcode::ReverseBytes => Self::ReverseBytes { dst, src },
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, 2))
             }
 
-            op if op == Opcode::AddImm32 as u8
-                || op == Opcode::AddImm64 as u8
-                || op == Opcode::AndImm as u8
-                || op == Opcode::XorImm as u8
-                || op == Opcode::OrImm as u8
-                || op == Opcode::MulImm32 as u8
-                || op == Opcode::MulImm64 as u8
-                || op == Opcode::SetLtUImm as u8
-                || op == Opcode::SetLtSImm as u8
-                || op == Opcode::ShloLImm32 as u8
-                || op == Opcode::ShloRImm32 as u8
-                || op == Opcode::SharRImm32 as u8
-                || op == Opcode::ShloLImm64 as u8
-                || op == Opcode::ShloRImm64 as u8
-                || op == Opcode::SharRImm64 as u8
-                || op == Opcode::NegAddImm32 as u8
-                || op == Opcode::NegAddImm64 as u8
-                || op == Opcode::SetGtUImm as u8
-                || op == Opcode::SetGtSImm as u8
-                || op == Opcode::CmovIzImm as u8
-                || op == Opcode::CmovNzImm as u8
-                || op == Opcode::LoadIndU8 as u8
-                || op == Opcode::LoadIndI8 as u8
-                || op == Opcode::LoadIndU16 as u8
-                || op == Opcode::LoadIndI16 as u8
-                || op == Opcode::LoadIndU32 as u8
-                || op == Opcode::LoadIndI32 as u8
-                || op == Opcode::LoadIndU64 as u8
-                || op == Opcode::StoreIndU8 as u8
-                || op == Opcode::StoreIndU16 as u8
-                || op == Opcode::StoreIndU32 as u8
-                || op == Opcode::StoreIndU64 as u8
-                || op == Opcode::ShloLImmAlt32 as u8
-                || op == Opcode::ShloRImmAlt32 as u8
-                || op == Opcode::SharRImmAlt32 as u8
-                || op == Opcode::ShloLImmAlt64 as u8
-                || op == Opcode::ShloRImmAlt64 as u8
-                || op == Opcode::SharRImmAlt64 as u8
-                || op == Opcode::RotRImm64 as u8
-                || op == Opcode::RotRImmAlt64 as u8
-                || op == Opcode::RotRImm32 as u8
-                || op == Opcode::RotRImmAlt32 as u8 =>
-            {
-                ensure_min_len(bytes, 2, "TwoRegOneImm")?;
+            EncodingFamily::TwoRegOneImm => {
                 let hi = (bytes[1] >> 4) & 0x0F;
                 let lo = bytes[1] & 0x0F;
                 let value = decode_imm_signed(&bytes[2..], "TwoRegOneImm immediate")?;
+                let (dst, src) = (lo, hi);
 
                 let instruction = match op {
-                    op if op == Opcode::AddImm32 as u8 => Self::AddImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::AddImm64 as u8 => Self::AddImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::AndImm as u8 => Self::AndImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::XorImm as u8 => Self::XorImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::OrImm as u8 => Self::OrImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::MulImm32 as u8 => Self::MulImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::MulImm64 as u8 => Self::MulImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SetLtUImm as u8 => Self::SetLtUImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SetLtSImm as u8 => Self::SetLtSImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloLImm32 as u8 => Self::ShloLImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloRImm32 as u8 => Self::ShloRImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SharRImm32 as u8 => Self::SharRImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloLImm64 as u8 => Self::ShloLImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloRImm64 as u8 => Self::ShloRImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SharRImm64 as u8 => Self::SharRImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::NegAddImm32 as u8 => Self::NegAddImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::NegAddImm64 as u8 => Self::NegAddImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SetGtUImm as u8 => Self::SetGtUImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SetGtSImm as u8 => Self::SetGtSImm {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::CmovIzImm as u8 => Self::CmovIzImm {
-                        dst: lo,
-                        cond: hi,
+                    Opcode::AddImm32 => Self::AddImm32 { dst, src, value },
+                    Opcode::AddImm64 => Self::AddImm64 { dst, src, value },
+                    Opcode::AndImm => Self::AndImm { dst, src, value },
+                    Opcode::XorImm => Self::XorImm { dst, src, value },
+                    Opcode::OrImm => Self::OrImm { dst, src, value },
+                    Opcode::MulImm32 => Self::MulImm32 { dst, src, value },
+                    Opcode::MulImm64 => Self::MulImm64 { dst, src, value },
+                    Opcode::SetLtUImm => Self::SetLtUImm { dst, src, value },
+                    Opcode::SetLtSImm => Self::SetLtSImm { dst, src, value },
+                    Opcode::ShloLImm32 => Self::ShloLImm32 { dst, src, value },
+                    Opcode::ShloRImm32 => Self::ShloRImm32 { dst, src, value },
+                    Opcode::SharRImm32 => Self::SharRImm32 { dst, src, value },
+                    Opcode::ShloLImm64 => Self::ShloLImm64 { dst, src, value },
+                    Opcode::ShloRImm64 => Self::ShloRImm64 { dst, src, value },
+                    Opcode::SharRImm64 => Self::SharRImm64 { dst, src, value },
+                    Opcode::NegAddImm32 => Self::NegAddImm32 { dst, src, value },
+                    Opcode::NegAddImm64 => Self::NegAddImm64 { dst, src, value },
+                    Opcode::SetGtUImm => Self::SetGtUImm { dst, src, value },
+                    Opcode::SetGtSImm => Self::SetGtSImm { dst, src, value },
+                    Opcode::ShloLImmAlt32 => Self::ShloLImmAlt32 { dst, src, value },
+                    Opcode::ShloRImmAlt32 => Self::ShloRImmAlt32 { dst, src, value },
+                    Opcode::SharRImmAlt32 => Self::SharRImmAlt32 { dst, src, value },
+                    Opcode::ShloLImmAlt64 => Self::ShloLImmAlt64 { dst, src, value },
+                    Opcode::ShloRImmAlt64 => Self::ShloRImmAlt64 { dst, src, value },
+                    Opcode::SharRImmAlt64 => Self::SharRImmAlt64 { dst, src, value },
+                    Opcode::RotRImm64 => Self::RotRImm64 { dst, src, value },
+                    Opcode::RotRImmAlt64 => Self::RotRImmAlt64 { dst, src, value },
+                    Opcode::RotRImm32 => Self::RotRImm32 { dst, src, value },
+                    Opcode::RotRImmAlt32 => Self::RotRImmAlt32 { dst, src, value },
+                    Opcode::CmovIzImm => Self::CmovIzImm {
+                        dst,
+                        cond: src,
                         value,
                     },
-                    op if op == Opcode::CmovNzImm as u8 => Self::CmovNzImm {
-                        dst: lo,
-                        cond: hi,
+                    Opcode::CmovNzImm => Self::CmovNzImm {
+                        dst,
+                        cond: src,
                         value,
                     },
-                    op if op == Opcode::LoadIndU8 as u8 => Self::LoadIndU8 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndU8 => Self::LoadIndU8 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::LoadIndI8 as u8 => Self::LoadIndI8 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndI8 => Self::LoadIndI8 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::LoadIndU16 as u8 => Self::LoadIndU16 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndU16 => Self::LoadIndU16 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::LoadIndI16 as u8 => Self::LoadIndI16 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndI16 => Self::LoadIndI16 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::LoadIndU32 as u8 => Self::LoadIndU32 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndU32 => Self::LoadIndU32 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::LoadIndI32 as u8 => Self::LoadIndI32 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndI32 => Self::LoadIndI32 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::LoadIndU64 as u8 => Self::LoadIndU64 {
-                        dst: lo,
-                        base: hi,
+                    Opcode::LoadIndU64 => Self::LoadIndU64 {
+                        dst,
+                        base: src,
                         offset: value,
                     },
-                    op if op == Opcode::StoreIndU8 as u8 => Self::StoreIndU8 {
+                    // Stores put the value register in the low nibble.
+                    Opcode::StoreIndU8 => Self::StoreIndU8 {
                         base: hi,
                         src: lo,
                         offset: value,
                     },
-                    op if op == Opcode::StoreIndU16 as u8 => Self::StoreIndU16 {
+                    Opcode::StoreIndU16 => Self::StoreIndU16 {
                         base: hi,
                         src: lo,
                         offset: value,
                     },
-                    op if op == Opcode::StoreIndU32 as u8 => Self::StoreIndU32 {
+                    Opcode::StoreIndU32 => Self::StoreIndU32 {
                         base: hi,
                         src: lo,
                         offset: value,
                     },
-                    op if op == Opcode::StoreIndU64 as u8 => Self::StoreIndU64 {
+                    Opcode::StoreIndU64 => Self::StoreIndU64 {
                         base: hi,
                         src: lo,
                         offset: value,
                     },
-                    op if op == Opcode::ShloLImmAlt32 as u8 => Self::ShloLImmAlt32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloRImmAlt32 as u8 => Self::ShloRImmAlt32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SharRImmAlt32 as u8 => Self::SharRImmAlt32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloLImmAlt64 as u8 => Self::ShloLImmAlt64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::ShloRImmAlt64 as u8 => Self::ShloRImmAlt64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::SharRImmAlt64 as u8 => Self::SharRImmAlt64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::RotRImm64 as u8 => Self::RotRImm64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::RotRImmAlt64 as u8 => Self::RotRImmAlt64 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::RotRImm32 as u8 => Self::RotRImm32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    op if op == Opcode::RotRImmAlt32 as u8 => Self::RotRImmAlt32 {
-                        dst: lo,
-                        src: hi,
-                        value,
-                    },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "TwoRegOneImm decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, bytes.len()))
             }
 
-            op if op == Opcode::BranchEq as u8
-                || op == Opcode::BranchNe as u8
-                || op == Opcode::BranchLtU as u8
-                || op == Opcode::BranchLtS as u8
-                || op == Opcode::BranchGeU as u8
-                || op == Opcode::BranchGeS as u8 =>
-            {
-                ensure_min_len(bytes, 2, "TwoRegOneOff")?;
+            EncodingFamily::TwoRegOneOff => {
                 let reg1 = (bytes[1] >> 4) & 0x0F;
                 let reg2 = bytes[1] & 0x0F;
                 let offset = decode_imm_signed(&bytes[2..], "TwoRegOneOff offset")?;
 
                 let instruction = match op {
-                    op if op == Opcode::BranchEq as u8 => Self::BranchEq { reg1, reg2, offset },
-                    op if op == Opcode::BranchNe as u8 => Self::BranchNe { reg1, reg2, offset },
-                    op if op == Opcode::BranchLtU as u8 => Self::BranchLtU { reg1, reg2, offset },
-                    op if op == Opcode::BranchLtS as u8 => Self::BranchLtS { reg1, reg2, offset },
-                    op if op == Opcode::BranchGeU as u8 => Self::BranchGeU { reg1, reg2, offset },
-                    op if op == Opcode::BranchGeS as u8 => Self::BranchGeS { reg1, reg2, offset },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "TwoRegOneOff decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    Opcode::BranchEq => Self::BranchEq { reg1, reg2, offset },
+                    Opcode::BranchNe => Self::BranchNe { reg1, reg2, offset },
+                    Opcode::BranchLtU => Self::BranchLtU { reg1, reg2, offset },
+                    Opcode::BranchLtS => Self::BranchLtS { reg1, reg2, offset },
+                    Opcode::BranchGeU => Self::BranchGeU { reg1, reg2, offset },
+                    Opcode::BranchGeS => Self::BranchGeS { reg1, reg2, offset },
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, bytes.len()))
             }
 
-            op if op == Opcode::LoadImmJumpInd as u8 => {
-                ensure_min_len(bytes, 3, "TwoRegTwoImm")?;
+            EncodingFamily::TwoRegTwoImm => {
                 let base = (bytes[1] >> 4) & 0x0F;
                 let dst = bytes[1] & 0x0F;
                 let imm1_len = (bytes[2] & 0x0F) as usize;
@@ -1366,131 +1132,80 @@ impl Instruction {
                 let value = decode_imm_signed(&bytes[3..imm1_end], "TwoRegTwoImm value")?;
                 let offset = decode_imm_signed(&bytes[imm1_end..], "TwoRegTwoImm offset")?;
 
-                Ok((
-                    Self::LoadImmJumpInd {
-                        base,
-                        dst,
-                        value,
-                        offset,
-                    },
-                    bytes.len(),
-                ))
-            }
-
-            op if op == Opcode::Add32 as u8
-                || op == Opcode::Sub32 as u8
-                || op == Opcode::Mul32 as u8
-                || op == Opcode::DivU32 as u8
-                || op == Opcode::DivS32 as u8
-                || op == Opcode::RemU32 as u8
-                || op == Opcode::RemS32 as u8
-                || op == Opcode::ShloL32 as u8
-                || op == Opcode::ShloR32 as u8
-                || op == Opcode::SharR32 as u8
-                || op == Opcode::Add64 as u8
-                || op == Opcode::Sub64 as u8
-                || op == Opcode::Mul64 as u8
-                || op == Opcode::DivU64 as u8
-                || op == Opcode::DivS64 as u8
-                || op == Opcode::RemU64 as u8
-                || op == Opcode::RemS64 as u8
-                || op == Opcode::ShloL64 as u8
-                || op == Opcode::ShloR64 as u8
-                || op == Opcode::SharR64 as u8
-                || op == Opcode::SetLtU as u8
-                || op == Opcode::SetLtS as u8
-                || op == Opcode::CmovIz as u8
-                || op == Opcode::CmovNz as u8
-                || op == Opcode::And as u8
-                || op == Opcode::Xor as u8
-                || op == Opcode::Or as u8
-                || op == Opcode::MulUpperSS as u8
-                || op == Opcode::MulUpperUU as u8
-                || op == Opcode::MulUpperSU as u8
-                || op == Opcode::RotL64 as u8
-                || op == Opcode::RotL32 as u8
-                || op == Opcode::RotR64 as u8
-                || op == Opcode::RotR32 as u8
-                || op == Opcode::AndInv as u8
-                || op == Opcode::OrInv as u8
-                || op == Opcode::Xnor as u8
-                || op == Opcode::Max as u8
-                || op == Opcode::MaxU as u8
-                || op == Opcode::Min as u8
-                || op == Opcode::MinU as u8 =>
-            {
-                ensure_min_len(bytes, 3, "ThreeReg")?;
+                match op {
+                    Opcode::LoadImmJumpInd => Ok((
+                        Self::LoadImmJumpInd {
+                            base,
+                            dst,
+                            value,
+                            offset,
+                        },
+                        bytes.len(),
+                    )),
+                    _ => Err(mismatch()),
+                }
+            }
+
+            EncodingFamily::ThreeReg => {
                 let src1 = bytes[1] & 0x0F;
                 let src2 = (bytes[1] >> 4) & 0x0F;
                 let dst = bytes[2] & 0x0F;
 
                 let instruction = match op {
-                    op if op == Opcode::Add32 as u8 => Self::Add32 { dst, src1, src2 },
-                    op if op == Opcode::Sub32 as u8 => Self::Sub32 { dst, src1, src2 },
-                    op if op == Opcode::Mul32 as u8 => Self::Mul32 { dst, src1, src2 },
-                    op if op == Opcode::DivU32 as u8 => Self::DivU32 { dst, src1, src2 },
-                    op if op == Opcode::DivS32 as u8 => Self::DivS32 { dst, src1, src2 },
-                    op if op == Opcode::RemU32 as u8 => Self::RemU32 { dst, src1, src2 },
-                    op if op == Opcode::RemS32 as u8 => Self::RemS32 { dst, src1, src2 },
-                    op if op == Opcode::ShloL32 as u8 => Self::ShloL32 { dst, src1, src2 },
-                    op if op == Opcode::ShloR32 as u8 => Self::ShloR32 { dst, src1, src2 },
-                    op if op == Opcode::SharR32 as u8 => Self::SharR32 { dst, src1, src2 },
-                    op if op == Opcode::Add64 as u8 => Self::Add64 { dst, src1, src2 },
-                    op if op == Opcode::Sub64 as u8 => Self::Sub64 { dst, src1, src2 },
-                    op if op == Opcode::Mul64 as u8 => Self::Mul64 { dst, src1, src2 },
-                    op if op == Opcode::DivU64 as u8 => Self::DivU64 { dst, src1, src2 },
-                    op if op == Opcode::DivS64 as u8 => Self::DivS64 { dst, src1, src2 },
-                    op if op == Opcode::RemU64 as u8 => Self::RemU64 { dst, src1, src2 },
-                    op if op == Opcode::RemS64 as u8 => Self::RemS64 { dst, src1, src2 },
-                    op if op == Opcode::ShloL64 as u8 => Self::ShloL64 { dst, src1, src2 },
-                    op if op == Opcode::ShloR64 as u8 => Self::ShloR64 { dst, src1, src2 },
-                    op if op == Opcode::SharR64 as u8 => Self::SharR64 { dst, src1, src2 },
-                    op if op == Opcode::SetLtU as u8 => Self::SetLtU { dst, src1, src2 },
-                    op if op == Opcode::SetLtS as u8 => Self::SetLtS { dst, src1, src2 },
-                    op if op == Opcode::CmovIz as u8 => Self::CmovIz {
+                    Opcode::Add32 => Self::Add32 { dst, src1, src2 },
+                    Opcode::Sub32 => Self::Sub32 { dst, src1, src2 },
+                    Opcode::Mul32 => Self::Mul32 { dst, src1, src2 },
+                    Opcode::DivU32 => Self::DivU32 { dst, src1, src2 },
+                    Opcode::DivS32 => Self::DivS32 { dst, src1, src2 },
+                    Opcode::RemU32 => Self::RemU32 { dst, src1, src2 },
+                    Opcode::RemS32 => Self::RemS32 { dst, src1, src2 },
+                    Opcode::ShloL32 => Self::ShloL32 { dst, src1, src2 },
+                    Opcode::ShloR32 => Self::ShloR32 { dst, src1, src2 },
+                    Opcode::SharR32 => Self::SharR32 { dst, src1, src2 },
+                    Opcode::Add64 => Self::Add64 { dst, src1, src2 },
+                    Opcode::Sub64 => Self::Sub64 { dst, src1, src2 },
+                    Opcode::Mul64 => Self::Mul64 { dst, src1, src2 },
+                    Opcode::DivU64 => Self::DivU64 { dst, src1, src2 },
+                    Opcode::DivS64 => Self::DivS64 { dst, src1, src2 },
+                    Opcode::RemU64 => Self::RemU64 { dst, src1, src2 },
+                    Opcode::RemS64 => Self::RemS64 { dst, src1, src2 },
+                    Opcode::ShloL64 => Self::ShloL64 { dst, src1, src2 },
+                    Opcode::ShloR64 => Self::ShloR64 { dst, src1, src2 },
+                    Opcode::SharR64 => Self::SharR64 { dst, src1, src2 },
+                    Opcode::SetLtU => Self::SetLtU { dst, src1, src2 },
+                    Opcode::SetLtS => Self::SetLtS { dst, src1, src2 },
+                    Opcode::CmovIz => Self::CmovIz {
                         dst,
                         src: src1,
                         cond: src2,
                     },
-                    op if op == Opcode::CmovNz as u8 => Self::CmovNz {
+                    Opcode::CmovNz => Self::CmovNz {
                         dst,
                         src: src1,
                         cond: src2,
                     },
-                    op if op == Opcode::And as u8 => Self::And { dst, src1, src2 },
-                    op if op == Opcode::Xor as u8 => Self::Xor { dst, src1, src2 },
-                    op if op == Opcode::Or as u8 => Self::Or { dst, src1, src2 },
-                    op if op == Opcode::MulUpperSS as u8 => Self::MulUpperSS { dst, src1, src2 },
-                    op if op == Opcode::MulUpperUU as u8 => Self::MulUpperUU { dst, src1, src2 },
-                    op if op == Opcode::MulUpperSU as u8 => Self::MulUpperSU { dst, src1, src2 },
-                    op if op == Opcode::RotL64 as u8 => Self::RotL64 { dst, src1, src2 },
-                    op if op == Opcode::RotL32 as u8 => Self::RotL32 { dst, src1, src2 },
-                    op if op == Opcode::RotR64 as u8 => Self::RotR64 { dst, src1, src2 },
-                    op if op == Opcode::RotR32 as u8 => Self::RotR32 { dst, src1, src2 },
-                    op if op == Opcode::AndInv as u8 => Self::AndInv { dst, src1, src2 },
-                    op if op == Opcode::OrInv as u8 => Self::OrInv { dst, src1, src2 },
-                    op if op == Opcode::Xnor as u8 => Self::Xnor { dst, src1, src2 },
-                    op if op == Opcode::Max as u8 => Self::Max { dst, src1, src2 },
-                    op if op == Opcode::MaxU as u8 => Self::MaxU { dst, src1, src2 },
-                    op if op == Opcode::Min as u8 => Self::Min { dst, src1, src2 },
-                    op if op == Opcode::MinU as u8 => Self::MinU { dst, src1, src2 },
-                    _ => {
-                        return Err(Error::Internal(format!(
-                            "ThreeReg decode dispatch mismatch for opcode {op}"
-                        )));
-                    }
+                    Opcode::And => Self::And { dst, src1, src2 },
+                    Opcode::Xor => Self::Xor { dst, src1, src2 },
+                    Opcode::Or => Self::Or { dst, src1, src2 },
+                    Opcode::MulUpperSS => Self::MulUpperSS { dst, src1, src2 },
+                    Opcode::MulUpperUU => Self::MulUpperUU { dst, src1, src2 },
+                    Opcode::MulUpperSU => Self::MulUpperSU { dst, src1, src2 },
+                    Opcode::RotL64 => Self::RotL64 { dst, src1, src2 },
+                    Opcode::RotL32 => Self::RotL32 { dst, src1, src2 },
+                    Opcode::RotR64 => Self::RotR64 { dst, src1, src2 },
+                    Opcode::RotR32 => Self::RotR32 { dst, src1, src2 },
+                    Opcode::AndInv => Self::AndInv { dst, src1, src2 },
+                    Opcode::OrInv => Self::OrInv { dst, src1, src2 },
+                    Opcode::Xnor => Self::Xnor { dst, src1, src2 },
+                    Opcode::Max => Self::Max { dst, src1, src2 },
+                    Opcode::MaxU => Self::MaxU { dst, src1, src2 },
+                    Opcode::Min => Self::Min { dst, src1, src2 },
+                    Opcode::MinU => Self::MinU { dst, src1, src2 },
+                    _ => return Err(mismatch()),
                 };
 
                 Ok((instruction, 3))
             }
-
-            _ => Ok((
-                Self::Unknown {
-                    opcode,
-                    raw_bytes: bytes.to_vec(),
-                },
-                bytes.len(),
-            )),
         }
     }
 
@@ -1504,11 +1219,7 @@ impl Instruction {
                 bytes.extend_from_slice(&value.to_le_bytes());
                 bytes
             }
-            Self::LoadImm { reg, value } => {
-                let mut bytes = vec![Opcode::LoadImm as u8, *reg & 0x0F];
-                bytes.extend_from_slice(&encode_imm(*value));
-                bytes
-            }
+            Self::LoadImm { reg, value } => encode_one_reg_one_imm(Opcode::LoadImm, *reg, *value),
             Self::Add32 { dst, src1, src2 } => encode_three_reg(Opcode::Add32, *dst, *src1, *src2),
             Self::Sub32 { dst, src1, src2 } => encode_three_reg(Opcode::Sub32, *dst, *src1, *src2),
             Self::Mul32 { dst, src1, src2 } => encode_three_reg(Opcode::Mul32, *dst, *src1, *src2),
@@ -1572,11 +1283,7 @@ impl Instruction {
             Self::LoadImmJump { reg, value, offset } => {
                 encode_one_reg_one_imm_one_off_fixed(Opcode::LoadImmJump, *reg, *value, *offset)
             }
-            Self::JumpInd { reg, offset } => {
-                let mut bytes = vec![Opcode::JumpInd as u8, *reg & 0x0F];
-                bytes.extend_from_slice(&encode_imm(*offset));
-                bytes
-            }
+            Self::JumpInd { reg, offset } => encode_one_reg_one_imm(Opcode::JumpInd, *reg, *offset),
             Self::AddImm32 { dst, src, value } => {
                 encode_two_reg_one_imm(Opcode::AddImm32, *dst, *src, *value)
             }
@@ -1894,6 +1601,153 @@ impl Instruction {
         }
     }
 
+    /// Length of [`Self::encode`]'s output, computed without allocating.
+    #[must_use]
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            Self::LoadImm64 { .. } => 10,
+            Self::Jump { offset } => 1 + imm_len(offset),
+            Self::JumpFixed { .. } => 5,
+            Self::LoadImmJump { value, .. } => 2 + imm_len(value) + 4,
+            Self::Ecalli { index } => 1 + uimm_len(index),
+            Self::Unknown { ref raw_bytes, .. } => raw_bytes.len(),
+            Self::LoadImm { value: imm, .. }
+            | Self::JumpInd { offset: imm, .. }
+            | Self::LoadU8 { address: imm, .. }
+            | Self::LoadI8 { address: imm, .. }
+            | Self::LoadU16 { address: imm, .. }
+            | Self::LoadI16 { address: imm, .. }
+            | Self::LoadU32 { address: imm, .. }
+            | Self::LoadI32 { address: imm, .. }
+            | Self::LoadU64 { address: imm, .. }
+            | Self::StoreU8 { address: imm, .. }
+            | Self::StoreU16 { address: imm, .. }
+            | Self::StoreU32 { address: imm, .. }
+            | Self::StoreU64 { address: imm, .. }
+            | Self::AddImm32 { value: imm, .. }
+            | Self::AddImm64 { value: imm, .. }
+            | Self::AndImm { value: imm, .. }
+            | Self::XorImm { value: imm, .. }
+            | Self::OrImm { value: imm, .. }
+            | Self::MulImm32 { value: imm, .. }
+            | Self::MulImm64 { value: imm, .. }
+            | Self::ShloLImm32 { value: imm, .. }
+            | Self::ShloRImm32 { value: imm, .. }
+            | Self::SharRImm32 { value: imm, .. }
+            | Self::ShloLImm64 { value: imm, .. }
+            | Self::ShloRImm64 { value: imm, .. }
+            | Self::SharRImm64 { value: imm, .. }
+            | Self::NegAddImm32 { value: imm, .. }
+            | Self::NegAddImm64 { value: imm, .. }
+            | Self::SetGtUImm { value: imm, .. }
+            | Self::SetGtSImm { value: imm, .. }
+            | Self::LoadIndU32 { offset: imm, .. }
+            | Self::StoreIndU32 { offset: imm, .. }
+            | Self::LoadIndU64 { offset: imm, .. }
+            | Self::StoreIndU64 { offset: imm, .. }
+            | Self::SetLtUImm { value: imm, .. }
+            | Self::SetLtSImm { value: imm, .. }
+            | Self::LoadIndU8 { offset: imm, .. }
+            | Self::LoadIndI8 { offset: imm, .. }
+            | Self::StoreIndU8 { offset: imm, .. }
+            | Self::LoadIndU16 { offset: imm, .. }
+            | Self::LoadIndI16 { offset: imm, .. }
+            | Self::StoreIndU16 { offset: imm, .. }
+            | Self::CmovIzImm { value: imm, .. }
+            | Self::CmovNzImm { value: imm, .. }
+            | Self::LoadIndI32 { offset: imm, .. }
+            | Self::ShloLImmAlt32 { value: imm, .. }
+            | Self::ShloRImmAlt32 { value: imm, .. }
+            | Self::SharRImmAlt32 { value: imm, .. }
+            | Self::ShloLImmAlt64 { value: imm, .. }
+            | Self::ShloRImmAlt64 { value: imm, .. }
+            | Self::SharRImmAlt64 { value: imm, .. }
+            | Self::RotRImm64 { value: imm, .. }
+            | Self::RotRImmAlt64 { value: imm, .. }
+            | Self::RotRImm32 { value: imm, .. }
+            | Self::RotRImmAlt32 { value: imm, .. }
+            | Self::BranchEq { offset: imm, .. }
+            | Self::BranchNe { offset: imm, .. }
+            | Self::BranchGeU { offset: imm, .. }
+            | Self::BranchLtU { offset: imm, .. }
+            | Self::BranchLtS { offset: imm, .. }
+            | Self::BranchGeS { offset: imm, .. } => 2 + imm_len(imm),
+            Self::BranchNeImm { value, offset, .. }
+            | Self::BranchEqImm { value, offset, .. }
+            | Self::BranchGeSImm { value, offset, .. }
+            | Self::BranchLtUImm { value, offset, .. }
+            | Self::BranchLeUImm { value, offset, .. }
+            | Self::BranchGeUImm { value, offset, .. }
+            | Self::BranchGtUImm { value, offset, .. }
+            | Self::BranchLtSImm { value, offset, .. }
+            | Self::BranchLeSImm { value, offset, .. }
+            | Self::BranchGtSImm { value, offset, .. }
+            | Self::StoreImmIndU8 { offset, value, .. }
+            | Self::StoreImmIndU16 { offset, value, .. }
+            | Self::StoreImmIndU32 { offset, value, .. }
+            | Self::StoreImmIndU64 { offset, value, .. } => 2 + imm_len(value) + imm_len(offset),
+            Self::StoreImmU8 { address, value }
+            | Self::StoreImmU16 { address, value }
+            | Self::StoreImmU32 { address, value }
+            | Self::StoreImmU64 { address, value } => 2 + imm_len(address) + imm_len(value),
+            Self::LoadImmJumpInd { value, offset, .. } => 3 + imm_len(value) + imm_len(offset),
+            Self::Trap | Self::Fallthrough => 1,
+            Self::MoveReg { .. }
+            | Self::Sbrk { .. }
+            | Self::CountSetBits64 { .. }
+            | Self::CountSetBits32 { .. }
+            | Self::LeadingZeroBits64 { .. }
+            | Self::LeadingZeroBits32 { .. }
+            | Self::TrailingZeroBits64 { .. }
+            | Self::TrailingZeroBits32 { .. }
+            | Self::SignExtend8 { .. }
+            | Self::SignExtend16 { .. }
+            | Self::ZeroExtend16 { .. }
+            | Self::ReverseBytes { .. } => 2,
+            Self::Add32 { .. }
+            | Self::Sub32 { .. }
+            | Self::Mul32 { .. }
+            | Self::DivU32 { .. }
+            | Self::DivS32 { .. }
+            | Self::RemU32 { .. }
+            | Self::RemS32 { .. }
+            | Self::Add64 { .. }
+            | Self::Sub64 { .. }
+            | Self::Mul64 { .. }
+            | Self::DivU64 { .. }
+            | Self::DivS64 { .. }
+            | Self::RemU64 { .. }
+            | Self::RemS64 { .. }
+            | Self::ShloL64 { .. }
+            | Self::ShloR64 { .. }
+            | Self::SharR64 { .. }
+            | Self::SetLtU { .. }
+            | Self::SetLtS { .. }
+            | Self::CmovIz { .. }
+            | Self::CmovNz { .. }
+            | Self::And { .. }
+            | Self::Xor { .. }
+            | Self::Or { .. }
+            | Self::ShloL32 { .. }
+            | Self::ShloR32 { .. }
+            | Self::SharR32 { .. }
+            | Self::MulUpperSS { .. }
+            | Self::MulUpperUU { .. }
+            | Self::MulUpperSU { .. }
+            | Self::RotL64 { .. }
+            | Self::RotL32 { .. }
+            | Self::RotR64 { .. }
+            | Self::RotR32 { .. }
+            | Self::AndInv { .. }
+            | Self::OrInv { .. }
+            | Self::Xnor { .. }
+            | Self::Max { .. }
+            | Self::MaxU { .. }
+            | Self::Min { .. }
+            | Self::MinU { .. } => 3,
+        }
+    }
+
     /// Returns the destination register written by this instruction, if any.
     /// Used by the register cache to invalidate stale entries.
     #[must_use]
@@ -2077,6 +1931,60 @@ impl Instruction {
         )
     }
 
+    /// The code-relative offset of a static jump or branch, if `self` has one.
+    /// The target is the offset added to the instruction's own start.
+    #[must_use]
+    pub const fn jump_offset(&self) -> Option<i32> {
+        match *self {
+            Self::Jump { offset }
+            | Self::JumpFixed { offset }
+            | Self::LoadImmJump { offset, .. }
+            | Self::BranchNeImm { offset, .. }
+            | Self::BranchEqImm { offset, .. }
+            | Self::BranchGeSImm { offset, .. }
+            | Self::BranchLtUImm { offset, .. }
+            | Self::BranchLeUImm { offset, .. }
+            | Self::BranchGeUImm { offset, .. }
+            | Self::BranchGtUImm { offset, .. }
+            | Self::BranchLtSImm { offset, .. }
+            | Self::BranchLeSImm { offset, .. }
+            | Self::BranchGtSImm { offset, .. }
+            | Self::BranchEq { offset, .. }
+            | Self::BranchNe { offset, .. }
+            | Self::BranchGeU { offset, .. }
+            | Self::BranchLtU { offset, .. }
+            | Self::BranchLtS { offset, .. }
+            | Self::BranchGeS { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// The conditional branch taken exactly when `self` is not taken, with the
+    /// same operands and offset (`BranchEq` ↔ `BranchNe`, `BranchLtU` ↔
+    /// `BranchGeU`, ...). `None` for anything that is not a conditional branch.
+    #[must_use]
+    pub const fn invert_branch(&self) -> Option<Self> {
+        Some(match *self {
+            Self::BranchEqImm { reg, value, offset } => Self::BranchNeImm { reg, value, offset },
+            Self::BranchNeImm { reg, value, offset } => Self::BranchEqImm { reg, value, offset },
+            Self::BranchLtUImm { reg, value, offset } => Self::BranchGeUImm { reg, value, offset },
+            Self::BranchGeUImm { reg, value, offset } => Self::BranchLtUImm { reg, value, offset },
+            Self::BranchLeUImm { reg, value, offset } => Self::BranchGtUImm { reg, value, offset },
+            Self::BranchGtUImm { reg, value, offset } => Self::BranchLeUImm { reg, value, offset },
+            Self::BranchLtSImm { reg, value, offset } => Self::BranchGeSImm { reg, value, offset },
+            Self::BranchGeSImm { reg, value, offset } => Self::BranchLtSImm { reg, value, offset },
+            Self::BranchLeSImm { reg, value, offset } => Self::BranchGtSImm { reg, value, offset },
+            Self::BranchGtSImm { reg, value, offset } => Self::BranchLeSImm { reg, value, offset },
+            Self::BranchEq { reg1, reg2, offset } => Self::BranchNe { reg1, reg2, offset },
+            Self::BranchNe { reg1, reg2, offset } => Self::BranchEq { reg1, reg2, offset },
+            Self::BranchLtU { reg1, reg2, offset } => Self::BranchGeU { reg1, reg2, offset },
+            Self::BranchGeU { reg1, reg2, offset } => Self::BranchLtU { reg1, reg2, offset },
+            Self::BranchLtS { reg1, reg2, offset } => Self::BranchGeS { reg1, reg2, offset },
+            Self::BranchGeS { reg1, reg2, offset } => Self::BranchLtS { reg1, reg2, offset },
+            _ => return None,
+        })
+    }
+
     /// Returns the source registers read by this instruction.
     /// Used by dead code elimination to determine liveness.
     /// Returns up to 3 registers (most instructions use 0-2, some 3).
@@ -2266,7 +2174,18 @@ impl Instruction {
     }
 }
 
+/// Every `encode_*` helper checks that it is only handed opcodes of its own
+/// layout, keeping `encode` in sync with the family-driven `decode`.
+fn debug_assert_family(opcode: Opcode, family: EncodingFamily) {
+    debug_assert_eq!(
+        opcode.encoding_family(),
+        family,
+        "{opcode:?} encoded with the {family:?} layout"
+    );
+}
+
 fn encode_three_reg(opcode: Opcode, dst: u8, src1: u8, src2: u8) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::ThreeReg);
     // PVM three-reg encoding: [opcode, rB_hi | rA_lo, rD]
     // Semantics: reg[rD] = reg[rA] OP reg[rB]
     // We want: reg[dst] = reg[src1] OP reg[src2], so rA=src1, rB=src2
@@ -2274,16 +2193,19 @@ fn encode_three_reg(opcode: Opcode, dst: u8, src1: u8, src2: u8) -> Vec<u8> {
 }
 
 fn encode_two_reg_one_imm(opcode: Opcode, dst: u8, src: u8, value: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::TwoRegOneImm);
     let mut bytes = vec![opcode as u8, (src & 0x0F) << 4 | (dst & 0x0F)];
     bytes.extend_from_slice(&encode_imm(value));
     bytes
 }
 
 fn encode_two_reg(opcode: Opcode, dst: u8, src: u8) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::TwoReg);
     vec![opcode as u8, (src & 0x0F) << 4 | (dst & 0x0F)]
 }
 
 fn encode_one_reg_one_imm_one_off(opcode: Opcode, reg: u8, imm: i32, offset: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::OneRegOneImmOneOff);
     let imm_enc = encode_imm(imm);
     let imm_len = imm_enc.len() as u8;
     let mut bytes = vec![opcode as u8, (imm_len << 4) | (reg & 0x0F)];
@@ -2299,6 +2221,7 @@ fn encode_one_reg_one_imm_one_off(opcode: Opcode, reg: u8, imm: i32, offset: i32
 /// encoded length must not depend on the offset value. Decoding sign-extends
 /// from the actual length, so the fixed form stays spec-compatible.
 fn encode_one_reg_one_imm_one_off_fixed(opcode: Opcode, reg: u8, imm: i32, offset: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::OneRegOneImmOneOff);
     let imm_enc = encode_imm(imm);
     let imm_len = imm_enc.len() as u8;
     let mut bytes = vec![opcode as u8, (imm_len << 4) | (reg & 0x0F)];
@@ -2308,6 +2231,7 @@ fn encode_one_reg_one_imm_one_off_fixed(opcode: Opcode, reg: u8, imm: i32, offse
 }
 
 fn encode_one_reg_two_imm(opcode: Opcode, reg: u8, imm1: i32, imm2: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::OneRegTwoImm);
     let imm1_enc = encode_imm(imm1);
     let imm1_len = imm1_enc.len() as u8;
     let imm2_enc = encode_imm(imm2);
@@ -2318,12 +2242,14 @@ fn encode_one_reg_two_imm(opcode: Opcode, reg: u8, imm1: i32, imm2: i32) -> Vec<
 }
 
 fn encode_one_reg_one_imm(opcode: Opcode, reg: u8, value: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::OneRegOneImm);
     let mut bytes = vec![opcode as u8, reg & 0x0F];
     bytes.extend_from_slice(&encode_imm(value));
     bytes
 }
 
 fn encode_two_reg_two_imm(opcode: Opcode, reg1: u8, reg2: u8, imm1: i32, imm2: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::TwoRegTwoImm);
     let imm1_enc = encode_imm(imm1);
     let imm1_len = imm1_enc.len() as u8;
     let imm2_enc = encode_imm(imm2);
@@ -2338,12 +2264,14 @@ fn encode_two_reg_two_imm(opcode: Opcode, reg1: u8, reg2: u8, imm1: i32, imm2: i
 }
 
 fn encode_two_reg_one_off(opcode: Opcode, reg1: u8, reg2: u8, offset: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::TwoRegOneOff);
     let mut bytes = vec![opcode as u8, (reg1 & 0x0F) << 4 | (reg2 & 0x0F)];
     bytes.extend_from_slice(&encode_imm(offset));
     bytes
 }
 
 fn encode_two_imm(opcode: Opcode, imm1: i32, imm2: i32) -> Vec<u8> {
+    debug_assert_family(opcode, EncodingFamily::TwoImm);
     let imm1_enc = encode_imm(imm1);
     let imm1_len = imm1_enc.len() as u8;
     let imm2_enc = encode_imm(imm2);
@@ -2354,8 +2282,13 @@ fn encode_two_imm(opcode: Opcode, imm1: i32, imm2: i32) -> Vec<u8> {
 }
 
 fn encode_uimm(value: u32) -> Vec<u8> {
-    let bytes = value.to_le_bytes();
-    let len = if value == 0 {
+    value.to_le_bytes()[..uimm_len(value)].to_vec()
+}
+
+/// Bytes [`encode_uimm`] needs for `value`: zero for 0, else the fewest
+/// little-endian bytes that hold it.
+const fn uimm_len(value: u32) -> usize {
+    if value == 0 {
         0
     } else if value <= 0xFF {
         1
@@ -2365,24 +2298,27 @@ fn encode_uimm(value: u32) -> Vec<u8> {
         3
     } else {
         4
-    };
-    bytes[..len].to_vec()
+    }
 }
 
 fn encode_imm(value: i32) -> Vec<u8> {
-    let bytes = value.to_le_bytes();
-    let len = if value == 0 {
+    value.to_le_bytes()[..imm_len(value)].to_vec()
+}
+
+/// Bytes [`encode_imm`] needs for `value`: zero for 0, else the fewest bytes
+/// that sign-extend back to it.
+const fn imm_len(value: i32) -> usize {
+    if value == 0 {
         0
-    } else if (-128..=127).contains(&value) {
+    } else if value >= -128 && value <= 127 {
         1
-    } else if (-32768..=32767).contains(&value) {
+    } else if value >= -32768 && value <= 32767 {
         2
-    } else if (-8_388_608..=8_388_607).contains(&value) {
+    } else if value >= -8_388_608 && value <= 8_388_607 {
         3
     } else {
         4
-    };
-    bytes[..len].to_vec()
+    }
 }
 
 fn ensure_min_len(bytes: &[u8], min_len: usize, context: &str) -> Result<()> {
@@ -2430,7 +2366,7 @@ fn decode_imm_unsigned(bytes: &[u8], context: &str) -> Result<u32> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     fn assert_decode_roundtrip(instr: &Instruction) {
@@ -2444,7 +2380,8 @@ mod tests {
         assert_eq!(&decoded, instr, "decode(encode(instr)) mismatch");
     }
 
-    fn sample_instructions() -> Vec<Instruction> {
+    /// One instance of every instruction variant, with distinctive operands.
+    pub(crate) fn sample_instructions() -> Vec<Instruction> {
         vec![
             Instruction::Trap,
             Instruction::Fallthrough,
@@ -3074,6 +3011,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_every_opcode_has_encoding_family() {
+        for byte in 0..=u8::MAX {
+            let Some(op) = Opcode::from_u8(byte) else {
+                continue;
+            };
+            let family = op.encoding_family();
+            assert!(family.min_len() <= family.max_len(), "{op:?}: {family:?}");
+
+            // The smallest buffer the family allows must decode to this opcode.
+            let mut bytes = vec![0u8; family.min_len()];
+            bytes[0] = byte;
+            let (decoded, consumed) = Instruction::decode(&bytes)
+                .unwrap_or_else(|e| panic!("{op:?} ({family:?}) failed to decode: {e}"));
+            assert!(
+                !matches!(decoded, Instruction::Unknown { .. }),
+                "{op:?} decoded as Unknown"
+            );
+            assert_eq!(consumed, family.min_len(), "{op:?} ({family:?})");
+            assert_eq!(
+                decoded.encode()[0],
+                byte,
+                "{op:?} re-encoded with another opcode"
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_agree_with_encoding_family() {
+        let mut covered = std::collections::BTreeSet::new();
+        for instr in sample_instructions() {
+            let encoded = instr.encode();
+            let Some(op) = Opcode::from_u8(encoded[0]) else {
+                continue; // `Unknown` passthrough
+            };
+            let family = op.encoding_family();
+            covered.insert(encoded[0]);
+
+            assert!(
+                (family.min_len()..=family.max_len()).contains(&encoded.len()),
+                "{instr:?}: {} bytes outside the {family:?} range",
+                encoded.len()
+            );
+            // Truncating below the family minimum must be rejected, not misread.
+            if family.min_len() > 1 {
+                assert!(
+                    Instruction::decode(&encoded[..family.min_len() - 1]).is_err(),
+                    "{instr:?}: truncated {family:?} encoding decoded"
+                );
+            }
+            assert_decode_roundtrip(&instr);
+        }
+
+        let missing: Vec<Opcode> = (0..=u8::MAX)
+            .filter(|b| !covered.contains(b))
+            .filter_map(Opcode::from_u8)
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "sample_instructions misses opcodes: {missing:?}"
+        );
+    }
+
     #[test]
     fn test_decode_roundtrip_immediate_edges() {
         let edge_values = [
@@ -3128,6 +3128,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_encoded_len_matches_encode_for_every_opcode() {
+        let magnitudes = [
+            0,
+            1,
+            -1,
+            127,
+            128,
+            -129,
+            32_767,
+            32_768,
+            8_388_607,
+            -8_388_609,
+            i32::MAX,
+            i32::MIN,
+        ];
+        for opcode in Opcode::all() {
+            for &a in &magnitudes {
+                for &b in &magnitudes {
+                    let op = opcode as u8;
+                    let imm_a = encode_imm(a);
+                    let len_a = imm_a.len() as u8;
+                    let imm_b = encode_imm(b);
+                    let bytes = match opcode.encoding_family() {
+                        EncodingFamily::NoArgs => vec![op],
+                        EncodingFamily::OneImm => {
+                            [vec![op], encode_uimm(a.cast_unsigned())].concat()
+                        }
+                        EncodingFamily::OneRegExtImm => {
+                            [vec![op, 1], i64::from(a).to_le_bytes().to_vec()].concat()
+                        }
+                        EncodingFamily::TwoImm => [vec![op, len_a], imm_a, imm_b].concat(),
+                        EncodingFamily::OneOff => [vec![op], imm_a].concat(),
+                        EncodingFamily::OneRegOneImm => [vec![op, 1], imm_a].concat(),
+                        EncodingFamily::OneRegTwoImm | EncodingFamily::OneRegOneImmOneOff => {
+                            [vec![op, len_a << 4 | 1], imm_a, imm_b].concat()
+                        }
+                        EncodingFamily::TwoReg => vec![op, 0x21],
+                        EncodingFamily::TwoRegOneImm | EncodingFamily::TwoRegOneOff => {
+                            [vec![op, 0x21], imm_a].concat()
+                        }
+                        EncodingFamily::TwoRegTwoImm => {
+                            [vec![op, 0x21, len_a], imm_a, imm_b].concat()
+                        }
+                        EncodingFamily::ThreeReg => vec![op, 0x21, 3],
+                    };
+                    let (instr, _) = Instruction::decode(&bytes).expect("decode");
+                    assert!(
+                        !matches!(instr, Instruction::Unknown { .. }),
+                        "{opcode:?} decoded as Unknown"
+                    );
+                    assert_eq!(
+                        instr.encoded_len(),
+                        instr.encode().len(),
+                        "encoded_len drifted from encode for {instr:?}"
+                    );
+                }
+            }
+        }
+        for instr in sample_instructions() {
+            assert_eq!(instr.encoded_len(), instr.encode().len(), "{instr:?}");
+        }
+    }
+
     #[test]
     fn test_decode_rejects_empty_slice() {
         let error = Instruction::decode(&[]).expect_err("empty input should fail");
@@ -3855,4 +3919,60 @@ mod tests {
         }
         i32::from_le_bytes(buf)
     }
+
+    #[test]
+    fn invert_branch_pairs_are_mutual_inverses() {
+        let imm = |make: fn(u8, i32, i32) -> Instruction| make(3, -7, 40);
+        let regs = |make: fn(u8, u8, i32) -> Instruction| make(3, 4, 40);
+        let pairs = [
+            (
+                imm(|reg, value, offset| Instruction::BranchEqImm { reg, value, offset }),
+                imm(|reg, value, offset| Instruction::BranchNeImm { reg, value, offset }),
+            ),
+            (
+                imm(|reg, value, offset| Instruction::BranchLtUImm { reg, value, offset }),
+                imm(|reg, value, offset| Instruction::BranchGeUImm { reg, value, offset }),
+            ),
+            (
+                imm(|reg, value, offset| Instruction::BranchLeUImm { reg, value, offset }),
+                imm(|reg, value, offset| Instruction::BranchGtUImm { reg, value, offset }),
+            ),
+            (
+                imm(|reg, value, offset| Instruction::BranchLtSImm { reg, value, offset }),
+                imm(|reg, value, offset| Instruction::BranchGeSImm { reg, value, offset }),
+            ),
+            (
+                imm(|reg, value, offset| Instruction::BranchLeSImm { reg, value, offset }),
+                imm(|reg, value, offset| Instruction::BranchGtSImm { reg, value, offset }),
+            ),
+            (
+                regs(|reg1, reg2, offset| Instruction::BranchEq { reg1, reg2, offset }),
+                regs(|reg1, reg2, offset| Instruction::BranchNe { reg1, reg2, offset }),
+            ),
+            (
+                regs(|reg1, reg2, offset| Instruction::BranchLtU { reg1, reg2, offset }),
+                regs(|reg1, reg2, offset| Instruction::BranchGeU { reg1, reg2, offset }),
+            ),
+            (
+                regs(|reg1, reg2, offset| Instruction::BranchLtS { reg1, reg2, offset }),
+                regs(|reg1, reg2, offset| Instruction::BranchGeS { reg1, reg2, offset }),
+            ),
+        ];
+        for (a, b) in pairs {
+            assert_eq!(a.invert_branch(), Some(b.clone()), "{a:?}");
+            assert_eq!(b.invert_branch(), Some(a.clone()), "{b:?}");
+            assert_eq!(a.encode().len(), b.encode().len(), "{a:?}");
+        }
+    }
+
+    #[test]
+    fn invert_branch_rejects_non_branches() {
+        for instr in [
+            Instruction::Jump { offset: 8 },
+            Instruction::Trap,
+            Instruction::LoadImm { reg: 2, value: 1 },
+        ] {
+            assert_eq!(instr.invert_branch(), None, "{instr:?}");
+        }
+    }
 }
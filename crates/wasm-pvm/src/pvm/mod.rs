@@ -1,13 +1,24 @@
 // PVM encoding utilities use explicit 'as' casts for byte packing and serialization.
 #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
 
+mod asm;
 mod blob;
+mod disasm;
+pub mod gas;
 mod instruction;
 mod opcode;
 #[cfg(feature = "compiler")]
 pub(crate) mod peephole;
+#[cfg(feature = "compiler")]
+mod reoptimize;
 
-pub use blob::ProgramBlob;
-pub(crate) use blob::encode_var_u32;
+pub(crate) use blob::{ByteReader, encode_var_u32};
+pub use blob::{ProgramBlob, instruction_offsets};
+pub use disasm::disassemble;
+pub use gas::{GasEstimate, estimate_gas};
 pub use instruction::Instruction;
-pub use opcode::Opcode;
+#[cfg(test)]
+pub(crate) use instruction::tests::sample_instructions;
+pub use opcode::{EncodingFamily, Opcode};
+#[cfg(feature = "compiler")]
+pub use reoptimize::reoptimize;
@@ -166,6 +166,11 @@ pub enum Opcode {
 }
 
 impl Opcode {
+    /// Every opcode, in byte order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..=u8::MAX).filter_map(Self::from_u8)
+    }
+
     #[must_use]
     pub const fn from_u8(opcode: u8) -> Option<Self> {
         match opcode {
@@ -340,12 +345,748 @@ impl Opcode {
                 | Self::LoadImmJumpInd
         )
     }
+
+    /// Operand layout this opcode uses in the instruction stream.
+    ///
+    /// `Instruction::decode` dispatches on this, and every `encode_*` helper
+    /// asserts it, so an opcode cannot be wired into one path with a layout
+    /// the other does not expect.
+    #[must_use]
+    pub const fn encoding_family(self) -> EncodingFamily {
+        match self {
+            Self::Trap | Self::Fallthrough => EncodingFamily::NoArgs,
+            Self::Ecalli => EncodingFamily::OneImm,
+            Self::LoadImm64 => EncodingFamily::OneRegExtImm,
+            Self::StoreImmU8 | Self::StoreImmU16 | Self::StoreImmU32 | Self::StoreImmU64 => {
+                EncodingFamily::TwoImm
+            }
+            Self::Jump => EncodingFamily::OneOff,
+            Self::JumpInd
+            | Self::LoadImm
+            | Self::LoadU8
+            | Self::LoadI8
+            | Self::LoadU16
+            | Self::LoadI16
+            | Self::LoadU32
+            | Self::LoadI32
+            | Self::LoadU64
+            | Self::StoreU8
+            | Self::StoreU16
+            | Self::StoreU32
+            | Self::StoreU64 => EncodingFamily::OneRegOneImm,
+            Self::StoreImmIndU8
+            | Self::StoreImmIndU16
+            | Self::StoreImmIndU32
+            | Self::StoreImmIndU64 => EncodingFamily::OneRegTwoImm,
+            Self::LoadImmJump
+            | Self::BranchEqImm
+            | Self::BranchNeImm
+            | Self::BranchLtUImm
+            | Self::BranchLeUImm
+            | Self::BranchGeUImm
+            | Self::BranchGtUImm
+            | Self::BranchLtSImm
+            | Self::BranchLeSImm
+            | Self::BranchGeSImm
+            | Self::BranchGtSImm => EncodingFamily::OneRegOneImmOneOff,
+            Self::MoveReg
+            | Self::Sbrk
+            | Self::CountSetBits64
+            | Self::CountSetBits32
+            | Self::LeadingZeroBits64
+            | Self::LeadingZeroBits32
+            | Self::TrailingZeroBits64
+            | Self::TrailingZeroBits32
+            | Self::SignExtend8
+            | Self::SignExtend16
+            | Self::ZeroExtend16
+            | Self::ReverseBytes => EncodingFamily::TwoReg,
+            Self::StoreIndU8
+            | Self::StoreIndU16
+            | Self::StoreIndU32
+            | Self::StoreIndU64
+            | Self::LoadIndU8
+            | Self::LoadIndI8
+            | Self::LoadIndU16
+            | Self::LoadIndI16
+            | Self::LoadIndU32
+            | Self::LoadIndI32
+            | Self::LoadIndU64
+            | Self::AddImm32
+            | Self::AndImm
+            | Self::XorImm
+            | Self::OrImm
+            | Self::MulImm32
+            | Self::SetLtUImm
+            | Self::SetLtSImm
+            | Self::ShloLImm32
+            | Self::ShloRImm32
+            | Self::SharRImm32
+            | Self::NegAddImm32
+            | Self::SetGtUImm
+            | Self::SetGtSImm
+            | Self::ShloLImmAlt32
+            | Self::ShloRImmAlt32
+            | Self::SharRImmAlt32
+            | Self::CmovIzImm
+            | Self::CmovNzImm
+            | Self::AddImm64
+            | Self::MulImm64
+            | Self::ShloLImm64
+            | Self::ShloRImm64
+            | Self::SharRImm64
+            | Self::NegAddImm64
+            | Self::ShloLImmAlt64
+            | Self::ShloRImmAlt64
+            | Self::SharRImmAlt64
+            | Self::RotRImm64
+            | Self::RotRImmAlt64
+            | Self::RotRImm32
+            | Self::RotRImmAlt32 => EncodingFamily::TwoRegOneImm,
+            Self::BranchEq
+            | Self::BranchNe
+            | Self::BranchLtU
+            | Self::BranchLtS
+            | Self::BranchGeU
+            | Self::BranchGeS => EncodingFamily::TwoRegOneOff,
+            Self::LoadImmJumpInd => EncodingFamily::TwoRegTwoImm,
+            Self::Add32
+            | Self::Sub32
+            | Self::Mul32
+            | Self::DivU32
+            | Self::DivS32
+            | Self::RemU32
+            | Self::RemS32
+            | Self::ShloL32
+            | Self::ShloR32
+            | Self::SharR32
+            | Self::Add64
+            | Self::Sub64
+            | Self::Mul64
+            | Self::DivU64
+            | Self::DivS64
+            | Self::RemU64
+            | Self::RemS64
+            | Self::ShloL64
+            | Self::ShloR64
+            | Self::SharR64
+            | Self::And
+            | Self::Xor
+            | Self::Or
+            | Self::MulUpperSS
+            | Self::MulUpperUU
+            | Self::MulUpperSU
+            | Self::SetLtU
+            | Self::SetLtS
+            | Self::CmovIz
+            | Self::CmovNz
+            | Self::RotL64
+            | Self::RotL32
+            | Self::RotR64
+            | Self::RotR32
+            | Self::AndInv
+            | Self::OrInv
+            | Self::Xnor
+            | Self::Max
+            | Self::MaxU
+            | Self::Min
+            | Self::MinU => EncodingFamily::ThreeReg,
+        }
+    }
+
+    /// Assembler mnemonic: the variant name in `snake_case`, with a width
+    /// suffix attached to a `u`/`i` type letter (`load_ind_u8`) and otherwise
+    /// split off (`rot_r_64`).
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Trap => "trap",
+            Self::Fallthrough => "fallthrough",
+            Self::Ecalli => "ecalli",
+            Self::LoadImm64 => "load_imm_64",
+            Self::StoreImmU8 => "store_imm_u8",
+            Self::StoreImmU16 => "store_imm_u16",
+            Self::StoreImmU32 => "store_imm_u32",
+            Self::StoreImmU64 => "store_imm_u64",
+            Self::Jump => "jump",
+            Self::JumpInd => "jump_ind",
+            Self::LoadImm => "load_imm",
+            Self::LoadU8 => "load_u8",
+            Self::LoadI8 => "load_i8",
+            Self::LoadU16 => "load_u16",
+            Self::LoadI16 => "load_i16",
+            Self::LoadU32 => "load_u32",
+            Self::LoadI32 => "load_i32",
+            Self::LoadU64 => "load_u64",
+            Self::StoreU8 => "store_u8",
+            Self::StoreU16 => "store_u16",
+            Self::StoreU32 => "store_u32",
+            Self::StoreU64 => "store_u64",
+            Self::StoreImmIndU8 => "store_imm_ind_u8",
+            Self::StoreImmIndU16 => "store_imm_ind_u16",
+            Self::StoreImmIndU32 => "store_imm_ind_u32",
+            Self::StoreImmIndU64 => "store_imm_ind_u64",
+            Self::LoadImmJump => "load_imm_jump",
+            Self::BranchEqImm => "branch_eq_imm",
+            Self::BranchNeImm => "branch_ne_imm",
+            Self::BranchLtUImm => "branch_lt_u_imm",
+            Self::BranchLeUImm => "branch_le_u_imm",
+            Self::BranchGeUImm => "branch_ge_u_imm",
+            Self::BranchGtUImm => "branch_gt_u_imm",
+            Self::BranchLtSImm => "branch_lt_s_imm",
+            Self::BranchLeSImm => "branch_le_s_imm",
+            Self::BranchGeSImm => "branch_ge_s_imm",
+            Self::BranchGtSImm => "branch_gt_s_imm",
+            Self::MoveReg => "move_reg",
+            Self::Sbrk => "sbrk",
+            Self::CountSetBits64 => "count_set_bits_64",
+            Self::CountSetBits32 => "count_set_bits_32",
+            Self::LeadingZeroBits64 => "leading_zero_bits_64",
+            Self::LeadingZeroBits32 => "leading_zero_bits_32",
+            Self::TrailingZeroBits64 => "trailing_zero_bits_64",
+            Self::TrailingZeroBits32 => "trailing_zero_bits_32",
+            Self::SignExtend8 => "sign_extend_8",
+            Self::SignExtend16 => "sign_extend_16",
+            Self::ZeroExtend16 => "zero_extend_16",
+            Self::ReverseBytes => "reverse_bytes",
+            Self::StoreIndU8 => "store_ind_u8",
+            Self::StoreIndU16 => "store_ind_u16",
+            Self::StoreIndU32 => "store_ind_u32",
+            Self::StoreIndU64 => "store_ind_u64",
+            Self::LoadIndU8 => "load_ind_u8",
+            Self::LoadIndI8 => "load_ind_i8",
+            Self::LoadIndU16 => "load_ind_u16",
+            Self::LoadIndI16 => "load_ind_i16",
+            Self::LoadIndU32 => "load_ind_u32",
+            Self::LoadIndI32 => "load_ind_i32",
+            Self::LoadIndU64 => "load_ind_u64",
+            Self::AddImm32 => "add_imm_32",
+            Self::AndImm => "and_imm",
+            Self::XorImm => "xor_imm",
+            Self::OrImm => "or_imm",
+            Self::MulImm32 => "mul_imm_32",
+            Self::SetLtUImm => "set_lt_u_imm",
+            Self::SetLtSImm => "set_lt_s_imm",
+            Self::ShloLImm32 => "shlo_l_imm_32",
+            Self::ShloRImm32 => "shlo_r_imm_32",
+            Self::SharRImm32 => "shar_r_imm_32",
+            Self::NegAddImm32 => "neg_add_imm_32",
+            Self::SetGtUImm => "set_gt_u_imm",
+            Self::SetGtSImm => "set_gt_s_imm",
+            Self::ShloLImmAlt32 => "shlo_l_imm_alt_32",
+            Self::ShloRImmAlt32 => "shlo_r_imm_alt_32",
+            Self::SharRImmAlt32 => "shar_r_imm_alt_32",
+            Self::CmovIzImm => "cmov_iz_imm",
+            Self::CmovNzImm => "cmov_nz_imm",
+            Self::AddImm64 => "add_imm_64",
+            Self::MulImm64 => "mul_imm_64",
+            Self::ShloLImm64 => "shlo_l_imm_64",
+            Self::ShloRImm64 => "shlo_r_imm_64",
+            Self::SharRImm64 => "shar_r_imm_64",
+            Self::NegAddImm64 => "neg_add_imm_64",
+            Self::ShloLImmAlt64 => "shlo_l_imm_alt_64",
+            Self::ShloRImmAlt64 => "shlo_r_imm_alt_64",
+            Self::SharRImmAlt64 => "shar_r_imm_alt_64",
+            Self::RotRImm64 => "rot_r_imm_64",
+            Self::RotRImmAlt64 => "rot_r_imm_alt_64",
+            Self::RotRImm32 => "rot_r_imm_32",
+            Self::RotRImmAlt32 => "rot_r_imm_alt_32",
+            Self::BranchEq => "branch_eq",
+            Self::BranchNe => "branch_ne",
+            Self::BranchLtU => "branch_lt_u",
+            Self::BranchLtS => "branch_lt_s",
+            Self::BranchGeU => "branch_ge_u",
+            Self::BranchGeS => "branch_ge_s",
+            Self::LoadImmJumpInd => "load_imm_jump_ind",
+            Self::Add32 => "add_32",
+            Self::Sub32 => "sub_32",
+            Self::Mul32 => "mul_32",
+            Self::DivU32 => "div_u32",
+            Self::DivS32 => "div_s_32",
+            Self::RemU32 => "rem_u32",
+            Self::RemS32 => "rem_s_32",
+            Self::ShloL32 => "shlo_l_32",
+            Self::ShloR32 => "shlo_r_32",
+            Self::SharR32 => "shar_r_32",
+            Self::Add64 => "add_64",
+            Self::Sub64 => "sub_64",
+            Self::Mul64 => "mul_64",
+            Self::DivU64 => "div_u64",
+            Self::DivS64 => "div_s_64",
+            Self::RemU64 => "rem_u64",
+            Self::RemS64 => "rem_s_64",
+            Self::ShloL64 => "shlo_l_64",
+            Self::ShloR64 => "shlo_r_64",
+            Self::SharR64 => "shar_r_64",
+            Self::And => "and",
+            Self::Xor => "xor",
+            Self::Or => "or",
+            Self::MulUpperSS => "mul_upper_s_s",
+            Self::MulUpperUU => "mul_upper_u_u",
+            Self::MulUpperSU => "mul_upper_s_u",
+            Self::SetLtU => "set_lt_u",
+            Self::SetLtS => "set_lt_s",
+            Self::CmovIz => "cmov_iz",
+            Self::CmovNz => "cmov_nz",
+            Self::RotL64 => "rot_l_64",
+            Self::RotL32 => "rot_l_32",
+            Self::RotR64 => "rot_r_64",
+            Self::RotR32 => "rot_r_32",
+            Self::AndInv => "and_inv",
+            Self::OrInv => "or_inv",
+            Self::Xnor => "xnor",
+            Self::Max => "max",
+            Self::MaxU => "max_u",
+            Self::Min => "min",
+            Self::MinU => "min_u",
+        }
+    }
+
+    /// One-line semantics of the instruction, in the Gray Paper's notation.
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Trap => "Panic: halt execution with a trap",
+            Self::Fallthrough => "No-op marking the start of a basic block",
+            Self::Ecalli => {
+                "Host call: invoke host function `index` (arguments and results in registers)"
+            }
+            Self::LoadImm64 => "Load 64-bit immediate: reg[dst] = value",
+            Self::StoreImmU8 => "Store immediate to absolute address: mem[address] = value (u8)",
+            Self::StoreImmU16 => "Store immediate to absolute address: mem[address] = value (u16)",
+            Self::StoreImmU32 => "Store immediate to absolute address: mem[address] = value (u32)",
+            Self::StoreImmU64 => {
+                "Store immediate to absolute address: mem[address] = `sign_extend(value)` (u64)"
+            }
+            Self::Jump => "Unconditional jump: goto(PC + offset)",
+            Self::JumpInd => {
+                "Indirect jump through the jump table: goto(jump_table[(reg[base] + offset) / 2 - 1])"
+            }
+            Self::LoadImm => "Load immediate: reg[dst] = `sign_extend(value)`",
+            Self::LoadU8 => {
+                "Load from absolute address: reg[dst] = `zero_extend(mem[address])` (u8)"
+            }
+            Self::LoadI8 => {
+                "Load from absolute address: reg[dst] = `sign_extend(mem[address])` (i8)"
+            }
+            Self::LoadU16 => {
+                "Load from absolute address: reg[dst] = `zero_extend(mem[address])` (u16)"
+            }
+            Self::LoadI16 => {
+                "Load from absolute address: reg[dst] = `sign_extend(mem[address])` (i16)"
+            }
+            Self::LoadU32 => {
+                "Load from absolute address: reg[dst] = `zero_extend(mem[address])` (u32)"
+            }
+            Self::LoadI32 => {
+                "Load from absolute address: reg[dst] = `sign_extend(mem[address])` (i32)"
+            }
+            Self::LoadU64 => "Load from absolute address: reg[dst] = mem[address] (u64)",
+            Self::StoreU8 => "Store to absolute address: mem[address] = reg[src] (u8)",
+            Self::StoreU16 => "Store to absolute address: mem[address] = reg[src] (u16)",
+            Self::StoreU32 => "Store to absolute address: mem[address] = reg[src] (u32)",
+            Self::StoreU64 => "Store to absolute address: mem[address] = reg[src] (u64)",
+            Self::StoreImmIndU8 => {
+                "Store immediate to [base + offset]: mem[reg[base] + offset] = value (u8)"
+            }
+            Self::StoreImmIndU16 => {
+                "Store immediate to [base + offset]: mem[reg[base] + offset] = value (u16)"
+            }
+            Self::StoreImmIndU32 => {
+                "Store immediate to [base + offset]: mem[reg[base] + offset] = value (u32)"
+            }
+            Self::StoreImmIndU64 => {
+                "Store immediate to [base + offset]: mem[reg[base] + offset] = `sign_extend(value)` (u64)"
+            }
+            Self::LoadImmJump => {
+                "Combined load-immediate + jump: `reg = sign_extend(value); goto(PC + offset)`"
+            }
+            Self::BranchEqImm => {
+                "Branch if equal immediate: if reg[reg] == `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchNeImm => {
+                "Branch if not equal immediate: if reg[reg] != `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchLtUImm => {
+                "Branch if less than (unsigned) immediate: if reg[reg] <u `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchLeUImm => {
+                "Branch if less or equal (unsigned) immediate: if reg[reg] <=u `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchGeUImm => {
+                "Branch if greater or equal (unsigned) immediate: if reg[reg] >=u `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchGtUImm => {
+                "Branch if greater than (unsigned) immediate: if reg[reg] >u `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchLtSImm => {
+                "Branch if less than (signed) immediate: if reg[reg] <s `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchLeSImm => {
+                "Branch if less or equal (signed) immediate: if reg[reg] <=s `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchGeSImm => {
+                "Branch if greater or equal (signed) immediate: if reg[reg] >=s `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::BranchGtSImm => {
+                "Branch if greater than (signed) immediate: if reg[reg] >s `sign_extend(value)` then goto(PC + offset)"
+            }
+            Self::MoveReg => "Register copy: reg[dst] = reg[src]",
+            Self::Sbrk => {
+                "Grow the heap by reg[src] bytes: reg[dst] = previous heap end (0 on failure)"
+            }
+            Self::CountSetBits64 => "Population count (64-bit): reg[dst] = popcount(reg[src])",
+            Self::CountSetBits32 => {
+                "Population count (32-bit): reg[dst] = popcount(reg[src] mod 2^32)"
+            }
+            Self::LeadingZeroBits64 => "Count leading zeros (64-bit): reg[dst] = clz(reg[src])",
+            Self::LeadingZeroBits32 => {
+                "Count leading zeros (32-bit): reg[dst] = clz(reg[src] mod 2^32)"
+            }
+            Self::TrailingZeroBits64 => "Count trailing zeros (64-bit): reg[dst] = ctz(reg[src])",
+            Self::TrailingZeroBits32 => {
+                "Count trailing zeros (32-bit): reg[dst] = ctz(reg[src] mod 2^32)"
+            }
+            Self::SignExtend8 => {
+                "Sign-extend the low byte: reg[dst] = `sign_extend(reg[src] mod 2^8)`"
+            }
+            Self::SignExtend16 => {
+                "Sign-extend the low 16 bits: reg[dst] = `sign_extend(reg[src] mod 2^16)`"
+            }
+            Self::ZeroExtend16 => "Zero-extend the low 16 bits: reg[dst] = reg[src] mod 2^16",
+            Self::ReverseBytes => "Reverse byte order: reg[dst] = bswap(reg[src])",
+            Self::StoreIndU8 => "Store indirect: mem[reg[base] + offset] = reg[src] (u8)",
+            Self::StoreIndU16 => "Store indirect: mem[reg[base] + offset] = reg[src] (u16)",
+            Self::StoreIndU32 => "Store indirect: mem[reg[base] + offset] = reg[src] (u32)",
+            Self::StoreIndU64 => "Store indirect: mem[reg[base] + offset] = reg[src] (u64)",
+            Self::LoadIndU8 => {
+                "Load indirect: reg[dst] = `zero_extend(mem[reg[base] + offset])` (u8)"
+            }
+            Self::LoadIndI8 => {
+                "Load indirect: reg[dst] = `sign_extend(mem[reg[base] + offset])` (i8)"
+            }
+            Self::LoadIndU16 => {
+                "Load indirect: reg[dst] = `zero_extend(mem[reg[base] + offset])` (u16)"
+            }
+            Self::LoadIndI16 => {
+                "Load indirect: reg[dst] = `sign_extend(mem[reg[base] + offset])` (i16)"
+            }
+            Self::LoadIndU32 => {
+                "Load indirect: reg[dst] = `zero_extend(mem[reg[base] + offset])` (u32)"
+            }
+            Self::LoadIndI32 => {
+                "Load indirect with sign extension: reg[dst] = `sign_extend(mem[reg[base] + offset])` (i32)"
+            }
+            Self::LoadIndU64 => "Load indirect: reg[dst] = mem[reg[base] + offset] (u64)",
+            Self::AddImm32 => {
+                "Add immediate (32-bit): reg[dst] = `sign_extend((reg[src] + value) mod 2^32)`"
+            }
+            Self::AndImm => {
+                "Bitwise AND with immediate: reg[dst] = reg[src] & `sign_extend(value)`"
+            }
+            Self::XorImm => {
+                "Bitwise XOR with immediate: reg[dst] = reg[src] ^ `sign_extend(value)`"
+            }
+            Self::OrImm => "Bitwise OR with immediate: reg[dst] = reg[src] | `sign_extend(value)`",
+            Self::MulImm32 => {
+                "Multiply by immediate (32-bit): reg[dst] = `sign_extend((reg[src] * value) mod 2^32)`"
+            }
+            Self::SetLtUImm => {
+                "Set if less than immediate (unsigned): reg[dst] = reg[src] <u `sign_extend(value)` ? 1 : 0"
+            }
+            Self::SetLtSImm => {
+                "Set if less than immediate (signed): reg[dst] = reg[src] <s value ? 1 : 0"
+            }
+            Self::ShloLImm32 => {
+                "Shift left by immediate (32-bit): reg[dst] = `sign_extend((reg[src] << value) mod 2^32)`"
+            }
+            Self::ShloRImm32 => {
+                "Shift logical right by immediate (32-bit): reg[dst] = `sign_extend((reg[src] mod 2^32) >> value)`"
+            }
+            Self::SharRImm32 => {
+                "Shift arithmetic right by immediate (32-bit): reg[dst] = `sign_extend(reg[src] mod 2^32)` >>> value"
+            }
+            Self::NegAddImm32 => {
+                "Reverse subtract immediate (32-bit): reg[dst] = `sign_extend((value - reg[src]) mod 2^32)`"
+            }
+            Self::SetGtUImm => {
+                "Set if greater than immediate (unsigned): reg[dst] = reg[src] >u `sign_extend(value)` ? 1 : 0"
+            }
+            Self::SetGtSImm => {
+                "Set if greater than immediate (signed): reg[dst] = reg[src] >s value ? 1 : 0"
+            }
+            Self::ShloLImmAlt32 => {
+                "Alternate shift left immediate (32-bit): reg[dst] = `sign_extend(value)` << reg[src]"
+            }
+            Self::ShloRImmAlt32 => {
+                "Alternate shift logical right immediate (32-bit): reg[dst] = `sign_extend(value)` >> reg[src]"
+            }
+            Self::SharRImmAlt32 => {
+                "Alternate shift arithmetic right immediate (32-bit): reg[dst] = `sign_extend(value)` >>> reg[src]"
+            }
+            Self::CmovIzImm => {
+                "Conditional move if zero with immediate: if reg[cond] == 0 then reg[dst] = `sign_extend(value)`"
+            }
+            Self::CmovNzImm => {
+                "Conditional move if non-zero with immediate: if reg[cond] != 0 then reg[dst] = `sign_extend(value)`"
+            }
+            Self::AddImm64 => "Add immediate (64-bit): reg[dst] = reg[src] + `sign_extend(value)`",
+            Self::MulImm64 => {
+                "Multiply by immediate (64-bit): reg[dst] = reg[src] * `sign_extend(value)`"
+            }
+            Self::ShloLImm64 => "Shift left by immediate (64-bit): reg[dst] = reg[src] << value",
+            Self::ShloRImm64 => {
+                "Shift logical right by immediate (64-bit): reg[dst] = reg[src] >> value"
+            }
+            Self::SharRImm64 => {
+                "Shift arithmetic right by immediate (64-bit): reg[dst] = reg[src] >>> value"
+            }
+            Self::NegAddImm64 => {
+                "Reverse subtract immediate (64-bit): reg[dst] = `sign_extend(value)` - reg[src]"
+            }
+            Self::ShloLImmAlt64 => {
+                "Alternate shift left immediate (64-bit): reg[dst] = `sign_extend(value)` << reg[src]"
+            }
+            Self::ShloRImmAlt64 => {
+                "Alternate shift logical right immediate (64-bit): reg[dst] = `sign_extend(value)` >> reg[src]"
+            }
+            Self::SharRImmAlt64 => {
+                "Alternate shift arithmetic right immediate (64-bit): reg[dst] = `sign_extend(value)` >>> reg[src]"
+            }
+            Self::RotRImm64 => "Rotate right by immediate (64-bit): reg[dst] = reg[src] ROR value",
+            Self::RotRImmAlt64 => {
+                "Alternate rotate right (64-bit): reg[dst] = `sign_extend(value)` ROR reg[src]"
+            }
+            Self::RotRImm32 => "Rotate right by immediate (32-bit): reg[dst] = reg[src] ROR value",
+            Self::RotRImmAlt32 => {
+                "Alternate rotate right (32-bit): reg[dst] = `sign_extend(value)` ROR reg[src]"
+            }
+            Self::BranchEq => "Branch if equal: if reg[reg2] == reg[reg1] then goto(PC + offset)",
+            Self::BranchNe => {
+                "Branch if not equal: if reg[reg2] != reg[reg1] then goto(PC + offset)"
+            }
+            Self::BranchLtU => {
+                "Branch if less than (unsigned, reversed operands): if reg[reg2] <u reg[reg1] then goto(PC + offset)"
+            }
+            Self::BranchLtS => {
+                "Branch if less than (signed, reversed operands): if reg[reg2] <s reg[reg1] then goto(PC + offset)"
+            }
+            Self::BranchGeU => {
+                "Branch if greater or equal (unsigned, reversed operands): if reg[reg2] >=u reg[reg1] then goto(PC + offset)"
+            }
+            Self::BranchGeS => {
+                "Branch if greater or equal (signed, reversed operands): if reg[reg2] >=s reg[reg1] then goto(PC + offset)"
+            }
+            Self::LoadImmJumpInd => {
+                "Combined load-immediate + indirect jump: `reg[dst] = sign_extend(value); jump to reg[base] + sign_extend(offset)`"
+            }
+            Self::Add32 => {
+                "Add (32-bit): reg[dst] = `sign_extend((reg[src1] + reg[src2]) mod 2^32)`"
+            }
+            Self::Sub32 => {
+                "Subtract (32-bit): reg[dst] = `sign_extend((reg[src1] - reg[src2]) mod 2^32)`"
+            }
+            Self::Mul32 => {
+                "Multiply (32-bit): reg[dst] = `sign_extend((reg[src1] * reg[src2]) mod 2^32)`"
+            }
+            Self::DivU32 => {
+                "Divide (32-bit, unsigned): reg[dst] = reg[src1] /u reg[src2] (all ones on division by zero)"
+            }
+            Self::DivS32 => {
+                "Divide (32-bit, signed): reg[dst] = reg[src1] /s reg[src2] (all ones on division by zero)"
+            }
+            Self::RemU32 => {
+                "Remainder (32-bit, unsigned): reg[dst] = reg[src1] %u reg[src2] (reg[src1] on division by zero)"
+            }
+            Self::RemS32 => {
+                "Remainder (32-bit, signed): reg[dst] = reg[src1] %s reg[src2] (reg[src1] on division by zero)"
+            }
+            Self::ShloL32 => {
+                "Shift left (32-bit): reg[dst] = `sign_extend((reg[src1] << (reg[src2] mod 32)) mod 2^32)`"
+            }
+            Self::ShloR32 => {
+                "Shift logical right (32-bit): reg[dst] = `sign_extend((reg[src1] mod 2^32) >> (reg[src2] mod 32))`"
+            }
+            Self::SharR32 => {
+                "Shift arithmetic right (32-bit): reg[dst] = `sign_extend(reg[src1] mod 2^32)` >>> (reg[src2] mod 32)"
+            }
+            Self::Add64 => "Add (64-bit): reg[dst] = reg[src1] + reg[src2]",
+            Self::Sub64 => "Subtract (64-bit): reg[dst] = reg[src1] - reg[src2]",
+            Self::Mul64 => "Multiply (64-bit): reg[dst] = reg[src1] * reg[src2]",
+            Self::DivU64 => {
+                "Divide (64-bit, unsigned): reg[dst] = reg[src1] /u reg[src2] (all ones on division by zero)"
+            }
+            Self::DivS64 => {
+                "Divide (64-bit, signed): reg[dst] = reg[src1] /s reg[src2] (all ones on division by zero)"
+            }
+            Self::RemU64 => {
+                "Remainder (64-bit, unsigned): reg[dst] = reg[src1] %u reg[src2] (reg[src1] on division by zero)"
+            }
+            Self::RemS64 => {
+                "Remainder (64-bit, signed): reg[dst] = reg[src1] %s reg[src2] (reg[src1] on division by zero)"
+            }
+            Self::ShloL64 => "Shift left (64-bit): reg[dst] = reg[src1] << (reg[src2] mod 64)",
+            Self::ShloR64 => {
+                "Shift logical right (64-bit): reg[dst] = reg[src1] >> (reg[src2] mod 64)"
+            }
+            Self::SharR64 => {
+                "Shift arithmetic right (64-bit): reg[dst] = reg[src1] >>> (reg[src2] mod 64)"
+            }
+            Self::And => "Bitwise AND: reg[dst] = reg[src1] & reg[src2]",
+            Self::Xor => "Bitwise XOR: reg[dst] = reg[src1] ^ reg[src2]",
+            Self::Or => "Bitwise OR: reg[dst] = reg[src1] | reg[src2]",
+            Self::MulUpperSS => "Upper 64 bits of signed*signed 128-bit multiply",
+            Self::MulUpperUU => "Upper 64 bits of unsigned*unsigned 128-bit multiply",
+            Self::MulUpperSU => "Upper 64 bits of signed*unsigned 128-bit multiply",
+            Self::SetLtU => {
+                "Set if less than (unsigned): reg[dst] = reg[src1] <u reg[src2] ? 1 : 0"
+            }
+            Self::SetLtS => "Set if less than (signed): reg[dst] = reg[src1] <s reg[src2] ? 1 : 0",
+            Self::CmovIz => {
+                "Conditional move if zero: `if reg[cond] == 0 then reg[dst] = reg[src]`"
+            }
+            Self::CmovNz => {
+                "Conditional move if non-zero: `if reg[cond] != 0 then reg[dst] = reg[src]`"
+            }
+            Self::RotL64 => "Rotate left (64-bit): reg[dst] = reg[src1] ROL reg[src2]",
+            Self::RotL32 => "Rotate left (32-bit): reg[dst] = reg[src1] ROL reg[src2]",
+            Self::RotR64 => "Rotate right (64-bit): reg[dst] = reg[src1] ROR reg[src2]",
+            Self::RotR32 => "Rotate right (32-bit): reg[dst] = reg[src1] ROR reg[src2]",
+            Self::AndInv => "AND with inverted second operand: reg[dst] = reg[src1] & ~reg[src2]",
+            Self::OrInv => "OR with inverted second operand: reg[dst] = reg[src1] | ~reg[src2]",
+            Self::Xnor => "XNOR: reg[dst] = ~(reg[src1] ^ reg[src2])",
+            Self::Max => "Signed maximum: reg[dst] = max(reg[src1], reg[src2])",
+            Self::MaxU => "Unsigned maximum: reg[dst] = `max_u(reg[src1], reg[src2])`",
+            Self::Min => "Signed minimum: reg[dst] = min(reg[src1], reg[src2])",
+            Self::MinU => "Unsigned minimum: reg[dst] = `min_u(reg[src1], reg[src2])`",
+        }
+    }
+
+    /// Look an opcode up by mnemonic. Case and underscores are ignored, so
+    /// `rot_r_64`, `rotr64` and `RotR64` all name the same instruction.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|&c| c != '_')
+                .map(|c| c.to_ascii_lowercase())
+                .collect::<alloc::string::String>()
+        };
+        let wanted = normalize(name);
+        Self::all().find(|op| normalize(op.name()) == wanted)
+    }
+}
+
+/// Operand layouts of PVM instructions (Gray Paper appendix A.5).
+///
+/// Immediates are little-endian and sign-extended from their encoded length
+/// (0-4 bytes). A layout with two immediates stores the first one's length
+/// in a nibble; the last immediate/offset runs to the end of the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingFamily {
+    /// `[op]`
+    NoArgs,
+    /// `[op, imm..]` — unsigned immediate.
+    OneImm,
+    /// `[op, reg, imm64]` — full 8-byte immediate.
+    OneRegExtImm,
+    /// `[op, len1, imm1.., imm2..]`
+    TwoImm,
+    /// `[op, offset..]`
+    OneOff,
+    /// `[op, reg, imm..]`
+    OneRegOneImm,
+    /// `[op, len1 << 4 | reg, imm1.., imm2..]`
+    OneRegTwoImm,
+    /// `[op, len1 << 4 | reg, imm.., offset..]`
+    OneRegOneImmOneOff,
+    /// `[op, rB << 4 | rA]`
+    TwoReg,
+    /// `[op, rB << 4 | rA, imm..]`
+    TwoRegOneImm,
+    /// `[op, rA << 4 | rB, offset..]`
+    TwoRegOneOff,
+    /// `[op, rA << 4 | rB, len1, imm1.., imm2..]`
+    TwoRegTwoImm,
+    /// `[op, rB << 4 | rA, rD]`
+    ThreeReg,
+}
+
+impl EncodingFamily {
+    /// Smallest valid encoding in bytes, opcode included (all immediates empty).
+    #[must_use]
+    pub const fn min_len(self) -> usize {
+        match self {
+            Self::NoArgs | Self::OneImm | Self::OneOff => 1,
+            Self::TwoImm
+            | Self::OneRegOneImm
+            | Self::OneRegTwoImm
+            | Self::OneRegOneImmOneOff
+            | Self::TwoReg
+            | Self::TwoRegOneImm
+            | Self::TwoRegOneOff => 2,
+            Self::TwoRegTwoImm | Self::ThreeReg => 3,
+            Self::OneRegExtImm => 10,
+        }
+    }
+
+    /// Largest valid encoding in bytes, opcode included (all immediates 4 bytes).
+    #[must_use]
+    pub const fn max_len(self) -> usize {
+        match self {
+            Self::NoArgs => 1,
+            Self::TwoReg => 2,
+            Self::ThreeReg => 3,
+            Self::OneImm | Self::OneOff => 5,
+            Self::OneRegOneImm | Self::TwoRegOneImm | Self::TwoRegOneOff => 6,
+            Self::TwoImm | Self::OneRegTwoImm | Self::OneRegOneImmOneOff | Self::OneRegExtImm => 10,
+            Self::TwoRegTwoImm => 11,
+        }
+    }
 }
 
 impl TryFrom<u8> for Opcode {
     type Error = ();
 
-    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error> {
         Self::from_u8(value).ok_or(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Opcode;
+
+    #[test]
+    fn all_opcode_bytes_are_unique() {
+        let bytes: Vec<u8> = Opcode::all().map(|op| op as u8).collect();
+        // `all()` walks bytes in order, so a byte decoding to a variant with a
+        // different discriminant shows up as a repeat or out-of-order entry.
+        assert!(
+            bytes.windows(2).all(|w| w[0] < w[1]),
+            "opcode bytes are not unique: {bytes:?}"
+        );
+        for op in Opcode::all() {
+            assert_eq!(Opcode::try_from(op as u8), Ok(op));
+        }
+    }
+
+    #[test]
+    fn mnemonics_are_unique_and_round_trip() {
+        let mut seen = std::collections::HashSet::new();
+        for op in Opcode::all() {
+            assert!(seen.insert(op.name()), "duplicate mnemonic {}", op.name());
+            assert_eq!(Opcode::from_name(op.name()), Some(op));
+            assert!(!op.description().is_empty(), "{op:?} has no description");
+        }
+        assert_eq!(Opcode::from_name("ADD64"), Some(Opcode::Add64));
+        assert_eq!(Opcode::from_name("no_such_op"), None);
+    }
+
+    #[test]
+    fn explain_rot_r_64_mentions_rotate() {
+        let op = Opcode::from_name("rot_r_64").expect("rot_r_64 is a mnemonic");
+        assert_eq!(op, Opcode::RotR64);
+        assert!(op.description().to_lowercase().contains("rotate"));
+    }
+}
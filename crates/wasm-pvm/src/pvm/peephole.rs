@@ -8,6 +8,21 @@ use std::collections::BTreeSet;
 use super::Instruction;
 use crate::llvm_backend::{LlvmCallFixup, LlvmIndirectCallFixup};
 
+/// Move each label from the byte offset it has in `old_offsets` to the one
+/// the same instruction has in `new_offsets`.
+///
+/// Both are indexed by instruction, with the end of the stream last (see
+/// `instruction_offsets`), so every pass that changes encoded lengths in place
+/// snapshots the offsets first and calls this afterwards. Labels that are not
+/// at an instruction boundary are left alone.
+fn remap_labels(old_offsets: &[usize], new_offsets: &[usize], labels: &mut [Option<usize>]) {
+    for label in labels.iter_mut().flatten() {
+        if let Ok(idx) = old_offsets.binary_search(label) {
+            *label = new_offsets[idx];
+        }
+    }
+}
+
 /// Compact the instruction stream by removing entries where `keep[i]` is false.
 ///
 /// Updates all fixup indices and label byte offsets to match the compacted stream.
@@ -23,23 +38,8 @@ fn compact_instructions(
     let len = keep.len();
     debug_assert_eq!(len, instructions.len());
 
-    // Cache encoded length per instruction to avoid repeated encode() calls.
-    let encoded_lengths: Vec<usize> = instructions.iter().map(|i| i.encode().len()).collect();
-
-    // Compute byte offset for each instruction before compaction.
-    let mut byte_offsets = Vec::with_capacity(len + 1);
-    let mut running = 0usize;
-    for &enc_len in &encoded_lengths {
-        byte_offsets.push(running);
-        running += enc_len;
-    }
-    byte_offsets.push(running);
-
-    // Build reverse map: byte_offset → instruction_index for label resolution.
-    let mut byte_to_idx = std::collections::BTreeMap::new();
-    for (idx, &off) in byte_offsets.iter().enumerate() {
-        byte_to_idx.entry(off).or_insert(idx);
-    }
+    // Byte offset of each instruction before compaction.
+    let byte_offsets = super::instruction_offsets(instructions);
 
     // Build old→new index remap.
     let mut remap = vec![0usize; len + 1];
@@ -69,24 +69,10 @@ fn compact_instructions(
     }
     instructions.truncate(write);
 
-    // Recompute byte offsets for the compacted stream using cached lengths.
-    let mut new_byte_offsets = Vec::with_capacity(instructions.len() + 1);
-    let mut new_running = 0usize;
-    for (old_idx, &kept) in keep.iter().enumerate() {
-        if kept {
-            new_byte_offsets.push(new_running);
-            new_running += encoded_lengths[old_idx];
-        }
-    }
-    new_byte_offsets.push(new_running);
-
-    // Update labels: map old byte offset → old instr index → new instr index → new byte offset.
-    for label in labels.iter_mut().flatten() {
-        if let Some(&old_idx) = byte_to_idx.get(label) {
-            let new_i = remap[old_idx.min(len)];
-            *label = new_byte_offsets[new_i.min(instructions.len())];
-        }
-    }
+    // A label on a removed instruction moves to the next kept one.
+    let new_byte_offsets = super::instruction_offsets(instructions);
+    let moved_offsets: Vec<usize> = remap.iter().map(|&i| new_byte_offsets[i]).collect();
+    remap_labels(&byte_offsets, &moved_offsets, labels);
 
     // Remap all fixup indices.
     for (instr_idx, _label) in fixups.iter_mut() {
@@ -169,6 +155,155 @@ pub fn eliminate_dead_stores(
     );
 }
 
+/// Remove the `Fallthrough`s that `define_label` inserts in front of labels no
+/// jump or branch refers to.
+///
+/// A label is a jump target only if some fixup names it; blocks reached purely
+/// by falling through from the previous one don't need to start a new basic
+/// block. A `Fallthrough` right after a terminator (a call's return point) is
+/// always kept.
+///
+/// Only sound while `fixups` lists every way a label can be reached, so it
+/// must not run on streams whose labels are also jump-table entries. Must be
+/// called **before** `resolve_fixups()`.
+pub fn remove_untargeted_fallthroughs(
+    instructions: &mut Vec<Instruction>,
+    fixups: &mut [(usize, usize)],
+    call_fixups: &mut [LlvmCallFixup],
+    indirect_call_fixups: &mut [LlvmIndirectCallFixup],
+    labels: &mut [Option<usize>],
+) {
+    let len = instructions.len();
+    if len < 2 {
+        return;
+    }
+
+    let targeted: BTreeSet<usize> = fixups
+        .iter()
+        .filter_map(|&(_, label)| labels.get(label).copied().flatten())
+        .collect();
+
+    let mut keep = vec![true; len];
+    let mut offset = instructions[0].encoded_len();
+    for i in 1..len {
+        let next = offset + instructions[i].encoded_len();
+        if matches!(instructions[i], Instruction::Fallthrough)
+            && !instructions[i - 1].is_terminating()
+            && !targeted.contains(&next)
+        {
+            keep[i] = false;
+        }
+        offset = next;
+    }
+
+    compact_instructions(
+        instructions,
+        &keep,
+        fixups,
+        call_fixups,
+        indirect_call_fixups,
+        labels,
+    );
+}
+
+/// Remove the instructions no path from the function entry reaches.
+///
+/// Reachability walks the instruction stream from index 0. Execution moves on
+/// to the next instruction after anything that is not a terminator, after a
+/// `Fallthrough`, a conditional branch and a call (which resumes at its return
+/// point); jumps and branches also reach the label their fixup names, and
+/// `Trap` and the returning `JumpInd` end the path. LLVM has already dropped
+/// its unreachable blocks, so what this finds is code behind a `Trap` that
+/// LLVM still considers live, e.g. the rest of a block after a call to an
+/// import mapped to `trap`.
+///
+/// Successors are read off the PVM instructions and their fixups rather than
+/// through `llvm_backend::successors`: that walks LLVM terminators, and the
+/// dead code here sits *inside* an LLVM block, after a `Trap` only the
+/// lowering introduced. The LLVM CFG has no edge to drop.
+///
+/// Fixups and call fixups of removed instructions are dropped rather than
+/// remapped; a removed call keeps its jump-table slot, which stays 0. Keeps
+/// everything if a jump or branch has no fixup, since its target is unknown.
+/// Like [`remove_untargeted_fallthroughs`], only sound while `fixups` lists
+/// every way a label can be reached. Must be called **before**
+/// `resolve_fixups()`.
+pub fn eliminate_dead_blocks(
+    instructions: &mut Vec<Instruction>,
+    fixups: &mut Vec<(usize, usize)>,
+    call_fixups: &mut Vec<LlvmCallFixup>,
+    indirect_call_fixups: &mut Vec<LlvmIndirectCallFixup>,
+    labels: &mut [Option<usize>],
+) {
+    let len = instructions.len();
+    if len == 0 {
+        return;
+    }
+
+    let offsets = super::instruction_offsets(instructions);
+    let mut targets = vec![None; len];
+    for &(idx, label) in fixups.iter() {
+        let Some(target) = labels
+            .get(label)
+            .copied()
+            .flatten()
+            .and_then(|offset| offsets.binary_search(&offset).ok())
+        else {
+            return;
+        };
+        targets[idx] = Some(target);
+    }
+    let calls: BTreeSet<usize> = call_fixups
+        .iter()
+        .map(|fixup| fixup.jump_instr)
+        .chain(
+            indirect_call_fixups
+                .iter()
+                .map(|fixup| fixup.jump_ind_instr),
+        )
+        .collect();
+
+    let mut reachable = vec![false; len];
+    let mut worklist = vec![0];
+    while let Some(i) = worklist.pop() {
+        if i >= len || reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+
+        let instr = &instructions[i];
+        let is_call = calls.contains(&i);
+        if !instr.is_terminating()
+            || matches!(instr, Instruction::Fallthrough)
+            || instr.invert_branch().is_some()
+            || is_call
+        {
+            worklist.push(i + 1);
+        }
+        if let Some(target) = targets[i] {
+            worklist.push(target);
+        } else if instr.jump_offset().is_some() && !is_call {
+            return;
+        }
+    }
+
+    if reachable.iter().all(|&r| r) {
+        return;
+    }
+
+    fixups.retain(|&(idx, _)| reachable[idx]);
+    call_fixups.retain(|fixup| reachable[fixup.jump_instr]);
+    indirect_call_fixups.retain(|fixup| reachable[fixup.jump_ind_instr]);
+    compact_instructions(
+        instructions,
+        &reachable,
+        fixups,
+        call_fixups,
+        indirect_call_fixups,
+        labels,
+    );
+}
+
 /// Returns true if the instruction is a 32-bit producer that sign-extends its result.
 /// PVM 32-bit operations write `u32SignExtend(result)` to the destination register,
 /// so a subsequent `AddImm32(x, x, 0)` truncation is redundant.
@@ -220,11 +355,10 @@ pub fn optimize(
     // in place, which would desync our recomputed byte offsets from the
     // (stale) label offsets and silently let us drop a real branch target.
     //
-    // The peephole itself is size-preserving: removals (`keep[i+1] = false`)
-    // don't change any instruction's encoded length, and the
-    // `LoadIndU64 → MoveReg` rewrite is gated on an exact length match in
-    // `optimize_store_then_load`, so running it here doesn't disturb the
-    // size invariants the later passes rely on.
+    // Removals (`keep[i+1] = false`) don't change any instruction's encoded
+    // length, and when a `LoadIndU64 → MoveReg` rewrite shrinks the stream
+    // `optimize_store_then_load` remaps `labels` itself, so the later passes
+    // start from consistent byte offsets.
     let mut keep = vec![true; len];
     optimize_store_then_load(instructions, &mut keep, labels);
 
@@ -245,6 +379,15 @@ pub fn optimize(
         );
     }
 
+    // 2b. Algebraic identities (`x - x`, `x & x`, `x * 0`, `x | 0`, ...).
+    simplify_algebraic_identities(instructions, labels);
+
+    // 2c. ALU ops on known constants → `LoadImm` of the result.
+    fold_constants(instructions, &mut keep, labels);
+
+    // 2d. `LoadImm r, 0` when another register already holds 0 → reuse it.
+    reuse_zero_registers(instructions, &mut keep, labels);
+
     // 3. Simple peephole patterns (redundant fallthroughs).
     // Mark instructions for removal (true = keep, false = remove).
     let len = instructions.len();
@@ -289,9 +432,27 @@ pub fn optimize(
         }
     }
 
+    // 3b. Byte swap of a byte swap is the identity.
+    fold_double_reverse_bytes(instructions, &mut keep, labels);
+
+    // 3c. Branch over an unconditional jump → inverted branch to the jump's target.
+    invert_branch_over_jump(instructions, &mut keep, fixups, labels);
+
     // 4. Fuse LoadImm + AddImm chains and chained AddImm operations.
     // (Store/load peephole already ran at the top of `optimize()`.)
-    optimize_immediate_chains(instructions, &mut keep);
+    optimize_immediate_chains(instructions, &mut keep, labels);
+
+    // 5. `LoadImm r, v; StoreIndU* [base + off], r` → `StoreImmIndU*` when `r` is dead.
+    fuse_load_imm_store(instructions, &mut keep, labels);
+
+    // 6. `LoadImm r0, ret; Jump` → `LoadImmJump r0, ret` (legacy call sequences).
+    fuse_load_imm_jump(
+        instructions,
+        &mut keep,
+        call_fixups,
+        indirect_call_fixups,
+        labels,
+    );
 
     compact_instructions(
         instructions,
@@ -303,765 +464,2877 @@ pub fn optimize(
     );
 }
 
-/// Fuse `LoadImm` + `AddImm` chains and chained `AddImm` operations.
+/// Rewrite ALU ops whose result is an algebraic identity of their operands.
 ///
-/// Pattern 1: `LoadImm r1, A; AddImm r1, r1, B` → `LoadImm r1, A+B`
-/// Pattern 2: `AddImm r1, r1, A; AddImm r1, r1, B` → `AddImm r1, r1, A+B`
-/// Pattern 3: `MoveReg r1, r1` → remove (no-op self-move)
-fn optimize_immediate_chains(instructions: &mut [Instruction], keep: &mut [bool]) {
-    let len = instructions.len();
-    if len < 2 {
-        return;
+/// Three-register ops whose two sources are the same register:
+///
+/// - `Sub32`/`Sub64`/`Xor dst, x, x` → `LoadImm dst, 0` (the result is always 0)
+/// - `And`/`Or dst, x, x` → `MoveReg dst, x` (the result is `x`)
+///
+/// Immediate ops with a neutral or absorbing constant:
+///
+/// - `MulImm32`/`MulImm64 dst, x, 0` → `LoadImm dst, 0`
+/// - `MulImm64 dst, x, 1`, `OrImm`/`XorImm dst, x, 0`, `AndImm dst, x, -1` and
+///   the 64-bit shifts by 0 → `MoveReg dst, x`
+///
+/// `x + 0` and `x - 0` arrive as `AddImm64 dst, x, 0`, which
+/// `optimize_immediate_chains` already turns into a move. The 32-bit forms
+/// other than the multiply by 0 are left alone: they sign-extend the low 32
+/// bits of `x` rather than copy it.
+///
+/// The replacements encode shorter, so labels are remapped through instruction
+/// indices, as in `optimize_address_calculation`. A resulting `MoveReg x, x`
+/// is dropped later by `optimize_immediate_chains`.
+fn simplify_algebraic_identities(instructions: &mut [Instruction], labels: &mut [Option<usize>]) {
+    let old_byte_offsets = super::instruction_offsets(instructions);
+    let mut changed = false;
+    for instr in instructions.iter_mut() {
+        let replacement = match *instr {
+            Instruction::Sub32 { dst, src1, src2 }
+            | Instruction::Sub64 { dst, src1, src2 }
+            | Instruction::Xor { dst, src1, src2 }
+                if src1 == src2 =>
+            {
+                Instruction::LoadImm { reg: dst, value: 0 }
+            }
+            Instruction::And { dst, src1, src2 } | Instruction::Or { dst, src1, src2 }
+                if src1 == src2 =>
+            {
+                Instruction::MoveReg { dst, src: src1 }
+            }
+            Instruction::MulImm32 { dst, value: 0, .. }
+            | Instruction::MulImm64 { dst, value: 0, .. } => {
+                Instruction::LoadImm { reg: dst, value: 0 }
+            }
+            Instruction::MulImm64 { dst, src, value: 1 }
+            | Instruction::OrImm { dst, src, value: 0 }
+            | Instruction::XorImm { dst, src, value: 0 }
+            | Instruction::AndImm {
+                dst,
+                src,
+                value: -1,
+            }
+            | Instruction::ShloLImm64 { dst, src, value: 0 }
+            | Instruction::ShloRImm64 { dst, src, value: 0 }
+            | Instruction::SharRImm64 { dst, src, value: 0 } => Instruction::MoveReg { dst, src },
+            _ => continue,
+        };
+        *instr = replacement;
+        changed = true;
     }
+    if changed {
+        remap_labels(
+            &old_byte_offsets,
+            &super::instruction_offsets(instructions),
+            labels,
+        );
+    }
+}
 
-    // First pass: identify which instructions can be fused/removed
-    // We track which LoadImm instructions have been patched so we don't double-patch
-    let mut patched_loadimm = vec![false; len];
-
-    for i in 0..len.saturating_sub(1) {
+/// Replace ALU ops whose operands are all known constants with a `LoadImm`
+/// of the result.
+///
+/// Register values come from `LoadImm`/`LoadImm64` (and earlier folds) and
+/// are tracked through one straight-line run: a label, terminator or host
+/// call forgets them all, and any other write forgets its destination. The
+/// result follows PVM semantics: 32-bit ops wrap and sign-extend, shift
+/// amounts are taken modulo the width. It is folded only when it fits the
+/// sign-extended 32-bit immediate of `LoadImm`. Division and remainder by
+/// zero and the signed `MIN / -1` overflow are never folded; the guards the
+/// frontend emits around them stay meaningful.
+///
+/// A `LoadImm` whose only reader was the folded op is dropped once its
+/// register is dead (see `is_overwritten_before_read`). Labels are remapped
+/// through instruction indices, as in `optimize_immediate_chains`.
+fn fold_constants(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
+    labels: &mut [Option<usize>],
+) {
+    let len = instructions.len();
+    let label_offsets: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let old_byte_offsets = super::instruction_offsets(instructions);
+
+    // Per register: its known value, and the `LoadImm` that set it if no
+    // other instruction has read it since.
+    let mut known: [Option<u64>; 13] = [None; 13];
+    let mut unread_load: [Option<usize>; 13] = [None; 13];
+    let mut changed = false;
+    for i in 0..len {
+        if label_offsets.contains(&old_byte_offsets[i]) {
+            known = [None; 13];
+            unread_load = [None; 13];
+        }
         if !keep[i] {
             continue;
         }
-
-        // Pattern 3: MoveReg r1, r1 (self-move) - always remove
-        if let Instruction::MoveReg { dst, src } = instructions[i]
-            && dst == src
-        {
-            keep[i] = false;
+        let instr = &instructions[i];
+        if instr.is_terminating() || matches!(instr, Instruction::Ecalli { .. }) {
+            known = [None; 13];
+            unread_load = [None; 13];
             continue;
         }
-
-        // Pattern 1: LoadImm followed by AddImm32/64 to same register
-        if let Instruction::LoadImm {
-            reg: load_reg,
-            value: load_val,
-        } = instructions[i]
-            && i + 1 < len
-            && keep[i + 1]
-            && !patched_loadimm[i]
-        {
-            let can_fuse = match &instructions[i + 1] {
-                Instruction::AddImm32 {
-                    dst,
-                    src,
-                    value: add_val,
-                }
-                | Instruction::AddImm64 {
-                    dst,
-                    src,
-                    value: add_val,
-                } if *dst == load_reg && *src == load_reg => load_val.checked_add(*add_val),
-                _ => None,
-            };
-
-            if let Some(combined) = can_fuse {
-                // Mark the AddImm for removal and patch the LoadImm
-                keep[i + 1] = false;
-                instructions[i] = Instruction::LoadImm {
-                    reg: load_reg,
-                    value: combined,
-                };
-                patched_loadimm[i] = true;
+        match *instr {
+            Instruction::LoadImm { reg, value } => {
+                known[reg as usize] = Some(i64::from(value).cast_unsigned());
+                unread_load[reg as usize] = Some(i);
+                continue;
             }
+            Instruction::LoadImm64 { reg, value } => {
+                known[reg as usize] = Some(value);
+                unread_load[reg as usize] = None;
+                continue;
+            }
+            _ => {}
         }
 
-        // Pattern 2: Chained AddImm to same register
-        let add_info = match &instructions[i] {
-            Instruction::AddImm32 { dst, src, value } if *dst == *src => Some((*dst, *value)),
-            Instruction::AddImm64 { dst, src, value } if *dst == *src => Some((*dst, *value)),
-            _ => None,
+        let srcs = instr.src_regs();
+        let folded = evaluate_constant_alu(instr, |reg| known[reg as usize]);
+        let Some(dst) = instr.dest_reg() else {
+            for reg in srcs.into_iter().flatten() {
+                unread_load[reg as usize] = None;
+            }
+            continue;
+        };
+        let Some(value) = folded.and_then(|v| i32::try_from(v.cast_signed()).ok()) else {
+            for reg in srcs.into_iter().flatten() {
+                unread_load[reg as usize] = None;
+            }
+            known[dst as usize] = folded;
+            unread_load[dst as usize] = None;
+            continue;
         };
 
-        if let Some((dst, val1)) = add_info
-            && i + 1 < len
-            && keep[i + 1]
-        {
-            let can_fuse = match &instructions[i + 1] {
-                Instruction::AddImm32 {
-                    dst: dst2,
-                    src: src2,
-                    value: val2,
-                } if *dst2 == dst && *src2 == dst => val1.checked_add(*val2),
-                Instruction::AddImm64 {
-                    dst: dst2,
-                    src: src2,
-                    value: val2,
-                } if *dst2 == dst && *src2 == dst => val1.checked_add(*val2),
-                _ => None,
-            };
-
-            if let Some(combined) = can_fuse {
-                // Mark the second AddImm for removal and patch the first
-                keep[i + 1] = false;
-                // Patch first instruction with combined value
-                match &mut instructions[i] {
-                    Instruction::AddImm32 { value, .. } | Instruction::AddImm64 { value, .. } => {
-                        *value = combined;
-                    }
-                    _ => unreachable!(),
-                }
+        instructions[i] = Instruction::LoadImm { reg: dst, value };
+        changed = true;
+        for reg in srcs.into_iter().flatten() {
+            if let Some(load) = unread_load[reg as usize].take()
+                && (reg == dst
+                    || is_overwritten_before_read(
+                        instructions,
+                        keep,
+                        i + 1,
+                        reg,
+                        &old_byte_offsets,
+                        &label_offsets,
+                    ))
+            {
+                keep[load] = false;
             }
         }
+        known[dst as usize] = Some(i64::from(value).cast_unsigned());
+        unread_load[dst as usize] = Some(i);
+    }
+    if !changed {
+        return;
     }
+
+    remap_labels(
+        &old_byte_offsets,
+        &super::instruction_offsets(instructions),
+        labels,
+    );
 }
 
-/// Eliminate redundant `LoadIndU64` immediately following a `StoreIndU64` at
-/// the same `(base, offset)`.
-///
-/// The store puts the value of `src` at `mem[base + offset]`. The load reads
-/// the same 8 bytes back into `dst`. If `dst == src`, the value is already in
-/// the register and the load is a pure no-op. If `dst != src`, the load
-/// becomes a register copy, which we encode as `MoveReg` (2 bytes — same as
-/// the smallest `LoadIndU64`).
+/// The value `instr` writes when `reg_value` knows all of its register
+/// operands, or `None` if it is not a foldable ALU op (see `fold_constants`).
+fn evaluate_constant_alu(
+    instr: &Instruction,
+    reg_value: impl Fn(u8) -> Option<u64>,
+) -> Option<u64> {
+    fn sx32(value: u64) -> u64 {
+        i64::from((value as u32).cast_signed()).cast_unsigned()
+    }
+    fn imm(value: i32) -> u64 {
+        i64::from(value).cast_unsigned()
+    }
+    let (a, b) = match *instr {
+        Instruction::Add32 { src1, src2, .. }
+        | Instruction::Sub32 { src1, src2, .. }
+        | Instruction::Mul32 { src1, src2, .. }
+        | Instruction::DivU32 { src1, src2, .. }
+        | Instruction::DivS32 { src1, src2, .. }
+        | Instruction::RemU32 { src1, src2, .. }
+        | Instruction::RemS32 { src1, src2, .. }
+        | Instruction::ShloL32 { src1, src2, .. }
+        | Instruction::ShloR32 { src1, src2, .. }
+        | Instruction::SharR32 { src1, src2, .. }
+        | Instruction::Add64 { src1, src2, .. }
+        | Instruction::Sub64 { src1, src2, .. }
+        | Instruction::Mul64 { src1, src2, .. }
+        | Instruction::DivU64 { src1, src2, .. }
+        | Instruction::DivS64 { src1, src2, .. }
+        | Instruction::RemU64 { src1, src2, .. }
+        | Instruction::RemS64 { src1, src2, .. }
+        | Instruction::ShloL64 { src1, src2, .. }
+        | Instruction::ShloR64 { src1, src2, .. }
+        | Instruction::SharR64 { src1, src2, .. }
+        | Instruction::And { src1, src2, .. }
+        | Instruction::Or { src1, src2, .. }
+        | Instruction::Xor { src1, src2, .. } => (reg_value(src1)?, reg_value(src2)?),
+        Instruction::AddImm32 { src, value, .. }
+        | Instruction::MulImm32 { src, value, .. }
+        | Instruction::ShloLImm32 { src, value, .. }
+        | Instruction::ShloRImm32 { src, value, .. }
+        | Instruction::SharRImm32 { src, value, .. }
+        | Instruction::AddImm64 { src, value, .. }
+        | Instruction::MulImm64 { src, value, .. }
+        | Instruction::ShloLImm64 { src, value, .. }
+        | Instruction::ShloRImm64 { src, value, .. }
+        | Instruction::SharRImm64 { src, value, .. }
+        | Instruction::AndImm { src, value, .. }
+        | Instruction::OrImm { src, value, .. }
+        | Instruction::XorImm { src, value, .. } => (reg_value(src)?, imm(value)),
+        _ => return None,
+    };
+    let (a32, b32) = (a as u32, b as u32);
+    Some(match instr {
+        Instruction::Add32 { .. } | Instruction::AddImm32 { .. } => sx32(a.wrapping_add(b)),
+        Instruction::Sub32 { .. } => sx32(a.wrapping_sub(b)),
+        Instruction::Mul32 { .. } | Instruction::MulImm32 { .. } => sx32(a.wrapping_mul(b)),
+        Instruction::DivU32 { .. } => sx32(u64::from(a32.checked_div(b32)?)),
+        Instruction::RemU32 { .. } => sx32(u64::from(a32.checked_rem(b32)?)),
+        Instruction::DivS32 { .. } => {
+            i64::from(a32.cast_signed().checked_div(b32.cast_signed())?).cast_unsigned()
+        }
+        Instruction::RemS32 { .. } => {
+            i64::from(a32.cast_signed().checked_rem(b32.cast_signed())?).cast_unsigned()
+        }
+        Instruction::ShloL32 { .. } | Instruction::ShloLImm32 { .. } => {
+            sx32(u64::from(a32 << (b % 32)))
+        }
+        Instruction::ShloR32 { .. } | Instruction::ShloRImm32 { .. } => {
+            sx32(u64::from(a32 >> (b % 32)))
+        }
+        Instruction::SharR32 { .. } | Instruction::SharRImm32 { .. } => {
+            i64::from(a32.cast_signed() >> (b % 32)).cast_unsigned()
+        }
+        Instruction::Add64 { .. } | Instruction::AddImm64 { .. } => a.wrapping_add(b),
+        Instruction::Sub64 { .. } => a.wrapping_sub(b),
+        Instruction::Mul64 { .. } | Instruction::MulImm64 { .. } => a.wrapping_mul(b),
+        Instruction::DivU64 { .. } => a.checked_div(b)?,
+        Instruction::RemU64 { .. } => a.checked_rem(b)?,
+        Instruction::DivS64 { .. } => a
+            .cast_signed()
+            .checked_div(b.cast_signed())?
+            .cast_unsigned(),
+        Instruction::RemS64 { .. } => a
+            .cast_signed()
+            .checked_rem(b.cast_signed())?
+            .cast_unsigned(),
+        Instruction::ShloL64 { .. } | Instruction::ShloLImm64 { .. } => a << (b % 64),
+        Instruction::ShloR64 { .. } | Instruction::ShloRImm64 { .. } => a >> (b % 64),
+        Instruction::SharR64 { .. } | Instruction::SharRImm64 { .. } => {
+            (a.cast_signed() >> (b % 64)).cast_unsigned()
+        }
+        Instruction::And { .. } | Instruction::AndImm { .. } => a & b,
+        Instruction::Or { .. } | Instruction::OrImm { .. } => a | b,
+        Instruction::Xor { .. } | Instruction::XorImm { .. } => a ^ b,
+        _ => return None,
+    })
+}
+
+/// Drop `LoadImm r, 0` when another register already holds 0.
 ///
-/// Skipped if a label points at the load's byte offset (branches from
-/// elsewhere may target the load and depend on `dst` being materialized).
-fn optimize_store_then_load(
+/// Zeros are tracked through one straight-line run, as in `fold_constants`.
+/// A `LoadImm r, 0` is removed outright when `r` is already 0, and otherwise
+/// when some `z` is: its next reader must be a commutative three-register op
+/// (`Add*`, `Mul*`, `And`, `Or`, `Xor`), which then reads `z` instead, with
+/// neither register written in between and `r` dead afterwards. Only
+/// register numbers change, so no instruction moves and labels stay put.
+fn reuse_zero_registers(
     instructions: &mut [Instruction],
     keep: &mut [bool],
     labels: &[Option<usize>],
 ) {
     let len = instructions.len();
-    if len < 2 {
-        return;
-    }
-
-    // Byte offset of each instruction (so we can match against label targets).
-    let mut byte_offsets = Vec::with_capacity(len + 1);
-    let mut running = 0usize;
-    for instr in instructions.iter() {
-        byte_offsets.push(running);
-        running += instr.encode().len();
-    }
-    byte_offsets.push(running);
-
-    let labeled: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let label_offsets: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let offsets = super::instruction_offsets(instructions);
 
-    for i in 0..len - 1 {
-        if !keep[i] || !keep[i + 1] {
-            continue;
+    let mut zero = [false; 13];
+    for i in 0..len {
+        if label_offsets.contains(&offsets[i]) {
+            zero = [false; 13];
         }
-
-        let Instruction::StoreIndU64 {
-            base: s_base,
-            src: s_src,
-            offset: s_off,
-        } = instructions[i]
-        else {
-            continue;
-        };
-        let Instruction::LoadIndU64 {
-            dst: l_dst,
-            base: l_base,
-            offset: l_off,
-        } = instructions[i + 1]
-        else {
-            continue;
-        };
-        if s_base != l_base || s_off != l_off {
+        if !keep[i] {
             continue;
         }
-        if labeled.contains(&byte_offsets[i + 1]) {
-            // Branch target — must not drop the load.
+        let instr = &instructions[i];
+        if instr.is_terminating() || matches!(instr, Instruction::Ecalli { .. }) {
+            zero = [false; 13];
             continue;
         }
-
-        if l_dst == s_src {
-            // Pure no-op: value already in dst. Marking for removal is safe
-            // because `compact_instructions` knows how to remove instructions
-            // (it remaps fixups + labels through `byte_to_idx`).
-            keep[i + 1] = false;
-        } else {
-            // Replace load with a register-to-register copy. Both `MoveReg`
-            // and `LoadIndU64` use 2-byte encodings only when the load's
-            // offset is zero (`encode_imm(0)` returns an empty slice). For
-            // any non-zero offset, `LoadIndU64` is 3-6 bytes while `MoveReg`
-            // stays at 2, and `compact_instructions` caches `encoded_lengths`
-            // BEFORE this pass mutates, so a size-shrinking in-place rewrite
-            // would silently leave labels past the rewrite point pointing at
-            // stale byte offsets. Only do the rewrite when encoded sizes
-            // match (the offset == 0 case); skip otherwise.
-            let replacement = Instruction::MoveReg {
-                dst: l_dst,
-                src: s_src,
-            };
-            if replacement.encode().len() == instructions[i + 1].encode().len() {
-                instructions[i + 1] = replacement;
+        match *instr {
+            Instruction::LoadImm { reg, value: 0 } => {
+                if zero[reg as usize] {
+                    keep[i] = false;
+                    continue;
+                }
+                let reuse = (0..13u8).filter(|&z| zero[z as usize]).find_map(|z| {
+                    zero_reader(instructions, keep, i + 1, reg, z, &offsets, &label_offsets)
+                        .map(|reader| (reader, z))
+                });
+                if let Some((reader, z)) = reuse {
+                    if let Some(sources) = commutative_sources_mut(&mut instructions[reader]) {
+                        for src in sources {
+                            if *src == reg {
+                                *src = z;
+                            }
+                        }
+                    }
+                    keep[i] = false;
+                } else {
+                    zero[reg as usize] = true;
+                }
+            }
+            Instruction::LoadImm64 { reg, value: 0 } => zero[reg as usize] = true,
+            _ => {
+                if let Some(dst) = instr.dest_reg() {
+                    zero[dst as usize] = false;
+                }
             }
         }
     }
 }
 
-/// Optimize address calculations by fusing `AddImm` into `LoadInd`/`StoreInd` offsets.
-/// Also performs simple copy propagation for `MoveReg`.
-///
-/// Pattern:
-/// 1. `MoveReg dst=A, src=B` → Record A is alias of B.
-/// 2. `AddImm dst=A, src=B, val=C` → Record A is B + C.
-/// 3. `LoadInd base=A, offset=D` → Rewrite as `LoadInd base=B, offset=C+D`.
-///
-/// This pass assumes sequential execution within basic blocks (reset at labels/branches).
-/// It updates instructions in-place.
-pub fn optimize_address_calculation(
-    instructions: &mut [Instruction],
-    labels: &mut [Option<usize>],
-) {
-    #[derive(Clone, Copy, PartialEq, Eq)]
-    enum AddImmWidth {
-        W32,
-        W64,
-    }
-
-    #[derive(Clone, Copy)]
-    struct TrackedAddress {
-        base: u8,
-        offset: i32,
-        // Width of the AddImm that produced this relation. `None` means plain
-        // alias/copy tracking (e.g., MoveReg), which is safe for both widths.
-        width: Option<AddImmWidth>,
+/// The index of the next reader of `reg` after `start` if `reuse_zero_registers`
+/// may point it at `zero` instead (see there).
+fn zero_reader(
+    instructions: &[Instruction],
+    keep: &[bool],
+    start: usize,
+    reg: u8,
+    zero: u8,
+    offsets: &[usize],
+    label_offsets: &BTreeSet<usize>,
+) -> Option<usize> {
+    for k in (start..instructions.len()).filter(|&k| keep[k]) {
+        let instr = &instructions[k];
+        if label_offsets.contains(&offsets[k])
+            || instr.is_terminating()
+            || matches!(instr, Instruction::Ecalli { .. })
+        {
+            return None;
+        }
+        if instr.src_regs().contains(&Some(reg)) {
+            let commutative = matches!(
+                instr,
+                Instruction::Add32 { .. }
+                    | Instruction::Add64 { .. }
+                    | Instruction::Mul32 { .. }
+                    | Instruction::Mul64 { .. }
+                    | Instruction::And { .. }
+                    | Instruction::Or { .. }
+                    | Instruction::Xor { .. }
+            );
+            let dead_after = instr.dest_reg() == Some(reg)
+                || is_overwritten_before_read(
+                    instructions,
+                    keep,
+                    k + 1,
+                    reg,
+                    offsets,
+                    label_offsets,
+                );
+            return (commutative && dead_after).then_some(k);
+        }
+        if matches!(instr.dest_reg(), Some(dst) if dst == reg || dst == zero) {
+            return None;
+        }
     }
+    None
+}
 
-    // Map register -> tracked address relation.
-    // entry[R] = Some({base, offset, ..}) means value of R is (value of base) + offset.
-    let mut state: [Option<TrackedAddress>; 13] = [None; 13];
-
-    // Compute pre-pass byte offsets (one per instruction) and build a reverse map
-    // (byte_offset → instruction index). We need this to remap labels after the pass,
-    // because mutating offsets can change the encoded length of an instruction.
-    let mut old_byte_offsets: Vec<usize> = Vec::with_capacity(instructions.len() + 1);
-    let mut running = 0usize;
-    for instr in instructions.iter() {
-        old_byte_offsets.push(running);
-        running += instr.encode().len();
-    }
-    // Include the end-of-stream offset so labels pointing past the last instruction
-    // (e.g., a label defined after the last emitted instruction) are also remapped.
-    old_byte_offsets.push(running);
-    let mut old_offset_to_idx: std::collections::BTreeMap<usize, usize> =
-        std::collections::BTreeMap::new();
-    for (idx, &off) in old_byte_offsets.iter().enumerate() {
-        old_offset_to_idx.entry(off).or_insert(idx);
+/// Both source registers of the ops `zero_reader` accepts.
+fn commutative_sources_mut(instr: &mut Instruction) -> Option<[&mut u8; 2]> {
+    match instr {
+        Instruction::Add32 { src1, src2, .. }
+        | Instruction::Add64 { src1, src2, .. }
+        | Instruction::Mul32 { src1, src2, .. }
+        | Instruction::Mul64 { src1, src2, .. }
+        | Instruction::And { src1, src2, .. }
+        | Instruction::Or { src1, src2, .. }
+        | Instruction::Xor { src1, src2, .. } => Some([src1, src2]),
+        _ => None,
     }
+}
 
-    // Track label offsets (pre-pass) to reset state at block boundaries.
-    let mut label_offsets = BTreeSet::new();
-    for label in labels.iter().flatten() {
-        label_offsets.insert(*label);
+/// Rewrite `if cond goto L1; goto L2; L1:` as `if !cond goto L2; L1:`.
+///
+/// The jump must not be a branch target itself. Both branches encode the same
+/// fields, so only the removed jump moves labels. Its fixup can't be dropped
+/// from the slice, so it is pointed at the inverted branch with the same label
+/// as the branch's own fixup; resolving the pair twice is harmless.
+fn invert_branch_over_jump(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
+    fixups: &mut [(usize, usize)],
+    labels: &[Option<usize>],
+) {
+    let len = instructions.len();
+    if len < 3 {
+        return;
     }
-
-    for (i, instr) in instructions.iter_mut().enumerate() {
-        let byte_offset = old_byte_offsets[i];
-
-        // If this instruction is a label target, reset state.
-        if label_offsets.contains(&byte_offset) {
-            state = [None; 13];
+    let label_offsets: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let offsets = super::instruction_offsets(instructions);
+    let fixup_of: std::collections::BTreeMap<usize, usize> = fixups
+        .iter()
+        .enumerate()
+        .map(|(fixup_idx, &(instr_idx, _))| (instr_idx, fixup_idx))
+        .collect();
+
+    for i in 0..len - 2 {
+        if !keep[i] || !keep[i + 1] || label_offsets.contains(&offsets[i + 1]) {
+            continue;
         }
-
-        // 1. Try to rewrite usage of registers based on state.
-        match instr {
-            Instruction::LoadIndU8 { base, offset, .. }
-            | Instruction::LoadIndI8 { base, offset, .. }
-            | Instruction::LoadIndU16 { base, offset, .. }
-            | Instruction::LoadIndI16 { base, offset, .. }
-            | Instruction::LoadIndU32 { base, offset, .. }
-            | Instruction::LoadIndU64 { base, offset, .. }
-            | Instruction::StoreIndU8 { base, offset, .. }
-            | Instruction::StoreIndU16 { base, offset, .. }
-            | Instruction::StoreIndU32 { base, offset, .. }
-            | Instruction::StoreIndU64 { base, offset, .. } => {
-                if let Some(tracked) = state[*base as usize]
-                    && let Some(new_off) = offset.checked_add(tracked.offset)
-                {
-                    *base = tracked.base;
-                    *offset = new_off;
-                }
-            }
-            Instruction::JumpInd { reg, offset, .. } => {
-                if let Some(tracked) = state[*reg as usize]
-                    && let Some(new_off) = offset.checked_add(tracked.offset)
-                {
-                    *reg = tracked.base;
-                    *offset = new_off;
-                }
-            }
-            Instruction::AddImm32 { src, value, .. } => {
-                if let Some(tracked) = state[*src as usize]
-                    && matches!(tracked.width, None | Some(AddImmWidth::W32))
-                    && let Some(new_val) = value.checked_add(tracked.offset)
-                {
-                    *src = tracked.base;
-                    *value = new_val;
-                }
-            }
-            Instruction::AddImm64 { src, value, .. } => {
-                if let Some(tracked) = state[*src as usize]
-                    && matches!(tracked.width, None | Some(AddImmWidth::W64))
-                    && let Some(new_val) = value.checked_add(tracked.offset)
-                {
-                    *src = tracked.base;
-                    *value = new_val;
-                }
-            }
-            _ => {}
+        let (Some(inverted), Instruction::Jump { .. }) =
+            (instructions[i].invert_branch(), &instructions[i + 1])
+        else {
+            continue;
+        };
+        let (Some(&branch_fixup), Some(&jump_fixup)) = (fixup_of.get(&i), fixup_of.get(&(i + 1)))
+        else {
+            continue;
+        };
+        let branch_label = fixups[branch_fixup].1;
+        if labels.get(branch_label).copied().flatten() != Some(offsets[i + 2]) {
+            continue;
         }
+        let jump_label = fixups[jump_fixup].1;
+        instructions[i] = inverted;
+        fixups[branch_fixup] = (i, jump_label);
+        fixups[jump_fixup] = (i, jump_label);
+        keep[i + 1] = false;
+    }
+}
 
-        // 2. Update state based on destination.
-        let dest = instr.dest_reg();
+/// Fold `ReverseBytes a, x; ReverseBytes b, a` into `MoveReg b, x`.
+///
+/// The first swap is dropped when `a` is dead afterwards (overwritten before
+/// any read within the same straight-line run); otherwise it stays and only
+/// the second becomes a move. Skipped when a label targets the second swap,
+/// since a branch could arrive there with a different `a`, or when `a == x`
+/// (the first swap clobbers the source). Both encodings are two bytes, so no
+/// label offsets move.
+fn fold_double_reverse_bytes(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
+    labels: &[Option<usize>],
+) {
+    let label_offsets: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let offsets = super::instruction_offsets(instructions);
 
-        // Invalidate any state that depends on the overwritten register.
-        if let Some(dst) = dest {
-            for s in &mut state {
-                if matches!(s, Some(TrackedAddress { base, .. }) if *base == dst) {
-                    *s = None;
-                }
-            }
+    for i in 0..instructions.len().saturating_sub(1) {
+        if !keep[i] || !keep[i + 1] || label_offsets.contains(&offsets[i + 1]) {
+            continue;
         }
-
-        // Set new state for dst.
-        match instr {
-            Instruction::MoveReg { dst, src } => {
-                if dst == src {
-                    state[*dst as usize] = None;
-                } else {
-                    state[*dst as usize] = state[*src as usize].or(Some(TrackedAddress {
-                        base: *src,
-                        offset: 0,
-                        width: None,
-                    }));
-                }
-            }
-            Instruction::AddImm32 { dst, src, value } => {
-                if dst == src {
-                    // In-place update (A = A + imm).
-                    // Original value of A is lost, so we cannot track A as an alias of (A + imm).
-                    state[*dst as usize] = None;
-                } else {
-                    // dst = src + value
-                    // If src is tracked (Base, Off), then dst = (Base, Off + value).
-                    // Else dst = (src, value).
-                    // Note: src/value might have been optimized in step 1 (folding constant).
-                    // If so, src is already Base.
-                    state[*dst as usize] = Some(TrackedAddress {
-                        base: *src,
-                        offset: *value,
-                        width: Some(AddImmWidth::W32),
-                    });
-                }
-            }
-            Instruction::AddImm64 { dst, src, value } => {
-                if dst == src {
-                    state[*dst as usize] = None;
-                } else {
-                    state[*dst as usize] = Some(TrackedAddress {
-                        base: *src,
-                        offset: *value,
-                        width: Some(AddImmWidth::W64),
-                    });
-                }
-            }
-            _ => {
-                // Other instructions overwrite the register with an unknown value.
-                // Clear tracking since there's nothing useful to fold.
-                if let Some(dst) = dest {
-                    state[dst as usize] = None;
-                }
-            }
+        let (
+            &Instruction::ReverseBytes { dst: a, src: x },
+            &Instruction::ReverseBytes { dst: b, src },
+        ) = (&instructions[i], &instructions[i + 1])
+        else {
+            continue;
+        };
+        if src != a || a == x {
+            continue;
+        }
+        instructions[i + 1] = Instruction::MoveReg { dst: b, src: x };
+        if b == a
+            || is_overwritten_before_read(instructions, keep, i + 2, a, &offsets, &label_offsets)
+        {
+            keep[i] = false;
         }
     }
+}
 
-    // Recompute post-pass byte offsets (encoded lengths may have changed).
-    // Update labels: map old byte offset → instruction index → new byte offset.
-    let mut new_byte_offsets: Vec<usize> = Vec::with_capacity(instructions.len() + 1);
-    let mut post_running = 0usize;
-    for instr in instructions.iter() {
-        new_byte_offsets.push(post_running);
-        post_running += instr.encode().len();
-    }
-    new_byte_offsets.push(post_running); // end-of-stream offset
-
-    for label in labels.iter_mut().flatten() {
-        if let Some(&idx) = old_offset_to_idx.get(label) {
-            *label = new_byte_offsets[idx];
+/// Whether `reg` is written before it is read, scanning forward from `start`
+/// without crossing a label, terminator or host call. Instructions already
+/// marked for removal are skipped. A conditional move into `reg` counts as a
+/// read: it keeps the old value when its condition fails.
+fn is_overwritten_before_read(
+    instructions: &[Instruction],
+    keep: &[bool],
+    start: usize,
+    reg: u8,
+    offsets: &[usize],
+    label_offsets: &BTreeSet<usize>,
+) -> bool {
+    for ((instr, offset), _) in instructions[start..]
+        .iter()
+        .zip(&offsets[start..])
+        .zip(&keep[start..])
+        .filter(|&(_, &kept)| kept)
+    {
+        if label_offsets.contains(offset)
+            || instr.is_terminating()
+            || matches!(instr, Instruction::Ecalli { .. })
+            || instr.src_regs().contains(&Some(reg))
+        {
+            return false;
+        }
+        if instr.dest_reg() == Some(reg) {
+            return !matches!(
+                instr,
+                Instruction::CmovIz { .. }
+                    | Instruction::CmovNz { .. }
+                    | Instruction::CmovIzImm { .. }
+                    | Instruction::CmovNzImm { .. }
+            );
         }
     }
+    false
 }
 
-/// Eliminate dead code (instructions defining unused registers).
+/// Fuse `LoadImm` + `AddImm` chains and chained `AddImm` operations.
 ///
-/// Iterates backwards to track liveness.
-/// Must be called **before** `resolve_fixups()` and after other optimizations.
-pub fn eliminate_dead_code(
-    instructions: &mut Vec<Instruction>,
-    fixups: &mut [(usize, usize)],
-    call_fixups: &mut [LlvmCallFixup],
-    indirect_call_fixups: &mut [LlvmIndirectCallFixup],
+/// Pattern 1: `LoadImm r1, A; AddImm r1, r1, B` → `LoadImm r1, A+B`
+/// Pattern 2: `AddImm r1, r1, A; AddImm r1, r1, B` → `AddImm r1, r1, A+B`
+/// Pattern 3: `MoveReg r1, r1` → remove (no-op self-move)
+/// Pattern 4: `AddImm64 r1, r2, 0` → `MoveReg r1, r2`, or remove when `r1 == r2`
+///
+/// Pattern 4 runs last, since pattern 2 can cancel a chain out to a zero
+/// addend. Both encode in two bytes (a zero immediate takes none), so the
+/// rewrite itself keeps every byte offset. `AddImm32 r1, r2, 0` is not a
+/// move: it sign-extends the low 32 bits of `r2`.
+///
+/// A fused immediate can encode shorter or longer than the original, so
+/// labels are remapped through instruction indices afterwards.
+fn optimize_immediate_chains(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
     labels: &mut [Option<usize>],
 ) {
     let len = instructions.len();
-    if len == 0 {
+    if len < 2 {
         return;
     }
 
-    let mut keep = vec![true; len];
-    let mut needed_regs = [true; 13]; // Default to all needed (conservative)
+    let old_byte_offsets = super::instruction_offsets(instructions);
 
-    // Compute byte offsets for label matching
-    let mut offsets = Vec::with_capacity(len);
-    let mut running = 0;
-    for instr in &*instructions {
-        offsets.push(running);
-        running += instr.encode().len();
-    }
-    let mut label_offsets = BTreeSet::new();
-    for label in labels.iter().flatten() {
-        label_offsets.insert(*label);
-    }
+    // First pass: identify which instructions can be fused/removed
+    // We track which LoadImm instructions have been patched so we don't double-patch
+    let mut patched_loadimm = vec![false; len];
 
-    for i in (0..len).rev() {
-        // If this is a label target, reset liveness to ALL (conservative).
-        if label_offsets.contains(&offsets[i]) {
-            needed_regs = [true; 13];
+    for i in 0..len.saturating_sub(1) {
+        if !keep[i] {
+            continue;
         }
 
-        let instr = &instructions[i];
-        let mut remove = false;
+        // Pattern 3: MoveReg r1, r1 (self-move) - always remove
+        if let Instruction::MoveReg { dst, src } = instructions[i]
+            && dst == src
+        {
+            keep[i] = false;
+            continue;
+        }
 
-        if instr.is_terminating() {
-            needed_regs = [true; 13];
-        } else {
-            match instr {
-                Instruction::StoreIndU8 { .. }
-                | Instruction::StoreIndU16 { .. }
-                | Instruction::StoreIndU32 { .. }
-                | Instruction::StoreIndU64 { .. }
-                | Instruction::StoreImmIndU8 { .. }
-                | Instruction::StoreImmIndU16 { .. }
-                | Instruction::StoreImmIndU32 { .. }
-                | Instruction::StoreImmIndU64 { .. }
-                | Instruction::StoreImmU8 { .. }
-                | Instruction::StoreImmU16 { .. }
-                | Instruction::StoreImmU32 { .. }
-                | Instruction::StoreImmU64 { .. }
-                | Instruction::StoreU8 { .. }
-                | Instruction::StoreU16 { .. }
-                | Instruction::StoreU32 { .. }
-                | Instruction::StoreU64 { .. }
-                | Instruction::Trap
-                | Instruction::Sbrk { .. } => {
-                    // Side effects (memory writes / traps), keep.
+        // Pattern 1: LoadImm followed by AddImm32/64 to same register
+        if let Instruction::LoadImm {
+            reg: load_reg,
+            value: load_val,
+        } = instructions[i]
+            && i + 1 < len
+            && keep[i + 1]
+            && !patched_loadimm[i]
+        {
+            let can_fuse = match &instructions[i + 1] {
+                Instruction::AddImm32 {
+                    dst,
+                    src,
+                    value: add_val,
                 }
-                // Memory loads can trap on out-of-bounds access: treat as side-effecting.
-                // The result register is still tracked for liveness so that any dead
-                // *definition* of that register earlier can be eliminated, but the load
-                // itself is never removed.
-                Instruction::LoadIndU8 { dst, .. }
-                | Instruction::LoadIndI8 { dst, .. }
-                | Instruction::LoadIndU16 { dst, .. }
-                | Instruction::LoadIndI16 { dst, .. }
-                | Instruction::LoadIndU32 { dst, .. }
-                | Instruction::LoadIndI32 { dst, .. }
-                | Instruction::LoadIndU64 { dst, .. }
-                | Instruction::LoadU8 { dst, .. }
-                | Instruction::LoadI8 { dst, .. }
-                | Instruction::LoadU16 { dst, .. }
-                | Instruction::LoadI16 { dst, .. }
-                | Instruction::LoadU32 { dst, .. }
-                | Instruction::LoadI32 { dst, .. }
-                | Instruction::LoadU64 { dst, .. } => {
-                    // Mark dst as no longer needed upstream (the load defines it here).
-                    needed_regs[*dst as usize] = false;
+                | Instruction::AddImm64 {
+                    dst,
+                    src,
+                    value: add_val,
+                } if *dst == load_reg && *src == load_reg => load_val.checked_add(*add_val),
+                _ => None,
+            };
+
+            if let Some(combined) = can_fuse {
+                // Mark the AddImm for removal and patch the LoadImm
+                keep[i + 1] = false;
+                instructions[i] = Instruction::LoadImm {
+                    reg: load_reg,
+                    value: combined,
+                };
+                patched_loadimm[i] = true;
+            }
+        }
+
+        // Pattern 2: Chained AddImm to same register
+        let add_info = match &instructions[i] {
+            Instruction::AddImm32 { dst, src, value } if *dst == *src => Some((*dst, *value)),
+            Instruction::AddImm64 { dst, src, value } if *dst == *src => Some((*dst, *value)),
+            _ => None,
+        };
+
+        if let Some((dst, val1)) = add_info
+            && i + 1 < len
+            && keep[i + 1]
+        {
+            let can_fuse = match &instructions[i + 1] {
+                Instruction::AddImm32 {
+                    dst: dst2,
+                    src: src2,
+                    value: val2,
+                } if *dst2 == dst && *src2 == dst => val1.checked_add(*val2),
+                Instruction::AddImm64 {
+                    dst: dst2,
+                    src: src2,
+                    value: val2,
+                } if *dst2 == dst && *src2 == dst => val1.checked_add(*val2),
+                _ => None,
+            };
+
+            if let Some(combined) = can_fuse {
+                // Mark the second AddImm for removal and patch the first
+                keep[i + 1] = false;
+                // Patch first instruction with combined value
+                match &mut instructions[i] {
+                    Instruction::AddImm32 { value, .. } | Instruction::AddImm64 { value, .. } => {
+                        *value = combined;
+                    }
+                    _ => unreachable!(),
                 }
-                // Integer division/remainder can trap on divide-by-zero: treat as
-                // side-effecting (never remove), but still track the destination register.
-                Instruction::DivU32 { dst, .. }
-                | Instruction::DivS32 { dst, .. }
-                | Instruction::RemU32 { dst, .. }
-                | Instruction::RemS32 { dst, .. }
-                | Instruction::DivU64 { dst, .. }
-                | Instruction::DivS64 { dst, .. }
-                | Instruction::RemU64 { dst, .. }
-                | Instruction::RemS64 { dst, .. } => {
-                    // Mark dst as no longer needed upstream (the instruction defines it).
-                    needed_regs[*dst as usize] = false;
+            }
+        }
+    }
+
+    // Pattern 4: AddImm64 with a zero addend is a register copy.
+    for (instr, kept) in instructions.iter_mut().zip(keep.iter_mut()) {
+        if !*kept {
+            continue;
+        }
+        if let Instruction::AddImm64 { dst, src, value: 0 } = *instr {
+            if dst == src {
+                *kept = false;
+            } else {
+                *instr = Instruction::MoveReg { dst, src };
+            }
+        }
+    }
+
+    remap_labels(
+        &old_byte_offsets,
+        &super::instruction_offsets(instructions),
+        labels,
+    );
+}
+
+/// Fuse `LoadImm r, v; StoreIndU* [base + off], r` into
+/// `StoreImmIndU* [base + off], v`.
+///
+/// Both immediates are sign-extended from 32 bits, so the stored bytes are the
+/// same. The `LoadImm` is dropped, so `r` must be dead after the store
+/// (overwritten before any read, see `is_overwritten_before_read`), must not
+/// be the base, and no label may target the store. A label on the `LoadImm`
+/// moves to the fused store on compaction. The fused store encodes shorter
+/// than the original, so labels are remapped through instruction indices, as
+/// in `optimize_immediate_chains`.
+fn fuse_load_imm_store(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
+    labels: &mut [Option<usize>],
+) {
+    let len = instructions.len();
+    let label_offsets: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let old_byte_offsets = super::instruction_offsets(instructions);
+
+    let mut changed = false;
+    for i in 0..len.saturating_sub(1) {
+        if !keep[i] || !keep[i + 1] || label_offsets.contains(&old_byte_offsets[i + 1]) {
+            continue;
+        }
+        let Instruction::LoadImm { reg, value } = instructions[i] else {
+            continue;
+        };
+        let fused = match instructions[i + 1] {
+            Instruction::StoreIndU8 { base, src, offset } if src == reg && base != reg => {
+                Instruction::StoreImmIndU8 {
+                    base,
+                    offset,
+                    value,
                 }
-                // Ecalli: the called host function may read any argument register.
-                // Reset liveness conservatively so that all register setup instructions
-                // feeding the Ecalli are preserved.
-                Instruction::Ecalli { .. } => {
-                    needed_regs = [true; 13];
+            }
+            Instruction::StoreIndU16 { base, src, offset } if src == reg && base != reg => {
+                Instruction::StoreImmIndU16 {
+                    base,
+                    offset,
+                    value,
                 }
-                _ => {
-                    if let Some(dst) = instr.dest_reg() {
-                        if needed_regs[dst as usize] {
-                            needed_regs[dst as usize] = false;
-                        } else {
-                            remove = true;
-                        }
-                    }
+            }
+            Instruction::StoreIndU32 { base, src, offset } if src == reg && base != reg => {
+                Instruction::StoreImmIndU32 {
+                    base,
+                    offset,
+                    value,
+                }
+            }
+            Instruction::StoreIndU64 { base, src, offset } if src == reg && base != reg => {
+                Instruction::StoreImmIndU64 {
+                    base,
+                    offset,
+                    value,
                 }
             }
+            _ => continue,
+        };
+        if !is_overwritten_before_read(
+            instructions,
+            keep,
+            i + 2,
+            reg,
+            &old_byte_offsets,
+            &label_offsets,
+        ) {
+            continue;
         }
+        instructions[i + 1] = fused;
+        keep[i] = false;
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+
+    remap_labels(
+        &old_byte_offsets,
+        &super::instruction_offsets(instructions),
+        labels,
+    );
+}
+
+/// Fuse a return-address load directly followed by a `Jump`, i.e.
+/// `LoadImm r0, ret; Jump off` (or `LoadImm64` of a value that fits in 32
+/// bits), into `LoadImmJump r0, ret, off`: one instruction, one gas less.
+///
+/// The emitter produces `LoadImmJump` for direct calls itself; this catches
+/// the two-instruction form in blobs from older compilers re-run through
+/// `reoptimize`. Only `RETURN_ADDR_REG` qualifies: a call's jump crosses
+/// functions and needs the wide offset anyway, while for an intra-function
+/// jump the fixed 4-byte `LoadImmJump` offset would often cost more bytes
+/// than the dropped instruction saves.
+///
+/// No label may target the `Jump`, and neither instruction may belong to a
+/// pending call fixup, which patches the pair by index once call targets are
+/// known. A branch fixup on the `Jump` stays on the fused instruction, and
+/// compaction moves a label on the `LoadImm` there too. The fused form
+/// encodes at a different length, so labels are remapped through instruction
+/// indices, as in `fuse_load_imm_store`.
+fn fuse_load_imm_jump(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
+    call_fixups: &[LlvmCallFixup],
+    indirect_call_fixups: &[LlvmIndirectCallFixup],
+    labels: &mut [Option<usize>],
+) {
+    const RA: u8 = crate::abi::RETURN_ADDR_REG;
+
+    let len = instructions.len();
+    let label_offsets: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+    let fixed_up: BTreeSet<usize> = call_fixups
+        .iter()
+        .flat_map(|f| [f.return_addr_instr, f.jump_instr])
+        .chain(
+            indirect_call_fixups
+                .iter()
+                .flat_map(|f| [f.return_addr_instr, f.jump_ind_instr]),
+        )
+        .collect();
+    let old_byte_offsets = super::instruction_offsets(instructions);
+
+    let mut changed = false;
+    for i in 0..len.saturating_sub(1) {
+        if !keep[i]
+            || !keep[i + 1]
+            || label_offsets.contains(&old_byte_offsets[i + 1])
+            || fixed_up.contains(&i)
+            || fixed_up.contains(&(i + 1))
+        {
+            continue;
+        }
+        let value = match instructions[i] {
+            Instruction::LoadImm { reg: RA, value } => value,
+            Instruction::LoadImm64 { reg: RA, value } => match i32::try_from(value.cast_signed()) {
+                Ok(value) => value,
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        let Instruction::Jump { offset } = instructions[i + 1] else {
+            continue;
+        };
+        instructions[i + 1] = Instruction::LoadImmJump {
+            reg: RA,
+            value,
+            offset,
+        };
+        keep[i] = false;
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+
+    remap_labels(
+        &old_byte_offsets,
+        &super::instruction_offsets(instructions),
+        labels,
+    );
+}
+
+/// Eliminate redundant `LoadIndU64` immediately following a `StoreIndU64` at
+/// the same `(base, offset)`.
+///
+/// The store puts the value of `src` at `mem[base + offset]`. The load reads
+/// the same 8 bytes back into `dst`. If `dst == src`, the value is already in
+/// the register and the load is a pure no-op. If `dst != src`, the load
+/// becomes `MoveReg dst, src`. Aliasing is handled conservatively: only the
+/// same literal base register and offset match, with nothing in between.
+///
+/// Skipped if a label points at the load's byte offset (branches from
+/// elsewhere may target the load and depend on `dst` being materialized).
+/// A load with a non-zero offset encodes longer than the `MoveReg`, so labels
+/// are remapped through instruction indices, as in `simplify_algebraic_identities`.
+fn optimize_store_then_load(
+    instructions: &mut [Instruction],
+    keep: &mut [bool],
+    labels: &mut [Option<usize>],
+) {
+    let len = instructions.len();
+    if len < 2 {
+        return;
+    }
+
+    // Byte offset of each instruction (so we can match against label targets).
+    let byte_offsets = super::instruction_offsets(instructions);
+
+    let labeled: BTreeSet<usize> = labels.iter().flatten().copied().collect();
+
+    let mut resized = false;
+    for i in 0..len - 1 {
+        if !keep[i] || !keep[i + 1] {
+            continue;
+        }
+
+        let Instruction::StoreIndU64 {
+            base: s_base,
+            src: s_src,
+            offset: s_off,
+        } = instructions[i]
+        else {
+            continue;
+        };
+        let Instruction::LoadIndU64 {
+            dst: l_dst,
+            base: l_base,
+            offset: l_off,
+        } = instructions[i + 1]
+        else {
+            continue;
+        };
+        if s_base != l_base || s_off != l_off {
+            continue;
+        }
+        if labeled.contains(&byte_offsets[i + 1]) {
+            // Branch target — must not drop the load.
+            continue;
+        }
+
+        if l_dst == s_src {
+            // Pure no-op: value already in dst. Marking for removal is safe
+            // because `compact_instructions` knows how to remove instructions
+            // (it remaps fixups and labels).
+            keep[i + 1] = false;
+        } else {
+            // Only a zero-offset `LoadIndU64` is as short as `MoveReg`
+            // (`encode_imm(0)` is empty); any other offset shrinks the stream.
+            let replacement = Instruction::MoveReg {
+                dst: l_dst,
+                src: s_src,
+            };
+            resized |= replacement.encoded_len() != instructions[i + 1].encoded_len();
+            instructions[i + 1] = replacement;
+        }
+    }
+    if resized {
+        remap_labels(
+            &byte_offsets,
+            &super::instruction_offsets(instructions),
+            labels,
+        );
+    }
+}
+
+/// Optimize address calculations by fusing `AddImm` into `LoadInd`/`StoreInd` offsets.
+/// Also performs simple copy propagation for `MoveReg`.
+///
+/// Pattern:
+/// 1. `MoveReg dst=A, src=B` → Record A is alias of B.
+/// 2. `AddImm dst=A, src=B, val=C` → Record A is B + C.
+/// 3. `LoadInd base=A, offset=D` → Rewrite as `LoadInd base=B, offset=C+D`.
+///
+/// Registers holding a known constant (`LoadImm`, or a `ShloLImm` of one) are
+/// tracked too, so an `Add` with a constant operand is first rewritten to the
+/// equivalent `AddImm` and then folds like any other. This covers array
+/// indexing (`ShloLImm idx, idx, log2(size); Add addr, base, idx; LoadInd`)
+/// when either the scaled index or the array base is a constant: the load
+/// absorbs `idx * size` (or the base address) into its offset, and the
+/// `LoadImm`s and `Add` are left for dead-code elimination.
+///
+/// This pass assumes sequential execution within basic blocks (reset at labels/branches).
+/// It updates instructions in-place.
+pub fn optimize_address_calculation(
+    instructions: &mut [Instruction],
+    labels: &mut [Option<usize>],
+) {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum AddImmWidth {
+        W32,
+        W64,
+    }
+
+    #[derive(Clone, Copy)]
+    struct TrackedAddress {
+        base: u8,
+        offset: i32,
+        // Width of the AddImm that produced this relation. `None` means plain
+        // alias/copy tracking (e.g., MoveReg), which is safe for both widths.
+        width: Option<AddImmWidth>,
+    }
+
+    // Map register -> tracked address relation.
+    // entry[R] = Some({base, offset, ..}) means value of R is (value of base) + offset.
+    let mut state: [Option<TrackedAddress>; 13] = [None; 13];
+    // Map register -> known constant value (as held in the 64-bit register).
+    let mut consts: [Option<i64>; 13] = [None; 13];
+
+    // Pre-pass byte offsets. We need these to remap labels after the pass,
+    // because mutating offsets can change the encoded length of an instruction.
+    let old_byte_offsets = super::instruction_offsets(instructions);
+
+    // Track label offsets (pre-pass) to reset state at block boundaries.
+    let mut label_offsets = BTreeSet::new();
+    for label in labels.iter().flatten() {
+        label_offsets.insert(*label);
+    }
+
+    for (i, instr) in instructions.iter_mut().enumerate() {
+        let byte_offset = old_byte_offsets[i];
+
+        // If this instruction is a label target, reset state.
+        if label_offsets.contains(&byte_offset) {
+            state = [None; 13];
+            consts = [None; 13];
+        }
+
+        // 0. `Add` with a constant operand → `AddImm`, so step 1 can fold it.
+        let known_imm = |reg: u8| consts[reg as usize].and_then(|c| i32::try_from(c).ok());
+        match *instr {
+            Instruction::Add32 { dst, src1, src2 } => {
+                if let Some(value) = known_imm(src2) {
+                    *instr = Instruction::AddImm32 {
+                        dst,
+                        src: src1,
+                        value,
+                    };
+                } else if let Some(value) = known_imm(src1) {
+                    *instr = Instruction::AddImm32 {
+                        dst,
+                        src: src2,
+                        value,
+                    };
+                }
+            }
+            Instruction::Add64 { dst, src1, src2 } => {
+                if let Some(value) = known_imm(src2) {
+                    *instr = Instruction::AddImm64 {
+                        dst,
+                        src: src1,
+                        value,
+                    };
+                } else if let Some(value) = known_imm(src1) {
+                    *instr = Instruction::AddImm64 {
+                        dst,
+                        src: src2,
+                        value,
+                    };
+                }
+            }
+            _ => {}
+        }
+
+        // 1. Try to rewrite usage of registers based on state.
+        match instr {
+            Instruction::LoadIndU8 { base, offset, .. }
+            | Instruction::LoadIndI8 { base, offset, .. }
+            | Instruction::LoadIndU16 { base, offset, .. }
+            | Instruction::LoadIndI16 { base, offset, .. }
+            | Instruction::LoadIndU32 { base, offset, .. }
+            | Instruction::LoadIndU64 { base, offset, .. }
+            | Instruction::StoreIndU8 { base, offset, .. }
+            | Instruction::StoreIndU16 { base, offset, .. }
+            | Instruction::StoreIndU32 { base, offset, .. }
+            | Instruction::StoreIndU64 { base, offset, .. } => {
+                if let Some(tracked) = state[*base as usize]
+                    && let Some(new_off) = offset.checked_add(tracked.offset)
+                {
+                    *base = tracked.base;
+                    *offset = new_off;
+                }
+            }
+            Instruction::JumpInd { reg, offset, .. } => {
+                if let Some(tracked) = state[*reg as usize]
+                    && let Some(new_off) = offset.checked_add(tracked.offset)
+                {
+                    *reg = tracked.base;
+                    *offset = new_off;
+                }
+            }
+            Instruction::AddImm32 { src, value, .. } => {
+                if let Some(tracked) = state[*src as usize]
+                    && matches!(tracked.width, None | Some(AddImmWidth::W32))
+                    && let Some(new_val) = value.checked_add(tracked.offset)
+                {
+                    *src = tracked.base;
+                    *value = new_val;
+                }
+            }
+            Instruction::AddImm64 { src, value, .. } => {
+                if let Some(tracked) = state[*src as usize]
+                    && matches!(tracked.width, None | Some(AddImmWidth::W64))
+                    && let Some(new_val) = value.checked_add(tracked.offset)
+                {
+                    *src = tracked.base;
+                    *value = new_val;
+                }
+            }
+            _ => {}
+        }
+
+        // 2. Update state based on destination.
+        let dest = instr.dest_reg();
+
+        if let Some(dst) = dest {
+            consts[dst as usize] = match *instr {
+                Instruction::LoadImm { value, .. } => Some(i64::from(value)),
+                Instruction::LoadImm64 { value, .. } => Some(value as i64),
+                Instruction::ShloLImm32 { src, value, .. } => consts[src as usize]
+                    .map(|c| i64::from((c as i32).wrapping_shl(value as u32 & 31))),
+                Instruction::ShloLImm64 { src, value, .. } => {
+                    consts[src as usize].map(|c| c.wrapping_shl(value as u32 & 63))
+                }
+                _ => None,
+            };
+        }
+        // Calls and host calls clobber registers they don't name as a dest.
+        if instr.is_terminating() || matches!(instr, Instruction::Ecalli { .. }) {
+            consts = [None; 13];
+        }
+
+        // Invalidate any state that depends on the overwritten register.
+        if let Some(dst) = dest {
+            for s in &mut state {
+                if matches!(s, Some(TrackedAddress { base, .. }) if *base == dst) {
+                    *s = None;
+                }
+            }
+        }
+
+        // Set new state for dst.
+        match instr {
+            Instruction::MoveReg { dst, src } => {
+                if dst == src {
+                    state[*dst as usize] = None;
+                } else {
+                    state[*dst as usize] = state[*src as usize].or(Some(TrackedAddress {
+                        base: *src,
+                        offset: 0,
+                        width: None,
+                    }));
+                }
+            }
+            Instruction::AddImm32 { dst, src, value } => {
+                if dst == src {
+                    // In-place update (A = A + imm).
+                    // Original value of A is lost, so we cannot track A as an alias of (A + imm).
+                    state[*dst as usize] = None;
+                } else {
+                    // dst = src + value
+                    // If src is tracked (Base, Off), then dst = (Base, Off + value).
+                    // Else dst = (src, value).
+                    // Note: src/value might have been optimized in step 1 (folding constant).
+                    // If so, src is already Base.
+                    state[*dst as usize] = Some(TrackedAddress {
+                        base: *src,
+                        offset: *value,
+                        width: Some(AddImmWidth::W32),
+                    });
+                }
+            }
+            Instruction::AddImm64 { dst, src, value } => {
+                if dst == src {
+                    state[*dst as usize] = None;
+                } else {
+                    state[*dst as usize] = Some(TrackedAddress {
+                        base: *src,
+                        offset: *value,
+                        width: Some(AddImmWidth::W64),
+                    });
+                }
+            }
+            _ => {
+                // Other instructions overwrite the register with an unknown value.
+                // Clear tracking since there's nothing useful to fold.
+                if let Some(dst) = dest {
+                    state[dst as usize] = None;
+                }
+            }
+        }
+    }
+
+    // Encoded lengths may have changed.
+    remap_labels(
+        &old_byte_offsets,
+        &super::instruction_offsets(instructions),
+        labels,
+    );
+}
+
+/// Eliminate dead code (instructions defining unused registers).
+///
+/// Iterates backwards to track liveness.
+/// Must be called **before** `resolve_fixups()` and after other optimizations.
+pub fn eliminate_dead_code(
+    instructions: &mut Vec<Instruction>,
+    fixups: &mut [(usize, usize)],
+    call_fixups: &mut [LlvmCallFixup],
+    indirect_call_fixups: &mut [LlvmIndirectCallFixup],
+    labels: &mut [Option<usize>],
+) {
+    let len = instructions.len();
+    if len == 0 {
+        return;
+    }
+
+    let mut keep = vec![true; len];
+    let mut needed_regs = [true; 13]; // Default to all needed (conservative)
+
+    // Compute byte offsets for label matching
+    let mut offsets = Vec::with_capacity(len);
+    let mut running = 0;
+    for instr in &*instructions {
+        offsets.push(running);
+        running += instr.encoded_len();
+    }
+    let mut label_offsets = BTreeSet::new();
+    for label in labels.iter().flatten() {
+        label_offsets.insert(*label);
+    }
+
+    for i in (0..len).rev() {
+        // If this is a label target, reset liveness to ALL (conservative).
+        if label_offsets.contains(&offsets[i]) {
+            needed_regs = [true; 13];
+        }
+
+        let instr = &instructions[i];
+        let mut remove = false;
+
+        if instr.is_terminating() {
+            needed_regs = [true; 13];
+        } else {
+            match instr {
+                Instruction::StoreIndU8 { .. }
+                | Instruction::StoreIndU16 { .. }
+                | Instruction::StoreIndU32 { .. }
+                | Instruction::StoreIndU64 { .. }
+                | Instruction::StoreImmIndU8 { .. }
+                | Instruction::StoreImmIndU16 { .. }
+                | Instruction::StoreImmIndU32 { .. }
+                | Instruction::StoreImmIndU64 { .. }
+                | Instruction::StoreImmU8 { .. }
+                | Instruction::StoreImmU16 { .. }
+                | Instruction::StoreImmU32 { .. }
+                | Instruction::StoreImmU64 { .. }
+                | Instruction::StoreU8 { .. }
+                | Instruction::StoreU16 { .. }
+                | Instruction::StoreU32 { .. }
+                | Instruction::StoreU64 { .. }
+                | Instruction::Trap
+                | Instruction::Sbrk { .. } => {
+                    // Side effects (memory writes / traps), keep.
+                }
+                // Memory loads can trap on out-of-bounds access: treat as side-effecting.
+                // The result register is still tracked for liveness so that any dead
+                // *definition* of that register earlier can be eliminated, but the load
+                // itself is never removed.
+                Instruction::LoadIndU8 { dst, .. }
+                | Instruction::LoadIndI8 { dst, .. }
+                | Instruction::LoadIndU16 { dst, .. }
+                | Instruction::LoadIndI16 { dst, .. }
+                | Instruction::LoadIndU32 { dst, .. }
+                | Instruction::LoadIndI32 { dst, .. }
+                | Instruction::LoadIndU64 { dst, .. }
+                | Instruction::LoadU8 { dst, .. }
+                | Instruction::LoadI8 { dst, .. }
+                | Instruction::LoadU16 { dst, .. }
+                | Instruction::LoadI16 { dst, .. }
+                | Instruction::LoadU32 { dst, .. }
+                | Instruction::LoadI32 { dst, .. }
+                | Instruction::LoadU64 { dst, .. } => {
+                    // Mark dst as no longer needed upstream (the load defines it here).
+                    needed_regs[*dst as usize] = false;
+                }
+                // Integer division/remainder can trap on divide-by-zero: treat as
+                // side-effecting (never remove), but still track the destination register.
+                Instruction::DivU32 { dst, .. }
+                | Instruction::DivS32 { dst, .. }
+                | Instruction::RemU32 { dst, .. }
+                | Instruction::RemS32 { dst, .. }
+                | Instruction::DivU64 { dst, .. }
+                | Instruction::DivS64 { dst, .. }
+                | Instruction::RemU64 { dst, .. }
+                | Instruction::RemS64 { dst, .. } => {
+                    // Mark dst as no longer needed upstream (the instruction defines it).
+                    needed_regs[*dst as usize] = false;
+                }
+                // Ecalli: the called host function may read any argument register.
+                // Reset liveness conservatively so that all register setup instructions
+                // feeding the Ecalli are preserved.
+                Instruction::Ecalli { .. } => {
+                    needed_regs = [true; 13];
+                }
+                _ => {
+                    if let Some(dst) = instr.dest_reg() {
+                        if needed_regs[dst as usize] {
+                            needed_regs[dst as usize] = false;
+                        } else {
+                            remove = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        if remove {
+            keep[i] = false;
+        } else {
+            // Mark sources needed
+            for reg in instr.src_regs().into_iter().flatten() {
+                needed_regs[reg as usize] = true;
+            }
+        }
+    }
+
+    compact_instructions(
+        instructions,
+        &keep,
+        fixups,
+        call_fixups,
+        indirect_call_fixups,
+        labels,
+    );
+}
+
+/// Rewrite absolute-address loads and stores (`LoadU32 { address }`, ...) to
+/// their indirect forms based on `base`, for position-independent output.
+///
+/// One-for-one, so instruction indices in fixups and labels stay valid; run
+/// it after the other passes, which may still fold addresses into absolute
+/// forms.
+pub fn rebase_absolute_accesses(instructions: &mut [Instruction], base: u8) {
+    for instr in instructions.iter_mut() {
+        let rebased = match *instr {
+            Instruction::LoadU8 { dst, address } => Instruction::LoadIndU8 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::LoadI8 { dst, address } => Instruction::LoadIndI8 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::LoadU16 { dst, address } => Instruction::LoadIndU16 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::LoadI16 { dst, address } => Instruction::LoadIndI16 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::LoadU32 { dst, address } => Instruction::LoadIndU32 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::LoadI32 { dst, address } => Instruction::LoadIndI32 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::LoadU64 { dst, address } => Instruction::LoadIndU64 {
+                dst,
+                base,
+                offset: address,
+            },
+            Instruction::StoreU8 { src, address } => Instruction::StoreIndU8 {
+                base,
+                src,
+                offset: address,
+            },
+            Instruction::StoreU16 { src, address } => Instruction::StoreIndU16 {
+                base,
+                src,
+                offset: address,
+            },
+            Instruction::StoreU32 { src, address } => Instruction::StoreIndU32 {
+                base,
+                src,
+                offset: address,
+            },
+            Instruction::StoreU64 { src, address } => Instruction::StoreIndU64 {
+                base,
+                src,
+                offset: address,
+            },
+            Instruction::StoreImmU8 { address, value } => Instruction::StoreImmIndU8 {
+                base,
+                offset: address,
+                value,
+            },
+            Instruction::StoreImmU16 { address, value } => Instruction::StoreImmIndU16 {
+                base,
+                offset: address,
+                value,
+            },
+            Instruction::StoreImmU32 { address, value } => Instruction::StoreImmIndU32 {
+                base,
+                offset: address,
+                value,
+            },
+            Instruction::StoreImmU64 { address, value } => Instruction::StoreImmIndU64 {
+                base,
+                offset: address,
+                value,
+            },
+            _ => continue,
+        };
+        *instr = rebased;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pvm::instruction_offsets;
+
+    const SP: u8 = crate::abi::STACK_PTR_REG;
+
+    #[test]
+    fn collapse_consecutive_fallthroughs() {
+        let mut instrs = vec![
+            Instruction::Fallthrough,
+            Instruction::Fallthrough,
+            Instruction::Fallthrough,
+            Instruction::LoadImm { reg: 0, value: 42 },
+        ];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![];
+        let mut indirect_call_fixups = vec![];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        assert_eq!(instrs.len(), 2);
+        assert!(matches!(instrs[0], Instruction::Fallthrough));
+        assert!(matches!(
+            instrs[1],
+            Instruction::LoadImm { reg: 0, value: 42 }
+        ));
+    }
+
+    #[test]
+    fn remove_fallthrough_before_jump() {
+        let mut instrs = vec![
+            Instruction::LoadImm { reg: 0, value: 1 },
+            Instruction::Fallthrough,
+            Instruction::Jump { offset: 0 },
+        ];
+        let mut fixups = vec![(2, 0)]; // jump at index 2
+        let mut call_fixups = vec![];
+        let mut indirect_call_fixups = vec![];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        assert_eq!(instrs.len(), 2);
+        assert!(matches!(instrs[0], Instruction::LoadImm { .. }));
+        assert!(matches!(instrs[1], Instruction::Jump { .. }));
+        assert_eq!(fixups[0].0, 1); // remapped from 2 to 1
+    }
+
+    #[test]
+    fn remove_fallthrough_before_load_imm_jump() {
+        let mut instrs = vec![
+            Instruction::Fallthrough,
+            Instruction::LoadImmJump {
+                reg: 0,
+                value: 2,
+                offset: 0,
+            },
+            Instruction::Fallthrough,
+        ];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![LlvmCallFixup {
+            return_addr_instr: 1,
+            jump_instr: 1,
+            target_func: 0,
+        }];
+        let mut indirect_call_fixups = vec![];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        // First Fallthrough removed (before LoadImmJump), second kept
+        assert_eq!(instrs.len(), 2);
+        assert!(matches!(instrs[0], Instruction::LoadImmJump { .. }));
+        assert!(matches!(instrs[1], Instruction::Fallthrough));
+        // Call fixup remapped from index 1 to 0
+        assert_eq!(call_fixups[0].return_addr_instr, 0);
+        assert_eq!(call_fixups[0].jump_instr, 0);
+    }
+
+    #[test]
+    fn remove_fallthrough_before_load_imm_jump_ind() {
+        let mut instrs = vec![
+            Instruction::Fallthrough,
+            Instruction::LoadImmJumpInd {
+                base: 8,
+                dst: 0,
+                value: 2,
+                offset: 0,
+            },
+            Instruction::Fallthrough,
+        ];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![];
+        let mut indirect_call_fixups = vec![LlvmIndirectCallFixup {
+            return_addr_instr: 1,
+            jump_ind_instr: 1,
+        }];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        // First Fallthrough removed (before LoadImmJumpInd), second kept.
+        assert_eq!(instrs.len(), 2);
+        assert!(matches!(instrs[0], Instruction::LoadImmJumpInd { .. }));
+        assert!(matches!(instrs[1], Instruction::Fallthrough));
+        // Indirect call fixup remapped from index 1 to 0.
+        assert_eq!(indirect_call_fixups[0].return_addr_instr, 0);
+        assert_eq!(indirect_call_fixups[0].jump_ind_instr, 0);
+    }
+
+    #[test]
+    fn remove_fallthrough_before_trap() {
+        let mut instrs = vec![Instruction::Fallthrough, Instruction::Trap];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![];
+        let mut indirect_call_fixups = vec![];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        assert_eq!(instrs.len(), 1);
+        assert!(matches!(instrs[0], Instruction::Trap));
+    }
+
+    #[test]
+    fn remaps_call_fixups() {
+        let mut instrs = vec![
+            Instruction::Fallthrough,
+            Instruction::Fallthrough,
+            Instruction::LoadImm { reg: 0, value: 0 }, // return_addr_instr
+            Instruction::Jump { offset: 0 },           // jump_instr
+        ];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![LlvmCallFixup {
+            return_addr_instr: 2,
+            jump_instr: 3,
+            target_func: 0,
+        }];
+        let mut indirect_call_fixups = vec![];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        // One fallthrough removed (first one before second fallthrough)
+        assert_eq!(instrs.len(), 3);
+        assert_eq!(call_fixups[0].return_addr_instr, 1);
+        assert_eq!(call_fixups[0].jump_instr, 2);
+    }
+
+    #[test]
+    fn no_op_when_nothing_to_optimize() {
+        // r2 is unknown, so the add cannot be constant-folded.
+        let mut instrs = vec![
+            Instruction::LoadImm { reg: 0, value: 1 },
+            Instruction::LoadImm { reg: 1, value: 2 },
+            Instruction::Add64 {
+                dst: 3,
+                src1: 0,
+                src2: 2,
+            },
+        ];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![];
+        let mut indirect_call_fixups = vec![];
+        let mut labels = vec![];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        assert_eq!(instrs.len(), 3);
+    }
+
+    #[test]
+    fn updates_label_byte_offsets() {
+        // Fallthrough (1 byte), Fallthrough (1 byte), LoadImm (6 bytes)
+        // Label at byte offset 2 (pointing to LoadImm)
+        let mut instrs = vec![
+            Instruction::Fallthrough,
+            Instruction::Fallthrough,
+            Instruction::LoadImm { reg: 0, value: 42 },
+        ];
+        let mut fixups = vec![];
+        let mut call_fixups = vec![];
+        let mut indirect_call_fixups = vec![];
+        // Label points to byte offset 2 (start of LoadImm, after two 1-byte Fallthroughs)
+        let mut labels = vec![Some(2usize)];
+
+        optimize(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut labels,
+        );
+
+        // First Fallthrough removed, so: Fallthrough (1 byte), LoadImm (6 bytes)
+        assert_eq!(instrs.len(), 2);
+        // Label should now point to byte offset 1 (start of LoadImm after one Fallthrough)
+        assert_eq!(labels[0], Some(1));
+    }
+
+    #[test]
+    fn untargeted_label_fallthroughs_are_removed() {
+        let mut instrs = vec![
+            Instruction::LoadImm { reg: 2, value: 1 },
+            Instruction::Fallthrough, // label 0: not a target
+            Instruction::AddImm64 {
+                dst: 2,
+                src: 2,
+                value: 1,
+            },
+            Instruction::Fallthrough, // label 1: branched to below
+            Instruction::AddImm64 {
+                dst: 3,
+                src: 2,
+                value: 5,
+            },
+            Instruction::BranchNeImm {
+                reg: 3,
+                value: 9,
+                offset: 0,
+            },
+            Instruction::Fallthrough, // after a terminator: always kept
+            Instruction::Trap,
+        ];
+        let offset_after = |instrs: &[Instruction], idx: usize| -> usize {
+            instrs[..=idx].iter().map(|i| i.encode().len()).sum()
+        };
+        let mut labels = vec![
+            Some(offset_after(&instrs, 1)),
+            Some(offset_after(&instrs, 3)),
+        ];
+        let mut fixups = vec![(5, 1)];
+
+        remove_untargeted_fallthroughs(&mut instrs, &mut fixups, &mut [], &mut [], &mut labels);
+
+        assert_eq!(instrs.len(), 7);
+        assert!(!matches!(instrs[1], Instruction::Fallthrough));
+        assert!(matches!(instrs[2], Instruction::Fallthrough));
+        assert!(matches!(instrs[5], Instruction::Fallthrough));
+        assert_eq!(fixups, vec![(4, 1)]);
+        assert_eq!(labels[1], Some(offset_after(&instrs, 2)));
+    }
+
+    #[test]
+    fn code_behind_a_trap_is_removed() {
+        let mut instrs = vec![
+            Instruction::BranchEqImm {
+                reg: 2,
+                value: 0,
+                offset: 0,
+            },
+            Instruction::LoadImmJump {
+                reg: 0,
+                value: 2,
+                offset: 0,
+            },
+            Instruction::Trap, // call's return point
+            Instruction::LoadImm { reg: 7, value: 1 },
+            Instruction::Jump { offset: 0 },
+            Instruction::Fallthrough, // label 0: branched to
+            Instruction::LoadImm { reg: 7, value: 2 },
+            Instruction::Fallthrough, // label 1: only the dead jump targets it
+            Instruction::JumpInd { reg: 0, offset: 0 },
+        ];
+        let mut labels = vec![
+            Some(instruction_offsets(&instrs)[5]),
+            Some(instruction_offsets(&instrs)[7]),
+        ];
+        let mut fixups = vec![(0, 0), (4, 1)];
+        let mut call_fixups = vec![LlvmCallFixup {
+            return_addr_instr: 1,
+            jump_instr: 1,
+            target_func: 0,
+        }];
+
+        eliminate_dead_blocks(
+            &mut instrs,
+            &mut fixups,
+            &mut call_fixups,
+            &mut Vec::new(),
+            &mut labels,
+        );
+
+        assert_eq!(instrs.len(), 7);
+        assert!(matches!(instrs[2], Instruction::Trap));
+        assert!(matches!(instrs[3], Instruction::Fallthrough));
+        assert_eq!(fixups, vec![(0, 0)]);
+        assert_eq!(call_fixups.len(), 1);
+        assert_eq!(labels[0], Some(instruction_offsets(&instrs)[3]));
+        assert_eq!(labels[1], Some(instruction_offsets(&instrs)[5]));
+    }
+
+    #[test]
+    fn dead_calls_lose_their_fixups() {
+        let mut instrs = vec![
+            Instruction::Trap,
+            Instruction::LoadImmJump {
+                reg: 0,
+                value: 2,
+                offset: 0,
+            },
+            Instruction::LoadImmJumpInd {
+                base: 8,
+                dst: 0,
+                value: 4,
+                offset: 0,
+            },
+            Instruction::JumpInd { reg: 0, offset: 0 },
+        ];
+        let mut call_fixups = vec![LlvmCallFixup {
+            return_addr_instr: 1,
+            jump_instr: 1,
+            target_func: 0,
+        }];
+        let mut indirect_call_fixups = vec![LlvmIndirectCallFixup {
+            return_addr_instr: 2,
+            jump_ind_instr: 2,
+        }];
+
+        eliminate_dead_blocks(
+            &mut instrs,
+            &mut Vec::new(),
+            &mut call_fixups,
+            &mut indirect_call_fixups,
+            &mut [],
+        );
+
+        assert_eq!(instrs, vec![Instruction::Trap]);
+        assert!(call_fixups.is_empty());
+        assert!(indirect_call_fixups.is_empty());
+    }
+
+    #[test]
+    fn jump_without_fixup_keeps_everything() {
+        let mut instrs = vec![Instruction::Jump { offset: 0 }, Instruction::Trap];
+
+        eliminate_dead_blocks(
+            &mut instrs,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut [],
+        );
+
+        assert_eq!(instrs.len(), 2);
+    }
+
+    fn run_optimize(instrs: &mut Vec<Instruction>, labels: &mut [Option<usize>]) {
+        optimize(instrs, &mut [], &mut [], &mut [], labels);
+    }
+
+    #[test]
+    fn same_operand_sub_and_xor_become_load_zero() {
+        for instr in [
+            Instruction::Sub32 {
+                dst: 2,
+                src1: 7,
+                src2: 7,
+            },
+            Instruction::Sub64 {
+                dst: 2,
+                src1: 7,
+                src2: 7,
+            },
+            Instruction::Xor {
+                dst: 2,
+                src1: 7,
+                src2: 7,
+            },
+        ] {
+            let mut instrs = vec![instr, Instruction::Trap];
+            run_optimize(&mut instrs, &mut []);
+            assert_eq!(
+                instrs,
+                vec![Instruction::LoadImm { reg: 2, value: 0 }, Instruction::Trap]
+            );
+        }
+    }
+
+    #[test]
+    fn same_operand_and_becomes_move() {
+        let mut instrs = vec![
+            Instruction::And {
+                dst: 2,
+                src1: 7,
+                src2: 7,
+            },
+            Instruction::Trap,
+        ];
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(
+            instrs,
+            vec![Instruction::MoveReg { dst: 2, src: 7 }, Instruction::Trap]
+        );
+
+        // In place, `x & x` is a no-op and disappears entirely.
+        let mut instrs = vec![
+            Instruction::And {
+                dst: 7,
+                src1: 7,
+                src2: 7,
+            },
+            Instruction::Trap,
+        ];
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(instrs, vec![Instruction::Trap]);
+    }
+
+    #[test]
+    fn multiply_by_zero_becomes_load_zero() {
+        for instr in [
+            Instruction::MulImm32 {
+                dst: 2,
+                src: 7,
+                value: 0,
+            },
+            Instruction::MulImm64 {
+                dst: 2,
+                src: 7,
+                value: 0,
+            },
+        ] {
+            let mut instrs = vec![instr, Instruction::Trap];
+            run_optimize(&mut instrs, &mut []);
+            assert_eq!(
+                instrs,
+                vec![Instruction::LoadImm { reg: 2, value: 0 }, Instruction::Trap]
+            );
+        }
+    }
+
+    /// `dst = x op c` for every immediate op where `c` is neutral.
+    fn neutral_immediates(dst: u8, src: u8) -> [Instruction; 7] {
+        [
+            Instruction::MulImm64 { dst, src, value: 1 },
+            Instruction::OrImm { dst, src, value: 0 },
+            Instruction::XorImm { dst, src, value: 0 },
+            Instruction::AndImm {
+                dst,
+                src,
+                value: -1,
+            },
+            Instruction::ShloLImm64 { dst, src, value: 0 },
+            Instruction::ShloRImm64 { dst, src, value: 0 },
+            Instruction::SharRImm64 { dst, src, value: 0 },
+        ]
+    }
+
+    #[test]
+    fn neutral_immediate_becomes_move() {
+        for instr in neutral_immediates(2, 7) {
+            let mut instrs = vec![instr.clone(), Instruction::Trap];
+            run_optimize(&mut instrs, &mut []);
+            assert_eq!(
+                instrs,
+                vec![Instruction::MoveReg { dst: 2, src: 7 }, Instruction::Trap],
+                "{instr:?}"
+            );
+        }
+
+        // In place, the identity disappears entirely.
+        for instr in neutral_immediates(7, 7) {
+            let mut instrs = vec![instr.clone(), Instruction::Trap];
+            run_optimize(&mut instrs, &mut []);
+            assert_eq!(instrs, vec![Instruction::Trap], "{instr:?}");
+        }
+    }
+
+    #[test]
+    fn sign_extending_32_bit_identities_are_kept() {
+        // `x * 1` and `x << 0` in 32 bits sign-extend the low half of `x`.
+        let original = vec![
+            Instruction::MulImm32 {
+                dst: 2,
+                src: 7,
+                value: 1,
+            },
+            Instruction::ShloLImm32 {
+                dst: 3,
+                src: 7,
+                value: 0,
+            },
+            Instruction::Trap,
+        ];
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(instrs, original);
+    }
+
+    #[test]
+    fn identity_rewrite_remaps_labels() {
+        // MulImm64 by 1 (3 bytes) shrinks to MoveReg (2 bytes); a label on
+        // the following instruction moves from byte 3 to byte 2.
+        let mut instrs = vec![
+            Instruction::MulImm64 {
+                dst: 2,
+                src: 7,
+                value: 1,
+            },
+            Instruction::Trap,
+        ];
+        assert_eq!(instrs[0].encode().len(), 3);
+        let mut labels = vec![Some(3usize)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(instrs[0], Instruction::MoveReg { dst: 2, src: 7 });
+        assert_eq!(labels[0], Some(2));
+    }
+
+    /// `LoadImm r2, v; store r2; r2 = mem[sp]` — the reload keeps `r2` dead
+    /// after the store without being removed as dead code itself.
+    fn load_imm_then_store(store: Instruction) -> Vec<Instruction> {
+        vec![
+            Instruction::LoadImm { reg: 2, value: -5 },
+            store,
+            Instruction::LoadIndU64 {
+                dst: 2,
+                base: 1,
+                offset: 0,
+            },
+            Instruction::Trap,
+        ]
+    }
+
+    #[test]
+    fn load_imm_store_fuses_into_store_imm() {
+        let (base, src, offset, value) = (1, 2, 24, -5);
+        for (store, fused) in [
+            (
+                Instruction::StoreIndU8 { base, src, offset },
+                Instruction::StoreImmIndU8 {
+                    base,
+                    offset,
+                    value,
+                },
+            ),
+            (
+                Instruction::StoreIndU16 { base, src, offset },
+                Instruction::StoreImmIndU16 {
+                    base,
+                    offset,
+                    value,
+                },
+            ),
+            (
+                Instruction::StoreIndU32 { base, src, offset },
+                Instruction::StoreImmIndU32 {
+                    base,
+                    offset,
+                    value,
+                },
+            ),
+            (
+                Instruction::StoreIndU64 { base, src, offset },
+                Instruction::StoreImmIndU64 {
+                    base,
+                    offset,
+                    value,
+                },
+            ),
+        ] {
+            let mut instrs = load_imm_then_store(store);
+            run_optimize(&mut instrs, &mut []);
+            assert_eq!(instrs[0], fused);
+            assert_eq!(instrs.len(), 3, "{instrs:?}");
+        }
+    }
+
+    #[test]
+    fn load_imm_store_kept_while_register_is_live() {
+        // The stored register is read again afterwards.
+        let original = vec![
+            Instruction::LoadImm { reg: 2, value: 7 },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 2,
+                offset: 8,
+            },
+            Instruction::StoreIndU32 {
+                base: 3,
+                src: 2,
+                offset: 0,
+            },
+            Instruction::Trap,
+        ];
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(instrs, original);
+
+        // The loaded register is also the store's base.
+        let original = load_imm_then_store(Instruction::StoreIndU64 {
+            base: 2,
+            src: 2,
+            offset: 8,
+        });
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(instrs, original);
+    }
+
+    #[test]
+    fn load_imm_store_kept_when_cmov_follows() {
+        // r2 still holds 7 after the `CmovIz` whenever r4 is non-zero.
+        let original = vec![
+            Instruction::LoadImm { reg: 2, value: 7 },
+            Instruction::StoreIndU32 {
+                base: 1,
+                src: 2,
+                offset: 0,
+            },
+            Instruction::CmovIz {
+                dst: 2,
+                src: 3,
+                cond: 4,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 2,
+                offset: 8,
+            },
+            Instruction::Trap,
+        ];
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(instrs, original);
+    }
+
+    #[test]
+    fn load_imm_store_fusion_keeps_labels_and_fixups() {
+        // A label on the `LoadImm` moves to the fused store; the jump and the
+        // label after it shift back by the bytes saved.
+        let mut instrs = vec![
+            Instruction::LoadImm {
+                reg: 2,
+                value: 1000,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 2,
+                offset: 8,
+            },
+            Instruction::LoadIndU64 {
+                dst: 2,
+                base: 1,
+                offset: 0,
+            },
+            Instruction::Jump { offset: 0 },
+            Instruction::Trap,
+        ];
+        let trap_offset: usize = instrs[..4].iter().map(Instruction::encoded_len).sum();
+        let mut fixups = vec![(3, 1)];
+        let mut labels = vec![Some(0), Some(trap_offset)];
+        optimize(&mut instrs, &mut fixups, &mut [], &mut [], &mut labels);
 
-        if remove {
-            keep[i] = false;
-        } else {
-            // Mark sources needed
-            for reg in instr.src_regs().into_iter().flatten() {
-                needed_regs[reg as usize] = true;
+        assert_eq!(
+            instrs[0],
+            Instruction::StoreImmIndU64 {
+                base: 1,
+                offset: 8,
+                value: 1000,
             }
+        );
+        assert!(matches!(instrs[2], Instruction::Jump { .. }));
+        assert_eq!(fixups, vec![(2, 1)]);
+        let new_trap_offset: usize = instrs[..3].iter().map(Instruction::encoded_len).sum();
+        assert_eq!(labels, vec![Some(0), Some(new_trap_offset)]);
+        assert!(new_trap_offset < trap_offset);
+    }
+
+    #[test]
+    fn return_address_load_and_jump_fuse_into_load_imm_jump() {
+        for load in [
+            Instruction::LoadImm { reg: 0, value: 6 },
+            Instruction::LoadImm64 { reg: 0, value: 6 },
+        ] {
+            let mut instrs = vec![load, Instruction::Jump { offset: 0 }, Instruction::Trap];
+            let return_offset: usize = instrs[..2].iter().map(Instruction::encoded_len).sum();
+            let mut fixups = vec![(1, 2)];
+            let mut labels = vec![None, None, Some(return_offset)];
+            optimize(&mut instrs, &mut fixups, &mut [], &mut [], &mut labels);
+
+            let fused = Instruction::LoadImmJump {
+                reg: 0,
+                value: 6,
+                offset: 0,
+            };
+            assert_eq!(instrs, vec![fused.clone(), Instruction::Trap]);
+            assert_eq!(fixups, vec![(0, 2)]);
+            assert_eq!(labels, vec![None, None, Some(fused.encoded_len())]);
         }
     }
 
-    compact_instructions(
-        instructions,
-        &keep,
-        fixups,
-        call_fixups,
-        indirect_call_fixups,
-        labels,
-    );
-}
+    #[test]
+    fn load_and_jump_stay_apart_unless_a_return_address() {
+        let jump_after = |load| vec![load, Instruction::Jump { offset: 0 }, Instruction::Trap];
+        for load in [
+            // Not the return-address register.
+            Instruction::LoadImm { reg: 2, value: 6 },
+            // Not representable as a sign-extended 32-bit immediate.
+            Instruction::LoadImm64 {
+                reg: 0,
+                value: 1 << 40,
+            },
+        ] {
+            let original = jump_after(load);
+            let mut instrs = original.clone();
+            let return_offset: usize = instrs[..2].iter().map(Instruction::encoded_len).sum();
+            optimize(
+                &mut instrs,
+                &mut [(1, 0)],
+                &mut [],
+                &mut [],
+                &mut [Some(return_offset)],
+            );
+            assert_eq!(instrs, original);
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // A label on the jump: other code jumps there without setting r0.
+        let original = jump_after(Instruction::LoadImm { reg: 0, value: 6 });
+        let mut instrs = original.clone();
+        let jump_offset = instrs[0].encoded_len();
+        let return_offset = jump_offset + instrs[1].encoded_len();
+        optimize(
+            &mut instrs,
+            &mut [(1, 1)],
+            &mut [],
+            &mut [],
+            &mut [Some(return_offset), Some(jump_offset)],
+        );
+        assert_eq!(instrs, original);
 
-    const SP: u8 = crate::abi::STACK_PTR_REG;
+        // A pending call fixup still patches the pair separately.
+        let original = jump_after(Instruction::LoadImm { reg: 0, value: 0 });
+        let mut instrs = original.clone();
+        optimize(
+            &mut instrs,
+            &mut [],
+            &mut [LlvmCallFixup {
+                return_addr_instr: 0,
+                jump_instr: 1,
+                target_func: 0,
+            }],
+            &mut [],
+            &mut [],
+        );
+        assert_eq!(instrs, original);
+    }
 
-    #[test]
-    fn collapse_consecutive_fallthroughs() {
+    macro_rules! three_reg {
+        ($op:ident) => {
+            Instruction::$op {
+                dst: 9,
+                src1: 7,
+                src2: 8,
+            }
+        };
+    }
+
+    macro_rules! reg_imm {
+        ($op:ident, $value:expr) => {
+            Instruction::$op {
+                dst: 9,
+                src: 7,
+                value: $value,
+            }
+        };
+    }
+
+    /// Load `a` into r7 and `b` into r8, run `op` (r9 = r7 op r8, or r7 op
+    /// imm) and store r9; returns the `LoadImm` value `op` folded to, if any.
+    fn fold(a: i64, b: i64, op: Instruction) -> Option<i32> {
+        let load = |reg, v: i64| {
+            i32::try_from(v).map_or(
+                Instruction::LoadImm64 {
+                    reg,
+                    value: v.cast_unsigned(),
+                },
+                |value| Instruction::LoadImm { reg, value },
+            )
+        };
         let mut instrs = vec![
-            Instruction::Fallthrough,
-            Instruction::Fallthrough,
-            Instruction::Fallthrough,
-            Instruction::LoadImm { reg: 0, value: 42 },
+            load(7, a),
+            load(8, b),
+            op,
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 9,
+                offset: 0,
+            },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![];
-        let mut indirect_call_fixups = vec![];
-        let mut labels = vec![];
+        run_optimize(&mut instrs, &mut []);
+        instrs.iter().find_map(|instr| match *instr {
+            Instruction::LoadImm { reg: 9, value } => Some(value),
+            _ => None,
+        })
+    }
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+    #[test]
+    fn constant_arithmetic_folds_with_wrapping() {
+        let (max, min) = (i64::from(i32::MAX), i64::from(i32::MIN));
+        assert_eq!(fold(max, 1, three_reg!(Add32)), Some(i32::MIN));
+        assert_eq!(fold(min, 1, three_reg!(Sub32)), Some(i32::MAX));
+        assert_eq!(fold(0x1_0000, 0x1_0000, three_reg!(Mul32)), Some(0));
+        assert_eq!(fold(max, 0, reg_imm!(AddImm32, 1)), Some(i32::MIN));
+        assert_eq!(fold(-6, 7, three_reg!(Mul64)), Some(-42));
+        assert_eq!(fold(0b1100, 0b1010, three_reg!(And)), Some(0b1000));
+        assert_eq!(fold(0b1100, 0b1010, three_reg!(Or)), Some(0b1110));
+        assert_eq!(fold(0b1100, 0b1010, three_reg!(Xor)), Some(0b0110));
+        assert_eq!(fold(-1, 0, reg_imm!(XorImm, 0xFF)), Some(-256));
+        // 64-bit sources from `LoadImm64` fold to a small result.
+        assert_eq!(fold(1 << 32, 5 - (1 << 32), three_reg!(Add64)), Some(5));
+        // `i32::MAX + 1` without 32-bit wrapping does not fit `LoadImm`.
+        assert_eq!(fold(max, 1, three_reg!(Add64)), None);
+    }
+
+    #[test]
+    fn constant_shift_amounts_are_masked() {
+        assert_eq!(fold(1, 33, three_reg!(ShloL32)), Some(2));
+        assert_eq!(fold(-1, 36, three_reg!(ShloR32)), Some(0x0FFF_FFFF));
+        assert_eq!(fold(i64::from(i32::MIN), 63, three_reg!(SharR32)), Some(-1));
+        assert_eq!(fold(-8, 65, three_reg!(SharR64)), Some(-4));
+        assert_eq!(
+            fold(0x4000_0000, 0, reg_imm!(ShloLImm32, 33)),
+            Some(i32::MIN)
         );
+        assert_eq!(fold(1, 0, reg_imm!(ShloLImm64, 66)), Some(4));
+        assert_eq!(fold(-1, 0, reg_imm!(ShloRImm64, 64)), Some(-1));
+    }
 
-        assert_eq!(instrs.len(), 2);
-        assert!(matches!(instrs[0], Instruction::Fallthrough));
-        assert!(matches!(
-            instrs[1],
-            Instruction::LoadImm { reg: 0, value: 42 }
-        ));
+    #[test]
+    fn division_by_zero_and_signed_overflow_are_not_folded() {
+        assert_eq!(fold(-7, 2, three_reg!(DivS32)), Some(-3));
+        assert_eq!(fold(-7, 2, three_reg!(RemS32)), Some(-1));
+        assert_eq!(fold(7, 2, three_reg!(DivU64)), Some(3));
+
+        for op in [
+            three_reg!(DivU32),
+            three_reg!(DivS32),
+            three_reg!(RemU32),
+            three_reg!(RemS32),
+            three_reg!(DivU64),
+            three_reg!(DivS64),
+            three_reg!(RemU64),
+            three_reg!(RemS64),
+        ] {
+            assert_eq!(fold(5, 0, op.clone()), None, "{op:?} by zero");
+        }
+        for op in [three_reg!(DivS32), three_reg!(RemS32)] {
+            assert_eq!(fold(i64::from(i32::MIN), -1, op.clone()), None, "{op:?}");
+        }
+        for op in [three_reg!(DivS64), three_reg!(RemS64)] {
+            assert_eq!(fold(i64::MIN, -1, op.clone()), None, "{op:?}");
+        }
     }
 
     #[test]
-    fn remove_fallthrough_before_jump() {
+    fn folded_operands_are_dropped_once_dead() {
+        // The label on the `Trap` keeps dead-code elimination from running,
+        // so only the fold removes the loads: r7 is overwritten by the fold
+        // itself, r8 by the next instruction.
         let mut instrs = vec![
-            Instruction::LoadImm { reg: 0, value: 1 },
-            Instruction::Fallthrough,
-            Instruction::Jump { offset: 0 },
+            Instruction::LoadImm { reg: 7, value: 5 },
+            Instruction::LoadImm { reg: 8, value: 7 },
+            Instruction::Mul64 {
+                dst: 7,
+                src1: 7,
+                src2: 8,
+            },
+            Instruction::LoadImm { reg: 8, value: 0 },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 7,
+                offset: 0,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 8,
+                offset: 8,
+            },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![(2, 0)]; // jump at index 2
-        let mut call_fixups = vec![];
-        let mut indirect_call_fixups = vec![];
-        let mut labels = vec![];
+        let trap_offset: usize = instrs[..6].iter().map(Instruction::encoded_len).sum();
+        let mut labels = vec![Some(trap_offset)];
+        run_optimize(&mut instrs, &mut labels);
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+        assert_eq!(
+            instrs[..2],
+            [
+                Instruction::LoadImm { reg: 7, value: 35 },
+                Instruction::LoadImm { reg: 8, value: 0 },
+            ]
         );
+        assert_eq!(instrs.len(), 5, "{instrs:?}");
+        let new_trap_offset: usize = instrs[..4].iter().map(Instruction::encoded_len).sum();
+        assert_eq!(labels, vec![Some(new_trap_offset)]);
+    }
 
-        assert_eq!(instrs.len(), 2);
-        assert!(matches!(instrs[0], Instruction::LoadImm { .. }));
-        assert!(matches!(instrs[1], Instruction::Jump { .. }));
-        assert_eq!(fixups[0].0, 1); // remapped from 2 to 1
+    #[test]
+    fn folded_operand_kept_when_cmov_follows() {
+        // r7 keeps 5 whenever r9 is zero, so its load must stay.
+        let mut instrs = vec![
+            Instruction::LoadImm { reg: 7, value: 5 },
+            Instruction::AddImm64 {
+                dst: 8,
+                src: 7,
+                value: 1,
+            },
+            Instruction::CmovNzImm {
+                dst: 7,
+                cond: 9,
+                value: 7,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 7,
+                offset: 0,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 8,
+                offset: 8,
+            },
+            Instruction::Trap,
+        ];
+        let trap_offset: usize = instrs[..5].iter().map(Instruction::encoded_len).sum();
+        let mut labels = vec![Some(trap_offset)];
+        run_optimize(&mut instrs, &mut labels);
+
+        assert_eq!(
+            instrs[..3],
+            [
+                Instruction::LoadImm { reg: 7, value: 5 },
+                Instruction::LoadImm { reg: 8, value: 6 },
+                Instruction::CmovNzImm {
+                    dst: 7,
+                    cond: 9,
+                    value: 7,
+                },
+            ]
+        );
     }
 
     #[test]
-    fn remove_fallthrough_before_load_imm_jump() {
+    fn constants_are_forgotten_at_labels() {
+        // A branch could reach the add with other values in r7/r8.
+        let original = vec![
+            Instruction::LoadImm { reg: 7, value: 5 },
+            Instruction::LoadImm { reg: 8, value: 7 },
+            three_reg!(Add64),
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 9,
+                offset: 0,
+            },
+            Instruction::Trap,
+        ];
+        let add_offset: usize = original[..2].iter().map(Instruction::encoded_len).sum();
+        let mut instrs = original.clone();
+        let mut labels = vec![Some(add_offset)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(instrs, original);
+        assert_eq!(labels, vec![Some(add_offset)]);
+    }
+
+    /// Zero r2 and xor it into r3, then zero r5 and feed it to `second`
+    /// (which must overwrite r5). Both results are stored; the label on the
+    /// final `Trap` keeps dead-code elimination out of the way.
+    fn two_zero_uses(second: Instruction) -> Vec<Instruction> {
         let mut instrs = vec![
-            Instruction::Fallthrough,
-            Instruction::LoadImmJump {
-                reg: 0,
-                value: 2,
+            Instruction::LoadImm { reg: 2, value: 0 },
+            Instruction::Xor {
+                dst: 3,
+                src1: 4,
+                src2: 2,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 3,
                 offset: 0,
             },
-            Instruction::Fallthrough,
+            Instruction::LoadImm { reg: 5, value: 0 },
+            second,
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 5,
+                offset: 8,
+            },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![LlvmCallFixup {
-            return_addr_instr: 1,
-            jump_instr: 1,
-            target_func: 0,
-        }];
-        let mut indirect_call_fixups = vec![];
-        let mut labels = vec![];
+        let trap_offset: usize = instrs[..6].iter().map(Instruction::encoded_len).sum();
+        let mut labels = vec![Some(trap_offset)];
+        run_optimize(&mut instrs, &mut labels);
+        instrs
+    }
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+    fn zero_loads(instrs: &[Instruction]) -> usize {
+        instrs
+            .iter()
+            .filter(|instr| matches!(instr, Instruction::LoadImm { value: 0, .. }))
+            .count()
+    }
+
+    #[test]
+    fn second_zero_reuses_known_zero_register() {
+        let instrs = two_zero_uses(Instruction::Or {
+            dst: 5,
+            src1: 8,
+            src2: 5,
+        });
+        assert_eq!(zero_loads(&instrs), 1, "{instrs:?}");
+        assert!(
+            instrs.contains(&Instruction::Or {
+                dst: 5,
+                src1: 8,
+                src2: 2,
+            }),
+            "{instrs:?}"
         );
+    }
 
-        // First Fallthrough removed (before LoadImmJump), second kept
-        assert_eq!(instrs.len(), 2);
-        assert!(matches!(instrs[0], Instruction::LoadImmJump { .. }));
-        assert!(matches!(instrs[1], Instruction::Fallthrough));
-        // Call fixup remapped from index 1 to 0
-        assert_eq!(call_fixups[0].return_addr_instr, 0);
-        assert_eq!(call_fixups[0].jump_instr, 0);
+    #[test]
+    fn zero_is_not_reused_by_non_commutative_op() {
+        let instrs = two_zero_uses(Instruction::Sub64 {
+            dst: 5,
+            src1: 8,
+            src2: 5,
+        });
+        assert_eq!(zero_loads(&instrs), 2, "{instrs:?}");
     }
 
     #[test]
-    fn remove_fallthrough_before_load_imm_jump_ind() {
+    fn zero_is_not_reused_once_overwritten() {
+        // r2 = r4 ^ 0 clobbers the first zero before the second is needed.
         let mut instrs = vec![
-            Instruction::Fallthrough,
-            Instruction::LoadImmJumpInd {
-                base: 8,
-                dst: 0,
-                value: 2,
+            Instruction::LoadImm { reg: 2, value: 0 },
+            Instruction::Xor {
+                dst: 2,
+                src1: 4,
+                src2: 2,
+            },
+            Instruction::LoadImm { reg: 5, value: 0 },
+            Instruction::Or {
+                dst: 5,
+                src1: 8,
+                src2: 5,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 2,
+                offset: 0,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 5,
+                offset: 8,
+            },
+            Instruction::Trap,
+        ];
+        let trap_offset: usize = instrs[..6].iter().map(Instruction::encoded_len).sum();
+        let mut labels = vec![Some(trap_offset)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(zero_loads(&instrs), 2, "{instrs:?}");
+    }
+
+    #[test]
+    fn zero_is_not_reused_when_cmov_keeps_it() {
+        // r5 is still zero after the `CmovNz` whenever r9 is zero, and the
+        // last store reads it.
+        let mut instrs = vec![
+            Instruction::LoadImm { reg: 2, value: 0 },
+            Instruction::Xor {
+                dst: 3,
+                src1: 4,
+                src2: 2,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 3,
+                offset: 0,
+            },
+            Instruction::LoadImm { reg: 5, value: 0 },
+            Instruction::Or {
+                dst: 6,
+                src1: 8,
+                src2: 5,
+            },
+            Instruction::CmovNz {
+                dst: 5,
+                src: 8,
+                cond: 9,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 6,
+                offset: 8,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 5,
+                offset: 16,
+            },
+            Instruction::Trap,
+        ];
+        let trap_offset: usize = instrs[..8].iter().map(Instruction::encoded_len).sum();
+        let mut labels = vec![Some(trap_offset)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(zero_loads(&instrs), 2, "{instrs:?}");
+    }
+
+    #[test]
+    fn reloading_a_zero_register_is_dropped() {
+        let mut instrs = vec![
+            Instruction::LoadImm { reg: 2, value: 0 },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 2,
+                offset: 0,
+            },
+            Instruction::LoadImm { reg: 2, value: 0 },
+            Instruction::Xor {
+                dst: 3,
+                src1: 4,
+                src2: 2,
+            },
+            Instruction::StoreIndU64 {
+                base: 1,
+                src: 3,
+                offset: 8,
+            },
+            Instruction::Trap,
+        ];
+        let trap_offset: usize = instrs[..5].iter().map(Instruction::encoded_len).sum();
+        let mut labels = vec![Some(trap_offset)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(zero_loads(&instrs), 1, "{instrs:?}");
+        let new_trap_offset: usize = instrs[..instrs.len() - 1]
+            .iter()
+            .map(Instruction::encoded_len)
+            .sum();
+        assert_eq!(labels, vec![Some(new_trap_offset)]);
+    }
+
+    type Stream = (Vec<Instruction>, Vec<(usize, usize)>, Vec<Option<usize>>);
+
+    /// `if r2 == 0 goto then; goto else; then: ...; else: ...`, with labels
+    /// 0 = then and 1 = else. Returns the stream, fixups and labels.
+    fn branch_over_jump(jump_is_target: bool) -> Stream {
+        let instrs = vec![
+            Instruction::BranchEqImm {
+                reg: 2,
+                value: 0,
                 offset: 0,
             },
-            Instruction::Fallthrough,
+            Instruction::Jump { offset: 0 },
+            Instruction::LoadImm { reg: 3, value: 1 },
+            Instruction::Trap,
+            Instruction::LoadImm { reg: 3, value: 2 },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![];
-        let mut indirect_call_fixups = vec![LlvmIndirectCallFixup {
-            return_addr_instr: 1,
-            jump_ind_instr: 1,
-        }];
-        let mut labels = vec![];
+        let offsets: Vec<usize> = instrs
+            .iter()
+            .scan(0, |off, i| {
+                let here = *off;
+                *off += i.encode().len();
+                Some(here)
+            })
+            .collect();
+        let mut labels = vec![Some(offsets[2]), Some(offsets[4])];
+        if jump_is_target {
+            labels.push(Some(offsets[1]));
+        }
+        (instrs, vec![(0, 0), (1, 1)], labels)
+    }
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+    #[test]
+    fn branch_over_jump_is_inverted() {
+        let (mut instrs, mut fixups, mut labels) = branch_over_jump(false);
+        optimize(&mut instrs, &mut fixups, &mut [], &mut [], &mut labels);
+        assert_eq!(
+            instrs,
+            vec![
+                Instruction::BranchNeImm {
+                    reg: 2,
+                    value: 0,
+                    offset: 0,
+                },
+                Instruction::LoadImm { reg: 3, value: 1 },
+                Instruction::Trap,
+                Instruction::LoadImm { reg: 3, value: 2 },
+                Instruction::Trap,
+            ]
         );
-
-        // First Fallthrough removed (before LoadImmJumpInd), second kept.
-        assert_eq!(instrs.len(), 2);
-        assert!(matches!(instrs[0], Instruction::LoadImmJumpInd { .. }));
-        assert!(matches!(instrs[1], Instruction::Fallthrough));
-        // Indirect call fixup remapped from index 1 to 0.
-        assert_eq!(indirect_call_fixups[0].return_addr_instr, 0);
-        assert_eq!(indirect_call_fixups[0].jump_ind_instr, 0);
+        // Both fixups now resolve the inverted branch to the old jump target.
+        assert_eq!(fixups, vec![(0, 1), (0, 1)]);
+        let else_offset: usize = instrs[..3].iter().map(|i| i.encode().len()).sum();
+        assert_eq!(labels[1], Some(else_offset));
     }
 
     #[test]
-    fn remove_fallthrough_before_trap() {
-        let mut instrs = vec![Instruction::Fallthrough, Instruction::Trap];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![];
-        let mut indirect_call_fixups = vec![];
-        let mut labels = vec![];
+    fn branch_over_jump_target_is_kept() {
+        let (mut instrs, mut fixups, mut labels) = branch_over_jump(true);
+        let before = instrs.clone();
+        optimize(&mut instrs, &mut fixups, &mut [], &mut [], &mut labels);
+        assert_eq!(instrs, before);
+        assert_eq!(fixups, vec![(0, 0), (1, 1)]);
+    }
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+    #[test]
+    fn double_reverse_bytes_in_place_becomes_move() {
+        let mut instrs = vec![
+            Instruction::ReverseBytes { dst: 2, src: 7 },
+            Instruction::ReverseBytes { dst: 2, src: 2 },
+            Instruction::Trap,
+        ];
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(
+            instrs,
+            vec![Instruction::MoveReg { dst: 2, src: 7 }, Instruction::Trap]
         );
+    }
 
-        assert_eq!(instrs.len(), 1);
-        assert!(matches!(instrs[0], Instruction::Trap));
+    #[test]
+    fn double_reverse_bytes_drops_dead_intermediate() {
+        // r2 is overwritten before any read, so only the move survives.
+        let mut instrs = vec![
+            Instruction::ReverseBytes { dst: 2, src: 7 },
+            Instruction::ReverseBytes { dst: 3, src: 2 },
+            Instruction::LoadImm { reg: 2, value: 1 },
+            Instruction::Trap,
+        ];
+        // A label keeps dead-code elimination out of the way.
+        let mut labels = [Some(0)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(
+            instrs,
+            vec![
+                Instruction::MoveReg { dst: 3, src: 7 },
+                Instruction::LoadImm { reg: 2, value: 1 },
+                Instruction::Trap,
+            ]
+        );
+        assert_eq!(labels[0], Some(0));
     }
 
     #[test]
-    fn remaps_call_fixups() {
+    fn double_reverse_bytes_keeps_live_intermediate() {
         let mut instrs = vec![
-            Instruction::Fallthrough,
-            Instruction::Fallthrough,
-            Instruction::LoadImm { reg: 0, value: 0 }, // return_addr_instr
-            Instruction::Jump { offset: 0 },           // jump_instr
+            Instruction::ReverseBytes { dst: 2, src: 7 },
+            Instruction::ReverseBytes { dst: 3, src: 2 },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![LlvmCallFixup {
-            return_addr_instr: 2,
-            jump_instr: 3,
-            target_func: 0,
-        }];
-        let mut indirect_call_fixups = vec![];
-        let mut labels = vec![];
+        let mut labels = [Some(0)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(
+            instrs,
+            vec![
+                Instruction::ReverseBytes { dst: 2, src: 7 },
+                Instruction::MoveReg { dst: 3, src: 7 },
+                Instruction::Trap,
+            ]
+        );
+    }
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+    #[test]
+    fn double_reverse_bytes_keeps_intermediate_read_by_cmov() {
+        // The `CmovNz` leaves r2 alone when r5 is zero, so r2 is still live.
+        let mut instrs = vec![
+            Instruction::ReverseBytes { dst: 2, src: 7 },
+            Instruction::ReverseBytes { dst: 3, src: 2 },
+            Instruction::CmovNz {
+                dst: 2,
+                src: 4,
+                cond: 5,
+            },
+            Instruction::Trap,
+        ];
+        let mut labels = [Some(0)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(
+            instrs,
+            vec![
+                Instruction::ReverseBytes { dst: 2, src: 7 },
+                Instruction::MoveReg { dst: 3, src: 7 },
+                Instruction::CmovNz {
+                    dst: 2,
+                    src: 4,
+                    cond: 5,
+                },
+                Instruction::Trap,
+            ]
         );
+    }
 
-        // One fallthrough removed (first one before second fallthrough)
-        assert_eq!(instrs.len(), 3);
-        assert_eq!(call_fixups[0].return_addr_instr, 1);
-        assert_eq!(call_fixups[0].jump_instr, 2);
+    #[test]
+    fn double_reverse_bytes_at_branch_target_is_kept() {
+        let mut instrs = vec![
+            Instruction::ReverseBytes { dst: 2, src: 7 },
+            Instruction::ReverseBytes { dst: 2, src: 2 },
+            Instruction::Trap,
+        ];
+        let mut labels = [Some(2)];
+        run_optimize(&mut instrs, &mut labels);
+        assert!(matches!(instrs[1], Instruction::ReverseBytes { .. }));
     }
 
     #[test]
-    fn no_op_when_nothing_to_optimize() {
+    fn distinct_operands_are_left_alone() {
+        let original = vec![
+            Instruction::Sub64 {
+                dst: 2,
+                src1: 7,
+                src2: 8,
+            },
+            Instruction::And {
+                dst: 2,
+                src1: 2,
+                src2: 8,
+            },
+            Instruction::Trap,
+        ];
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut []);
+        assert_eq!(instrs, original);
+    }
+
+    #[test]
+    fn same_operand_rewrite_remaps_labels() {
+        // Xor (3 bytes) shrinks to LoadImm (2 bytes); a label on the
+        // following instruction moves from byte 3 to byte 2.
         let mut instrs = vec![
-            Instruction::LoadImm { reg: 0, value: 1 },
-            Instruction::LoadImm { reg: 1, value: 2 },
-            Instruction::Add64 {
-                dst: 0,
-                src1: 0,
-                src2: 1,
+            Instruction::Xor {
+                dst: 2,
+                src1: 7,
+                src2: 7,
             },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![];
-        let mut indirect_call_fixups = vec![];
-        let mut labels = vec![];
+        let mut labels = vec![Some(3usize)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(instrs[0], Instruction::LoadImm { reg: 2, value: 0 });
+        assert_eq!(labels[0], Some(2));
+    }
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+    #[test]
+    fn add_imm64_zero_becomes_move() {
+        let mut instrs = vec![
+            Instruction::AddImm64 {
+                dst: 3,
+                src: 7,
+                value: 0,
+            },
+            Instruction::Trap,
+        ];
+        let before = instrs[0].encode().len();
+        let mut labels = [Some(0), Some(before)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(
+            instrs,
+            vec![Instruction::MoveReg { dst: 3, src: 7 }, Instruction::Trap]
         );
-
-        assert_eq!(instrs.len(), 3);
+        assert_eq!(instrs[0].encode().len(), before);
+        assert_eq!(labels, [Some(0), Some(before)]);
     }
 
     #[test]
-    fn updates_label_byte_offsets() {
-        // Fallthrough (1 byte), Fallthrough (1 byte), LoadImm (6 bytes)
-        // Label at byte offset 2 (pointing to LoadImm)
+    fn cancelled_add_chain_is_dropped() {
+        // `+8; -8` fuses to `AddImm64 r2, r2, 0`, which is then a self-move.
         let mut instrs = vec![
-            Instruction::Fallthrough,
-            Instruction::Fallthrough,
-            Instruction::LoadImm { reg: 0, value: 42 },
+            Instruction::AddImm64 {
+                dst: 2,
+                src: 2,
+                value: 8,
+            },
+            Instruction::AddImm64 {
+                dst: 2,
+                src: 2,
+                value: -8,
+            },
+            Instruction::Jump { offset: 0 },
+            Instruction::Trap,
         ];
-        let mut fixups = vec![];
-        let mut call_fixups = vec![];
-        let mut indirect_call_fixups = vec![];
-        // Label points to byte offset 2 (start of LoadImm, after two 1-byte Fallthroughs)
-        let mut labels = vec![Some(2usize)];
+        let old_len: usize = instrs.iter().map(|i| i.encode().len()).sum();
+        let trap_at = old_len - 1;
+        let mut fixups = vec![(2, 0)];
+        let mut labels = [Some(trap_at)];
+        optimize(&mut instrs, &mut fixups, &mut [], &mut [], &mut labels);
 
-        optimize(
-            &mut instrs,
-            &mut fixups,
-            &mut call_fixups,
-            &mut indirect_call_fixups,
-            &mut labels,
+        assert_eq!(
+            instrs,
+            vec![Instruction::Jump { offset: 0 }, Instruction::Trap]
         );
+        let new_len: usize = instrs.iter().map(|i| i.encode().len()).sum();
+        assert!(new_len < old_len);
+        assert_eq!(fixups, vec![(0, 0)]);
+        assert_eq!(labels, [Some(new_len - 1)]);
+    }
 
-        // First Fallthrough removed, so: Fallthrough (1 byte), LoadImm (6 bytes)
+    #[test]
+    fn add_imm32_zero_is_not_a_move() {
+        // Sign-extends the low 32 bits, so it must survive.
+        let original = vec![
+            Instruction::AddImm32 {
+                dst: 3,
+                src: 7,
+                value: 0,
+            },
+            Instruction::Trap,
+        ];
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut [Some(0)]);
+        assert_eq!(instrs, original);
+    }
+
+    fn store_load_pair(load_dst: u8, load_offset: i32) -> Vec<Instruction> {
+        vec![
+            Instruction::StoreIndU64 {
+                base: 5,
+                src: 7,
+                offset: 16,
+            },
+            Instruction::LoadIndU64 {
+                dst: load_dst,
+                base: 5,
+                offset: load_offset,
+            },
+            Instruction::Trap,
+        ]
+    }
+
+    #[test]
+    fn store_then_load_same_slot_becomes_move() {
+        // The 3-byte load shrinks to a 2-byte move; a label on the Trap
+        // moves from byte 6 to byte 5.
+        let mut instrs = store_load_pair(3, 16);
+        let mut labels = vec![Some(6usize)];
+        run_optimize(&mut instrs, &mut labels);
+        assert_eq!(instrs[1], Instruction::MoveReg { dst: 3, src: 7 });
+        assert_eq!(labels[0], Some(5));
+    }
+
+    #[test]
+    fn store_then_load_into_source_register_is_dropped() {
+        let mut instrs = store_load_pair(7, 16);
+        let mut labels = vec![Some(0usize)];
+        run_optimize(&mut instrs, &mut labels);
         assert_eq!(instrs.len(), 2);
-        // Label should now point to byte offset 1 (start of LoadImm after one Fallthrough)
-        assert_eq!(labels[0], Some(1));
+        assert_eq!(instrs[1], Instruction::Trap);
+    }
+
+    #[test]
+    fn store_then_load_of_another_slot_is_kept() {
+        let original = store_load_pair(3, 24);
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut [Some(0)]);
+        assert_eq!(instrs, original);
+    }
+
+    #[test]
+    fn store_then_load_at_branch_target_is_kept() {
+        let original = store_load_pair(3, 16);
+        let mut instrs = original.clone();
+        run_optimize(&mut instrs, &mut [Some(3)]);
+        assert_eq!(instrs, original);
     }
 
     #[test]
@@ -1148,6 +3421,93 @@ mod tests {
         ));
     }
 
+    /// `arr[idx]` with 4-byte elements: `r4 = load32(r1 + (r2 << 2) + 16)`.
+    fn indexed_load(arr: Option<i32>, idx: Option<i32>) -> Vec<Instruction> {
+        let mut instrs = vec![];
+        if let Some(value) = arr {
+            instrs.push(Instruction::LoadImm { reg: 1, value });
+        }
+        if let Some(value) = idx {
+            instrs.push(Instruction::LoadImm { reg: 2, value });
+        }
+        instrs.extend([
+            Instruction::ShloLImm64 {
+                dst: 2,
+                src: 2,
+                value: 2,
+            },
+            Instruction::Add64 {
+                dst: 3,
+                src1: 1,
+                src2: 2,
+            },
+            Instruction::LoadIndU32 {
+                dst: 4,
+                base: 3,
+                offset: 16,
+            },
+        ]);
+        instrs
+    }
+
+    #[test]
+    fn address_fold_absorbs_constant_scaled_index() {
+        let mut instrs = indexed_load(None, Some(5));
+        optimize_address_calculation(&mut instrs, &mut []);
+
+        assert_eq!(
+            instrs[2],
+            Instruction::AddImm64 {
+                dst: 3,
+                src: 1,
+                value: 20
+            }
+        );
+        assert_eq!(
+            instrs[3],
+            Instruction::LoadIndU32 {
+                dst: 4,
+                base: 1,
+                offset: 36
+            }
+        );
+    }
+
+    #[test]
+    fn address_fold_absorbs_constant_array_base() {
+        let mut instrs = indexed_load(Some(0x1000), None);
+        optimize_address_calculation(&mut instrs, &mut []);
+
+        assert_eq!(
+            instrs[3],
+            Instruction::LoadIndU32 {
+                dst: 4,
+                base: 2,
+                offset: 0x1010
+            }
+        );
+    }
+
+    #[test]
+    fn address_fold_leaves_variable_index_and_base() {
+        let mut instrs = indexed_load(None, None);
+        let original = instrs.clone();
+        optimize_address_calculation(&mut instrs, &mut []);
+        assert_eq!(instrs, original);
+    }
+
+    #[test]
+    fn address_fold_forgets_constants_at_labels() {
+        let mut instrs = indexed_load(None, Some(5));
+        // Branch target at the shift: `r2` may arrive with any value.
+        let shift_offset = instrs[0].encode().len();
+        let mut labels = vec![Some(shift_offset)];
+        let original = instrs.clone();
+        optimize_address_calculation(&mut instrs, &mut labels);
+        assert_eq!(instrs, original);
+        assert_eq!(labels, [Some(shift_offset)]);
+    }
+
     // ── Dead store elimination tests ──
 
     #[test]
@@ -1521,7 +3881,7 @@ mod tests {
                 src: 5,
                 value: 0,
             },
-            Instruction::LoadImm { reg: 0, value: 0 },
+            Instruction::LoadImm { reg: 2, value: 0 },
             Instruction::Jump { offset: 0 },
         ];
         let mut fixups = vec![(3, 0)];
@@ -1732,4 +4092,53 @@ mod tests {
 
         assert_eq!(instrs.len(), 2);
     }
+
+    #[test]
+    fn rebase_absolute_accesses_uses_base_register() {
+        let mut instrs = vec![
+            Instruction::LoadU32 {
+                dst: 2,
+                address: 0x30000,
+            },
+            Instruction::StoreU64 {
+                src: 3,
+                address: 0x30008,
+            },
+            Instruction::StoreImmU32 {
+                address: 0x30010,
+                value: 7,
+            },
+            Instruction::LoadIndU32 {
+                dst: 4,
+                base: 5,
+                offset: 0x50000,
+            },
+        ];
+        rebase_absolute_accesses(&mut instrs, 12);
+        assert_eq!(
+            instrs,
+            vec![
+                Instruction::LoadIndU32 {
+                    dst: 2,
+                    base: 12,
+                    offset: 0x30000,
+                },
+                Instruction::StoreIndU64 {
+                    base: 12,
+                    src: 3,
+                    offset: 0x30008,
+                },
+                Instruction::StoreImmIndU32 {
+                    base: 12,
+                    offset: 0x30010,
+                    value: 7,
+                },
+                Instruction::LoadIndU32 {
+                    dst: 4,
+                    base: 5,
+                    offset: 0x50000,
+                },
+            ]
+        );
+    }
 }
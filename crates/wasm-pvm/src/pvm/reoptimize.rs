@@ -0,0 +1,144 @@
+// Re-run the post-emission PVM passes on an already linked program.
+//
+// The compiler runs dead-store elimination and the peephole optimizer per
+// function, before fixups are resolved. To apply them to a finished blob we
+// rebuild that pre-resolution state for the whole program: every code-relative
+// offset becomes a `(instruction, label)` fixup, every jump-table entry a
+// label, and after the passes the fixups are resolved again with the same
+// relaxation the emitter uses.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Instruction, ProgramBlob, peephole};
+use crate::llvm_backend::resolve_label_fixups;
+use crate::{Error, Result, SpiProgram};
+
+/// Re-optimize a compiled program with the peephole and dead-store passes.
+///
+/// Metadata, data sections and memory configuration are carried over
+/// unchanged. Jump-table entries keep their indices, so return addresses and
+/// function-table references in code and `ro_data` stay valid.
+pub fn reoptimize(program: &SpiProgram) -> Result<SpiProgram> {
+    let blob = program.code();
+    let mut instructions = blob.instructions().to_vec();
+
+    let mut offsets = Vec::with_capacity(instructions.len());
+    let mut running = 0usize;
+    for instr in &instructions {
+        offsets.push(running);
+        running += instr.encoded_len();
+    }
+    let offset_to_idx: BTreeMap<usize, usize> = offsets
+        .iter()
+        .enumerate()
+        .map(|(idx, &off)| (off, idx))
+        .collect();
+
+    // One label per distinct target instruction.
+    let mut label_targets: Vec<usize> = Vec::new();
+    let mut label_of: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut label_for = |byte_offset: i64| -> Result<usize> {
+        let target = usize::try_from(byte_offset)
+            .ok()
+            .and_then(|off| offset_to_idx.get(&off).copied())
+            .ok_or_else(|| {
+                Error::Internal(format!(
+                    "jump target {byte_offset} is not an instruction boundary"
+                ))
+            })?;
+        Ok(*label_of.entry(target).or_insert_with(|| {
+            label_targets.push(target);
+            label_targets.len() - 1
+        }))
+    };
+
+    let mut fixups = Vec::new();
+    for (idx, instr) in instructions.iter_mut().enumerate() {
+        if let Some(offset) = jump_offset_mut(instr) {
+            let label = label_for(offsets[idx] as i64 + i64::from(*offset))?;
+            fixups.push((idx, label));
+            // Start relaxation from the smallest encoding, as the emitter does.
+            *offset = 0;
+        }
+    }
+    let jump_table_labels = blob
+        .jump_table()
+        .iter()
+        .map(|&entry| label_for(i64::from(entry)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Labels are byte offsets in the zero-offset layout the passes expect.
+    let mut zeroed_offsets = Vec::with_capacity(instructions.len());
+    running = 0;
+    for instr in &instructions {
+        zeroed_offsets.push(running);
+        running += instr.encoded_len();
+    }
+    let mut labels: Vec<Option<usize>> = label_targets
+        .iter()
+        .map(|&idx| Some(zeroed_offsets[idx]))
+        .collect();
+
+    // The read set spans the whole program, so this removes no more than the
+    // per-function pass would.
+    peephole::eliminate_dead_stores(
+        &mut instructions,
+        &mut fixups,
+        &mut [],
+        &mut [],
+        &mut labels,
+        &BTreeSet::new(),
+    );
+    peephole::optimize(
+        &mut instructions,
+        &mut fixups,
+        &mut [],
+        &mut [],
+        &mut labels,
+    );
+    resolve_label_fixups(&mut instructions, &fixups, &mut labels)?;
+
+    let jump_table = jump_table_labels
+        .iter()
+        .map(|&label| {
+            labels[label]
+                .and_then(|off| u32::try_from(off).ok())
+                .ok_or_else(|| Error::Internal("jump table entry lost its target".to_string()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(
+        SpiProgram::new(ProgramBlob::new(instructions).with_jump_table(jump_table))
+            .with_metadata(program.metadata().to_vec())
+            .with_ro_data(program.ro_data().to_vec())
+            .with_rw_data(program.rw_data().to_vec())
+            .with_heap_pages(program.heap_pages())
+            .with_stack_size(program.stack_size()),
+    )
+}
+
+/// The code-relative offset of a jump or branch, if `instr` has one.
+fn jump_offset_mut(instr: &mut Instruction) -> Option<&mut i32> {
+    match instr {
+        Instruction::Jump { offset }
+        | Instruction::JumpFixed { offset }
+        | Instruction::LoadImmJump { offset, .. }
+        | Instruction::BranchNeImm { offset, .. }
+        | Instruction::BranchEqImm { offset, .. }
+        | Instruction::BranchGeSImm { offset, .. }
+        | Instruction::BranchLtUImm { offset, .. }
+        | Instruction::BranchLeUImm { offset, .. }
+        | Instruction::BranchGeUImm { offset, .. }
+        | Instruction::BranchGtUImm { offset, .. }
+        | Instruction::BranchLtSImm { offset, .. }
+        | Instruction::BranchLeSImm { offset, .. }
+        | Instruction::BranchGtSImm { offset, .. }
+        | Instruction::BranchEq { offset, .. }
+        | Instruction::BranchNe { offset, .. }
+        | Instruction::BranchGeU { offset, .. }
+        | Instruction::BranchLtU { offset, .. }
+        | Instruction::BranchLtS { offset, .. }
+        | Instruction::BranchGeS { offset, .. } => Some(offset),
+        _ => None,
+    }
+}
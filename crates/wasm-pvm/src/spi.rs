@@ -1,7 +1,18 @@
 // SPI encoding uses u32 lengths but writes u24. Truncation is checked or expected.
 #![allow(clippy::cast_possible_truncation)]
 
-use crate::pvm::ProgramBlob;
+use alloc::{format, vec::Vec};
+
+use crate::memory_layout::{GLOBAL_MEMORY_BASE, RO_DATA_BASE};
+use crate::pvm::{ByteReader, ProgramBlob};
+use crate::{Error, Result};
+
+/// Largest value a u24 SPI header field can hold.
+const MAX_U24: usize = (1 << 24) - 1;
+
+/// Bytes of `ro_data` that keep `rw_data` at `GLOBAL_MEMORY_BASE`: the loader
+/// leaves one unmapped 64 KB guard segment between the two.
+const RO_DATA_CAPACITY: usize = (GLOBAL_MEMORY_BASE - RO_DATA_BASE - 0x1_0000) as usize;
 
 pub struct SpiProgram {
     metadata: Vec<u8>,
@@ -75,11 +86,46 @@ impl SpiProgram {
         self.heap_pages
     }
 
+    #[must_use]
+    pub fn stack_size(&self) -> u32 {
+        self.stack_size
+    }
+
     #[must_use]
     pub fn metadata(&self) -> &[u8] {
         &self.metadata
     }
 
+    /// Check that the sections land where compiled code expects them.
+    ///
+    /// The loader places `rw_data` on the first 64 KB boundary after
+    /// `ro_data`, but compiled code addresses globals and linear memory from
+    /// `GLOBAL_MEMORY_BASE` (0x30000). An `ro_data` larger than the 64 KB
+    /// segment at `RO_DATA_BASE` would push `rw_data` up, so every global and
+    /// memory access would hit the wrong bytes. Sizes that overflow their
+    /// 24-bit header fields are rejected too, since `encode` would truncate
+    /// them.
+    pub fn validate(&self) -> Result<()> {
+        if self.ro_data.len() > RO_DATA_CAPACITY {
+            return Err(Error::SpiLayout(format!(
+                "ro_data is {} bytes but only {RO_DATA_CAPACITY} fit at 0x{RO_DATA_BASE:X}; \
+                 the excess would overlap rw_data, which must start at 0x{GLOBAL_MEMORY_BASE:X}",
+                self.ro_data.len()
+            )));
+        }
+        for (section, len) in [
+            ("rw_data", self.rw_data.len()),
+            ("stack size", self.stack_size as usize),
+        ] {
+            if len > MAX_U24 {
+                return Err(Error::SpiLayout(format!(
+                    "{section} is {len} bytes, more than the {MAX_U24} a 24-bit header field holds"
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Encode the SPI program with metadata prefix.
     ///
     /// Format: `[varint: metadata_len][metadata_bytes][SPI header + data + code]`
@@ -105,6 +151,34 @@ impl SpiProgram {
 
         output
     }
+
+    /// Decode a program produced by [`SpiProgram::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(bytes, "SPI program");
+        let metadata_len = reader.var_u32("metadata length")? as usize;
+        let metadata = reader.take(metadata_len, "metadata")?.to_vec();
+
+        let data_lens = [
+            reader.le_u32(3, "ro_data length")? as usize,
+            reader.le_u32(3, "rw_data length")? as usize,
+        ];
+        let heap_pages = reader.le_u32(2, "heap pages")? as u16;
+        let stack_size = reader.le_u32(3, "stack size")?;
+        // Field initializers run in source order, matching the layout.
+        let program = Self {
+            metadata,
+            ro_data: reader.take(data_lens[0], "ro_data")?.to_vec(),
+            rw_data: reader.take(data_lens[1], "rw_data")?.to_vec(),
+            heap_pages,
+            stack_size,
+            code: {
+                let code_len = reader.le_u32(4, "code length")? as usize;
+                ProgramBlob::decode(reader.take(code_len, "code")?)?
+            },
+        };
+        reader.finish()?;
+        Ok(program)
+    }
 }
 
 fn encode_u24(value: u32) -> [u8; 3] {
@@ -167,4 +241,65 @@ mod tests {
             "metadata should contain the string"
         );
     }
+
+    #[test]
+    fn test_spi_decode_roundtrip() {
+        let code = ProgramBlob::new(vec![
+            Instruction::LoadImm { reg: 7, value: -3 },
+            Instruction::Jump { offset: 6 },
+            Instruction::Trap,
+        ])
+        .with_jump_table(vec![6]);
+        let spi = SpiProgram::new(code)
+            .with_metadata(b"meta".to_vec())
+            .with_ro_data(vec![1, 2, 3])
+            .with_rw_data(vec![4; 300])
+            .with_heap_pages(3)
+            .with_stack_size(4096);
+        let encoded = spi.encode();
+
+        let decoded = SpiProgram::decode(&encoded).unwrap();
+        assert_eq!(decoded.metadata(), b"meta");
+        assert_eq!(decoded.ro_data(), &[1, 2, 3]);
+        assert_eq!(decoded.rw_data(), &[4; 300][..]);
+        assert_eq!(decoded.heap_pages(), 3);
+        assert_eq!(decoded.stack_size(), 4096);
+        assert_eq!(decoded.code().jump_table(), &[6]);
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    #[test]
+    fn validate_accepts_compiler_sized_sections() {
+        let spi = SpiProgram::new(ProgramBlob::new(vec![Instruction::Trap]))
+            .with_ro_data(vec![0; 0x10000])
+            .with_rw_data(vec![0; 4096]);
+        assert!(spi.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_ro_data_overlapping_rw_data() {
+        let spi = SpiProgram::new(ProgramBlob::new(vec![Instruction::Trap]))
+            .with_ro_data(vec![0; 0x10001]);
+        let err = spi.validate().unwrap_err();
+        assert!(matches!(err, Error::SpiLayout(_)), "{err:?}");
+        let msg = err.to_string();
+        assert!(msg.contains("ro_data is 65537 bytes"), "{msg}");
+        assert!(msg.contains("0x30000"), "{msg}");
+    }
+
+    #[test]
+    fn validate_rejects_rw_data_too_large_for_header() {
+        let spi = SpiProgram::new(ProgramBlob::new(vec![Instruction::Trap]))
+            .with_rw_data(vec![0; MAX_U24 + 1]);
+        assert!(matches!(spi.validate(), Err(Error::SpiLayout(_))));
+    }
+
+    #[test]
+    fn test_spi_decode_rejects_truncated_input() {
+        let encoded = SpiProgram::new(ProgramBlob::new(vec![Instruction::Trap])).encode();
+        assert!(SpiProgram::decode(&encoded[..encoded.len() - 1]).is_err());
+        let mut padded = encoded.clone();
+        padded.push(0);
+        assert!(SpiProgram::decode(&padded).is_err());
+    }
 }
@@ -44,7 +44,9 @@
     clippy::manual_assert,
     clippy::missing_panics_doc,
     clippy::uninlined_format_args,
-    clippy::implicit_hasher
+    clippy::implicit_hasher,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
 )]
 
 use std::collections::BTreeMap;
@@ -52,13 +54,39 @@ use std::collections::BTreeMap;
 use crate::llvm_frontend;
 use crate::pvm::{Instruction, Opcode};
 use crate::translate::{ImportAction, WasmModule};
-use crate::{CompileOptions, Error, Result, SpiProgram, compile, compile_with_options};
+use crate::{
+    CompileOptions, Error, Result, SpiProgram, compile, compile_with_options, compile_with_stats,
+};
+
+mod interpreter;
+
+pub use interpreter::{DEFAULT_GAS, ExitReason, Interpreter, RunResult, run_program};
 
 /// Parse WAT (WebAssembly Text) format to WASM binary
 pub fn wat_to_wasm(wat: &str) -> Result<Vec<u8>> {
     wat::parse_str(wat).map_err(|e| Error::Internal(format!("WAT parse error: {e}")))
 }
 
+/// WAT for a module whose exported `main($p, $l)` runs `body` and returns
+/// the first `output_len` bytes of memory as its output.
+///
+/// `items` goes in front of `main` (memory, data segments, globals, helper
+/// functions, ...); `body` may start with `local` declarations.
+pub fn main_module_wat(items: &str, body: &str, output_len: u32) -> String {
+    let packed = u64::from(output_len) << 32;
+    format!(
+        r#"
+        (module
+            {items}
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                {body}
+                (i64.const {packed})
+            )
+        )
+        "#
+    )
+}
+
 /// Compile WAT directly to a SPI program
 pub fn compile_wat(wat: &str) -> Result<SpiProgram> {
     let wasm = wat_to_wasm(wat)?;
@@ -108,9 +136,13 @@ pub fn dump_llvm_ir(wat: &str) -> Result<String> {
         opts.llvm_passes,
         opts.inlining,
         opts.inline_threshold,
+        opts.unroll,
         /* trap_floats */ false,
+        /* split_misaligned_accesses */ false,
+        /* soft_float */ false,
         opts.libcall_recognition,
         opts.mergefunc,
+        opts.tail_calls,
     )?;
     Ok(llvm_module.print_to_string().to_string())
 }
@@ -120,6 +152,127 @@ pub fn extract_instructions(program: &SpiProgram) -> Vec<Instruction> {
     program.code().instructions().to_vec()
 }
 
+/// Where a program trapped, as located by [`run_and_expect_trap_near`].
+#[derive(Debug, Clone)]
+pub struct TrapSite {
+    /// PC of the executed `Trap`.
+    pub pc: u32,
+    /// Name of the function containing the trap (as in `CompileStats`).
+    pub function: String,
+    /// `pc` relative to the start of `function`.
+    pub function_offset: u32,
+    /// The conditional branch that jumps over the `Trap` when the check
+    /// passes, if the trap is a guard.
+    pub guard: Option<Instruction>,
+    /// The block the guard protects: the instructions after the `Trap` up to
+    /// and including the next terminator.
+    pub guarded: Vec<Instruction>,
+}
+
+/// Compile `wasm`, run it with `args` in the [`Interpreter`] and assert that
+/// it hits a `Trap` inside `wasm_func` whose guarded block contains `hint`.
+///
+/// Checks the compiler emits as `branch-over; Trap; <operation>` (division
+/// by zero, signed overflow, bounds checks) are mapped back to the operation
+/// they protect, so a test can tell *which* construct trapped rather than
+/// only that something did. Returns the located [`TrapSite`] for further
+/// assertions.
+pub fn run_and_expect_trap_near(
+    wasm: &[u8],
+    args: &[u8],
+    wasm_func: &str,
+    hint: Opcode,
+) -> TrapSite {
+    run_and_expect_trap_near_with_options(wasm, args, wasm_func, hint, &CompileOptions::default())
+}
+
+/// [`run_and_expect_trap_near`], compiling with `options`.
+pub fn run_and_expect_trap_near_with_options(
+    wasm: &[u8],
+    args: &[u8],
+    wasm_func: &str,
+    hint: Opcode,
+    options: &CompileOptions,
+) -> TrapSite {
+    let (program, stats) = compile_with_stats(wasm, options).expect("Failed to compile");
+    let result = run_program(&program, args);
+    let instructions = program.code().instructions();
+
+    let mut pcs = Vec::with_capacity(instructions.len() + 1);
+    let mut pc = 0u32;
+    for instr in instructions {
+        pcs.push(pc);
+        pc += instr.encoded_len() as u32;
+    }
+    pcs.push(pc);
+
+    if result.exit != ExitReason::Trap {
+        panic!(
+            "Expected a trap in `{}`, got {:?} at pc {}",
+            wasm_func, result.exit, result.pc
+        );
+    }
+    let idx = pcs
+        .binary_search(&result.pc)
+        .expect("trap pc is an instruction start");
+
+    let function = stats
+        .functions
+        .iter()
+        .filter(|f| f.code_offset as u32 <= result.pc)
+        .max_by_key(|f| f.code_offset)
+        .unwrap_or_else(|| panic!("Trap at pc {} is outside every function", result.pc));
+    if function.name != wasm_func {
+        panic!(
+            "Expected a trap in `{}`, but pc {} is in `{}`",
+            wasm_func, result.pc, function.name
+        );
+    }
+
+    // A guard branches from just before the trap to just after it.
+    let guard = idx
+        .checked_sub(1)
+        .map(|prev| &instructions[prev])
+        .filter(|prev| {
+            // Only a conditional branch can fall through into the trap.
+            prev.invert_branch()
+                .and(prev.jump_offset())
+                .is_some_and(|offset| pcs[idx - 1].wrapping_add(offset as u32) == pcs[idx + 1])
+        })
+        .cloned();
+
+    let mut guarded = Vec::new();
+    for instr in &instructions[idx + 1..] {
+        if matches!(instr, Instruction::Fallthrough) {
+            continue;
+        }
+        guarded.push(instr.clone());
+        if instr.is_terminating() {
+            break;
+        }
+    }
+
+    let site = TrapSite {
+        pc: result.pc,
+        function: function.name.clone(),
+        function_offset: result.pc - function.code_offset as u32,
+        guard,
+        guarded,
+    };
+    if site.guard.is_none() || !has_opcode(&site.guarded, hint) {
+        panic!(
+            "Trap at pc {} (`{}`+{}) does not guard {:?}.\n\nGuard: {:?}\n\nGuarded block:\n{}",
+            site.pc,
+            site.function,
+            site.function_offset,
+            hint,
+            site.guard,
+            format_instructions(&site.guarded)
+        );
+    }
+    site
+}
+
 /// Pattern matching for instruction fields
 #[derive(Debug, Clone)]
 pub enum Pat<T> {
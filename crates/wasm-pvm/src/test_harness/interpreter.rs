@@ -0,0 +1,1107 @@
+//! Reference PVM interpreter for harness tests.
+//!
+//! Executes a compiled [`SpiProgram`] from the same initial state the SPI
+//! loader (anan-as `run --spi`) sets up, so Rust tests can observe runtime
+//! behaviour — results, traps and the PC they happened at — without going
+//! through the TypeScript toolchain.
+//!
+//! The interpreter runs the program's in-memory [`Instruction`] list rather
+//! than re-decoding the encoded blob; PCs are byte offsets into the encoded
+//! code section, exactly as a real PVM would see them.
+//!
+//! Initial state (Gray Paper SPI layout, `Z_Z` = 64 KB segments, 4 KB pages):
+//!
+//! ```text
+//!   0x10000                     ro_data (read-only)
+//!   0x20000 + Z_Z(|ro|)         rw_data, then heap_pages zero pages (sbrk grows from here)
+//!   0xFEFE0000 - stack_size     stack (writable), SP (r1) = 0xFEFE0000
+//!   0xFEFF0000                  args (read-only), r7 = ptr, r8 = len
+//!   r0 = 0xFFFF0000             jumping here halts; the result is memory[r7..r7+r8]
+//! ```
+
+// PVM arithmetic is defined on wrapping u64/u32 values; the casts below are
+// the instruction semantics, not accidents.
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss,
+    clippy::cast_lossless
+)]
+
+use std::collections::HashMap;
+
+use crate::SpiProgram;
+use crate::pvm::Instruction;
+
+/// PVM page size.
+pub const PAGE_SIZE: u32 = 4096;
+
+/// SPI segment size (`Z_Z`): ro/rw data are placed on 64 KB boundaries.
+const SEGMENT_SIZE: u32 = 0x1_0000;
+
+/// Addresses below this always panic.
+const LOWEST_ACCESSIBLE: u32 = 0x1_0000;
+
+const STACK_END: u32 = 0xFEFE_0000;
+const ARGS_BASE: u32 = 0xFEFF_0000;
+const EXIT_ADDRESS: u32 = 0xFFFF_0000;
+
+/// Gas budget used by [`run_program`]. Each instruction costs 1.
+pub const DEFAULT_GAS: u64 = 100_000_000;
+
+/// Why execution stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Dynamic jump to the exit address.
+    Halt,
+    /// A `Trap` instruction was executed.
+    Trap,
+    /// Any other PVM panic: invalid jump target, unknown opcode, access
+    /// below 0x10000, running off the end of the code.
+    Panic(String),
+    /// Access to an unmapped page (or a write to a read-only one).
+    /// Carries the page-aligned faulting address.
+    PageFault(u32),
+    OutOfGas,
+    /// `ecalli` was executed. The PC already points past it, so calling
+    /// [`Interpreter::run`] again resumes execution.
+    HostCall(u32),
+}
+
+/// Outcome of [`run_program`].
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub exit: ExitReason,
+    /// PC of the instruction that stopped execution (the `Trap`, the faulting
+    /// access, ...). For [`ExitReason::Halt`] this is the final jump.
+    pub pc: u32,
+    /// `memory[r7..r7+r8]` on halt; empty otherwise or if unreadable.
+    pub output: Vec<u8>,
+    pub regs: [u64; 13],
+    pub gas_used: u64,
+}
+
+struct Page {
+    data: Box<[u8; PAGE_SIZE as usize]>,
+    writable: bool,
+}
+
+#[derive(Default)]
+struct Memory {
+    pages: HashMap<u32, Page>,
+}
+
+impl Memory {
+    fn map(&mut self, start: u32, len: u32, writable: bool) {
+        if len == 0 {
+            return;
+        }
+        let first = start / PAGE_SIZE;
+        let last = (u64::from(start) + u64::from(len) - 1) / u64::from(PAGE_SIZE);
+        for page in u64::from(first)..=last {
+            self.pages.entry(page as u32).or_insert_with(|| Page {
+                data: Box::new([0; PAGE_SIZE as usize]),
+                writable,
+            });
+        }
+    }
+
+    fn check(&self, addr: u32, len: u32, write: bool) -> Result<(), ExitReason> {
+        if addr < LOWEST_ACCESSIBLE {
+            return Err(ExitReason::Panic(format!(
+                "memory access at {addr:#x} (below {LOWEST_ACCESSIBLE:#x})"
+            )));
+        }
+        for i in 0..len {
+            let a = addr.wrapping_add(i);
+            match self.pages.get(&(a / PAGE_SIZE)) {
+                Some(page) if !write || page.writable => {}
+                _ => return Err(ExitReason::PageFault(a / PAGE_SIZE * PAGE_SIZE)),
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&self, addr: u32, len: u32) -> Result<Vec<u8>, ExitReason> {
+        self.check(addr, len, false)?;
+        Ok((0..len)
+            .map(|i| {
+                let a = addr.wrapping_add(i);
+                self.pages[&(a / PAGE_SIZE)].data[(a % PAGE_SIZE) as usize]
+            })
+            .collect())
+    }
+
+    fn write(&mut self, addr: u32, bytes: &[u8]) -> Result<(), ExitReason> {
+        self.check(addr, bytes.len() as u32, true)?;
+        for (i, &b) in bytes.iter().enumerate() {
+            let a = addr.wrapping_add(i as u32);
+            let page = self.pages.get_mut(&(a / PAGE_SIZE)).expect("checked above");
+            page.data[(a % PAGE_SIZE) as usize] = b;
+        }
+        Ok(())
+    }
+
+    /// Write without permission checks (initial image setup).
+    fn init(&mut self, addr: u32, bytes: &[u8]) {
+        for (i, &b) in bytes.iter().enumerate() {
+            let a = addr.wrapping_add(i as u32);
+            let page = self
+                .pages
+                .get_mut(&(a / PAGE_SIZE))
+                .expect("mapped by caller");
+            page.data[(a % PAGE_SIZE) as usize] = b;
+        }
+    }
+}
+
+fn align_up(value: u32, align: u32) -> u32 {
+    value.div_ceil(align) * align
+}
+
+/// Sign-extend the low 32 bits.
+fn sx32(value: u64) -> u64 {
+    value as u32 as i32 as i64 as u64
+}
+
+/// Sign-extend an instruction immediate to a register value.
+fn imm(value: i32) -> u64 {
+    value as i64 as u64
+}
+
+/// Single-program PVM interpreter. See the module docs for the initial state.
+pub struct Interpreter {
+    instructions: Vec<Instruction>,
+    /// Start PC of each instruction, ascending.
+    pcs: Vec<u32>,
+    jump_table: Vec<u32>,
+    regs: [u64; 13],
+    /// Index into `instructions` of the next instruction to execute.
+    next: usize,
+    memory: Memory,
+    heap_end: u32,
+    /// Lowest stack address; the heap may not grow past it.
+    stack_base: u32,
+    gas: u64,
+    gas_used: u64,
+}
+
+impl Interpreter {
+    #[must_use]
+    pub fn new(program: &SpiProgram, args: &[u8]) -> Self {
+        let code = program.code();
+        let instructions = code.instructions().to_vec();
+        let mut pcs = Vec::with_capacity(instructions.len());
+        let mut pc = 0u32;
+        for instr in &instructions {
+            pcs.push(pc);
+            pc += instr.encoded_len() as u32;
+        }
+
+        let mut memory = Memory::default();
+
+        let ro = program.ro_data();
+        memory.map(SEGMENT_SIZE, align_up(ro.len() as u32, PAGE_SIZE), false);
+        memory.init(SEGMENT_SIZE, ro);
+
+        let rw = program.rw_data();
+        let rw_base = 2 * SEGMENT_SIZE + align_up(ro.len() as u32, SEGMENT_SIZE);
+        let heap_end = rw_base
+            + align_up(rw.len() as u32, PAGE_SIZE)
+            + u32::from(program.heap_pages()) * PAGE_SIZE;
+        memory.map(rw_base, heap_end - rw_base, true);
+        memory.init(rw_base, rw);
+
+        let stack_base = STACK_END - align_up(program.stack_size(), PAGE_SIZE);
+        memory.map(stack_base, STACK_END - stack_base, true);
+
+        memory.map(ARGS_BASE, align_up(args.len() as u32, PAGE_SIZE), false);
+        memory.init(ARGS_BASE, args);
+
+        let mut regs = [0u64; 13];
+        regs[0] = u64::from(EXIT_ADDRESS);
+        regs[1] = u64::from(STACK_END);
+        regs[7] = u64::from(ARGS_BASE);
+        regs[8] = args.len() as u64;
+
+        Self {
+            instructions,
+            pcs,
+            jump_table: code.jump_table().to_vec(),
+            regs,
+            next: 0,
+            memory,
+            heap_end,
+            stack_base,
+            gas: DEFAULT_GAS,
+            gas_used: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_gas(mut self, gas: u64) -> Self {
+        self.gas = gas;
+        self
+    }
+
+    #[must_use]
+    pub fn regs(&self) -> &[u64; 13] {
+        &self.regs
+    }
+
+    pub fn set_reg(&mut self, reg: u8, value: u64) {
+        self.regs[reg as usize] = value;
+    }
+
+    /// PC of the next instruction to execute.
+    #[must_use]
+    pub fn pc(&self) -> u32 {
+        self.pcs
+            .get(self.next)
+            .copied()
+            .unwrap_or_else(|| self.code_len())
+    }
+
+    #[must_use]
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    /// Read guest memory, ignoring page permissions' write bit.
+    #[must_use]
+    pub fn read_memory(&self, addr: u32, len: u32) -> Option<Vec<u8>> {
+        self.memory.read(addr, len).ok()
+    }
+
+    /// Write guest memory (e.g. from a host-call handler). Returns false on a
+    /// fault.
+    pub fn write_memory(&mut self, addr: u32, bytes: &[u8]) -> bool {
+        self.memory.write(addr, bytes).is_ok()
+    }
+
+    /// `memory[r7..r7+r8]`, the program's result after a halt.
+    #[must_use]
+    pub fn output(&self) -> Vec<u8> {
+        self.read_memory(self.regs[7] as u32, self.regs[8] as u32)
+            .unwrap_or_default()
+    }
+
+    fn code_len(&self) -> u32 {
+        match (self.pcs.last(), self.instructions.last()) {
+            (Some(pc), Some(instr)) => pc + instr.encoded_len() as u32,
+            _ => 0,
+        }
+    }
+
+    fn index_of(&self, pc: u32) -> Result<usize, ExitReason> {
+        self.pcs
+            .binary_search(&pc)
+            .map_err(|_| ExitReason::Panic(format!("jump to {pc:#x}, not an instruction start")))
+    }
+
+    /// Resolve a dynamic jump through the jump table.
+    fn djump(&self, addr: u32) -> Result<Option<usize>, ExitReason> {
+        if addr == EXIT_ADDRESS {
+            return Ok(None);
+        }
+        if addr == 0 || !addr.is_multiple_of(2) || (addr / 2) as usize > self.jump_table.len() {
+            return Err(ExitReason::Panic(format!(
+                "invalid dynamic jump to {addr:#x}"
+            )));
+        }
+        let target = self.jump_table[(addr / 2 - 1) as usize];
+        self.index_of(target).map(Some)
+    }
+
+    fn load(&self, addr: u64, len: u32) -> Result<u64, ExitReason> {
+        let bytes = self.memory.read(addr as u32, len)?;
+        let mut buf = [0u8; 8];
+        buf[..len as usize].copy_from_slice(&bytes);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn store(&mut self, addr: u64, len: u32, value: u64) -> Result<(), ExitReason> {
+        self.memory
+            .write(addr as u32, &value.to_le_bytes()[..len as usize])
+    }
+
+    fn sbrk(&mut self, size: u64) -> u64 {
+        let old = self.heap_end;
+        if size == 0 {
+            return u64::from(old);
+        }
+        let Some(new_end) = u32::try_from(size)
+            .ok()
+            .and_then(|s| old.checked_add(s))
+            .filter(|&end| end <= self.stack_base)
+        else {
+            return 0;
+        };
+        self.memory.map(old, new_end - old, true);
+        self.heap_end = new_end;
+        u64::from(old)
+    }
+
+    /// Run until the program halts, traps, faults, runs out of gas or makes
+    /// a host call.
+    pub fn run(&mut self) -> ExitReason {
+        loop {
+            if let Err(exit) = self.step() {
+                return exit;
+            }
+        }
+    }
+
+    /// Execute one instruction. `Err` carries the reason execution stopped;
+    /// on a stop the PC is left at the stopping instruction (except host
+    /// calls, which advance past `ecalli`).
+    #[allow(clippy::too_many_lines)]
+    fn step(&mut self) -> Result<(), ExitReason> {
+        let idx = self.next;
+        let Some(instr) = self.instructions.get(idx).cloned() else {
+            return Err(ExitReason::Panic(
+                "execution ran past the end of the code".into(),
+            ));
+        };
+        if self.gas == 0 {
+            return Err(ExitReason::OutOfGas);
+        }
+        self.gas -= 1;
+        self.gas_used += 1;
+
+        let pc = self.pcs[idx];
+        let r = self.regs;
+        let rd = |reg: &u8| r[*reg as usize];
+        let mut next = idx + 1;
+        let mut set = None::<(u8, u64)>;
+        // Static jump target (PC) taken by this instruction, if any.
+        let mut jump = None::<u32>;
+
+        macro_rules! branch {
+            ($taken:expr, $offset:expr) => {{
+                if $taken {
+                    jump = Some(pc.wrapping_add(*$offset as u32));
+                }
+            }};
+        }
+        macro_rules! three {
+            ($dst:expr, $a:expr, $b:expr, $f:expr) => {{
+                let f: fn(u64, u64) -> u64 = $f;
+                set = Some((*$dst, f(rd($a), rd($b))));
+            }};
+        }
+        macro_rules! with_imm {
+            ($dst:expr, $src:expr, $v:expr, $f:expr) => {{
+                let f: fn(u64, u64) -> u64 = $f;
+                set = Some((*$dst, f(rd($src), imm(*$v))));
+            }};
+        }
+        macro_rules! unary {
+            ($dst:expr, $src:expr, $f:expr) => {{
+                let f: fn(u64) -> u64 = $f;
+                set = Some((*$dst, f(rd($src))));
+            }};
+        }
+
+        match &instr {
+            Instruction::Trap => return Err(ExitReason::Trap),
+            Instruction::Fallthrough => {}
+            Instruction::Unknown { opcode, .. } => {
+                return Err(ExitReason::Panic(format!("unknown opcode {opcode}")));
+            }
+            Instruction::Ecalli { index } => {
+                self.next = next;
+                return Err(ExitReason::HostCall(*index));
+            }
+
+            Instruction::LoadImm64 { reg, value } => set = Some((*reg, *value)),
+            Instruction::LoadImm { reg, value } => set = Some((*reg, imm(*value))),
+            Instruction::MoveReg { dst, src } => set = Some((*dst, rd(src))),
+
+            // ── 32-bit three-register ALU (results sign-extended) ──
+            Instruction::Add32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(a.wrapping_add(b)));
+            }
+            Instruction::Sub32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(a.wrapping_sub(b)));
+            }
+            Instruction::Mul32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(a.wrapping_mul(b)));
+            }
+            Instruction::DivU32 { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                (a as u32)
+                    .checked_div(b as u32)
+                    .map_or(u64::MAX, |q| sx32(u64::from(q)))
+            }),
+            Instruction::DivS32 { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                let (a, b) = (a as u32 as i32, b as u32 as i32);
+                if b == 0 {
+                    u64::MAX
+                } else {
+                    a.wrapping_div(b) as i64 as u64
+                }
+            }),
+            Instruction::RemU32 { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                let (a, b) = (a as u32, b as u32);
+                if b == 0 {
+                    sx32(u64::from(a))
+                } else {
+                    sx32(u64::from(a % b))
+                }
+            }),
+            Instruction::RemS32 { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                let (a, b) = (a as u32 as i32, b as u32 as i32);
+                if b == 0 {
+                    a as i64 as u64
+                } else {
+                    a.wrapping_rem(b) as i64 as u64
+                }
+            }),
+            Instruction::ShloL32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(u64::from(
+                    (a as u32) << (b % 32)
+                )));
+            }
+            Instruction::ShloR32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(u64::from(
+                    (a as u32) >> (b % 32)
+                )));
+            }
+            Instruction::SharR32 { dst, src1, src2 } => {
+                three!(
+                    dst,
+                    src1,
+                    src2,
+                    |a, b| ((a as u32 as i32) >> (b % 32)) as i64 as u64
+                );
+            }
+            Instruction::RotL32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(u64::from(
+                    (a as u32).rotate_left((b % 32) as u32)
+                )));
+            }
+            Instruction::RotR32 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| sx32(u64::from(
+                    (a as u32).rotate_right((b % 32) as u32)
+                )));
+            }
+
+            // ── 64-bit three-register ALU ──
+            Instruction::Add64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, u64::wrapping_add);
+            }
+            Instruction::Sub64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, u64::wrapping_sub);
+            }
+            Instruction::Mul64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, u64::wrapping_mul);
+            }
+            Instruction::DivU64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| a.checked_div(b).unwrap_or(u64::MAX));
+            }
+            Instruction::DivS64 { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                if b == 0 {
+                    u64::MAX
+                } else {
+                    (a as i64).wrapping_div(b as i64) as u64
+                }
+            }),
+            Instruction::RemU64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| a.checked_rem(b).unwrap_or(a));
+            }
+            Instruction::RemS64 { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                if b == 0 {
+                    a
+                } else {
+                    (a as i64).wrapping_rem(b as i64) as u64
+                }
+            }),
+            Instruction::ShloL64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| a << (b % 64));
+            }
+            Instruction::ShloR64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| a >> (b % 64));
+            }
+            Instruction::SharR64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| ((a as i64) >> (b % 64)) as u64);
+            }
+            Instruction::RotL64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| a.rotate_left((b % 64) as u32));
+            }
+            Instruction::RotR64 { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| a.rotate_right((b % 64) as u32));
+            }
+            Instruction::MulUpperSS { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                ((i128::from(a as i64) * i128::from(b as i64)) >> 64) as u64
+            }),
+            Instruction::MulUpperUU { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                ((u128::from(a) * u128::from(b)) >> 64) as u64
+            }),
+            Instruction::MulUpperSU { dst, src1, src2 } => three!(dst, src1, src2, |a, b| {
+                ((i128::from(a as i64) * i128::from(b)) >> 64) as u64
+            }),
+            Instruction::And { dst, src1, src2 } => three!(dst, src1, src2, |a, b| a & b),
+            Instruction::Or { dst, src1, src2 } => three!(dst, src1, src2, |a, b| a | b),
+            Instruction::Xor { dst, src1, src2 } => three!(dst, src1, src2, |a, b| a ^ b),
+            Instruction::AndInv { dst, src1, src2 } => three!(dst, src1, src2, |a, b| a & !b),
+            Instruction::OrInv { dst, src1, src2 } => three!(dst, src1, src2, |a, b| a | !b),
+            Instruction::Xnor { dst, src1, src2 } => three!(dst, src1, src2, |a, b| !(a ^ b)),
+            Instruction::SetLtU { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| u64::from(a < b));
+            }
+            Instruction::SetLtS { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| u64::from((a as i64) < (b as i64)));
+            }
+            Instruction::Max { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| (a as i64).max(b as i64) as u64);
+            }
+            Instruction::MaxU { dst, src1, src2 } => three!(dst, src1, src2, u64::max),
+            Instruction::Min { dst, src1, src2 } => {
+                three!(dst, src1, src2, |a, b| (a as i64).min(b as i64) as u64);
+            }
+            Instruction::MinU { dst, src1, src2 } => three!(dst, src1, src2, u64::min),
+            Instruction::CmovIz { dst, src, cond } => {
+                if rd(cond) == 0 {
+                    set = Some((*dst, rd(src)));
+                }
+            }
+            Instruction::CmovNz { dst, src, cond } => {
+                if rd(cond) != 0 {
+                    set = Some((*dst, rd(src)));
+                }
+            }
+
+            // ── Register + immediate ALU ──
+            Instruction::AddImm32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(a.wrapping_add(b)));
+            }
+            Instruction::AddImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, u64::wrapping_add);
+            }
+            Instruction::MulImm32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(a.wrapping_mul(b)));
+            }
+            Instruction::MulImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, u64::wrapping_mul);
+            }
+            Instruction::NegAddImm32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(b.wrapping_sub(a)));
+            }
+            Instruction::NegAddImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| b.wrapping_sub(a));
+            }
+            Instruction::AndImm { dst, src, value } => with_imm!(dst, src, value, |a, b| a & b),
+            Instruction::OrImm { dst, src, value } => with_imm!(dst, src, value, |a, b| a | b),
+            Instruction::XorImm { dst, src, value } => with_imm!(dst, src, value, |a, b| a ^ b),
+            Instruction::SetLtUImm { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| u64::from(a < b));
+            }
+            Instruction::SetLtSImm { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| u64::from((a as i64) < (b as i64)));
+            }
+            Instruction::SetGtUImm { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| u64::from(a > b));
+            }
+            Instruction::SetGtSImm { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| u64::from((a as i64) > (b as i64)));
+            }
+            Instruction::ShloLImm32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(u64::from(
+                    (a as u32) << (b % 32)
+                )));
+            }
+            Instruction::ShloRImm32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(u64::from(
+                    (a as u32) >> (b % 32)
+                )));
+            }
+            Instruction::SharRImm32 { dst, src, value } => {
+                with_imm!(
+                    dst,
+                    src,
+                    value,
+                    |a, b| ((a as u32 as i32) >> (b % 32)) as i64 as u64
+                );
+            }
+            Instruction::ShloLImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| a << (b % 64));
+            }
+            Instruction::ShloRImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| a >> (b % 64));
+            }
+            Instruction::SharRImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| ((a as i64) >> (b % 64)) as u64);
+            }
+            // `Alt` forms shift/rotate the immediate by the register.
+            Instruction::ShloLImmAlt32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(u64::from(
+                    (b as u32) << (a % 32)
+                )));
+            }
+            Instruction::ShloRImmAlt32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(u64::from(
+                    (b as u32) >> (a % 32)
+                )));
+            }
+            Instruction::SharRImmAlt32 { dst, src, value } => {
+                with_imm!(
+                    dst,
+                    src,
+                    value,
+                    |a, b| ((b as u32 as i32) >> (a % 32)) as i64 as u64
+                );
+            }
+            Instruction::ShloLImmAlt64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| b << (a % 64));
+            }
+            Instruction::ShloRImmAlt64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| b >> (a % 64));
+            }
+            Instruction::SharRImmAlt64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| ((b as i64) >> (a % 64)) as u64);
+            }
+            Instruction::RotRImm64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| a.rotate_right((b % 64) as u32));
+            }
+            Instruction::RotRImmAlt64 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| b.rotate_right((a % 64) as u32));
+            }
+            Instruction::RotRImm32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(u64::from(
+                    (a as u32).rotate_right((b % 32) as u32)
+                )));
+            }
+            Instruction::RotRImmAlt32 { dst, src, value } => {
+                with_imm!(dst, src, value, |a, b| sx32(u64::from(
+                    (b as u32).rotate_right((a % 32) as u32)
+                )));
+            }
+            Instruction::CmovIzImm { dst, cond, value } => {
+                if rd(cond) == 0 {
+                    set = Some((*dst, imm(*value)));
+                }
+            }
+            Instruction::CmovNzImm { dst, cond, value } => {
+                if rd(cond) != 0 {
+                    set = Some((*dst, imm(*value)));
+                }
+            }
+
+            // ── Unary ──
+            Instruction::CountSetBits64 { dst, src } => {
+                unary!(dst, src, |a| u64::from(a.count_ones()));
+            }
+            Instruction::CountSetBits32 { dst, src } => {
+                unary!(dst, src, |a| u64::from((a as u32).count_ones()));
+            }
+            Instruction::LeadingZeroBits64 { dst, src } => {
+                unary!(dst, src, |a| u64::from(a.leading_zeros()));
+            }
+            Instruction::LeadingZeroBits32 { dst, src } => {
+                unary!(dst, src, |a| u64::from((a as u32).leading_zeros()));
+            }
+            Instruction::TrailingZeroBits64 { dst, src } => {
+                unary!(dst, src, |a| u64::from(a.trailing_zeros()));
+            }
+            Instruction::TrailingZeroBits32 { dst, src } => {
+                unary!(dst, src, |a| u64::from((a as u32).trailing_zeros()));
+            }
+            Instruction::SignExtend8 { dst, src } => unary!(dst, src, |a| a as i8 as i64 as u64),
+            Instruction::SignExtend16 { dst, src } => {
+                unary!(dst, src, |a| a as i16 as i64 as u64);
+            }
+            Instruction::ZeroExtend16 { dst, src } => unary!(dst, src, |a| a & 0xFFFF),
+            Instruction::ReverseBytes { dst, src } => unary!(dst, src, u64::swap_bytes),
+            Instruction::Sbrk { dst, src } => {
+                let size = rd(src);
+                set = Some((*dst, self.sbrk(size)));
+            }
+
+            // ── Memory ──
+            Instruction::LoadU8 { dst, address } => {
+                set = Some((*dst, self.load(imm(*address), 1)?));
+            }
+            Instruction::LoadI8 { dst, address } => {
+                let v = self.load(imm(*address), 1)?;
+                set = Some((*dst, v as i8 as i64 as u64));
+            }
+            Instruction::LoadU16 { dst, address } => {
+                set = Some((*dst, self.load(imm(*address), 2)?));
+            }
+            Instruction::LoadI16 { dst, address } => {
+                let v = self.load(imm(*address), 2)?;
+                set = Some((*dst, v as i16 as i64 as u64));
+            }
+            Instruction::LoadU32 { dst, address } => {
+                set = Some((*dst, self.load(imm(*address), 4)?));
+            }
+            Instruction::LoadI32 { dst, address } => {
+                let v = self.load(imm(*address), 4)?;
+                set = Some((*dst, sx32(v)));
+            }
+            Instruction::LoadU64 { dst, address } => {
+                set = Some((*dst, self.load(imm(*address), 8)?));
+            }
+            Instruction::StoreU8 { src, address } => self.store(imm(*address), 1, rd(src))?,
+            Instruction::StoreU16 { src, address } => self.store(imm(*address), 2, rd(src))?,
+            Instruction::StoreU32 { src, address } => self.store(imm(*address), 4, rd(src))?,
+            Instruction::StoreU64 { src, address } => self.store(imm(*address), 8, rd(src))?,
+            Instruction::StoreImmU8 { address, value } => {
+                self.store(imm(*address), 1, imm(*value))?;
+            }
+            Instruction::StoreImmU16 { address, value } => {
+                self.store(imm(*address), 2, imm(*value))?;
+            }
+            Instruction::StoreImmU32 { address, value } => {
+                self.store(imm(*address), 4, imm(*value))?;
+            }
+            Instruction::StoreImmU64 { address, value } => {
+                self.store(imm(*address), 8, imm(*value))?;
+            }
+            Instruction::LoadIndU8 { dst, base, offset } => {
+                set = Some((*dst, self.load(rd(base).wrapping_add(imm(*offset)), 1)?));
+            }
+            Instruction::LoadIndI8 { dst, base, offset } => {
+                let v = self.load(rd(base).wrapping_add(imm(*offset)), 1)?;
+                set = Some((*dst, v as i8 as i64 as u64));
+            }
+            Instruction::LoadIndU16 { dst, base, offset } => {
+                set = Some((*dst, self.load(rd(base).wrapping_add(imm(*offset)), 2)?));
+            }
+            Instruction::LoadIndI16 { dst, base, offset } => {
+                let v = self.load(rd(base).wrapping_add(imm(*offset)), 2)?;
+                set = Some((*dst, v as i16 as i64 as u64));
+            }
+            Instruction::LoadIndU32 { dst, base, offset } => {
+                set = Some((*dst, self.load(rd(base).wrapping_add(imm(*offset)), 4)?));
+            }
+            Instruction::LoadIndI32 { dst, base, offset } => {
+                let v = self.load(rd(base).wrapping_add(imm(*offset)), 4)?;
+                set = Some((*dst, sx32(v)));
+            }
+            Instruction::LoadIndU64 { dst, base, offset } => {
+                set = Some((*dst, self.load(rd(base).wrapping_add(imm(*offset)), 8)?));
+            }
+            Instruction::StoreIndU8 { base, src, offset } => {
+                self.store(rd(base).wrapping_add(imm(*offset)), 1, rd(src))?;
+            }
+            Instruction::StoreIndU16 { base, src, offset } => {
+                self.store(rd(base).wrapping_add(imm(*offset)), 2, rd(src))?;
+            }
+            Instruction::StoreIndU32 { base, src, offset } => {
+                self.store(rd(base).wrapping_add(imm(*offset)), 4, rd(src))?;
+            }
+            Instruction::StoreIndU64 { base, src, offset } => {
+                self.store(rd(base).wrapping_add(imm(*offset)), 8, rd(src))?;
+            }
+            Instruction::StoreImmIndU8 {
+                base,
+                offset,
+                value,
+            } => self.store(rd(base).wrapping_add(imm(*offset)), 1, imm(*value))?,
+            Instruction::StoreImmIndU16 {
+                base,
+                offset,
+                value,
+            } => self.store(rd(base).wrapping_add(imm(*offset)), 2, imm(*value))?,
+            Instruction::StoreImmIndU32 {
+                base,
+                offset,
+                value,
+            } => self.store(rd(base).wrapping_add(imm(*offset)), 4, imm(*value))?,
+            Instruction::StoreImmIndU64 {
+                base,
+                offset,
+                value,
+            } => self.store(rd(base).wrapping_add(imm(*offset)), 8, imm(*value))?,
+
+            // ── Control flow ──
+            Instruction::Jump { offset } | Instruction::JumpFixed { offset } => {
+                branch!(true, offset);
+            }
+            Instruction::LoadImmJump { reg, value, offset } => {
+                branch!(true, offset);
+                set = Some((*reg, imm(*value)));
+            }
+            Instruction::JumpInd { reg, offset } => {
+                match self.djump(rd(reg).wrapping_add(imm(*offset)) as u32)? {
+                    Some(target) => next = target,
+                    None => return Err(ExitReason::Halt),
+                }
+            }
+            Instruction::LoadImmJumpInd {
+                base,
+                dst,
+                value,
+                offset,
+            } => {
+                // The target is computed before `dst` is written.
+                let target = self.djump(rd(base).wrapping_add(imm(*offset)) as u32)?;
+                self.regs[*dst as usize] = imm(*value);
+                match target {
+                    Some(target) => next = target,
+                    None => return Err(ExitReason::Halt),
+                }
+            }
+            Instruction::BranchEqImm { reg, value, offset } => {
+                branch!(rd(reg) == imm(*value), offset);
+            }
+            Instruction::BranchNeImm { reg, value, offset } => {
+                branch!(rd(reg) != imm(*value), offset);
+            }
+            Instruction::BranchLtUImm { reg, value, offset } => {
+                branch!(rd(reg) < imm(*value), offset);
+            }
+            Instruction::BranchLeUImm { reg, value, offset } => {
+                branch!(rd(reg) <= imm(*value), offset);
+            }
+            Instruction::BranchGeUImm { reg, value, offset } => {
+                branch!(rd(reg) >= imm(*value), offset);
+            }
+            Instruction::BranchGtUImm { reg, value, offset } => {
+                branch!(rd(reg) > imm(*value), offset);
+            }
+            Instruction::BranchLtSImm { reg, value, offset } => {
+                branch!((rd(reg) as i64) < i64::from(*value), offset);
+            }
+            Instruction::BranchLeSImm { reg, value, offset } => {
+                branch!((rd(reg) as i64) <= i64::from(*value), offset);
+            }
+            Instruction::BranchGeSImm { reg, value, offset } => {
+                branch!((rd(reg) as i64) >= i64::from(*value), offset);
+            }
+            Instruction::BranchGtSImm { reg, value, offset } => {
+                branch!((rd(reg) as i64) > i64::from(*value), offset);
+            }
+            // Two-register branches compare `reg2 OP reg1` (the encoding puts
+            // reg2 in the low nibble, which the PVM reads as the first operand).
+            Instruction::BranchEq { reg1, reg2, offset } => {
+                branch!(rd(reg2) == rd(reg1), offset);
+            }
+            Instruction::BranchNe { reg1, reg2, offset } => {
+                branch!(rd(reg2) != rd(reg1), offset);
+            }
+            Instruction::BranchLtU { reg1, reg2, offset } => {
+                branch!(rd(reg2) < rd(reg1), offset);
+            }
+            Instruction::BranchGeU { reg1, reg2, offset } => {
+                branch!(rd(reg2) >= rd(reg1), offset);
+            }
+            Instruction::BranchLtS { reg1, reg2, offset } => {
+                branch!((rd(reg2) as i64) < (rd(reg1) as i64), offset);
+            }
+            Instruction::BranchGeS { reg1, reg2, offset } => {
+                branch!((rd(reg2) as i64) >= (rd(reg1) as i64), offset);
+            }
+        }
+
+        if let Some(target) = jump {
+            next = self.index_of(target)?;
+        }
+        if let Some((reg, value)) = set {
+            self.regs[reg as usize] = value;
+        }
+        self.next = next;
+        Ok(())
+    }
+}
+
+/// Run `program` with `args` to completion (or the first host call) with the
+/// [`DEFAULT_GAS`] budget.
+#[must_use]
+pub fn run_program(program: &SpiProgram, args: &[u8]) -> RunResult {
+    let mut interp = Interpreter::new(program, args);
+    let exit = interp.run();
+    let output = if exit == ExitReason::Halt {
+        interp.output()
+    } else {
+        Vec::new()
+    };
+    RunResult {
+        exit,
+        pc: interp.pc(),
+        output,
+        regs: interp.regs,
+        gas_used: interp.gas_used,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pvm::ProgramBlob;
+
+    fn program(instructions: Vec<Instruction>) -> SpiProgram {
+        SpiProgram::new(ProgramBlob::new(instructions)).with_ro_data(vec![0])
+    }
+
+    /// `jump r0` with r0 = exit address halts, returning `memory[r7..r7+r8]`.
+    fn halt() -> Instruction {
+        Instruction::JumpInd { reg: 0, offset: 0 }
+    }
+
+    #[test]
+    fn echoes_args_on_halt() {
+        let result = run_program(&program(vec![halt()]), &[1, 2, 3]);
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.output, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn trap_reports_its_pc() {
+        let prog = program(vec![
+            Instruction::LoadImm { reg: 2, value: 1 },
+            Instruction::Trap,
+        ]);
+        let trap_pc = Instruction::LoadImm { reg: 2, value: 1 }.encode().len() as u32;
+        let result = run_program(&prog, &[]);
+        assert_eq!(result.exit, ExitReason::Trap);
+        assert_eq!(result.pc, trap_pc);
+    }
+
+    #[test]
+    fn arithmetic_follows_pvm_semantics() {
+        let prog = program(vec![
+            Instruction::LoadImm { reg: 2, value: -1 },
+            Instruction::LoadImm { reg: 3, value: 0 },
+            // 0xFFFFFFFF + 1 wraps to 0 in 32 bits.
+            Instruction::AddImm32 {
+                dst: 4,
+                src: 2,
+                value: 1,
+            },
+            // Division by zero yields all ones, remainder yields the dividend.
+            Instruction::DivU64 {
+                dst: 5,
+                src1: 2,
+                src2: 3,
+            },
+            Instruction::RemU32 {
+                dst: 6,
+                src1: 2,
+                src2: 3,
+            },
+            halt(),
+        ]);
+        let result = run_program(&prog, &[]);
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.regs[4], 0);
+        assert_eq!(result.regs[5], u64::MAX);
+        assert_eq!(result.regs[6], u64::MAX);
+    }
+
+    #[test]
+    fn two_register_branch_compares_reg2_against_reg1() {
+        // BranchLtU { reg1: 2, reg2: 3 } branches when r3 < r2.
+        let skip = Instruction::LoadImm { reg: 4, value: 7 };
+        // Any small nonzero offset has the same encoded width as the real one.
+        let branch = Instruction::BranchLtU {
+            reg1: 2,
+            reg2: 3,
+            offset: 1,
+        };
+        let offset = (branch.encode().len() + skip.encode().len()) as i32;
+        let prog = program(vec![
+            Instruction::LoadImm { reg: 2, value: 5 },
+            Instruction::LoadImm { reg: 3, value: 1 },
+            Instruction::BranchLtU {
+                reg1: 2,
+                reg2: 3,
+                offset,
+            },
+            skip,
+            halt(),
+        ]);
+        let result = run_program(&prog, &[]);
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.regs[4], 0, "branch should have skipped the LoadImm");
+    }
+
+    #[test]
+    fn unmapped_and_read_only_accesses_fault() {
+        let load = run_program(
+            &program(vec![Instruction::LoadU32 {
+                dst: 2,
+                address: 0x2_0000,
+            }]),
+            &[],
+        );
+        assert_eq!(load.exit, ExitReason::PageFault(0x2_0000));
+
+        let store_ro = run_program(
+            &program(vec![Instruction::StoreImmU8 {
+                address: 0x1_0000,
+                value: 1,
+            }]),
+            &[],
+        );
+        assert_eq!(store_ro.exit, ExitReason::PageFault(0x1_0000));
+
+        let low = run_program(
+            &program(vec![Instruction::LoadU8 {
+                dst: 2,
+                address: 0x100,
+            }]),
+            &[],
+        );
+        assert!(matches!(low.exit, ExitReason::Panic(_)));
+    }
+
+    #[test]
+    fn every_sample_instruction_decodes_and_executes() {
+        for instr in crate::pvm::sample_instructions() {
+            let prog = program(vec![instr.clone(), Instruction::Trap]);
+            let decoded = SpiProgram::decode(&prog.encode()).expect("decode");
+            assert_eq!(
+                decoded.code().instructions(),
+                [instr.clone(), Instruction::Trap],
+                "blob round trip changed {instr:?}"
+            );
+            // Control flow depends on the surrounding code layout.
+            if instr.is_terminating() {
+                continue;
+            }
+
+            let mut interp = Interpreter::new(&decoded, &[]);
+            // Point every register well above the inaccessible low range so
+            // indirect accesses land somewhere that can be mapped.
+            for reg in 0..13u8 {
+                interp.set_reg(reg, 0x2_0000 + u64::from(reg) * 0x100);
+            }
+            let mut faults = 0;
+            let exit = loop {
+                match interp.run() {
+                    // Give the access a writable page and retry it.
+                    ExitReason::PageFault(page) if faults < 4 => {
+                        faults += 1;
+                        interp.memory.pages.remove(&(page / PAGE_SIZE));
+                        interp.memory.map(page, PAGE_SIZE, true);
+                    }
+                    ExitReason::HostCall(_) if matches!(instr, Instruction::Ecalli { .. }) => {}
+                    exit => break exit,
+                }
+            };
+            match exit {
+                ExitReason::Trap => {
+                    assert_eq!(interp.pc(), instr.encode().len() as u32, "{instr:?}");
+                }
+                // Absolute addresses in the low 64 KB and undefined opcodes
+                // panic by definition.
+                ExitReason::Panic(msg)
+                    if msg.contains("below") || matches!(instr, Instruction::Unknown { .. }) =>
+                {
+                    assert_eq!(interp.pc(), 0, "{instr:?}");
+                }
+                exit => panic!("{instr:?} stopped with {exit:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_gas_stops_an_infinite_loop() {
+        let prog = program(vec![Instruction::Jump { offset: 0 }]);
+        let mut interp = Interpreter::new(&prog, &[]).with_gas(10);
+        assert_eq!(interp.run(), ExitReason::OutOfGas);
+        assert_eq!(interp.gas_used(), 10);
+    }
+}
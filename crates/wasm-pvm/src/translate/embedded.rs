@@ -0,0 +1,73 @@
+// Compiler configuration embedded in the WASM binary.
+//
+// Toolchains that can't ship `--imports`/`--adapter` files alongside a module
+// can embed them as custom sections instead:
+//
+//   `wasm-pvm-imports`  import map text, same `name = action` format
+//   `wasm-pvm-adapter`  adapter module WAT source
+//
+// Explicit `CompileOptions` win: their import map entries override embedded
+// entries of the same name, and an explicit adapter replaces the embedded one.
+
+use wasmparser::{Parser, Payload};
+
+use super::{CompileOptions, parse_import_map};
+use crate::{Error, Result};
+
+const IMPORTS_SECTION: &str = "wasm-pvm-imports";
+const ADAPTER_SECTION: &str = "wasm-pvm-adapter";
+
+/// `options` with the module's embedded configuration merged in, or `None`
+/// when the module carries none.
+pub(super) fn apply_embedded_config(
+    wasm: &[u8],
+    options: &CompileOptions,
+) -> Result<Option<CompileOptions>> {
+    let mut imports = None;
+    let mut adapter = None;
+    for payload in Parser::new(0).parse_all(wasm) {
+        let Payload::CustomSection(custom) = payload? else {
+            continue;
+        };
+        let slot = match custom.name() {
+            IMPORTS_SECTION => &mut imports,
+            ADAPTER_SECTION => &mut adapter,
+            _ => continue,
+        };
+        if slot.is_some() {
+            return Err(Error::Unsupported(format!(
+                "duplicate '{}' custom section",
+                custom.name()
+            )));
+        }
+        let text = std::str::from_utf8(custom.data()).map_err(|_| {
+            Error::Unsupported(format!(
+                "'{}' custom section is not valid UTF-8",
+                custom.name()
+            ))
+        })?;
+        *slot = Some(text.to_string());
+    }
+
+    if imports.is_none() && adapter.is_none() {
+        return Ok(None);
+    }
+
+    let mut resolved = options.clone();
+    if let Some(text) = imports {
+        let mut import_map = parse_import_map(&text).map_err(|e| match e {
+            Error::ImportMap(msg) => {
+                Error::ImportMap(format!("'{IMPORTS_SECTION}' custom section, {msg}"))
+            }
+            other => other,
+        })?;
+        if let Some(explicit) = &options.import_map {
+            import_map.extend(explicit.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        resolved.import_map = Some(import_map);
+    }
+    if resolved.adapter.is_none() {
+        resolved.adapter = adapter;
+    }
+    Ok(Some(resolved))
+}
@@ -6,11 +6,13 @@
 )]
 
 pub mod adapter_merge;
+mod embedded;
 pub use crate::memory_layout;
 pub mod stats;
 pub mod wasm_module;
 
 use std::collections::BTreeMap;
+use std::io::Read;
 
 use crate::pvm::Instruction;
 use crate::{Error, Result, SpiProgram};
@@ -29,9 +31,68 @@ pub enum ImportAction {
     Ecalli(u32),
 }
 
+/// Parse import map text: one `name = action` mapping per line, where action
+/// is `trap`, `nop` or `ecalli:N` (also written `ecalli N`). Blank lines and
+/// `#` comments are ignored.
+///
+/// ```text
+/// # Comments start with #
+/// abort = trap
+/// console.log = nop
+/// read = ecalli:5
+/// write = ecalli 6
+/// ```
+pub fn parse_import_map(text: &str) -> Result<BTreeMap<String, ImportAction>> {
+    let mut map = BTreeMap::new();
+
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_num = line_num + 1;
+
+        let (name, action) = line.split_once('=').ok_or_else(|| {
+            Error::ImportMap(format!(
+                "line {line_num}: invalid format, expected 'name = action'"
+            ))
+        })?;
+
+        let action = match action.trim() {
+            "trap" => ImportAction::Trap,
+            "nop" => ImportAction::Nop,
+            other => {
+                // `ecalli:N` or `ecalli N`.
+                let Some(idx_str) = other
+                    .strip_prefix("ecalli")
+                    .and_then(|rest| {
+                        rest.strip_prefix(':')
+                            .or_else(|| rest.strip_prefix(char::is_whitespace))
+                    })
+                    .map(str::trim_start)
+                else {
+                    return Err(Error::ImportMap(format!(
+                        "line {line_num}: unknown action '{other}', expected 'trap', 'nop', or 'ecalli:N'"
+                    )));
+                };
+                let idx = idx_str.parse().map_err(|_| {
+                    Error::ImportMap(format!(
+                        "line {line_num}: invalid ecalli index '{idx_str}', expected a number"
+                    ))
+                })?;
+                ImportAction::Ecalli(idx)
+            }
+        };
+
+        map.insert(name.trim().to_string(), action);
+    }
+
+    Ok(map)
+}
+
 /// Flags to enable/disable individual compiler optimizations.
 /// All optimizations are enabled by default.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct OptimizationFlags {
     /// Run LLVM optimization passes (mem2reg, instcombine, simplifycfg, gvn, dce).
@@ -47,6 +108,9 @@ pub struct OptimizationFlags {
     pub shrink_wrap_callee_saves: bool,
     /// Eliminate SP-relative stores whose target offset is never loaded from.
     pub dead_store_elimination: bool,
+    /// Drop instructions no path from the function entry reaches, such as the
+    /// rest of a block after a call to an import mapped to `trap`.
+    pub dead_block_elimination: bool,
     /// Skip redundant `LoadImm`/`LoadImm64` when the register already holds the constant.
     pub constant_propagation: bool,
     /// Inline small functions at the LLVM IR level to eliminate call overhead.
@@ -69,6 +133,12 @@ pub struct OptimizationFlags {
     /// These are caller-saved and idle after the prologue; in non-leaf functions,
     /// they are invalidated after calls via arity-aware predicate.
     pub allocate_caller_saved_regs: bool,
+    /// Steer register-allocated values in leaf functions towards caller-saved
+    /// r5-r8, as non-leaf functions already do for values not live across a
+    /// call. Every r9-r12 a function touches needs a save/restore pair in its
+    /// prologue/epilogue; r5-r8 need none. Without this, values in leaf
+    /// functions take whichever register is free.
+    pub prefer_caller_saved_regs: bool,
     /// Skip stack stores at definition for register-allocated values (lazy spill).
     /// Values are only written to the stack when required (call clobber, return,
     /// phi reads, eviction). Requires `register_allocation` to be effective.
@@ -78,6 +148,12 @@ pub struct OptimizationFlags {
     /// default (225). Default: `Some(5)` — only tiny helpers are inlined.
     /// Only effective when `inlining` is `true`.
     pub inline_threshold: Option<u32>,
+    /// Fully unroll loops whose trip count is a compile-time constant of at
+    /// most this many iterations, removing their back-edge branches. Runs in
+    /// the LLVM pipeline after post-inline cleanup; LLVM's unroll size budget
+    /// still applies, so loops with large bodies are left rolled. `None`
+    /// (default) disables unrolling.
+    pub unroll: Option<u32>,
     /// Skip the zero-extension mask (`zext i32→i64` / `and x, 0xFFFFFFFF`) on
     /// values consumed exclusively as memory-access addresses. For any wasm
     /// memory smaller than 2 GB, sign- and zero-extension agree on every
@@ -103,6 +179,22 @@ pub struct OptimizationFlags {
     /// (`quicksort`, `scale_info::TypeInfo::type_info` etc.) where many type
     /// parameters share a body.
     pub mergefunc: bool,
+    /// Reorder instructions within each basic block so operands are computed
+    /// right before their consumer, shortening live ranges ahead of register
+    /// allocation. Memory accesses, calls and trapping ops keep their order.
+    pub scheduling: bool,
+    /// Emit one copy of functions that share a signature and a byte-identical
+    /// WASM body (locals and code). The duplicates get no code of their own:
+    /// direct calls, their jump-table entry and so `call_indirect` all land
+    /// on the first copy. Entry functions are never shared, and the pass is
+    /// skipped under `CompileOptions::function_sections`, where every
+    /// function must own a replaceable section.
+    pub dedup_functions: bool,
+    /// Run LLVM's `tailcallelim` pass before inlining: a function calling
+    /// itself in tail position branches back to its entry with the new
+    /// arguments instead of pushing a frame, so deep tail recursion runs in
+    /// constant stack.
+    pub tail_calls: bool,
 }
 
 impl Default for OptimizationFlags {
@@ -115,6 +207,7 @@ impl Default for OptimizationFlags {
             address_mask_elision: true,
             shrink_wrap_callee_saves: true,
             dead_store_elimination: true,
+            dead_block_elimination: true,
             constant_propagation: true,
             inlining: true,
             cross_block_cache: true,
@@ -123,17 +216,42 @@ impl Default for OptimizationFlags {
             aggressive_register_allocation: true,
             allocate_scratch_regs: true,
             allocate_caller_saved_regs: true,
+            prefer_caller_saved_regs: true,
             lazy_spill: true,
             inline_threshold: Some(5),
+            unroll: None,
             libcall_recognition: true,
             mergefunc: true,
+            scheduling: true,
+            dedup_functions: true,
+            tail_calls: true,
         }
     }
 }
 
+/// Generates a `with_<field>(bool)` setter for each boolean flag.
+macro_rules! flag_setters {
+    ($($field:ident => $setter:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("Sets [`", stringify!($field), "`](Self::", stringify!($field), ").")]
+            #[must_use]
+            pub fn $setter(mut self, enabled: bool) -> Self {
+                self.$field = enabled;
+                self
+            }
+        )*
+    };
+}
+
 impl OptimizationFlags {
+    /// Every optimization enabled; the same as [`Default`].
+    #[must_use]
+    pub fn all() -> Self {
+        Self::default()
+    }
+
     /// All optional optimizations disabled. Used for correctness differential
-    /// testing — running the same fixture twice (default vs `all_disabled`) and
+    /// testing — running the same fixture twice (default vs `none`) and
     /// comparing results catches miscompiles that any optimization introduces.
     ///
     /// `llvm_passes` stays enabled because the PVM backend cannot lower
@@ -141,7 +259,7 @@ impl OptimizationFlags {
     /// would make non-trivial WASM fail to compile, not just run slower.
     /// `inline_threshold` is unchanged (it only matters when `inlining` is on).
     #[must_use]
-    pub fn all_disabled() -> Self {
+    pub fn none() -> Self {
         Self {
             llvm_passes: true,
             peephole: false,
@@ -150,6 +268,7 @@ impl OptimizationFlags {
             address_mask_elision: false,
             shrink_wrap_callee_saves: false,
             dead_store_elimination: false,
+            dead_block_elimination: false,
             constant_propagation: false,
             inlining: false,
             cross_block_cache: false,
@@ -158,23 +277,148 @@ impl OptimizationFlags {
             aggressive_register_allocation: false,
             allocate_scratch_regs: false,
             allocate_caller_saved_regs: false,
+            prefer_caller_saved_regs: false,
             lazy_spill: false,
             inline_threshold: Some(5),
+            unroll: None,
             libcall_recognition: false,
             mergefunc: false,
+            scheduling: false,
+            dedup_functions: false,
+            tail_calls: false,
+        }
+    }
+
+    /// Alias for [`OptimizationFlags::none`].
+    #[must_use]
+    pub fn all_disabled() -> Self {
+        Self::none()
+    }
+
+    flag_setters! {
+        llvm_passes => with_llvm_passes,
+        peephole => with_peephole,
+        register_cache => with_register_cache,
+        icmp_branch_fusion => with_icmp_branch_fusion,
+        shrink_wrap_callee_saves => with_shrink_wrap_callee_saves,
+        dead_store_elimination => with_dead_store_elimination,
+        dead_block_elimination => with_dead_block_elimination,
+        constant_propagation => with_constant_propagation,
+        inlining => with_inlining,
+        cross_block_cache => with_cross_block_cache,
+        register_allocation => with_register_allocation,
+        fallthrough_jumps => with_fallthrough_jumps,
+        aggressive_register_allocation => with_aggressive_register_allocation,
+        allocate_scratch_regs => with_allocate_scratch_regs,
+        allocate_caller_saved_regs => with_allocate_caller_saved_regs,
+        prefer_caller_saved_regs => with_prefer_caller_saved_regs,
+        lazy_spill => with_lazy_spill,
+        address_mask_elision => with_address_mask_elision,
+        libcall_recognition => with_libcall_recognition,
+        mergefunc => with_mergefunc,
+        scheduling => with_scheduling,
+        dedup_functions => with_dedup_functions,
+        tail_calls => with_tail_calls,
+    }
+
+    /// Sets [`inline_threshold`](Self::inline_threshold).
+    #[must_use]
+    pub fn with_inline_threshold(mut self, threshold: Option<u32>) -> Self {
+        self.inline_threshold = threshold;
+        self
+    }
+
+    /// Sets [`unroll`](Self::unroll).
+    #[must_use]
+    pub fn with_unroll(mut self, max_trip_count: Option<u32>) -> Self {
+        self.unroll = max_trip_count;
+        self
+    }
+
+    /// The flags that actually take effect once dependencies between
+    /// optimizations are resolved. A flag whose prerequisite is off is
+    /// reported as off:
+    /// - `inlining`, `unroll`, `mergefunc` and `tail_calls` run inside the
+    ///   LLVM pipeline (`llvm_passes`);
+    /// - `cross_block_cache` propagates the per-block `register_cache`;
+    /// - the regalloc refinements (`aggressive_register_allocation`,
+    ///   `allocate_scratch_regs`, `allocate_caller_saved_regs`,
+    ///   `prefer_caller_saved_regs`, `lazy_spill`)
+    ///   need `register_allocation`.
+    ///
+    /// Module-dependent downgrades (e.g. `address_mask_elision` for memories
+    /// of 2 GB or more) are applied by the compiler and recorded in
+    /// [`CompileStats::optimizations`](stats::CompileStats::optimizations).
+    #[must_use]
+    pub fn effective(&self) -> Self {
+        let mut flags = self.clone();
+        flags.inlining &= flags.llvm_passes;
+        flags.mergefunc &= flags.llvm_passes;
+        flags.tail_calls &= flags.llvm_passes;
+        if !flags.llvm_passes {
+            flags.unroll = None;
         }
+        flags.cross_block_cache &= flags.register_cache;
+        let regalloc = flags.register_allocation;
+        flags.aggressive_register_allocation &= regalloc;
+        flags.allocate_scratch_regs &= regalloc;
+        flags.allocate_caller_saved_regs &= regalloc;
+        flags.prefer_caller_saved_regs &= regalloc;
+        flags.lazy_spill &= regalloc;
+        flags
+    }
+
+    /// Every boolean flag paired with its field name, in declaration order.
+    /// Used by reporting (CLI stats output) to list what ran.
+    #[must_use]
+    pub fn named(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("llvm_passes", self.llvm_passes),
+            ("peephole", self.peephole),
+            ("register_cache", self.register_cache),
+            ("icmp_branch_fusion", self.icmp_branch_fusion),
+            ("shrink_wrap_callee_saves", self.shrink_wrap_callee_saves),
+            ("dead_store_elimination", self.dead_store_elimination),
+            ("dead_block_elimination", self.dead_block_elimination),
+            ("constant_propagation", self.constant_propagation),
+            ("inlining", self.inlining),
+            ("cross_block_cache", self.cross_block_cache),
+            ("register_allocation", self.register_allocation),
+            ("fallthrough_jumps", self.fallthrough_jumps),
+            (
+                "aggressive_register_allocation",
+                self.aggressive_register_allocation,
+            ),
+            ("allocate_scratch_regs", self.allocate_scratch_regs),
+            (
+                "allocate_caller_saved_regs",
+                self.allocate_caller_saved_regs,
+            ),
+            ("prefer_caller_saved_regs", self.prefer_caller_saved_regs),
+            ("lazy_spill", self.lazy_spill),
+            ("address_mask_elision", self.address_mask_elision),
+            ("libcall_recognition", self.libcall_recognition),
+            ("mergefunc", self.mergefunc),
+            ("scheduling", self.scheduling),
+            ("dedup_functions", self.dedup_functions),
+            ("tail_calls", self.tail_calls),
+        ]
     }
 }
 
 /// Options for compilation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct CompileOptions {
     /// Mapping from import function names to actions.
     /// When provided, all imports (except known intrinsics like `host_call_N` and `pvm_ptr`)
     /// must have a mapping or compilation will fail with `UnresolvedImport`.
+    /// Entries here override same-named entries of an import map embedded in
+    /// the module's `wasm-pvm-imports` custom section.
     pub import_map: Option<BTreeMap<String, ImportAction>>,
     /// WAT source for an adapter module whose exports replace matching main imports.
-    /// Applied before the text-based import map, so the two compose.
+    /// Applied before the text-based import map, so the two compose. Takes the
+    /// place of an adapter embedded in a `wasm-pvm-adapter` custom section.
     pub adapter: Option<String>,
     /// Metadata blob to prepend to the SPI output.
     /// Typically contains the source filename and compiler version.
@@ -189,6 +433,102 @@ pub struct CompileOptions {
     /// a WASM module uses past the float wall. JAMs run normally if execution
     /// never reaches a float operator; otherwise they trap deterministically.
     pub trap_floats: bool,
+    /// Lower loads and stores whose alignment hint is below their natural
+    /// alignment (e.g. `i32.load align=1`) as single-byte `LoadIndU8` /
+    /// `StoreIndU8` sequences, for hosts where misaligned wide accesses are
+    /// slow or fault. Accesses with a natural alignment hint stay wide.
+    /// Off by default: every access uses the full-width instruction.
+    pub split_misaligned_accesses: bool,
+    /// Lower f32/f64 constants, loads, stores, sign operations, arithmetic
+    /// (`add`/`sub`/`mul`/`div`), comparisons and `promote`/`demote` to
+    /// integer code operating on the IEEE 754 bit patterns, instead of
+    /// rejecting them. Results are correctly rounded. The remaining float
+    /// operators (`min`/`max`, `sqrt`, rounding) are still rejected, or
+    /// trapped under `trap_floats`.
+    pub enable_soft_float: bool,
+    /// Start every function at a multiple of this many bytes in the code
+    /// section, padding the gap before it with `Trap`s. Helps profilers that
+    /// symbolicate by address range. `None` (or 0/1) packs functions tightly.
+    pub function_alignment: Option<usize>,
+    /// Lay each function out as a separately replaceable section for
+    /// hot-reload: every function starts and ends on a PVM page boundary
+    /// (4 KB, or `function_alignment` if larger), and direct calls jump
+    /// through the function's jump-table entry (`LoadImm` + `LoadImmJumpInd`)
+    /// instead of a code-relative `LoadImmJump`. Function `i` (local index)
+    /// owns jump-table entry `i`, so no code outside a function encodes its
+    /// offset. A replacement body must fit the same padded section and keep
+    /// the call-return jump-table indices of the code it replaces.
+    pub function_sections: bool,
+    /// Use only the first N callee-saved local registers (r9..r9+N-1) for
+    /// parameters, callee saves and register allocation; further parameters
+    /// go through the overflow area. Must be in `1..=abi::MAX_LOCAL_REGS`.
+    /// `None` uses all of them.
+    pub local_regs: Option<usize>,
+    /// Size of the stack region in bytes, below `memory_layout::STACK_SEGMENT_END`.
+    /// Function prologues trap once SP would drop under
+    /// `abi::stack_limit(stack_size)`, and the SPI header reserves the same
+    /// amount. Must be a non-zero multiple of the 4 KB PVM page size.
+    /// `None` uses `memory_layout::DEFAULT_STACK_SIZE` (64 KB).
+    pub stack_size: Option<u32>,
+    /// Registers the generated code must never read or write, for hosts whose
+    /// calling convention claims them. Only `abi::RESERVABLE_REGS` (r5, r6)
+    /// qualify; they are dropped from register allocation, and a function
+    /// whose lowering needs them as scratch is rejected.
+    pub reserved_registers: Vec<u8>,
+    /// Seed for breaking ties in heuristic passes (instruction scheduling,
+    /// block layout) where the cost model sees no difference. `0` (the
+    /// default) keeps the built-in choices; any other value perturbs them
+    /// reproducibly, for A/B experiments on the heuristics themselves.
+    pub seed: u64,
+    /// Emit position-independent data addressing: absolute addresses are
+    /// formed relative to `abi::PIC_BASE_REG` (r12), which the host
+    /// initializes with the relocation offset (0 for the standard layout).
+    /// Reserves r12, so `local_regs` defaults to and may not exceed 3, and
+    /// host calls are limited to 5 data arguments.
+    pub pic: bool,
+    /// Panic when an internal invariant check fails, instead of returning
+    /// [`Error::Internal`]. The checks guard compiler bugs (a violation means
+    /// the output would be corrupt), so the default suits embedders in
+    /// long-running hosts; set this to get a backtrace at the failing check.
+    pub panic_on_internal_error: bool,
+    /// Append a [`TrapTable`](crate::TrapTable) to the SPI metadata, mapping
+    /// the code offset of every `Trap` inside a function body to that WASM
+    /// function (named from the `name` section when present), so a host can
+    /// attribute a trapped PC without the compile-time stats.
+    pub trap_table: bool,
+    /// Run `wasmparser::validate` over the module before translating it.
+    /// On by default. Turning it off saves a full pass over modules already
+    /// validated upstream (e.g. by the toolchain that produced them), but
+    /// the compiler then trusts the input: malformed or ill-typed code fails
+    /// with whatever [`Error`] translation hits first (a parse error, an
+    /// operand stack underflow, an LLVM verifier failure) instead of a
+    /// validation message naming the offending instruction.
+    pub validate: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            import_map: None,
+            adapter: None,
+            metadata: Vec::new(),
+            optimizations: OptimizationFlags::default(),
+            max_memory_pages: None,
+            trap_floats: false,
+            split_misaligned_accesses: false,
+            enable_soft_float: false,
+            function_alignment: None,
+            function_sections: false,
+            local_regs: None,
+            stack_size: None,
+            reserved_registers: Vec::new(),
+            seed: 0,
+            pic: false,
+            panic_on_internal_error: false,
+            trap_table: false,
+            validate: true,
+        }
+    }
 }
 
 // Re-export register constants from abi module
@@ -210,6 +550,10 @@ pub struct IndirectCallFixup {
     pub jump_ind_instr: usize,
 }
 
+/// Alignment of a function section under `CompileOptions::function_sections`:
+/// one PVM page.
+const FUNCTION_SECTION_ALIGN: usize = 4096;
+
 /// `RO_DATA` region size is 64KB (0x10000 to 0x1FFFF)
 const RO_DATA_SIZE: usize = 64 * 1024;
 
@@ -230,10 +574,30 @@ pub fn compile_with_options(wasm: &[u8], options: &CompileOptions) -> Result<Spi
     Ok(program)
 }
 
+/// Compile a module read from `reader`, e.g. a file or a network stream.
+///
+/// The module is read incrementally into one internal buffer (the parser
+/// needs the whole module at once), so the caller doesn't have to hold its
+/// own copy alongside it. Otherwise the same as [`compile_with_options`].
+pub fn compile_reader<R: Read>(mut reader: R, options: &CompileOptions) -> Result<SpiProgram> {
+    let mut wasm = Vec::new();
+    reader.read_to_end(&mut wasm)?;
+    compile_with_options(&wasm, options)
+}
+
 pub fn compile_with_stats(
     wasm: &[u8],
     options: &CompileOptions,
 ) -> Result<(SpiProgram, stats::CompileStats)> {
+    // Fold in any import map / adapter embedded in the module's custom sections.
+    let embedded_options;
+    let options = if let Some(resolved) = embedded::apply_embedded_config(wasm, options)? {
+        embedded_options = resolved;
+        &embedded_options
+    } else {
+        options
+    };
+
     // Apply adapter merge if provided (produces a new WASM binary with fewer imports).
     let merged_wasm;
     let wasm = if let Some(adapter_wat) = &options.adapter {
@@ -243,7 +607,41 @@ pub fn compile_with_stats(
         wasm
     };
 
-    let mut module = WasmModule::parse(wasm)?;
+    let max_local_regs = if options.pic {
+        crate::abi::MAX_LOCAL_REGS - 1
+    } else {
+        crate::abi::MAX_LOCAL_REGS
+    };
+    let local_regs = options.local_regs.unwrap_or(max_local_regs);
+    if options.pic && local_regs > max_local_regs {
+        return Err(Error::Unsupported(format!(
+            "local_regs = {local_regs} conflicts with pic, which reserves r{} as the base register",
+            crate::abi::PIC_BASE_REG
+        )));
+    }
+    if let Some(stack_size) = options.stack_size
+        && (stack_size == 0 || !stack_size.is_multiple_of(4096))
+    {
+        return Err(Error::Unsupported(format!(
+            "stack_size = {stack_size} is not a non-zero multiple of the 4096-byte page size"
+        )));
+    }
+    if let Some(&reg) = options
+        .reserved_registers
+        .iter()
+        .find(|reg| !crate::abi::RESERVABLE_REGS.contains(reg))
+    {
+        return Err(Error::Unsupported(format!(
+            "r{reg} cannot be reserved: only r{} and r{} are free of fixed roles",
+            crate::abi::SCRATCH1,
+            crate::abi::SCRATCH2
+        )));
+    }
+    let mut module = if options.validate {
+        WasmModule::parse_with_local_regs(wasm, local_regs)?
+    } else {
+        WasmModule::parse_unvalidated(wasm, local_regs)?
+    };
 
     // Apply max_memory_pages override if provided.
     if let Some(max_pages) = options.max_memory_pages {
@@ -308,6 +706,11 @@ pub fn compile_with_stats(
 
     let result = compile_via_llvm(&module, options)?;
 
+    let mut applied_optimizations = options.optimizations.effective();
+    // Mirrors the gate in `llvm_backend::lower_function_inner`.
+    applied_optimizations.address_mask_elision &=
+        u64::from(module.max_memory_pages) * 65536 < (1u64 << 31);
+
     let spi_blob_bytes = result.program.encode().len();
 
     let compile_stats = stats::CompileStats {
@@ -326,12 +729,40 @@ pub fn compile_with_stats(
         ro_data_bytes: result.program.ro_data().len(),
         rw_data_bytes: result.program.rw_data().len(),
         heap_pages: result.program.heap_pages(),
-        stack_size: memory_layout::DEFAULT_STACK_SIZE,
+        stack_size: result.program.stack_size(),
+        exported_memory: module
+            .memory_export_name
+            .clone()
+            .map(|name| stats::ExportedMemory {
+                name,
+                base: module.wasm_memory_base,
+                initial_bytes: u64::from(module.memory_limits.initial_pages) * 65536,
+            }),
+        imported_memory: module.memory_import.clone().map(|(module_name, name)| {
+            stats::ImportedMemory {
+                module: module_name,
+                name,
+                base: module.wasm_memory_base,
+                initial_bytes: u64::from(module.memory_limits.initial_pages) * 65536,
+            }
+        }),
+        exported_globals: module
+            .global_exports
+            .iter()
+            .map(|(name, index)| stats::ExportedGlobal {
+                name: name.clone(),
+                index: *index,
+                address: module.global_offsets[*index],
+                width: module.global_widths[*index],
+                mutable: module.globals[*index].mutable,
+            })
+            .collect(),
         pvm_instructions: result.pvm_instructions,
         code_bytes: result.code_bytes,
         jump_table_entries: result.jump_table_entries,
         spi_blob_bytes,
         functions: result.function_stats,
+        optimizations: applied_optimizations,
     };
 
     Ok((result.program, compile_stats))
@@ -359,9 +790,13 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         options.optimizations.llvm_passes,
         options.optimizations.inlining,
         options.optimizations.inline_threshold,
+        options.optimizations.unroll,
         options.trap_floats,
+        options.split_misaligned_accesses,
+        options.enable_soft_float,
         options.optimizations.libcall_recognition,
         options.optimizations.mergefunc,
+        options.optimizations.tail_calls,
     )?;
 
     // Calculate RO_DATA offsets and lengths for passive data segments
@@ -409,6 +844,9 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         passive_ordinal,
         module.needs_memory_size_global,
     );
+    let stack_size = options
+        .stack_size
+        .unwrap_or(memory_layout::DEFAULT_STACK_SIZE);
     let ctx = LoweringContext {
         wasm_memory_base: module.wasm_memory_base,
         num_globals: module.globals.len(),
@@ -417,19 +855,26 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         global_widths: module.global_widths.clone(),
         param_overflow_base,
         param_overflow_reserved: module.needs_param_overflow,
+        local_regs: module.local_regs,
         function_signatures: module.function_signatures.clone(),
+        function_names: module.function_names.clone(),
         type_signatures: module.type_signatures.clone(),
         function_table: module.function_table.clone(),
         num_imported_funcs: module.num_imported_funcs as usize,
         imported_func_names: module.imported_func_names.clone(),
         initial_memory_pages: module.memory_limits.initial_pages,
         max_memory_pages: module.max_memory_pages,
-        stack_size: memory_layout::DEFAULT_STACK_SIZE,
+        stack_size,
         data_segment_offsets,
         data_segment_lengths,
         data_segment_length_addrs,
         wasm_import_map: options.import_map.clone(),
         optimizations: options.optimizations.clone(),
+        seed: options.seed,
+        pic: options.pic,
+        panic_on_internal_error: options.panic_on_internal_error,
+        function_sections: options.function_sections,
+        reserved_registers: options.reserved_registers.clone(),
     };
 
     // Phase 3: LLVM IR → PVM bytecode for each function
@@ -437,8 +882,31 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
     let mut all_call_fixups: Vec<(usize, CallFixup)> = Vec::new();
     let mut all_indirect_call_fixups: Vec<(usize, IndirectCallFixup)> = Vec::new();
     let mut function_offsets: Vec<usize> = vec![0; module.functions.len()];
-    let mut next_call_return_idx: usize = 0;
+    // `[start, end)` code range of each function body, in emission order
+    // (ascending), excluding the alignment padding in front of it.
+    let mut function_ranges: Vec<(usize, usize, usize)> =
+        Vec::with_capacity(module.functions.len());
+    // Under `function_sections` the function entries take the first jump-table
+    // slots, so a function's jump-ref is known before any code is laid out.
+    let mut next_call_return_idx: usize = if options.function_sections {
+        module.functions.len()
+    } else {
+        0
+    };
+    let mut function_alignment = options.function_alignment.filter(|&a| a > 1);
+    if options.function_sections {
+        function_alignment = Some(
+            function_alignment.map_or(FUNCTION_SECTION_ALIGN, |a| a.max(FUNCTION_SECTION_ALIGN)),
+        );
+    }
     let mut function_stats: Vec<stats::FunctionStats> = Vec::with_capacity(module.functions.len());
+    // Position of each emitted function's entry in `function_stats`.
+    let mut function_stats_idx: Vec<usize> = vec![0; module.functions.len()];
+    let shared_with = if options.optimizations.dedup_functions && !options.function_sections {
+        find_duplicate_functions(module)
+    } else {
+        vec![None; module.functions.len()]
+    };
 
     // Entry header: Jump to main (PC=0) + Trap or secondary Jump (PC=5).
     // When there's no secondary entry, we omit the Fallthrough padding (6 bytes instead of 10).
@@ -466,15 +934,31 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         }
     }
 
-    // Running total of `all_instructions.iter().map(|i| i.encode().len()).sum()`.
+    // Running total of `all_instructions.iter().map(Instruction::encoded_len).sum()`.
     // Updated incrementally each time we append instructions so per-function
     // `func_start_offset` lookups stay O(1) — re-summing on every iteration
     // was an O(N²) shape that made compile times unbounded on real-world
     // modules (see issue #225). Seed from the entry header pushed above so
     // the first function's offset is correct.
-    let mut current_code_bytes: usize = all_instructions.iter().map(|i| i.encode().len()).sum();
+    let mut current_code_bytes: usize = all_instructions.iter().map(Instruction::encoded_len).sum();
 
     for &local_func_idx in &emission_order {
+        // A duplicate reuses the code of its first copy, which has a lower
+        // index and is never an entry function, so it is already laid out.
+        if let Some(original) = shared_with[local_func_idx] {
+            function_offsets[local_func_idx] = function_offsets[original];
+            let mut shared = function_stats[function_stats_idx[original]].clone();
+            shared.name = module.local_function_display_name(local_func_idx);
+            shared.index = local_func_idx;
+            shared.instruction_count = 0;
+            shared.regalloc = stats::FunctionRegAllocStats::default();
+            shared.pre_dse_instructions = 0;
+            shared.pre_peephole_instructions = 0;
+            shared.shared_with = Some(original);
+            function_stats.push(shared);
+            continue;
+        }
+
         let global_func_idx = module.num_imported_funcs as usize + local_func_idx;
         let fn_name = format!("wasm_func_{global_func_idx}");
         let llvm_func = llvm_module
@@ -485,6 +969,12 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         let is_secondary = module.secondary_entry_local_idx == Some(local_func_idx);
         let is_entry = is_main || is_secondary;
 
+        // Padding follows the previous function's terminator (or the entry
+        // header), so it is never executed.
+        if let Some(align) = function_alignment {
+            pad_with_traps(&mut all_instructions, &mut current_code_bytes, align);
+        }
+
         function_offsets[local_func_idx] = current_code_bytes;
         let func_emission_start = all_instructions.len();
 
@@ -507,24 +997,46 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
                 offset: 8,
             });
 
-            // Call start function using LoadImmJump (combined load + jump).
+            // Call start function using LoadImmJump (combined load + jump),
+            // or through its jump-table entry under `function_sections`.
             let call_return_addr = ((next_call_return_idx + 1) * 2) as i32;
             next_call_return_idx += 1;
-            let current_instr_idx = all_instructions.len();
-            all_instructions.push(Instruction::LoadImmJump {
-                reg: RETURN_ADDR_REG,
-                value: call_return_addr,
-                offset: 0, // patched during fixup resolution
-            });
+            if options.function_sections {
+                all_instructions.push(Instruction::LoadImm {
+                    reg: crate::abi::TEMP1,
+                    value: ((start_local_idx + 1) * 2) as i32,
+                });
+                let current_instr_idx = all_instructions.len();
+                all_instructions.push(Instruction::LoadImmJumpInd {
+                    base: crate::abi::TEMP1,
+                    dst: RETURN_ADDR_REG,
+                    value: call_return_addr,
+                    offset: 0,
+                });
+                all_indirect_call_fixups.push((
+                    current_instr_idx,
+                    IndirectCallFixup {
+                        return_addr_instr: 0,
+                        jump_ind_instr: 0,
+                    },
+                ));
+            } else {
+                let current_instr_idx = all_instructions.len();
+                all_instructions.push(Instruction::LoadImmJump {
+                    reg: RETURN_ADDR_REG,
+                    value: call_return_addr,
+                    offset: 0, // patched during fixup resolution
+                });
 
-            all_call_fixups.push((
-                current_instr_idx,
-                CallFixup {
-                    target_func: start_local_idx as u32,
-                    return_addr_instr: 0,
-                    jump_instr: 0, // same instruction for LoadImmJump
-                },
-            ));
+                all_call_fixups.push((
+                    current_instr_idx,
+                    CallFixup {
+                        target_func: start_local_idx as u32,
+                        return_addr_instr: 0,
+                        jump_instr: 0, // same instruction for LoadImmJump
+                    },
+                ));
+            }
 
             // Restore r7 and r8.
             all_instructions.push(Instruction::LoadIndU64 {
@@ -577,9 +1089,11 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         }
 
         let ls = &translation.lowering_stats;
+        function_stats_idx[local_func_idx] = function_stats.len();
         function_stats.push(stats::FunctionStats {
             name: module.local_function_display_name(local_func_idx),
             index: local_func_idx,
+            code_offset: function_offsets[local_func_idx],
             instruction_count: translation.instructions.len(),
             frame_size: ls.frame_size,
             is_leaf: ls.is_leaf,
@@ -601,6 +1115,7 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
             },
             pre_dse_instructions: ls.pre_dse_instructions,
             pre_peephole_instructions: ls.pre_peephole_instructions,
+            shared_with: None,
         });
 
         all_instructions.extend(translation.instructions);
@@ -608,8 +1123,19 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         // Update the running byte counter to cover everything emitted this
         // iteration (entry-header trampoline pushes + the lowered body).
         for ins in &all_instructions[func_emission_start..] {
-            current_code_bytes += ins.encode().len();
+            current_code_bytes += ins.encoded_len();
         }
+        function_ranges.push((
+            function_offsets[local_func_idx],
+            current_code_bytes,
+            local_func_idx,
+        ));
+    }
+    // Close the last section too, so every function owns whole pages.
+    if options.function_sections
+        && let Some(align) = function_alignment
+    {
+        pad_with_traps(&mut all_instructions, &mut current_code_bytes, align);
     }
 
     // Phase 3.5: patch the entry-header jumps. The header uses
@@ -626,7 +1152,7 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
     if let Some(secondary_idx) = module.secondary_entry_local_idx {
         // Offset is relative to the second header instruction, which starts
         // right after the first 5-byte JumpFixed (the pc=5 ABI entry).
-        let i0_len = i32::try_from(all_instructions[0].encode().len())
+        let i0_len = i32::try_from(all_instructions[0].encoded_len())
             .map_err(|_| Error::Internal("entry-header length exceeds i32 range".to_string()))?;
         let secondary_offset = i32::try_from(function_offsets[secondary_idx])
             .map_err(|_| Error::Internal("secondary entry offset exceeds i32 range".to_string()))?
@@ -642,6 +1168,9 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         &all_call_fixups,
         &all_indirect_call_fixups,
         &function_offsets,
+        &module.function_names[module.num_imported_funcs as usize..],
+        options.function_sections,
+        options.panic_on_internal_error,
     )?;
 
     // The prefix-sum / running-counter optimisation (#225) relies on the
@@ -654,38 +1183,50 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
     // change patches a variable-length immediate after Phase 3.5,
     // `function_offsets` and `jump_table` silently desync; catch that here
     // instead of producing a corrupt JAM.
-    debug_assert_eq!(
-        all_instructions
-            .iter()
-            .map(|i| i.encode().len())
-            .sum::<usize>(),
-        current_code_bytes,
-        "post-patch instruction stream size differs from emission-time total — \
-         a patched instruction's encoded length changed, invalidating \
-         function_offsets / jump_table",
-    );
+    let patched_code_bytes: usize = all_instructions.iter().map(Instruction::encoded_len).sum();
+    Error::check_invariant(
+        patched_code_bytes == current_code_bytes,
+        options.panic_on_internal_error,
+        || {
+            format!(
+                "post-patch instruction stream size {patched_code_bytes} differs from \
+                 emission-time total {current_code_bytes} — a patched instruction's encoded \
+                 length changed, invalidating function_offsets / jump_table"
+            )
+        },
+    )?;
 
     // Phase 5: Build dispatch table for call_indirect.
     let mut ro_data = vec![0u8];
     if !module.function_table.is_empty() {
         ro_data.clear();
         for &func_idx in &module.function_table {
-            if func_idx == u32::MAX || (func_idx as usize) < module.num_imported_funcs as usize {
-                ro_data.extend_from_slice(&u32::MAX.to_le_bytes());
-                ro_data.extend_from_slice(&u32::MAX.to_le_bytes());
-            } else {
-                let local_func_idx = func_idx as usize - module.num_imported_funcs as usize;
-                let jump_ref = 2 * (func_entry_jump_table_base + local_func_idx + 1) as u32;
+            if let Some(jump_ref) = func_jump_ref(module, func_entry_jump_table_base, func_idx) {
                 ro_data.extend_from_slice(&jump_ref.to_le_bytes());
+                let local_func_idx = func_idx as usize - module.num_imported_funcs as usize;
                 let type_idx = *module
                     .function_type_indices
                     .get(local_func_idx)
                     .unwrap_or(&u32::MAX);
                 ro_data.extend_from_slice(&type_idx.to_le_bytes());
+            } else {
+                ro_data.extend_from_slice(&u32::MAX.to_le_bytes());
+                ro_data.extend_from_slice(&u32::MAX.to_le_bytes());
             }
         }
     }
 
+    // Funcref globals initialized with `ref.func` hold the same jump-ref the
+    // dispatch table stores. Imported functions have no entry; they stay null
+    // (0), so calling through them traps.
+    let mut global_init_values = module.global_init_values.clone();
+    for (value, func_ref) in global_init_values.iter_mut().zip(&module.global_func_refs) {
+        if let Some(func_idx) = *func_ref {
+            *value =
+                func_jump_ref(module, func_entry_jump_table_base, func_idx).map_or(0, i64::from);
+        }
+    }
+
     // Append passive data segments to RO_DATA.
     // NOTE: This loop must iterate data_segments in the same order as the offset
     // calculation loop above, since data_segment_offsets indices depend on it.
@@ -697,13 +1238,19 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
 
     // Capture stats before moving instructions into the blob.
     let pvm_instructions = all_instructions.len();
-    let code_bytes: usize = all_instructions.iter().map(|i| i.encode().len()).sum();
     let jump_table_entries = jump_table.len();
 
+    let mut metadata = options.metadata.clone();
+    if options.trap_table {
+        build_trap_table(module, &all_instructions, &function_ranges)
+            .append_to_metadata(&mut metadata);
+    }
+
     let blob = crate::pvm::ProgramBlob::new(all_instructions).with_jump_table(jump_table);
+    let code_bytes = blob.code_len();
     let rw_data_section = build_rw_data(
         &module.data_segments,
-        &module.global_init_values,
+        &global_init_values,
         &module.global_widths,
         module.memory_limits.initial_pages,
         module.wasm_memory_base,
@@ -716,13 +1263,16 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
         rw_data_section.len(),
         module.wasm_memory_base,
         module.memory_limits.initial_pages,
+        stack_size,
     )?;
 
     let program = SpiProgram::new(blob)
+        .with_stack_size(stack_size)
         .with_heap_pages(heap_pages)
         .with_ro_data(ro_data)
         .with_rw_data(rw_data_section)
-        .with_metadata(options.metadata.clone());
+        .with_metadata(metadata);
+    program.validate()?;
 
     Ok(CompilationOutput {
         program,
@@ -747,10 +1297,19 @@ fn compile_via_llvm(module: &WasmModule, options: &CompileOptions) -> Result<Com
 /// boundary of the initial WASM memory. Without it, PVM-in-PVM execution fails because
 /// the inner interpreter's page-fault handling at the exact heap boundary doesn't
 /// correctly propagate through the outer PVM.
+///
+/// `rw_data` itself must fit in that region: an active data segment placed
+/// past the initial linear memory would make the loader map `rw_data` over
+/// addresses the program treats as unallocated.
+///
+/// The mapped heap must end at or below `abi::stack_limit(stack_size)`, the
+/// lowest address the stack overflow checks let SP reach; otherwise the two
+/// regions would overlap.
 fn calculate_heap_pages(
     rw_data_len: usize,
     wasm_memory_base: i32,
     initial_pages: u32,
+    stack_size: u32,
 ) -> Result<u16> {
     use wasm_module::MIN_INITIAL_WASM_PAGES;
 
@@ -758,10 +1317,26 @@ fn calculate_heap_pages(
     let wasm_memory_initial_end = wasm_memory_base as usize + (initial_pages as usize) * 64 * 1024;
 
     let total_bytes = wasm_memory_initial_end - memory_layout::GLOBAL_MEMORY_BASE as usize;
+    if rw_data_len > total_bytes {
+        return Err(Error::SpiLayout(format!(
+            "rw_data is {rw_data_len} bytes but globals and {initial_pages} pages of linear \
+             memory at 0x{wasm_memory_base:X} end {total_bytes} bytes past 0x{:X}; \
+             a data segment lies outside the initial memory",
+            memory_layout::GLOBAL_MEMORY_BASE
+        )));
+    }
     let rw_pages = rw_data_len.div_ceil(4096);
     let total_pages = total_bytes.div_ceil(4096);
     let heap_pages = total_pages.saturating_sub(rw_pages) + 1;
 
+    let heap_end = memory_layout::GLOBAL_MEMORY_BASE as usize + (rw_pages + heap_pages) * 4096;
+    let stack_limit = crate::abi::stack_limit(stack_size) as u32 as usize;
+    if heap_end > stack_limit {
+        return Err(Error::SpiLayout(format!(
+            "heap ends at 0x{heap_end:X} but a {stack_size}-byte stack starts at 0x{stack_limit:X}"
+        )));
+    }
+
     u16::try_from(heap_pages).map_err(|_| {
         Error::Internal(format!(
             "heap size {heap_pages} pages exceeds u16::MAX ({}) — module too large",
@@ -892,6 +1467,14 @@ pub(crate) fn build_rw_data(
     Ok(rw_data)
 }
 
+/// Append `Trap`s until `code_bytes` is a multiple of `align`.
+fn pad_with_traps(instructions: &mut Vec<Instruction>, code_bytes: &mut usize, align: usize) {
+    while *code_bytes % align != 0 {
+        instructions.push(Instruction::Trap);
+        *code_bytes += 1;
+    }
+}
+
 /// Extract the pre-assigned jump-table index from a return-address load instruction.
 ///
 /// Call return addresses are pre-assigned as `(idx + 1) * 2` at emission time.
@@ -922,33 +1505,103 @@ fn return_addr_jump_table_idx(
     }
 }
 
+/// For each local function, the lowest-indexed function with the same
+/// signature and a byte-identical body, when that is another function.
+/// Identical bytes mean identical behavior: every index in the body resolves
+/// against the same module. Entry functions are lowered with a different
+/// prologue and epilogue, so they neither share nor are shared.
+fn find_duplicate_functions(module: &WasmModule) -> Vec<Option<usize>> {
+    let mut first_copy: std::collections::HashMap<(&wasmparser::FuncType, &[u8]), usize> =
+        std::collections::HashMap::new();
+    module
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(local_func_idx, body)| {
+            if local_func_idx == module.main_func_local_idx
+                || module.secondary_entry_local_idx == Some(local_func_idx)
+            {
+                return None;
+            }
+            let type_idx = *module.function_type_indices.get(local_func_idx)?;
+            let func_type = module.func_types.get(type_idx as usize)?;
+            let first = *first_copy
+                .entry((func_type, body.as_bytes()))
+                .or_insert(local_func_idx);
+            (first != local_func_idx).then_some(first)
+        })
+        .collect()
+}
+
+/// Dispatch-table jump-ref (`2 * (jump table index + 1)`) of a local
+/// function's entry, or `None` for imports and invalid indices.
+fn func_jump_ref(
+    module: &WasmModule,
+    func_entry_jump_table_base: usize,
+    func_idx: u32,
+) -> Option<u32> {
+    let local_func_idx = (func_idx as usize).checked_sub(module.num_imported_funcs as usize)?;
+    if func_idx == u32::MAX || local_func_idx >= module.functions.len() {
+        return None;
+    }
+    Some(2 * (func_entry_jump_table_base + local_func_idx + 1) as u32)
+}
+
+/// Attribute every `Trap` in `instructions` to the function whose code range
+/// (from `function_ranges`, ascending) contains it.
+fn build_trap_table(
+    module: &WasmModule,
+    instructions: &[Instruction],
+    function_ranges: &[(usize, usize, usize)],
+) -> crate::TrapTable {
+    let offsets = crate::pvm::instruction_offsets(instructions);
+    let mut table = crate::TrapTable::default();
+    let mut ranges = function_ranges.iter().peekable();
+    for (instr, &pc) in instructions.iter().zip(&offsets) {
+        while ranges.next_if(|&&(_, end, _)| end <= pc).is_some() {}
+        let Some(&&(start, _, local_func_idx)) = ranges.peek() else {
+            break;
+        };
+        if !matches!(instr, Instruction::Trap) || pc < start {
+            continue;
+        }
+        let function = module.num_imported_funcs + local_func_idx as u32;
+        table
+            .functions
+            .entry(function)
+            .or_insert_with(|| module.local_function_display_name(local_func_idx));
+        table.traps.push(crate::TrapEntry {
+            pc: pc as u32,
+            function,
+        });
+    }
+    table
+}
+
 fn resolve_call_fixups(
     instructions: &mut [Instruction],
     call_fixups: &[(usize, CallFixup)],
     indirect_call_fixups: &[(usize, IndirectCallFixup)],
     function_offsets: &[usize],
+    local_function_names: &[Option<String>],
+    func_entries_first: bool,
+    panic_on_internal_error: bool,
 ) -> Result<(Vec<u32>, usize)> {
-    // Pre-compute a prefix sum of instruction byte sizes. `byte_prefix[i]` is
-    // the sum of `instructions[0..i].encode().len()`. Patching `LoadImmJump`
+    // Pre-compute the byte offset of every instruction. `byte_prefix[i]` is
+    // the sum of `instructions[0..i].encoded_len()`. Patching `LoadImmJump`
     // only changes the `offset` field — a fixed 4-byte field per
     // `encode_one_reg_one_imm_one_off` — and `Jump.offset` is likewise fixed
     // 4 bytes, so these prefix sums stay valid throughout the loop below and
     // through the entry-header patches in `compile_via_llvm` afterwards.
     //
     // Before this precompute existed, the loop body re-summed
-    // `instructions[..=jump_idx].iter().map(|i| i.encode().len()).sum()` per
+    // `instructions[..=jump_idx].iter().map(|i| i.encoded_len()).sum()` per
     // fixup → O(N × M) where N = #fixups, M = #instructions. On Polkadot
     // runtimes that's ~10⁹ operations + Vec allocations (encode() returns a
     // fresh Vec each time just to count bytes), which made compile times
     // unbounded once the recent backend fixes let real-world modules reach
     // this point — see issue #225.
-    let mut byte_prefix: Vec<usize> = Vec::with_capacity(instructions.len() + 1);
-    byte_prefix.push(0);
-    let mut acc: usize = 0;
-    for ins in instructions.iter() {
-        acc += ins.encode().len();
-        byte_prefix.push(acc);
-    }
+    let byte_prefix = crate::pvm::instruction_offsets(instructions);
 
     // Count total call-return entries by finding the maximum pre-assigned index.
     // Entries are written at their pre-assigned slot so mixed direct/indirect
@@ -964,6 +1617,11 @@ fn resolve_call_fixups(
         num_call_returns = num_call_returns.max(idx + 1);
     }
 
+    // With `func_entries_first`, slots `0..function_offsets.len()` hold the
+    // function entries and call returns were numbered after them.
+    if func_entries_first {
+        num_call_returns = num_call_returns.max(function_offsets.len());
+    }
     let mut jump_table: Vec<u32> = vec![0u32; num_call_returns];
 
     // Call return addresses (LoadImmJump/LoadImm/LoadImmJumpInd values) are pre-assigned at emission time,
@@ -973,7 +1631,10 @@ fn resolve_call_fixups(
         let target_offset = function_offsets
             .get(fixup.target_func as usize)
             .ok_or_else(|| {
-                Error::Unsupported(format!("call to unknown function {}", fixup.target_func))
+                Error::Unsupported(format!(
+                    "call to unknown function {}",
+                    wasm_module::describe_function(local_function_names, fixup.target_func)
+                ))
             })?;
 
         let jump_idx = instr_base + fixup.jump_instr;
@@ -986,11 +1647,16 @@ fn resolve_call_fixups(
 
         // Verify pre-assigned jump table address matches actual index.
         let expected_addr = ((slot + 1) * 2) as i32;
-        debug_assert!(
+        Error::check_invariant(
             matches!(&instructions[jump_idx], Instruction::LoadImmJump { value, .. } if *value == expected_addr),
-            "pre-assigned jump table address mismatch: expected {expected_addr}, got {:?}",
-            &instructions[jump_idx]
-        );
+            panic_on_internal_error,
+            || {
+                format!(
+                    "pre-assigned jump table address mismatch: expected {expected_addr}, got {:?}",
+                    &instructions[jump_idx]
+                )
+            },
+        )?;
 
         // Patch the offset field of LoadImmJump.
         let jump_start_offset = byte_prefix[jump_idx];
@@ -1010,6 +1676,13 @@ fn resolve_call_fixups(
         jump_table[slot] = return_addr_offset as u32;
     }
 
+    if func_entries_first {
+        for (slot, &offset) in jump_table.iter_mut().zip(function_offsets) {
+            *slot = offset as u32;
+        }
+        return Ok((jump_table, 0));
+    }
+
     let func_entry_base = jump_table.len();
     for &offset in function_offsets {
         jump_table.push(offset as u32);
@@ -1033,12 +1706,13 @@ mod tests {
         // `llvm_passes` must stay on — the PVM backend cannot lower alloca.
         assert!(f.llvm_passes, "llvm_passes must stay enabled");
         // Every other boolean must be false. If a new optional optimization
-        // is added, update `all_disabled()` and this assertion together.
+        // is added, update `none()` and this assertion together.
         assert!(!f.peephole);
         assert!(!f.register_cache);
         assert!(!f.icmp_branch_fusion);
         assert!(!f.shrink_wrap_callee_saves);
         assert!(!f.dead_store_elimination);
+        assert!(!f.dead_block_elimination);
         assert!(!f.constant_propagation);
         assert!(!f.inlining);
         assert!(!f.cross_block_cache);
@@ -1047,9 +1721,61 @@ mod tests {
         assert!(!f.aggressive_register_allocation);
         assert!(!f.allocate_scratch_regs);
         assert!(!f.allocate_caller_saved_regs);
+        assert!(!f.prefer_caller_saved_regs);
         assert!(!f.lazy_spill);
         assert!(!f.libcall_recognition);
         assert!(!f.mergefunc);
+        assert!(!f.scheduling);
+        assert!(!f.dedup_functions);
+        assert!(!f.tail_calls);
+    }
+
+    #[test]
+    fn all_enables_every_flag_and_setters_touch_one_field() {
+        let all = OptimizationFlags::all();
+        assert!(all.named().iter().all(|&(_, on)| on));
+        assert_eq!(all, OptimizationFlags::default());
+        assert_eq!(OptimizationFlags::all_disabled(), OptimizationFlags::none());
+
+        let f = OptimizationFlags::all()
+            .with_scheduling(false)
+            .with_unroll(Some(4));
+        assert_eq!(
+            f,
+            OptimizationFlags {
+                scheduling: false,
+                unroll: Some(4),
+                ..OptimizationFlags::all()
+            }
+        );
+    }
+
+    #[test]
+    fn effective_drops_flags_whose_prerequisite_is_off() {
+        let f = OptimizationFlags {
+            llvm_passes: false,
+            register_cache: false,
+            register_allocation: false,
+            unroll: Some(4),
+            ..OptimizationFlags::default()
+        }
+        .effective();
+        assert!(!f.inlining);
+        assert_eq!(f.unroll, None);
+        assert!(!f.mergefunc);
+        assert!(!f.tail_calls);
+        assert!(!f.cross_block_cache);
+        assert!(!f.aggressive_register_allocation);
+        assert!(!f.allocate_scratch_regs);
+        assert!(!f.allocate_caller_saved_regs);
+        assert!(!f.prefer_caller_saved_regs);
+        assert!(!f.lazy_spill);
+        // Independent flags pass through untouched.
+        assert!(f.peephole);
+        assert!(f.scheduling);
+
+        let d = OptimizationFlags::default();
+        assert_eq!(d.effective().named(), d.named());
     }
 
     #[test]
@@ -1256,22 +1982,32 @@ mod tests {
         // total_bytes = 0x131000 - 0x30000 = 0x101000 = 1052672
         // total_pages = ceil(1052672 / 4096) = 257
         // rw_pages = 0, heap_pages = 257 + 1 = 258
-        let pages = super::calculate_heap_pages(0, 0x31000, 0).unwrap();
+        let pages =
+            super::calculate_heap_pages(0, 0x31000, 0, memory_layout::DEFAULT_STACK_SIZE).unwrap();
         assert_eq!(pages, 258);
     }
 
     #[test]
     fn heap_pages_reduced_by_rw_data_pages() {
         // Same scenario but with 8192 bytes of rw_data (2 pages)
-        let pages_no_rw = super::calculate_heap_pages(0, 0x31000, 0).unwrap();
-        let pages_with_rw = super::calculate_heap_pages(8192, 0x31000, 0).unwrap();
+        let pages_no_rw =
+            super::calculate_heap_pages(0, 0x31000, 0, memory_layout::DEFAULT_STACK_SIZE).unwrap();
+        let pages_with_rw =
+            super::calculate_heap_pages(8192, 0x31000, 0, memory_layout::DEFAULT_STACK_SIZE)
+                .unwrap();
         assert_eq!(pages_no_rw - pages_with_rw, 2);
     }
 
     #[test]
     fn heap_pages_saturates_at_one_for_large_rw_data() {
         // rw_data that covers more than total_pages still gets +1 headroom
-        let pages = super::calculate_heap_pages(2 * 1024 * 1024, 0x31000, 0).unwrap();
+        let pages = super::calculate_heap_pages(
+            2 * 1024 * 1024,
+            0x31000,
+            0,
+            memory_layout::DEFAULT_STACK_SIZE,
+        )
+        .unwrap();
         assert_eq!(pages, 1);
     }
 
@@ -1282,7 +2018,105 @@ mod tests {
         // total_bytes = 0x231000 - 0x30000 = 0x201000
         // total_pages = ceil(0x201000 / 4096) = 513
         // heap_pages = 513 + 1 = 514
-        let pages = super::calculate_heap_pages(0, 0x31000, 32).unwrap();
+        let pages =
+            super::calculate_heap_pages(0, 0x31000, 32, memory_layout::DEFAULT_STACK_SIZE).unwrap();
         assert_eq!(pages, 514);
     }
+
+    #[test]
+    fn heap_pages_cover_large_initial_memory() {
+        // 2048 initial pages (128 MB) must be mapped in full, with rw_data
+        // counting towards the total.
+        let base = 0x31000;
+        for rw_len in [0, 5000] {
+            let pages =
+                super::calculate_heap_pages(rw_len, base, 2048, memory_layout::DEFAULT_STACK_SIZE)
+                    .unwrap();
+            let mapped = (rw_len.div_ceil(4096) + usize::from(pages)) * 4096;
+            let needed =
+                base as usize + 2048 * 64 * 1024 - memory_layout::GLOBAL_MEMORY_BASE as usize;
+            assert!(
+                mapped >= needed,
+                "{mapped} < {needed} with rw_data {rw_len}"
+            );
+        }
+    }
+
+    #[test]
+    fn heap_pages_reject_overlap_with_stack() {
+        // 2048 initial pages (128 MB) end around 0x0803_1000; a stack this
+        // large would start below that.
+        let stack_size = 0xF800_0000;
+        assert!(memory_layout::stack_limit(stack_size) as u32 <= 0x0800_0000);
+        let err = super::calculate_heap_pages(0, 0x31000, 2048, stack_size).unwrap_err();
+        assert!(matches!(err, crate::Error::SpiLayout(_)), "{err}");
+        assert!(super::calculate_heap_pages(0, 0x31000, 2048, 0x1000_0000).is_ok());
+    }
+
+    #[test]
+    fn heap_pages_reject_rw_data_past_initial_memory() {
+        // 16 pages of linear memory at 0x31000 end 0x101000 bytes past 0x30000.
+        let end = 0x31000 - 0x30000 + 16 * 64 * 1024;
+        assert!(super::calculate_heap_pages(end, 0x31000, 16, 0x1_0000).is_ok());
+        let err = super::calculate_heap_pages(end + 1, 0x31000, 16, 0x1_0000).unwrap_err();
+        assert!(matches!(err, crate::Error::SpiLayout(_)), "{err}");
+        assert!(
+            err.to_string().contains("rw_data is 1052673 bytes"),
+            "{err}"
+        );
+    }
+
+    /// A call whose `LoadImmJump` disagrees with its pre-assigned jump-table
+    /// address (slot 0 expects 2, the jump carries 4).
+    fn mismatched_call() -> (Vec<crate::pvm::Instruction>, Vec<(usize, super::CallFixup)>) {
+        use crate::pvm::Instruction;
+        let instructions = vec![
+            Instruction::LoadImm { reg: 0, value: 2 },
+            Instruction::LoadImmJump {
+                reg: 0,
+                value: 4,
+                offset: 0,
+            },
+        ];
+        let fixup = super::CallFixup {
+            return_addr_instr: 0,
+            jump_instr: 1,
+            target_func: 0,
+        };
+        (instructions, vec![(0, fixup)])
+    }
+
+    #[test]
+    fn broken_invariant_is_an_internal_error_by_default() {
+        let (mut instructions, call_fixups) = mismatched_call();
+        let err = super::resolve_call_fixups(
+            &mut instructions,
+            &call_fixups,
+            &[],
+            &[0],
+            &[],
+            false,
+            false,
+        )
+        .expect_err("mismatch must be reported");
+        assert!(
+            matches!(&err, crate::Error::Internal(msg) if msg.contains("jump table address mismatch")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "jump table address mismatch")]
+    fn broken_invariant_panics_when_requested() {
+        let (mut instructions, call_fixups) = mismatched_call();
+        let _ = super::resolve_call_fixups(
+            &mut instructions,
+            &call_fixups,
+            &[],
+            &[0],
+            &[],
+            false,
+            true,
+        );
+    }
 }
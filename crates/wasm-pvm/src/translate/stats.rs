@@ -1,3 +1,5 @@
+use super::OptimizationFlags;
+
 /// Compilation statistics collected during the WASM-to-PVM pipeline.
 #[derive(Debug, Clone)]
 pub struct CompileStats {
@@ -21,6 +23,13 @@ pub struct CompileStats {
     pub rw_data_bytes: usize,
     pub heap_pages: u16,
     pub stack_size: u32,
+    /// Where the exported linear memory lives in PVM address space, if the
+    /// module exports one.
+    pub exported_memory: Option<ExportedMemory>,
+    /// Where an imported linear memory was mapped, if the module imports one.
+    pub imported_memory: Option<ImportedMemory>,
+    /// Where each exported global is stored, in export order.
+    pub exported_globals: Vec<ExportedGlobal>,
 
     // ── Output ──
     pub pvm_instructions: usize,
@@ -30,6 +39,52 @@ pub struct CompileStats {
 
     // ── Per-function ──
     pub functions: Vec<FunctionStats>,
+
+    // ── Optimizations ──
+    /// The optimization flags that actually applied: the requested flags with
+    /// dependencies resolved ([`OptimizationFlags::effective`]) and
+    /// module-dependent downgrades (address-mask elision on ≥2 GB memories).
+    pub optimizations: OptimizationFlags,
+}
+
+/// The module's exported linear memory, as placed in PVM address space.
+#[derive(Debug, Clone)]
+pub struct ExportedMemory {
+    /// Export name (conventionally `"memory"`).
+    pub name: String,
+    /// PVM address of WASM address 0 (equal to `wasm_memory_base`).
+    pub base: i32,
+    /// Initial size in bytes (initial pages × 64 KB).
+    pub initial_bytes: u64,
+}
+
+/// The module's imported linear memory. The host does not supply it
+/// separately; it is mapped onto the PVM region a defined memory would use.
+#[derive(Debug, Clone)]
+pub struct ImportedMemory {
+    /// Import module name (e.g. `"env"`).
+    pub module: String,
+    /// Import field name (conventionally `"memory"`).
+    pub name: String,
+    /// PVM address of WASM address 0 (equal to `wasm_memory_base`).
+    pub base: i32,
+    /// Initial size in bytes (the import's minimum pages × 64 KB).
+    pub initial_bytes: u64,
+}
+
+/// An exported global's storage slot in PVM address space. The slot holds the
+/// value little-endian; hosts can read it after execution halts.
+#[derive(Debug, Clone)]
+pub struct ExportedGlobal {
+    /// Export name.
+    pub name: String,
+    /// Index of the global in the module's own global section.
+    pub index: usize,
+    /// PVM address of the storage slot.
+    pub address: i32,
+    /// Slot width in bytes (4 for i32, 8 for i64 and funcref).
+    pub width: u32,
+    pub mutable: bool,
 }
 
 /// How an imported function was resolved.
@@ -44,6 +99,8 @@ pub struct ImportResolution {
 pub struct FunctionStats {
     pub name: String,
     pub index: usize,
+    /// Byte offset of the function's first instruction in the code section.
+    pub code_offset: usize,
     pub instruction_count: usize,
     pub frame_size: i32,
     pub is_leaf: bool,
@@ -53,6 +110,10 @@ pub struct FunctionStats {
     pub pre_dse_instructions: usize,
     /// Instructions before peephole (0 if peephole disabled).
     pub pre_peephole_instructions: usize,
+    /// Local index of the function whose code this one reuses under
+    /// `OptimizationFlags::dedup_functions`. Its `code_offset`, `frame_size`
+    /// and `is_leaf` are that function's; its counts are zero.
+    pub shared_with: Option<usize>,
 }
 
 /// Register allocation statistics for a single function.
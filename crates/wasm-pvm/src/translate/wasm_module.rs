@@ -52,9 +52,15 @@ pub struct WasmModule<'a> {
     /// Global variable types.
     pub globals: Vec<GlobalType>,
     /// Initial values of global variables, captured as i64 to cover both
-    /// `(global i32 ...)` and `(global i64 ...)`. Float/ref/v128 globals are
-    /// either rejected outright or recorded as zero (see `Self::parse`).
+    /// `(global i32 ...)` and `(global i64 ...)`. Funcref globals are recorded
+    /// as zero here (see `global_func_refs`); float/v128/externref globals are
+    /// rejected outright (see `Self::parse`).
     pub global_init_values: Vec<i64>,
+    /// Function index a funcref global is initialized to with `ref.func`,
+    /// parallel to `globals`. Its value is the function's dispatch-table
+    /// jump-ref, which is only known after linking, so the matching
+    /// `global_init_values` entry stays 0 until `compile_via_llvm` patches it.
+    pub global_func_refs: Vec<Option<u32>>,
     /// Byte width of each global's storage slot (4 for i32/f32, 8 for i64/f64).
     /// Parallels `globals` / `global_init_values`.
     pub global_widths: Vec<u32>,
@@ -64,8 +70,22 @@ pub struct WasmModule<'a> {
     pub global_offsets: Vec<i32>,
     /// Active data segments from the data section.
     pub data_segments: Vec<DataSegment>,
-    /// Memory limits parsed from the memory section.
+    /// Memory limits parsed from the memory section, or from the memory
+    /// import when the module imports its memory instead.
     pub memory_limits: MemoryLimits,
+    /// `(module, name)` of an imported linear memory. PVM has no separate
+    /// host memory, so the import is backed by the same region a defined
+    /// memory would occupy (at `wasm_memory_base`), and active data segments
+    /// are written into it as usual.
+    pub memory_import: Option<(String, String)>,
+    /// Name under which the module exports its linear memory (conventionally
+    /// `"memory"`), if it does. Hosts use it to locate the memory, which PVM
+    /// places at `wasm_memory_base`.
+    pub memory_export_name: Option<String>,
+    /// Exported module globals as `(export name, index into globals)`, in
+    /// export order. Hosts read them at the matching `global_offsets` address.
+    /// Re-exported imported globals have no storage slot and are not listed.
+    pub global_exports: Vec<(String, usize)>,
     /// Number of imported functions (precede local functions in global index space).
     pub num_imported_funcs: u32,
     /// Type indices for imported functions.
@@ -76,6 +96,11 @@ pub struct WasmModule<'a> {
     /// Populated from the WASM "name" custom section, falling back to export names.
     /// `None` means no name is known; callers should use a synthetic identifier.
     pub local_function_names: Vec<Option<String>>,
+    /// Function names from the WASM "name" custom section alone, indexed by
+    /// global function index (imports first, then locals). `None` where the
+    /// section names nothing or is absent. Used to make diagnostics point at
+    /// source names (see `describe_function`).
+    pub function_names: Vec<Option<String>>,
 
     // --- Derived data ---
     /// Local function index of the main entry point.
@@ -96,21 +121,29 @@ pub struct WasmModule<'a> {
     pub wasm_memory_base: i32,
     /// Maximum WASM memory pages available for memory.grow.
     pub max_memory_pages: u32,
-    /// Whether the module uses `memory.size`, `memory.grow`, or `memory.init`.
+    /// Whether the module uses `memory.size`, `memory.grow`, `memory.init`,
+    /// `memory.copy` or `memory.fill` (the last three bounds-check against the
+    /// current size).
     /// These are the only ops that read/write the compiler-managed memory-size
     /// global, so if none of them appear we skip emitting that 4-byte slot.
     pub needs_memory_size_global: bool,
     /// Whether the compiler must reserve a parameter-overflow area in the PVM
     /// data region. True iff any type signature in the module has more than
-    /// `MAX_LOCAL_REGS` (4) parameters — covering both local function
-    /// declarations and `call_indirect` type annotations, since the caller
-    /// writes args[4..] to the overflow area even when the caller's own
-    /// function is low-arity. When false, the 256-byte overflow reservation
+    /// `local_regs` parameters or more than one result — covering both local
+    /// function declarations and `call_indirect` type annotations, since the
+    /// caller writes args[local_regs..] to the overflow area even when the
+    /// caller's own function is low-arity (and extra results come back through
+    /// the same area). When false, the 256-byte overflow reservation
     /// is skipped and `wasm_memory_base` sits tight against the end of the
     /// globals/passive-length region (no 4KB alignment is applied; see
     /// `compute_wasm_memory_base` for the full layout rules).
     pub needs_param_overflow: bool,
 
+    /// Number of parameters passed in registers (r9..r9+N-1); the rest go
+    /// through the param-overflow area. `abi::MAX_LOCAL_REGS` unless
+    /// overridden via `CompileOptions::local_regs`.
+    pub local_regs: usize,
+
     /// Libcall recognition metadata. Populated during parsing by scanning
     /// for compiler-builtins functions whose bodies the LLVM frontend can
     /// replace with hand-crafted PVM-friendly implementations (`__multi3`,
@@ -169,14 +202,37 @@ impl<'a> WasmModule<'a> {
 
     /// Parse and validate a WASM binary, producing a `WasmModule` with all derived data.
     pub fn parse(wasm: &'a [u8]) -> Result<Self> {
+        Self::parse_with_local_regs(wasm, crate::abi::MAX_LOCAL_REGS)
+    }
+
+    /// Like [`parse`](Self::parse), but for a calling convention that passes
+    /// only `local_regs` parameters in registers (see
+    /// `CompileOptions::local_regs`). This moves the param-overflow threshold
+    /// and the maximum supported arity.
+    pub fn parse_with_local_regs(wasm: &'a [u8], local_regs: usize) -> Result<Self> {
         wasmparser::validate(wasm)
             .map_err(|e| Error::Internal(format!("WASM validation error: {e}")))?;
+        Self::parse_unvalidated(wasm, local_regs)
+    }
+
+    /// Like [`parse_with_local_regs`](Self::parse_with_local_regs), but
+    /// without running `wasmparser::validate` first. The caller vouches for
+    /// the module (see `CompileOptions::validate`); structural errors still
+    /// surface as parse errors, but type errors go unnoticed here.
+    pub fn parse_unvalidated(wasm: &'a [u8], local_regs: usize) -> Result<Self> {
+        if !(1..=crate::abi::MAX_LOCAL_REGS).contains(&local_regs) {
+            return Err(Error::Unsupported(format!(
+                "local_regs must be between 1 and {}, got {local_regs}",
+                crate::abi::MAX_LOCAL_REGS
+            )));
+        }
 
         let mut functions = Vec::new();
         let mut func_types: Vec<wasmparser::FuncType> = Vec::new();
         let mut function_type_indices = Vec::new();
         let mut globals: Vec<GlobalType> = Vec::new();
         let mut global_init_values: Vec<i64> = Vec::new();
+        let mut global_func_refs: Vec<Option<u32>> = Vec::new();
         let mut main_func_idx: Option<u32> = None;
         let mut secondary_entry_func_idx: Option<u32> = None;
         let mut start_func_idx: Option<u32> = None;
@@ -184,7 +240,13 @@ impl<'a> WasmModule<'a> {
         let mut table_elements: Vec<(u32, u32, Vec<u32>)> = Vec::new();
         let mut data_segments: Vec<DataSegment> = Vec::new();
         let mut memory_limits = MemoryLimits::default();
+        let mut memory_export_name: Option<String> = None;
+        let mut memory_import: Option<(String, String)> = None;
+        let mut global_exports: Vec<(String, usize)> = Vec::new();
         let mut num_imported_funcs: u32 = 0;
+        // Imported globals have no compile-time value; counted only so that
+        // `global.get` in constant expressions resolves to the right global.
+        let mut num_imported_globals: u32 = 0;
         let mut imported_func_type_indices: Vec<u32> = Vec::new();
         let mut imported_func_names: Vec<String> = Vec::new();
         // Raw (global_func_idx, name) pairs collected from the WASM "name" custom
@@ -192,9 +254,11 @@ impl<'a> WasmModule<'a> {
         // since the name section may precede the import section in pathological
         // modules and `num_imported_funcs` may not yet be known.
         let mut name_section_entries: Vec<(u32, String)> = Vec::new();
-        // First export name observed for each global function index (fallback when
-        // the name section is absent).
-        let mut export_name_by_global_idx: std::collections::BTreeMap<u32, String> =
+        // Preferred export name for each global function index (fallback when
+        // the name section is absent). A function exported under several names
+        // is named after its canonical entry export (`main`/`main2`), then an
+        // entry alias, then the first other name — independent of export order.
+        let mut export_name_by_global_idx: std::collections::BTreeMap<u32, (u8, String)> =
             std::collections::BTreeMap::new();
 
         for payload in Parser::new(0).parse_all(wasm) {
@@ -213,10 +277,22 @@ impl<'a> WasmModule<'a> {
                 Payload::ImportSection(reader) => {
                     for import in reader {
                         let import = import?;
-                        if let wasmparser::TypeRef::Func(type_idx) = import.ty {
-                            num_imported_funcs += 1;
-                            imported_func_type_indices.push(type_idx);
-                            imported_func_names.push(import.name.to_string());
+                        match import.ty {
+                            wasmparser::TypeRef::Func(type_idx) => {
+                                num_imported_funcs += 1;
+                                imported_func_type_indices.push(type_idx);
+                                imported_func_names.push(import.name.to_string());
+                            }
+                            wasmparser::TypeRef::Global(_) => num_imported_globals += 1,
+                            wasmparser::TypeRef::Memory(mem) => {
+                                memory_limits = MemoryLimits {
+                                    initial_pages: mem.initial as u32,
+                                    max_pages: mem.maximum.map(|m| m as u32),
+                                };
+                                memory_import =
+                                    Some((import.module.to_string(), import.name.to_string()));
+                            }
+                            _ => {}
                         }
                     }
                 }
@@ -228,25 +304,38 @@ impl<'a> WasmModule<'a> {
                 Payload::GlobalSection(reader) => {
                     for global in reader {
                         let g = global?;
-                        // Only integer globals are supported. Float globals (f32/f64)
-                        // would have their initial value silently zeroed by
-                        // `eval_const_global_init` and could subsequently be observed
-                        // via `i32.reinterpret_f32` / `i64.reinterpret_f64` or by
-                        // forwarding the global into another function, producing
-                        // wrong results — `--trap-floats` only traps float *operators*,
-                        // not the integer-typed plumbing around float globals.
-                        // v128 and ref-type globals have no lowering path at all.
+                        // Only integer and funcref globals are supported. Float
+                        // globals (f32/f64) would have their initial value silently
+                        // zeroed by `eval_const_global_init` and could subsequently be
+                        // observed via `i32.reinterpret_f32` / `i64.reinterpret_f64`
+                        // or by forwarding the global into another function,
+                        // producing wrong results — `--trap-floats` only traps float
+                        // *operators*, not the integer-typed plumbing around float
+                        // globals. v128 and externref globals have no lowering path.
                         match g.ty.content_type {
-                            wasmparser::ValType::I32 | wasmparser::ValType::I64 => {}
+                            wasmparser::ValType::I32 | wasmparser::ValType::I64 => {
+                                let env = ConstEnv {
+                                    num_imported_globals,
+                                    globals: &globals,
+                                    values: &global_init_values,
+                                };
+                                let value = eval_const_global_init(&g.init_expr, &env)?;
+                                global_init_values.push(value);
+                                global_func_refs.push(None);
+                            }
+                            wasmparser::ValType::Ref(ref_type) if is_func_ref(ref_type) => {
+                                // Null is 0: `call_ref` jumps through it and the
+                                // PVM faults on a dynamic jump to address 0.
+                                global_init_values.push(0);
+                                global_func_refs.push(eval_const_funcref_init(&g.init_expr)?);
+                            }
                             other => {
                                 return Err(Error::Unsupported(format!(
-                                    "WASM global type {other:?} is not supported (only i32 and i64 globals are supported)"
+                                    "WASM global type {other:?} is not supported (only i32, i64 and funcref globals are supported)"
                                 )));
                             }
                         }
                         globals.push(g.ty);
-                        let init_value = eval_const_global_init(&g.init_expr)?;
-                        global_init_values.push(init_value);
                     }
                 }
                 Payload::StartSection { func, .. } => {
@@ -275,7 +364,12 @@ impl<'a> WasmModule<'a> {
                         } = element.kind
                         {
                             let table_idx = table_index.unwrap_or(0);
-                            let offset = eval_const_i32(&offset_expr)?;
+                            let env = ConstEnv {
+                                num_imported_globals,
+                                globals: &globals,
+                                values: &global_init_values,
+                            };
+                            let offset = eval_const_i32(&offset_expr, &env)?;
                             let func_indices: Vec<u32> = match element.items {
                                 wasmparser::ElementItems::Functions(reader) => {
                                     reader.into_iter().collect::<std::result::Result<_, _>>()?
@@ -298,10 +392,17 @@ impl<'a> WasmModule<'a> {
                 Payload::ExportSection(reader) => {
                     for export in reader {
                         let export = export?;
+                        if export.kind == wasmparser::ExternalKind::Memory
+                            && memory_export_name.is_none()
+                        {
+                            memory_export_name = Some(export.name.to_string());
+                        }
+                        if export.kind == wasmparser::ExternalKind::Global
+                            && let Some(local_idx) = export.index.checked_sub(num_imported_globals)
+                        {
+                            global_exports.push((export.name.to_string(), local_idx as usize));
+                        }
                         if export.kind == wasmparser::ExternalKind::Func {
-                            export_name_by_global_idx
-                                .entry(export.index)
-                                .or_insert_with(|| export.name.to_string());
                             let is_imported = export.index < num_imported_funcs;
                             let is_main_name = matches!(
                                 export.name,
@@ -313,6 +414,17 @@ impl<'a> WasmModule<'a> {
                             );
                             let is_secondary_name =
                                 matches!(export.name, "main2" | "accumulate" | "accumulate_ext");
+                            let rank = match export.name {
+                                "main" | "main2" => 0,
+                                _ if is_main_name || is_secondary_name => 1,
+                                _ => 2,
+                            };
+                            let preferred = export_name_by_global_idx
+                                .entry(export.index)
+                                .or_insert_with(|| (rank, export.name.to_string()));
+                            if rank < preferred.0 {
+                                *preferred = (rank, export.name.to_string());
+                            }
                             if is_imported && (is_main_name || is_secondary_name) {
                                 return Err(Error::Internal(format!(
                                     "Entry export '{}' refers to imported function index {}",
@@ -352,7 +464,12 @@ impl<'a> WasmModule<'a> {
                                 memory_index: _,
                                 offset_expr,
                             } => {
-                                let offset = eval_const_i32(&offset_expr)? as u32;
+                                let env = ConstEnv {
+                                    num_imported_globals,
+                                    globals: &globals,
+                                    values: &global_init_values,
+                                };
+                                let offset = eval_const_i32(&offset_expr, &env)? as u32;
                                 data_segments.push(DataSegment {
                                     offset: Some(offset),
                                     data: data.data.to_vec(),
@@ -389,6 +506,14 @@ impl<'a> WasmModule<'a> {
             return Err(Error::NoExportedFunction);
         }
 
+        let mut function_names: Vec<Option<String>> =
+            vec![None; num_imported_funcs as usize + functions.len()];
+        for (global_idx, name) in &name_section_entries {
+            if let Some(slot) = function_names.get_mut(*global_idx as usize) {
+                *slot = Some(name.clone());
+            }
+        }
+
         // Convert main_func_idx from global to local function index
         let main_func_local_idx = if let Some(idx) = main_func_idx {
             idx as usize - num_imported_funcs as usize
@@ -404,7 +529,8 @@ impl<'a> WasmModule<'a> {
                 .map(|v| v as usize)
                 .or_else(|| {
                     tracing::warn!(
-                        "secondary entry function {idx} is an imported function, ignoring"
+                        "secondary entry function {} is an imported function, ignoring",
+                        describe_function(&function_names, idx)
                     );
                     None
                 })
@@ -414,7 +540,10 @@ impl<'a> WasmModule<'a> {
             idx.checked_sub(num_imported_funcs)
                 .map(|v| v as usize)
                 .or_else(|| {
-                    tracing::warn!("start function {idx} is an imported function, ignoring");
+                    tracing::warn!(
+                        "start function {} is an imported function, ignoring",
+                        describe_function(&function_names, idx)
+                    );
                     None
                 })
         });
@@ -460,36 +589,86 @@ impl<'a> WasmModule<'a> {
 
         // Reject signatures that would overflow the fixed 256-byte param
         // overflow window. Call lowering writes arg[i] at
-        // `param_overflow_base + (i - MAX_LOCAL_REGS) * 8` without bounds
-        // checking, so arity > MAX_TOTAL_PARAMS would corrupt the start of
-        // WASM linear memory. Enforce the cap here rather than at the emit
-        // site so both local-function and `call_indirect` type annotations
-        // are covered uniformly.
+        // `param_overflow_base + (i - local_regs) * 8` without bounds
+        // checking, so a larger arity would corrupt the start of WASM linear
+        // memory. Enforce the cap here rather than at the emit site so both
+        // local-function and `call_indirect` type annotations are covered
+        // uniformly.
+        let max_total_params = local_regs + memory_layout::PARAM_OVERFLOW_SIZE / 8;
         if let Some((idx, ft)) = func_types
             .iter()
             .enumerate()
-            .find(|(_, ft)| ft.params().len() > memory_layout::MAX_TOTAL_PARAMS)
+            .find(|(_, ft)| ft.params().len() > max_total_params)
         {
             return Err(Error::Internal(format!(
-                "type {idx} has {arity} params; maximum supported is {max} \
-                (4 in registers + 32 in the {bytes}-byte param-overflow area)",
+                "type {idx} has {arity} params; maximum supported is {max_total_params} \
+                ({local_regs} in registers + {overflow} in the {bytes}-byte param-overflow area)",
                 arity = ft.params().len(),
-                max = memory_layout::MAX_TOTAL_PARAMS,
+                overflow = memory_layout::PARAM_OVERFLOW_SIZE / 8,
                 bytes = memory_layout::PARAM_OVERFLOW_SIZE,
             )));
         }
 
+        // Results after the first go through the same window: the callee
+        // writes result `i` (i >= 1) to `param_overflow_base + (i - 1) * 8`
+        // just before returning and the caller reads it back right after the
+        // call. Entry functions still return one packed `ptr | len << 32`
+        // value, and imports return whatever the host leaves in r7, so a
+        // multi-value signature there has no lowering.
+        if let Some((idx, ft)) = func_types
+            .iter()
+            .enumerate()
+            .find(|(_, ft)| ft.results().len() > memory_layout::MAX_RESULTS)
+        {
+            return Err(Error::Unsupported(format!(
+                "type {idx} has {count} results; at most {max} multi-value results are \
+                 supported (1 in r7 + {overflow} in the param-overflow area)",
+                count = ft.results().len(),
+                max = memory_layout::MAX_RESULTS,
+                overflow = memory_layout::MAX_RESULTS - 1,
+            )));
+        }
+        let result_count = |type_idx: u32| {
+            func_types
+                .get(type_idx as usize)
+                .map_or(0, |ft| ft.results().len())
+        };
+        if let Some((idx, &type_idx)) = imported_func_type_indices
+            .iter()
+            .enumerate()
+            .find(|&(_, &type_idx)| result_count(type_idx) > 1)
+        {
+            return Err(Error::Unsupported(format!(
+                "import {} returns {} values; multi-value results of imported functions \
+                 are not supported",
+                imported_func_names[idx],
+                result_count(type_idx),
+            )));
+        }
+        for local_idx in std::iter::once(main_func_local_idx).chain(secondary_entry_local_idx) {
+            if let Some(&type_idx) = function_type_indices.get(local_idx)
+                && result_count(type_idx) > 1
+            {
+                return Err(Error::Unsupported(format!(
+                    "entry function {local_idx} returns {} values; multi-value results are \
+                     only supported for non-entry functions",
+                    result_count(type_idx),
+                )));
+            }
+        }
+
         // The parameter-overflow area is needed whenever any signature in the
-        // module carries more than `MAX_LOCAL_REGS` params — both for local
-        // function declarations (caller writes args[4..] before the call) *and*
-        // for `call_indirect` type annotations (caller writes args[4..] even if
-        // the caller function itself has ≤4 params). Scanning all types is a
-        // conservative superset: it may reserve 256 bytes of overflow for a
-        // module that declares a high-arity type it never calls, but avoids the
-        // real correctness bug of writing into unreserved memory.
+        // module carries more than `local_regs` params — both for local
+        // function declarations (caller writes the extra args before the call)
+        // *and* for `call_indirect` type annotations (caller writes them even if
+        // the caller function itself is low-arity) — or more than one result.
+        // Scanning all types is a conservative superset: it may reserve 256
+        // bytes of overflow for a module that declares a high-arity type it
+        // never calls, but avoids the real correctness bug of writing into
+        // unreserved memory.
         let needs_param_overflow = func_types
             .iter()
-            .any(|ft| ft.params().len() > crate::abi::MAX_LOCAL_REGS);
+            .any(|ft| ft.params().len() > local_regs || ft.results().len() > 1);
 
         // Compute per-global storage widths (4 B for i32/f32, 8 B for i64/f64),
         // then precompute absolute PVM addresses so backend lowering doesn't
@@ -500,6 +679,17 @@ impl<'a> WasmModule<'a> {
             .collect();
         let global_offsets =
             memory_layout::compute_global_offsets(&global_widths, needs_memory_size_global);
+        if needs_memory_size_global
+            && let Some(idx) = global_offsets
+                .iter()
+                .zip(&global_widths)
+                .position(|(&addr, &width)| memory_layout::overlaps_memory_size_slot(addr, width))
+        {
+            return Err(Error::Internal(format!(
+                "wasm_global_{idx} at 0x{:X} overlaps the memory-size slot",
+                global_offsets[idx]
+            )));
+        }
 
         // Compute WASM memory base
         let wasm_memory_base = memory_layout::compute_wasm_memory_base(
@@ -527,7 +717,7 @@ impl<'a> WasmModule<'a> {
                 *slot = Some(name.clone());
             }
         }
-        for (global_idx, name) in &export_name_by_global_idx {
+        for (global_idx, (_, name)) in &export_name_by_global_idx {
             if let Some(local_idx) = (*global_idx).checked_sub(num_imported_funcs)
                 && let Some(slot) = local_function_names.get_mut(local_idx as usize)
                 && slot.is_none()
@@ -557,14 +747,19 @@ impl<'a> WasmModule<'a> {
             function_type_indices,
             globals,
             global_init_values,
+            global_func_refs,
             global_widths,
             global_offsets,
             data_segments,
             memory_limits,
+            memory_import,
+            memory_export_name,
+            global_exports,
             num_imported_funcs,
             imported_func_type_indices,
             imported_func_names,
             local_function_names,
+            function_names,
             main_func_local_idx,
             has_secondary_entry,
             secondary_entry_local_idx,
@@ -576,11 +771,21 @@ impl<'a> WasmModule<'a> {
             max_memory_pages,
             needs_memory_size_global,
             needs_param_overflow,
+            local_regs,
             libcall_targets,
         })
     }
 }
 
+/// `names[idx]` as "`` `$name` (idx N) ``" for diagnostics, or just "N" when
+/// the name section does not name that function.
+pub(crate) fn describe_function(names: &[Option<String>], idx: u32) -> String {
+    match names.get(idx as usize) {
+        Some(Some(name)) => format!("`${name}` (idx {idx})"),
+        _ => idx.to_string(),
+    }
+}
+
 /// Locate compiler-builtins libcalls by name in the parsed WASM module.
 ///
 /// Returns global function indices (imports first, then locals) for each
@@ -735,7 +940,8 @@ fn find_first_global_get(body: &FunctionBody<'_>) -> Option<u32> {
 }
 
 /// Scan function bodies for any operator that reads/writes the compiler-managed
-/// memory-size global (`memory.size`, `memory.grow`, `memory.init`).
+/// memory-size global (`memory.size`, `memory.grow`, `memory.init`, `memory.copy`,
+/// `memory.fill`).
 fn scan_needs_memory_size_global(functions: &[FunctionBody<'_>]) -> Result<bool> {
     for body in functions {
         let mut reader = body
@@ -748,7 +954,9 @@ fn scan_needs_memory_size_global(functions: &[FunctionBody<'_>]) -> Result<bool>
             {
                 wasmparser::Operator::MemorySize { .. }
                 | wasmparser::Operator::MemoryGrow { .. }
-                | wasmparser::Operator::MemoryInit { .. } => return Ok(true),
+                | wasmparser::Operator::MemoryInit { .. }
+                | wasmparser::Operator::MemoryCopy { .. }
+                | wasmparser::Operator::MemoryFill { .. } => return Ok(true),
                 _ => {}
             }
         }
@@ -756,51 +964,134 @@ fn scan_needs_memory_size_global(functions: &[FunctionBody<'_>]) -> Result<bool>
     Ok(false)
 }
 
-fn eval_const_i32(expr: &wasmparser::ConstExpr) -> Result<i32> {
+/// Globals visible to a constant expression: imported globals first (no
+/// known value), then the module's own globals defined so far.
+struct ConstEnv<'a> {
+    num_imported_globals: u32,
+    globals: &'a [GlobalType],
+    values: &'a [i64],
+}
+
+/// Evaluate an integer constant expression. Besides single `i32.const` /
+/// `i64.const` literals this folds the extended-const proposal's `add`, `sub`
+/// and `mul` and `global.get` of an earlier immutable module global. i32
+/// results are returned sign-extended. Anything else (e.g. `global.get` of an
+/// imported global, whose value is only known at instantiation) is an error
+/// rather than a silent zero.
+fn eval_const_expr(expr: &wasmparser::ConstExpr, env: &ConstEnv) -> Result<i64> {
+    use wasmparser::Operator;
+
+    let mut stack: Vec<i64> = Vec::new();
     let mut reader = expr.get_binary_reader();
     while !reader.eof() {
-        match reader.read_operator()? {
-            wasmparser::Operator::I32Const { value } => return Ok(value),
-            wasmparser::Operator::End => break,
-            _ => {}
+        let op = reader.read_operator()?;
+        let value = match op {
+            Operator::End => break,
+            Operator::I32Const { value } => i64::from(value),
+            Operator::I64Const { value } => value,
+            Operator::GlobalGet { global_index } => {
+                let local_idx = global_index
+                    .checked_sub(env.num_imported_globals)
+                    .ok_or_else(|| {
+                        Error::Unsupported(format!(
+                            "constant expression reads imported global {global_index}, whose value is not known at compile time"
+                        ))
+                    })? as usize;
+                match (env.globals.get(local_idx), env.values.get(local_idx)) {
+                    (Some(ty), Some(&value)) if !ty.mutable => value,
+                    _ => {
+                        return Err(Error::Unsupported(format!(
+                            "constant expression reads global {global_index}, which is not an earlier immutable global"
+                        )));
+                    }
+                }
+            }
+            Operator::I32Add | Operator::I32Sub | Operator::I32Mul => {
+                let (lhs, rhs) = pop_const_operands(&mut stack)?;
+                let (lhs, rhs) = (lhs as i32, rhs as i32);
+                i64::from(match op {
+                    Operator::I32Add => lhs.wrapping_add(rhs),
+                    Operator::I32Sub => lhs.wrapping_sub(rhs),
+                    _ => lhs.wrapping_mul(rhs),
+                })
+            }
+            Operator::I64Add | Operator::I64Sub | Operator::I64Mul => {
+                let (lhs, rhs) = pop_const_operands(&mut stack)?;
+                match op {
+                    Operator::I64Add => lhs.wrapping_add(rhs),
+                    Operator::I64Sub => lhs.wrapping_sub(rhs),
+                    _ => lhs.wrapping_mul(rhs),
+                }
+            }
+            other => {
+                return Err(Error::Unsupported(format!(
+                    "unsupported operator in constant expression: {other:?}"
+                )));
+            }
+        };
+        stack.push(value);
+    }
+    match stack.as_slice() {
+        // Empty const-expr; treat as zero (no value produced).
+        [] => Ok(0),
+        [value] => Ok(*value),
+        _ => Err(Error::Internal(format!(
+            "constant expression left {} values on the stack",
+            stack.len()
+        ))),
+    }
+}
+
+fn pop_const_operands(stack: &mut Vec<i64>) -> Result<(i64, i64)> {
+    match (stack.pop(), stack.pop()) {
+        (Some(rhs), Some(lhs)) => Ok((lhs, rhs)),
+        _ => Err(Error::Internal(
+            "constant expression operator is missing operands".to_string(),
+        )),
+    }
+}
+
+fn eval_const_i32(expr: &wasmparser::ConstExpr, env: &ConstEnv) -> Result<i32> {
+    Ok(eval_const_expr(expr, env)? as i32)
+}
+
+/// Evaluate an integer global's initializer (see `eval_const_expr`).
+fn eval_const_global_init(expr: &wasmparser::ConstExpr, env: &ConstEnv) -> Result<i64> {
+    eval_const_expr(expr, env).map_err(|err| match err {
+        Error::Unsupported(msg) => {
+            Error::Unsupported(format!("unsupported global init expression: {msg}"))
         }
+        other => other,
+    })
+}
+
+/// Whether a reference type points at functions (`funcref` or a typed
+/// `(ref null $t)`), as opposed to `externref` and other abstract heap types.
+fn is_func_ref(ref_type: wasmparser::RefType) -> bool {
+    match ref_type.heap_type() {
+        wasmparser::HeapType::Abstract { ty, .. } => ty == wasmparser::AbstractHeapType::Func,
+        wasmparser::HeapType::Concrete(_) => true,
     }
-    Ok(0)
 }
 
-/// Evaluate a global's initializer constant-expression. Only single-literal
-/// `i32.const` / `i64.const` initializers are supported; legal-but-unsupported
-/// init-expressions (e.g. `global.get` referencing an imported const global,
-/// `ref.func`, `ref.null`, or extended-const arithmetic like
-/// `i32.const 5; i32.const 7; i32.add`) error out instead of silently producing
-/// the first literal and dropping the rest. wasmparser's `EXTENDED_CONST`
-/// feature is on by default, so multi-operator const-exprs *can* reach this
-/// function after validation — we have to inspect the trailing operator and
-/// require `End`, not just return on the first literal.
-fn eval_const_global_init(expr: &wasmparser::ConstExpr) -> Result<i64> {
+/// Evaluate a funcref global's initializer: `ref.func $f` yields `Some(f)`,
+/// `ref.null` yields `None`. Anything else (e.g. `global.get` of an imported
+/// funcref) is rejected, matching `eval_const_global_init`.
+fn eval_const_funcref_init(expr: &wasmparser::ConstExpr) -> Result<Option<u32>> {
     let mut reader = expr.get_binary_reader();
-    if reader.eof() {
-        // Empty const-expr; treat as zero (no value produced).
-        return Ok(0);
-    }
-    let value = match reader.read_operator()? {
-        wasmparser::Operator::I32Const { value } => i64::from(value),
-        wasmparser::Operator::I64Const { value } => value,
-        // `End` alone produces no value — zero is the only sensible default.
-        wasmparser::Operator::End => return Ok(0),
+    let func_ref = match reader.read_operator()? {
+        wasmparser::Operator::RefFunc { function_index } => Some(function_index),
+        wasmparser::Operator::RefNull { .. } => None,
         other => {
             return Err(Error::Unsupported(format!(
-                "unsupported global init expression: {other:?} (only i32.const and i64.const literals are supported)"
+                "unsupported funcref global init expression: {other:?} (only ref.func and ref.null are supported)"
             )));
         }
     };
-    // Reject extended-const-expression tails (e.g. an `i32.add` after a literal
-    // pair) instead of silently using only the first literal. The const-expr
-    // must consist of exactly one literal followed by `End`.
     match reader.read_operator()? {
-        wasmparser::Operator::End => Ok(value),
+        wasmparser::Operator::End => Ok(func_ref),
         other => Err(Error::Unsupported(format!(
-            "unsupported global init expression: trailing operator {other:?} after literal (only single-literal i32.const/i64.const initializers are supported)"
+            "unsupported funcref global init expression: trailing operator {other:?}"
         ))),
     }
 }
@@ -823,7 +1114,7 @@ fn eval_const_ref(expr: &wasmparser::ConstExpr) -> Option<u32> {
 
 #[cfg(test)]
 mod tests {
-    use super::WasmModule;
+    use super::{WasmModule, describe_function};
 
     #[test]
     fn main_export_name_overrides_alias() {
@@ -889,6 +1180,27 @@ mod tests {
         assert_eq!(module.secondary_entry_local_idx, Some(1));
     }
 
+    #[test]
+    fn function_exported_under_alias_is_named_after_main() {
+        for exports in [
+            r#"(export "run" (func 1)) (export "main" (func 1))"#,
+            r#"(export "main" (func 1)) (export "run" (func 1))"#,
+        ] {
+            // No `$` identifiers: those would populate the name section, which
+            // takes precedence over export names.
+            let wasm =
+                wat::parse_str(format!("(module (func) (func) {exports})")).expect("valid WAT");
+            let module = WasmModule::parse(&wasm).expect("valid module");
+
+            assert_eq!(module.main_func_local_idx, 1, "{exports}");
+            assert_eq!(
+                module.local_function_names[1].as_deref(),
+                Some("main"),
+                "{exports}"
+            );
+        }
+    }
+
     #[test]
     fn param_overflow_reserved_for_high_arity_indirect_call() {
         // Regression: a module whose only local function has ≤4 params but
@@ -982,6 +1294,47 @@ mod tests {
         assert!(module.needs_param_overflow);
     }
 
+    #[test]
+    fn multi_value_results_reserve_param_overflow() {
+        // Results after the first come back through the overflow window, so
+        // even a narrow multi-value signature needs it; MAX_RESULTS fills it.
+        let results: String = (0..super::memory_layout::MAX_RESULTS)
+            .map(|_| " i64")
+            .collect();
+        let wasm = wat::parse_str(format!(
+            r#"(module
+                (type $pair (func (result i32 i32)))
+                (type $maximal (func (result{results})))
+                (func $main (export "main") (param i32 i32) (result i64)
+                    (i64.const 0))
+            )"#
+        ))
+        .expect("valid WAT");
+
+        let module = WasmModule::parse(&wasm).expect("multi-value types must parse");
+        assert!(module.needs_param_overflow);
+    }
+
+    #[test]
+    fn overlapping_element_segments_apply_in_declaration_order() {
+        // Active segments are applied in order, so the second one overwrites
+        // slots 1..3 of the first.
+        let wasm = wat::parse_str(
+            r#"(module
+                (table 4 funcref)
+                (elem (i32.const 0) $a $b $c)
+                (elem (i32.const 1) $d $e)
+                (func $a) (func $b) (func $c) (func $d) (func $e)
+                (func $main (export "main") (param i32 i32) (result i64)
+                    (i64.const 0))
+            )"#,
+        )
+        .expect("valid WAT");
+
+        let module = WasmModule::parse(&wasm).expect("parse");
+        assert_eq!(module.function_table, vec![0, 3, 4, u32::MAX]);
+    }
+
     #[test]
     fn display_name_uses_name_section_entry() {
         // `$identifier` in WAT becomes a name-section entry; that wins over
@@ -1040,6 +1393,30 @@ mod tests {
         assert_eq!(module.local_function_display_name(99), "wasm_func_99");
     }
 
+    #[test]
+    fn function_names_cover_imports_and_locals() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (func $entry (export "main") (param i32 i32) (result i64) (i64.const 0))
+                (func (export "helper"))
+            )"#,
+        )
+        .expect("valid WAT");
+        let module = WasmModule::parse(&wasm).expect("valid module");
+        // Export names are not name-section entries.
+        assert_eq!(
+            module.function_names,
+            vec![Some("log".to_string()), Some("entry".to_string()), None]
+        );
+        assert_eq!(
+            describe_function(&module.function_names, 1),
+            "`$entry` (idx 1)"
+        );
+        assert_eq!(describe_function(&module.function_names, 2), "2");
+        assert_eq!(describe_function(&module.function_names, 99), "99");
+    }
+
     #[test]
     fn imported_entry_export_returns_error() {
         let wasm = wat::parse_str(
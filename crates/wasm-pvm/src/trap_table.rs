@@ -0,0 +1,212 @@
+// Side table mapping `Trap` instructions back to the WASM functions they were
+// compiled from, carried in the SPI metadata.
+
+use alloc::collections::BTreeMap;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::pvm::{ByteReader, encode_var_u32};
+use crate::{Error, Result};
+
+/// Marks the end of a metadata blob that carries a trap table.
+const TRAILER_MAGIC: &[u8; 4] = b"TRAP";
+
+/// Trailer after the encoded table: its length (u32 LE) and the magic.
+const TRAILER_LEN: usize = 4 + TRAILER_MAGIC.len();
+
+/// One `Trap` instruction of the code section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrapEntry {
+    /// Code offset of the `Trap`.
+    pub pc: u32,
+    /// WASM function index (imports included) of the function it belongs to.
+    pub function: u32,
+}
+
+/// Every `Trap` emitted inside a function body, with the function it came from.
+///
+/// Locations are function-granular: the backend emits most traps (division
+/// checks, `unreachable`, indirect-call guards) while lowering LLVM IR, where
+/// the WASM operator offset is no longer known. Entry-header and alignment
+/// padding `Trap`s lie outside every function and have no entry.
+///
+/// Built when `CompileOptions::trap_table` is set and appended to the SPI
+/// metadata, after any user metadata; [`TrapTable::from_metadata`] finds it
+/// again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrapTable {
+    /// Display name of every function that has at least one entry.
+    pub functions: BTreeMap<u32, String>,
+    /// Entries sorted by `pc`.
+    pub traps: Vec<TrapEntry>,
+}
+
+impl TrapTable {
+    /// The entry for the `Trap` at `pc`, if any.
+    #[must_use]
+    pub fn lookup(&self, pc: u32) -> Option<TrapEntry> {
+        self.traps
+            .binary_search_by_key(&pc, |entry| entry.pc)
+            .ok()
+            .map(|idx| self.traps[idx])
+    }
+
+    /// Display name of WASM function `function`, if the table names it.
+    #[must_use]
+    pub fn function_name(&self, function: u32) -> Option<&str> {
+        self.functions.get(&function).map(String::as_str)
+    }
+
+    /// Format: `[varint: function count]` then per function
+    /// `[varint: index][varint: name len][name]`, then `[varint: trap count]`
+    /// and per trap `[varint: pc delta from the previous entry][varint: function]`.
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(encode_var_u32(len_u32(self.functions.len())));
+        for (&index, name) in &self.functions {
+            out.extend(encode_var_u32(index));
+            out.extend(encode_var_u32(len_u32(name.len())));
+            out.extend(name.as_bytes());
+        }
+        out.extend(encode_var_u32(len_u32(self.traps.len())));
+        let mut prev_pc = 0;
+        for entry in &self.traps {
+            out.extend(encode_var_u32(entry.pc - prev_pc));
+            out.extend(encode_var_u32(entry.function));
+            prev_pc = entry.pc;
+        }
+        out
+    }
+
+    /// Decode a table produced by [`TrapTable::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = ByteReader::new(bytes, "trap table");
+        let mut functions = BTreeMap::new();
+        for _ in 0..reader.var_u32("function count")? {
+            let index = reader.var_u32("function index")?;
+            let name_len = reader.var_u32("name length")? as usize;
+            let name = core::str::from_utf8(reader.take(name_len, "function name")?)
+                .map_err(|_| reader.malformed("function name is not UTF-8"))?;
+            functions.insert(index, name.to_string());
+        }
+        let trap_count = reader.var_u32("trap count")?;
+        let mut traps = Vec::new();
+        let mut pc = 0u32;
+        for _ in 0..trap_count {
+            pc = pc
+                .checked_add(reader.var_u32("pc delta")?)
+                .ok_or_else(|| reader.malformed("pc overflows u32"))?;
+            traps.push(TrapEntry {
+                pc,
+                function: reader.var_u32("function")?,
+            });
+        }
+        reader.finish()?;
+        Ok(Self { functions, traps })
+    }
+
+    /// Append the encoded table to `metadata`, followed by a trailer that
+    /// [`TrapTable::from_metadata`] recognizes.
+    pub fn append_to_metadata(&self, metadata: &mut Vec<u8>) {
+        let table = self.encode();
+        metadata.extend(&table);
+        metadata.extend(len_u32(table.len()).to_le_bytes());
+        metadata.extend(TRAILER_MAGIC);
+    }
+
+    /// The table at the end of an SPI metadata blob, or `None` if the
+    /// metadata carries no trap table.
+    pub fn from_metadata(metadata: &[u8]) -> Result<Option<Self>> {
+        let Some(body_len) = metadata.len().checked_sub(TRAILER_LEN) else {
+            return Ok(None);
+        };
+        let (body, trailer) = metadata.split_at(body_len);
+        if &trailer[4..] != TRAILER_MAGIC {
+            return Ok(None);
+        }
+        let table_len = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let table = body
+            .len()
+            .checked_sub(table_len as usize)
+            .map(|start| &body[start..])
+            .ok_or_else(|| {
+                Error::Internal(format!(
+                    "malformed trap table: {table_len} bytes claimed, {body_len} available"
+                ))
+            })?;
+        Self::decode(table).map(Some)
+    }
+}
+
+/// Lengths in a table come from a code section whose size fits the SPI's
+/// `u32` code length.
+#[allow(clippy::cast_possible_truncation)]
+fn len_u32(len: usize) -> u32 {
+    len as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> TrapTable {
+        TrapTable {
+            functions: BTreeMap::from([(1, "div".to_string()), (3, "wasm_func_3".to_string())]),
+            traps: vec![
+                TrapEntry {
+                    pc: 12,
+                    function: 1,
+                },
+                TrapEntry {
+                    pc: 40,
+                    function: 1,
+                },
+                TrapEntry {
+                    pc: 300,
+                    function: 3,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn metadata_round_trip_keeps_the_user_prefix() {
+        let mut metadata = b"main.wasm (wasm-pvm 0.1)".to_vec();
+        sample().append_to_metadata(&mut metadata);
+        assert!(metadata.starts_with(b"main.wasm (wasm-pvm 0.1)"));
+
+        let table = TrapTable::from_metadata(&metadata)
+            .expect("decode")
+            .expect("table present");
+        assert_eq!(table, sample());
+        assert_eq!(
+            table.lookup(40),
+            Some(TrapEntry {
+                pc: 40,
+                function: 1
+            })
+        );
+        assert_eq!(table.lookup(41), None);
+        assert_eq!(table.function_name(3), Some("wasm_func_3"));
+    }
+
+    #[test]
+    fn plain_metadata_has_no_table() {
+        assert_eq!(TrapTable::from_metadata(b"").expect("decode"), None);
+        assert_eq!(
+            TrapTable::from_metadata(b"main.wasm (wasm-pvm 0.1)").expect("decode"),
+            None
+        );
+    }
+
+    #[test]
+    fn truncated_table_is_rejected() {
+        let mut metadata = Vec::new();
+        sample().append_to_metadata(&mut metadata);
+        assert!(TrapTable::from_metadata(&metadata[3..]).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Tests for `CompileStats.optimizations`: the optimization flags that
+//! actually applied, after dependencies between flags are resolved.
+
+use wasm_pvm::test_harness::wat_to_wasm;
+use wasm_pvm::{CompileOptions, OptimizationFlags, compile_with_stats};
+
+const WAT: &str = r#"
+    (module
+        (func (export "main") (param i32 i32) (result i64)
+            i64.const 0
+        )
+    )
+"#;
+
+/// Cross-block cache propagates the per-block register cache, so requesting
+/// it without `register_cache` must show up as disabled in the report.
+#[test]
+fn cross_block_cache_reported_off_without_register_cache() {
+    let wasm = wat_to_wasm(WAT).expect("WAT should parse");
+    let options = CompileOptions {
+        optimizations: OptimizationFlags {
+            register_cache: false,
+            cross_block_cache: true,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    };
+    let (_program, stats) = compile_with_stats(&wasm, &options).expect("compile");
+
+    assert!(!stats.optimizations.register_cache);
+    assert!(
+        !stats.optimizations.cross_block_cache,
+        "cross_block_cache cannot apply without register_cache"
+    );
+    // Unrelated flags are reported exactly as requested.
+    assert!(stats.optimizations.peephole);
+    assert!(stats.optimizations.register_allocation);
+}
+
+/// With default options every flag applies (small memory keeps address-mask
+/// elision on).
+#[test]
+fn default_flags_all_applied() {
+    let wasm = wat_to_wasm(WAT).expect("WAT should parse");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    let off: Vec<&str> = stats
+        .optimizations
+        .named()
+        .into_iter()
+        .filter(|(_, on)| !on)
+        .map(|(name, _)| name)
+        .collect();
+    assert!(off.is_empty(), "unexpectedly disabled: {off:?}");
+}
@@ -0,0 +1,68 @@
+//! Array element loads, `arr[idx]` → `base + (idx << log2(size))`.
+//!
+//! The peephole folds a constant scaled index, or a constant array base, into
+//! the load offset; a fully variable address must load the same element.
+
+use wasm_pvm::test_harness::*;
+
+/// Eight i32s at 0x100 (`10 * (i + 1)`); output is the word stored at 0.
+fn module(body: &str) -> String {
+    main_module_wat(
+        r#"
+            (memory 1)
+            (data (i32.const 0x100)
+                "\0a\00\00\00" "\14\00\00\00" "\1e\00\00\00" "\28\00\00\00"
+                "\32\00\00\00" "\3c\00\00\00" "\46\00\00\00" "\50\00\00\00")
+        "#,
+        &format!(
+            r#"
+                (local $base i32) (local $idx i32)
+                (local.set $base (i32.load offset=4 (local.get $p)))
+                (local.set $idx (i32.load (local.get $p)))
+                (i32.store (i32.const 0) {body})
+            "#
+        ),
+        4,
+    )
+}
+
+fn run(body: &str, idx: i32, base: i32) -> Vec<u8> {
+    let program = compile_wat(&module(body)).expect("compile");
+    let mut input = idx.to_le_bytes().to_vec();
+    input.extend(base.to_le_bytes());
+    let result = run_program(&program, &input);
+    assert_eq!(result.exit, ExitReason::Halt, "{body}");
+    result.output
+}
+
+#[test]
+fn constant_index_load() {
+    let body = "(i32.load (i32.add (local.get $base) (i32.shl (i32.const 5) (i32.const 2))))";
+    assert_eq!(run(body, 0, 0x100), 60i32.to_le_bytes());
+    assert_eq!(run(body, 0, 0x108), 80i32.to_le_bytes());
+}
+
+#[test]
+fn variable_index_constant_base_load() {
+    let body = "(i32.load (i32.add (i32.const 0x100) (i32.shl (local.get $idx) (i32.const 2))))";
+    for idx in 0..8 {
+        assert_eq!(
+            run(body, idx, 0),
+            (10 * (idx + 1)).to_le_bytes(),
+            "idx {idx}"
+        );
+    }
+}
+
+#[test]
+fn variable_index_and_base_load() {
+    let body =
+        "(i32.load offset=4 (i32.add (local.get $base) (i32.shl (local.get $idx) (i32.const 2))))";
+    for idx in 0..7 {
+        assert_eq!(
+            run(body, idx, 0x100),
+            (10 * (idx + 2)).to_le_bytes(),
+            "idx {idx}"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Hot/cold block layout: blocks that can only end in a trap are emitted after
+//! every other block, and a conditional branch whose `else` is cold is
+//! inverted so the likely `then` path falls through.
+//!
+//! The cold arm here is a float operator under `trap_floats`, which lowers to
+//! `llvm.trap` + `unreachable` and so survives LLVM's CFG simplification.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, OptimizationFlags};
+
+/// `main` checks `op(v, 100)` and stores `v * 3` on success; the failure arm
+/// traps. Output: the stored word.
+fn range_check_wat(op: &str) -> String {
+    format!(
+        r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $v i32)
+                (local.set $v (i32.load (local.get $p)))
+                (if ({op} (local.get $v) (i32.const 100))
+                    (then (i32.store (i32.const 0) (i32.mul (local.get $v) (i32.const 3))))
+                    (else (drop (f64.const 0))))
+                (i64.const 17179869184)
+            )
+        )
+    "#
+    )
+}
+
+fn options(fallthrough_jumps: bool) -> CompileOptions {
+    CompileOptions {
+        trap_floats: true,
+        optimizations: OptimizationFlags {
+            fallthrough_jumps,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    }
+}
+
+#[test]
+fn cold_error_block_is_emitted_after_hot_continuation() {
+    let program =
+        compile_wat_with_options(&range_check_wat("i32.lt_u"), &options(true)).expect("compile");
+    let instructions = extract_instructions(&program);
+
+    let ret_idx = instructions
+        .iter()
+        .position(|i| {
+            matches!(
+                i,
+                Instruction::JumpInd { .. } | Instruction::LoadImmJumpInd { .. }
+            )
+        })
+        .expect("main returns through a dynamic jump");
+    let trap_idx = instructions
+        .iter()
+        .rposition(|i| matches!(i, Instruction::Trap))
+        .expect("the error arm traps");
+    assert!(
+        trap_idx > ret_idx,
+        "error block (Trap at {trap_idx}) should follow the hot return (at {ret_idx}):\n{instructions:#?}"
+    );
+}
+
+/// Inverting the branch must preserve semantics for every comparison.
+#[test]
+fn inverted_range_checks_keep_semantics() {
+    let cases: [(&str, i32, bool); 8] = [
+        ("i32.lt_u", 14, true),
+        ("i32.lt_u", 200, false),
+        ("i32.ge_u", 200, true),
+        ("i32.le_s", -5, true),
+        ("i32.gt_s", -5, false),
+        ("i32.ne", 100, false),
+        ("i32.eq", 100, true),
+        ("i32.gt_u", -1, true),
+    ];
+    for (op, v, in_range) in cases {
+        for fallthrough in [true, false] {
+            let program = compile_wat_with_options(&range_check_wat(op), &options(fallthrough))
+                .expect("compile");
+            let result = run_program(&program, &v.to_le_bytes());
+            if in_range {
+                assert_eq!(result.exit, ExitReason::Halt, "{op} {v}");
+                assert_eq!(result.output, v.wrapping_mul(3).to_le_bytes(), "{op} {v}");
+            } else {
+                assert_eq!(result.exit, ExitReason::Trap, "{op} {v}");
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Block types given by a type index: `block`, `if` and `loop` take their
+//! parameters from the operand stack and a `br` to a loop carries them back
+//! to the header.
+
+use wasm_pvm::test_harness::*;
+
+const BLOCK_PARAMS: &str = include_str!("../../../tests/fixtures/wat/block-params.jam.wat");
+
+fn run(op: u32, a: u32, b: u32) -> u32 {
+    let program = compile_wat(BLOCK_PARAMS).expect("compile");
+    let args: Vec<u8> = [op, a, b].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let result = run_program(&program, &args);
+    assert_eq!(result.exit, ExitReason::Halt, "op {op} ({a}, {b})");
+    u32::from_le_bytes(result.output.try_into().expect("4 bytes"))
+}
+
+#[test]
+fn block_consumes_its_params_in_order() {
+    assert_eq!(run(0, 10, 3), 7);
+    assert_eq!(run(0, 3, 10), 3u32.wrapping_sub(10));
+    assert_eq!(run(1, 10, 3), 7);
+}
+
+#[test]
+fn both_if_arms_start_from_the_params() {
+    assert_eq!(run(2, 10, 3), 7);
+    assert_eq!(run(2, 3, 10), 13);
+}
+
+#[test]
+fn if_without_else_passes_params_through() {
+    assert_eq!(run(3, 10, 3), 20);
+    assert_eq!(run(3, 3, 10), 3);
+}
+
+#[test]
+fn loop_params_are_carried_by_br() {
+    for (a, b) in [(0, 0), (5, 4), (100, 10)] {
+        assert_eq!(run(4, a, b), a + b * (b + 1) / 2, "({a}, {b})");
+    }
+}
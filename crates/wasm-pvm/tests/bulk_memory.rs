@@ -0,0 +1,91 @@
+//! `memory.copy` (memmove semantics) and `memory.fill`, checked against a
+//! reference model of the 32 bytes they touch, plus their bounds checks.
+
+use wasm_pvm::test_harness::*;
+
+/// Bytes 0..32 start as 1..=32. Args are `op, a, b, len` as i32s: op 0 is
+/// `memory.copy(dst=a, src=b, len)`, op 1 is `memory.fill(dst=a, val=b, len)`.
+/// Returns the 32 bytes at address 0.
+const BULK_WAT: &str = r#"
+    (module
+        (memory 1)
+        (data (i32.const 0)
+            "\01\02\03\04\05\06\07\08\09\0a\0b\0c\0d\0e\0f\10"
+            "\11\12\13\14\15\16\17\18\19\1a\1b\1c\1d\1e\1f\20")
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (if (i32.load (local.get $p))
+                (then
+                    (memory.fill
+                        (i32.load offset=4 (local.get $p))
+                        (i32.load offset=8 (local.get $p))
+                        (i32.load offset=12 (local.get $p))))
+                (else
+                    (memory.copy
+                        (i32.load offset=4 (local.get $p))
+                        (i32.load offset=8 (local.get $p))
+                        (i32.load offset=12 (local.get $p)))))
+            ;; 32 bytes at address 0.
+            (i64.const 137438953472)
+        )
+    )
+"#;
+
+fn initial() -> Vec<u8> {
+    (1..=32).collect()
+}
+
+fn run(op: u32, a: u32, b: u32, len: u32) -> RunResult {
+    let program = compile_wat(BULK_WAT).expect("compile");
+    let args: Vec<u8> = [op, a, b, len]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    run_program(&program, &args)
+}
+
+#[test]
+fn memory_copy_matches_memmove() {
+    // (dst, src, len): disjoint, overlapping in both directions, word-sized
+    // bulk plus byte tails, and an in-place copy.
+    for (dst, src, len) in [
+        (16, 0, 11),
+        (0, 20, 12),
+        (3, 0, 20),
+        (0, 3, 20),
+        (9, 8, 23),
+        (8, 9, 23),
+        (5, 5, 17),
+        (4, 0, 0),
+    ] {
+        let mut expected = initial();
+        expected.copy_within(src as usize..(src + len) as usize, dst as usize);
+
+        let result = run(0, dst, src, len);
+        assert_eq!(result.exit, ExitReason::Halt, "copy {dst} <- {src} x{len}");
+        assert_eq!(result.output, expected, "copy {dst} <- {src} x{len}");
+    }
+}
+
+#[test]
+fn memory_fill_writes_the_low_byte_of_the_value() {
+    for (dst, val, len) in [(0, 0xAB, 32), (3, 0x1FF, 19), (7, 0, 1), (2, 0x55, 0)] {
+        let mut expected = initial();
+        expected[dst as usize..(dst + len) as usize].fill(val.to_le_bytes()[0]);
+
+        let result = run(1, dst, val, len);
+        assert_eq!(result.exit, ExitReason::Halt, "fill {dst} x{len}");
+        assert_eq!(result.output, expected, "fill {dst} x{len}");
+    }
+}
+
+#[test]
+fn memory_fill_traps_past_the_memory_end() {
+    // Ending exactly at the last byte, or empty at the end, is in bounds.
+    assert_eq!(run(1, 65528, 0xEE, 8).exit, ExitReason::Halt);
+    assert_eq!(run(1, 65536, 0xEE, 0).exit, ExitReason::Halt);
+
+    assert_eq!(run(1, 65532, 0xEE, 8).exit, ExitReason::Trap);
+    assert_eq!(run(1, 65537, 0xEE, 0).exit, ExitReason::Trap);
+    // dst + len wraps around 2^32.
+    assert_eq!(run(1, 0xFFFF_FFF8, 0xEE, 16).exit, ExitReason::Trap);
+}
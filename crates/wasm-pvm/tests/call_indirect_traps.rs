@@ -0,0 +1,69 @@
+//! `call_indirect` traps on separate paths for an empty (or imported) table
+//! slot and for a signature mismatch, leaving a distinct code in r7.
+
+use std::collections::BTreeMap;
+
+use wasm_pvm::abi::{CALL_INDIRECT_NULL_TRAP, CALL_INDIRECT_SIGNATURE_TRAP, RETURN_VALUE_REG};
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{ImportAction, SpiProgram};
+
+const CALL_INDIRECT_TRAPS: &str =
+    include_str!("../../../tests/fixtures/wat/call-indirect-traps.jam.wat");
+
+fn run(program: &SpiProgram, slot: u32, value: u32) -> RunResult {
+    let args: Vec<u8> = [slot, value].iter().flat_map(|v| v.to_le_bytes()).collect();
+    run_program(program, &args)
+}
+
+fn trap_code(result: &RunResult) -> i32 {
+    assert_eq!(result.exit, ExitReason::Trap);
+    i32::try_from(result.regs[RETURN_VALUE_REG as usize]).expect("small trap code")
+}
+
+#[test]
+fn null_slot_and_signature_mismatch_trap_separately() {
+    let program = compile_wat(CALL_INDIRECT_TRAPS).expect("compile");
+
+    let ok = run(&program, 0, 21);
+    assert_eq!(ok.exit, ExitReason::Halt);
+    assert_eq!(ok.output, 42u32.to_le_bytes());
+
+    let mismatch = run(&program, 1, 21);
+    let null = run(&program, 2, 21);
+    assert_eq!(trap_code(&mismatch), CALL_INDIRECT_SIGNATURE_TRAP);
+    assert_eq!(trap_code(&null), CALL_INDIRECT_NULL_TRAP);
+    assert_ne!(mismatch.pc, null.pc);
+}
+
+#[test]
+fn imported_function_slot_traps_as_null() {
+    let wat = r#"
+        (module
+            (import "env" "log" (func $log (param i32) (result i32)))
+            (type $unary (func (param i32) (result i32)))
+            (memory 1)
+            (table 2 funcref)
+            (elem (i32.const 0) $log $double)
+            (func $double (type $unary)
+                (i32.mul (local.get 0) (i32.const 2))
+            )
+            (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+                (i32.store (i32.const 0)
+                    (call_indirect (type $unary)
+                        (i32.load offset=4 (local.get $args_ptr))
+                        (i32.load (local.get $args_ptr))))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let mut map = BTreeMap::new();
+    map.insert("log".to_string(), ImportAction::Nop);
+    let program = compile_wat_with_imports(wat, map).expect("compile");
+
+    let ok = run(&program, 1, 4);
+    assert_eq!(ok.exit, ExitReason::Halt);
+    assert_eq!(ok.output, 8u32.to_le_bytes());
+
+    // The import has the right type, but there is no PVM code to call.
+    assert_eq!(trap_code(&run(&program, 0, 4)), CALL_INDIRECT_NULL_TRAP);
+}
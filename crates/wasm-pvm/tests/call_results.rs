@@ -0,0 +1,103 @@
+//! Operand-stack balance around direct calls: a void call pushes nothing,
+//! dropped results (one or several) are popped, and consuming a void call's
+//! "result" is rejected rather than compiled against a phantom value.
+
+use wasm_pvm::test_harness::*;
+
+#[test]
+fn void_call_followed_by_more_code() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $bump (param $addr i32)
+                (i32.store (local.get $addr)
+                    (i32.add (i32.load (local.get $addr)) (i32.const 1))))
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const 0) (i32.load (local.get $p)))
+                (call $bump (i32.const 0))
+                (call $bump (i32.const 0))
+                (i32.store (i32.const 0) (i32.mul (i32.load (i32.const 0)) (i32.const 2)))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &19i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
+
+#[test]
+fn dropped_call_result_is_popped() {
+    // If the dropped result stayed on the operand stack, the `i32.sub` would
+    // read it instead of the value loaded after the call.
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $returns_i32 (param $x i32) (result i32)
+                (i32.store (i32.const 4) (local.get $x))
+                (i32.const 1000))
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $r i32)
+                (i32.const 50)
+                (drop (call $returns_i32 (i32.const 8)))
+                (i32.load (i32.const 4))
+                (i32.sub)
+                (local.set $r)
+                (i32.store (i32.const 0) (local.get $r))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
+
+#[test]
+fn consuming_a_void_call_is_rejected() {
+    let wat = r#"
+        (module
+            (func $nothing)
+            (func (export "main") (param i32 i32) (result i64)
+                (call $nothing)
+                (i64.extend_i32_u)
+            )
+        )
+    "#;
+    // `wat` doesn't type-check, so this reaches `WasmModule::parse`, whose
+    // validation rejects it before the frontend could underflow its stack.
+    assert!(
+        compile_wat(wat).is_err(),
+        "a void call has no result to consume"
+    );
+}
+
+#[test]
+fn dropping_multi_value_call_results_pops_both() {
+    // A `(result i32 i32)` callee leaves two values on the caller's stack;
+    // if only the r7 result were pushed, the second `drop` would pop the 50
+    // and the `i32.sub` would underflow.
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $pair (result i32 i32) (i32.const 1) (i32.const 2))
+            (func (export "main") (param i32 i32) (result i64)
+                (i32.const 0)
+                (i32.const 50)
+                (call $pair)
+                (drop)
+                (drop)
+                (i32.const 8)
+                (i32.sub)
+                (i32.store)
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
@@ -0,0 +1,51 @@
+//! `ProgramBlob::offset_of` / `code_len` agree with summing each
+//! instruction's encoding, and `ProgramBlob::validate` accepts the code, for
+//! every standalone WAT fixture of the layer tests.
+
+use std::fs;
+use std::path::Path;
+
+use wasm_pvm::ProgramBlob;
+use wasm_pvm::test_harness::*;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../tests/fixtures");
+
+#[test]
+fn offset_map_matches_summed_encodings_for_all_fixtures() {
+    let imports = Path::new(FIXTURES).join("imports");
+    let mut checked = 0;
+    for entry in fs::read_dir(Path::new(FIXTURES).join("wat")).expect("fixtures dir") {
+        let path = entry.expect("fixture entry").path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let Some(stem) = name.strip_suffix(".jam.wat") else {
+            continue;
+        };
+        // Fixtures with an import map or adapter need them to compile.
+        if imports.join(format!("{stem}.imports")).exists()
+            || imports.join(format!("{stem}.adapter.wat")).exists()
+        {
+            continue;
+        }
+
+        let wat = fs::read_to_string(&path).expect("read fixture");
+        let program = compile_wat(&wat).unwrap_or_else(|e| panic!("{name}: {e}"));
+        let blob = program.code();
+
+        let mut summed = 0;
+        for (idx, instr) in blob.instructions().iter().enumerate() {
+            assert_eq!(
+                blob.offset_of(idx),
+                Some(summed),
+                "{name}: instruction {idx}"
+            );
+            summed += instr.encode().len();
+        }
+        assert_eq!(blob.code_len(), summed, "{name}");
+
+        let decoded = ProgramBlob::decode(&blob.encode()).expect("decode");
+        assert_eq!(decoded.code_len(), summed, "{name}");
+        decoded.validate().unwrap_or_else(|e| panic!("{name}: {e}"));
+        checked += 1;
+    }
+    assert!(checked > 0, "no fixtures found under {FIXTURES}");
+}
@@ -0,0 +1,48 @@
+//! `compile_reader` reads the module from any `std::io::Read` and produces the
+//! same program as the slice API.
+
+use std::io::{self, Cursor, Read};
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Error, compile_reader, compile_with_options};
+
+const BLOCK_PARAMS: &str = include_str!("../../../tests/fixtures/wat/block-params.jam.wat");
+
+#[test]
+fn reader_matches_slice_compilation() {
+    let wasm = wat_to_wasm(BLOCK_PARAMS).expect("wat");
+    let options = CompileOptions::default();
+    let from_slice = compile_with_options(&wasm, &options).expect("compile slice");
+    let from_reader = compile_reader(Cursor::new(&wasm), &options).expect("compile reader");
+    assert_eq!(from_reader.encode(), from_slice.encode());
+}
+
+/// Hands out a few bytes per `read` call, then fails.
+struct Truncated<'a>(&'a [u8]);
+
+impl Read for Truncated<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "stream reset",
+            ));
+        }
+        let n = buf.len().min(self.0.len()).min(7);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn read_errors_are_reported() {
+    let wasm = wat_to_wasm(BLOCK_PARAMS).expect("wat");
+    let err = compile_reader(Truncated(&wasm), &CompileOptions::default())
+        .err()
+        .expect("the stream fails");
+    assert!(
+        matches!(&err, Error::Io(e) if e.kind() == io::ErrorKind::ConnectionReset),
+        "{err}"
+    );
+}
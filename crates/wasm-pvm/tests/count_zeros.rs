@@ -0,0 +1,126 @@
+//! `clz`/`ctz` lower to the native `LeadingZeroBits*`/`TrailingZeroBits*`
+//! whichever way LLVM sets the intrinsics' `is_zero_poison` flag: WASM's
+//! operators reach the backend with it off (zero yields the width), and
+//! instcombine turns it on once it proves the operand non-zero.
+
+use wasm_pvm::Opcode;
+use wasm_pvm::test_harness::*;
+
+const VALUES: [u64; 7] = [
+    0,
+    1,
+    0x80,
+    0x8000_0000,
+    0x0001_0000_0000,
+    0x8000_0000_0000_0000,
+    u64::MAX,
+];
+
+/// `main` loads the i64 at `args[0..8]` into `$v` (and its low half into
+/// `$v32`) and stores `i32.clz`, `i32.ctz`, `i64.clz` and `i64.ctz` of the
+/// four operand expressions at 0, 4, 8 and 16.
+fn module(operands: [&str; 4]) -> String {
+    let [clz32, ctz32, clz64, ctz64] = operands;
+    main_module_wat(
+        "(memory 1)",
+        &format!(
+            r#"
+                (local $v i64) (local $v32 i32)
+                (local.set $v (i64.load (local.get $p)))
+                (local.set $v32 (i32.wrap_i64 (local.get $v)))
+                (i32.store (i32.const 0) (i32.clz {clz32}))
+                (i32.store (i32.const 4) (i32.ctz {ctz32}))
+                (i64.store (i32.const 8) (i64.clz {clz64}))
+                (i64.store (i32.const 16) (i64.ctz {ctz64}))
+            "#
+        ),
+        24,
+    )
+}
+
+/// The four counts `main` stores, given each operand's value.
+fn expected(clz32: u32, ctz32: u32, clz64: u64, ctz64: u64) -> Vec<u8> {
+    [
+        clz32.leading_zeros().to_le_bytes(),
+        ctz32.trailing_zeros().to_le_bytes(),
+    ]
+    .concat()
+    .into_iter()
+    .chain(u64::from(clz64.leading_zeros()).to_le_bytes())
+    .chain(u64::from(ctz64.trailing_zeros()).to_le_bytes())
+    .collect()
+}
+
+/// Whether `ir` calls `intrinsic` with the given `is_zero_poison` flag.
+fn calls_with_flag(ir: &str, intrinsic: &str, poison: bool) -> bool {
+    let flag = if poison { "i1 true" } else { "i1 false" };
+    ir.lines()
+        .any(|line| line.contains("call ") && line.contains(intrinsic) && line.contains(flag))
+}
+
+/// Check the flag in the optimized IR, that the backend picked the native
+/// ops, and that `main` agrees with `reference` on every value.
+fn check(operands: [&str; 4], poison: bool, reference: impl Fn(u64) -> Vec<u8>) {
+    let wat = module(operands);
+    let ir = dump_llvm_ir(&wat).expect("dump");
+    for intrinsic in [
+        "@llvm.ctlz.i32",
+        "@llvm.cttz.i32",
+        "@llvm.ctlz.i64",
+        "@llvm.cttz.i64",
+    ] {
+        assert!(
+            calls_with_flag(&ir, intrinsic, poison),
+            "{intrinsic}:\n{ir}"
+        );
+    }
+
+    let program = compile_wat(&wat).expect("compile");
+    let instructions = extract_instructions(&program);
+    for opcode in [
+        Opcode::LeadingZeroBits32,
+        Opcode::TrailingZeroBits32,
+        Opcode::LeadingZeroBits64,
+        Opcode::TrailingZeroBits64,
+    ] {
+        assert!(has_opcode(&instructions, opcode), "missing {opcode:?}");
+    }
+
+    for v in VALUES {
+        let result = run_program(&program, &v.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "{v:#x}");
+        assert_eq!(result.output, reference(v), "{v:#x}");
+    }
+}
+
+#[test]
+#[allow(clippy::cast_possible_truncation)]
+fn zero_defined_counts_return_the_width_for_zero() {
+    check(
+        [
+            "(local.get $v32)",
+            "(local.get $v32)",
+            "(local.get $v)",
+            "(local.get $v)",
+        ],
+        false,
+        |v| expected(v as u32, v as u32, v, v),
+    );
+}
+
+#[test]
+#[allow(clippy::cast_possible_truncation)]
+fn poison_on_zero_counts_of_non_zero_operands() {
+    // Setting a bit at the far end keeps each operand non-zero without
+    // making the count a constant, so instcombine flips the flag.
+    check(
+        [
+            "(i32.or (local.get $v32) (i32.const 1))",
+            "(i32.or (local.get $v32) (i32.const 0x80000000))",
+            "(i64.or (local.get $v) (i64.const 1))",
+            "(i64.or (local.get $v) (i64.const 0x8000000000000000))",
+        ],
+        true,
+        |v| expected(v as u32 | 1, v as u32 | 1 << 31, v | 1, v | 1 << 63),
+    );
+}
@@ -0,0 +1,47 @@
+//! `OptimizationFlags::dead_block_elimination`: code behind a call to an
+//! import mapped to `trap` is dropped, and the remaining paths still run.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, OptimizationFlags, SpiProgram, parse_import_map};
+
+const DEAD_BLOCK: &str = include_str!("../../../tests/fixtures/wat/dead-block.jam.wat");
+const DEAD_BLOCK_IMPORTS: &str = include_str!("../../../tests/fixtures/imports/dead-block.imports");
+
+fn compile(dead_block_elimination: bool) -> SpiProgram {
+    let options = CompileOptions {
+        import_map: Some(parse_import_map(DEAD_BLOCK_IMPORTS).expect("import map")),
+        optimizations: OptimizationFlags {
+            dead_block_elimination,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_wat_with_options(DEAD_BLOCK, &options).expect("compile")
+}
+
+fn run(program: &SpiProgram, op: u32, a: u32, b: u32) -> RunResult {
+    let args: Vec<u8> = [op, a, b].iter().flat_map(|v| v.to_le_bytes()).collect();
+    run_program(program, &args)
+}
+
+#[test]
+fn dead_blocks_shrink_code_without_changing_results() {
+    let pruned = compile(true);
+    let full = compile(false);
+    assert!(
+        pruned.code().code_len() < full.code().code_len(),
+        "{} >= {}",
+        pruned.code().code_len(),
+        full.code().code_len()
+    );
+
+    for program in [&pruned, &full] {
+        for (op, a, b, expected) in [(0, 21, 4, 5), (0, 7, 7, 1), (1, 9, 4, 5), (1, 4, 4, 0)] {
+            let result = run(program, op, a, b);
+            assert_eq!(result.exit, ExitReason::Halt, "op {op} ({a}, {b})");
+            assert_eq!(result.output, expected.to_le_bytes(), "op {op} ({a}, {b})");
+        }
+        assert_eq!(run(program, 0, 21, 0).exit, ExitReason::Trap);
+        assert_eq!(run(program, 1, 4, 9).exit, ExitReason::Trap);
+    }
+}
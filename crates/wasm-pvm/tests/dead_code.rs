@@ -0,0 +1,102 @@
+//! Dead code after `return` / `br` / `unreachable` must not reach the blob.
+//!
+//! The frontend stops translating operators once the current block ends in an
+//! unconditional transfer (its `unreachable` flag) and only tracks control
+//! structure until the matching `End`/`Else`. These tests pin that down with
+//! a marker constant that appears only in the dead statements.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, OptimizationFlags};
+
+/// Appears nowhere in the live code below.
+const MARKER: i32 = 0x0bad_c0de;
+
+fn mentions_marker(program: &wasm_pvm::SpiProgram) -> bool {
+    extract_instructions(program)
+        .iter()
+        .any(|instr| match instr {
+            Instruction::LoadImm { value, .. } => *value == MARKER,
+            Instruction::LoadImm64 { value, .. } => i64::try_from(*value) == Ok(i64::from(MARKER)),
+            Instruction::StoreImmU32 { value, .. } | Instruction::StoreImmIndU32 { value, .. } => {
+                *value == MARKER
+            }
+            _ => false,
+        })
+}
+
+/// Stores after a `return` (and the nested block and loop around them) are
+/// dropped; the live path still computes the right result.
+const AFTER_RETURN_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0) (i32.add (i32.load (local.get $p)) (i32.const 1)))
+            (return (i64.const 17179869184))
+            (i32.store (i32.const 0) (i32.const 0x0badc0de))
+            (block
+                (loop
+                    (i32.store (i32.const 4) (i32.const 0x0badc0de))
+                    (br 0)))
+            (i64.const 0x0badc0de)
+        )
+    )
+"#;
+
+#[test]
+fn statements_after_return_are_not_emitted() {
+    let program = compile_wat(AFTER_RETURN_WAT).expect("compile");
+    assert!(
+        !mentions_marker(&program),
+        "dead stores leaked into the blob"
+    );
+
+    let result = run_program(&program, &41i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
+
+/// The skip is done by the frontend, not by later optimizations: the dead
+/// statements stay out with every optional optimization off.
+#[test]
+fn statements_after_return_are_not_emitted_without_optimizations() {
+    let options = CompileOptions {
+        optimizations: OptimizationFlags::all_disabled(),
+        ..CompileOptions::default()
+    };
+    let program = compile_wat_with_options(AFTER_RETURN_WAT, &options).expect("compile");
+    assert!(
+        !mentions_marker(&program),
+        "dead stores leaked into the blob"
+    );
+}
+
+/// Code after `br` out of a block and after `unreachable` inside an `if` arm
+/// is dropped, while the code following the block's `End` is live again.
+#[test]
+fn statements_after_br_and_unreachable_are_not_emitted() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (block $out
+                    (br $out)
+                    (i32.store (i32.const 0) (i32.const 0x0badc0de)))
+                (if (i32.eqz (local.get $l))
+                    (then
+                        unreachable
+                        (i32.store (i32.const 0) (i32.const 0x0badc0de))))
+                (i32.store (i32.const 0) (i32.const 7))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    assert!(
+        !mentions_marker(&program),
+        "dead stores leaked into the blob"
+    );
+
+    let result = run_program(&program, &[1]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 7i32.to_le_bytes());
+}
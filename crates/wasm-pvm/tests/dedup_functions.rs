@@ -0,0 +1,77 @@
+//! `OptimizationFlags::dedup_functions`: functions with byte-identical bodies
+//! and the same signature share one copy of code, reached by direct calls and
+//! `call_indirect` alike.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, CompileStats, OptimizationFlags, SpiProgram, compile_with_stats};
+
+const DEDUP_FUNCTIONS: &str = include_str!("../../../tests/fixtures/wat/dedup-functions.jam.wat");
+
+/// Local indices of the fixture's helpers: three copies, then a distinct one.
+const SUM_SQUARES: [usize; 3] = [0, 1, 2];
+const SUM_CUBES: usize = 3;
+
+fn compile(dedup_functions: bool) -> (SpiProgram, CompileStats) {
+    let wasm = wat_to_wasm(DEDUP_FUNCTIONS).expect("wat");
+    let options = CompileOptions {
+        optimizations: OptimizationFlags {
+            dedup_functions,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_with_stats(&wasm, &options).expect("compile")
+}
+
+fn run(program: &SpiProgram, op: u32, n: u32) -> u32 {
+    let args: Vec<u8> = [op, n].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let result = run_program(program, &args);
+    assert_eq!(result.exit, ExitReason::Halt, "op {op} ({n})");
+    u32::from_le_bytes(result.output.try_into().expect("4 bytes"))
+}
+
+#[test]
+fn duplicates_share_the_first_copy() {
+    let (_, stats) = compile(true);
+    let by_index = |index| {
+        stats
+            .functions
+            .iter()
+            .find(|f| f.index == index)
+            .expect("function stats")
+    };
+    let [first, rest @ ..] = SUM_SQUARES;
+    assert_eq!(by_index(first).shared_with, None);
+    for index in rest {
+        let f = by_index(index);
+        assert_eq!(f.shared_with, Some(first));
+        assert_eq!(f.code_offset, by_index(first).code_offset);
+        assert_eq!(f.instruction_count, 0);
+    }
+    assert_eq!(by_index(SUM_CUBES).shared_with, None);
+}
+
+#[test]
+fn dedup_shrinks_code_without_changing_results() {
+    let (deduped, _) = compile(true);
+    let (separate, stats) = compile(false);
+    assert!(stats.functions.iter().all(|f| f.shared_with.is_none()));
+    assert!(
+        deduped.code().code_len() < separate.code().code_len(),
+        "{} >= {}",
+        deduped.code().code_len(),
+        separate.code().code_len()
+    );
+
+    for program in [&deduped, &separate] {
+        for n in [0, 1, 5, 10] {
+            let squares = (0..n).map(|i| i * i).sum::<u32>();
+            let cubes = (0..n).map(|i| i * i * i).sum::<u32>();
+            assert_eq!(run(program, 0, n), squares);
+            assert_eq!(run(program, 1, n), squares);
+            assert_eq!(run(program, 2, n), squares);
+            assert_eq!(run(program, 3, n), cubes);
+            assert_eq!(run(program, 4, n), 3 * squares);
+        }
+    }
+}
@@ -303,3 +303,154 @@ fn test_loop_with_stack_accumulation() {
         "Should compile loop with stack accumulation"
     );
 }
+
+/// Stack-form `main` that applies `i64.<op>` to the input word while four
+/// call results sit below it on the operand stack (depth 6), so the operand
+/// is live across calls and must come back from its stack slot with all 64
+/// bits. Output: the sum of the five values as an i64.
+fn bit_count_at_depth_wat(op: &str) -> String {
+    format!(
+        r#"
+        (module
+            (memory 1)
+            (func $mix (param i64) (result i64)
+                (i64.mul (local.get 0) (i64.const 3)))
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $x i64)
+                (local.set $x (i64.load (local.get $p)))
+                i32.const 0
+                local.get $x
+                call $mix
+                local.get $x
+                i64.const 1
+                i64.add
+                call $mix
+                local.get $x
+                i64.const 2
+                i64.xor
+                call $mix
+                local.get $x
+                i64.const 4
+                i64.or
+                call $mix
+                local.get $x
+                i64.{op}
+                i64.add
+                i64.add
+                i64.add
+                i64.add
+                i64.store
+                i64.const 34359738368
+            )
+        )
+        "#
+    )
+}
+
+fn check_bit_count_at_depth(op: &str, count: fn(u64) -> u32) {
+    let program = compile_wat(&bit_count_at_depth_wat(op)).expect("compile");
+    // Set bits only in the upper half, straddling it, and none at all: a
+    // 32-bit load of the operand would give a different answer for each.
+    for x in [
+        0x0000_0F00_0000_0000u64,
+        0x8000_0001_0000_0000,
+        0xFFFF_FFFF_FFFF_FFFF,
+        0x0000_0001_8000_0000,
+        0,
+    ] {
+        let mixed = [x, x.wrapping_add(1), x ^ 2, x | 4]
+            .iter()
+            .fold(0u64, |acc, v| acc.wrapping_add(v.wrapping_mul(3)));
+        let expected = mixed.wrapping_add(u64::from(count(x)));
+        let result = run_program(&program, &x.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "i64.{op}, x = {x:#x}");
+        assert_eq!(
+            result.output,
+            expected.to_le_bytes(),
+            "i64.{op}, x = {x:#x}"
+        );
+    }
+}
+
+#[test]
+fn test_i64_popcnt_operand_at_spilled_depth() {
+    check_bit_count_at_depth("popcnt", u64::count_ones);
+}
+
+#[test]
+fn test_i64_clz_operand_at_spilled_depth() {
+    check_bit_count_at_depth("clz", u64::leading_zeros);
+}
+
+#[test]
+fn test_i64_ctz_operand_at_spilled_depth() {
+    check_bit_count_at_depth("ctz", u64::trailing_zeros);
+}
+
+/// `f(a) + g(b)` with four operands already on the stack, so `a`, `f(a)` and
+/// `b` sit at depth 5 and beyond while the calls happen. `g` itself calls `f`
+/// with its own deep stack. Input: `a`, `b` as two i32s; output: the i32 sum.
+#[test]
+fn test_operands_survive_nested_calls_at_spilled_depth() {
+    let program = compile_wat(
+        r#"
+        (module
+            (memory 1)
+            (func $f (param i32) (result i32)
+                (i32.add (i32.mul (local.get 0) (i32.const 7)) (i32.const 3)))
+            (func $g (param i32) (result i32)
+                local.get 0
+                i32.const 100
+                local.get 0
+                i32.const 1
+                i32.xor
+                local.get 0
+                call $f
+                i32.add
+                i32.add
+                i32.mul
+            )
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $a i32) (local $b i32)
+                (local.set $a (i32.load (local.get $p)))
+                (local.set $b (i32.load offset=4 (local.get $p)))
+                i32.const 0
+                local.get $a
+                i32.const 11
+                i32.mul
+                local.get $b
+                i32.const 13
+                i32.mul
+                local.get $a
+                local.get $b
+                i32.sub
+                local.get $a
+                call $f
+                local.get $b
+                call $g
+                i32.add
+                i32.add
+                i32.add
+                i32.add
+                i32.store
+                i64.const 17179869184
+            )
+        )
+        "#,
+    )
+    .expect("compile");
+    let f = |x: i32| x.wrapping_mul(7).wrapping_add(3);
+    let g = |x: i32| x.wrapping_mul(100i32.wrapping_add((x ^ 1).wrapping_add(f(x))));
+    for (a, b) in [(0i32, 0i32), (1, 2), (-5, 9), (1000, -77), (i32::MAX, 3)] {
+        let mut input = a.to_le_bytes().to_vec();
+        input.extend_from_slice(&b.to_le_bytes());
+        let expected = a
+            .wrapping_mul(11)
+            .wrapping_add(b.wrapping_mul(13))
+            .wrapping_add(a.wrapping_sub(b))
+            .wrapping_add(f(a).wrapping_add(g(b)));
+        let result = run_program(&program, &input);
+        assert_eq!(result.exit, ExitReason::Halt, "a = {a}, b = {b}");
+        assert_eq!(result.output, expected.to_le_bytes(), "a = {a}, b = {b}");
+    }
+}
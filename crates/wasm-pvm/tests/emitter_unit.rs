@@ -156,6 +156,87 @@ fn test_large_constant_uses_load_imm64() {
     );
 }
 
+/// A 64-bit constant used several times in one block is materialized once;
+/// later uses copy it from the register that already holds it.
+#[test]
+fn test_repeated_large_constant_loaded_once() {
+    const C: u64 = 0x1234_5678_9abc_def0;
+    let program = compile_wat(
+        r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $a i64)
+                (local.set $a (i64.load (local.get $p)))
+                (i64.store (i32.const 0) (i64.add (local.get $a) (i64.const 0x123456789abcdef0)))
+                (i64.store (i32.const 8) (i64.xor (local.get $a) (i64.const 0x123456789abcdef0)))
+                (i64.store (i32.const 16) (i64.const 0x123456789abcdef0))
+                (i64.const 103079215104)
+            )
+        )
+        "#,
+    )
+    .expect("compile");
+    let instructions = extract_instructions(&program);
+
+    let loads = instructions
+        .iter()
+        .filter(|i| matches!(i, Instruction::LoadImm64 { value: C, .. }))
+        .count();
+    assert_eq!(loads, 1, "expected a single LoadImm64 of {C:#x}");
+
+    let result = run_program(&program, &5u64.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    let expected = [5u64.wrapping_add(C), 5 ^ C, C]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(result.output, expected);
+}
+
+/// A call clobbers the register holding a 64-bit constant; the use after it
+/// must not copy the stale register.
+#[test]
+fn test_large_constant_reloaded_after_call() {
+    const C: u64 = 0x1234_5678_9abc_def0;
+    const D: u64 = 0x0fed_cba9_8765_4321;
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $mix (param $x i64) (result i64)
+                (i64.xor (local.get $x) (i64.const 0x0fedcba987654321))
+            )
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $a i64)
+                (local.set $a (i64.load (local.get $p)))
+                (i64.store (i32.const 0) (i64.add (local.get $a) (i64.const 0x123456789abcdef0)))
+                (i64.store (i32.const 8) (call $mix (local.get $a)))
+                (i64.store (i32.const 16) (i64.xor (local.get $a) (i64.const 0x123456789abcdef0)))
+                (i64.const 103079215104)
+            )
+        )
+    "#;
+    let program = compile_wat_with_options(
+        wat,
+        &CompileOptions {
+            optimizations: OptimizationFlags {
+                inlining: false,
+                ..OptimizationFlags::default()
+            },
+            ..CompileOptions::default()
+        },
+    )
+    .expect("compile");
+
+    let result = run_program(&program, &5u64.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    let expected = [5u64.wrapping_add(C), 5 ^ D, 5 ^ C]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(result.output, expected);
+}
+
 /// Negative i32 constants should use sign-extended `LoadImm` (compact encoding).
 #[test]
 fn test_negative_constant_uses_load_imm() {
@@ -758,6 +839,75 @@ fn test_shrink_wrap_calling_function_saves_all() {
     }
 }
 
+/// Callee-save stores (`StoreIndU64 [SP + off], rN` for r10-r12) in `instructions`.
+/// r9 is excluded: it holds the single parameter of the leaf in the test below.
+fn callee_save_stores(instructions: &[Instruction]) -> usize {
+    instructions
+        .iter()
+        .filter(|instr| {
+            matches!(
+                instr,
+                Instruction::StoreIndU64 { base: 1, src: 10..=12, offset } if *offset >= 0
+            )
+        })
+        .count()
+}
+
+/// A leaf function whose allocated values never cross a call should live in
+/// caller-saved r5/r6 rather than r10-r12, so its prologue needs no extra
+/// callee-save stores.
+#[test]
+fn test_leaf_regalloc_prefers_caller_saved() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $sum_to (param $n i32) (result i32)
+                (local $i i32) (local $acc i32)
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+                        (local.set $acc
+                            (i32.add (local.get $acc) (i32.load (local.get $i))))
+                        (local.set $i (i32.add (local.get $i) (i32.const 4)))
+                        (br $next)))
+                (local.get $acc)
+            )
+            (func (export "main") (param i32 i32) (result i32)
+                (call $sum_to (i32.const 100))
+            )
+        )
+    "#;
+    let compile = |prefer_caller_saved_regs| {
+        compile_wat_with_options(
+            wat,
+            &CompileOptions {
+                optimizations: OptimizationFlags {
+                    inlining: false,
+                    prefer_caller_saved_regs,
+                    ..OptimizationFlags::default()
+                },
+                ..CompileOptions::default()
+            },
+        )
+        .expect("compile")
+    };
+
+    let preferred = extract_instructions(&compile(true));
+    assert_eq!(
+        callee_save_stores(&preferred),
+        0,
+        "leaf values should not need r10-r12 saves"
+    );
+    let uses_scratch = preferred.iter().any(|instr| {
+        matches!(instr.dest_reg(), Some(5 | 6))
+            || instr.src_regs().iter().any(|r| matches!(r, Some(5 | 6)))
+    });
+    assert!(uses_scratch, "loop values should be allocated to r5/r6");
+
+    let unpreferred = extract_instructions(&compile(false));
+    assert!(callee_save_stores(&unpreferred) >= callee_save_stores(&preferred));
+}
+
 /// Inlining: calling a small leaf function should produce different (inlined) code.
 #[test]
 fn test_inlining_changes_codegen() {
@@ -867,15 +1017,18 @@ fn test_mergefunc_shrinks_identical_bodies() {
 
     // Inlining off in both arms so the bodies survive into mergefunc's view —
     // otherwise a tiny body might be inlined into all callers and the duplicate
-    // we're testing disappears for the wrong reason.
+    // we're testing disappears for the wrong reason. `dedup_functions` is off
+    // too: it would emit a single copy in both arms before `mergefunc` matters.
     let opts_off = OptimizationFlags {
         inlining: false,
         mergefunc: false,
+        dedup_functions: false,
         ..OptimizationFlags::default()
     };
     let opts_on = OptimizationFlags {
         inlining: false,
         mergefunc: true,
+        dedup_functions: false,
         ..OptimizationFlags::default()
     };
 
@@ -1302,3 +1455,59 @@ fn test_entry_return_void_no_unpack() {
         "Void entry function should not emit ShloR64 (packed return unpacking)"
     );
 }
+
+// ── Register cache ──
+
+/// A `local.tee`'d value reused several times is served from registers
+/// (including copies made by `MoveReg`) instead of being reloaded from its
+/// stack slot, so the register cache yields fewer instructions.
+#[test]
+fn test_register_cache_reuses_local_tee_value() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $t i32)
+                (i32.store (i32.const 0)
+                    (i32.add
+                        (local.tee $t (i32.mul (i32.load (local.get $p)) (i32.const 3)))
+                        (i32.xor (local.get $t) (i32.sub (local.get $t) (i32.const 5)))))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let compile = |register_cache| {
+        compile_wat_with_options(
+            wat,
+            &CompileOptions {
+                optimizations: OptimizationFlags {
+                    register_cache,
+                    register_allocation: false,
+                    aggressive_register_allocation: false,
+                    allocate_scratch_regs: false,
+                    allocate_caller_saved_regs: false,
+                    ..OptimizationFlags::default()
+                },
+                ..CompileOptions::default()
+            },
+        )
+        .expect("compile")
+    };
+    let cached = compile(true);
+    let uncached = compile(false);
+
+    let size_cached = cached.code().instructions().len();
+    let size_uncached = uncached.code().instructions().len();
+    assert!(
+        size_cached < size_uncached,
+        "register cache should shrink the code: {size_cached} >= {size_uncached}"
+    );
+
+    let t = 14i32 * 3;
+    let expected = t + (t ^ (t - 5));
+    for program in [&cached, &uncached] {
+        let result = run_program(program, &14i32.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.output, expected.to_le_bytes());
+    }
+}
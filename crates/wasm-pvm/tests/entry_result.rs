@@ -0,0 +1,28 @@
+//! The entry function's result comes only from its packed `i64` return value
+//! (`ptr | len << 32`). Module globals play no part in the exit ABI, even when
+//! the first two are mutable `i32`s that look like a pointer/length pair.
+
+use wasm_pvm::test_harness::*;
+
+#[test]
+fn leading_mutable_globals_do_not_select_the_result() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $ptr (mut i32) (i32.const 0))
+            (global $len (mut i32) (i32.const 0))
+            (data (i32.const 0) "\de\ad\be\ef")
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (global.set $ptr (i32.const 0))
+                (global.set $len (i32.const 4))
+                (i32.store (i32.const 16) (i32.load (local.get $p)))
+                ;; 4 bytes at address 16.
+                (i64.const 17179869200)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &0x0102_0304i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 0x0102_0304i32.to_le_bytes());
+}
@@ -0,0 +1,48 @@
+//! A function exported under more than one name.
+//!
+//! Exporting the entry function as both `main` and an arbitrary alias must not
+//! be treated as a conflict: `main` is selected as the single entry point
+//! whichever export comes first.
+
+use wasm_pvm::test_harness::*;
+
+/// `$entry` stores its argument plus one, exported as `main` then `run`.
+const MAIN_FIRST: &str = r#"
+    (module
+        (memory 1)
+        (func $entry (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0) (i32.add (i32.load (local.get $p)) (i32.const 1)))
+            (i64.const 17179869184))
+        (export "main" (func $entry))
+        (export "run" (func $entry))
+    )
+"#;
+
+/// `MAIN_FIRST` with the two exports swapped.
+const RUN_FIRST: &str = r#"
+    (module
+        (memory 1)
+        (func $entry (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0) (i32.add (i32.load (local.get $p)) (i32.const 1)))
+            (i64.const 17179869184))
+        (export "run" (func $entry))
+        (export "main" (func $entry))
+    )
+"#;
+
+#[test]
+fn main_and_run_alias_select_one_entry() {
+    for wat in [MAIN_FIRST, RUN_FIRST] {
+        let program = compile_wat(wat).unwrap_or_else(|e| panic!("{wat}: compile failed: {e}"));
+        let result = run_program(&program, &41i32.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "{wat}");
+        assert_eq!(result.output, 42i32.to_le_bytes(), "{wat}");
+    }
+}
+
+#[test]
+fn export_order_does_not_change_output() {
+    let main_first = compile_wat(MAIN_FIRST).expect("compile");
+    let run_first = compile_wat(RUN_FIRST).expect("compile");
+    assert_eq!(main_first.encode(), run_first.encode());
+}
@@ -0,0 +1,55 @@
+//! Exported functions other than the entry points.
+//!
+//! A host may jump straight into any exported function by name, using the
+//! offset from `CompileStats`, so an export is kept even when nothing in the
+//! module calls it. Any reachability-based pruning has to treat every export
+//! as a root, not just `main`/`main2`.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, compile_with_stats};
+
+/// Appears only in the body of the uncalled export.
+const MARKER: i32 = 0x0e5c_a1ed;
+
+#[test]
+fn uncalled_export_is_retained_with_its_offset() {
+    let wat = format!(
+        r#"
+        (module
+            (memory 1)
+            (func $checksum (export "checksum") (param $x i32) (result i32)
+                (i32.xor (local.get $x) (i32.const {MARKER})))
+            (func $entry (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const 0) (i32.load (local.get $p)))
+                (i64.const 17179869184))
+        )
+        "#
+    );
+    let wasm = wat_to_wasm(&wat).expect("WAT should parse");
+    let (program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    let checksum = stats
+        .functions
+        .iter()
+        .find(|f| f.name == "checksum")
+        .expect("exported helper should appear in the function stats");
+    assert!(!checksum.is_entry);
+    assert!(checksum.instruction_count > 0);
+    let code_len: usize = program
+        .code()
+        .instructions()
+        .iter()
+        .map(|i| i.encode().len())
+        .sum();
+    assert!(
+        checksum.code_offset > 0 && checksum.code_offset < code_len,
+        "offset {} outside the {code_len}-byte code section",
+        checksum.code_offset
+    );
+
+    let has_marker = extract_instructions(&program).iter().any(|instr| {
+        matches!(instr, Instruction::LoadImm { value, .. } if *value == MARKER)
+            || matches!(instr, Instruction::XorImm { value, .. } if *value == MARKER)
+    });
+    assert!(has_marker, "body of the uncalled export is missing");
+}
@@ -0,0 +1,69 @@
+//! Extended-const expressions (`add`/`sub`/`mul` and `global.get` of earlier
+//! immutable globals) in global initializers and data/element offsets.
+
+use wasm_pvm::test_harness::*;
+
+#[test]
+fn data_offset_computed_with_i32_add() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (data (offset (i32.add (i32.const 0x1000) (i32.const 8))) "\2a\00\00\00")
+            (func (export "main") (param i32 i32) (result i64)
+                (i32.store (i32.const 0) (i32.load (i32.const 0x1008)))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
+
+#[test]
+fn global_init_folds_arithmetic_over_earlier_globals() {
+    // `$g` was previously rejected as a multi-operator init expression.
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $base i32 (i32.const 0x1000))
+            (global $g (mut i32) (i32.add (i32.const 5) (i32.const 7)))
+            (global $h i32 (i32.sub (i32.mul (global.get $base) (i32.const 3)) (i32.const 8)))
+            (global $wide i64 (i64.mul (i64.const 0x1_0000_0000) (i64.const 3)))
+            (func (export "main") (param i32 i32) (result i64)
+                (i32.store (i32.const 0) (global.get $g))
+                (i32.store (i32.const 4) (global.get $h))
+                (i64.store (i32.const 8) (global.get $wide))
+                (i64.const 68719476736)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    let expected: Vec<u8> = [
+        &12i32.to_le_bytes()[..],
+        &0x2ff8i32.to_le_bytes(),
+        &0x3_0000_0000i64.to_le_bytes(),
+    ]
+    .concat();
+    assert_eq!(result.output, expected);
+}
+
+#[test]
+fn offset_from_imported_global_is_rejected() {
+    // The imported global's value is only known at instantiation.
+    let wat = r#"
+        (module
+            (import "env" "BASE" (global $base i32))
+            (memory 1)
+            (data (offset (i32.add (global.get $base) (i32.const 8))) "x")
+            (func (export "main") (param i32 i32) (result i64) (i64.const 0))
+        )
+    "#;
+    assert!(
+        compile_wat(wat).is_err(),
+        "data offset depending on an imported global must be rejected"
+    );
+}
@@ -11,6 +11,13 @@
 //!    operator is replaced with a runtime trap so the rest of the function (and
 //!    the rest of the module) can still be compiled. This lets users find out
 //!    what *other* unsupported features a module uses past the float wall.
+//!
+//! Integer↔float conversions and reinterprets are the exception: they have a
+//! soft-float lowering and compile (and run) in both modes.
+//!
+//! 3. **`--soft-float`** (`CompileOptions::enable_soft_float` = true): constants,
+//!    loads/stores, arithmetic and comparisons are emulated with integer code
+//!    and produce the same bits as native IEEE 754 arithmetic.
 
 use wasm_pvm::test_harness::*;
 use wasm_pvm::{CompileOptions, Error, Opcode};
@@ -430,3 +437,254 @@ fn default_mode_still_rejects_floats() {
         .expect("default should still fail");
     assert!(matches!(err, Error::Located { .. }));
 }
+
+// ──────────────────────────────────────────────────────────────────────────
+// Soft-float conversions
+// ──────────────────────────────────────────────────────────────────────────
+
+/// `main` truncates the f64 whose bits are passed as args to an i32.
+const TRUNC_F64_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+            (i32.store (i32.const 0)
+                (i32.trunc_f64_s (f64.reinterpret_i64 (i64.load (local.get $args_ptr)))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+/// Conversions compile in default mode and produce real code: `3.9` truncates
+/// to 3 at runtime.
+#[test]
+fn trunc_f64_s_compiles_and_truncates_toward_zero() {
+    let program = compile_wat(TRUNC_F64_WAT).expect("conversions should compile by default");
+
+    let result = run_program(&program, &3.9f64.to_bits().to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 3i32.to_le_bytes());
+
+    let result = run_program(&program, &(-3.9f64).to_bits().to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, (-3i32).to_le_bytes());
+}
+
+/// Out-of-range and NaN inputs trap.
+#[test]
+fn trunc_f64_s_traps_out_of_range_and_nan() {
+    let program = compile_wat(TRUNC_F64_WAT).expect("Failed to compile");
+    for input in [
+        2_147_483_648.0f64,
+        -2_147_483_649.0,
+        f64::NAN,
+        f64::INFINITY,
+    ] {
+        let result = run_program(&program, &input.to_bits().to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Trap, "input {input}");
+    }
+}
+
+/// `main` reads an f64 and an f32 (as bits) and stores, in order: the f64
+/// and the f32 through `i32.trunc_sat_*_{s,u}`, then through
+/// `i64.trunc_sat_*_{s,u}` (48 bytes at address 0).
+const TRUNC_SAT_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0)
+                (i32.trunc_sat_f64_s (f64.reinterpret_i64 (i64.load (local.get $p)))))
+            (i32.store (i32.const 4)
+                (i32.trunc_sat_f64_u (f64.reinterpret_i64 (i64.load (local.get $p)))))
+            (i32.store (i32.const 8)
+                (i32.trunc_sat_f32_s (f32.reinterpret_i32 (i32.load offset=8 (local.get $p)))))
+            (i32.store (i32.const 12)
+                (i32.trunc_sat_f32_u (f32.reinterpret_i32 (i32.load offset=8 (local.get $p)))))
+            (i64.store (i32.const 16)
+                (i64.trunc_sat_f64_s (f64.reinterpret_i64 (i64.load (local.get $p)))))
+            (i64.store (i32.const 24)
+                (i64.trunc_sat_f64_u (f64.reinterpret_i64 (i64.load (local.get $p)))))
+            (i64.store (i32.const 32)
+                (i64.trunc_sat_f32_s (f32.reinterpret_i32 (i32.load offset=8 (local.get $p)))))
+            (i64.store (i32.const 40)
+                (i64.trunc_sat_f32_u (f32.reinterpret_i32 (i32.load offset=8 (local.get $p)))))
+            (i64.const 206158430208)
+        )
+    )
+"#;
+
+/// `trunc_sat` compiles by default and saturates instead of trapping: NaN
+/// gives 0, ±∞ and out-of-range values clamp to the target's min/max. Rust's
+/// `as` casts follow the same rules, so they supply the expected values.
+#[test]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn trunc_sat_saturates_instead_of_trapping() {
+    let program = compile_wat(TRUNC_SAT_WAT).expect("trunc_sat should compile by default");
+    for input in [
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NAN,
+        -0.9,
+        1.5,
+        2_147_483_647.9,
+        2_147_483_648.0,
+        -2_147_483_648.9,
+        -2_147_483_649.0,
+        4_294_967_295.5,
+        4_294_967_296.0,
+        9.0e18,
+        9_223_372_036_854_775_808.0,
+        -9_223_372_036_854_775_808.0,
+        -9_223_372_036_854_777_856.0,
+        18_446_744_073_709_549_568.0,
+        18_446_744_073_709_551_616.0,
+    ] {
+        let narrow = input as f32;
+        let mut args = input.to_bits().to_le_bytes().to_vec();
+        args.extend_from_slice(&narrow.to_bits().to_le_bytes());
+        let result = run_program(&program, &args);
+        assert_eq!(result.exit, ExitReason::Halt, "input {input}");
+
+        let mut expected = Vec::new();
+        for value in [
+            input as i32 as u32,
+            input as u32,
+            narrow as i32 as u32,
+            narrow as u32,
+        ] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [
+            input as i64 as u64,
+            input as u64,
+            narrow as i64 as u64,
+            narrow as u64,
+        ] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(result.output, expected, "input {input}");
+    }
+}
+
+/// `f64.convert_i32_s(i32::MAX)` is exact.
+#[test]
+fn convert_i32_s_to_f64_is_exact() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+                (i64.store (i32.const 0)
+                    (i64.reinterpret_f64 (f64.convert_i32_s (i32.load (local.get $args_ptr)))))
+                (i64.const 34359738368)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("Failed to compile");
+    let result = run_program(&program, &i32::MAX.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, f64::from(i32::MAX).to_bits().to_le_bytes());
+}
+
+/// Under `--trap-floats`, conversions keep their soft-float lowering instead
+/// of being replaced by a trap.
+#[test]
+fn trap_floats_keeps_soft_float_conversions() {
+    let opts = CompileOptions {
+        trap_floats: true,
+        ..CompileOptions::default()
+    };
+    let program = compile_wat_with_options(TRUNC_F64_WAT, &opts).expect("Failed to compile");
+    let result = run_program(&program, &3.9f64.to_bits().to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 3i32.to_le_bytes());
+}
+
+// ──────────────────────────────────────────────────────────────────────────
+// --soft-float: emulated arithmetic and comparisons
+// ──────────────────────────────────────────────────────────────────────────
+
+fn soft_float() -> CompileOptions {
+    CompileOptions {
+        enable_soft_float: true,
+        ..CompileOptions::default()
+    }
+}
+
+/// Adds the two f64 arguments, stores the sum at 0 and `sum > 0.3` at 8.
+const F64_ADD_CMP_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+            (local $sum f64)
+            (local.set $sum
+                (f64.add
+                    (f64.load (local.get $args_ptr))
+                    (f64.load offset=8 (local.get $args_ptr))))
+            (f64.store (i32.const 0) (local.get $sum))
+            (i32.store (i32.const 8) (f64.gt (local.get $sum) (f64.const 0.3)))
+            ;; 12 bytes at address 0.
+            (i64.const 51539607552)
+        )
+    )
+"#;
+
+#[test]
+fn soft_float_f64_add_and_compare() {
+    let program = compile_wat_with_options(F64_ADD_CMP_WAT, &soft_float()).expect("compile");
+    for (a, b) in [
+        (0.1f64, 0.2f64),
+        (1.5, -1.5),
+        (0.15, 0.15),
+        (f64::MAX, f64::MAX),
+        (5e-324, -1e-300),
+        (f64::INFINITY, f64::NEG_INFINITY),
+    ] {
+        let mut args = a.to_bits().to_le_bytes().to_vec();
+        args.extend_from_slice(&b.to_bits().to_le_bytes());
+        let result = run_program(&program, &args);
+        assert_eq!(result.exit, ExitReason::Halt, "{a} + {b}");
+
+        let sum = a + b;
+        let sum_bits = if sum.is_nan() {
+            0x7FF8_0000_0000_0000
+        } else {
+            sum.to_bits()
+        };
+        let mut expected = sum_bits.to_le_bytes().to_vec();
+        expected.extend_from_slice(&u32::from(sum > 0.3).to_le_bytes());
+        assert_eq!(result.output, expected, "{a} + {b}");
+    }
+}
+
+#[test]
+fn soft_float_f32_div_demote_and_promote() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+                (f64.store (i32.const 0)
+                    (f64.promote_f32
+                        (f32.div
+                            (f32.demote_f64 (f64.load (local.get $args_ptr)))
+                            (f32.const -3))))
+                (i64.const 34359738368)
+            )
+        )
+    "#;
+    let program = compile_wat_with_options(wat, &soft_float()).expect("compile");
+    for input in [1.0f64, 1e-40, 3.4e38, 1e39, -0.0] {
+        let result = run_program(&program, &input.to_bits().to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "input {input}");
+        let expected = f64::from(input as f32 / -3.0);
+        assert_eq!(
+            result.output,
+            expected.to_bits().to_le_bytes(),
+            "input {input}"
+        );
+    }
+}
+
+/// Soft-float is opt-in: without it arithmetic is still rejected.
+#[test]
+fn soft_float_is_opt_in() {
+    assert!(compile_wat(F64_ADD_CMP_WAT).is_err());
+}
@@ -0,0 +1,94 @@
+//! Funcref globals initialized with `ref.func`.
+//!
+//! A funcref global holds the callee's dispatch-table jump-ref (the same
+//! value the element section writes into the `call_indirect` table), patched
+//! into RW data once the jump table is laid out. `call_ref` jumps through it;
+//! null is 0, which the PVM rejects as a dynamic-jump target.
+
+use wasm_pvm::test_harness::*;
+
+/// `main` calls through `$op` (initially `$triple`), optionally after
+/// overwriting it with `$negate` from another global, and reports whether the
+/// funcref globals are null. Output: `[result, is_null($plain), is_null($none)]`.
+const FUNCREF_WAT: &str = r#"
+    (module
+        (memory 1)
+        (type $unary (func (param i32) (result i32)))
+        (func $triple (type $unary) (i32.mul (local.get 0) (i32.const 3)))
+        (func $negate (type $unary) (i32.sub (i32.const 0) (local.get 0)))
+        (elem declare func $triple $negate)
+        (global $op (mut (ref null $unary)) (ref.func $triple))
+        (global $alt (ref null $unary) (ref.func $negate))
+        (global $plain funcref (ref.func $triple))
+        (global $none (ref null $unary) (ref.null $unary))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (if (i32.load offset=4 (local.get $p))
+                (then (global.set $op (global.get $alt))))
+            (i32.store (i32.const 0)
+                (call_ref $unary (i32.load (local.get $p)) (global.get $op)))
+            (i32.store (i32.const 4) (ref.is_null (global.get $plain)))
+            (i32.store (i32.const 8) (ref.is_null (global.get $none)))
+            (i64.const 51539607552)
+        )
+    )
+"#;
+
+fn args(value: i32, swap: bool) -> Vec<u8> {
+    [value.to_le_bytes(), i32::from(swap).to_le_bytes()].concat()
+}
+
+fn words(values: [i32; 3]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[test]
+fn call_ref_through_ref_func_global() {
+    let program = compile_wat(FUNCREF_WAT).expect("compile");
+    let result = run_program(&program, &args(14, false));
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, words([42, 0, 1]));
+}
+
+#[test]
+fn funcref_global_can_be_reassigned() {
+    let program = compile_wat(FUNCREF_WAT).expect("compile");
+    let result = run_program(&program, &args(14, true));
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, words([-14, 0, 1]));
+}
+
+#[test]
+fn call_ref_through_null_global_faults() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (type $unary (func (param i32) (result i32)))
+            (global $none (ref null $unary) (ref.null $unary))
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const 0) (call_ref $unary (i32.const 1) (global.get $none)))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert!(
+        matches!(result.exit, ExitReason::Panic(_)),
+        "expected a fault on the null jump, got {:?}",
+        result.exit
+    );
+}
+
+#[test]
+fn externref_global_is_rejected() {
+    let wat = r#"
+        (module
+            (global $e externref (ref.null extern))
+            (func (export "main") (param i32 i32) (result i64) (i64.const 0))
+        )
+    "#;
+    assert!(
+        compile_wat(wat).is_err(),
+        "externref globals have no lowering"
+    );
+}
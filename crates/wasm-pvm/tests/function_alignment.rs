@@ -0,0 +1,62 @@
+//! `CompileOptions::function_alignment`: every function starts at an aligned
+//! code offset, and the padding doesn't change behaviour.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, compile_with_stats};
+
+/// `main` reaches `$inc` directly and `$double` through the table, so entry,
+/// call and indirect-call targets all move with the padding.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (type $unary (func (param i32) (result i32)))
+        (table 1 funcref)
+        (elem (i32.const 0) $double)
+        (func $inc (param $x i32) (result i32)
+            (i32.add (local.get $x) (i32.const 1)))
+        (func $double (param $x i32) (result i32)
+            (i32.add (local.get $x) (local.get $x)))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0)
+                (call_indirect (type $unary)
+                    (call $inc (i32.load (local.get $p)))
+                    (i32.const 0)))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+#[test]
+fn functions_start_at_aligned_offsets() {
+    let wasm = wat_to_wasm(WAT).expect("wat");
+    let options = CompileOptions {
+        function_alignment: Some(16),
+        ..CompileOptions::default()
+    };
+    let (program, stats) = compile_with_stats(&wasm, &options).expect("compile");
+
+    assert_eq!(stats.functions.len(), 3);
+    for func in &stats.functions {
+        assert_eq!(
+            func.code_offset % 16,
+            0,
+            "{} starts at unaligned offset {}",
+            func.name,
+            func.code_offset
+        );
+    }
+
+    let result = run_program(&program, &20i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
+
+#[test]
+fn alignment_is_off_by_default() {
+    let wasm = wat_to_wasm(WAT).expect("wat");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    // Main directly follows the 6-byte entry header.
+    let main = stats.functions.iter().find(|f| f.is_entry).expect("main");
+    assert_eq!(main.code_offset, 6);
+}
@@ -0,0 +1,166 @@
+//! `CompileOptions::function_sections`: every function owns whole pages of the
+//! code section and is only ever reached through its jump-table entry, so one
+//! function's code can be replaced without touching the rest of the program.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{
+    CompileOptions, CompileStats, Instruction, OptimizationFlags, ProgramBlob, SpiProgram,
+    compile_with_stats,
+};
+
+const PAGE: usize = 4096;
+
+/// `$scale` (local function 0) is called directly, `$double` through the
+/// table, and the start function `$init` from `main`'s prologue. `main`
+/// stores `double(scale(x)) + bias`, where `$init` sets `bias` to 5.
+fn module(scale_body: &str) -> String {
+    main_module_wat(
+        &format!(
+            r#"
+                (memory 1)
+                (type $unary (func (param i32) (result i32)))
+                (table 1 funcref)
+                (elem (i32.const 0) $double)
+                (global $bias (mut i32) (i32.const 0))
+                (func $scale (param $x i32) (result i32)
+                    {scale_body})
+                (func $double (param $x i32) (result i32)
+                    (i32.add (local.get $x) (local.get $x)))
+                (func $init
+                    (global.set $bias (i32.const 5)))
+                (start $init)
+            "#
+        ),
+        r#"
+            (i32.store (i32.const 0)
+                (i32.add
+                    (call_indirect (type $unary)
+                        (call $scale (i32.load (local.get $p)))
+                        (i32.const 0))
+                    (global.get $bias)))
+        "#,
+        4,
+    )
+}
+
+fn compile(scale_body: &str) -> (SpiProgram, CompileStats) {
+    let wasm = wat_to_wasm(&module(scale_body)).expect("wat");
+    let options = CompileOptions {
+        function_sections: true,
+        // Keep `$scale` out of line so `main` really calls it.
+        optimizations: OptimizationFlags {
+            inlining: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_with_stats(&wasm, &options).expect("compile")
+}
+
+fn output(program: &SpiProgram, x: i32) -> i32 {
+    let result = run_program(program, &x.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt, "x = {x}");
+    i32::from_le_bytes(result.output.try_into().expect("4 bytes"))
+}
+
+/// The instructions of `blob` whose offset lies in `range`.
+fn instructions_in(blob: &ProgramBlob, range: std::ops::Range<usize>) -> Vec<Instruction> {
+    blob.instructions()
+        .iter()
+        .enumerate()
+        .filter(|&(idx, _)| range.contains(&blob.offset_of(idx).unwrap()))
+        .map(|(_, instr)| instr.clone())
+        .collect()
+}
+
+#[test]
+fn calls_resolve_through_function_entries() {
+    let (program, stats) = compile("(i32.mul (local.get $x) (i32.const 3))");
+    let blob = program.code();
+
+    assert_eq!(blob.code_len() % PAGE, 0);
+    assert_eq!(stats.functions.len(), 4);
+    for func in &stats.functions {
+        assert_eq!(
+            func.code_offset % PAGE,
+            0,
+            "{} is not page-aligned",
+            func.name
+        );
+        assert_eq!(
+            blob.jump_table()[func.index],
+            u32::try_from(func.code_offset).unwrap(),
+            "jump-table entry {} should hold {}",
+            func.index,
+            func.name
+        );
+    }
+    // No code-relative call survives; every call jumps through the table.
+    assert!(
+        !blob
+            .instructions()
+            .iter()
+            .any(|instr| matches!(instr, Instruction::LoadImmJump { .. })),
+        "{:#?}",
+        blob.instructions()
+    );
+
+    assert_eq!(output(&program, 7), 47);
+    assert_eq!(output(&program, -2), -7);
+}
+
+#[test]
+fn swapping_one_section_replaces_that_function() {
+    let (old, old_stats) = compile("(i32.mul (local.get $x) (i32.const 3))");
+    let (new, new_stats) = compile("(i32.add (local.get $x) (i32.const 100))");
+    let (old_blob, new_blob) = (old.code(), new.code());
+
+    // `$scale`'s section runs up to the next function (or the end of code).
+    let start = old_stats
+        .functions
+        .iter()
+        .find(|f| f.index == 0)
+        .unwrap()
+        .code_offset;
+    let end = old_stats
+        .functions
+        .iter()
+        .map(|f| f.code_offset)
+        .filter(|&offset| offset > start)
+        .min()
+        .unwrap_or(old_blob.code_len());
+    assert_eq!(
+        new_stats
+            .functions
+            .iter()
+            .find(|f| f.index == 0)
+            .unwrap()
+            .code_offset,
+        start
+    );
+
+    // Everything outside the section, jump table included, is unchanged.
+    assert_eq!(old_blob.jump_table(), new_blob.jump_table());
+    assert_eq!(
+        instructions_in(old_blob, 0..start),
+        instructions_in(new_blob, 0..start)
+    );
+    assert_eq!(
+        instructions_in(old_blob, end..usize::MAX),
+        instructions_in(new_blob, end..usize::MAX)
+    );
+
+    let mut patched = instructions_in(old_blob, 0..start);
+    patched.extend(instructions_in(new_blob, start..end));
+    patched.extend(instructions_in(old_blob, end..usize::MAX));
+    let swapped =
+        SpiProgram::new(ProgramBlob::new(patched).with_jump_table(old_blob.jump_table().to_vec()))
+            .with_ro_data(old.ro_data().to_vec())
+            .with_rw_data(old.rw_data().to_vec())
+            .with_heap_pages(old.heap_pages())
+            .with_stack_size(old.stack_size());
+
+    assert_eq!(output(&old, 7), 47);
+    assert_eq!(output(&swapped, 7), 219);
+    assert_eq!(output(&swapped, 7), output(&new, 7));
+}
@@ -0,0 +1,35 @@
+//! GC proposal operators validate but have no lowering; they must fail with a
+//! dedicated diagnostic rather than a generic "unsupported opcode".
+
+use wasm_pvm::Error;
+use wasm_pvm::test_harness::*;
+
+#[test]
+fn ref_i31_reports_gc_limitation() {
+    let wat = r#"
+        (module
+            (func (export "main") (param i32 i32) (result i64)
+                (drop (ref.i31 (i32.const 7)))
+                (i64.const 0)
+            )
+        )
+    "#;
+    let err = compile_wat(wat)
+        .err()
+        .expect("expected compilation to fail on ref.i31");
+    let Error::Located { cause, .. } = &err else {
+        panic!("expected Error::Located, got {err:?}");
+    };
+    let Error::UnsupportedWasmOp { op, reason } = cause.as_ref() else {
+        panic!("expected Error::UnsupportedWasmOp, got {cause:?}");
+    };
+    assert_eq!(op, "RefI31");
+    assert!(
+        reason.contains("GC"),
+        "reason should name the GC proposal: {reason}"
+    );
+    assert!(
+        err.to_string().contains("Unsupported WASM operator RefI31"),
+        "{err}"
+    );
+}
@@ -0,0 +1,66 @@
+//! Tests for `CompileStats.exported_globals`: where each exported global is
+//! stored in PVM address space, so hosts can read it back after execution.
+
+use wasm_pvm::test_harness::{ExitReason, Interpreter, wat_to_wasm};
+use wasm_pvm::{CompileOptions, compile_with_stats};
+
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (global $hidden (mut i32) (i32.const 1))
+        (global $counter (mut i64) (i64.const 40))
+        (global $version i32 (i32.const 3))
+        (export "counter" (global $counter))
+        (export "version" (global $version))
+        (func (export "main") (param i32 i32) (result i64)
+            (global.set $counter (i64.add (global.get $counter) (i64.const 2)))
+            (i64.const 0)
+        )
+    )
+"#;
+
+#[test]
+fn exported_globals_are_reported_with_addresses() {
+    let wasm = wat_to_wasm(WAT).expect("wat");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    let exported: Vec<_> = stats
+        .exported_globals
+        .iter()
+        .map(|g| (g.name.as_str(), g.index, g.width, g.mutable))
+        .collect();
+    assert_eq!(
+        exported,
+        [("counter", 1, 8, true), ("version", 2, 4, false)]
+    );
+
+    let counter = &stats.exported_globals[0];
+    let version = &stats.exported_globals[1];
+    assert_ne!(counter.address, version.address);
+    for global in &stats.exported_globals {
+        assert!(
+            i64::from(global.address) + i64::from(global.width)
+                <= i64::from(stats.wasm_memory_base),
+            "{} slot overlaps linear memory",
+            global.name
+        );
+    }
+}
+
+#[test]
+fn exported_global_slot_holds_final_value() {
+    let wasm = wat_to_wasm(WAT).expect("wat");
+    let (program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+    let counter = stats
+        .exported_globals
+        .iter()
+        .find(|g| g.name == "counter")
+        .expect("counter exported");
+
+    let mut interp = Interpreter::new(&program, &[]);
+    assert_eq!(interp.run(), ExitReason::Halt);
+    let slot = interp
+        .read_memory(u32::try_from(counter.address).unwrap(), counter.width)
+        .expect("global slot readable");
+    assert_eq!(slot, 42u64.to_le_bytes());
+}
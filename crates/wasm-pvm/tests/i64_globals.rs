@@ -182,6 +182,30 @@ fn mixed_i32_i64_globals_compile_with_distinct_opcodes() {
     );
 }
 
+#[test]
+fn i64_global_initializer_keeps_high_bits() {
+    // `0x1_0000_0001` reads back as 1 if the initializer (or its rw_data
+    // slot) is ever narrowed to 32 bits. The i32 global after it checks the
+    // 8-byte slot doesn't spill into its neighbour.
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $g (mut i64) (i64.const 0x1_0000_0001))
+            (global $next (mut i32) (i32.const 7))
+            (func (export "main") (param i32 i32) (result i64)
+                (i64.store (i32.const 0) (global.get $g))
+                (i32.store (i32.const 8) (global.get $next))
+                (i64.const 51539607552)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output[..8], 0x1_0000_0001u64.to_le_bytes());
+    assert_eq!(result.output[8..], 7u32.to_le_bytes());
+}
+
 #[test]
 fn unsupported_global_type_is_rejected() {
     // v128 globals must error out at parse time rather than silently miscompile.
@@ -247,19 +271,18 @@ fn non_const_global_init_is_rejected() {
 }
 
 #[test]
-fn extended_const_init_is_rejected() {
-    // Extended constant expressions (i32.add of two consts) are legal WASM
-    // — wasmparser's EXTENDED_CONST feature is on by default. We can only
-    // evaluate single literals, so a multi-operator init must error rather
-    // than silently truncate to the first literal.
-    let wat = r#"
-        (module
-            (global $g (mut i32) (i32.add (i32.const 5) (i32.const 7)))
-            (func (export "main") (param i32 i32) (result i64) (i64.const 0))
-        )
-    "#;
-    assert!(
-        compile_wat(wat).is_err(),
-        "extended-const init expression (i32.add) must be rejected"
-    );
+fn fixture_reads_back_values_above_u32_max() {
+    let wat = include_str!("../../../tests/fixtures/wat/i64-globals.jam.wat");
+    let program = compile_wat(wat).expect("compile");
+    for (op, x, expected) in [
+        (0u32, 0u32, 1u32),
+        (1, 0, 0x2345_6789),
+        (2, 5, 6),
+        (3, 0, 8),
+    ] {
+        let args: Vec<u8> = [op, x].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let result = run_program(&program, &args);
+        assert_eq!(result.exit, ExitReason::Halt, "op {op}");
+        assert_eq!(result.output, expected.to_le_bytes(), "op {op}");
+    }
 }
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 use wasm_pvm::pvm::{Instruction, Opcode};
 use wasm_pvm::test_harness::*;
-use wasm_pvm::{CompileOptions, ImportAction};
+use wasm_pvm::{CompileOptions, ImportAction, OptimizationFlags};
 
 #[test]
 fn test_import_map_trap() {
@@ -199,3 +199,152 @@ fn test_import_map_ecalli_zero_args() {
         "Expected Ecalli {{ index: 3 }} for zero-arg import"
     );
 }
+
+#[test]
+fn test_parse_import_map_ecalli_forms() {
+    let map = wasm_pvm::parse_import_map("a = ecalli:5\nb = ecalli 6\nc = ecalli\t 7\n")
+        .expect("valid map");
+    assert_eq!(map["a"], ImportAction::Ecalli(5));
+    assert_eq!(map["b"], ImportAction::Ecalli(6));
+    assert_eq!(map["c"], ImportAction::Ecalli(7));
+
+    for bad in ["d = ecalli5", "d = ecalli", "d = ecalli 5x"] {
+        let err = wasm_pvm::parse_import_map(bad).expect_err(bad);
+        assert!(
+            matches!(err, wasm_pvm::Error::ImportMap(_)),
+            "{bad}: {err:?}"
+        );
+    }
+}
+
+const ECALLI_WAT: &str = include_str!("../../../tests/fixtures/wat/import-ecalli.jam.wat");
+const ECALLI_IMPORTS: &str = include_str!("../../../tests/fixtures/imports/import-ecalli.imports");
+
+fn ecalli_program(optimizations: OptimizationFlags) -> wasm_pvm::SpiProgram {
+    let options = CompileOptions {
+        import_map: Some(wasm_pvm::parse_import_map(ECALLI_IMPORTS).expect("import map")),
+        optimizations,
+        ..CompileOptions::default()
+    };
+    compile_wat_with_options(ECALLI_WAT, &options).expect("Failed to compile")
+}
+
+#[test]
+fn test_import_map_ecalli_instruction_sequence() {
+    // Unoptimized, so every argument is a fresh load and the result a store.
+    let program = ecalli_program(OptimizationFlags::all_disabled());
+    let instructions = extract_instructions(&program);
+    let at = instructions
+        .iter()
+        .position(|i| matches!(i, Instruction::Ecalli { index: 7 }))
+        .expect("Expected Ecalli { index: 7 }");
+
+    let mut arg_regs: Vec<u8> = instructions[at - 2..at]
+        .iter()
+        .filter_map(Instruction::dest_reg)
+        .collect();
+    arg_regs.sort_unstable();
+    assert_eq!(arg_regs, [7, 8], "{:#?}", &instructions[at - 2..=at]);
+    assert!(
+        instructions[at + 1].src_regs().contains(&Some(7)),
+        "result should be read from r7: {:?}",
+        instructions[at + 1]
+    );
+}
+
+#[test]
+fn test_import_map_ecalli_register_contract() {
+    let program = ecalli_program(OptimizationFlags::default());
+    let mut args = 20u32.to_le_bytes().to_vec();
+    args.extend_from_slice(&22u32.to_le_bytes());
+
+    let mut interp = Interpreter::new(&program, &args);
+    assert_eq!(interp.run(), ExitReason::HostCall(7));
+    assert_eq!(interp.regs()[7], 20);
+    assert_eq!(interp.regs()[8], 22);
+
+    // The host answers in r7 and resumes after the `ecalli`.
+    interp.set_reg(7, 41);
+    assert_eq!(interp.run(), ExitReason::Halt);
+    assert_eq!(interp.output(), 42u32.to_le_bytes());
+}
+
+/// `read` is mapped only by the module's own `wasm-pvm-imports` section.
+const EMBEDDED_MAP_WAT: &str = r#"
+    (module
+        (@custom "wasm-pvm-imports" "abort = trap\nread = ecalli:5\n")
+        (import "env" "read" (func $read (param i32 i32) (result i64)))
+        (memory (export "memory") 1)
+        (func (export "main") (param i32 i32) (result i32)
+            (drop (call $read (i32.const 100) (i32.const 200)))
+            (i32.const 0)
+        )
+    )
+"#;
+
+fn has_ecalli(instructions: &[Instruction], index: u32) -> bool {
+    instructions
+        .iter()
+        .any(|i| matches!(i, Instruction::Ecalli { index: n } if *n == index))
+}
+
+#[test]
+fn test_embedded_import_map_applies_without_options() {
+    let program = compile_wat(EMBEDDED_MAP_WAT).expect("Failed to compile");
+    let instructions = extract_instructions(&program);
+    assert!(
+        has_ecalli(&instructions, 5),
+        "Expected Ecalli {{ index: 5 }} from the embedded import map"
+    );
+}
+
+#[test]
+fn test_explicit_import_map_overrides_embedded() {
+    let mut map = BTreeMap::new();
+    map.insert("read".to_string(), ImportAction::Ecalli(7));
+
+    let program = compile_wat_with_imports(EMBEDDED_MAP_WAT, map).expect("Failed to compile");
+    let instructions = extract_instructions(&program);
+    assert!(has_ecalli(&instructions, 7), "explicit mapping should win");
+    assert!(
+        !has_ecalli(&instructions, 5),
+        "embedded mapping should be overridden"
+    );
+}
+
+#[test]
+fn test_embedded_import_map_syntax_error() {
+    let wat = r#"
+        (module
+            (@custom "wasm-pvm-imports" "read = ecalli:five\n")
+            (import "env" "read" (func $read (result i64)))
+            (func (export "main") (param i32 i32) (result i32)
+                (drop (call $read))
+                (i32.const 0)
+            )
+        )
+    "#;
+    let err = compile_wat(wat).err().expect("malformed embedded map");
+    assert!(
+        matches!(err, wasm_pvm::Error::ImportMap(_)),
+        "expected Error::ImportMap, got {err:?}"
+    );
+    assert!(err.to_string().contains("line 1"), "{err}");
+}
+
+#[test]
+fn test_embedded_adapter_replaces_import() {
+    let wat = r#"
+        (module
+            (@custom "wasm-pvm-adapter"
+                "(module (func (export \"console.log\") (param i32)))")
+            (import "env" "console.log" (func $log (param i32)))
+            (func (export "main") (param i32 i32) (result i32)
+                (call $log (i32.const 42))
+                (i32.const 0)
+            )
+        )
+    "#;
+    // Without the adapter, `console.log` would be an unresolved import.
+    compile_wat(wat).expect("adapter from custom section resolves the import");
+}
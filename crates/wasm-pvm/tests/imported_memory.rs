@@ -0,0 +1,58 @@
+//! Modules that import their linear memory instead of defining it. The import
+//! is mapped onto the region a defined memory would use, sized by the import's
+//! limits, with active data segments written into it.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, compile_with_stats};
+
+/// Returns the 4 bytes at address 0x10 followed by `memory.size`.
+const IMPORTED_WAT: &str = r#"
+    (module
+        (import "env" "memory" (memory 3 8))
+        (data (i32.const 0x10) "\de\ad\be\ef")
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0x14) (memory.size))
+            ;; 8 bytes at address 0x10.
+            (i64.const 34359738384)
+        )
+    )
+"#;
+
+#[test]
+fn data_segments_land_in_the_imported_memory() {
+    let program = compile_wat(IMPORTED_WAT).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output[..4], [0xde, 0xad, 0xbe, 0xef]);
+    // The import's minimum, not the 1-page default for memory-less modules.
+    assert_eq!(result.output[4..], 3u32.to_le_bytes());
+}
+
+#[test]
+fn imported_memory_is_reported_at_wasm_memory_base() {
+    let wasm = wat_to_wasm(IMPORTED_WAT).expect("wat");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    let memory = stats.imported_memory.expect("memory import recorded");
+    assert_eq!(memory.module, "env");
+    assert_eq!(memory.name, "memory");
+    assert_eq!(memory.base, stats.wasm_memory_base);
+    assert_eq!(memory.initial_bytes, 3 * 65536);
+    assert!(stats.exported_memory.is_none());
+}
+
+#[test]
+fn defined_memory_is_not_reported_as_imported() {
+    let wasm = wat_to_wasm(
+        r#"
+        (module
+            (memory 1)
+            (func (export "main") (param i32 i32) (result i64) (i64.const 0))
+        )
+    "#,
+    )
+    .expect("wat");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    assert!(stats.imported_memory.is_none());
+}
@@ -0,0 +1,36 @@
+//! A declared minimum well above the default memory floor is mapped in full.
+
+use wasm_pvm::test_harness::*;
+
+/// 2048 pages = 128 MB of linear memory.
+const PAGES: u32 = 2048;
+
+#[test]
+fn last_word_of_large_initial_memory_is_writable() {
+    let last = PAGES * 65536 - 4;
+    let wat = format!(
+        r#"
+        (module
+            (memory {PAGES})
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const {last}) (i32.load (local.get $p)))
+                (i32.store (i32.const 0) (i32.load (i32.const {last})))
+                (i32.store (i32.const 4) (memory.size))
+                (i64.const 34359738368)
+            )
+        )
+        "#
+    );
+    let program = compile_wat(&wat).expect("compile");
+    assert!(
+        u32::from(program.heap_pages()) >= PAGES * 16,
+        "heap_pages {} cannot hold {PAGES} wasm pages",
+        program.heap_pages()
+    );
+
+    let result = run_program(&program, &0x1234_5678i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    let mut expected = 0x1234_5678i32.to_le_bytes().to_vec();
+    expected.extend_from_slice(&PAGES.to_le_bytes());
+    assert_eq!(result.output, expected);
+}
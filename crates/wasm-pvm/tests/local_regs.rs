@@ -0,0 +1,84 @@
+//! `CompileOptions::local_regs`: passing fewer parameters in r9-r12.
+//!
+//! With `local_regs: Some(3)` only r9-r11 carry parameters and are
+//! saved/allocated; the fourth and later parameters go through the overflow
+//! area, and r12 is never touched.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction};
+
+/// `$mix` takes five params so that with three local registers both the
+/// fourth and fifth spill to the overflow area. `main` loops over it to force
+/// values across calls. Output: the final accumulator.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func $mix (param $a i32) (param $b i32) (param $c i32) (param $d i32) (param $e i32)
+            (result i32)
+            (i32.add
+                (i32.add (i32.mul (local.get $a) (i32.const 10000))
+                         (i32.mul (local.get $b) (i32.const 1000)))
+                (i32.add (i32.mul (local.get $c) (i32.const 100))
+                         (i32.add (i32.mul (local.get $d) (i32.const 10)) (local.get $e)))))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $i i32) (local $acc i32)
+            (block $done
+                (loop $next
+                    (br_if $done (i32.ge_u (local.get $i) (i32.load (local.get $p))))
+                    (local.set $acc
+                        (i32.add (local.get $acc)
+                            (call $mix (i32.const 1) (i32.const 2) (i32.const 3)
+                                       (local.get $i) (i32.add (local.get $i) (i32.const 5)))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $next)))
+            (i32.store (i32.const 0) (local.get $acc))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn with_local_regs(local_regs: Option<usize>) -> CompileOptions {
+    CompileOptions {
+        local_regs,
+        ..CompileOptions::default()
+    }
+}
+
+#[test]
+fn three_local_regs_matches_default() {
+    let default = compile_wat(WAT).expect("compile default");
+    let reduced = compile_wat_with_options(WAT, &with_local_regs(Some(3))).expect("compile");
+
+    for n in [0i32, 1, 3] {
+        let expected = run_program(&default, &n.to_le_bytes());
+        let actual = run_program(&reduced, &n.to_le_bytes());
+        assert_eq!(expected.exit, ExitReason::Halt, "n = {n}");
+        assert_eq!(actual.exit, expected.exit, "n = {n}");
+        assert_eq!(actual.output, expected.output, "n = {n}");
+    }
+    // Per call: 12300 + 10 * i + (i + 5).
+    let result = run_program(&reduced, &3i32.to_le_bytes());
+    assert_eq!(result.output, (3 * 12305 + 33).to_le_bytes());
+}
+
+#[test]
+fn three_local_regs_never_touches_r12() {
+    let program = compile_wat_with_options(WAT, &with_local_regs(Some(3))).expect("compile");
+    let instructions = extract_instructions(&program);
+    let touches_r12 =
+        |i: &Instruction| i.dest_reg() == Some(12) || i.src_regs().contains(&Some(12));
+    assert!(
+        !instructions.iter().any(touches_r12),
+        "r12 used with local_regs = 3:\n{instructions:#?}"
+    );
+}
+
+#[test]
+fn out_of_range_local_regs_is_rejected() {
+    for bad in [0, 5] {
+        assert!(
+            compile_wat_with_options(WAT, &with_local_regs(Some(bad))).is_err(),
+            "local_regs = {bad} should be rejected"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! A `br_if` back to the enclosing `loop` (a bottom-tested `continue`) lowers
+//! to a single backward transfer: either one conditional branch to the header,
+//! or an exit branch followed by the loop-carried moves and one `Jump` back.
+//! The not-taken path never gets a jump of its own.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{Instruction, SpiProgram};
+
+/// Sums the argument bytes (at least one) with a `br_if` back-edge.
+/// Output: the sum.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $i i32) (local $acc i32)
+            (loop $next
+                (local.set $acc
+                    (i32.add (local.get $acc)
+                        (i32.load8_u (i32.add (local.get $p) (local.get $i)))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $next (i32.lt_u (local.get $i) (local.get $l))))
+            (i32.store (i32.const 0) (local.get $acc))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+/// `(offset, instruction)` for every instruction of `program`.
+fn listing(program: &SpiProgram) -> Vec<(i64, Instruction)> {
+    let blob = program.code();
+    blob.instructions()
+        .iter()
+        .enumerate()
+        .map(|(idx, instr)| {
+            (
+                i64::try_from(blob.offset_of(idx).unwrap()).unwrap(),
+                instr.clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn br_if_back_edge_is_the_loops_only_jump() {
+    let program = compile_wat(WAT).expect("compile");
+    let code = listing(&program);
+
+    let back_edges: Vec<usize> = code
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, instr))| instr.jump_offset().is_some_and(|rel| rel < 0))
+        .map(|(idx, _)| idx)
+        .collect();
+    assert_eq!(back_edges.len(), 1, "one loop, one back edge: {code:#?}");
+    let back_edge = back_edges[0];
+    let (at, instr) = &code[back_edge];
+    let header = at + i64::from(instr.jump_offset().unwrap());
+
+    // Inside the loop, the only unconditional jump (if any) is the back edge.
+    let loop_jumps: Vec<&(i64, Instruction)> = code
+        .iter()
+        .filter(|(offset, instr)| {
+            (header..=*at).contains(offset) && matches!(instr, Instruction::Jump { .. })
+        })
+        .collect();
+    assert!(
+        loop_jumps.iter().all(|(offset, _)| offset == at),
+        "extra jumps in the loop: {loop_jumps:?}\n{code:#?}"
+    );
+    // A back-edge `Jump` needs loop-carried moves in front of it; with none,
+    // the exit branch must not just hop over it.
+    if matches!(instr, Instruction::Jump { .. }) {
+        let exit = code.get(back_edge + 1).map(|(offset, _)| *offset);
+        let (from, before) = &code[back_edge - 1];
+        if let Some(rel) = before.jump_offset() {
+            assert_ne!(
+                Some(from + i64::from(rel)),
+                exit,
+                "branch over the back-edge jump should be a single branch: {code:#?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn br_if_loop_sums_its_input() {
+    let program = compile_wat(WAT).expect("compile");
+    for input in [&[7u8][..], &[1, 2, 3, 250], &[255; 16]] {
+        let expected: u32 = input.iter().map(|&b| u32::from(b)).sum();
+        let result = run_program(&program, input);
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.output, expected.to_le_bytes(), "input {input:?}");
+    }
+}
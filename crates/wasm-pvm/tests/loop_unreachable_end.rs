@@ -84,3 +84,27 @@ fn dummy_block_frame_with_terminated_merge_compiles() {
 
     compile_wat(wat).expect("compilation should succeed");
 }
+
+/// A reachable `loop (result i32)` must keep its fall-through value on the
+/// operand stack past `End` (the loop `End` used to truncate it away, which
+/// the frame-balance assertion now catches).
+#[test]
+fn loop_result_survives_end() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const 0)
+                    (i32.add
+                        (loop (result i32) (i32.load (local.get $p)))
+                        (i32.const 1)))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &41i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 42i32.to_le_bytes());
+}
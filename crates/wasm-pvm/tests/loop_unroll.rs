@@ -0,0 +1,93 @@
+//! `OptimizationFlags::unroll`: full unrolling of small constant-trip-count
+//! loops in the LLVM pipeline.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, OptimizationFlags, SpiProgram};
+
+/// Sums four words from the argument buffer in a 4-iteration loop with a
+/// bottom-tested back edge. Output: the sum.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $i i32) (local $acc i32)
+            (loop $next
+                (local.set $acc
+                    (i32.add (local.get $acc)
+                        (i32.load (i32.add (local.get $p) (i32.shl (local.get $i) (i32.const 2))))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if $next (i32.lt_u (local.get $i) (i32.const 4))))
+            (i32.store (i32.const 0) (local.get $acc))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn compile_unrolled(unroll: Option<u32>) -> SpiProgram {
+    let options = CompileOptions {
+        optimizations: OptimizationFlags {
+            unroll,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_wat_with_options(WAT, &options).expect("compile")
+}
+
+fn has_backward_branch(program: &SpiProgram) -> bool {
+    extract_instructions(program).iter().any(|i| match i {
+        Instruction::Jump { offset }
+        | Instruction::BranchNeImm { offset, .. }
+        | Instruction::BranchEqImm { offset, .. }
+        | Instruction::BranchGeSImm { offset, .. }
+        | Instruction::BranchLtUImm { offset, .. }
+        | Instruction::BranchLeUImm { offset, .. }
+        | Instruction::BranchGeUImm { offset, .. }
+        | Instruction::BranchGtUImm { offset, .. }
+        | Instruction::BranchLtSImm { offset, .. }
+        | Instruction::BranchLeSImm { offset, .. }
+        | Instruction::BranchGtSImm { offset, .. }
+        | Instruction::BranchEq { offset, .. }
+        | Instruction::BranchNe { offset, .. }
+        | Instruction::BranchGeU { offset, .. }
+        | Instruction::BranchLtU { offset, .. }
+        | Instruction::BranchLtS { offset, .. }
+        | Instruction::BranchGeS { offset, .. } => *offset < 0,
+        _ => false,
+    })
+}
+
+fn words(values: [i32; 4]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[test]
+fn four_iteration_loop_loses_its_back_edge() {
+    assert!(
+        has_backward_branch(&compile_unrolled(None)),
+        "the rolled loop should keep its back edge"
+    );
+    assert!(
+        !has_backward_branch(&compile_unrolled(Some(4))),
+        "unroll = Some(4) should fully unroll a 4-iteration loop"
+    );
+}
+
+#[test]
+fn trip_count_above_threshold_stays_rolled() {
+    assert!(has_backward_branch(&compile_unrolled(Some(3))));
+}
+
+#[test]
+fn unrolled_loop_computes_the_same_sum() {
+    let rolled = compile_unrolled(None);
+    let unrolled = compile_unrolled(Some(8));
+    for input in [[1, 2, 3, 4], [-5, 100, 0, 7]] {
+        let expected = input.iter().sum::<i32>().to_le_bytes();
+        for program in [&rolled, &unrolled] {
+            let result = run_program(program, &words(input));
+            assert_eq!(result.exit, ExitReason::Halt);
+            assert_eq!(result.output, expected, "input {input:?}");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Tests for `CompileStats.exported_memory`: where a module's exported linear
+//! memory ends up in PVM address space, so hosts can find it.
+
+use wasm_pvm::test_harness::wat_to_wasm;
+use wasm_pvm::{CompileOptions, compile_with_stats};
+
+#[test]
+fn exported_memory_is_reported_at_wasm_memory_base() {
+    let wasm = wat_to_wasm(
+        r#"
+        (module
+            (memory 2)
+            (export "memory" (memory 0))
+            (func (export "main") (param i32 i32) (result i64) (i64.const 0))
+        )
+    "#,
+    )
+    .expect("wat");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    let memory = stats.exported_memory.expect("memory export recorded");
+    assert_eq!(memory.name, "memory");
+    assert_eq!(memory.base, stats.wasm_memory_base);
+    assert_eq!(memory.initial_bytes, 2 * 65536);
+}
+
+#[test]
+fn unexported_memory_is_not_reported() {
+    let wasm = wat_to_wasm(
+        r#"
+        (module
+            (memory 1)
+            (func (export "main") (param i32 i32) (result i64) (i64.const 0))
+        )
+    "#,
+    )
+    .expect("wat");
+    let (_program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+
+    assert!(stats.exported_memory.is_none());
+}
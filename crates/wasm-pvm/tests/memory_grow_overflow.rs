@@ -0,0 +1,65 @@
+//! `memory.grow` with deltas whose page or byte counts overflow must fail
+//! with -1 and leave the memory size unchanged.
+
+use wasm_pvm::test_harness::*;
+
+/// Grows by the delta read from the args, then stores the grow result at 0
+/// and `memory.size` at 4. Output: those two words.
+fn module(max_pages: u32) -> String {
+    main_module_wat(
+        &format!("(memory 1 {max_pages})"),
+        r#"
+            (i32.store (i32.const 0) (memory.grow (i32.load (local.get $p))))
+            (i32.store (i32.const 4) (memory.size))
+        "#,
+        8,
+    )
+}
+
+/// Returns `(grow result, memory.size after)`.
+fn grow(max_pages: u32, delta: u32) -> (i32, i32) {
+    let program = compile_wat(&module(max_pages)).expect("compile");
+    let result = run_program(&program, &delta.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt, "delta {delta:#x}");
+    let word = |i: usize| i32::from_le_bytes(result.output[i..i + 4].try_into().unwrap());
+    (word(0), word(4))
+}
+
+#[test]
+fn grow_by_u32_max_fails() {
+    assert_eq!(grow(16, 0xFFFF_FFFF), (-1, 1));
+    assert_eq!(grow(65536, 0xFFFF_FFFF), (-1, 1));
+}
+
+#[test]
+fn grow_with_wrapping_page_count_fails() {
+    // 1 + 0xFFFF_FFFF wraps to 0 pages; 1 + 0x8000_0000 sets the i32 sign bit.
+    for delta in [0xFFFF_FFFF, 0xFFFF_FFFE, 0x8000_0000, 0x7FFF_FFFF] {
+        assert_eq!(grow(65536, delta), (-1, 1), "delta {delta:#x}");
+    }
+}
+
+#[test]
+fn grow_beyond_host_memory_fails_without_resizing() {
+    // Within the declared max, but 0xFFFF pages is ~4 GB of sbrk: more than
+    // the address space holds, and a sign-extended byte count as a 32-bit
+    // shift. The host refuses, so the size must not be committed.
+    assert_eq!(grow(65536, 0xFFFF), (-1, 1));
+}
+
+#[test]
+fn small_grow_still_succeeds() {
+    assert_eq!(grow(16, 2), (1, 3));
+    assert_eq!(grow(16, 0), (1, 1));
+}
+
+#[test]
+fn grow_stops_at_the_declared_max() {
+    let wat = include_str!("../../../tests/fixtures/wat/memory-grow-max.jam.wat");
+    let program = compile_wat(wat).expect("compile");
+    for (op, expected) in [(0u32, 1i32), (1, 3), (2, -1), (3, 3), (4, -1), (5, 1)] {
+        let result = run_program(&program, &op.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "op {op}");
+        assert_eq!(result.output, expected.to_le_bytes(), "op {op}");
+    }
+}
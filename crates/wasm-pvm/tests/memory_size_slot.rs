@@ -0,0 +1,45 @@
+//! The compiler-managed memory-size slot sits in front of the user globals;
+//! writing the globals around it must never change what `memory.size` sees.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, compile_with_stats};
+
+const GLOBALS: usize = 300;
+
+/// `GLOBALS` mutable i64 globals. `main` grows memory by one page, sets the
+/// first and last global to all ones, then stores `memory.size` at 0 and the
+/// first global at 8.
+fn module() -> String {
+    let globals: String = (0..GLOBALS)
+        .map(|i| format!("(global $g{i} (mut i64) (i64.const {i}))\n"))
+        .collect();
+    let last = GLOBALS - 1;
+    main_module_wat(
+        &format!("(memory 1 4)\n{globals}"),
+        &format!(
+            r#"
+                (drop (memory.grow (i32.const 1)))
+                (global.set $g0 (i64.const -1))
+                (global.set $g{last} (i64.const -1))
+                (i32.store (i32.const 0) (memory.size))
+                (i64.store (i32.const 8) (global.get $g0))
+            "#
+        ),
+        16,
+    )
+}
+
+#[test]
+fn user_globals_start_after_the_memory_size_slot() {
+    let wasm = wat_to_wasm(&module()).expect("wat");
+    let (program, stats) = compile_with_stats(&wasm, &CompileOptions::default()).expect("compile");
+    // The slot's 4 bytes precede GLOBALS * 8 bytes of globals.
+    assert_eq!(stats.globals_region_bytes, 4 + GLOBALS * 8);
+    assert_eq!(program.rw_data()[..4], 1u32.to_le_bytes());
+    assert_eq!(program.rw_data()[4..12], 0u64.to_le_bytes());
+
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output[..4], 2u32.to_le_bytes());
+    assert_eq!(result.output[8..], u64::MAX.to_le_bytes());
+}
@@ -0,0 +1,85 @@
+//! `CompileOptions::split_misaligned_accesses`: accesses whose alignment hint
+//! is below their natural alignment become byte accesses; the rest stay wide.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Opcode};
+
+fn split() -> CompileOptions {
+    CompileOptions {
+        split_misaligned_accesses: true,
+        ..CompileOptions::default()
+    }
+}
+
+/// Reads the `i32` at `args + 1` through a byte-aligned load.
+const BYTE_ALIGNED_LOAD: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0)
+                (i32.load align=1 (i32.add (local.get $p) (i32.const 1))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+#[test]
+fn byte_aligned_i32_load_splits_into_four_byte_loads() {
+    let wide = compile_wat(BYTE_ALIGNED_LOAD).expect("compile");
+    let bytes = compile_wat_with_options(BYTE_ALIGNED_LOAD, &split()).expect("compile");
+    let wide_instrs = extract_instructions(&wide);
+    let byte_instrs = extract_instructions(&bytes);
+
+    assert!(has_opcode(&wide_instrs, Opcode::LoadIndU32));
+    assert_eq!(
+        count_opcode(&byte_instrs, Opcode::LoadIndU8),
+        count_opcode(&wide_instrs, Opcode::LoadIndU8) + 4
+    );
+    assert!(!has_opcode(&byte_instrs, Opcode::LoadIndU32));
+
+    let args = [0xAA, 0x78, 0x56, 0x34, 0x12];
+    for program in [&wide, &bytes] {
+        let result = run_program(program, &args);
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.output, 0x1234_5678i32.to_le_bytes());
+    }
+}
+
+#[test]
+fn naturally_aligned_access_stays_wide() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store align=4 (i32.const 0) (i32.load align=4 (local.get $p)))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let wide = compile_wat(wat).expect("compile");
+    let with_option = compile_wat_with_options(wat, &split()).expect("compile");
+    assert_eq!(
+        extract_instructions(&with_option),
+        extract_instructions(&wide)
+    );
+}
+
+#[test]
+fn split_signed_load_and_store_keep_their_semantics() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i64.store align=1 (i32.const 0)
+                    (i64.load32_s align=2 (i32.add (local.get $p) (i32.const 1))))
+                (i64.const 34359738368)
+            )
+        )
+    "#;
+    let program = compile_wat_with_options(wat, &split()).expect("compile");
+    assert!(count_opcode(&extract_instructions(&program), Opcode::StoreIndU8) >= 8);
+
+    let result = run_program(&program, &[0x00, 0x78, 0x56, 0x34, 0x92]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 0xFFFF_FFFF_9234_5678u64.to_le_bytes());
+}
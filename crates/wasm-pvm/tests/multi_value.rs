@@ -0,0 +1,173 @@
+//! Multi-value results: a function's first result returns in r7, the rest
+//! through the param-overflow area; block/if frames carry one phi per result.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Error, OptimizationFlags};
+
+/// `main` reads an i64 `x` from the arguments and writes six i64s:
+/// `x / 7`, `x % 7` (from `$divmod`, 2 results), then `x`, `2x`, `3x` (from
+/// `$triple`, 3 results, called indirectly), then `x + 1` (from a 2-result
+/// `block` left by `br`).
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (type $triple_t (func (param i64) (result i64 i64 i64)))
+        (table 1 funcref)
+        (elem (i32.const 0) $triple)
+        (func $divmod (param $a i64) (param $b i64) (result i64 i64)
+            (i64.div_u (local.get $a) (local.get $b))
+            (i64.rem_u (local.get $a) (local.get $b)))
+        (func $triple (param $x i64) (result i64 i64 i64)
+            (local.get $x)
+            (i64.mul (local.get $x) (i64.const 2))
+            (i64.mul (local.get $x) (i64.const 3)))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $x i64) (local $a i64) (local $b i64) (local $c i64)
+            (local.set $x (i64.load (local.get $p)))
+
+            (call $divmod (local.get $x) (i64.const 7))
+            (local.set $b)
+            (local.set $a)
+            (i64.store (i32.const 0) (local.get $a))
+            (i64.store (i32.const 8) (local.get $b))
+
+            (call_indirect (type $triple_t) (local.get $x) (i32.const 0))
+            (local.set $c)
+            (local.set $b)
+            (local.set $a)
+            (i64.store (i32.const 16) (local.get $a))
+            (i64.store (i32.const 24) (local.get $b))
+            (i64.store (i32.const 32) (local.get $c))
+
+            (block $pair (result i64 i64)
+                (local.get $x)
+                (i64.const 1)
+                (br $pair)
+            )
+            (i64.add)
+            (local.set $a)
+            (i64.store (i32.const 40) (local.get $a))
+
+            (i64.const 206158430208)
+        )
+    )
+"#;
+
+fn expected(x: u64) -> Vec<u8> {
+    [x / 7, x % 7, x, 2 * x, 3 * x, x + 1]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+fn run(options: &CompileOptions) {
+    let program = compile_wat_with_options(WAT, options).expect("compile");
+    for x in [0u64, 100, 0x1234_5678_9abc] {
+        let result = run_program(&program, &x.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "x = {x}");
+        assert_eq!(result.output, expected(x), "x = {x}");
+    }
+}
+
+#[test]
+fn two_and_three_i64_results() {
+    run(&CompileOptions::default());
+}
+
+#[test]
+fn two_and_three_i64_results_without_inlining() {
+    // Keep the callees out of line so the results really cross a call.
+    run(&CompileOptions {
+        optimizations: OptimizationFlags {
+            inlining: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    });
+}
+
+#[test]
+fn multi_value_if_and_br_if() {
+    // `$pick` returns (a, b) or (b, a) from an `if`, and `$clamp` leaves a
+    // two-result block early with `br_if`.
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $pick (param $swap i32) (param $a i64) (param $b i64) (result i64 i64)
+                (if (result i64 i64) (local.get $swap)
+                    (then (local.get $b) (local.get $a))
+                    (else (local.get $a) (local.get $b))))
+            (func $clamp (param $v i64) (result i64 i64)
+                (block $done (result i64 i64)
+                    (i64.const 100)
+                    (i64.const 1)
+                    (br_if $done (i64.gt_u (local.get $v) (i64.const 100)))
+                    (drop)
+                    (drop)
+                    (local.get $v)
+                    (i64.const 0)))
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $a i64) (local $b i64)
+                (call $pick (i32.load8_u (local.get $p)) (i64.const 3) (i64.const 4))
+                (local.set $b)
+                (local.set $a)
+                (i64.store (i32.const 0) (i64.sub (i64.mul (local.get $a) (i64.const 10)) (local.get $b)))
+                (call $clamp (i64.load8_u offset=1 (local.get $p)))
+                (local.set $b)
+                (local.set $a)
+                (i64.store (i32.const 8) (local.get $a))
+                (i64.store (i32.const 16) (local.get $b))
+                (i64.const 103079215104)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let output = |args: &[u8]| {
+        let result = run_program(&program, args);
+        assert_eq!(result.exit, ExitReason::Halt, "{args:?}");
+        result
+            .output
+            .chunks(8)
+            .map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(output(&[0, 42]), [26, 42, 0]);
+    assert_eq!(output(&[1, 200]), [37, 100, 1]);
+}
+
+#[test]
+fn multi_value_entry_function_is_rejected() {
+    let wat = r#"
+        (module
+            (func (export "main") (param i32 i32) (result i32 i32)
+                (i32.const 0) (i32.const 4))
+        )
+    "#;
+    let err = compile_wat(wat)
+        .err()
+        .expect("entry functions return one packed value");
+    assert!(
+        matches!(&err, Error::Unsupported(msg) if msg.contains("entry function")),
+        "{err}"
+    );
+}
+
+#[test]
+fn too_many_results_are_rejected() {
+    let results = "i64 ".repeat(wasm_pvm::memory_layout::MAX_RESULTS + 1);
+    let values = "(i64.const 0)".repeat(wasm_pvm::memory_layout::MAX_RESULTS + 1);
+    let wat = format!(
+        r#"
+        (module
+            (func $many (result {results}) {values})
+            (func (export "main") (param i32 i32) (result i64)
+                (i64.const 0))
+        )
+        "#
+    );
+    let err = compile_wat(&wat).err().expect("over the result cap");
+    assert!(
+        matches!(&err, Error::Unsupported(msg) if msg.contains("results")),
+        "{err}"
+    );
+}
@@ -0,0 +1,99 @@
+//! A `return` nested several blocks deep leaves through the full epilogue:
+//! callee-saved registers, SP and the return address are all restored, so
+//! the caller's live values survive.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, OptimizationFlags};
+
+/// `$find(p, n, target)` scans `n` bytes at `p`, summing `$weight` of each
+/// byte seen, and returns `i * 1000 + sum` from inside `block`/`loop`/`block`/`if`
+/// at the first byte equal to `target`, or -1. `main` takes the target as the
+/// first argument byte and the haystack as the rest; it stores `$find`'s
+/// result, then `x = len * 7 + target` and `len`, both live across the call.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func $weight (param $b i32) (result i32)
+            (i32.mul (local.get $b) (i32.const 3)))
+        (func $find (param $p i32) (param $n i32) (param $target i32) (result i32)
+            (local $i i32) (local $acc i32) (local $b i32)
+            (block $outer
+                (loop $next
+                    (block $inner
+                        (br_if $outer (i32.ge_u (local.get $i) (local.get $n)))
+                        (local.set $b (i32.load8_u (i32.add (local.get $p) (local.get $i))))
+                        (local.set $acc (i32.add (local.get $acc) (call $weight (local.get $b))))
+                        (if (i32.eq (local.get $b) (local.get $target))
+                            (then
+                                (return
+                                    (i32.add
+                                        (i32.mul (local.get $i) (i32.const 1000))
+                                        (local.get $acc))))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $next)))
+            (i32.const -1))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $target i32) (local $x i32)
+            (local.set $target (i32.load8_u (local.get $p)))
+            (local.set $x (i32.add (i32.mul (local.get $l) (i32.const 7)) (local.get $target)))
+            (i32.store (i32.const 0)
+                (call $find
+                    (i32.add (local.get $p) (i32.const 1))
+                    (i32.sub (local.get $l) (i32.const 1))
+                    (local.get $target)))
+            (i32.store (i32.const 4) (local.get $x))
+            (i32.store (i32.const 8) (local.get $l))
+            (i64.const 51539607552)
+        )
+    )
+"#;
+
+fn expected(args: &[u8]) -> Vec<u8> {
+    let (target, haystack) = (args[0], &args[1..]);
+    let mut acc = 0i32;
+    let mut found = -1i32;
+    for (i, &b) in haystack.iter().enumerate() {
+        acc += i32::from(b) * 3;
+        if b == target {
+            found = i32::try_from(i).unwrap() * 1000 + acc;
+            break;
+        }
+    }
+    let len = i32::try_from(args.len()).unwrap();
+    [found, len * 7 + i32::from(target), len]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+fn run(options: &CompileOptions) {
+    let program = compile_wat_with_options(WAT, options).expect("compile");
+    for args in [
+        &[4u8, 1, 2, 3, 4, 5][..],
+        &[9, 9],
+        &[7, 1, 2, 3],
+        &[200, 10, 20, 30, 40, 50, 60, 70, 200, 1],
+    ] {
+        let result = run_program(&program, args);
+        assert_eq!(result.exit, ExitReason::Halt, "args {args:?}");
+        assert_eq!(result.output, expected(args), "args {args:?}");
+    }
+}
+
+#[test]
+fn return_from_nested_blocks() {
+    run(&CompileOptions::default());
+}
+
+#[test]
+fn return_from_nested_blocks_without_inlining() {
+    // Keep `$find` and `$weight` out of line so the early return really
+    // crosses a frame that saved callee-saved registers.
+    run(&CompileOptions {
+        optimizations: OptimizationFlags {
+            inlining: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    });
+}
@@ -0,0 +1,58 @@
+//! `CompileOptions::validate` only gates the upfront `wasmparser::validate`
+//! pass: a valid module compiles to the same bytes either way, and an
+//! invalid one is still rejected, just not by the validator.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Error, compile_with_options};
+
+fn unvalidated() -> CompileOptions {
+    CompileOptions {
+        validate: false,
+        ..CompileOptions::default()
+    }
+}
+
+#[test]
+fn valid_module_compiles_identically_without_validation() {
+    let wasm = wat_to_wasm(include_str!(
+        "../../../tests/fixtures/wat/block-params.jam.wat"
+    ))
+    .expect("wat");
+    let validated = compile_with_options(&wasm, &CompileOptions::default()).expect("compile");
+    let trusted = compile_with_options(&wasm, &unvalidated()).expect("compile");
+    assert_eq!(validated.encode(), trusted.encode());
+}
+
+#[test]
+fn skipping_validation_changes_how_invalid_input_fails() {
+    // `i32.add` on an empty operand stack: well-formed, but ill-typed.
+    let wasm = wat_to_wasm(
+        r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                i32.add
+                drop
+                (i64.const 0)
+            )
+        )
+        "#,
+    )
+    .expect("wat");
+
+    let err = compile_with_options(&wasm, &CompileOptions::default())
+        .err()
+        .expect("validator rejects the module");
+    assert!(
+        matches!(&err, Error::Internal(msg) if msg.starts_with("WASM validation error")),
+        "{err}"
+    );
+
+    let err = compile_with_options(&wasm, &unvalidated())
+        .err()
+        .expect("translation still rejects the module");
+    assert!(
+        !err.to_string().contains("WASM validation error"),
+        "validation ran anyway: {err}"
+    );
+}
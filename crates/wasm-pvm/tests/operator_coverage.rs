@@ -1047,6 +1047,157 @@ fn test_br_table() {
     // optimized via immediates or data loads.
 }
 
+/// `br_if` on a fresh comparison should fuse into a single compare-and-branch
+/// instead of materializing the boolean and testing it with `BranchNeImm`.
+#[test]
+fn test_br_if_on_compare_fuses_into_branch() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param i32 i32) (result i32)
+                (block $skip
+                    (br_if $skip (i32.lt_s (local.get 0) (local.get 1)))
+                    (i32.store (i32.const 0) (local.get 0))
+                )
+                (i32.load (i32.const 0))
+            )
+        )
+    "#;
+
+    let program = compile_wat(wat).expect("compile");
+    let instructions = extract_instructions(&program);
+
+    assert_eq!(
+        count_opcode(&instructions, Opcode::BranchLtS),
+        1,
+        "br_if (i32.lt_s x y) should emit exactly one BranchLtS.\nInstructions:\n{}",
+        instructions
+            .iter()
+            .map(|i| format!("  {i:?}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+    assert!(
+        !has_opcode(&instructions, Opcode::SetLtS),
+        "the comparison result should not be materialized"
+    );
+}
+
+/// `br_if` on a comparison against a constant, taken when the comparison
+/// holds or (under `i32.eqz`) when it fails, should become a single
+/// immediate branch of the predicate's signedness, never a `Set*` followed
+/// by a branch on zero.
+#[test]
+fn test_br_if_on_constant_compare_uses_branch_imm() {
+    const SIGNED: [Opcode; 4] = [
+        Opcode::BranchLtSImm,
+        Opcode::BranchLeSImm,
+        Opcode::BranchGtSImm,
+        Opcode::BranchGeSImm,
+    ];
+    const UNSIGNED: [Opcode; 4] = [
+        Opcode::BranchLtUImm,
+        Opcode::BranchLeUImm,
+        Opcode::BranchGtUImm,
+        Opcode::BranchGeUImm,
+    ];
+    let cases: [(&str, &[Opcode], fn(i32) -> bool); 6] = [
+        ("lt_s", &SIGNED, |x| x < 5),
+        ("le_s", &SIGNED, |x| x <= 5),
+        ("gt_s", &SIGNED, |x| x > 5),
+        ("ge_s", &SIGNED, |x| x >= 5),
+        ("lt_u", &UNSIGNED, |x| x.cast_unsigned() < 5),
+        ("ge_u", &UNSIGNED, |x| x.cast_unsigned() >= 5),
+    ];
+
+    for (op, family, holds) in cases {
+        for negate in [false, true] {
+            let cond = format!("(i32.{op} (i32.load (local.get $p)) (i32.const 5))");
+            let cond = if negate {
+                format!("(i32.eqz {cond})")
+            } else {
+                cond
+            };
+            let wat = format!(
+                r#"
+                (module
+                    (memory 1)
+                    (func (export "main") (param $p i32) (param $l i32) (result i64)
+                        (block $skip
+                            (br_if $skip {cond})
+                            (i32.store (i32.const 0) (i32.const 1))
+                        )
+                        (i64.const 17179869184)
+                    )
+                )
+                "#
+            );
+            let program = compile_wat(&wat).expect("compile");
+            let instructions = extract_instructions(&program);
+            let context = format!("{cond}\nInstructions:\n{instructions:#?}");
+
+            assert!(
+                family.iter().any(|&o| has_opcode(&instructions, o)),
+                "{context}"
+            );
+            for set in [
+                Opcode::SetLtUImm,
+                Opcode::SetLtSImm,
+                Opcode::SetGtUImm,
+                Opcode::SetGtSImm,
+                Opcode::SetLtU,
+                Opcode::SetLtS,
+            ] {
+                assert!(!has_opcode(&instructions, set), "{set:?}: {context}");
+            }
+
+            for x in [-7i32, 4, 5, 6, 100] {
+                let taken = holds(x) != negate;
+                let result = run_program(&program, &x.to_le_bytes());
+                assert_eq!(result.exit, ExitReason::Halt, "{cond} ({x})");
+                assert_eq!(
+                    result.output,
+                    u32::from(!taken).to_le_bytes(),
+                    "{cond} ({x})"
+                );
+            }
+        }
+    }
+}
+
+/// A comparison computed before a loop and branched on at its bottom lives
+/// in a different block than its `br`. It must not be left deferred for
+/// fusion, or the loop header's own branch would consume it.
+#[test]
+fn test_br_if_on_compare_from_another_block() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $n i32)
+                (local $small i32)
+                (local.set $small (i32.lt_s (i32.load (local.get $p)) (i32.const 5)))
+                (block $done
+                    (loop $again
+                        (local.set $n (i32.add (local.get $n) (i32.const 1)))
+                        (br_if $done (i32.gt_u (local.get $n) (i32.const 100)))
+                        (i32.store (i32.const 0) (local.get $n))
+                        (br_if $again (local.get $small))
+                    )
+                )
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+
+    let program = compile_wat(wat).expect("compile");
+    for (x, expected) in [(0i32, 100i32), (9, 1)] {
+        let result = run_program(&program, &x.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "x = {x}");
+        assert_eq!(result.output, expected.to_le_bytes(), "x = {x}");
+    }
+}
+
 // =============================================================================
 // Global Variables
 // =============================================================================
@@ -1116,7 +1267,7 @@ fn test_multiple_globals() {
 }
 
 // =============================================================================
-// Memory Operations: memory.size, memory.grow
+// Memory Operations: memory.size, memory.grow, memory.copy
 // =============================================================================
 
 /// memory.size should compile and return the current memory size.
@@ -1165,6 +1316,45 @@ fn test_memory_grow() {
     );
 }
 
+/// memory.copy should bounds-check both ranges against the current memory size
+/// and trap before touching PVM memory outside the WASM linear memory.
+#[test]
+fn test_memory_copy_emits_bounds_check_traps() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param i32 i32 i32)
+                local.get 0
+                local.get 1
+                local.get 2
+                memory.copy
+            )
+        )
+    "#;
+
+    let program = compile_wat(wat).expect("compile");
+    let instructions = extract_instructions(&program);
+
+    // One guard each for the destination and source ranges.
+    assert!(
+        count_opcode(&instructions, Opcode::Trap) >= 2,
+        "memory.copy should emit a trap for each out-of-bounds range"
+    );
+    assert!(
+        count_opcode(&instructions, Opcode::BranchGeU) >= 2,
+        "memory.copy should compare addr + len against the memory size"
+    );
+    // The check reads the current size from the memory-size global slot.
+    assert!(
+        instructions.iter().any(|i| matches!(
+            i,
+            wasm_pvm::Instruction::LoadU32 { address, .. }
+                if *address == wasm_pvm::abi::memory_size_global_offset()
+        )),
+        "memory.copy should load the current memory size"
+    );
+}
+
 // =============================================================================
 // local.tee (load and keep on stack)
 // =============================================================================
@@ -1793,6 +1983,36 @@ fn test_call_indirect() {
     );
 }
 
+/// A later element segment overwrites the slots it shares with an earlier one.
+#[test]
+fn test_call_indirect_overlapping_elem_segments() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (type $sig (func (param i32) (result i32)))
+            (table 3 funcref)
+            (elem (i32.const 0) $double $double $double)
+            (elem (i32.const 1) $triple)
+            (func $double (param i32) (result i32)
+                (i32.mul (local.get 0) (i32.const 2)))
+            (func $triple (param i32) (result i32)
+                (i32.mul (local.get 0) (i32.const 3)))
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const 0)
+                    (call_indirect (type $sig) (i32.const 7) (i32.load (local.get $p))))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+
+    let program = compile_wat(wat).expect("compile");
+    for (slot, expected) in [(0i32, 14i32), (1, 21), (2, 14)] {
+        let result = run_program(&program, &slot.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "slot {slot}");
+        assert_eq!(result.output, expected.to_le_bytes(), "slot {slot}");
+    }
+}
+
 // =============================================================================
 // Rotation Operators
 // =============================================================================
@@ -2060,6 +2280,54 @@ fn test_select_uses_cmov_nz() {
     );
 }
 
+/// `if (c) x = a; else x = b;` with no other side effects: simplifycfg folds
+/// the diamond into a `select`, which lowers to a conditional move, so `main`
+/// contains no conditional branch at all. Input: `c`, `a`, `b` as i32s.
+#[test]
+fn if_else_assigning_local_becomes_cmov() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $c i32) (local $a i32) (local $b i32) (local $x i32)
+                (local.set $c (i32.load (local.get $p)))
+                (local.set $a (i32.load offset=4 (local.get $p)))
+                (local.set $b (i32.load offset=8 (local.get $p)))
+                (if (i32.lt_s (local.get $c) (i32.const 10))
+                    (then (local.set $x (local.get $a)))
+                    (else (local.set $x (local.get $b))))
+                (i32.store (i32.const 0) (local.get $x))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+
+    let program = compile_wat(wat).expect("compile");
+    let instructions = extract_instructions(&program);
+    let cmovs = count_opcode(&instructions, Opcode::CmovIz)
+        + count_opcode(&instructions, Opcode::CmovNz)
+        + count_opcode(&instructions, Opcode::CmovIzImm)
+        + count_opcode(&instructions, Opcode::CmovNzImm);
+    assert!(cmovs > 0, "expected a Cmov:\n{instructions:#?}");
+    let branches: Vec<Opcode> = Opcode::all()
+        .filter(|op| op.name().starts_with("branch_"))
+        .filter(|&op| has_opcode(&instructions, op))
+        .collect();
+    assert!(
+        branches.is_empty(),
+        "expected no conditional branch, found {branches:?}:\n{instructions:#?}"
+    );
+
+    for (c, expected) in [(3i32, 111i32), (10, 222), (-50, 111), (99, 222)] {
+        let mut input = c.to_le_bytes().to_vec();
+        input.extend_from_slice(&111i32.to_le_bytes());
+        input.extend_from_slice(&222i32.to_le_bytes());
+        let result = run_program(&program, &input);
+        assert_eq!(result.exit, ExitReason::Halt, "c = {c}");
+        assert_eq!(result.output, expected.to_le_bytes(), "c = {c}");
+    }
+}
+
 // =============================================================================
 // ALU Immediate Opcode Folding
 // =============================================================================
@@ -2178,6 +2446,25 @@ fn i32_gt_u_const_produces_set_gt_u_imm() {
     );
 }
 
+/// Bitwise NOT (`x ^ -1`) lowers to a single `XorImm -1`, without materializing
+/// the all-ones constant in a register.
+#[test]
+fn i32_not_produces_single_xor_imm() {
+    let program = compile_wat(
+        r#"(module (memory 1)
+            (func (export "main") (param i32) (result i32)
+                local.get 0 i32.const -1 i32.xor))"#,
+    )
+    .expect("compile");
+    let instructions = extract_instructions(&program);
+    let nots = instructions
+        .iter()
+        .filter(|i| matches!(i, wasm_pvm::Instruction::XorImm { value: -1, .. }))
+        .count();
+    assert_eq!(nots, 1, "expected exactly one XorImm -1");
+    assert!(!has_opcode(&instructions, Opcode::Xor));
+}
+
 // =============================================================================
 // Specialized Instruction Emission (Issues #104 + #105)
 // =============================================================================
@@ -0,0 +1,93 @@
+//! The optimization passes, taken together, must pay for themselves: the
+//! default flags emit fewer instructions than `none`, and `none` really does
+//! leave code the passes would have cleaned up (see also
+//! `benches/opt_presets.rs` for the per-pass table).
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{
+    CompileOptions, CompileStats, Instruction, OptimizationFlags, SpiProgram, compile_with_stats,
+};
+
+const FIBONACCI: &str = include_str!("../../../tests/fixtures/wat/fibonacci.jam.wat");
+
+fn compile(optimizations: OptimizationFlags) -> (SpiProgram, CompileStats) {
+    let wasm = wat_to_wasm(FIBONACCI).expect("wat");
+    let options = CompileOptions {
+        optimizations,
+        ..CompileOptions::default()
+    };
+    compile_with_stats(&wasm, &options).expect("compile")
+}
+
+#[test]
+fn all_on_emits_fewer_instructions_than_all_off() {
+    let (on, on_stats) = compile(OptimizationFlags::all());
+    let (off, off_stats) = compile(OptimizationFlags::none());
+    assert!(
+        on_stats.pvm_instructions < off_stats.pvm_instructions,
+        "all-on {} vs all-off {} instructions",
+        on_stats.pvm_instructions,
+        off_stats.pvm_instructions
+    );
+
+    // Both still compute fib(20).
+    for program in [&on, &off] {
+        let result = run_program(program, &20u32.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.output, 6765u32.to_le_bytes());
+    }
+}
+
+/// `main(p)` uses one 64-bit constant three times.
+const REPEATED_CONSTANT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $a i64)
+            (local.set $a (i64.load (local.get $p)))
+            (i64.store (i32.const 0) (i64.add (local.get $a) (i64.const 0x123456789abcdef0)))
+            (i64.store (i32.const 8) (i64.xor (local.get $a) (i64.const 0x123456789abcdef0)))
+            (i64.store (i32.const 16) (i64.const 0x123456789abcdef0))
+            (i64.const 103079215104)
+        )
+    )
+"#;
+
+#[test]
+fn none_keeps_redundant_constant_loads_that_all_removes() {
+    const C: u64 = 0x1234_5678_9abc_def0;
+    let build = |optimizations| {
+        compile_wat_with_options(
+            REPEATED_CONSTANT,
+            &CompileOptions {
+                optimizations,
+                ..CompileOptions::default()
+            },
+        )
+        .expect("compile")
+    };
+    let loads_of_c = |program: &SpiProgram| {
+        extract_instructions(program)
+            .iter()
+            .filter(|i| matches!(i, Instruction::LoadImm64 { value: C, .. }))
+            .count()
+    };
+
+    let none = build(OptimizationFlags::none());
+    let all = build(OptimizationFlags::all());
+    assert!(
+        loads_of_c(&none) > 1,
+        "without constant propagation every use should reload {C:#x}"
+    );
+    assert_eq!(loads_of_c(&all), 1, "expected a single LoadImm64 of {C:#x}");
+
+    let expected = [5u64.wrapping_add(C), 5 ^ C, C]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    for program in [&none, &all] {
+        let result = run_program(program, &5u64.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt);
+        assert_eq!(result.output, expected);
+    }
+}
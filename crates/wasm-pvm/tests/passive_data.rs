@@ -0,0 +1,77 @@
+//! Passive data segments: `memory.init` copies them into linear memory and
+//! `data.drop` empties them, so later non-empty inits trap.
+
+use wasm_pvm::test_harness::*;
+
+/// Copies `$bytes[src..src + 4]` to address 0x40 after optionally dropping
+/// the segment, then returns the 4 bytes at 0x40.
+const INIT_WAT: &str = r#"
+    (module
+        (memory 1)
+        (data (i32.const 0x80) "\aa\bb")
+        (data $bytes "\01\02\03\04\05\06\07\08")
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (if (i32.load (local.get $p))
+                (then (data.drop $bytes)))
+            (memory.init $bytes
+                (i32.const 0x40)
+                (i32.load offset=4 (local.get $p))
+                (i32.load offset=8 (local.get $p)))
+            ;; 4 bytes at address 0x40.
+            (i64.const 17179869248)
+        )
+    )
+"#;
+
+fn args(drop: u32, src: u32, len: u32) -> Vec<u8> {
+    [drop, src, len]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn memory_init_copies_passive_bytes_to_the_destination() {
+    let program = compile_wat(INIT_WAT).expect("compile");
+    let result = run_program(&program, &args(0, 3, 4));
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, [0x04, 0x05, 0x06, 0x07]);
+}
+
+#[test]
+fn memory_init_past_the_segment_end_traps() {
+    let program = compile_wat(INIT_WAT).expect("compile");
+    let result = run_program(&program, &args(0, 6, 4));
+    assert_eq!(result.exit, ExitReason::Trap);
+}
+
+#[test]
+fn data_drop_empties_the_segment() {
+    let program = compile_wat(INIT_WAT).expect("compile");
+
+    let result = run_program(&program, &args(1, 0, 4));
+    assert_eq!(result.exit, ExitReason::Trap);
+
+    // A zero-length init from a dropped segment is still in bounds.
+    let result = run_program(&program, &args(1, 0, 0));
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, [0; 4]);
+}
+
+#[test]
+fn dropping_an_active_segment_is_a_no_op() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (data $active (i32.const 0x40) "\de\ad\be\ef")
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (data.drop $active)
+                (i64.const 17179869248)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, [0xde, 0xad, 0xbe, 0xef]);
+}
@@ -0,0 +1,146 @@
+//! `CompileOptions::pic`: data addresses relative to the r12 base register.
+//!
+//! A PIC build must contain no absolute-address loads or stores, never write
+//! r12, behave like the default build when r12 is 0, and still produce the
+//! right result when the host relocates the data image and sets r12 to the
+//! relocation offset.
+
+use wasm_pvm::memory_layout::GLOBAL_MEMORY_BASE;
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, Opcode};
+
+/// Touches globals, linear memory (an active data segment and the input),
+/// the memory-size global and a five-parameter call (the fifth parameter
+/// goes through the overflow area). Output: `13726 + input`.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (global $g (mut i32) (i32.const 1000))
+        (global $h (mut i64) (i64.const 5))
+        (data (i32.const 16) "\2a\00\00\00")
+        (func $mix (param $a i32) (param $b i32) (param $c i32) (param $d i32) (param $e i32)
+            (result i32)
+            (i32.add
+                (i32.add (i32.mul (local.get $a) (i32.const 10000))
+                         (i32.mul (local.get $b) (i32.const 1000)))
+                (i32.add (i32.mul (local.get $c) (i32.const 100))
+                         (i32.add (i32.mul (local.get $d) (i32.const 10)) (local.get $e)))))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (global.set $g (i32.add (global.get $g) (i32.load (local.get $p))))
+            (global.set $h (i64.add (global.get $h) (i64.extend_i32_u (memory.size))))
+            (i32.store (i32.const 0)
+                (i32.add
+                    (call $mix (i32.const 1) (i32.const 2) (i32.const 3)
+                               (i32.load (i32.const 16)) (global.get $g))
+                    (i32.wrap_i64 (global.get $h))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn pic_options() -> CompileOptions {
+    CompileOptions {
+        pic: true,
+        ..CompileOptions::default()
+    }
+}
+
+fn expected(x: i32) -> [u8; 4] {
+    (13726 + x).to_le_bytes()
+}
+
+#[test]
+fn pic_matches_default_build_at_base_zero() {
+    let default = compile_wat(WAT).expect("compile default");
+    let pic = compile_wat_with_options(WAT, &pic_options()).expect("compile pic");
+    for x in [0i32, 7, 123] {
+        let expected_run = run_program(&default, &x.to_le_bytes());
+        let actual = run_program(&pic, &x.to_le_bytes());
+        assert_eq!(actual.exit, ExitReason::Halt, "x = {x}");
+        assert_eq!(actual.output, expected_run.output, "x = {x}");
+        assert_eq!(actual.output, expected(x), "x = {x}");
+    }
+}
+
+#[test]
+fn pic_has_no_absolute_accesses() {
+    let program = compile_wat_with_options(WAT, &pic_options()).expect("compile");
+    let instructions = extract_instructions(&program);
+    let absolute = [
+        Opcode::LoadU8,
+        Opcode::LoadI8,
+        Opcode::LoadU16,
+        Opcode::LoadI16,
+        Opcode::LoadU32,
+        Opcode::LoadI32,
+        Opcode::LoadU64,
+        Opcode::StoreU8,
+        Opcode::StoreU16,
+        Opcode::StoreU32,
+        Opcode::StoreU64,
+        Opcode::StoreImmU8,
+        Opcode::StoreImmU16,
+        Opcode::StoreImmU32,
+        Opcode::StoreImmU64,
+    ];
+    for opcode in absolute {
+        assert!(
+            !has_opcode(&instructions, opcode),
+            "absolute {opcode:?} in PIC output:\n{instructions:#?}"
+        );
+    }
+    assert!(
+        !instructions
+            .iter()
+            .any(|i: &Instruction| i.dest_reg() == Some(12)),
+        "PIC output writes the base register:\n{instructions:#?}"
+    );
+}
+
+#[test]
+fn pic_runs_with_relocated_data() {
+    const DELTA: u32 = 0x1000;
+    let program = compile_wat_with_options(WAT, &pic_options()).expect("compile");
+    let x = 55i32;
+    let unrelocated = run_program(&program, &x.to_le_bytes());
+    let mut interp = Interpreter::new(&program, &x.to_le_bytes());
+    // Move the read-write image up by DELTA, as a relocating host would.
+    assert!(interp.write_memory(GLOBAL_MEMORY_BASE as u32 + DELTA, program.rw_data()));
+    interp.set_reg(12, u64::from(DELTA));
+    assert_eq!(interp.run(), ExitReason::Halt);
+    assert_eq!(interp.output(), expected(x));
+    assert_eq!(
+        interp.regs()[7],
+        unrelocated.regs[7] + u64::from(DELTA),
+        "result pointer should be relocated"
+    );
+}
+
+#[test]
+fn pic_rejects_fourth_local_register() {
+    let options = CompileOptions {
+        local_regs: Some(4),
+        ..pic_options()
+    };
+    assert!(compile_wat_with_options(WAT, &options).is_err());
+}
+
+#[test]
+fn pic_rejects_host_call_writing_r12() {
+    let wat = r#"
+        (module
+            (import "env" "host_call_6"
+                (func $hc (param i64 i64 i64 i64 i64 i64 i64) (result i64)))
+            (func (export "main") (param i32 i32) (result i64)
+                (drop (call $hc (i64.const 1) (i64.const 0) (i64.const 0) (i64.const 0)
+                                (i64.const 0) (i64.const 0) (i64.const 0)))
+                (i64.const 0)
+            )
+        )
+    "#;
+    assert!(compile_wat(wat).is_ok());
+    let err = compile_wat_with_options(wat, &pic_options())
+        .err()
+        .expect("host_call_6 writes r12");
+    assert!(err.to_string().contains("r12"), "{err}");
+}
@@ -0,0 +1,19 @@
+//! A self-recursive function with more locals than local registers computes
+//! the right result at every depth.
+
+use wasm_pvm::test_harness::*;
+
+const RECURSIVE_FIB_LOCALS: &str =
+    include_str!("../../../tests/fixtures/wat/recursive-fib-locals.jam.wat");
+
+#[test]
+fn recursive_fib_with_six_locals() {
+    let program = compile_wat(RECURSIVE_FIB_LOCALS).expect("compile");
+    let (mut a, mut b) = (0u32, 1u32);
+    for n in 0..=20u32 {
+        let result = run_program(&program, &n.to_le_bytes());
+        assert_eq!(result.exit, ExitReason::Halt, "fib({n})");
+        assert_eq!(result.output, a.to_le_bytes(), "fib({n})");
+        (a, b) = (b, a + b);
+    }
+}
@@ -0,0 +1,152 @@
+//! Re-optimizing an already compiled SPI blob (`wasm-pvm optimize`).
+//!
+//! A program compiled with the peephole and dead-store passes off is decoded,
+//! re-optimized and run again: it must get smaller and behave the same.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{
+    CompileOptions, Instruction, OptimizationFlags, ProgramBlob, SpiProgram, reoptimize,
+};
+
+/// Sums `n` words from memory through a direct and an indirect call, so the
+/// blob has loops, return addresses and a function table in `ro_data`.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (type $unary (func (param i32) (result i32)))
+        (table 2 funcref)
+        (elem (i32.const 0) $double $sum_to)
+        (func $double (param $x i32) (result i32)
+            (i32.add (local.get $x) (local.get $x)))
+        (func $sum_to (param $n i32) (result i32)
+            (local $i i32) (local $acc i32)
+            (block $done
+                (loop $next
+                    (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+                    (local.set $acc
+                        (i32.add (local.get $acc)
+                            (i32.load (i32.shl (local.get $i) (i32.const 2)))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $next)))
+            (local.get $acc))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $n i32)
+            (local.set $n (i32.load (local.get $p)))
+            (i32.store (i32.const 0) (i32.const 5))
+            (i32.store (i32.const 4) (i32.const 7))
+            (i32.store (i32.const 8) (i32.const 11))
+            (i32.store (i32.const 0)
+                (call_indirect (type $unary)
+                    (call $sum_to (local.get $n))
+                    (i32.const 0)))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn unoptimized() -> SpiProgram {
+    let options = CompileOptions {
+        optimizations: OptimizationFlags {
+            peephole: false,
+            dead_store_elimination: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    };
+    compile_wat_with_options(WAT, &options).expect("compile")
+}
+
+#[test]
+fn reoptimizing_shrinks_an_unoptimized_blob() {
+    let program = unoptimized();
+    let encoded = program.encode();
+    let decoded = SpiProgram::decode(&encoded).expect("decode");
+    let optimized = reoptimize(&decoded).expect("reoptimize");
+
+    assert!(
+        optimized.encode().len() < encoded.len(),
+        "expected a smaller blob, got {} -> {} bytes",
+        encoded.len(),
+        optimized.encode().len()
+    );
+    assert_eq!(optimized.metadata(), program.metadata());
+    assert_eq!(optimized.ro_data(), program.ro_data());
+    assert_eq!(optimized.rw_data(), program.rw_data());
+    assert_eq!(
+        optimized.code().jump_table().len(),
+        program.code().jump_table().len()
+    );
+}
+
+#[test]
+fn reoptimizing_preserves_semantics() {
+    let program = unoptimized();
+    let optimized =
+        reoptimize(&SpiProgram::decode(&program.encode()).expect("decode")).expect("reoptimize");
+
+    for n in [0i32, 1, 2, 3] {
+        let before = run_program(&program, &n.to_le_bytes());
+        let after = run_program(&optimized, &n.to_le_bytes());
+        assert_eq!(before.exit, ExitReason::Halt, "n = {n}");
+        assert_eq!(after.exit, before.exit, "n = {n}");
+        assert_eq!(after.output, before.output, "n = {n}");
+    }
+    let result = run_program(&optimized, &3i32.to_le_bytes());
+    assert_eq!(result.output, 46i32.to_le_bytes());
+}
+
+/// A call in the two-instruction form older compilers emitted,
+/// `LoadImm64 r0, ret; Jump callee`, fuses into one `LoadImmJump`.
+#[test]
+fn reoptimizing_fuses_legacy_call_sequences() {
+    let mut instructions = vec![
+        Instruction::MoveReg { dst: 9, src: 0 },
+        // Jump-table address 2 is entry 0, the return point below.
+        Instruction::LoadImm64 { reg: 0, value: 2 },
+        // Placeholder, patched below once the callee's offset is known.
+        Instruction::Jump { offset: 1 },
+        Instruction::MoveReg { dst: 0, src: 9 },
+        Instruction::JumpInd { reg: 0, offset: 0 },
+        // Callee.
+        Instruction::LoadImm { reg: 10, value: 42 },
+        Instruction::JumpInd { reg: 0, offset: 0 },
+    ];
+    let offset = |instructions: &[Instruction], idx: usize| -> usize {
+        instructions[..idx]
+            .iter()
+            .map(Instruction::encoded_len)
+            .sum()
+    };
+    // Small enough to keep the placeholder's encoded width.
+    let to_callee = offset(&instructions, 5) - offset(&instructions, 2);
+    instructions[2] = Instruction::Jump {
+        offset: i32::try_from(to_callee).unwrap(),
+    };
+    let return_point = u32::try_from(offset(&instructions, 3)).unwrap();
+    let program =
+        SpiProgram::new(ProgramBlob::new(instructions.clone()).with_jump_table(vec![return_point]))
+            .with_ro_data(vec![0]);
+
+    let optimized = reoptimize(&program).expect("reoptimize");
+    let fused = optimized.code().instructions();
+    assert!(
+        fused.iter().any(|i| matches!(
+            i,
+            Instruction::LoadImmJump {
+                reg: 0,
+                value: 2,
+                ..
+            }
+        )),
+        "{fused:?}"
+    );
+    assert_eq!(fused.len(), instructions.len() - 1);
+
+    let before = run_program(&program, &[]);
+    let after = run_program(&optimized, &[]);
+    assert_eq!(before.exit, ExitReason::Halt);
+    assert_eq!(after.exit, before.exit);
+    assert_eq!(before.regs[10], 42);
+    assert_eq!(after.regs[10], before.regs[10]);
+    assert!(after.gas_used < before.gas_used);
+}
@@ -0,0 +1,101 @@
+//! `CompileOptions::reserved_registers`: a reserved register never appears
+//! as a source or destination in the emitted code.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Error, SpiProgram};
+
+/// A leaf loop with enough live values that register allocation reaches for
+/// r5/r6. `main` sums `i * k` for `i` in `0..n` and `k` in 1..=4 (with `n`
+/// the first argument byte) and stores the four sums.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $n i32) (local $i i32)
+            (local $a i32) (local $b i32) (local $c i32) (local $d i32)
+            (local.set $n (i32.load8_u (local.get $p)))
+            (block $done
+                (loop $next
+                    (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+                    (local.set $a (i32.add (local.get $a) (local.get $i)))
+                    (local.set $b (i32.add (local.get $b) (i32.mul (local.get $i) (i32.const 2))))
+                    (local.set $c (i32.add (local.get $c) (i32.mul (local.get $i) (i32.const 3))))
+                    (local.set $d (i32.add (local.get $d) (i32.mul (local.get $i) (i32.const 4))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $next)))
+            (i32.store (i32.const 0) (local.get $a))
+            (i32.store (i32.const 4) (local.get $b))
+            (i32.store (i32.const 8) (local.get $c))
+            (i32.store (i32.const 12) (local.get $d))
+            (i64.const 68719476736)
+        )
+    )
+"#;
+
+fn reserving(regs: &[u8]) -> CompileOptions {
+    CompileOptions {
+        reserved_registers: regs.to_vec(),
+        ..CompileOptions::default()
+    }
+}
+
+fn uses_reg(program: &SpiProgram, reg: u8) -> bool {
+    program
+        .code()
+        .instructions()
+        .iter()
+        .any(|instr| instr.dest_reg() == Some(reg) || instr.src_regs().contains(&Some(reg)))
+}
+
+#[test]
+fn reserved_r6_is_never_touched() {
+    let program = compile_wat_with_options(WAT, &reserving(&[6])).expect("compile");
+    assert!(
+        !uses_reg(&program, 6),
+        "{:#?}",
+        program.code().instructions()
+    );
+
+    let result = run_program(&program, &[10]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    let sum: u32 = (0..10).sum();
+    let expected: Vec<u8> = (1..=4u32).flat_map(|k| (sum * k).to_le_bytes()).collect();
+    assert_eq!(result.output, expected);
+}
+
+#[test]
+fn only_scratch_registers_can_be_reserved() {
+    for reg in [0, 1, 2, 7, 9, 12, 13] {
+        let err = compile_wat_with_options(WAT, &reserving(&[reg]))
+            .err()
+            .expect("register has a fixed role");
+        assert!(
+            matches!(&err, Error::Unsupported(msg) if msg.contains(&format!("r{reg} "))),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn scratch_heavy_lowering_is_rejected() {
+    // `memory.fill` uses r5/r6 as loop registers.
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (memory.fill (i32.const 0) (i32.const 7) (local.get $l))
+                (i64.const 0)
+            )
+        )
+    "#;
+    let err = compile_wat_with_options(wat, &reserving(&[5]))
+        .err()
+        .expect("memory.fill needs r5/r6");
+    let Error::Located { cause, .. } = &err else {
+        panic!("expected Error::Located, got {err:?}");
+    };
+    assert!(
+        matches!(cause.as_ref(), Error::Unsupported(msg) if msg.contains("reserved_registers")),
+        "{err}"
+    );
+}
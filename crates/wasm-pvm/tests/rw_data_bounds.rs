@@ -0,0 +1,43 @@
+//! Active data segments must end inside the initial linear memory: `rw_data`
+//! is mapped as-is, so a segment past the end would land in memory the
+//! program considers unallocated.
+
+use wasm_pvm::Error;
+use wasm_pvm::test_harness::*;
+
+/// 16 pages, the default memory floor, so the declared size is what gets
+/// mapped.
+const PAGES: u32 = 16;
+
+/// A 4-byte segment at `offset`; `main` returns it.
+fn module(offset: u32) -> String {
+    main_module_wat(
+        &format!(
+            r#"
+                (memory {PAGES})
+                (data (i32.const {offset}) "\01\02\03\04")
+            "#
+        ),
+        &format!("(i32.store (i32.const 0) (i32.load (i32.const {offset})))"),
+        4,
+    )
+}
+
+#[test]
+fn segment_ending_at_memory_end_is_accepted() {
+    let program = compile_wat(&module(PAGES * 65536 - 4)).expect("compile");
+    let result = run_program(&program, &[]);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, [1, 2, 3, 4]);
+}
+
+#[test]
+fn segment_past_memory_end_is_rejected() {
+    let err = compile_wat(&module(PAGES * 65536 - 3))
+        .err()
+        .expect("segment overruns the initial memory");
+    assert!(
+        matches!(&err, Error::SpiLayout(msg) if msg.contains("outside the initial memory")),
+        "{err}"
+    );
+}
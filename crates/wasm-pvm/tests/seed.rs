@@ -0,0 +1,69 @@
+//! `CompileOptions::seed`: seeded tie-breaks in scheduling and block layout.
+//!
+//! A given seed must always produce the same binary, seed 0 must match the
+//! default build, and any seed may only reorder code, never change behavior.
+
+use wasm_pvm::CompileOptions;
+use wasm_pvm::test_harness::*;
+
+/// Independent expression trees (scheduling ties) and a two-way branch with
+/// both arms hot (layout tie). Output: the stored word.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func $mix (param $a i32) (param $b i32) (result i32)
+            (i32.add
+                (i32.mul (i32.add (local.get $a) (i32.const 3)) (i32.sub (local.get $a) (i32.const 1)))
+                (i32.mul (i32.xor (local.get $b) (i32.const 5)) (i32.or (local.get $b) (i32.const 2)))))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (local $x i32)
+            (local.set $x (i32.load (local.get $p)))
+            (if (i32.lt_s (local.get $x) (i32.const 10))
+                (then (i32.store (i32.const 0) (call $mix (local.get $x) (i32.const 7))))
+                (else (i32.store (i32.const 0) (call $mix (i32.const 7) (local.get $x)))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn compile_seeded(seed: u64) -> Vec<u8> {
+    let options = CompileOptions {
+        seed,
+        ..CompileOptions::default()
+    };
+    compile_wat_with_options(WAT, &options)
+        .expect("compile")
+        .encode()
+}
+
+#[test]
+fn seed_zero_matches_default_build() {
+    let default = compile_wat(WAT).expect("compile").encode();
+    assert_eq!(compile_seeded(0), default);
+}
+
+#[test]
+fn same_seed_is_byte_identical() {
+    for seed in [1, 42, 0xdead_beef] {
+        assert_eq!(compile_seeded(seed), compile_seeded(seed), "seed {seed}");
+    }
+}
+
+#[test]
+fn seeds_only_reorder_code() {
+    let default = compile_wat(WAT).expect("compile");
+    for seed in 1..=8 {
+        let options = CompileOptions {
+            seed,
+            ..CompileOptions::default()
+        };
+        let seeded = compile_wat_with_options(WAT, &options).expect("compile");
+        for x in [-3i32, 4, 10, 99] {
+            let expected = run_program(&default, &x.to_le_bytes());
+            let actual = run_program(&seeded, &x.to_le_bytes());
+            assert_eq!(expected.exit, ExitReason::Halt, "x = {x}");
+            assert_eq!(actual.exit, expected.exit, "seed {seed}, x = {x}");
+            assert_eq!(actual.output, expected.output, "seed {seed}, x = {x}");
+        }
+    }
+}
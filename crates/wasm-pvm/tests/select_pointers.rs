@@ -0,0 +1,88 @@
+//! `select` between pointers whose PVM address has the high bit set.
+//!
+//! `main`'s `$p` points into the args region near `0xFEFF_0000`, so pointers
+//! derived from it must survive the conditional move with all 64 bits intact
+//! before being dereferenced.
+
+use wasm_pvm::test_harness::*;
+
+/// Input: `[cond: i32][a][b]`. Loads through `select(cond, &a, &b)`.
+/// Output: the loaded value (4 or 8 bytes).
+fn select_load_wat(ty: &str, width: u32) -> String {
+    let b_offset = 4 + width;
+    let packed = u64::from(width) << 32;
+    format!(
+        r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (local $ptr i32)
+                (local.set $ptr
+                    (select
+                        (i32.add (local.get $p) (i32.const 4))
+                        (i32.add (local.get $p) (i32.const {b_offset}))
+                        (i32.load (local.get $p))))
+                ({ty}.store (i32.const 0) ({ty}.load (local.get $ptr)))
+                (i64.const {packed})
+            )
+        )
+        "#
+    )
+}
+
+fn input(cond: i32, a: &[u8], b: &[u8]) -> Vec<u8> {
+    [&cond.to_le_bytes()[..], a, b].concat()
+}
+
+#[test]
+fn select_between_arg_pointers_i32() {
+    let program = compile_wat(&select_load_wat("i32", 4)).expect("compile");
+    let a = 0x1234_5678u32.to_le_bytes();
+    let b = 0x9ABC_DEF0u32.to_le_bytes();
+    for (cond, expected) in [(1, a), (-1, a), (0, b)] {
+        let result = run_program(&program, &input(cond, &a, &b));
+        assert_eq!(result.exit, ExitReason::Halt, "cond = {cond}");
+        assert_eq!(result.output, expected, "cond = {cond}");
+    }
+}
+
+#[test]
+fn select_between_arg_pointers_i64() {
+    let program = compile_wat(&select_load_wat("i64", 8)).expect("compile");
+    let a = 0x8000_0001_2345_6789u64.to_le_bytes();
+    let b = 0xFEDC_BA98_7654_3210u64.to_le_bytes();
+    for (cond, expected) in [(7, a), (0, b)] {
+        let result = run_program(&program, &input(cond, &a, &b));
+        assert_eq!(result.exit, ExitReason::Halt, "cond = {cond}");
+        assert_eq!(result.output, expected, "cond = {cond}");
+    }
+}
+
+/// One arm is a small constant address (immediate cmov form), the other a
+/// high args pointer kept as the default register.
+#[test]
+fn select_between_arg_pointer_and_constant_address() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (data (i32.const 16) "\07\00\00\00")
+            (func (export "main") (param $p i32) (param $l i32) (result i64)
+                (i32.store (i32.const 0)
+                    (i32.load
+                        (select
+                            (i32.add (local.get $p) (i32.const 4))
+                            (i32.const 16)
+                            (i32.load (local.get $p)))))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let program = compile_wat(wat).expect("compile");
+    let a = 0xCAFE_F00Du32.to_le_bytes();
+    let taken = run_program(&program, &input(1, &a, &[]));
+    assert_eq!(taken.exit, ExitReason::Halt);
+    assert_eq!(taken.output, a);
+    let not_taken = run_program(&program, &input(0, &a, &[]));
+    assert_eq!(not_taken.exit, ExitReason::Halt);
+    assert_eq!(not_taken.output, 7u32.to_le_bytes());
+}
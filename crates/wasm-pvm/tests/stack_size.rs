@@ -0,0 +1,95 @@
+//! `CompileOptions::stack_size`: the SPI header, the compile stats and the
+//! prologue overflow checks all use the requested stack size.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Error, OptimizationFlags, abi, compile_with_stats, memory_layout};
+
+/// `main(n)` stores `depth(n)`, which recurses `n` levels deep. Compiled
+/// without `tail_calls`, which would turn the recursion into a loop, so every
+/// level keeps its frame.
+const RECURSION_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func $depth (param $n i32) (result i32)
+            (if (i32.eqz (local.get $n)) (then (return (i32.const 0))))
+            (i32.add (call $depth (i32.sub (local.get $n) (i32.const 1))) (i32.const 1))
+        )
+        (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+            (i32.store (i32.const 0) (call $depth (i32.load (local.get $args_ptr))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn with_stack_size(stack_size: Option<u32>) -> CompileOptions {
+    CompileOptions {
+        stack_size,
+        optimizations: OptimizationFlags {
+            tail_calls: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    }
+}
+
+#[test]
+fn larger_stack_fits_deeper_recursion() {
+    let wasm = wat_to_wasm(RECURSION_WAT).expect("wat");
+    let (default, stats) = compile_with_stats(&wasm, &with_stack_size(None)).expect("compile");
+    assert_eq!(default.stack_size(), memory_layout::DEFAULT_STACK_SIZE);
+    assert_eq!(stats.stack_size, memory_layout::DEFAULT_STACK_SIZE);
+
+    // Twice as many frames as the default stack holds.
+    let frame_size = stats
+        .functions
+        .iter()
+        .find(|f| f.name == "depth")
+        .expect("depth is compiled")
+        .frame_size;
+    let frame_size = u32::try_from(frame_size).expect("frame size is non-negative");
+    let depth = 2 * memory_layout::DEFAULT_STACK_SIZE / frame_size;
+    let args = depth.to_le_bytes();
+    assert_eq!(run_program(&default, &args).exit, ExitReason::Trap);
+
+    let stack_size = 4 * memory_layout::DEFAULT_STACK_SIZE;
+    let (large, stats) =
+        compile_with_stats(&wasm, &with_stack_size(Some(stack_size))).expect("compile");
+    assert_eq!(large.stack_size(), stack_size);
+    assert_eq!(stats.stack_size, stack_size);
+    let result = run_program(&large, &args);
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, depth.to_le_bytes());
+}
+
+#[test]
+fn smaller_stack_traps_at_its_own_limit() {
+    let program =
+        compile_wat_with_options(RECURSION_WAT, &with_stack_size(Some(4096))).expect("compile");
+    assert_eq!(program.stack_size(), 4096);
+
+    let result = run_program(&program, &1000i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Trap);
+    let sp = result.regs[usize::from(abi::STACK_PTR_REG)];
+    let limit = u64::from(abi::stack_limit(4096).cast_unsigned());
+    assert!(
+        sp >= limit,
+        "trapped below the limit: sp {sp:#x}, limit {limit:#x}"
+    );
+    assert!(
+        sp < limit + 4096,
+        "trapped before using the stack: sp {sp:#x}, limit {limit:#x}"
+    );
+}
+
+#[test]
+fn stack_size_must_be_whole_pages() {
+    for stack_size in [0, 1000, 4096 + 8] {
+        let err = compile_wat_with_options(RECURSION_WAT, &with_stack_size(Some(stack_size)))
+            .err()
+            .expect("not a multiple of the page size");
+        assert!(
+            matches!(&err, Error::Unsupported(msg) if msg.contains("stack_size")),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! `OptimizationFlags::tail_calls`: self-recursive calls in tail position
+//! become loops, so recursion depth no longer consumes stack.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, OptimizationFlags, SpiProgram};
+
+const TAIL_RECURSION: &str = include_str!("../../../tests/fixtures/wat/tail-recursion.jam.wat");
+
+/// Far more frames than the default 64 KB stack holds.
+const DEPTH: u32 = 100_000;
+
+fn compile(tail_calls: bool) -> SpiProgram {
+    compile_wat_with_options(
+        TAIL_RECURSION,
+        &CompileOptions {
+            optimizations: OptimizationFlags {
+                tail_calls,
+                ..OptimizationFlags::default()
+            },
+            ..CompileOptions::default()
+        },
+    )
+    .expect("compile")
+}
+
+fn args(op: u32, a: u32, b: u32) -> Vec<u8> {
+    [op, a, b].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[test]
+fn deep_tail_recursion_overflows_without_elimination() {
+    let result = run_program(&compile(false), &args(0, DEPTH, 0));
+    assert_eq!(result.exit, ExitReason::Trap);
+}
+
+#[test]
+fn deep_tail_recursion_completes_with_elimination() {
+    let result = run_program(&compile(true), &args(0, DEPTH, 0));
+    assert_eq!(result.exit, ExitReason::Halt);
+    let expected = (1..=DEPTH).fold(0u32, u32::wrapping_add);
+    assert_eq!(result.output, expected.to_le_bytes());
+}
+
+#[test]
+fn results_agree_at_shallow_depths() {
+    for program in [compile(false), compile(true)] {
+        for (a, b, sum) in [(0, 0, 0), (10, 0, 55), (500, 7, 125_257)] {
+            let result = run_program(&program, &args(0, a, b));
+            assert_eq!(result.exit, ExitReason::Halt, "sum({a}, {b})");
+            assert_eq!(result.output, u32::to_le_bytes(sum), "sum({a}, {b})");
+        }
+        for (a, b, gcd) in [(48, 18, 6), (17, 0, 17), (13, 34, 1)] {
+            let result = run_program(&program, &args(1, a, b));
+            assert_eq!(result.exit, ExitReason::Halt, "gcd({a}, {b})");
+            assert_eq!(result.output, u32::to_le_bytes(gcd), "gcd({a}, {b})");
+        }
+    }
+}
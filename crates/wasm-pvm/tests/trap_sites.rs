@@ -0,0 +1,142 @@
+//! Runtime trap-location tests.
+//!
+//! These run compiled programs in the harness interpreter and check not only
+//! that a WASM construct traps, but that the executed `Trap` is the check
+//! guarding that construct (via `run_and_expect_trap_near`).
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, Opcode, OptimizationFlags, abi, compile_with_stats};
+
+/// `main(a, b)` stores `a / b` (unsigned) at address 0 and returns it.
+const DIV_U_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+            (i32.store (i32.const 0)
+                (i32.div_u
+                    (i32.load (local.get $args_ptr))
+                    (i32.load offset=4 (local.get $args_ptr))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn args(a: i32, b: i32) -> Vec<u8> {
+    [a.to_le_bytes(), b.to_le_bytes()].concat()
+}
+
+#[test]
+fn test_div_u_runs_without_trapping() {
+    let program = compile_wat(DIV_U_WAT).expect("Failed to compile");
+    let result = run_program(&program, &args(42, 5));
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 8u32.to_le_bytes());
+}
+
+#[test]
+fn test_div_by_zero_traps_at_div_check() {
+    let wasm = wat_to_wasm(DIV_U_WAT).expect("Failed to parse WAT");
+    let site = run_and_expect_trap_near(&wasm, &args(42, 0), "main", Opcode::DivU32);
+
+    // The guard is the divisor-zero check branching over the trap.
+    assert!(
+        matches!(site.guard, Some(Instruction::BranchNeImm { value: 0, .. })),
+        "expected a `BranchNeImm reg, 0` guard, got {:?}",
+        site.guard
+    );
+}
+
+#[test]
+fn test_signed_overflow_traps_at_overflow_check() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+                (i32.store (i32.const 0)
+                    (i32.div_s
+                        (i32.load (local.get $args_ptr))
+                        (i32.load offset=4 (local.get $args_ptr))))
+                (i64.const 17179869184)
+            )
+        )
+    "#;
+    let wasm = wat_to_wasm(wat).expect("Failed to parse WAT");
+
+    // Divisor is nonzero, so the zero check passes and the overflow check fires.
+    let site = run_and_expect_trap_near(&wasm, &args(i32::MIN, -1), "main", Opcode::DivS32);
+    assert!(
+        !matches!(site.guard, Some(Instruction::BranchNeImm { value: 0, .. })),
+        "INT_MIN / -1 should trap at the overflow check, not the zero check"
+    );
+}
+
+/// `main(n)` stores `depth(n)`, which recurses `n` levels deep. Compiled
+/// without `tail_calls`, which would turn the recursion into a loop, so every
+/// level keeps its frame.
+const RECURSION_WAT: &str = r#"
+    (module
+        (memory 1)
+        (func $depth (param $n i32) (result i32)
+            (if (i32.eqz (local.get $n)) (then (return (i32.const 0))))
+            (i32.add (call $depth (i32.sub (local.get $n) (i32.const 1))) (i32.const 1))
+        )
+        (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+            (i32.store (i32.const 0) (call $depth (i32.load (local.get $args_ptr))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+fn without_tail_calls() -> CompileOptions {
+    CompileOptions {
+        optimizations: OptimizationFlags {
+            tail_calls: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    }
+}
+
+#[test]
+fn test_shallow_recursion_stays_within_the_stack() {
+    let program =
+        compile_wat_with_options(RECURSION_WAT, &without_tail_calls()).expect("Failed to compile");
+    let result = run_program(&program, &100i32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Halt);
+    assert_eq!(result.output, 100i32.to_le_bytes());
+}
+
+#[test]
+fn test_stack_overflow_traps_at_the_stack_limit() {
+    let wasm = wat_to_wasm(RECURSION_WAT).expect("Failed to parse WAT");
+    let args = 1_000_000i32.to_le_bytes();
+
+    // The prologue check guards the frame allocation, unsigned-compared.
+    let options = without_tail_calls();
+    let site =
+        run_and_expect_trap_near_with_options(&wasm, &args, "depth", Opcode::AddImm64, &options);
+    assert!(
+        matches!(site.guard, Some(Instruction::BranchGeU { .. })),
+        "expected a `BranchGeU` guard, got {:?}",
+        site.guard
+    );
+
+    // It fires on the first frame that would cross the limit, not earlier.
+    let (program, stats) = compile_with_stats(&wasm, &options).expect("Failed to compile");
+    let frame_size = stats
+        .functions
+        .iter()
+        .find(|f| f.name == "depth")
+        .expect("depth is compiled")
+        .frame_size;
+    let frame_size = u64::try_from(frame_size).expect("frame size is non-negative");
+    let result = run_program(&program, &args);
+    assert_eq!(result.exit, ExitReason::Trap);
+    let sp = result.regs[usize::from(abi::STACK_PTR_REG)];
+    let limit = u64::from(abi::stack_limit(program.stack_size()).cast_unsigned());
+    assert!(sp >= limit, "trapped early: sp {sp:#x}, limit {limit:#x}");
+    assert!(
+        sp - frame_size < limit,
+        "frame at sp {sp:#x} (size {frame_size}) fits above limit {limit:#x}"
+    );
+}
@@ -0,0 +1,95 @@
+//! `CompileOptions::trap_table`: the SPI metadata carries a table attributing
+//! each in-function `Trap` to its WASM function.
+
+use wasm_pvm::test_harness::*;
+use wasm_pvm::{CompileOptions, Instruction, OptimizationFlags, TrapTable};
+
+/// `main` stores `7 / args[0]` (unsigned), computed by the named `$div`.
+const WAT: &str = r#"
+    (module
+        (memory 1)
+        (func $div (param $a i32) (param $b i32) (result i32)
+            (i32.div_u (local.get $a) (local.get $b)))
+        (func (export "main") (param $p i32) (param $l i32) (result i64)
+            (i32.store (i32.const 0)
+                (call $div (i32.const 7) (i32.load (local.get $p))))
+            (i64.const 17179869184)
+        )
+    )
+"#;
+
+/// Keeps `$div` a separate function, so its trap isn't attributed to `main`.
+fn options() -> CompileOptions {
+    CompileOptions {
+        trap_table: true,
+        metadata: b"div.wasm".to_vec(),
+        optimizations: OptimizationFlags {
+            inlining: false,
+            ..OptimizationFlags::default()
+        },
+        ..CompileOptions::default()
+    }
+}
+
+fn table(program: &wasm_pvm::SpiProgram) -> TrapTable {
+    TrapTable::from_metadata(program.metadata())
+        .expect("decode")
+        .expect("trap table present")
+}
+
+#[test]
+fn div_by_zero_trap_maps_to_its_function() {
+    let program = compile_wat_with_options(WAT, &options()).expect("compile");
+    assert!(program.metadata().starts_with(b"div.wasm"));
+
+    let ok = run_program(&program, &2u32.to_le_bytes());
+    assert_eq!(ok.exit, ExitReason::Halt);
+    assert_eq!(ok.output, 3u32.to_le_bytes());
+
+    let result = run_program(&program, &0u32.to_le_bytes());
+    assert_eq!(result.exit, ExitReason::Trap);
+    let table = table(&program);
+    let entry = table
+        .lookup(result.pc)
+        .unwrap_or_else(|| panic!("no entry for trap at pc {}: {table:?}", result.pc));
+    assert_eq!(entry.function, 0);
+    assert_eq!(table.function_name(entry.function), Some("div"));
+}
+
+#[test]
+fn every_entry_is_an_in_function_trap() {
+    let program = compile_wat_with_options(WAT, &options()).expect("compile");
+    let blob = program.code();
+    let table = table(&program);
+    assert!(!table.traps.is_empty());
+
+    for entry in &table.traps {
+        let idx = (0..blob.instructions().len())
+            .find(|&idx| blob.offset_of(idx) == Some(entry.pc as usize))
+            .unwrap_or_else(|| panic!("pc {} is not an instruction boundary", entry.pc));
+        assert_eq!(blob.instructions()[idx], Instruction::Trap);
+        assert!(table.function_name(entry.function).is_some());
+    }
+    // The entry header's `Trap` (pc 5) is not in any function.
+    assert_eq!(table.lookup(5), None);
+
+    // Alignment padding adds `Trap`s between functions, but no entries.
+    let padded = compile_wat_with_options(
+        WAT,
+        &CompileOptions {
+            function_alignment: Some(64),
+            ..options()
+        },
+    )
+    .expect("compile");
+    assert_eq!(self::table(&padded).traps.len(), table.traps.len());
+}
+
+#[test]
+fn no_table_by_default() {
+    let program = compile_wat(WAT).expect("compile");
+    assert_eq!(
+        TrapTable::from_metadata(program.metadata()).expect("decode"),
+        None
+    );
+}
@@ -53,9 +53,11 @@ OPT_FLAGS=(
   --no-aggressive-regalloc
   --no-scratch-reg-alloc
   --no-caller-saved-alloc
+  --no-prefer-caller-saved
   --no-lazy-spill
   --no-libcall-recognition
   --no-mergefunc
+  --no-scheduling
 )
 
 # -----------------------------------------------------------------------------
@@ -28,23 +28,33 @@ import "../layer1/start-section.test";
 // --- Layer 2 imports (WAT-based suites; AS suites auto-skipped via import check) ---
 import "../layer2/bit-ops.test";
 import "../layer2/block-br-test.test";
+import "../layer2/block-params.test";
 import "../layer2/block-result.test";
 import "../layer2/br-table.test";
+import "../layer2/call-indirect-traps.test";
 import "../layer2/compare-test.test";
 import "../layer2/computed-addr-test.test";
+import "../layer2/dedup-functions.test";
 import "../layer2/entry-points.test";
+import "../layer2/float-conversions.test";
 import "../layer2/i64-ops.test";
 import "../layer2/loop-offset-store-test.test";
 import "../layer2/many-locals-call-test.test";
 import "../layer2/many-locals.test";
+import "../layer2/memory-copy-bounds.test";
+import "../layer2/memory-init.test";
 import "../layer2/memory-copy-overlap.test";
 import "../layer2/nested-calls.test";
 import "../layer2/recursive.test";
+import "../layer2/recursive-fib-locals.test";
 import "../layer2/rotate.test";
 import "../layer2/simple-memory-test.test";
 import "../layer2/stack-test.test";
 import "../layer2/memory-copy-word.test";
 import "../layer2/phi-cycles.test";
+import "../layer2/tail-recursion.test";
+import "../layer2/i64-globals.test";
+import "../layer2/memory-grow-max.test";
 
 // --- Generate differential test variants from registry ---
 import {
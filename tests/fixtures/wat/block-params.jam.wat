@@ -0,0 +1,93 @@
+(module
+  (memory 1)
+  ;; Blocks whose signature comes from a type index, with parameters.
+  ;; Input: operation selector, then two u32 operands $a and $b
+  ;; 0 = block (type $binop): a - b
+  ;; 1 = block (type $binop) left by br: a - b
+  ;; 2 = if/else (type $binop): a > b ? a - b : a + b
+  ;; 3 = if without else (type $unop): a > b ? 2a : a
+  ;; 4 = loop (type $binop) summing b down to 1 onto a
+  (type $binop (func (param i32 i32) (result i32)))
+  (type $unop (func (param i32) (result i32)))
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $op i32)
+    (local $a i32)
+    (local $b i32)
+    (local $n i32)
+    (local $result i32)
+
+    (local.set $op (i32.load (local.get $args_ptr)))
+    (local.set $a (i32.load offset=4 (local.get $args_ptr)))
+    (local.set $b (i32.load offset=8 (local.get $args_ptr)))
+
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then
+        (local.get $a)
+        (local.get $b)
+        (block (type $binop)
+          (i32.sub)
+        )
+        (local.set $result)
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then
+        (local.get $a)
+        (local.get $b)
+        (block $done (type $binop)
+          (i32.sub)
+          (br $done)
+        )
+        (local.set $result)
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then
+        (local.get $a)
+        (local.get $b)
+        (if (type $binop) (i32.gt_u (local.get $a) (local.get $b))
+          (then (i32.sub))
+          (else (i32.add))
+        )
+        (local.set $result)
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then
+        (local.get $a)
+        (if (type $unop) (i32.gt_u (local.get $a) (local.get $b))
+          (then (i32.mul (i32.const 2)))
+        )
+        (local.set $result)
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 4))
+      (then
+        (block $exit (result i32)
+          (local.get $a)
+          (local.get $b)
+          ;; Stack on every entry: acc, n
+          (loop $sum (type $binop)
+            (local.set $n)
+            (br_if $exit (i32.eqz (local.get $n)))
+            (i32.add (local.get $n))
+            (i32.sub (local.get $n) (i32.const 1))
+            (br $sum)
+          )
+        )
+        (local.set $result)
+      )
+    )
+
+    ;; Store result
+    (i32.store (i32.const 0) (local.get $result))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
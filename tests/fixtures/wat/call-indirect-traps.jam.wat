@@ -0,0 +1,31 @@
+(module
+  (memory 1)
+  ;; call_indirect through a table with a well-typed slot, a slot whose
+  ;; function has another signature, and a slot that is never initialized.
+  ;; Input: table index, then a u32 value
+  ;; 0 = double(value)
+  ;; 1 = traps: $sum takes two params, the call site passes one
+  ;; 2 = traps: null slot
+
+  (type $unary (func (param i32) (result i32)))
+  (type $binary (func (param i32 i32) (result i32)))
+
+  (table 3 funcref)
+  (elem (i32.const 0) $double $sum)
+
+  (func $double (type $unary)
+    (i32.mul (local.get 0) (i32.const 2))
+  )
+
+  (func $sum (type $binary)
+    (i32.add (local.get 0) (local.get 1))
+  )
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (i32.store (i32.const 0)
+      (call_indirect (type $unary)
+        (i32.load offset=4 (local.get $args_ptr))
+        (i32.load (local.get $args_ptr))))
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
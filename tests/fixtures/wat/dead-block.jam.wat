@@ -0,0 +1,54 @@
+(module
+  (import "env" "abort" (func $abort (param i32 i32 i32 i32)))
+  (import "env" "fail" (func $fail (param i32) (result i32)))
+  (memory 1)
+  ;; Both imports are mapped to `trap` (see dead-block.imports). LLVM keeps the
+  ;; code after each call, which is dead once the call lowers to a PVM `Trap`.
+  ;; Input: operation selector, then two u32 operands $a and $b
+  ;; 0 = a / b, aborting when b == 0
+  ;; 1 = a - b, or fail(a) * 3 + b when a < b
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $op i32)
+    (local $a i32)
+    (local $b i32)
+    (local $result i32)
+
+    (local.set $op (i32.load (local.get $args_ptr)))
+    (local.set $a (i32.load offset=4 (local.get $args_ptr)))
+    (local.set $b (i32.load offset=8 (local.get $args_ptr)))
+
+    (if (i32.eqz (local.get $op))
+      (then
+        (if (i32.eqz (local.get $b))
+          (then
+            (call $abort (i32.const 0) (i32.const 0) (i32.const 9) (i32.const 5))
+          )
+        )
+        (local.set $result (i32.div_u (local.get $a) (local.get $b)))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then
+        (if (i32.lt_u (local.get $a) (local.get $b))
+          (then
+            (local.set $result
+              (i32.add
+                (i32.mul (call $fail (local.get $a)) (i32.const 3))
+                (local.get $b)))
+          )
+          (else
+            (local.set $result (i32.sub (local.get $a) (local.get $b)))
+          )
+        )
+      )
+    )
+
+    ;; Store result
+    (i32.store (i32.const 0) (local.get $result))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
@@ -0,0 +1,107 @@
+(module
+  (memory 1)
+  ;; Three byte-identical copies of a sum-of-squares helper and one helper
+  ;; of the same shape that differs, called directly and through the table.
+  ;; Input: operation selector, then a u32 $n
+  ;; 0 = $sum_squares_a(n)
+  ;; 1 = $sum_squares_b(n)
+  ;; 2 = call_indirect of table[2] ($sum_squares_c)
+  ;; 3 = call_indirect of table[3] ($sum_cubes)
+  ;; 4 = $sum_squares_a(n) + $sum_squares_b(n) + $sum_squares_c(n)
+  (type $unop (func (param i32) (result i32)))
+  (table 4 funcref)
+  (elem (i32.const 0) $sum_squares_a $sum_squares_b $sum_squares_c $sum_cubes)
+
+  (func $sum_squares_a (type $unop)
+    (local $i i32)
+    (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get 0)))
+        (local.set $acc (i32.add (local.get $acc) (i32.mul (local.get $i) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)
+      )
+    )
+    (local.get $acc)
+  )
+
+  (func $sum_squares_b (type $unop)
+    (local $i i32)
+    (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get 0)))
+        (local.set $acc (i32.add (local.get $acc) (i32.mul (local.get $i) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)
+      )
+    )
+    (local.get $acc)
+  )
+
+  (func $sum_squares_c (type $unop)
+    (local $i i32)
+    (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get 0)))
+        (local.set $acc (i32.add (local.get $acc) (i32.mul (local.get $i) (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)
+      )
+    )
+    (local.get $acc)
+  )
+
+  (func $sum_cubes (type $unop)
+    (local $i i32)
+    (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get 0)))
+        (local.set $acc (i32.add (local.get $acc)
+          (i32.mul (local.get $i) (i32.mul (local.get $i) (local.get $i)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)
+      )
+    )
+    (local.get $acc)
+  )
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $op i32)
+    (local $n i32)
+    (local $result i32)
+
+    (local.set $op (i32.load (local.get $args_ptr)))
+    (local.set $n (i32.load offset=4 (local.get $args_ptr)))
+
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then (local.set $result (call $sum_squares_a (local.get $n))))
+    )
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then (local.set $result (call $sum_squares_b (local.get $n))))
+    )
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then (local.set $result (call_indirect (type $unop) (local.get $n) (i32.const 2))))
+    )
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then (local.set $result (call_indirect (type $unop) (local.get $n) (i32.const 3))))
+    )
+    (if (i32.eq (local.get $op) (i32.const 4))
+      (then
+        (local.set $result
+          (i32.add
+            (i32.add (call $sum_squares_a (local.get $n)) (call $sum_squares_b (local.get $n)))
+            (call $sum_squares_c (local.get $n))))
+      )
+    )
+
+    ;; Store result
+    (i32.store (i32.const 0) (local.get $result))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
@@ -0,0 +1,90 @@
+(module
+  (memory 1)
+
+  ;; Soft-float integer <-> float conversions.
+  ;; Args: [test_case: i32][operand: i64] (operand bytes are read as an i32
+  ;; or i64 depending on the case). Float results are returned as their bit
+  ;; patterns via reinterpret; integer results directly (low 32 bits).
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $test_case i32)
+    (local $result i32)
+    (local $bits i64)
+
+    (local.set $test_case (i32.load (local.get $args_ptr)))
+
+    (block $done
+      ;; Test 0: f64.convert_i32_s(x), high word of the f64 bits.
+      ;; i32::MAX -> 0x41DFFFFF_FFC00000.
+      (if (i32.eq (local.get $test_case) (i32.const 0))
+        (then
+          (local.set $bits
+            (i64.reinterpret_f64
+              (f64.convert_i32_s (i32.load offset=4 (local.get $args_ptr)))))
+          (local.set $result (i32.wrap_i64 (i64.shr_u (local.get $bits) (i64.const 32))))
+          (br $done)
+        )
+      )
+
+      ;; Test 1: f64.convert_i32_s(x), low word of the f64 bits.
+      (if (i32.eq (local.get $test_case) (i32.const 1))
+        (then
+          (local.set $result
+            (i32.wrap_i64
+              (i64.reinterpret_f64
+                (f64.convert_i32_s (i32.load offset=4 (local.get $args_ptr))))))
+          (br $done)
+        )
+      )
+
+      ;; Test 2: i32.trunc_f64_s of the f64 with the given bits.
+      ;; 3.9 -> 3, -3.9 -> -3; 2^31 and NaN trap.
+      (if (i32.eq (local.get $test_case) (i32.const 2))
+        (then
+          (local.set $result
+            (i32.trunc_f64_s
+              (f64.reinterpret_i64 (i64.load offset=4 (local.get $args_ptr)))))
+          (br $done)
+        )
+      )
+
+      ;; Test 3: f32.convert_i64_u(x) bits. u64::MAX rounds up to 2^64 = 0x5F800000.
+      (if (i32.eq (local.get $test_case) (i32.const 3))
+        (then
+          (local.set $result
+            (i32.reinterpret_f32
+              (f32.convert_i64_u (i64.load offset=4 (local.get $args_ptr)))))
+          (br $done)
+        )
+      )
+
+      ;; Test 4: i32.trunc_f32_u(f32.convert_i32_u(x)).
+      ;; 2^24 + 1 is a tie between 2^24 and 2^24 + 2 and rounds to even (2^24).
+      (if (i32.eq (local.get $test_case) (i32.const 4))
+        (then
+          (local.set $result
+            (i32.trunc_f32_u
+              (f32.convert_i32_u (i32.load offset=4 (local.get $args_ptr)))))
+          (br $done)
+        )
+      )
+
+      ;; Test 5: i64.trunc_f64_u of the f64 with the given bits (low word).
+      ;; 12345.75 -> 12345; -1.0 traps.
+      (if (i32.eq (local.get $test_case) (i32.const 5))
+        (then
+          (local.set $result
+            (i32.wrap_i64
+              (i64.trunc_f64_u
+                (f64.reinterpret_i64 (i64.load offset=4 (local.get $args_ptr))))))
+          (br $done)
+        )
+      )
+
+      (local.set $result (i32.const -1))
+    )
+
+    (i32.store (i32.const 0) (local.get $result))
+    (i64.const 17179869184)
+  )
+)
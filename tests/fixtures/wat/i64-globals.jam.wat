@@ -0,0 +1,63 @@
+(module
+  (memory 1)
+  ;; i64 globals keep all 64 bits in their rw_data slot.
+  ;; Input: operation selector, then a u32 operand $x
+  ;; 0 = high half of $big
+  ;; 1 = low half of $big
+  ;; 2 = $big += x << 32, then its high half
+  ;; 3 = $small + high half of $big (the neighbouring i32 slot is intact)
+  (global $big (mut i64) (i64.const 0x123456789))
+  (global $small (mut i32) (i32.const 7))
+
+  (func $get_big (result i64)
+    (global.get $big)
+  )
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $op i32)
+    (local $x i32)
+    (local $result i32)
+
+    (local.set $op (i32.load (local.get $args_ptr)))
+    (local.set $x (i32.load offset=4 (local.get $args_ptr)))
+
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then
+        (local.set $result
+          (i32.wrap_i64 (i64.shr_u (call $get_big) (i64.const 32))))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then
+        (local.set $result (i32.wrap_i64 (call $get_big)))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then
+        (global.set $big
+          (i64.add
+            (global.get $big)
+            (i64.shl (i64.extend_i32_u (local.get $x)) (i64.const 32))))
+        (local.set $result
+          (i32.wrap_i64 (i64.shr_u (call $get_big) (i64.const 32))))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then
+        (local.set $result
+          (i32.add
+            (global.get $small)
+            (i32.wrap_i64 (i64.shr_u (call $get_big) (i64.const 32)))))
+      )
+    )
+
+    ;; Store result
+    (i32.store (i32.const 0) (local.get $result))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
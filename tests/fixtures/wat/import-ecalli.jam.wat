@@ -0,0 +1,20 @@
+(module
+  ;; An import mapped to a host call by import-ecalli.imports (`mix = ecalli 7`).
+  ;; Input: two u32 operands $a and $b
+  ;; The host sees $a in r7 and $b in r8 and returns its result in r7;
+  ;; main stores that result + 1.
+  (import "env" "mix" (func $mix (param i32 i32) (result i32)))
+  (memory 1)
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (i32.store (i32.const 0)
+      (i32.add
+        (call $mix
+          (i32.load (local.get $args_ptr))
+          (i32.load offset=4 (local.get $args_ptr)))
+        (i32.const 1)))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
@@ -0,0 +1,79 @@
+(module
+  (memory 1)
+
+  ;; Test memory.copy bounds checking against the current memory size.
+  ;; In-range copies (including ones ending exactly at the last byte) must
+  ;; succeed; anything past the end must trap instead of writing into
+  ;; whatever follows linear memory in PVM address space.
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $test_case i32)
+    (local $result i32)
+
+    (local.set $test_case
+      (i32.load (local.get $args_ptr))
+    )
+
+    ;; Source pattern at address 0: 01 02 03 04 05 06 07 08
+    (i64.store (i32.const 0x0) (i64.const 0x0807060504030201))
+
+    (block $done
+      ;; Test 0: copy 8 bytes ending exactly at the memory end (65536).
+      ;; Read back [01 02 03 04] = 0x04030201.
+      (if (i32.eq (local.get $test_case) (i32.const 0))
+        (then
+          (memory.copy (i32.const 65528) (i32.const 0) (i32.const 8))
+          (local.set $result (i32.load (i32.const 65528)))
+          (br $done)
+        )
+      )
+
+      ;; Test 1: zero-length copy at exactly the memory end is allowed.
+      (if (i32.eq (local.get $test_case) (i32.const 1))
+        (then
+          (memory.copy (i32.const 65536) (i32.const 65536) (i32.const 0))
+          (local.set $result (i32.const 0x600d))
+          (br $done)
+        )
+      )
+
+      ;; Test 2: destination runs 4 bytes past the end -> trap.
+      (if (i32.eq (local.get $test_case) (i32.const 2))
+        (then
+          (memory.copy (i32.const 65532) (i32.const 0) (i32.const 8))
+          (local.set $result (i32.const 0xbad))
+          (br $done)
+        )
+      )
+
+      ;; Test 3: source runs 4 bytes past the end -> trap.
+      (if (i32.eq (local.get $test_case) (i32.const 3))
+        (then
+          (memory.copy (i32.const 0x100) (i32.const 65532) (i32.const 8))
+          (local.set $result (i32.const 0xbad))
+          (br $done)
+        )
+      )
+
+      ;; Test 4: dst + len wraps around 2^32 -> trap (must not pass as a
+      ;; small in-range sum).
+      (if (i32.eq (local.get $test_case) (i32.const 4))
+        (then
+          (memory.copy (i32.const 0xfffffff8) (i32.const 0) (i32.const 16))
+          (local.set $result (i32.const 0xbad))
+          (br $done)
+        )
+      )
+
+      ;; Test 5: zero-length copy one byte past the end -> trap.
+      (memory.copy (i32.const 65537) (i32.const 0) (i32.const 0))
+      (local.set $result (i32.const 0xbad))
+    )
+
+    ;; Return result at wasm-relative address 0x100
+    (i32.store (i32.const 0x100) (local.get $result))
+
+    ;; Return packed i64: ptr=0x100, len=4
+    (i64.const 17179869440)
+  )
+)
@@ -0,0 +1,65 @@
+(module
+  ;; The declared maximum (3 pages) is a hard cap for memory.grow.
+  ;; Input: operation selector
+  ;; 0 = grow(2) up to the max: old size
+  ;; 1 = grow(2), then memory.size
+  ;; 2 = grow(2), then grow(1) past the max: -1
+  ;; 3 = grow(2), grow(1), then memory.size (unchanged by the failed grow)
+  ;; 4 = grow(3) past the max in one step: -1
+  ;; 5 = grow(3), then memory.size
+  (memory 1 3)
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $op i32)
+    (local $result i32)
+
+    (local.set $op (i32.load (local.get $args_ptr)))
+
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then
+        (local.set $result (memory.grow (i32.const 2)))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then
+        (drop (memory.grow (i32.const 2)))
+        (local.set $result (memory.size))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then
+        (drop (memory.grow (i32.const 2)))
+        (local.set $result (memory.grow (i32.const 1)))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then
+        (drop (memory.grow (i32.const 2)))
+        (drop (memory.grow (i32.const 1)))
+        (local.set $result (memory.size))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 4))
+      (then
+        (local.set $result (memory.grow (i32.const 3)))
+      )
+    )
+
+    (if (i32.eq (local.get $op) (i32.const 5))
+      (then
+        (drop (memory.grow (i32.const 3)))
+        (local.set $result (memory.size))
+      )
+    )
+
+    ;; Store result
+    (i32.store (i32.const 0) (local.get $result))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
@@ -0,0 +1,100 @@
+(module
+  (memory 1)
+
+  ;; Test memory.init / data.drop on passive data segments. Passive segments
+  ;; live in RO_DATA; memory.init copies out of them into linear memory after
+  ;; checking the source range against the segment's current length, which
+  ;; data.drop zeroes. Dropping an active segment is a no-op.
+
+  (data $active (i32.const 0x200) "\aa\bb\cc\dd")
+  (data $bytes "\01\02\03\04\05\06\07\08")
+  (data $tail "\de\ad\be\ef")
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $test_case i32)
+    (local $result i32)
+
+    (local.set $test_case
+      (i32.load (local.get $args_ptr))
+    )
+
+    (block $done
+      ;; Test 0: copy all of $bytes to 0x40, read back bytes 4..8 = 0x08070605.
+      (if (i32.eq (local.get $test_case) (i32.const 0))
+        (then
+          (memory.init $bytes (i32.const 0x40) (i32.const 0) (i32.const 8))
+          (local.set $result (i32.load (i32.const 0x44)))
+          (br $done)
+        )
+      )
+
+      ;; Test 1: copy a sub-range [2, 6) of $bytes = 0x06050403.
+      (if (i32.eq (local.get $test_case) (i32.const 1))
+        (then
+          (memory.init $bytes (i32.const 0x40) (i32.const 2) (i32.const 4))
+          (local.set $result (i32.load (i32.const 0x40)))
+          (br $done)
+        )
+      )
+
+      ;; Test 2: the second passive segment lands at its own offset = 0xefbeadde.
+      (if (i32.eq (local.get $test_case) (i32.const 2))
+        (then
+          (memory.init $tail (i32.const 0x40) (i32.const 0) (i32.const 4))
+          (local.set $result (i32.load (i32.const 0x40)))
+          (br $done)
+        )
+      )
+
+      ;; Test 3: dropping the active segment leaves its bytes and $bytes intact.
+      (if (i32.eq (local.get $test_case) (i32.const 3))
+        (then
+          (data.drop $active)
+          (memory.init $bytes (i32.const 0x40) (i32.const 0) (i32.const 4))
+          (local.set $result
+            (i32.xor (i32.load (i32.const 0x40)) (i32.load (i32.const 0x200))))
+          (br $done)
+        )
+      )
+
+      ;; Test 4: len=0 after data.drop is allowed.
+      (if (i32.eq (local.get $test_case) (i32.const 4))
+        (then
+          (data.drop $bytes)
+          (memory.init $bytes (i32.const 0x40) (i32.const 0) (i32.const 0))
+          (local.set $result (i32.const 0x600d))
+          (br $done)
+        )
+      )
+
+      ;; Test 5: memory.init after data.drop -> trap.
+      (if (i32.eq (local.get $test_case) (i32.const 5))
+        (then
+          (data.drop $bytes)
+          (memory.init $bytes (i32.const 0x40) (i32.const 0) (i32.const 4))
+          (local.set $result (i32.const 0xbad))
+          (br $done)
+        )
+      )
+
+      ;; Test 6: source range runs past the segment end -> trap.
+      (if (i32.eq (local.get $test_case) (i32.const 6))
+        (then
+          (memory.init $bytes (i32.const 0x40) (i32.const 6) (i32.const 4))
+          (local.set $result (i32.const 0xbad))
+          (br $done)
+        )
+      )
+
+      ;; Test 7: destination runs past the memory end -> trap.
+      (memory.init $bytes (i32.const 65534) (i32.const 0) (i32.const 4))
+      (local.set $result (i32.const 0xbad))
+    )
+
+    ;; Return result at wasm-relative address 0x100
+    (i32.store (i32.const 0x100) (local.get $result))
+
+    ;; Return packed i64: ptr=0x100, len=4
+    (i64.const 17179869440)
+  )
+)
@@ -0,0 +1,29 @@
+(module
+  (memory 1)
+
+  ;; Naive recursive fib with six locals, more than the four local registers.
+  ;; $a is live across the second recursive call.
+  (func $fib (param $n i32) (result i32)
+    (local $n1 i32)
+    (local $n2 i32)
+    (local $a i32)
+    (local $b i32)
+    (local $sum i32)
+
+    (if (i32.lt_u (local.get $n) (i32.const 2))
+      (then (return (local.get $n)))
+    )
+
+    (local.set $n1 (i32.sub (local.get $n) (i32.const 1)))
+    (local.set $n2 (i32.sub (local.get $n) (i32.const 2)))
+    (local.set $a (call $fib (local.get $n1)))
+    (local.set $b (call $fib (local.get $n2)))
+    (local.set $sum (i32.add (local.get $a) (local.get $b)))
+    (local.get $sum)
+  )
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (i32.store (i32.const 0) (call $fib (i32.load (local.get $args_ptr))))
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
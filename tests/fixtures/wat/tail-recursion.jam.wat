@@ -0,0 +1,50 @@
+(module
+  (memory 1)
+  ;; Self-recursive functions whose recursive call is in tail position.
+  ;; Input: operation selector, then two u32 operands $a and $b
+  ;; 0 = $sum(a, b): b + a + (a - 1) + ... + 1, wrapping
+  ;; 1 = $gcd(a, b)
+
+  (func $sum (param $n i32) (param $acc i32) (result i32)
+    (if (result i32) (i32.eqz (local.get $n))
+      (then (local.get $acc))
+      (else
+        (call $sum
+          (i32.sub (local.get $n) (i32.const 1))
+          (i32.add (local.get $acc) (local.get $n))
+        )
+      )
+    )
+  )
+
+  (func $gcd (param $a i32) (param $b i32) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then (return (local.get $a)))
+    )
+    (call $gcd (local.get $b) (i32.rem_u (local.get $a) (local.get $b)))
+  )
+
+  (func (export "main") (param $args_ptr i32) (param $args_len i32) (result i64)
+    (local $op i32)
+    (local $a i32)
+    (local $b i32)
+    (local $result i32)
+
+    (local.set $op (i32.load (local.get $args_ptr)))
+    (local.set $a (i32.load offset=4 (local.get $args_ptr)))
+    (local.set $b (i32.load offset=8 (local.get $args_ptr)))
+
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then (local.set $result (call $sum (local.get $a) (local.get $b))))
+    )
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then (local.set $result (call $gcd (local.get $a) (local.get $b))))
+    )
+
+    ;; Store result
+    (i32.store (i32.const 0) (local.get $result))
+
+    ;; Set return value
+    (i64.const 17179869184)  ;; ptr=0, len=4
+  )
+)
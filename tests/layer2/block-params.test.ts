@@ -0,0 +1,17 @@
+import { defineSuite } from "../helpers/suite";
+
+// Block, if and loop signatures taken from a type index, with parameters.
+const tests = [
+  { args: "000000000a00000003000000", expected: 7, description: "block (type $binop) subtracts its params" },
+  { args: "010000000a00000003000000", expected: 7, description: "br carries the block result" },
+  { args: "020000000a00000003000000", expected: 7, description: "if (type $binop) then arm" },
+  { args: "02000000030000000a000000", expected: 13, description: "if (type $binop) else arm sees the params" },
+  { args: "030000000a00000003000000", expected: 20, description: "if without else, taken" },
+  { args: "03000000030000000a000000", expected: 3, description: "if without else passes the param through" },
+  { args: "040000000500000004000000", expected: 15, description: "loop params carried by br" },
+];
+
+defineSuite({
+  name: "block-params",
+  tests: tests,
+});
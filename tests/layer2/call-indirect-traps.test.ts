@@ -0,0 +1,13 @@
+import { defineSuite } from "../helpers/suite";
+
+// Slots 1 (wrong signature) and 2 (null) trap; see
+// crates/wasm-pvm/tests/call_indirect_traps.rs for those paths.
+const tests = [
+  { args: "0000000005000000", expected: 10, description: "double(5) = 10" },
+  { args: "0000000015000000", expected: 42, description: "double(21) = 42" },
+];
+
+defineSuite({
+  name: "call-indirect-traps",
+  tests: tests,
+});
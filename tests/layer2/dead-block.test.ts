@@ -0,0 +1,14 @@
+import { defineSuite } from "../helpers/suite";
+
+// `abort` and `fail` trap; only the paths that avoid them are checked here.
+const tests = [
+  { args: "000000001500000004000000", expected: 5, description: "21 / 4" },
+  { args: "000000000700000007000000", expected: 1, description: "7 / 7" },
+  { args: "010000000900000004000000", expected: 5, description: "9 - 4" },
+  { args: "010000000400000004000000", expected: 0, description: "4 - 4" },
+];
+
+defineSuite({
+  name: "dead-block",
+  tests: tests,
+});
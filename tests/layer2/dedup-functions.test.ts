@@ -0,0 +1,15 @@
+import { defineSuite } from "../helpers/suite";
+
+// Byte-identical functions share one copy of their code.
+const tests = [
+  { args: "0000000005000000", expected: 30, description: "direct call to the first copy" },
+  { args: "0100000005000000", expected: 30, description: "direct call to a duplicate" },
+  { args: "020000000a000000", expected: 285, description: "call_indirect to a duplicate" },
+  { args: "0300000005000000", expected: 100, description: "call_indirect to the distinct helper" },
+  { args: "0400000005000000", expected: 90, description: "all three copies" },
+];
+
+defineSuite({
+  name: "dedup-functions",
+  tests: tests,
+});
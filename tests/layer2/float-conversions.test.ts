@@ -0,0 +1,37 @@
+import { describe, expect, test } from "bun:test";
+import { resolve } from "node:path";
+import { defineSuite } from "../helpers/suite";
+import { runJamWithOutput } from "../helpers/run";
+import { JAM_DIR } from "../helpers/paths";
+
+// Args: 4-byte test case, then an 8-byte little-endian operand.
+defineSuite({
+  name: "float-conversions",
+  tests: [
+    { args: "00000000ffffff7f00000000", expected: 0x41dfffff, description: "f64.convert_i32_s(i32::MAX) high word" },
+    { args: "01000000ffffff7f00000000", expected: 0xffc00000, description: "f64.convert_i32_s(i32::MAX) low word" },
+    { args: "020000003333333333330f40", expected: 3, description: "i32.trunc_f64_s(3.9) = 3" },
+    { args: "020000003333333333330fc0", expected: 0xfffffffd, description: "i32.trunc_f64_s(-3.9) = -3" },
+    { args: "03000000ffffffffffffffff", expected: 0x5f800000, description: "f32.convert_i64_u(u64::MAX) rounds to 2^64" },
+    { args: "040000000100000100000000", expected: 0x01000000, description: "f32.convert_i32_u(2^24 + 1) ties to even" },
+    { args: "0500000000000000e01cc840", expected: 12345, description: "i64.trunc_f64_u(12345.75) = 12345" },
+  ],
+});
+
+// Out-of-range and NaN inputs to trapping truncations must trap.
+describe("float-conversions traps", () => {
+  const jamFile = resolve(JAM_DIR, "float-conversions.jam");
+  const trapCases = [
+    { args: "02000000000000000000e041", description: "i32.trunc_f64_s(2^31)" },
+    { args: "02000000000000000000f87f", description: "i32.trunc_f64_s(NaN)" },
+    { args: "05000000000000000000f0bf", description: "i64.trunc_f64_u(-1.0)" },
+  ];
+
+  for (const t of trapCases) {
+    test(`traps: ${t.description}`, () => {
+      const result = runJamWithOutput(jamFile, t.args);
+      expect(result.stdout).toMatch(/Status:\s*[1-9]/);
+      expect(result.stdout).toContain("Result: [0x]");
+    });
+  }
+});
@@ -0,0 +1,14 @@
+import { defineSuite } from "../helpers/suite";
+
+// $big starts at 0x1_2345_6789, above u32::MAX.
+const tests = [
+  { args: "0000000000000000", expected: 1, description: "high half of the initializer" },
+  { args: "0100000000000000", expected: 591751049, description: "low half of the initializer" },
+  { args: "0200000005000000", expected: 6, description: "global.set carries into the high half" },
+  { args: "0300000000000000", expected: 8, description: "neighbouring i32 global is intact" },
+];
+
+defineSuite({
+  name: "i64-globals",
+  tests: tests,
+});
@@ -0,0 +1,35 @@
+import { describe, expect, test } from "bun:test";
+import { resolve } from "node:path";
+import { defineSuite } from "../helpers/suite";
+import { runJamWithOutput } from "../helpers/run";
+import { JAM_DIR } from "../helpers/paths";
+
+// In-range copies, including ones touching the very last byte of memory.
+defineSuite({
+  name: "memory-copy-bounds",
+  tests: [
+    { args: "00000000", expected: 0x04030201, description: "memory.copy ending exactly at memory end" },
+    { args: "01000000", expected: 0x600d, description: "memory.copy len=0 at memory end" },
+  ],
+});
+
+// Out-of-range copies must trap rather than touch PVM memory outside the
+// WASM linear memory. anan-as reports a non-zero `Status:` and an empty
+// `Result: [0x]` when execution stops at a Trap.
+describe("memory-copy-bounds traps", () => {
+  const jamFile = resolve(JAM_DIR, "memory-copy-bounds.jam");
+  const trapCases = [
+    { args: "02000000", description: "dst + len past memory end" },
+    { args: "03000000", description: "src + len past memory end" },
+    { args: "04000000", description: "dst + len wrapping around 2^32" },
+    { args: "05000000", description: "len=0 with dst past memory end" },
+  ];
+
+  for (const t of trapCases) {
+    test(`traps: ${t.description}`, () => {
+      const result = runJamWithOutput(jamFile, t.args);
+      expect(result.stdout).toMatch(/Status:\s*[1-9]/);
+      expect(result.stdout).toContain("Result: [0x]");
+    });
+  }
+});
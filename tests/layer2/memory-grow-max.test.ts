@@ -0,0 +1,16 @@
+import { defineSuite } from "../helpers/suite";
+
+// (memory 1 3): grows that would pass 3 pages return -1 and change nothing.
+const tests = [
+  { args: "00000000", expected: 1, description: "grow up to the max returns the old size" },
+  { args: "01000000", expected: 3, description: "memory.size at the max" },
+  { args: "02000000", expected: 0xffffffff, description: "grow one page past the max returns -1" },
+  { args: "03000000", expected: 3, description: "failed grow leaves memory.size unchanged" },
+  { args: "04000000", expected: 0xffffffff, description: "single grow past the max returns -1" },
+  { args: "05000000", expected: 1, description: "single failed grow leaves memory.size unchanged" },
+];
+
+defineSuite({
+  name: "memory-grow-max",
+  tests: tests,
+});
@@ -0,0 +1,36 @@
+import { describe, expect, test } from "bun:test";
+import { resolve } from "node:path";
+import { defineSuite } from "../helpers/suite";
+import { runJamWithOutput } from "../helpers/run";
+import { JAM_DIR } from "../helpers/paths";
+
+// memory.init copies out of passive segments into linear memory.
+defineSuite({
+  name: "memory-init",
+  tests: [
+    { args: "00000000", expected: 0x08070605, description: "memory.init whole segment" },
+    { args: "01000000", expected: 0x06050403, description: "memory.init sub-range" },
+    { args: "02000000", expected: 0xefbeadde, description: "memory.init second passive segment" },
+    { args: "03000000", expected: 0xd9cfb9ab, description: "data.drop of an active segment is a no-op" },
+    { args: "04000000", expected: 0x600d, description: "memory.init len=0 after data.drop" },
+  ],
+});
+
+// A dropped segment has length 0, so any non-empty memory.init from it traps,
+// as do source or destination ranges that run past their end.
+describe("memory-init traps", () => {
+  const jamFile = resolve(JAM_DIR, "memory-init.jam");
+  const trapCases = [
+    { args: "05000000", description: "memory.init after data.drop" },
+    { args: "06000000", description: "src + len past segment end" },
+    { args: "07000000", description: "dst + len past memory end" },
+  ];
+
+  for (const t of trapCases) {
+    test(`traps: ${t.description}`, () => {
+      const result = runJamWithOutput(jamFile, t.args);
+      expect(result.stdout).toMatch(/Status:\s*[1-9]/);
+      expect(result.stdout).toContain("Result: [0x]");
+    });
+  }
+});
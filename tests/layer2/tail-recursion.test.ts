@@ -0,0 +1,17 @@
+import { defineSuite } from "../helpers/suite";
+
+// Self-recursive calls in tail position. Depths stay small enough to fit the
+// stack without tail-call elimination, so the no-opts build agrees.
+const tests = [
+  { args: "000000000000000000000000", expected: 0, description: "sum of nothing" },
+  { args: "000000000a00000000000000", expected: 55, description: "sum 1..10" },
+  { args: "00000000f401000007000000", expected: 125257, description: "sum 1..500 onto 7" },
+  { args: "010000003000000012000000", expected: 6, description: "gcd(48, 18)" },
+  { args: "010000001100000000000000", expected: 17, description: "gcd(17, 0)" },
+  { args: "010000000d00000022000000", expected: 1, description: "gcd(13, 34)" },
+];
+
+defineSuite({
+  name: "tail-recursion",
+  tests: tests,
+});